@@ -131,9 +131,27 @@ export interface RLMRequest {
   depth?: number;
 }
 
+export interface RLMCitation {
+  kb_name: KnowledgeBaseName;
+  document_id: string;
+  score: number;
+}
+
+export interface RLMTokenUsage {
+  prompt_tokens: number;
+  completion_tokens: number;
+}
+
 export interface RLMResponse {
   summary: string;
   converged: boolean;
+  /** Thoughts plus actions executed in this step. */
+  steps: number;
+  /** Model's self-reported 0..1 confidence; null when it gave none. */
+  confidence: number | null;
+  /** L4 documents the step relied on. */
+  citations: RLMCitation[];
+  usage: RLMTokenUsage;
 }
 
 // ---------------------------------------------------------------------------
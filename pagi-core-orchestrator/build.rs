@@ -1,5 +1,244 @@
+// Protobuf codegen (tonic) plus request validators: fields whose pagi.proto comment carries
+// `@validate(rule=value, ...)` get checks in an `impl Validate` written to OUT_DIR/pagi_validate.rs, which
+// src/validate.rs includes. Messages holding validated messages (singular or repeated) check them too.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+
+const PROTO: &str = "../pagi-proto/pagi.proto";
+
+const NUMERIC: &[&str] = &[
+    "int32", "int64", "uint32", "uint64", "sint32", "sint64", "fixed32", "fixed64", "sfixed32", "sfixed64", "float",
+    "double",
+];
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "static", "struct",
+    "trait", "true", "type", "unsafe", "use", "where", "while", "yield",
+];
+
+enum Kind {
+    Singular(String),
+    Optional(String),
+    Repeated(String),
+    Map,
+}
+
+impl Kind {
+    fn type_name(&self) -> Option<&str> {
+        match self {
+            Kind::Singular(t) | Kind::Optional(t) | Kind::Repeated(t) => Some(t),
+            Kind::Map => None,
+        }
+    }
+}
+
+struct Field {
+    name: String,
+    kind: Kind,
+    rules: Vec<(String, String)>,
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_build::compile_protos("../pagi-proto/pagi.proto")?;
-    println!("cargo:rerun-if-changed=../pagi-proto/pagi.proto");
+    tonic_build::compile_protos(PROTO)?;
+    let source = std::fs::read_to_string(PROTO)?;
+    let messages = parse(&source)?;
+    let out = std::path::PathBuf::from(std::env::var("OUT_DIR")?).join("pagi_validate.rs");
+    std::fs::write(out, generate(&messages)?)?;
+    println!("cargo:rerun-if-changed={}", PROTO);
     Ok(())
 }
+
+/// `type name` / `repeated type name` / `optional type name` / `map<K, V> name`.
+fn parse_field(decl: &str) -> Option<(String, Kind)> {
+    if let Some(rest) = decl.strip_prefix("map<") {
+        let (_, name) = rest.split_once('>')?;
+        return Some((name.trim().to_string(), Kind::Map));
+    }
+    match decl.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["repeated", ty, name] => Some((name.to_string(), Kind::Repeated(ty.to_string()))),
+        ["optional", ty, name] => Some((name.to_string(), Kind::Optional(ty.to_string()))),
+        [ty, name] => Some((name.to_string(), Kind::Singular(ty.to_string()))),
+        _ => None,
+    }
+}
+
+/// `@validate(a=1, b)` in a field comment → [("a", "1"), ("b", "")].
+fn parse_rules(comment: &str) -> Vec<(String, String)> {
+    let Some(start) = comment.find("@validate(") else {
+        return Vec::new();
+    };
+    let body = &comment[start + "@validate(".len()..];
+    let body = &body[..body.find(')').unwrap_or(body.len())];
+    body.split(',')
+        .map(str::trim)
+        .filter(|r| !r.is_empty())
+        .map(|r| match r.split_once('=') {
+            Some((k, v)) => (k.trim().to_string(), v.trim().to_string()),
+            None => (r.to_string(), String::new()),
+        })
+        .collect()
+}
+
+/// Top-level messages and their fields (pagi.proto has no nested types).
+fn parse(source: &str) -> Result<BTreeMap<String, Vec<Field>>, String> {
+    let mut messages: BTreeMap<String, Vec<Field>> = BTreeMap::new();
+    // Open blocks: the message name, or None for services and other blocks.
+    let mut blocks: Vec<Option<String>> = Vec::new();
+    for (n, raw) in source.lines().enumerate() {
+        let (code, comment) = match raw.find("//") {
+            Some(i) => (raw[..i].trim(), &raw[i + 2..]),
+            None => (raw.trim(), ""),
+        };
+        if code.ends_with('{') {
+            let mut words = code.split_whitespace();
+            let block = match (words.next(), words.next()) {
+                (Some("message"), Some(name)) => Some(name.trim_end_matches('{').to_string()),
+                _ => None,
+            };
+            if let Some(name) = &block {
+                messages.entry(name.clone()).or_default();
+            }
+            blocks.push(block);
+            continue;
+        }
+        if code.starts_with('}') {
+            blocks.pop();
+            continue;
+        }
+        let Some(Some(message)) = blocks.last() else {
+            continue;
+        };
+        let Some((decl, _)) = code.strip_suffix(';').and_then(|c| c.split_once('=')) else {
+            continue;
+        };
+        let (name, kind) =
+            parse_field(decl.trim()).ok_or_else(|| format!("{}:{}: cannot parse field {:?}", PROTO, n + 1, code))?;
+        let rules = parse_rules(comment);
+        messages.get_mut(message).expect("message block registered").push(Field { name, kind, rules });
+    }
+    Ok(messages)
+}
+
+/// prost's type name: UpperCamelCase with acronym runs as one word (RLMRequest → RlmRequest).
+fn rust_type(proto: &str) -> String {
+    let chars: Vec<char> = proto.chars().collect();
+    let mut out = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        let word_start = i == 0
+            || (c.is_uppercase()
+                && (chars[i - 1].is_lowercase() || chars.get(i + 1).is_some_and(|next| next.is_lowercase())));
+        if word_start {
+            out.extend(c.to_uppercase());
+        } else {
+            out.extend(c.to_lowercase());
+        }
+    }
+    out
+}
+
+fn rust_ident(field: &str) -> String {
+    if RUST_KEYWORDS.contains(&field) {
+        format!("r#{}", field)
+    } else {
+        field.to_string()
+    }
+}
+
+/// One check call for `rule` on `field` (read from `value`, a Rust expression).
+fn rule_check(message: &str, field: &Field, value: &str, rule: &str, arg: &str) -> Result<String, String> {
+    let count = |unit: &str| -> Result<(String, String), String> {
+        let n: usize = arg
+            .parse()
+            .map_err(|_| format!("{}.{}: {} needs a count, got {:?}", message, field.name, rule, arg))?;
+        Ok((n.to_string(), unit.to_string()))
+    };
+    let bound = || -> Result<String, String> {
+        let v: f64 = arg
+            .parse()
+            .map_err(|_| format!("{}.{}: {} needs a number, got {:?}", message, field.name, rule, arg))?;
+        Ok(format!("{:?}", v))
+    };
+    let ty = field.kind.type_name().unwrap_or("map");
+    let numeric = NUMERIC.contains(&ty) && !matches!(field.kind, Kind::Repeated(_));
+    let call = match (&field.kind, rule) {
+        (Kind::Singular(t), "min_len" | "max_len") if t == "string" => {
+            let (n, unit) = count("characters")?;
+            let f = if rule == "min_len" { "at_least" } else { "at_most" };
+            format!("{}(out, &p, {}.chars().count(), {}, {:?});", f, value, n, unit)
+        }
+        (Kind::Singular(t), "uuid") if t == "string" => format!("uuid(out, &p, &{});", value),
+        (Kind::Singular(t), "reasoning_id") if t == "string" => format!("reasoning_id(out, &p, &{});", value),
+        (Kind::Repeated(_), "min_items" | "max_items") => {
+            let (n, unit) = count("items")?;
+            let f = if rule == "min_items" { "at_least" } else { "at_most" };
+            format!("{}(out, &p, {}.len(), {}, {:?});", f, value, n, unit)
+        }
+        (Kind::Map, "min_pairs" | "max_pairs") => {
+            let (n, unit) = count("entries")?;
+            let f = if rule == "min_pairs" { "at_least" } else { "at_most" };
+            format!("{}(out, &p, {}.len(), {}, {:?});", f, value, n, unit)
+        }
+        (Kind::Singular(_), "gte" | "lte") if numeric => format!("{}(out, &p, {} as f64, {});", rule, value, bound()?),
+        (Kind::Optional(_), "gte" | "lte") if numeric => {
+            format!("if let Some(v) = {} {{ {}(out, &p, v as f64, {}); }}", value, rule, bound()?)
+        }
+        _ => return Err(format!("{}.{}: rule {:?} does not apply to {}", message, field.name, rule, ty)),
+    };
+    Ok(call)
+}
+
+fn generate(messages: &BTreeMap<String, Vec<Field>>) -> Result<String, String> {
+    // Annotated messages, then (to a fixpoint) the messages that contain them.
+    let mut checked: BTreeSet<&str> = messages
+        .iter()
+        .filter(|(_, fields)| fields.iter().any(|f| !f.rules.is_empty()))
+        .map(|(name, _)| name.as_str())
+        .collect();
+    loop {
+        let holders: Vec<&str> = messages
+            .iter()
+            .filter(|(name, fields)| {
+                !checked.contains(name.as_str())
+                    && fields.iter().any(|f| f.kind.type_name().is_some_and(|t| checked.contains(t)))
+            })
+            .map(|(name, _)| name.as_str())
+            .collect();
+        if holders.is_empty() {
+            break;
+        }
+        checked.extend(holders);
+    }
+
+    let mut out = String::from("// @generated by build.rs from the @validate annotations in pagi.proto.\n");
+    for &name in &checked {
+        let _ = writeln!(out, "\nimpl Validate for pagi_proto::{} {{", rust_type(name));
+        let _ = writeln!(out, "    fn check(&self, path: &str, out: &mut Vec<FieldViolation>) {{");
+        for field in &messages[name] {
+            let nested = field.kind.type_name().is_some_and(|t| checked.contains(t));
+            if field.rules.is_empty() && !nested {
+                continue;
+            }
+            let value = format!("self.{}", rust_ident(&field.name));
+            let _ = writeln!(out, "        {{");
+            let _ = writeln!(out, "            let p = field_path(path, {:?});", field.name);
+            for (rule, arg) in &field.rules {
+                let _ = writeln!(out, "            {}", rule_check(name, field, &value, rule, arg)?);
+            }
+            if nested {
+                let recurse = match field.kind {
+                    Kind::Repeated(_) => format!(
+                        "for (i, v) in {}.iter().enumerate() {{ v.check(&format!(\"{{}}[{{}}]\", p, i), out); }}",
+                        value
+                    ),
+                    _ => format!("if let Some(v) = &{} {{ v.check(&p, out); }}", value),
+                };
+                let _ = writeln!(out, "            {}", recurse);
+            }
+            let _ = writeln!(out, "        }}");
+        }
+        let _ = writeln!(out, "    }}\n}}");
+    }
+    Ok(out)
+}
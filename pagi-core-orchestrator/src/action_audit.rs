@@ -0,0 +1,222 @@
+// Audit trail of real L5 dispatches and the pieces of their forensic replay. Every real action gets a record (id
+// returned as ActionResponse.metadata["audit_id"]): skill, params as dispatched (after path confinement), timeout,
+// filesystem policy, the git blob id of the skill source that ran, the environment fingerprint and the outcome.
+// Records go to the durable store (PAGI_STORE) and the newest MAX_RECORDS stay in process, restored at startup.
+// ReplayAction (admin) rebuilds the invocation from a record: the skill source is recovered from git by blob id
+// (or from disk when the file still hashes to it), run under the recorded policy made read-only, and the new
+// observation diffed against the recorded one.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::skill_manifest::{Filesystem, Roots};
+use crate::store::{self, Store};
+
+/// Records kept in process for replay.
+const MAX_RECORDS: usize = 4096;
+
+/// One audited dispatch. Fields added after the first release default when absent from stored records.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionRecord {
+    pub at_ms: i64,
+    pub reasoning_id: String,
+    pub skill: String,
+    pub success: bool,
+    /// Observation, or the error when the run failed.
+    pub detail: String,
+    pub fingerprint: String,
+    pub usage: String,
+    pub params: HashMap<String, String>,
+    pub timeout_ms: u32,
+    /// Skill file relative to the bridge dir; empty for builtins.
+    pub skill_file: String,
+    /// Git blob id of the skill file's content at dispatch; empty when unreadable or builtin.
+    pub skill_blob: String,
+    /// Manifest filesystem the skill was confined to; None when unconfined.
+    pub filesystem: Option<Filesystem>,
+}
+
+/// Newest-last records by id, backed by the store when one is installed.
+pub struct AuditTrail {
+    records: Mutex<(HashMap<String, ActionRecord>, VecDeque<String>)>,
+    store: Option<Arc<Store>>,
+}
+
+impl AuditTrail {
+    /// Restore the newest records from `store`.
+    pub fn new(store: Option<Arc<Store>>) -> Self {
+        let mut restored: Vec<(String, ActionRecord)> =
+            store.as_ref().map(|s| s.take(store::AUDIT)).unwrap_or_default();
+        restored.sort_by_key(|(_, r)| r.at_ms);
+        let trail = Self {
+            records: Mutex::new((HashMap::new(), VecDeque::new())),
+            store: None,
+        };
+        for (id, record) in restored {
+            trail.insert(id, record);
+        }
+        Self { store, ..trail }
+    }
+
+    /// Append `record`; returns its id.
+    pub fn record(&self, record: ActionRecord) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        if let Some(store) = &self.store {
+            store.put(store::AUDIT, &id, &record);
+        }
+        self.insert(id.clone(), record);
+        id
+    }
+
+    fn insert(&self, id: String, record: ActionRecord) {
+        let mut guard = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        let (records, order) = &mut *guard;
+        if records.insert(id.clone(), record).is_none() {
+            order.push_back(id);
+        }
+        while order.len() > MAX_RECORDS {
+            if let Some(oldest) = order.pop_front() {
+                records.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<ActionRecord> {
+        self.records.lock().unwrap_or_else(|e| e.into_inner()).0.get(id).cloned()
+    }
+}
+
+/// Git blob id of `content` (what `git hash-object` prints).
+pub fn blob_id(content: &[u8]) -> String {
+    git2::Oid::hash_object(git2::ObjectType::Blob, content).map_or_else(|_| String::new(), |oid| oid.to_string())
+}
+
+/// The skill source a record ran: the blob from the bridge repo's object database, else the file on disk when
+/// it still hashes to the recorded id.
+pub fn recover_source(bridge_dir: &Path, record: &ActionRecord) -> Result<Vec<u8>, String> {
+    if record.skill_blob.is_empty() {
+        return Err(format!("audit record of {} has no skill source id", record.skill));
+    }
+    let from_git = git2::Repository::discover(bridge_dir).ok().and_then(|repo| {
+        let oid = git2::Oid::from_str(&record.skill_blob).ok()?;
+        repo.find_blob(oid).ok().map(|blob| blob.content().to_vec())
+    });
+    if let Some(content) = from_git {
+        return Ok(content);
+    }
+    match std::fs::read(bridge_dir.join(&record.skill_file)) {
+        Ok(content) if blob_id(&content) == record.skill_blob => Ok(content),
+        _ => Err(format!(
+            "skill source {} of {} is neither in git nor on disk any more",
+            record.skill_blob, record.skill
+        )),
+    }
+}
+
+/// The recorded policy with every writable directory demoted to read-only; unconfined skills may read the
+/// skill roots.
+pub fn read_only(filesystem: Option<&Filesystem>, roots: &Roots) -> Filesystem {
+    let read = match filesystem {
+        Some(fs) => fs.read.iter().chain(&fs.write).cloned().collect(),
+        None => std::iter::once(roots.bridge.clone()).chain(roots.data.clone()).collect::<Vec<PathBuf>>(),
+    };
+    Filesystem { read, write: Vec::new() }
+}
+
+/// Unified diff of the recorded and replayed observations; empty when they are equal.
+pub fn diff(recorded: &str, replayed: &str) -> String {
+    if recorded == replayed {
+        return String::new();
+    }
+    let patch = git2::Patch::from_buffers(
+        recorded.as_bytes(),
+        Some(Path::new("recorded")),
+        replayed.as_bytes(),
+        Some(Path::new("replayed")),
+        None,
+    );
+    match patch.and_then(|mut p| p.to_buf()) {
+        Ok(buf) => buf.as_str().unwrap_or_default().to_string(),
+        Err(e) => format!("(diff unavailable: {})", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A bridge dir holding `src/skills/peek.py` and a record of running it with a declared filesystem.
+    fn recorded() -> (PathBuf, ActionRecord) {
+        let dir = std::env::temp_dir().join(format!("pagi_action_audit_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("src/skills")).unwrap();
+        std::fs::write(dir.join("src/skills/peek.py"), "print('v1')\n").unwrap();
+        let record = ActionRecord {
+            skill: "peek".into(),
+            skill_file: "src/skills/peek.py".into(),
+            skill_blob: blob_id(b"print('v1')\n"),
+            filesystem: Some(Filesystem {
+                read: vec![dir.join("src")],
+                write: vec![dir.join("out")],
+            }),
+            ..Default::default()
+        };
+        (dir, record)
+    }
+
+    fn roots(dir: &Path) -> Roots {
+        Roots {
+            bridge: dir.to_path_buf(),
+            data: None,
+        }
+    }
+
+    #[test]
+    fn records_are_fetched_by_id_with_a_git_blob_id() {
+        let (dir, record) = recorded();
+        let trail = AuditTrail::new(None);
+        let id = trail.record(record.clone());
+        assert_eq!(trail.get(&id), Some(record.clone()));
+        assert_eq!(record.skill_blob.len(), 40);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn unchanged_sources_are_recovered_from_the_worktree() {
+        let (dir, record) = recorded();
+        assert_eq!(recover_source(&dir, &record).unwrap(), b"print('v1')\n");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn sources_edited_since_and_never_committed_are_lost() {
+        let (dir, record) = recorded();
+        std::fs::write(dir.join("src/skills/peek.py"), "print('v2')\n").unwrap();
+        assert!(recover_source(&dir, &record).is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn replays_may_read_but_never_write_the_declared_paths() {
+        let (dir, record) = recorded();
+        let sandbox = read_only(record.filesystem.as_ref(), &roots(&dir));
+        assert_eq!((sandbox.read, sandbox.write), (vec![dir.join("src"), dir.join("out")], vec![]));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn undeclared_replays_read_only_the_bridge() {
+        let dir = std::env::temp_dir().join("pagi_action_audit_bridge");
+        assert_eq!(read_only(None, &roots(&dir)).read, [dir]);
+    }
+
+    #[test]
+    fn diffs_show_changed_lines_only() {
+        assert_eq!(diff("same\n", "same\n"), "");
+        let changed = diff("a\nb\n", "a\nc\n");
+        assert!(changed.contains("-b\n") && changed.contains("+c\n"), "{}", changed);
+    }
+}
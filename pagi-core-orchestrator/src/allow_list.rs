@@ -0,0 +1,358 @@
+// L5 skill allow-list: sorted skill names, content hash, and a revision id for the source they came from.
+// Mismatch errors carry an AllowListMismatch detail (server hash, revision, set difference versus the
+// caller's catalog) so the bridge can resync its tool catalog without another round trip.
+// Skills are discovered from PAGI_SKILL_SOURCES roots (default src/skills); roots other than the
+// default contribute namespaced names (`plugin.skill`).
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use git2::Repository;
+use prost::Message;
+use sha2::{Digest, Sha256};
+use tonic::{Code, Status};
+
+use crate::proto::pagi_proto::AllowListMismatch;
+
+const DEFAULT_SOURCE: &str = "src/skills";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllowList {
+    /// Sorted skill names.
+    pub skills: Vec<String>,
+    /// Git tree id of the skills directory ("git:<short>"), or "worktree" when read from disk;
+    /// "sources:<short>" digest of per-root revisions when several roots contribute.
+    pub revision: String,
+    /// Skill files outside the default root (namespaced skills), relative to the bridge dir.
+    pub paths: BTreeMap<String, PathBuf>,
+}
+
+/// One PAGI_SKILL_SOURCES entry: `[namespace=]pattern`, pattern relative to the bridge dir.
+/// A `*` path segment matches any directory; its first match names the namespace by default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkillSource {
+    pub namespace: Option<String>,
+    pub pattern: String,
+}
+
+fn valid_name(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl SkillSource {
+    fn parse(entry: &str) -> Option<Self> {
+        let entry = entry.trim();
+        if entry.is_empty() {
+            return None;
+        }
+        let (namespace, pattern) = match entry.split_once('=') {
+            Some((ns, p)) if valid_name(ns.trim()) => (Some(ns.trim().to_string()), p.trim()),
+            Some(_) => {
+                eprintln!("[AllowList] invalid namespace in skill source {:?}", entry);
+                return None;
+            }
+            None => (None, entry),
+        };
+        Some(Self {
+            namespace,
+            pattern: pattern.trim_end_matches('/').replace('\\', "/"),
+        })
+    }
+
+    /// PAGI_SKILL_SOURCES (comma-separated); default src/skills.
+    pub fn from_env() -> Vec<Self> {
+        let raw = std::env::var("PAGI_SKILL_SOURCES").unwrap_or_default();
+        let sources: Vec<Self> = raw.split(',').filter_map(Self::parse).collect();
+        if sources.is_empty() {
+            vec![Self {
+                namespace: None,
+                pattern: DEFAULT_SOURCE.to_string(),
+            }]
+        } else {
+            sources
+        }
+    }
+
+    /// Concrete (namespace, dir) roots for this pattern under `bridge_dir`, sorted.
+    fn resolve(&self, bridge_dir: &Path) -> Vec<(Option<String>, PathBuf)> {
+        let mut found = Vec::new();
+        let segments: Vec<&str> = self.pattern.split('/').filter(|s| !s.is_empty() && *s != ".").collect();
+        expand(bridge_dir, &segments, None, &mut found);
+        found.sort();
+        found
+            .into_iter()
+            .filter_map(|(wild, dir)| match self.namespace.clone().or(wild) {
+                // Wildcard roots must yield an identifier namespace; plain paths may stay unnamespaced.
+                Some(ns) if !valid_name(&ns) => {
+                    eprintln!("[AllowList] skipping {} (namespace {:?} not an identifier)", dir.display(), ns);
+                    None
+                }
+                ns => Some((ns, dir)),
+            })
+            .collect()
+    }
+}
+
+/// Match `*` within a single path segment.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            if !name.starts_with(prefix) {
+                return false;
+            }
+            let tail = &name[prefix.len()..];
+            (0..=tail.len()).any(|i| tail.is_char_boundary(i) && wildcard_match(rest, &tail[i..]))
+        }
+    }
+}
+
+fn expand(dir: &Path, segments: &[&str], wild: Option<String>, out: &mut Vec<(Option<String>, PathBuf)>) {
+    let Some((seg, rest)) = segments.split_first() else {
+        if dir.is_dir() {
+            out.push((wild, dir.to_path_buf()));
+        }
+        return;
+    };
+    if !seg.contains('*') {
+        expand(&dir.join(seg), rest, wild, out);
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if entry.path().is_dir() && !name.starts_with('.') && wildcard_match(seg, &name) {
+            let wild = wild.clone().or_else(|| Some(name.clone()));
+            expand(&entry.path(), rest, wild, out);
+        }
+    }
+}
+
+fn skill_stem(file_name: &str) -> Option<&str> {
+    file_name
+        .strip_suffix(".py")
+        .filter(|stem| *stem != "__init__" && valid_name(stem))
+}
+
+/// Skill stems under `dir`: committed git tree when tracked (tracked files only), else the worktree.
+fn list_root(repo: Option<&Repository>, dir: &Path) -> (Vec<String>, String) {
+    if let Some(repo) = repo {
+        let tree = repo.workdir().and_then(|workdir| {
+            let rel = dir.strip_prefix(workdir).ok()?;
+            let rel = rel.to_string_lossy().replace('\\', "/");
+            let root = repo.head().ok()?.peel_to_commit().ok()?.tree().ok()?;
+            root.get_path(Path::new(&rel)).ok()?.to_object(repo).ok()?.peel_to_tree().ok()
+        });
+        if let Some(tree) = tree {
+            let names: Vec<String> = tree
+                .iter()
+                .filter_map(|e| e.name().and_then(skill_stem).map(str::to_string))
+                .collect();
+            if !names.is_empty() {
+                let mut id = tree.id().to_string();
+                id.truncate(12);
+                return (names, format!("git:{}", id));
+            }
+        }
+    }
+    let mut names = Vec::new();
+    if let Ok(rd) = std::fs::read_dir(dir) {
+        for e in rd.flatten() {
+            if let Some(stem) = e.file_name().to_str().and_then(skill_stem) {
+                names.push(stem.to_string());
+            }
+        }
+    }
+    (names, "worktree".to_string())
+}
+
+/// Merge all sources into one allow-list. Duplicate names keep the first source's entry.
+pub fn load(bridge_dir: &Path, sources: &[SkillSource]) -> AllowList {
+    let repo = Repository::discover(bridge_dir).ok();
+    let mut skills = BTreeSet::new();
+    let mut paths = BTreeMap::new();
+    let mut revisions = Vec::new();
+    for source in sources {
+        for (ns, dir) in source.resolve(bridge_dir) {
+            let (names, revision) = list_root(repo.as_ref(), &dir);
+            revisions.push(format!("{}={}", ns.as_deref().unwrap_or(""), revision));
+            for stem in names {
+                let name = match &ns {
+                    Some(ns) => format!("{}.{}", ns, stem),
+                    None => stem.clone(),
+                };
+                if !skills.insert(name.clone()) {
+                    eprintln!("[AllowList] duplicate skill {} in {}; keeping first", name, dir.display());
+                    continue;
+                }
+                if ns.is_some() {
+                    let file = dir.join(format!("{}.py", stem));
+                    let rel = file.strip_prefix(bridge_dir).map(Path::to_path_buf).unwrap_or(file);
+                    paths.insert(name, rel);
+                }
+            }
+        }
+    }
+    let revision = match revisions.as_slice() {
+        [] => "worktree".to_string(),
+        [single] => single.split_once('=').map(|(_, r)| r.to_string()).unwrap_or_default(),
+        many => {
+            let digest = Sha256::digest(many.join("\n").as_bytes());
+            format!("sources:{}", &format!("{:x}", digest)[..12])
+        }
+    };
+    AllowList {
+        skills: skills.into_iter().collect(),
+        revision,
+        paths,
+    }
+}
+
+impl AllowList {
+    /// Source file of a listed skill, relative to the bridge dir.
+    pub fn skill_file(&self, name: &str) -> PathBuf {
+        self.paths
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| Path::new(DEFAULT_SOURCE).join(format!("{}.py", name)))
+    }
+
+    /// SHA256 hex of sorted allow-list (one name per line) for consistency check.
+    pub fn hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        for s in &self.skills {
+            hasher.update(s.as_bytes());
+            hasher.update(b"\n");
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    pub fn contains(&self, skill: &str) -> bool {
+        self.skills.iter().any(|s| s == skill)
+    }
+
+    /// InvalidArgument with an encoded AllowListMismatch detail. `caller_skills` may be empty,
+    /// in which case only the server side (hash, revision, skills) is populated.
+    pub fn mismatch_status(&self, caller_hash: &str, caller_skills: &[String]) -> Status {
+        let server: BTreeSet<&String> = self.skills.iter().collect();
+        let caller: BTreeSet<&String> = caller_skills.iter().collect();
+        let (missing_on_caller, unknown_to_server) = if caller_skills.is_empty() {
+            (Vec::new(), Vec::new())
+        } else {
+            (
+                server.difference(&caller).map(|s| s.to_string()).collect(),
+                caller.difference(&server).map(|s| s.to_string()).collect::<Vec<_>>(),
+            )
+        };
+        let server_hash = self.hash();
+        let message = format!(
+            "Allow-list mismatch: caller {} vs server {} (revision {}); {} missing on caller, {} unknown to server",
+            short(caller_hash),
+            short(&server_hash),
+            self.revision,
+            missing_on_caller.len(),
+            unknown_to_server.len()
+        );
+        let detail = AllowListMismatch {
+            server_hash,
+            revision: self.revision.clone(),
+            server_skills: self.skills.clone(),
+            missing_on_caller,
+            unknown_to_server,
+        };
+        Status::with_details(Code::InvalidArgument, message, detail.encode_to_vec().into())
+    }
+}
+
+fn short(hash: &str) -> &str {
+    hash.get(..12).unwrap_or(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list() -> AllowList {
+        AllowList {
+            skills: vec!["list_dir".into(), "peek_file".into(), "save_skill".into()],
+            revision: "git:abc".into(),
+            paths: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn mismatches_are_invalid_argument() {
+        let status = list().mismatch_status("deadbeef", &["peek_file".to_string()]);
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert!(status.message().starts_with("Allow-list mismatch"));
+    }
+
+    #[test]
+    fn mismatch_details_carry_the_server_hash_and_both_set_differences() {
+        let list = list();
+        let caller = vec!["peek_file".to_string(), "old_skill".to_string()];
+        let detail = AllowListMismatch::decode(list.mismatch_status("deadbeef", &caller).details()).unwrap();
+        assert_eq!(detail.server_hash, list.hash());
+        assert_eq!(detail.revision, "git:abc");
+        assert_eq!(detail.missing_on_caller, vec!["list_dir", "save_skill"]);
+        assert_eq!(detail.unknown_to_server, vec!["old_skill"]);
+    }
+
+    #[test]
+    fn callers_without_a_list_get_the_server_skills() {
+        let bare = AllowListMismatch::decode(list().mismatch_status("x", &[]).details()).unwrap();
+        assert_eq!(bare.server_skills.len(), 3);
+        assert!(bare.missing_on_caller.is_empty());
+    }
+
+    /// A bridge with a core skill, two plugin skill dirs and an extra dir, plus files that are not skills.
+    fn bridge() -> PathBuf {
+        let bridge = std::env::temp_dir().join(format!("pagi_sources_{}", uuid::Uuid::new_v4()));
+        for rel in [
+            "src/skills/peek_file.py",
+            "src/skills/__init__.py",
+            "plugins/git_tools/skills/blame.py",
+            "plugins/web/skills/fetch.py",
+            "plugins/web/skills/not-a-skill.py",
+            "extra/lint.py",
+        ] {
+            let p = bridge.join(rel);
+            std::fs::create_dir_all(p.parent().unwrap()).unwrap();
+            std::fs::write(p, "").unwrap();
+        }
+        bridge
+    }
+
+    fn sources(spec: &str) -> Vec<SkillSource> {
+        spec.split(',').filter_map(SkillSource::parse).collect()
+    }
+
+    #[test]
+    fn sources_merge_with_namespaces() {
+        let bridge = bridge();
+        let list = load(&bridge, &sources("src/skills, plugins/*/skills, tools=extra"));
+        assert_eq!(list.skills, vec!["git_tools.blame", "peek_file", "tools.lint", "web.fetch"]);
+        assert!(list.revision.starts_with("sources:"));
+        let _ = std::fs::remove_dir_all(bridge);
+    }
+
+    #[test]
+    fn only_skills_outside_src_skills_record_their_path() {
+        let bridge = bridge();
+        let list = load(&bridge, &sources("src/skills, plugins/*/skills, tools=extra"));
+        assert_eq!(list.paths["web.fetch"], PathBuf::from("plugins/web/skills/fetch.py"));
+        assert!(!list.paths.contains_key("peek_file"));
+        let _ = std::fs::remove_dir_all(bridge);
+    }
+
+    #[test]
+    fn the_default_source_keeps_the_worktree_revision() {
+        let bridge = bridge();
+        let default = load(&bridge, &sources("src/skills"));
+        assert_eq!(default.skills, vec!["peek_file"]);
+        assert_eq!(default.revision, "worktree");
+        let _ = std::fs::remove_dir_all(bridge);
+    }
+}
@@ -0,0 +1,335 @@
+// Patch application queue: serializes ApplyPatch per target repo (FIFO) and tracks per-patch status
+// so concurrent applies cannot interleave test runs or race on the registry index.
+// With a durable store (PAGI_STORE), states are written through and restored on startup; applies that were
+// queued or running when the process died come back as failed.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+use crate::store::{self, Store};
+
+/// Finished entries retained for status queries before the oldest are dropped.
+const MAX_FINISHED: usize = 1024;
+
+/// Lifecycle of one apply request as seen by GetApplyStatus.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ApplyState {
+    Queued,
+    Running,
+    Applied { commit_hash: String },
+    Failed { error: String },
+    /// Undone by RollbackPatch; `commit_hash` is the registry commit reverting the apply.
+    RolledBack { commit_hash: String },
+}
+
+impl ApplyState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApplyState::Queued => "queued",
+            ApplyState::Running => "running",
+            ApplyState::Applied { .. } => "applied",
+            ApplyState::Failed { .. } => "failed",
+            ApplyState::RolledBack { .. } => "rolled_back",
+        }
+    }
+}
+
+/// Status snapshot returned to callers; queue_position is 0 when running or finished.
+#[derive(Debug, Clone)]
+pub struct ApplyStatus {
+    pub state: ApplyState,
+    pub queue_position: u32,
+    pub target: PathBuf,
+}
+
+/// Stored form of one `states` entry.
+#[derive(Serialize, Deserialize)]
+struct StoredState {
+    #[serde(flatten)]
+    state: ApplyState,
+    target: PathBuf,
+}
+
+/// Per-repo lane: FIFO mutex (tokio's Mutex is fair) plus the ordered list of waiters.
+#[derive(Default)]
+struct Lane {
+    lock: Arc<AsyncMutex<()>>,
+    waiting: Mutex<VecDeque<String>>,
+}
+
+pub struct ApplyQueue {
+    lanes: DashMap<PathBuf, Arc<Lane>>,
+    /// patch_id -> (state, target repo)
+    states: DashMap<String, (ApplyState, PathBuf)>,
+    finished_order: Mutex<VecDeque<String>>,
+    /// Durable copy of `states` (PAGI_STORE).
+    store: Option<Arc<Store>>,
+}
+
+/// Held while an apply waits or runs; dropping it releases the repo lane for the next queued patch.
+/// Dropped before `finish` (handler cancelled, e.g. AbortRequest) → recorded as failed, not left running.
+pub struct ApplyTicket<'a> {
+    queue: &'a ApplyQueue,
+    lane: Arc<Lane>,
+    patch_id: String,
+    _guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl Drop for ApplyTicket<'_> {
+    fn drop(&mut self) {
+        self.lane
+            .waiting
+            .lock()
+            .expect("apply lane poisoned")
+            .retain(|id| id != &self.patch_id);
+        let unfinished = self
+            .queue
+            .states
+            .get(&self.patch_id)
+            .is_some_and(|s| matches!(s.0, ApplyState::Queued | ApplyState::Running));
+        if unfinished {
+            self.queue.finish(
+                &self.patch_id,
+                ApplyState::Failed {
+                    error: "apply cancelled".to_string(),
+                },
+            );
+        }
+    }
+}
+
+impl ApplyQueue {
+    pub fn new() -> Self {
+        Self {
+            lanes: DashMap::new(),
+            states: DashMap::new(),
+            finished_order: Mutex::new(VecDeque::new()),
+            store: None,
+        }
+    }
+
+    /// Restore stored states (unfinished ones as failed) and write every later change through.
+    pub fn with_store(mut self, store: Arc<Store>) -> Self {
+        for (patch_id, stored) in store.take::<StoredState>(store::APPLY_STATUS) {
+            let state = match stored.state {
+                ApplyState::Queued | ApplyState::Running => {
+                    let state = ApplyState::Failed {
+                        error: "interrupted by orchestrator restart".to_string(),
+                    };
+                    store.put(
+                        store::APPLY_STATUS,
+                        &patch_id,
+                        &StoredState {
+                            state: state.clone(),
+                            target: stored.target.clone(),
+                        },
+                    );
+                    state
+                }
+                finished => finished,
+            };
+            self.states.insert(patch_id.clone(), (state, stored.target));
+            self.finished_order.get_mut().expect("apply order poisoned").push_back(patch_id);
+        }
+        self.store = Some(store);
+        self
+    }
+
+    /// Set patch_id's state, writing it through to the store.
+    fn set_state(&self, patch_id: &str, state: ApplyState, target: PathBuf) {
+        if let Some(store) = &self.store {
+            store.put(
+                store::APPLY_STATUS,
+                patch_id,
+                &StoredState {
+                    state: state.clone(),
+                    target: target.clone(),
+                },
+            );
+        }
+        self.states.insert(patch_id.to_string(), (state, target));
+    }
+
+    fn lane(&self, target: &Path) -> Arc<Lane> {
+        self.lanes
+            .entry(target.to_path_buf())
+            .or_insert_with(|| Arc::new(Lane::default()))
+            .clone()
+    }
+
+    /// Enqueue patch_id on the lane for `target` and wait for its turn. Returns an error string when
+    /// the patch is already queued or running (duplicate ApplyPatch for the same id).
+    pub async fn acquire(&self, target: &Path, patch_id: &str) -> Result<ApplyTicket<'_>, String> {
+        match self.states.entry(patch_id.to_string()) {
+            Entry::Occupied(mut e) => {
+                if matches!(e.get().0, ApplyState::Queued | ApplyState::Running) {
+                    return Err(format!("patch {} is already {}", patch_id, e.get().0.as_str()));
+                }
+                e.insert((ApplyState::Queued, target.to_path_buf()));
+            }
+            Entry::Vacant(e) => {
+                e.insert((ApplyState::Queued, target.to_path_buf()));
+            }
+        }
+        if let Some(store) = &self.store {
+            store.put(
+                store::APPLY_STATUS,
+                patch_id,
+                &StoredState {
+                    state: ApplyState::Queued,
+                    target: target.to_path_buf(),
+                },
+            );
+        }
+        let lane = self.lane(target);
+        lane.waiting
+            .lock()
+            .expect("apply lane poisoned")
+            .push_back(patch_id.to_string());
+
+        let mut ticket = ApplyTicket {
+            queue: self,
+            lane: Arc::clone(&lane),
+            patch_id: patch_id.to_string(),
+            _guard: None,
+        };
+        ticket._guard = Some(Arc::clone(&lane.lock).lock_owned().await);
+
+        lane.waiting
+            .lock()
+            .expect("apply lane poisoned")
+            .retain(|id| id != patch_id);
+        self.set_state(patch_id, ApplyState::Running, target.to_path_buf());
+        Ok(ticket)
+    }
+
+    /// Record the final outcome for patch_id (call before dropping the ticket).
+    pub fn finish(&self, patch_id: &str, state: ApplyState) {
+        let target = self
+            .states
+            .get(patch_id)
+            .map(|s| s.1.clone())
+            .unwrap_or_default();
+        self.set_state(patch_id, state, target);
+        let mut order = self.finished_order.lock().expect("apply order poisoned");
+        order.push_back(patch_id.to_string());
+        while order.len() > MAX_FINISHED {
+            if let Some(old) = order.pop_front() {
+                self.states.remove(&old);
+                if let Some(store) = &self.store {
+                    store.delete(store::APPLY_STATUS, &old);
+                }
+            }
+        }
+    }
+
+    /// Current status of patch_id, with 1-based queue position while queued.
+    pub fn status(&self, patch_id: &str) -> Option<ApplyStatus> {
+        let (state, target) = self.states.get(patch_id).map(|s| s.value().clone())?;
+        let queue_position = if state == ApplyState::Queued {
+            self.lanes
+                .get(&target)
+                .and_then(|lane| {
+                    lane.waiting
+                        .lock()
+                        .expect("apply lane poisoned")
+                        .iter()
+                        .position(|id| id == patch_id)
+                })
+                .map(|p| p as u32 + 1)
+                .unwrap_or(0)
+        } else {
+            0
+        };
+        Some(ApplyStatus {
+            state,
+            queue_position,
+            target,
+        })
+    }
+}
+
+impl Default for ApplyQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn second_apply_waits_and_reports_position() {
+        let q = Arc::new(ApplyQueue::new());
+        let target = PathBuf::from("/tmp/repo_a");
+        let first = q.acquire(&target, "p1").await.unwrap();
+        assert_eq!(q.status("p1").unwrap().state, ApplyState::Running);
+
+        let q2 = Arc::clone(&q);
+        let t2 = target.clone();
+        let waiter = tokio::spawn(async move {
+            let _t = q2.acquire(&t2, "p2").await.unwrap();
+            q2.finish("p2", ApplyState::Applied { commit_hash: "abc".into() });
+        });
+        tokio::task::yield_now().await;
+        while q.status("p2").is_none() {
+            tokio::task::yield_now().await;
+        }
+        let st = q.status("p2").unwrap();
+        assert_eq!(st.state, ApplyState::Queued);
+        assert_eq!(st.queue_position, 1);
+        assert!(q.acquire(&target, "p2").await.is_err(), "duplicate apply rejected");
+
+        q.finish("p1", ApplyState::Failed { error: "tests".into() });
+        drop(first);
+        waiter.await.unwrap();
+        assert_eq!(q.status("p2").unwrap().state.as_str(), "applied");
+        assert_eq!(q.status("p1").unwrap().state.as_str(), "failed");
+    }
+
+    #[tokio::test]
+    async fn cancelled_apply_is_not_left_running() {
+        let q = ApplyQueue::new();
+        let target = PathBuf::from("/tmp/repo_b");
+        let running = q.acquire(&target, "p1").await.unwrap();
+        // Queued apply cancelled while waiting for the lane.
+        let queued = tokio::time::timeout(std::time::Duration::from_millis(20), q.acquire(&target, "p2")).await;
+        assert!(queued.is_err());
+        assert_eq!(q.status("p2").unwrap().state.as_str(), "failed");
+        drop(running);
+        assert!(matches!(q.status("p1").unwrap().state, ApplyState::Failed { error } if error == "apply cancelled"));
+        assert!(q.acquire(&target, "p1").await.is_ok(), "retry after cancel allowed");
+    }
+
+    #[tokio::test]
+    async fn stored_states_restore_and_unfinished_become_failed() {
+        let dir = std::env::temp_dir().join(format!("pagi_apply_store_{}", uuid::Uuid::new_v4()));
+        let store = Store::open(Arc::new(store::FileRepository::new(dir.clone()))).await.unwrap();
+        let q = ApplyQueue::new().with_store(Arc::clone(&store));
+        let target = PathBuf::from("/tmp/repo_c");
+        let done = q.acquire(&target, "p1").await.unwrap();
+        q.finish("p1", ApplyState::Applied { commit_hash: "abc".into() });
+        drop(done);
+        let _running = q.acquire(&target, "p2").await.unwrap();
+        store.flush().await;
+
+        let reopened = Store::open(Arc::new(store::FileRepository::new(dir.clone()))).await.unwrap();
+        let restarted = ApplyQueue::new().with_store(reopened);
+        assert_eq!(
+            restarted.status("p1").unwrap().state,
+            ApplyState::Applied { commit_hash: "abc".into() }
+        );
+        let p2 = restarted.status("p2").unwrap();
+        assert!(matches!(p2.state, ApplyState::Failed { error } if error.contains("restart")));
+        assert_eq!(p2.target, target);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,170 @@
+// HITL approval-wait policy shared by HITL-gated flows: wait duration, reminder webhook re-sends,
+// an explicit timeout fallback recorded in the patch catalog, and which skill tiers need approval per action.
+
+use std::time::Duration;
+
+use crate::provenance::{self, SkillTier};
+
+/// What happens to a patch when the approval wait elapses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutFallback {
+    /// Leave the patch pending; ApplyPatch is denied until someone approves it later.
+    Deny,
+    /// Drop the patch from the catalog; a new proposal is required.
+    Reject,
+}
+
+impl TimeoutFallback {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimeoutFallback::Deny => "deny",
+            TimeoutFallback::Reject => "reject",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ApprovalPolicy {
+    /// Total wait for approval (PAGI_HITL_POLL_SECS, default 30).
+    pub wait: Duration,
+    /// Approve-flag poll step (PAGI_HITL_POLL_INTERVAL_MS, default 1000).
+    pub poll_interval: Duration,
+    /// Optional webhook notified when the wait starts and on each reminder (PAGI_HITL_WEBHOOK_URL).
+    pub webhook_url: Option<String>,
+    /// Reminder re-send interval; zero disables re-sends (PAGI_HITL_REMINDER_SECS).
+    pub reminder_interval: Duration,
+    /// PAGI_HITL_TIMEOUT_FALLBACK: "deny" (default) or "reject".
+    pub fallback: TimeoutFallback,
+    /// Skill tiers whose real dispatches wait for a reviewer on the HITL channel (PAGI_HITL_ACTION_TIERS).
+    pub action_tiers: Vec<SkillTier>,
+}
+
+impl ApprovalPolicy {
+    pub fn from_env() -> Self {
+        let number = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(default)
+        };
+        let webhook_url = std::env::var("PAGI_HITL_WEBHOOK_URL")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        let fallback = match std::env::var("PAGI_HITL_TIMEOUT_FALLBACK")
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "reject" => TimeoutFallback::Reject,
+            _ => TimeoutFallback::Deny,
+        };
+        Self {
+            wait: Duration::from_secs(number("PAGI_HITL_POLL_SECS", 30)),
+            poll_interval: Duration::from_millis(number("PAGI_HITL_POLL_INTERVAL_MS", 1000).max(1)),
+            webhook_url,
+            reminder_interval: Duration::from_secs(number("PAGI_HITL_REMINDER_SECS", 0)),
+            fallback,
+            action_tiers: provenance::tiers_from_env("PAGI_HITL_ACTION_TIERS"),
+        }
+    }
+
+    /// Policy for the ApplyPatch HITL gate: as `from_env`, but the wait is PAGI_HITL_APPLY_WAIT_SECS (default 0,
+    /// deny at once) since the apply RPC holds its repo lane while waiting.
+    pub fn for_apply() -> Self {
+        let wait = std::env::var("PAGI_HITL_APPLY_WAIT_SECS")
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(0);
+        Self {
+            wait: Duration::from_secs(wait),
+            ..Self::from_env()
+        }
+    }
+
+    pub fn gates_action(&self, skill: &str) -> bool {
+        self.action_tiers.contains(&SkillTier::of(skill))
+    }
+}
+
+/// Best-effort POST of an approval notification; failures are logged, never fatal.
+pub async fn send_reminder(url: &str, body: &serde_json::Value) {
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build()
+    {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("[Approval] webhook client: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = client.post(url).json(body).send().await {
+        eprintln!("[Approval] webhook {}: {}", url, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::watchdog::tests::lock_test_env;
+
+    fn policy(action_tiers: Vec<SkillTier>) -> ApprovalPolicy {
+        ApprovalPolicy {
+            wait: Duration::from_secs(1),
+            poll_interval: Duration::from_millis(10),
+            webhook_url: None,
+            reminder_interval: Duration::ZERO,
+            fallback: TimeoutFallback::Deny,
+            action_tiers,
+        }
+    }
+
+    #[tokio::test]
+    async fn timeout_fallback_is_reject_only_when_asked_for() {
+        let _g = lock_test_env().await;
+        std::env::set_var("PAGI_HITL_TIMEOUT_FALLBACK", " Reject ");
+        assert_eq!(ApprovalPolicy::from_env().fallback, TimeoutFallback::Reject);
+        std::env::set_var("PAGI_HITL_TIMEOUT_FALLBACK", "drop");
+        assert_eq!(ApprovalPolicy::from_env().fallback, TimeoutFallback::Deny);
+        std::env::remove_var("PAGI_HITL_TIMEOUT_FALLBACK");
+        assert_eq!(ApprovalPolicy::from_env().fallback, TimeoutFallback::Deny);
+    }
+
+    #[tokio::test]
+    async fn blank_webhook_urls_disable_notifications() {
+        let _g = lock_test_env().await;
+        std::env::set_var("PAGI_HITL_WEBHOOK_URL", "  ");
+        std::env::set_var("PAGI_HITL_POLL_SECS", "not a number");
+        let policy = ApprovalPolicy::from_env();
+        assert_eq!(policy.webhook_url, None);
+        assert_eq!(policy.wait, Duration::from_secs(30));
+        std::env::remove_var("PAGI_HITL_WEBHOOK_URL");
+        std::env::remove_var("PAGI_HITL_POLL_SECS");
+    }
+
+    #[tokio::test]
+    async fn poll_interval_and_apply_wait_come_from_env() {
+        let _g = lock_test_env().await;
+        assert_eq!(ApprovalPolicy::from_env().poll_interval, Duration::from_secs(1));
+        assert!(ApprovalPolicy::for_apply().wait.is_zero());
+        std::env::set_var("PAGI_HITL_POLL_INTERVAL_MS", "250");
+        std::env::set_var("PAGI_HITL_APPLY_WAIT_SECS", "20");
+        let policy = ApprovalPolicy::for_apply();
+        assert_eq!((policy.wait, policy.poll_interval), (Duration::from_secs(20), Duration::from_millis(250)));
+        std::env::set_var("PAGI_HITL_POLL_INTERVAL_MS", "0");
+        assert_eq!(ApprovalPolicy::from_env().poll_interval, Duration::from_millis(1), "a zero step would spin");
+        std::env::remove_var("PAGI_HITL_POLL_INTERVAL_MS");
+        std::env::remove_var("PAGI_HITL_APPLY_WAIT_SECS");
+    }
+
+    #[test]
+    fn only_listed_tiers_gate_actions() {
+        let writes = policy(vec![SkillTier::Write]);
+        assert!(writes.gates_action("save_skill"));
+        assert!(!writes.gates_action("peek_file"));
+        assert!(!writes.gates_action("some_unknown_skill"), "unknown skills are exec tier");
+        assert!(!policy(Vec::new()).gates_action("save_skill"));
+    }
+}
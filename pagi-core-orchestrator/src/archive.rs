@@ -0,0 +1,210 @@
+// L7 archive: append-only cold storage for L4 points dropped by the retention/decay pass. With PAGI_ARCHIVE_DIR
+// set, pruned points (vector and payload) are appended as JSON lines to numbered segments
+// (segment-000001.jsonl, …), rolling to a new segment past PAGI_ARCHIVE_SEGMENT_MB; RecallArchive finds them
+// again and can rehydrate them into L4. Segments are never rewritten, so they can be shipped to object storage.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::proto::pagi_proto::{ArchivedPoint, RecallArchiveRequest, VectorPoint};
+
+const SEGMENT_PREFIX: &str = "segment-";
+const SEGMENT_EXT: &str = ".jsonl";
+/// RecallArchive results when the request sets no limit.
+const DEFAULT_RECALL_LIMIT: usize = 20;
+
+/// One archived point (JSON line).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveRecord {
+    pub kb_name: String,
+    pub id: String,
+    pub vector: Vec<f32>,
+    /// Named vectors, for KBs with named vector spaces.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub vectors: BTreeMap<String, Vec<f32>>,
+    pub payload: HashMap<String, String>,
+    /// Why it left L4: "decay", "age" or "count".
+    pub reason: String,
+    /// Unix seconds.
+    pub archived_at: i64,
+}
+
+impl ArchiveRecord {
+    pub fn to_proto(&self) -> ArchivedPoint {
+        ArchivedPoint {
+            kb_name: self.kb_name.clone(),
+            id: self.id.clone(),
+            payload: self.payload.clone(),
+            reason: self.reason.clone(),
+            archived_at_unix: self.archived_at,
+        }
+    }
+
+    fn matches(&self, req: &RecallArchiveRequest, query: &str) -> bool {
+        (req.kb_name.is_empty() || self.kb_name == req.kb_name)
+            && (req.ids.is_empty() || req.ids.contains(&self.id))
+            && (query.is_empty()
+                || self
+                    .payload
+                    .get("content")
+                    .is_some_and(|c| c.to_lowercase().contains(query)))
+    }
+}
+
+pub struct Archive {
+    dir: PathBuf,
+    segment_bytes: u64,
+    /// Serializes appends and segment rolls.
+    write_lock: Mutex<()>,
+}
+
+impl Archive {
+    pub fn new(dir: PathBuf, segment_bytes: u64) -> Self {
+        Self {
+            dir,
+            segment_bytes: segment_bytes.max(1),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    /// PAGI_ARCHIVE_DIR (unset or empty disables L7) and PAGI_ARCHIVE_SEGMENT_MB (default 64).
+    pub fn from_env() -> Option<Self> {
+        let dir = std::env::var("PAGI_ARCHIVE_DIR")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())?;
+        let mb = std::env::var("PAGI_ARCHIVE_SEGMENT_MB")
+            .ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .unwrap_or(64);
+        Some(Self::new(PathBuf::from(dir), mb.saturating_mul(1024 * 1024)))
+    }
+
+    /// Segment numbers present in the archive dir, ascending.
+    fn segments(&self) -> Vec<u32> {
+        let mut out: Vec<u32> = std::fs::read_dir(&self.dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter_map(|e| {
+                        let name = e.file_name().to_string_lossy().into_owned();
+                        name.strip_prefix(SEGMENT_PREFIX)?.strip_suffix(SEGMENT_EXT)?.parse().ok()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        out.sort_unstable();
+        out
+    }
+
+    fn segment_path(&self, n: u32) -> PathBuf {
+        self.dir.join(format!("{}{:06}{}", SEGMENT_PREFIX, n, SEGMENT_EXT))
+    }
+
+    /// Append `points` from `kb_name`; returns the number archived. Nothing is deleted from L4 unless this
+    /// succeeds.
+    pub fn append(&self, kb_name: &str, points: &[VectorPoint], reason: &str, now: i64) -> Result<usize, String> {
+        if points.is_empty() {
+            return Ok(0);
+        }
+        let mut lines = String::new();
+        for p in points {
+            let record = ArchiveRecord {
+                kb_name: kb_name.to_string(),
+                id: p.id.clone(),
+                vector: p.vector.clone(),
+                vectors: p.vectors.iter().map(|(n, v)| (n.clone(), v.data.clone())).collect(),
+                payload: p.payload.clone(),
+                reason: reason.to_string(),
+                archived_at: now,
+            };
+            lines.push_str(&serde_json::to_string(&record).map_err(|e| e.to_string())?);
+            lines.push('\n');
+        }
+        let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+        std::fs::create_dir_all(&self.dir).map_err(|e| format!("create {}: {}", self.dir.display(), e))?;
+        let mut n = self.segments().last().copied().unwrap_or(1);
+        let size = std::fs::metadata(self.segment_path(n)).map_or(0, |m| m.len());
+        if size > 0 && size + lines.len() as u64 > self.segment_bytes {
+            n += 1;
+        }
+        let path = self.segment_path(n);
+        let mut f = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("open {}: {}", path.display(), e))?;
+        f.write_all(lines.as_bytes())
+            .map_err(|e| format!("write {}: {}", path.display(), e))?;
+        Ok(points.len())
+    }
+
+    /// Newest archived copy of each point matching `req` (kb, ids, case-insensitive content substring), newest
+    /// first, plus the number of segments read.
+    pub fn recall(&self, req: &RecallArchiveRequest) -> (Vec<ArchiveRecord>, u32) {
+        let limit = if req.limit == 0 { DEFAULT_RECALL_LIMIT } else { req.limit as usize };
+        let query = req.query.trim().to_lowercase();
+        let mut seen = HashSet::new();
+        let mut found = Vec::new();
+        let mut scanned = 0;
+        for n in self.segments().into_iter().rev() {
+            scanned += 1;
+            let Ok(raw) = std::fs::read_to_string(self.segment_path(n)) else {
+                continue;
+            };
+            for line in raw.lines().rev() {
+                let Ok(record) = serde_json::from_str::<ArchiveRecord>(line) else {
+                    continue;
+                };
+                if !seen.insert((record.kb_name.clone(), record.id.clone())) || !record.matches(req, &query) {
+                    continue;
+                }
+                found.push(record);
+                if found.len() >= limit {
+                    return (found, scanned);
+                }
+            }
+        }
+        (found, scanned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(id: &str, content: &str) -> VectorPoint {
+        VectorPoint {
+            id: id.to_string(),
+            vector: vec![1.0; 8],
+            payload: HashMap::from([("content".to_string(), content.to_string())]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn appends_roll_segments_and_recall_returns_newest_copies() {
+        let dir = std::env::temp_dir().join(format!("pagi_l7_{}", uuid::Uuid::new_v4()));
+        let archive = Archive::new(dir.clone(), 400);
+        archive.append("kb_core", &[point("a", "Alpha fact")], "age", 10).unwrap();
+        archive.append("kb_core", &[point("b", "beta fact")], "count", 20).unwrap();
+        archive.append("kb_core", &[point("a", "alpha fact v2")], "decay", 30).unwrap();
+        assert!(archive.segments().len() > 1, "small segments roll over");
+        let req = |query: &str| RecallArchiveRequest {
+            kb_name: "kb_core".into(),
+            query: query.into(),
+            ..Default::default()
+        };
+        let (found, scanned) = archive.recall(&req("ALPHA"));
+        assert_eq!(found.len(), 1, "only the newest copy of a point is returned");
+        assert_eq!((found[0].reason.as_str(), found[0].archived_at), ("decay", 30));
+        assert_eq!(scanned as usize, archive.segments().len());
+        assert_eq!(archive.recall(&req("")).0.len(), 2);
+        assert!(archive.recall(&RecallArchiveRequest { kb_name: "kb_other".into(), ..Default::default() }).0.is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
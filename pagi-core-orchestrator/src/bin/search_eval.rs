@@ -0,0 +1,41 @@
+//! Offline search-quality evaluation: score a labeled dataset under several search configurations.
+//!
+//! Usage:
+//!   cargo run --release --bin search_eval -- <dataset.json> [k]
+//!
+//! Datasets with `documents` are seeded into a scratch in-memory L4; otherwise the queries run against the
+//! L4 configured by the usual env (PAGI_VECTOR_BACKEND, PAGI_QDRANT_URI). Configurations come from the
+//! dataset's `configs`, else a vector-only / hybrid grid. See src/search_eval.rs for the format.
+
+use std::path::PathBuf;
+
+use pagi_core_orchestrator::search_eval::{self, Dataset};
+use pagi_core_orchestrator::MemoryManager;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut args = std::env::args().skip(1);
+    let Some(path) = args.next().map(PathBuf::from) else {
+        return Err("usage: search_eval <dataset.json> [k]".into());
+    };
+    let k: u32 = match args.next() {
+        Some(k) => k.parse().map_err(|_| format!("k must be a positive integer, got {:?}", k))?,
+        None => 0,
+    };
+    let dataset = Dataset::load(&path)?;
+    let memory = MemoryManager::new_async().await?;
+    let report = search_eval::evaluate_dataset(&memory, &dataset, &[], k)
+        .await
+        .map_err(|e| e.message().to_string())?;
+
+    println!("{} queries against {} ({}), k={}", dataset.queries.len(), dataset.kb_name, report.source, report.k);
+    println!("{:<24} {:>10} {:>8} {:>8} {:>9}", "config", "recall@k", "mrr", "missed", "degraded");
+    for r in &report.results {
+        println!(
+            "{:<24} {:>10.4} {:>8.4} {:>8} {:>9}",
+            r.name, r.recall_at_k, r.mrr, r.missed, r.degraded
+        );
+    }
+    println!("best: {}", report.best);
+    Ok(())
+}
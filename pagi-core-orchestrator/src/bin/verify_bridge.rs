@@ -0,0 +1,68 @@
+//! Bridge contract check: exercise a bridge checkout against what this orchestrator build expects.
+//!
+//! Usage:
+//!   cargo run --release --bin verify_bridge -- --emit                      # print the contract as JSON
+//!   cargo run --release --bin verify_bridge -- [<bridge_dir>] [--contract <contract.json>]
+//!
+//! The bridge dir defaults to PAGI_BRIDGE_DIR (else ../pagi-intelligence-bridge); --contract checks against a
+//! previously emitted contract instead of this build's. Runner/worker probes need `python` on PATH and are
+//! bounded by PAGI_VERIFY_BRIDGE_TIMEOUT_SECS (default 30). Exits non-zero when any check fails.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use pagi_core_orchestrator::bridge_contract::{self, Contract};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut bridge_dir = None;
+    let mut contract_path = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--emit" => {
+                println!("{}", serde_json::to_string_pretty(&Contract::current())?);
+                return Ok(());
+            }
+            "--contract" => {
+                contract_path = Some(PathBuf::from(args.next().ok_or("--contract needs a file")?));
+            }
+            _ if arg.starts_with("--") => {
+                return Err("usage: verify_bridge --emit | verify_bridge [<bridge_dir>] [--contract <file>]".into())
+            }
+            _ => bridge_dir = Some(PathBuf::from(arg)),
+        }
+    }
+    let contract = match contract_path {
+        Some(path) => serde_json::from_str(&std::fs::read_to_string(&path)?)
+            .map_err(|e| format!("{}: {}", path.display(), e))?,
+        None => Contract::current(),
+    };
+    let bridge_dir = bridge_dir
+        .or_else(|| std::env::var("PAGI_BRIDGE_DIR").ok().map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("../pagi-intelligence-bridge"));
+    let timeout = Duration::from_secs(
+        std::env::var("PAGI_VERIFY_BRIDGE_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(30u64)
+            .max(1),
+    );
+
+    println!(
+        "bridge {} against contract v{} ({} RPCs, runner protocol {})",
+        bridge_dir.display(),
+        contract.contract_version,
+        contract.rpcs.len(),
+        contract.runner.protocol
+    );
+    let checks = bridge_contract::verify(&contract, &bridge_dir, timeout).await;
+    for check in &checks {
+        println!("{:<12} {:<4} {}", check.name, if check.ok { "ok" } else { "FAIL" }, check.detail);
+    }
+    let failed = checks.iter().filter(|c| !c.ok).count();
+    if failed > 0 {
+        return Err(format!("{} of {} contract checks failed", failed, checks.len()).into());
+    }
+    Ok(())
+}
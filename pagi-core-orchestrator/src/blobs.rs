@@ -0,0 +1,296 @@
+// Large memory values, compressed and deduplicated. Strings of at least PAGI_BLOB_MIN_BYTES (default 16384; 0
+// disables) are kept once per content: L2 versions with the same large value share one allocation in process,
+// and the persistence layer writes each distinct value once, zstd-compressed (PAGI_BLOB_ZSTD_LEVEL, default 3)
+// and keyed by its sha256, leaving {"$blob": "<sha256>"} in its place. The L2 snapshot carries its blobs beside
+// the entries; the durable store (store.rs) keeps them in the "blobs" table, shared by every record that holds
+// the same observation, and drops the ones no record references when it opens. L1 sensory frames are never
+// persisted and stay as written; L3 has no backend yet.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use dashmap::DashMap;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+/// Key of the object that stands in for an externalized string.
+pub const REF_KEY: &str = "$blob";
+/// Interned hashes between sweeps of dropped values.
+const PRUNE_FLOOR: usize = 1024;
+
+/// Blobs by sha256: base64 of the zstd-compressed content.
+pub type Blobs = BTreeMap<String, String>;
+
+/// Size threshold and compression level.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlobPolicy {
+    /// Smallest string externalized; 0 disables.
+    pub min_bytes: usize,
+    pub level: i32,
+}
+
+impl BlobPolicy {
+    /// PAGI_BLOB_MIN_BYTES (default 16384; 0 disables) and PAGI_BLOB_ZSTD_LEVEL (default 3).
+    pub fn from_env() -> Self {
+        let env = |key: &str| std::env::var(key).ok().and_then(|s| s.trim().parse::<i64>().ok());
+        Self {
+            min_bytes: env("PAGI_BLOB_MIN_BYTES").map_or(16384, |n| n.max(0) as usize),
+            level: env("PAGI_BLOB_ZSTD_LEVEL").map_or(3, |n| n.clamp(1, 22) as i32),
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self { min_bytes: 0, level: 3 }
+    }
+
+    fn applies(&self, value: &str) -> bool {
+        self.min_bytes > 0 && value.len() >= self.min_bytes
+    }
+}
+
+pub fn hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+/// `content` compressed at `level`, as base64.
+pub fn pack(content: &str, level: i32) -> Result<String, String> {
+    let compressed = zstd::encode_all(content.as_bytes(), level).map_err(|e| format!("zstd: {}", e))?;
+    Ok(STANDARD.encode(compressed))
+}
+
+/// Inverse of `pack`.
+pub fn unpack(packed: &str) -> Result<String, String> {
+    let compressed = STANDARD.decode(packed).map_err(|e| format!("blob encoding: {}", e))?;
+    let content = zstd::decode_all(compressed.as_slice()).map_err(|e| format!("zstd: {}", e))?;
+    String::from_utf8(content).map_err(|e| format!("blob content: {}", e))
+}
+
+/// Replace every string in `value` the policy applies to by a reference, adding the packed content of those not
+/// `known` to `blobs` (each distinct content packed once). A string that fails to pack stays inline; object keys
+/// are left alone.
+pub fn externalize(value: &mut Value, policy: &BlobPolicy, known: &HashSet<String>, blobs: &mut Blobs) {
+    match value {
+        Value::String(s) if policy.applies(s) => {
+            let id = hash(s);
+            if !known.contains(&id) && !blobs.contains_key(&id) {
+                match pack(s, policy.level) {
+                    Ok(packed) => {
+                        blobs.insert(id.clone(), packed);
+                    }
+                    Err(e) => {
+                        eprintln!("[Blobs] {} kept inline: {}", id, e);
+                        return;
+                    }
+                }
+            }
+            *value = reference(id);
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| externalize(item, policy, known, blobs)),
+        Value::Object(fields) => fields
+            .values_mut()
+            .for_each(|field| externalize(field, policy, known, blobs)),
+        _ => {}
+    }
+}
+
+fn reference(id: String) -> Value {
+    Value::Object(Map::from_iter([(REF_KEY.to_string(), Value::String(id))]))
+}
+
+/// The blob id when `value` is a reference.
+fn referenced(value: &Value) -> Option<&str> {
+    match value {
+        Value::Object(fields) if fields.len() == 1 => fields.get(REF_KEY).and_then(Value::as_str),
+        _ => None,
+    }
+}
+
+/// Put the content of every reference in `value` back, unpacking from `blobs`; errors on one it lacks.
+pub fn resolve(value: &mut Value, blobs: &Blobs) -> Result<(), String> {
+    substitute(value, blobs, true)
+}
+
+/// `resolve` for the references `blobs` has only (blobs that could not be written are put back inline).
+pub fn inline(value: &mut Value, blobs: &Blobs) -> Result<(), String> {
+    substitute(value, blobs, false)
+}
+
+fn substitute(value: &mut Value, blobs: &Blobs, strict: bool) -> Result<(), String> {
+    if let Some(id) = referenced(value) {
+        let Some(packed) = blobs.get(id) else {
+            return if strict {
+                Err(format!("missing blob {}", id))
+            } else {
+                Ok(())
+            };
+        };
+        *value = Value::String(unpack(packed).map_err(|e| format!("blob {}: {}", id, e))?);
+        return Ok(());
+    }
+    match value {
+        Value::Array(items) => items.iter_mut().try_for_each(|item| substitute(item, blobs, strict)),
+        Value::Object(fields) => fields
+            .values_mut()
+            .try_for_each(|field| substitute(field, blobs, strict)),
+        _ => Ok(()),
+    }
+}
+
+/// Add the blob ids `value` references to `ids`.
+pub fn references(value: &Value, ids: &mut HashSet<String>) {
+    if let Some(id) = referenced(value) {
+        ids.insert(id.to_string());
+        return;
+    }
+    match value {
+        Value::Array(items) => items.iter().for_each(|item| references(item, ids)),
+        Value::Object(fields) => fields.values().for_each(|field| references(field, ids)),
+        _ => {}
+    }
+}
+
+/// In-process dedup of large values: interning a value returns the live copy of the same content, if any.
+pub struct Interner {
+    policy: BlobPolicy,
+    live: DashMap<String, Weak<str>>,
+    /// Size at which dropped values are next swept out of `live`.
+    prune_at: AtomicUsize,
+}
+
+impl Interner {
+    pub fn new(policy: BlobPolicy) -> Self {
+        Self {
+            policy,
+            live: DashMap::new(),
+            prune_at: AtomicUsize::new(PRUNE_FLOOR),
+        }
+    }
+
+    pub fn intern(&self, value: Arc<str>) -> Arc<str> {
+        if !self.policy.applies(&value) {
+            return value;
+        }
+        let shared = match self.live.entry(hash(&value)) {
+            dashmap::mapref::entry::Entry::Occupied(mut e) => match e.get().upgrade() {
+                Some(existing) => existing,
+                None => {
+                    e.insert(Arc::downgrade(&value));
+                    value
+                }
+            },
+            dashmap::mapref::entry::Entry::Vacant(e) => {
+                e.insert(Arc::downgrade(&value));
+                value
+            }
+        };
+        if self.live.len() >= self.prune_at.load(Ordering::Relaxed) {
+            self.live.retain(|_, v| v.strong_count() > 0);
+            self.prune_at
+                .store((self.live.len() * 2).max(PRUNE_FLOOR), Ordering::Relaxed);
+        }
+        shared
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: BlobPolicy = BlobPolicy {
+        min_bytes: 64,
+        level: 3,
+    };
+
+    fn observation() -> String {
+        "traceback line\n".repeat(100)
+    }
+
+    /// A record holding observation() twice plus short fields, externalized under POLICY.
+    fn externalized() -> (serde_json::Value, Blobs) {
+        let mut record = serde_json::json!({
+            "detail": observation(),
+            "steps": [observation(), "short"],
+            "skill": "peek_file",
+        });
+        let mut blobs = Blobs::new();
+        externalize(&mut record, &POLICY, &HashSet::new(), &mut blobs);
+        (record, blobs)
+    }
+
+    #[test]
+    fn large_strings_are_stored_once_compressed() {
+        let (record, blobs) = externalized();
+        assert_eq!(blobs.len(), 1, "identical content is stored once");
+        let id = hash(&observation());
+        assert_eq!(record["detail"], serde_json::json!({ "$blob": id }));
+        assert_eq!(record["steps"][1], "short");
+        assert!(
+            blobs[&id].len() < observation().len() / 4,
+            "compressed: {} bytes",
+            blobs[&id].len()
+        );
+    }
+
+    #[test]
+    fn known_blobs_are_referenced_not_repacked() {
+        let (record, _) = externalized();
+        let mut ids = HashSet::new();
+        references(&record, &mut ids);
+        let id = hash(&observation());
+        assert_eq!(ids, HashSet::from([id.clone()]));
+        let mut again = serde_json::json!({ "detail": observation() });
+        let mut fresh = Blobs::new();
+        externalize(&mut again, &POLICY, &ids, &mut fresh);
+        assert!(fresh.is_empty() && referenced(&again["detail"]) == Some(id.as_str()));
+    }
+
+    #[test]
+    fn references_resolve_back_to_the_content() {
+        let (mut record, blobs) = externalized();
+        resolve(&mut record, &blobs).unwrap();
+        assert_eq!(record["steps"][0], observation().as_str());
+    }
+
+    #[test]
+    fn dangling_references_fail_to_resolve_but_inline_leaves_them() {
+        let mut dangling = reference("0".repeat(64));
+        assert!(resolve(&mut dangling, &Blobs::new()).is_err());
+        assert_eq!(inline(&mut dangling, &Blobs::new()), Ok(()));
+        assert_eq!(referenced(&dangling), Some("0".repeat(64).as_str()), "left as a reference");
+    }
+
+    #[test]
+    fn a_disabled_policy_keeps_strings_inline() {
+        let mut untouched = serde_json::json!({ "detail": observation() });
+        externalize(
+            &mut untouched,
+            &BlobPolicy::disabled(),
+            &HashSet::new(),
+            &mut Blobs::new(),
+        );
+        assert_eq!(untouched["detail"], observation().as_str());
+    }
+
+    #[test]
+    fn the_interner_shares_one_allocation_per_large_content() {
+        let interner = Interner::new(POLICY);
+        let first = interner.intern(Arc::from(observation().as_str()));
+        let second = interner.intern(Arc::from(observation().as_str()));
+        assert!(Arc::ptr_eq(&first, &second));
+        let small = Arc::<str>::from("short");
+        assert!(Arc::ptr_eq(&interner.intern(small.clone()), &small), "small values are not interned");
+    }
+
+    #[test]
+    fn the_interner_does_not_keep_dropped_values_alive() {
+        let interner = Interner::new(POLICY);
+        drop(interner.intern(Arc::from(observation().as_str())));
+        let fresh = interner.intern(Arc::from(observation().as_str()));
+        assert_eq!(Arc::strong_count(&fresh), 1);
+    }
+}
@@ -0,0 +1,474 @@
+// Bridge contract: a machine-readable description of what the orchestrator expects from a bridge checkout —
+// gRPC methods (read from pagi.proto at build time), the skill runner's argv/env/envelope layout, the warm
+// worker's framing, and how the L5 allow-list is hashed — plus checks that exercise a bridge against it.
+// `verify_bridge --emit` prints the contract; `verify_bridge <bridge_dir>` runs the checks, so stale stubs or
+// a runner that drifted from runner_protocol fail before the first ExecuteAction does.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+
+use crate::allow_list::{self, SkillSource};
+use crate::runner_protocol;
+use crate::skill_manifest::Filesystem;
+
+/// Bumped when the contract's shape (not the protocols it describes) changes.
+pub const CONTRACT_VERSION: u32 = 1;
+/// Skill name no bridge defines; runner checks expect a clean "unknown skill" failure for it.
+const PROBE_SKILL: &str = "__pagi_contract_probe__";
+const PROTO: &str = include_str!("../../pagi-proto/pagi.proto");
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Contract {
+    pub contract_version: u32,
+    /// Fully-qualified gRPC service ("pagi.Pagi").
+    pub service: String,
+    pub rpcs: Vec<String>,
+    /// Generated Python stubs, relative to the bridge dir.
+    pub stubs: String,
+    pub runner: RunnerContract,
+    pub worker: WorkerContract,
+    pub allow_list: AllowListContract,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunnerContract {
+    pub program: String,
+    /// Relative to the bridge dir, which is also the working directory.
+    pub script: String,
+    /// Arguments after the script; `[..]` marks optional ones.
+    pub argv: Vec<String>,
+    pub protocol: u32,
+    /// Env var carrying the protocol version; when set, the invocation envelope is written to stdin.
+    pub protocol_env: String,
+    pub invocation_fields: Vec<String>,
+    /// Keys of the result envelope, the last stdout line.
+    pub result_fields: Vec<String>,
+    pub result_statuses: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkerContract {
+    pub script: String,
+    pub framing: String,
+    pub ops: Vec<String>,
+    pub request_fields: Vec<String>,
+    pub response_fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AllowListContract {
+    pub hash: String,
+    pub default_source: String,
+    pub sources_env: String,
+    pub namespace_separator: String,
+    /// What each skill file must define for the runner to dispatch it.
+    pub skill_exports: Vec<String>,
+}
+
+fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+/// (service, rpc names) declared in a .proto source.
+fn proto_service(proto: &str) -> (String, Vec<String>) {
+    let mut package = String::new();
+    let mut service = String::new();
+    let mut rpcs = Vec::new();
+    for line in proto.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix("package ") {
+            package = rest.trim_end_matches(';').trim().to_string();
+        } else if let Some(rest) = line.strip_prefix("service ") {
+            service = rest.trim_end_matches('{').trim().to_string();
+        } else if let Some(rest) = line.strip_prefix("rpc ") {
+            if let Some((name, _)) = rest.split_once('(') {
+                rpcs.push(name.trim().to_string());
+            }
+        }
+    }
+    let service = if package.is_empty() { service } else { format!("{}.{}", package, service) };
+    (service, rpcs)
+}
+
+impl Contract {
+    /// The contract this orchestrator build speaks.
+    pub fn current() -> Self {
+        let (service, rpcs) = proto_service(PROTO);
+        // Envelope keys come from serializing the real Invocation, so they cannot drift from spawn_runner.
+        let params = HashMap::new();
+        let filesystem = Filesystem::default();
+        let invocation = runner_protocol::Invocation {
+            protocol: runner_protocol::VERSION,
+            skill: PROBE_SKILL,
+            params: &params,
+            invocation_id: "",
+            temp_dir: Path::new(""),
+            deadline_unix_ms: 0,
+            skill_path: Some(Path::new("")),
+            filesystem: Some(&filesystem),
+        };
+        let invocation_fields = match serde_json::to_value(&invocation) {
+            Ok(serde_json::Value::Object(map)) => map.keys().cloned().collect(),
+            _ => Vec::new(),
+        };
+        Self {
+            contract_version: CONTRACT_VERSION,
+            service,
+            rpcs,
+            stubs: "src/pagi_pb/pagi_pb2_grpc.py".into(),
+            runner: RunnerContract {
+                program: "python".into(),
+                script: "scripts/run_skill.py".into(),
+                argv: strings(&["<skill>", "<params_json>", "[<skill_path>]"]),
+                protocol: runner_protocol::VERSION,
+                protocol_env: "PAGI_RUNNER_PROTOCOL".into(),
+                invocation_fields,
+                // runner_protocol's ResultEnvelope keys (the tests decode exactly these with parse_output).
+                result_fields: strings(&["protocol", "status", "observation", "error", "artifacts", "metrics", "inputs"]),
+                result_statuses: strings(&["ok", "error"]),
+            },
+            worker: WorkerContract {
+                script: "scripts/skill_worker.py".into(),
+                framing: "u32 big-endian length + UTF-8 JSON".into(),
+                ops: strings(&["invoke", "ping"]),
+                request_fields: strings(&["id", "op", "skill", "params", "path"]),
+                response_fields: strings(&["id", "ok", "observation", "error"]),
+            },
+            allow_list: AllowListContract {
+                hash: "sha256 hex of the sorted skill names, each followed by \\n".into(),
+                default_source: "src/skills".into(),
+                sources_env: "PAGI_SKILL_SOURCES".into(),
+                namespace_separator: ".".into(),
+                skill_exports: strings(&["def run(", "Params("]),
+            },
+        }
+    }
+}
+
+/// Outcome of one contract check.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, result: Result<String, String>) -> Self {
+        match result {
+            Ok(detail) => Self { name, ok: true, detail },
+            Err(detail) => Self { name, ok: false, detail },
+        }
+    }
+}
+
+/// Run every check against the bridge at `bridge_dir`; each subprocess is bounded by `timeout`.
+pub async fn verify(contract: &Contract, bridge_dir: &Path, timeout: Duration) -> Vec<Check> {
+    vec![
+        Check::new("stubs", check_stubs(contract, bridge_dir)),
+        Check::new("allow_list", check_skills(contract, bridge_dir)),
+        Check::new("runner_v2", check_runner_v2(contract, bridge_dir, timeout).await),
+        Check::new("runner_v1", check_runner_v1(contract, bridge_dir, timeout).await),
+        Check::new("worker", check_worker(contract, bridge_dir, timeout).await),
+    ]
+}
+
+/// Every contract RPC has a stub method, and the stubs expose none the proto no longer declares.
+fn check_stubs(contract: &Contract, bridge_dir: &Path) -> Result<String, String> {
+    let path = bridge_dir.join(&contract.stubs);
+    let source = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let prefix = format!("/{}/", contract.service);
+    let mut stubbed: Vec<&str> = source
+        .split(prefix.as_str())
+        .skip(1)
+        .filter_map(|rest| rest.split(|c: char| !c.is_ascii_alphanumeric() && c != '_').next())
+        .filter(|name| !name.is_empty())
+        .collect();
+    stubbed.sort_unstable();
+    stubbed.dedup();
+    let missing: Vec<&str> = contract
+        .rpcs
+        .iter()
+        .map(String::as_str)
+        .filter(|rpc| stubbed.binary_search(rpc).is_err())
+        .collect();
+    let stale: Vec<&str> = stubbed
+        .iter()
+        .copied()
+        .filter(|name| !contract.rpcs.iter().any(|rpc| rpc == name))
+        .collect();
+    if missing.is_empty() && stale.is_empty() {
+        return Ok(format!("{} RPCs of {} stubbed", contract.rpcs.len(), contract.service));
+    }
+    Err(format!(
+        "stubs out of date (regenerate with `make gen-proto`): missing {:?}, not in proto {:?}",
+        missing, stale
+    ))
+}
+
+/// The allow-list resolves to at least one skill and every skill file defines what the runner dispatches.
+fn check_skills(contract: &Contract, bridge_dir: &Path) -> Result<String, String> {
+    let list = allow_list::load(bridge_dir, &SkillSource::from_env());
+    if list.skills.is_empty() {
+        return Err(format!("no skills found under {} sources", contract.allow_list.sources_env));
+    }
+    let default_root = bridge_dir.join(&contract.allow_list.default_source);
+    let mut broken = Vec::new();
+    for skill in &list.skills {
+        let file = match list.paths.get(skill) {
+            Some(rel) => bridge_dir.join(rel),
+            None => default_root.join(format!("{}.py", skill)),
+        };
+        let source = std::fs::read_to_string(&file).unwrap_or_default();
+        if !contract.allow_list.skill_exports.iter().all(|export| source.contains(export.as_str())) {
+            broken.push(skill.as_str());
+        }
+    }
+    if !broken.is_empty() {
+        return Err(format!(
+            "skills missing {:?}: {}",
+            contract.allow_list.skill_exports,
+            broken.join(", ")
+        ));
+    }
+    Ok(format!(
+        "{} skills, hash {}, revision {}",
+        list.skills.len(),
+        &list.hash()[..12],
+        list.revision
+    ))
+}
+
+fn runner_command(contract: &Contract, bridge_dir: &Path) -> Result<Command, String> {
+    let script = bridge_dir.join(&contract.runner.script);
+    if !script.exists() {
+        return Err(format!("{} not found", script.display()));
+    }
+    let mut command = Command::new(&contract.runner.program);
+    command
+        .arg(script)
+        .arg(PROBE_SKILL)
+        .arg("{}")
+        .current_dir(bridge_dir)
+        .env_remove(&contract.runner.protocol_env)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    Ok(command)
+}
+
+/// The runner answers an envelope for an unknown skill with exactly one well-formed error result.
+async fn check_runner_v2(contract: &Contract, bridge_dir: &Path, timeout: Duration) -> Result<String, String> {
+    let mut command = runner_command(contract, bridge_dir)?;
+    command
+        .env(&contract.runner.protocol_env, contract.runner.protocol.to_string())
+        .stdin(Stdio::piped());
+    let mut child = command.spawn().map_err(|e| format!("spawn {}: {}", contract.runner.program, e))?;
+    let envelope: serde_json::Map<String, serde_json::Value> = contract
+        .runner
+        .invocation_fields
+        .iter()
+        .map(|field| {
+            let value = match field.as_str() {
+                "protocol" => serde_json::json!(contract.runner.protocol),
+                "skill" => serde_json::json!(PROBE_SKILL),
+                "params" => serde_json::json!({}),
+                "deadline_unix_ms" => serde_json::json!(chrono::Utc::now().timestamp_millis() + timeout.as_millis() as i64),
+                "temp_dir" => serde_json::json!(std::env::temp_dir()),
+                "skill_path" | "filesystem" => serde_json::Value::Null,
+                _ => serde_json::json!(""),
+            };
+            (field.clone(), value)
+        })
+        .collect();
+    if let Some(mut stdin) = child.stdin.take() {
+        let body = serde_json::to_vec(&envelope).map_err(|e| e.to_string())?;
+        stdin.write_all(&body).await.map_err(|e| format!("write envelope: {}", e))?;
+    }
+    let output = tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| format!("no result within {:?}", timeout))?
+        .map_err(|e| e.to_string())?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let last = stdout.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("");
+    let result: serde_json::Value =
+        serde_json::from_str(last.trim()).map_err(|e| format!("last stdout line is not a result envelope ({}): {:?}", e, last))?;
+    let missing: Vec<&str> = contract
+        .runner
+        .result_fields
+        .iter()
+        .map(String::as_str)
+        .filter(|f| result.get(f).is_none())
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("result envelope missing {:?}", missing));
+    }
+    if result["protocol"] != serde_json::json!(contract.runner.protocol) {
+        return Err(format!("result protocol {} (expected {})", result["protocol"], contract.runner.protocol));
+    }
+    let status = result["status"].as_str().unwrap_or_default();
+    if !contract.runner.result_statuses.iter().any(|s| s == status) {
+        return Err(format!("unknown result status {:?}", status));
+    }
+    if status != "error" || output.status.success() || result["error"].as_str().unwrap_or_default().is_empty() {
+        return Err(format!("unknown skill was not reported as an error (status {:?}, {})", status, output.status));
+    }
+    Ok(format!("protocol {} envelope with {} fields", contract.runner.protocol, contract.runner.result_fields.len()))
+}
+
+/// Without the protocol env the runner takes argv only and fails an unknown skill via exit status + stderr.
+async fn check_runner_v1(contract: &Contract, bridge_dir: &Path, timeout: Duration) -> Result<String, String> {
+    let mut command = runner_command(contract, bridge_dir)?;
+    command.stdin(Stdio::null());
+    let output = tokio::time::timeout(timeout, command.output())
+        .await
+        .map_err(|_| format!("no exit within {:?}", timeout))?
+        .map_err(|e| format!("spawn {}: {}", contract.runner.program, e))?;
+    if output.status.success() {
+        return Err("unknown skill exited successfully".into());
+    }
+    if String::from_utf8_lossy(&output.stderr).trim().is_empty() {
+        return Err(format!("{} with empty stderr", output.status));
+    }
+    Ok(format!("argv layout {}", contract.runner.argv.join(" ")))
+}
+
+async fn write_frame(stdin: &mut tokio::process::ChildStdin, frame: &serde_json::Value) -> Result<(), String> {
+    let body = serde_json::to_vec(frame).map_err(|e| e.to_string())?;
+    stdin.write_all(&(body.len() as u32).to_be_bytes()).await.map_err(|e| e.to_string())?;
+    stdin.write_all(&body).await.map_err(|e| e.to_string())?;
+    stdin.flush().await.map_err(|e| e.to_string())
+}
+
+async fn read_frame(stdout: &mut tokio::process::ChildStdout) -> Result<serde_json::Value, String> {
+    let mut header = [0u8; 4];
+    stdout.read_exact(&mut header).await.map_err(|e| format!("read header: {}", e))?;
+    let mut body = vec![0u8; u32::from_be_bytes(header) as usize];
+    stdout.read_exact(&mut body).await.map_err(|e| format!("read body: {}", e))?;
+    serde_json::from_slice(&body).map_err(|e| format!("decode: {}", e))
+}
+
+/// The warm worker (optional) answers a ping and reports an unknown skill without dying.
+async fn check_worker(contract: &Contract, bridge_dir: &Path, timeout: Duration) -> Result<String, String> {
+    let script = bridge_dir.join(&contract.worker.script);
+    if !script.exists() {
+        return Ok("no worker script; PAGI_SKILL_WORKER_POOL cannot be used with this bridge".into());
+    }
+    let mut child = Command::new(&contract.runner.program)
+        .arg(script)
+        .current_dir(bridge_dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("spawn {}: {}", contract.runner.program, e))?;
+    let (Some(mut stdin), Some(mut stdout)) = (child.stdin.take(), child.stdout.take()) else {
+        return Err("worker pipes unavailable".into());
+    };
+    let exchange = async {
+        write_frame(&mut stdin, &serde_json::json!({"id": 1, "op": "ping"})).await?;
+        let pong = read_frame(&mut stdout).await?;
+        write_frame(
+            &mut stdin,
+            &serde_json::json!({"id": 2, "op": "invoke", "skill": PROBE_SKILL, "params": {}}),
+        )
+        .await?;
+        let failed = read_frame(&mut stdout).await?;
+        Ok::<_, String>((pong, failed))
+    };
+    let (pong, failed) = tokio::time::timeout(timeout, exchange)
+        .await
+        .map_err(|_| format!("no reply within {:?}", timeout))??;
+    for resp in [&pong, &failed] {
+        let missing: Vec<&str> = contract
+            .worker
+            .response_fields
+            .iter()
+            .map(String::as_str)
+            .filter(|f| resp.get(f).is_none())
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!("response missing {:?}: {}", missing, resp));
+        }
+    }
+    if pong["id"] != 1 || pong["ok"] != true {
+        return Err(format!("bad ping reply: {}", pong));
+    }
+    if failed["id"] != 2 || failed["ok"] != false {
+        return Err(format!("unknown skill not reported as failed: {}", failed));
+    }
+    Ok(format!("{} framing, ops {:?}", contract.worker.framing, contract.worker.ops))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contract_lists_the_service_and_its_rpcs() {
+        let contract = Contract::current();
+        assert_eq!(contract.service, "pagi.Pagi");
+        assert!(contract.rpcs.iter().any(|r| r == "ExecuteAction"));
+        assert!(contract.rpcs.iter().any(|r| r == "AbortRequest"));
+    }
+
+    #[test]
+    fn contract_lists_every_runner_invocation_field() {
+        let fields = Contract::current().runner.invocation_fields;
+        for field in ["skill", "params", "invocation_id", "temp_dir", "deadline_unix_ms", "skill_path", "filesystem"] {
+            assert!(fields.iter().any(|f| f == field), "{}", field);
+        }
+    }
+
+    #[test]
+    fn emitted_contracts_round_trip() {
+        let contract = Contract::current();
+        let raw = serde_json::to_string(&contract).unwrap();
+        assert_eq!(serde_json::from_str::<Contract>(&raw).unwrap(), contract);
+    }
+
+    #[test]
+    fn a_result_with_exactly_the_contract_fields_decodes_as_v2() {
+        let result: serde_json::Map<String, serde_json::Value> = Contract::current()
+            .runner
+            .result_fields
+            .iter()
+            .map(|f| {
+                let value = match f.as_str() {
+                    "protocol" => serde_json::json!(runner_protocol::VERSION),
+                    "status" => serde_json::json!("ok"),
+                    "artifacts" => serde_json::json!(["a.py"]),
+                    "metrics" => serde_json::json!({}),
+                    "inputs" => serde_json::json!({ "a.txt": "ab12" }),
+                    _ => serde_json::json!("x"),
+                };
+                (f.clone(), value)
+            })
+            .collect();
+        let out = runner_protocol::parse_output(&serde_json::to_string(&result).unwrap(), "", true, Some(0));
+        assert_eq!(out.artifacts, ["a.py"]);
+        assert_eq!(out.inputs["a.txt"], "ab12");
+    }
+
+    #[test]
+    fn stub_check_reports_missing_and_stale_methods() {
+        let bridge = std::env::temp_dir().join(format!("pagi_contract_{}", uuid::Uuid::new_v4()));
+        let mut contract = Contract::current();
+        contract.rpcs = vec!["GetHealth".into(), "ExecuteAction".into()];
+        let stubs = bridge.join(&contract.stubs);
+        std::fs::create_dir_all(stubs.parent().unwrap()).unwrap();
+        std::fs::write(&stubs, "'/pagi.Pagi/GetHealth',\n'/pagi.Pagi/ExecuteAction',\n").unwrap();
+        assert!(check_stubs(&contract, &bridge).is_ok());
+
+        std::fs::write(&stubs, "'/pagi.Pagi/GetHealth',\n'/pagi.Pagi/Removed',\n").unwrap();
+        let err = check_stubs(&contract, &bridge).unwrap_err();
+        assert!(err.contains("\"ExecuteAction\"") && err.contains("\"Removed\""), "{}", err);
+        let _ = std::fs::remove_dir_all(bridge);
+    }
+}
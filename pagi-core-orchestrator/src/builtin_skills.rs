@@ -0,0 +1,388 @@
+// Native L5 skills executed in-process (no Python spawn), registered in the allow-list as `builtin:<name>`.
+// Sandbox: file skills are confined to PAGI_BUILTIN_ROOTS (default: bridge dir) after symlink resolution;
+// http_get only reaches PAGI_BUILTIN_HTTP_DOMAINS (and their subdomains) and never follows redirects.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::allow_list::AllowList;
+
+pub const PREFIX: &str = "builtin:";
+pub const NAMES: [&str; 4] = ["peek_file", "list_dir", "regex_search", "http_get"];
+
+/// Files larger than this are skipped by regex_search.
+const MAX_SEARCH_FILE_BYTES: u64 = 1 << 20;
+/// Files visited per regex_search call.
+const MAX_SEARCH_FILES: usize = 2000;
+
+#[derive(Debug, Clone)]
+pub struct BuiltinConfig {
+    /// PAGI_BUILTIN_SKILLS (default true).
+    pub enabled: bool,
+    /// Canonicalized roots file skills may read; relative `path` params resolve against the first.
+    pub roots: Vec<PathBuf>,
+    /// Hosts http_get may fetch (exact or subdomain match); empty denies all.
+    pub http_domains: Vec<String>,
+    /// Output cap in bytes (PAGI_BUILTIN_MAX_BYTES, default 65536).
+    pub max_bytes: usize,
+}
+
+impl BuiltinConfig {
+    pub fn from_env(bridge_dir: &Path) -> Self {
+        let enabled = std::env::var("PAGI_BUILTIN_SKILLS")
+            .map(|v| !matches!(v.trim().to_lowercase().as_str(), "0" | "false" | "no" | "off"))
+            .unwrap_or(true);
+        let roots: Vec<PathBuf> = match std::env::var_os("PAGI_BUILTIN_ROOTS") {
+            Some(raw) if !raw.is_empty() => std::env::split_paths(&raw).collect(),
+            _ => vec![bridge_dir.to_path_buf()],
+        };
+        let http_domains = std::env::var("PAGI_BUILTIN_HTTP_DOMAINS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|d| d.trim().trim_start_matches('.').to_lowercase())
+            .filter(|d| !d.is_empty())
+            .collect();
+        let max_bytes = std::env::var("PAGI_BUILTIN_MAX_BYTES")
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(65_536usize)
+            .max(1);
+        Self {
+            enabled,
+            roots: roots.iter().filter_map(|r| r.canonicalize().ok()).collect(),
+            http_domains,
+            max_bytes,
+        }
+    }
+
+    /// Add `builtin:<name>` entries to the allow-list (no-op when disabled).
+    pub fn register(&self, list: &mut AllowList) {
+        if !self.enabled {
+            return;
+        }
+        list.skills.extend(NAMES.iter().map(|n| format!("{}{}", PREFIX, n)));
+        list.skills.sort();
+        list.skills.dedup();
+    }
+
+    /// Resolve `raw` to an existing path inside one of the roots.
+    fn confine(&self, raw: &str) -> Result<PathBuf, String> {
+        let base = self.roots.first().ok_or("no readable PAGI_BUILTIN_ROOTS")?;
+        let candidate = Path::new(raw.trim());
+        let candidate = if candidate.is_absolute() {
+            candidate.to_path_buf()
+        } else {
+            base.join(candidate)
+        };
+        let resolved = candidate.canonicalize().map_err(|e| format!("{}: {}", raw, e))?;
+        if self.roots.iter().any(|root| resolved.starts_with(root)) {
+            Ok(resolved)
+        } else {
+            Err(format!("{} is outside the allowed roots", raw))
+        }
+    }
+
+    fn host_allowed(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+        self.http_domains
+            .iter()
+            .any(|d| host == *d || host.ends_with(&format!(".{}", d)))
+    }
+
+    fn truncate(&self, mut s: String) -> String {
+        if s.len() > self.max_bytes {
+            let mut end = self.max_bytes;
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+            s.truncate(end);
+            s.push_str("\n... [truncated]");
+        }
+        s
+    }
+}
+
+fn param<'a>(params: &'a HashMap<String, String>, key: &str) -> Option<&'a str> {
+    params.get(key).map(|v| v.trim()).filter(|v| !v.is_empty())
+}
+
+fn num_param(params: &HashMap<String, String>, key: &str, default: usize) -> Result<usize, String> {
+    match param(params, key) {
+        Some(v) => v.parse().map_err(|_| format!("{} must be a non-negative integer", key)),
+        None => Ok(default),
+    }
+}
+
+/// Run builtin `name` (without the prefix). Ok is the observation; Err is a skill failure.
+pub async fn execute(name: &str, params: &HashMap<String, String>, config: &BuiltinConfig) -> Result<String, String> {
+    if name == "http_get" {
+        return http_get(params, config).await;
+    }
+    let (name, params, config) = (name.to_string(), params.clone(), config.clone());
+    tokio::task::spawn_blocking(move || match name.as_str() {
+        "peek_file" => peek_file(&params, &config),
+        "list_dir" => list_dir(&params, &config),
+        "regex_search" => regex_search(&params, &config),
+        other => Err(format!("unknown builtin skill {}", other)),
+    })
+    .await
+    .map_err(|e| format!("builtin panicked: {}", e))?
+}
+
+/// Byte range [start, end) of a file (params: path, start=0, end=2000), as in the Python peek_file.
+fn peek_file(params: &HashMap<String, String>, config: &BuiltinConfig) -> Result<String, String> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let path = config.confine(param(params, "path").ok_or("path is required")?)?;
+    let start = num_param(params, "start", 0)?;
+    let end = num_param(params, "end", 2000)?;
+    if end < start {
+        return Err("invalid range".to_string());
+    }
+    let mut file = std::fs::File::open(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    file.seek(SeekFrom::Start(start as u64)).map_err(|e| e.to_string())?;
+    let mut buf = Vec::new();
+    file.take((end - start).min(config.max_bytes) as u64)
+        .read_to_end(&mut buf)
+        .map_err(|e| e.to_string())?;
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+/// Non-recursive listing (params: path=".", pattern suffix filter, max_items=20).
+fn list_dir(params: &HashMap<String, String>, config: &BuiltinConfig) -> Result<String, String> {
+    let dir = config.confine(param(params, "path").unwrap_or("."))?;
+    if !dir.is_dir() {
+        return Err(format!("not a directory: {}", dir.display()));
+    }
+    let max_items = num_param(params, "max_items", 20)?;
+    let suffix = param(params, "pattern").map(|p| p.trim_start_matches('*').to_lowercase());
+    let mut entries: Vec<(String, bool)> = std::fs::read_dir(&dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|e| e.ok())
+        .map(|e| (e.file_name().to_string_lossy().into_owned(), e.file_type().is_ok_and(|t| t.is_dir())))
+        .filter(|(name, _)| suffix.as_ref().is_none_or(|s| name.to_lowercase().ends_with(s.as_str())))
+        .collect();
+    entries.sort_by_key(|(name, _)| name.to_lowercase());
+    let total = entries.len();
+    let mut lines: Vec<String> = entries
+        .into_iter()
+        .take(max_items)
+        .map(|(name, is_dir)| format!("{} {}", name, if is_dir { "(dir)" } else { "(file)" }))
+        .collect();
+    if lines.is_empty() {
+        return Ok("[list_dir] Directory empty or no matches".to_string());
+    }
+    if total > max_items {
+        lines.push("... [truncated]".to_string());
+    }
+    Ok(config.truncate(format!("[list_dir] Contents of {}:\n{}", dir.display(), lines.join("\n"))))
+}
+
+/// Regex over a file or directory tree (params: pattern, path=".", max_matches=50); hidden entries,
+/// symlinks and files over 1 MiB are skipped. Output lines are `path:line: text`, paths relative to `path`.
+fn regex_search(params: &HashMap<String, String>, config: &BuiltinConfig) -> Result<String, String> {
+    let re = regex::Regex::new(param(params, "pattern").ok_or("pattern is required")?)
+        .map_err(|e| format!("invalid pattern: {}", e))?;
+    let root = config.confine(param(params, "path").unwrap_or("."))?;
+    let max_matches = num_param(params, "max_matches", 50)?;
+    let mut matches = Vec::new();
+    let mut stack = vec![root.clone()];
+    let mut visited = 0usize;
+    while let Some(path) = stack.pop() {
+        if matches.len() >= max_matches || visited >= MAX_SEARCH_FILES {
+            break;
+        }
+        // The root is canonical; symlinks below it could point outside the roots, so they are never followed.
+        let Ok(meta) = std::fs::symlink_metadata(&path) else {
+            continue;
+        };
+        if meta.is_dir() {
+            let Ok(entries) = std::fs::read_dir(&path) else {
+                continue;
+            };
+            let mut children: Vec<PathBuf> = entries
+                .filter_map(|e| e.ok())
+                .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+                .filter(|e| e.file_type().is_ok_and(|t| !t.is_symlink()))
+                .map(|e| e.path())
+                .collect();
+            children.sort();
+            stack.extend(children.into_iter().rev());
+            continue;
+        }
+        visited += 1;
+        if !meta.is_file() || meta.len() > MAX_SEARCH_FILE_BYTES {
+            continue;
+        }
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        // A single-file search reports the file name.
+        let rel = match path.strip_prefix(&root) {
+            Ok(rel) if !rel.as_os_str().is_empty() => rel,
+            _ => path.file_name().map(Path::new).unwrap_or(&path),
+        };
+        for (i, line) in text.lines().enumerate() {
+            if re.is_match(line) {
+                let line: String = line.trim().chars().take(200).collect();
+                matches.push(format!("{}:{}: {}", rel.display(), i + 1, line));
+                if matches.len() >= max_matches {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(config.truncate(format!("[regex_search] {} match(es)\n{}", matches.len(), matches.join("\n"))))
+}
+
+/// GET an allow-listed URL (params: url); reading stops once the body passes PAGI_BUILTIN_MAX_BYTES.
+async fn http_get(params: &HashMap<String, String>, config: &BuiltinConfig) -> Result<String, String> {
+    let raw = param(params, "url").ok_or("url is required")?;
+    let url = reqwest::Url::parse(raw).map_err(|e| format!("invalid url: {}", e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("unsupported scheme {}", url.scheme()));
+    }
+    let host = url.host_str().unwrap_or_default();
+    if !config.host_allowed(host) {
+        return Err(format!("host {} is not in PAGI_BUILTIN_HTTP_DOMAINS", host));
+    }
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut resp = client.get(url).send().await.map_err(|e| e.to_string())?;
+    let status = resp.status();
+    let mut body = Vec::new();
+    // One byte past the cap is enough for truncate() to mark the output; the rest is never downloaded.
+    while body.len() <= config.max_bytes {
+        match resp.chunk().await.map_err(|e| e.to_string())? {
+            Some(chunk) => body.extend_from_slice(&chunk),
+            None => break,
+        }
+    }
+    body.truncate(config.max_bytes + 1);
+    let body = String::from_utf8_lossy(&body);
+    Ok(config.truncate(format!("[http_get] {}\n{}", status, body)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sandbox() -> (PathBuf, BuiltinConfig) {
+        let root = std::env::temp_dir().join(format!("pagi_builtin_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/a.py"), "import os\nTODO = 1\n").unwrap();
+        std::fs::write(root.join("notes.md"), "nothing to do\n").unwrap();
+        let config = BuiltinConfig {
+            enabled: true,
+            roots: vec![root.canonicalize().unwrap()],
+            http_domains: vec!["example.com".to_string()],
+            max_bytes: 1024,
+        };
+        (root, config)
+    }
+
+    fn p(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[tokio::test]
+    async fn peek_file_reads_a_byte_range() {
+        let (root, config) = sandbox();
+        let head = execute("peek_file", &p(&[("path", "src/a.py"), ("end", "9")]), &config).await;
+        assert_eq!(head.unwrap(), "import os");
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn list_dir_marks_directories() {
+        let (root, config) = sandbox();
+        let listing = execute("list_dir", &p(&[]), &config).await.unwrap();
+        assert!(listing.ends_with("notes.md (file)\nsrc (dir)"), "{}", listing);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn regex_search_reports_paths_relative_to_the_root() {
+        let (root, config) = sandbox();
+        let found = execute("regex_search", &p(&[("pattern", "TODO")]), &config).await.unwrap();
+        let rel = Path::new("src").join("a.py");
+        assert_eq!(found, format!("[regex_search] 1 match(es)\n{}:2: TODO = 1", rel.display()));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn paths_outside_the_roots_are_rejected() {
+        let (root, config) = sandbox();
+        let escape = execute("peek_file", &p(&[("path", "../../etc/passwd")]), &config).await.unwrap_err();
+        assert!(escape.contains("outside the allowed roots") || escape.contains("No such file"), "{}", escape);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn regex_search_does_not_follow_symlinks_out_of_the_roots() {
+        let (root, config) = sandbox();
+        let outside = std::env::temp_dir().join(format!("pagi_builtin_outside_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("secret.txt"), "TODO leaked\n").unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("linked_dir")).unwrap();
+        std::os::unix::fs::symlink(outside.join("secret.txt"), root.join("linked_file.txt")).unwrap();
+
+        let found = execute("regex_search", &p(&[("pattern", "TODO")]), &config).await.unwrap();
+        assert!(found.starts_with("[regex_search] 1 match(es)") && !found.contains("leaked"), "{}", found);
+        let _ = std::fs::remove_dir_all(&root);
+        let _ = std::fs::remove_dir_all(&outside);
+    }
+
+    #[tokio::test]
+    async fn http_get_only_reaches_allowed_hosts() {
+        let (root, config) = sandbox();
+        let denied = execute("http_get", &p(&[("url", "https://evil.test/x")]), &config).await.unwrap_err();
+        assert!(denied.contains("not in PAGI_BUILTIN_HTTP_DOMAINS"), "{}", denied);
+        assert!(config.host_allowed("api.example.com") && !config.host_allowed("badexample.com"));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn http_get_stops_reading_at_the_cap() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Announces a 1 GB body and streams until the client hangs up.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut sock, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = sock.read(&mut request).await;
+            let _ = sock.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 1000000000\r\n\r\n").await;
+            let chunk = vec![b'x'; 8192];
+            while sock.write_all(&chunk).await.is_ok() {}
+        });
+        let (root, mut config) = sandbox();
+        config.http_domains = vec!["127.0.0.1".to_string()];
+
+        let url = format!("http://{}/big", addr);
+        let fetch = execute("http_get", &p(&[("url", &url)]), &config);
+        let body = tokio::time::timeout(Duration::from_secs(10), fetch).await.unwrap().unwrap();
+        assert!(body.starts_with("[http_get] 200 OK") && body.ends_with("... [truncated]"), "{}", body);
+        assert!(body.len() <= config.max_bytes + "\n... [truncated]".len());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn register_adds_prefixed_names_next_to_existing_skills() {
+        let (root, config) = sandbox();
+        let mut list = AllowList {
+            skills: vec!["peek_file".to_string()],
+            revision: "worktree".to_string(),
+            paths: Default::default(),
+        };
+        config.register(&mut list);
+        assert!(list.contains("builtin:regex_search") && list.contains("peek_file"));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
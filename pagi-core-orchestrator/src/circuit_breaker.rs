@@ -0,0 +1,185 @@
+// Consecutive-failure circuit breaker for external dependencies (L4 Qdrant).
+// Closed → Open after `threshold` failures; after `cooldown` one probe call is let through
+// (half-open): success closes the breaker, failure re-opens it for another cooldown.
+// RetryPolicy paces retries of failed calls with capped exponential backoff while the breaker is closed.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    Closed,
+    Open,
+    /// Cooldown elapsed and a probe call is in flight.
+    HalfOpen,
+}
+
+impl BreakerState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BreakerState::Closed => "closed",
+            BreakerState::Open => "open",
+            BreakerState::HalfOpen => "half_open",
+        }
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    probing: bool,
+}
+
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            cooldown,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Whether a call may proceed. While open, returns true once per cooldown (the recovery probe).
+    pub fn allow(&self) -> bool {
+        let mut inner = self.lock();
+        match inner.opened_at {
+            None => true,
+            Some(at) if !inner.probing && at.elapsed() >= self.cooldown => {
+                inner.probing = true;
+                true
+            }
+            Some(_) => false,
+        }
+    }
+
+    pub fn on_success(&self) {
+        let mut inner = self.lock();
+        if inner.opened_at.is_some() {
+            eprintln!("[CircuitBreaker] probe succeeded; closing");
+        }
+        *inner = Inner::default();
+    }
+
+    pub fn on_failure(&self) {
+        let mut inner = self.lock();
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        if inner.probing || (inner.opened_at.is_none() && inner.consecutive_failures >= self.threshold) {
+            if inner.opened_at.is_none() {
+                eprintln!(
+                    "[CircuitBreaker] open after {} consecutive failures",
+                    inner.consecutive_failures
+                );
+            }
+            inner.opened_at = Some(Instant::now());
+            inner.probing = false;
+        }
+    }
+
+    pub fn state(&self) -> BreakerState {
+        let inner = self.lock();
+        match (inner.opened_at, inner.probing) {
+            (None, _) => BreakerState::Closed,
+            (Some(_), true) => BreakerState::HalfOpen,
+            (Some(_), false) => BreakerState::Open,
+        }
+    }
+}
+
+/// Attempts per call and the backoff before each retry: `base`, doubling per retry, capped at `max`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total tries including the first; 1 disables retries.
+    pub attempts: u32,
+    pub base: Duration,
+    pub max: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(attempts: u32, base: Duration, max: Duration) -> Self {
+        Self {
+            attempts: attempts.max(1),
+            base,
+            max: max.max(base),
+        }
+    }
+
+    /// Delay before retry number `retry` (1 = the second attempt).
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 1u32 << retry.saturating_sub(1).min(16);
+        self.base.saturating_mul(factor).min(self.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COOLDOWN: Duration = Duration::from_millis(20);
+
+    fn tripped() -> CircuitBreaker {
+        let b = CircuitBreaker::new(2, COOLDOWN);
+        b.on_failure();
+        b.on_failure();
+        b
+    }
+
+    fn cooled(b: CircuitBreaker) -> CircuitBreaker {
+        std::thread::sleep(COOLDOWN + Duration::from_millis(5));
+        b
+    }
+
+    #[test]
+    fn trips_after_the_failure_threshold() {
+        let b = CircuitBreaker::new(2, COOLDOWN);
+        b.on_failure();
+        assert_eq!(b.state(), BreakerState::Closed);
+        b.on_failure();
+        assert_eq!(b.state(), BreakerState::Open);
+        assert!(!b.allow());
+    }
+
+    #[test]
+    fn allows_a_single_probe_after_the_cooldown() {
+        let b = cooled(tripped());
+        assert!(b.allow());
+        assert!(!b.allow(), "only one probe in flight");
+    }
+
+    #[test]
+    fn a_failed_probe_reopens() {
+        let b = cooled(tripped());
+        assert!(b.allow());
+        b.on_failure();
+        assert_eq!(b.state(), BreakerState::Open);
+        assert!(!b.allow());
+    }
+
+    #[test]
+    fn a_successful_probe_closes() {
+        let b = cooled(tripped());
+        assert!(b.allow());
+        b.on_success();
+        assert_eq!(b.state(), BreakerState::Closed);
+        assert!(b.allow());
+    }
+
+    #[test]
+    fn retry_backoff_doubles_up_to_the_cap() {
+        let policy = RetryPolicy::new(0, Duration::from_millis(100), Duration::from_millis(350));
+        assert_eq!(policy.attempts, 1, "at least one attempt");
+        let delays: Vec<u128> = (1..=4).map(|r| policy.backoff(r).as_millis()).collect();
+        assert_eq!(delays, [100, 200, 350, 350]);
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_millis(350));
+    }
+}
@@ -0,0 +1,147 @@
+// Time source for the timestamps the orchestrator records: registry commits, L2 version and retention/decay
+// cutoffs, audit and lineage entries, heal lifecycle stamps. MemoryManager owns the process clock and the
+// Watchdog, patch catalog and session journal share it; production uses the system clock, tests inject a
+// ManualClock so time-dependent behavior is deterministic. Exported reports (EndSession, GetHealReport) are
+// rendered in UTC unless the request asks for a fixed offset, so output does not depend on the host's zone.
+
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, FixedOffset, SecondsFormat, Utc};
+
+pub trait TimeSource: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+struct SystemTime;
+
+impl TimeSource for SystemTime {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Shared clock handle (cheap to clone); the default reads the system time.
+#[derive(Clone)]
+pub struct Clock(Arc<dyn TimeSource>);
+
+impl Clock {
+    pub fn system() -> Self {
+        Self(Arc::new(SystemTime))
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        self.0.now()
+    }
+
+    /// Unix milliseconds.
+    pub fn now_ms(&self) -> i64 {
+        self.now().timestamp_millis()
+    }
+
+    /// Unix seconds.
+    pub fn now_secs(&self) -> i64 {
+        self.now().timestamp()
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::system()
+    }
+}
+
+impl From<ManualClock> for Clock {
+    fn from(clock: ManualClock) -> Self {
+        Self(Arc::new(clock))
+    }
+}
+
+/// Deterministic clock for tests: time stands still until `set` or `advance`; clones share one time.
+#[derive(Clone)]
+#[allow(dead_code)]
+pub struct ManualClock(Arc<Mutex<DateTime<Utc>>>);
+
+#[allow(dead_code)]
+impl ManualClock {
+    /// Starts at `unix_secs`.
+    pub fn at(unix_secs: i64) -> Self {
+        Self(Arc::new(Mutex::new(DateTime::from_timestamp(unix_secs, 0).unwrap_or_default())))
+    }
+
+    pub fn set(&self, at: DateTime<Utc>) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = at;
+    }
+
+    pub fn advance(&self, by: std::time::Duration) {
+        let mut now = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(later) = Duration::from_std(by).ok().and_then(|by| now.checked_add_signed(by)) {
+            *now = later;
+        }
+    }
+}
+
+impl TimeSource for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Report time zone: empty, "UTC" or "Z" for UTC, else a fixed offset "+HH:MM", "-HH:MM", "+HHMM" or "+HH".
+/// Named zones other than UTC are rejected: there is no tz database here, and guessing from the host would
+/// make reports differ between machines.
+pub fn parse_timezone(spec: &str) -> Result<FixedOffset, String> {
+    let spec = spec.trim();
+    let utc = FixedOffset::east_opt(0).expect("zero offset");
+    if spec.is_empty() || spec.eq_ignore_ascii_case("utc") || spec.eq_ignore_ascii_case("z") {
+        return Ok(utc);
+    }
+    let bad = || format!("timezone {:?}: use UTC or a fixed offset such as +02:00 or -0530", spec);
+    let (sign, digits) = match spec.as_bytes().first() {
+        Some(b'+') => (1, &spec[1..]),
+        Some(b'-') => (-1, &spec[1..]),
+        _ => return Err(bad()),
+    };
+    let digits = digits.replacen(':', "", 1);
+    if !matches!(digits.len(), 2 | 4) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(bad());
+    }
+    let hours: i32 = digits[..2].parse().map_err(|_| bad())?;
+    let minutes: i32 = if digits.len() == 4 { digits[2..].parse().map_err(|_| bad())? } else { 0 };
+    if hours > 23 || minutes > 59 {
+        return Err(bad());
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).ok_or_else(bad)
+}
+
+/// RFC 3339 time of `at` in `tz`, to the second ("Z" for UTC).
+pub fn format_in(at: DateTime<Utc>, tz: FixedOffset) -> String {
+    at.with_timezone(&tz).to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual_clock_advances_only_when_told() {
+        let manual = ManualClock::at(1_700_000_000);
+        let clock = Clock::from(manual.clone());
+        assert_eq!(clock.now_secs(), 1_700_000_000);
+        assert_eq!(clock.now_secs(), 1_700_000_000);
+        manual.advance(std::time::Duration::from_millis(1500));
+        assert_eq!(clock.now_ms(), 1_700_000_001_500);
+    }
+
+    #[test]
+    fn reports_render_in_the_requested_offset() {
+        let at = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        assert_eq!(format_in(at, parse_timezone("").unwrap()), "2023-11-14T22:13:20Z");
+        assert_eq!(format_in(at, parse_timezone("UTC").unwrap()), "2023-11-14T22:13:20Z");
+        assert_eq!(format_in(at, parse_timezone("+02:00").unwrap()), "2023-11-15T00:13:20+02:00");
+        assert_eq!(format_in(at, parse_timezone("-0530").unwrap()), "2023-11-14T16:43:20-05:30");
+        assert_eq!(format_in(at, parse_timezone("+09").unwrap()), "2023-11-15T07:13:20+09:00");
+        for bad in ["Europe/Berlin", "+2", "+24:00", "+02:60", "02:00", "+02:00:00"] {
+            assert!(parse_timezone(bad).is_err(), "{}", bad);
+        }
+    }
+}
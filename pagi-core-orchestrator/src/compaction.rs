@@ -0,0 +1,177 @@
+// CompactMemory: the memory maintenance that otherwise runs on timers, on demand (before SnapshotKb, during
+// incident response). In order: the L2 consolidation sweep (when PAGI_CONSOLIDATE_AFTER_SECS is set), the L4
+// maintenance pass (decay, retention) and vector store optimizer over the requested collections, expired search
+// cache entries, then rewrites of the persistence files (L2 snapshot, L4 WAL). A failed step is reported on its
+// layer and the others still run. Entries and bytes per layer are GetMemoryStats' before and after the run.
+
+use std::path::Path;
+
+use tonic::Status;
+
+use crate::consolidation::{self, ConsolidationConfig};
+use crate::memory_manager::MemoryManager;
+use crate::proto::pagi_proto::{CompactMemoryReport, CompactMemoryRequest, LayerCompaction};
+
+fn file_len(path: &Path) -> u64 {
+    std::fs::metadata(path).map_or(0, |m| m.len())
+}
+
+pub async fn run(memory: &MemoryManager, req: CompactMemoryRequest) -> Result<CompactMemoryReport, Status> {
+    if !req.kb_names.is_empty() && !memory.l4_enabled() {
+        return Err(Status::failed_precondition("Qdrant disabled (PAGI_DISABLE_QDRANT=true)"));
+    }
+    let collections = memory.compaction_targets(&req.kb_names)?;
+    let started = std::time::Instant::now();
+    let before = memory.stats().await;
+    let mut layers: Vec<LayerCompaction> = before
+        .layers
+        .iter()
+        .map(|l| LayerCompaction {
+            layer: l.layer,
+            name: l.name.clone(),
+            entries_before: l.entries,
+            bytes_before: l.bytes,
+            ..Default::default()
+        })
+        .collect();
+    let mut report = CompactMemoryReport::default();
+    let mut l2_errors = Vec::new();
+    let mut l4_errors = Vec::new();
+
+    if let Some(cfg) = ConsolidationConfig::from_env().filter(|_| memory.l4_enabled()) {
+        match consolidation::run_once(memory, &cfg, memory.clock().now_ms()).await {
+            Ok(n) => report.l2_consolidated = n as u64,
+            Err(e) => l2_errors.push(format!("consolidation into {}: {}", cfg.kb, e)),
+        }
+    }
+
+    let l4 = memory
+        .compact_l4(&collections, !req.skip_optimize, memory.clock().now_secs())
+        .await;
+    report.retention = l4.retention;
+    report.optimized = l4.optimized;
+    report.tombstones_purged = l4.tombstones_purged;
+    report.search_cache_purged = l4.search_cache_purged;
+    l4_errors.extend(l4.errors);
+
+    let mut l2_file = None;
+    if let Some(path) = MemoryManager::l2_snapshot_path() {
+        let before = file_len(&path);
+        if let Err(e) = memory.snapshot_l2(&path) {
+            l2_errors.push(format!("L2 snapshot {}: {}", path.display(), e));
+        }
+        l2_file = Some((path.display().to_string(), before, file_len(&path)));
+    }
+    let mut wal_file = None;
+    if let Some(wal) = memory.wal() {
+        let path = wal.path().display().to_string();
+        match wal.compact_now() {
+            Ok((before, after)) => wal_file = Some((path, before, after)),
+            Err(e) => l4_errors.push(format!("WAL {}: {}", path, e)),
+        }
+    }
+
+    let after = memory.stats().await;
+    for layer in &mut layers {
+        if let Some(now) = after.layers.iter().find(|l| l.layer == layer.layer) {
+            layer.entries_after = now.entries;
+            layer.bytes_after = now.bytes;
+        }
+        let (file, errors) = match layer.layer {
+            2 => (l2_file.take(), std::mem::take(&mut l2_errors)),
+            4 => (wal_file.take(), std::mem::take(&mut l4_errors)),
+            _ => continue,
+        };
+        if let Some((path, before, after)) = file {
+            layer.file = path;
+            layer.file_bytes_before = before;
+            layer.file_bytes_after = after;
+        }
+        layer.errors = errors;
+    }
+    report.layers = layers;
+    report.duration_ms = started.elapsed().as_millis() as u64;
+    eprintln!(
+        "[Compaction] consolidated {} L2 key(s), maintained {} KB(s), optimized {} ({} tombstone(s) purged), \
+         dropped {} expired cache entries in {} ms",
+        report.l2_consolidated,
+        report.retention.len(),
+        report.optimized.len(),
+        report.tombstones_purged,
+        report.search_cache_purged,
+        report.duration_ms
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::pagi_proto::{DeleteVectorsRequest, UpsertRequest, VectorPoint};
+
+    /// kb_core holding p1 and p2, with p0 upserted then deleted.
+    async fn with_tombstone() -> MemoryManager {
+        let mm = MemoryManager::in_memory(4);
+        mm.init_kbs().await.unwrap();
+        let points = (0..3)
+            .map(|i| VectorPoint {
+                id: format!("p{}", i),
+                vector: vec![1.0, i as f32, 0.5, 0.0],
+                ..Default::default()
+            })
+            .collect();
+        mm.upsert_vectors(UpsertRequest {
+            kb_name: "kb_core".into(),
+            points,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        mm.delete_vectors(DeleteVectorsRequest {
+            kb_name: "kb_core".into(),
+            ids: vec!["p0".into()],
+        })
+        .await
+        .unwrap();
+        mm
+    }
+
+    fn kb_core() -> CompactMemoryRequest {
+        CompactMemoryRequest {
+            kb_names: vec!["kb_core".into()],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn unknown_kbs_are_not_found() {
+        let unknown = CompactMemoryRequest {
+            kb_names: vec!["kb_nope".into()],
+            ..Default::default()
+        };
+        let mm = with_tombstone().await;
+        assert_eq!(run(&mm, unknown).await.unwrap_err().code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn compaction_purges_tombstones_of_the_named_kbs() {
+        let report = run(&with_tombstone().await, kb_core()).await.unwrap();
+        assert_eq!(report.optimized, vec!["kb_core".to_string()]);
+        assert_eq!(report.tombstones_purged, 1);
+    }
+
+    #[tokio::test]
+    async fn the_l4_layer_reports_its_live_entries() {
+        let report = run(&with_tombstone().await, kb_core()).await.unwrap();
+        let l4 = report.layers.iter().find(|l| l.layer == 4).unwrap();
+        assert_eq!((l4.entries_before, l4.entries_after), (2, 2));
+        assert!(l4.errors.is_empty(), "{:?}", l4.errors);
+    }
+
+    #[tokio::test]
+    async fn a_second_compaction_has_nothing_to_purge() {
+        let mm = with_tombstone().await;
+        run(&mm, kb_core()).await.unwrap();
+        assert_eq!(run(&mm, kb_core()).await.unwrap().tombstones_purged, 0);
+    }
+}
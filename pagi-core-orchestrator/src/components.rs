@@ -0,0 +1,258 @@
+// Component registry: what a patch `component` means — target repo, apply-time test runner, patch file
+// extension, language and HITL tier. Built-ins cover rust_core and python_skill; PAGI_COMPONENTS_FILE
+// (JSON object keyed by component name) adds components or overrides built-in fields without code changes.
+// The test runner is set either as a `test_command` argv or as a `test` object (command, args, cwd, env and
+// success criteria: exit_codes, junit_xml; see test_runner.rs).
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use tonic::Status;
+
+use crate::test_runner::{TestRunner, TestSpec};
+
+/// Whether patches for a component need human approval before ApplyPatch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HitlTier {
+    /// Approval required (request flag or approve-flag file).
+    Always,
+    /// Applied without approval.
+    Never,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    pub name: String,
+    /// Repo the test step runs in; also the apply-queue lane key.
+    pub repo: PathBuf,
+    /// Apply-time test step; no command skips it.
+    pub test: TestRunner,
+    /// Extension of the proposed file in format-patch output (no dot).
+    pub extension: String,
+    /// Language named in local-model prompts.
+    pub language: String,
+    pub hitl: HitlTier,
+    /// Run evolve_skill_from_patch after an auto-committed apply (PAGI_AUTO_EVOLVE_SKILLS).
+    pub evolves_skills: bool,
+}
+
+impl Component {
+    pub fn requires_hitl(&self) -> bool {
+        self.hitl == HitlTier::Always
+    }
+
+    pub fn test_label(&self) -> String {
+        self.test.label()
+    }
+}
+
+/// One PAGI_COMPONENTS_FILE entry; omitted fields keep the built-in value (or the generic default).
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ComponentSpec {
+    repo: Option<PathBuf>,
+    test_command: Option<Vec<String>>,
+    test: Option<TestSpec>,
+    extension: Option<String>,
+    language: Option<String>,
+    hitl: Option<HitlTier>,
+    evolves_skills: Option<bool>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ComponentRegistry {
+    components: BTreeMap<String, Component>,
+}
+
+impl ComponentRegistry {
+    /// rust_core (core repo, cargo test, HITL) and python_skill (bridge repo, pytest, auto-apply).
+    pub fn builtin(core_dir: &Path, bridge_dir: &Path) -> Self {
+        let argv = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let components = [
+            Component {
+                name: "rust_core".to_string(),
+                repo: core_dir.to_path_buf(),
+                test: TestRunner::argv(argv(&["cargo", "test"])),
+                extension: "rs".to_string(),
+                language: "Rust".to_string(),
+                hitl: HitlTier::Always,
+                evolves_skills: false,
+            },
+            Component {
+                name: "python_skill".to_string(),
+                repo: bridge_dir.to_path_buf(),
+                test: TestRunner::argv(argv(&["poetry", "run", "pytest", "tests/", "-v"])),
+                extension: "py".to_string(),
+                language: "Python".to_string(),
+                hitl: HitlTier::Never,
+                evolves_skills: true,
+            },
+        ];
+        Self {
+            components: components.into_iter().map(|c| (c.name.clone(), c)).collect(),
+        }
+    }
+
+    /// Built-ins plus PAGI_COMPONENTS_FILE; an unreadable or invalid file is logged and ignored.
+    pub fn from_env(core_dir: &Path, bridge_dir: &Path) -> Self {
+        let mut registry = Self::builtin(core_dir, bridge_dir);
+        let Some(path) = std::env::var("PAGI_COMPONENTS_FILE")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+        else {
+            return registry;
+        };
+        let merged = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|raw| registry.merge_json(&raw));
+        match merged {
+            Ok(()) => eprintln!(
+                "[Components] {} component(s) from {}: {}",
+                registry.components.len(),
+                path,
+                registry.names().join(", ")
+            ),
+            Err(e) => eprintln!("[Components] ignoring {}: {}", path, e),
+        }
+        registry
+    }
+
+    /// Apply a JSON object of component specs; new components need `repo` and `extension`.
+    fn merge_json(&mut self, raw: &str) -> Result<(), String> {
+        let specs: BTreeMap<String, ComponentSpec> = serde_json::from_str(raw).map_err(|e| e.to_string())?;
+        let mut merged = self.components.clone();
+        for (name, spec) in specs {
+            let base = match merged.get(&name) {
+                Some(existing) => existing.clone(),
+                None => Component {
+                    name: name.clone(),
+                    repo: spec.repo.clone().ok_or_else(|| format!("{}: missing repo", name))?,
+                    test: TestRunner::default(),
+                    extension: spec.extension.clone().ok_or_else(|| format!("{}: missing extension", name))?,
+                    language: name.clone(),
+                    hitl: HitlTier::Always,
+                    evolves_skills: false,
+                },
+            };
+            let test = match (spec.test_command, spec.test) {
+                (Some(_), Some(_)) => return Err(format!("{}: set test_command or test, not both", name)),
+                (Some(argv), None) => TestRunner::argv(argv),
+                (None, Some(test)) => base.test.overlay(test).map_err(|e| format!("{}: {}", name, e))?,
+                (None, None) => base.test,
+            };
+            merged.insert(
+                name,
+                Component {
+                    repo: spec.repo.unwrap_or(base.repo),
+                    test,
+                    extension: spec.extension.unwrap_or(base.extension),
+                    language: spec.language.unwrap_or(base.language),
+                    hitl: spec.hitl.unwrap_or(base.hitl),
+                    evolves_skills: spec.evolves_skills.unwrap_or(base.evolves_skills),
+                    ..base
+                },
+            );
+        }
+        self.components = merged;
+        Ok(())
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.components.keys().map(String::as_str).collect()
+    }
+
+    /// Look up a component; unknown names are InvalidArgument listing the known ones.
+    pub fn get(&self, name: &str) -> Result<&Component, Status> {
+        self.components.get(name).ok_or_else(|| {
+            Status::invalid_argument(format!(
+                "unknown component {:?} (known: {})",
+                name,
+                self.names().join(", ")
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builtin() -> ComponentRegistry {
+        ComponentRegistry::builtin(Path::new("/core"), Path::new("/bridge"))
+    }
+
+    #[test]
+    fn builtin_components_gate_core_patches() {
+        let registry = builtin();
+        assert!(registry.get("rust_core").unwrap().requires_hitl());
+        assert_eq!(registry.get("python_skill").unwrap().test_label(), "poetry run pytest tests/ -v");
+    }
+
+    #[test]
+    fn config_adds_components() {
+        let mut registry = builtin();
+        registry
+            .merge_json(
+                r#"{"node_skill": {"repo": "/node", "test_command": ["npm", "test"], "extension": "js",
+                                   "language": "JavaScript", "hitl": "never"}}"#,
+            )
+            .unwrap();
+        let node = registry.get("node_skill").unwrap();
+        assert_eq!((node.repo.as_path(), node.extension.as_str()), (Path::new("/node"), "js"));
+        assert_eq!(node.test_label(), "npm test");
+        assert!(!node.requires_hitl());
+    }
+
+    #[test]
+    fn overrides_keep_unset_builtin_fields() {
+        let mut registry = builtin();
+        registry.merge_json(r#"{"rust_core": {"test_command": ["cargo", "test", "--workspace"]}}"#).unwrap();
+        let core = registry.get("rust_core").unwrap();
+        assert_eq!(core.test_label(), "cargo test --workspace");
+        assert_eq!(core.repo, PathBuf::from("/core"));
+    }
+
+    #[test]
+    fn test_runners_take_cwd_env_and_a_junit_report() {
+        let mut registry = builtin();
+        registry
+            .merge_json(
+                r#"{"go_core": {"repo": "/go", "extension": "go", "test": {
+                        "command": "sh", "args": ["-c", "go test ./... 2>&1 | go-junit-report > report.xml"],
+                        "cwd": "svc", "env": {"CGO_ENABLED": "0"}, "success": {"junit_xml": "report.xml"}}}}"#,
+            )
+            .unwrap();
+        let go = &registry.get("go_core").unwrap().test;
+        assert_eq!((go.command.as_str(), go.cwd.as_deref()), ("sh", Some(Path::new("svc"))));
+        assert_eq!((go.env["CGO_ENABLED"].as_str(), go.junit_xml.as_deref()), ("0", Some(Path::new("report.xml"))));
+    }
+
+    #[test]
+    fn partial_test_overrides_keep_the_builtin_runner() {
+        let mut registry = builtin();
+        registry.merge_json(r#"{"python_skill": {"test": {"success": {"exit_codes": [0, 5]}}}}"#).unwrap();
+        let python = &registry.get("python_skill").unwrap().test;
+        assert_eq!(python.label(), "poetry run pytest tests/ -v");
+        assert_eq!(python.exit_codes, [0, 5]);
+    }
+
+    #[test]
+    fn invalid_config_leaves_the_registry_unchanged() {
+        let mut registry = builtin();
+        assert!(registry.merge_json(r#"{"proto": {"extension": "proto"}}"#).unwrap_err().contains("missing repo"));
+        let both = r#"{"rust_core": {"test_command": ["make", "check"], "test": {"command": "make"}}}"#;
+        assert!(registry.merge_json(both).unwrap_err().contains("not both"));
+        assert!(registry.get("proto").is_err());
+        assert_eq!(registry.get("rust_core").unwrap().test_label(), "cargo test");
+    }
+
+    #[test]
+    fn unknown_components_are_invalid_arguments_naming_the_known_ones() {
+        let err = builtin().get("c_core").unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        assert!(err.message().contains("python_skill"), "{}", err.message());
+    }
+}
@@ -0,0 +1,210 @@
+// L2 → L4 consolidation ("dream cycle"): the scheduler task "consolidation" runs every
+// PAGI_CONSOLIDATE_INTERVAL_SECS. L2 keys untouched for PAGI_CONSOLIDATE_AFTER_SECS are digested (latest value
+// plus earlier versions), chunked, embedded (server-side embedder when configured, else the provenance hash
+// embedder) and upserted into PAGI_CONSOLIDATE_KB, then evicted from L2. A key written again meanwhile is kept; a
+// failed upsert leaves L2 untouched.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::memory_manager::{L2Version, MemoryManager};
+use crate::proto::pagi_proto::{UpsertRequest, VectorPoint};
+use crate::provenance;
+use crate::scheduler::Scheduler;
+
+/// Max chars per consolidated chunk (one L4 point each).
+const CHUNK_CHARS: usize = 1000;
+
+pub struct ConsolidationConfig {
+    /// Idle time before an L2 key is consolidated.
+    pub after: Duration,
+    pub interval: Duration,
+    pub kb: String,
+}
+
+impl ConsolidationConfig {
+    /// None when PAGI_CONSOLIDATE_AFTER_SECS is unset or 0 (consolidation disabled).
+    pub fn from_env() -> Option<Self> {
+        let secs = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|s| s.trim().parse::<u64>().ok())
+                .unwrap_or(default)
+        };
+        let after = secs("PAGI_CONSOLIDATE_AFTER_SECS", 0);
+        if after == 0 {
+            return None;
+        }
+        Some(Self {
+            after: Duration::from_secs(after),
+            interval: Duration::from_secs(secs("PAGI_CONSOLIDATE_INTERVAL_SECS", 300).max(1)),
+            kb: std::env::var("PAGI_CONSOLIDATE_KB")
+                .ok()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| "kb_episodic".to_string()),
+        })
+    }
+}
+
+/// Digest of one key's history split into chunks of at most CHUNK_CHARS chars.
+pub fn chunks(key: &str, history: &[L2Version]) -> Vec<String> {
+    let Some(latest) = history.last() else {
+        return Vec::new();
+    };
+    let mut digest = format!("L2 {} (v{}): {}", key, latest.version, latest.value);
+    if history.len() > 1 {
+        digest.push_str("\nEarlier:");
+        for h in history[..history.len() - 1].iter().rev() {
+            digest.push_str(&format!("\nv{}: {}", h.version, h.value));
+        }
+    }
+    let chars: Vec<char> = digest.chars().collect();
+    chars.chunks(CHUNK_CHARS).map(|c| c.iter().collect()).collect()
+}
+
+/// Consolidate every stale L2 key into `cfg.kb`; returns the number of keys evicted.
+pub async fn run_once(memory: &MemoryManager, cfg: &ConsolidationConfig, now_ms: i64) -> Result<usize, String> {
+    let stale = memory.stale_l2(now_ms - cfg.after.as_millis() as i64);
+    if stale.is_empty() {
+        return Ok(0);
+    }
+    memory.ensure_kb(&cfg.kb).await.map_err(|e| e.to_string())?;
+    let dim = memory.kb_dim(&cfg.kb);
+    let mut evicted = 0;
+    for (key, history) in stale {
+        let Some(latest) = history.last() else {
+            continue;
+        };
+        let parts = chunks(&key, &history);
+        let mut points = Vec::with_capacity(parts.len());
+        for (i, content) in parts.iter().enumerate() {
+            let vector = match memory.embed_query(content, dim).await {
+                Some(v) => v,
+                None => provenance::hash_embed(content, dim),
+            };
+            points.push(VectorPoint {
+                id: Uuid::new_v4().to_string(),
+                vector,
+                payload: HashMap::from([
+                    ("l2_key".to_string(), key.clone()),
+                    ("version".to_string(), latest.version.to_string()),
+                    ("chunk".to_string(), format!("{}/{}", i + 1, parts.len())),
+                    ("at".to_string(), (latest.written_at_ms / 1000).to_string()),
+                    ("content".to_string(), content.clone()),
+                ]),
+                ..Default::default()
+            });
+        }
+        memory
+            .upsert_vectors(UpsertRequest {
+                kb_name: cfg.kb.clone(),
+                points,
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| format!("{}: {}", key, e.message()))?;
+        if memory.evict_l2(&key, latest.version) {
+            evicted += 1;
+        }
+    }
+    Ok(evicted)
+}
+
+/// Register the "consolidation" task (every PAGI_CONSOLIDATE_INTERVAL_SECS); off when disabled or L4 is off.
+pub fn schedule(memory: &Arc<MemoryManager>, scheduler: Scheduler) -> Scheduler {
+    let cfg = ConsolidationConfig::from_env();
+    if cfg.is_some() && !memory.l4_enabled() {
+        eprintln!("[Consolidation] disabled: L4 is off");
+    }
+    let Some(cfg) = cfg.filter(|_| memory.l4_enabled()).map(Arc::new) else {
+        return scheduler.add("consolidation", Duration::ZERO, || std::future::ready(Ok(String::new())));
+    };
+    let memory = Arc::clone(memory);
+    scheduler.add("consolidation", cfg.interval, move || {
+        let (memory, cfg) = (Arc::clone(&memory), Arc::clone(&cfg));
+        async move {
+            match run_once(&memory, &cfg, memory.clock().now_ms()).await {
+                Ok(n) => {
+                    if n > 0 {
+                        eprintln!("[Consolidation] moved {} L2 key(s) into {}", n, cfg.kb);
+                    }
+                    Ok(format!("{} L2 key(s) moved into {}", n, cfg.kb))
+                }
+                Err(e) => Err(format!("{}: {}", cfg.kb, e)),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::pagi_proto::SearchRequest;
+    use crate::watchdog::tests::lock_test_env;
+
+    /// A memory backend with two versions of the L2 key "goal".
+    async fn with_goal() -> MemoryManager {
+        let _g = lock_test_env().await;
+        std::env::set_var("PAGI_VECTOR_BACKEND", "memory");
+        let memory = MemoryManager::new_async().await.unwrap();
+        std::env::remove_var("PAGI_VECTOR_BACKEND");
+        memory.access(2, "goal", Some("draft plan")).unwrap();
+        memory.access(2, "goal", Some("final plan")).unwrap();
+        memory
+    }
+
+    fn config() -> ConsolidationConfig {
+        ConsolidationConfig {
+            after: Duration::from_secs(60),
+            interval: Duration::from_secs(1),
+            kb: "kb_episodic".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn fresh_keys_stay_in_l2() {
+        let memory = with_goal().await;
+        let now = chrono::Utc::now().timestamp_millis();
+        assert_eq!(run_once(&memory, &config(), now).await.unwrap(), 0);
+        assert_eq!(memory.access(2, "goal", None).unwrap().0, "final plan");
+    }
+
+    #[tokio::test]
+    async fn stale_keys_are_evicted_from_l2() {
+        let memory = with_goal().await;
+        let later = chrono::Utc::now().timestamp_millis() + 61_000;
+        assert_eq!(run_once(&memory, &config(), later).await.unwrap(), 1);
+        assert_eq!(memory.access(2, "goal", None).unwrap().0, "");
+    }
+
+    #[tokio::test]
+    async fn stale_keys_land_in_l4_with_their_history() {
+        let memory = with_goal().await;
+        run_once(&memory, &config(), chrono::Utc::now().timestamp_millis() + 61_000).await.unwrap();
+        let found = memory
+            .search_points(SearchRequest {
+                query: "final plan".to_string(),
+                kb_name: "kb_episodic".to_string(),
+                limit: 5,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let content = &found.points[0].payload["content"];
+        assert_eq!(content, "L2 goal (v2): final plan\nEarlier:\nv1: draft plan");
+    }
+
+    #[test]
+    fn long_values_are_chunked() {
+        let long = L2Version {
+            version: 1,
+            written_at_ms: 0,
+            value: "x".repeat(2 * CHUNK_CHARS).into(),
+        };
+        assert_eq!(chunks("k", &[long]).len(), 3);
+    }
+}
@@ -0,0 +1,176 @@
+// Importance/recency decay for L4 points. With PAGI_MEMORY_DECAY_HALFLIFE (seconds; unset or 0 disables),
+// upserts stamp `at` (unix secs, when missing), `importance` (0.0–1.0, default 0.5) and `decay_score`
+// (0–100 integer, so range filters can skip faded memories); the retention pass rescores every point as
+// importance × 0.5^(age / halflife) and deletes those below PAGI_MEMORY_DECAY_MIN_SCORE (default 5).
+// Time-weighted searches (SearchRequest.recency_weight) rerank hits by the same `at` (see `blend_recency`).
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::vector_store::ScoredPoint;
+
+pub const DEFAULT_IMPORTANCE: f64 = 0.5;
+/// Payload fields the decay pass reads.
+pub const FIELDS: [&str; 3] = ["at", "importance", "decay_score"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decay {
+    pub halflife_secs: u64,
+    /// Points scoring below this (0–100) are deleted.
+    pub min_score: u32,
+}
+
+/// What one decay pass does to a KB.
+#[derive(Debug, Default, PartialEq)]
+pub struct DecayPlan {
+    pub delete: Vec<String>,
+    /// New decay_score → ids whose stored score differs.
+    pub rescore: BTreeMap<u32, Vec<String>>,
+}
+
+fn importance(payload: &HashMap<String, String>) -> f64 {
+    payload
+        .get("importance")
+        .and_then(|v| v.parse::<f64>().ok())
+        .filter(|x| x.is_finite())
+        .map_or(DEFAULT_IMPORTANCE, |x| x.clamp(0.0, 1.0))
+}
+
+impl Decay {
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|s| s.trim().parse::<u64>().ok())
+                .unwrap_or(default)
+        };
+        let halflife_secs = var("PAGI_MEMORY_DECAY_HALFLIFE", 0);
+        (halflife_secs > 0).then(|| Self {
+            halflife_secs,
+            min_score: var("PAGI_MEMORY_DECAY_MIN_SCORE", 5).min(100) as u32,
+        })
+    }
+
+    /// Score 0–100 for a point of `importance` written `age_secs` ago (future timestamps count as new).
+    pub fn score(&self, importance: f64, age_secs: i64) -> u32 {
+        let halflives = age_secs.max(0) as f64 / self.halflife_secs as f64;
+        (100.0 * importance * 0.5f64.powf(halflives)).round() as u32
+    }
+
+    /// Fill in the orchestrator-maintained fields of a point being upserted.
+    pub fn stamp(&self, payload: &mut HashMap<String, String>, now: i64) {
+        let at = match payload.get("at").and_then(|v| v.parse::<i64>().ok()) {
+            Some(at) => at,
+            None => {
+                payload.insert("at".to_string(), now.to_string());
+                now
+            }
+        };
+        let importance = importance(payload);
+        payload.insert("importance".to_string(), importance.to_string());
+        payload.insert("decay_score".to_string(), self.score(importance, now - at).to_string());
+    }
+
+    /// Rescore scanned points (id, FIELDS); points without `at` predate decay and keep their importance.
+    pub fn plan(&self, points: &[(String, HashMap<String, String>)], now: i64) -> DecayPlan {
+        let mut plan = DecayPlan::default();
+        for (id, payload) in points {
+            let age = payload.get("at").and_then(|v| v.parse::<i64>().ok()).map_or(0, |at| now - at);
+            let score = self.score(importance(payload), age);
+            if score < self.min_score {
+                plan.delete.push(id.clone());
+            } else if payload.get("decay_score").and_then(|v| v.parse::<u32>().ok()) != Some(score) {
+                plan.rescore.entry(score).or_default().push(id.clone());
+            }
+        }
+        plan
+    }
+}
+
+/// 0.5^(age / halflife) for a point written at `at` (unix secs): 1.0 when new or future-dated, 0.0 without `at`.
+pub fn recency(payload: &HashMap<String, String>, now: i64, halflife_secs: u64) -> f64 {
+    match payload.get("at").and_then(|v| v.parse::<i64>().ok()) {
+        Some(at) => 0.5f64.powf((now - at).max(0) as f64 / halflife_secs.max(1) as f64),
+        None => 0.0,
+    }
+}
+
+/// Time-weighted ranking of `points` (best first): `blend` with each hit's recency.
+pub fn blend_recency(points: Vec<ScoredPoint>, weight: f32, halflife_secs: u64, now: i64) -> Vec<ScoredPoint> {
+    blend(points, weight, |payload| recency(payload, now, halflife_secs))
+}
+
+/// Ranking of `points` (best first) blended with a payload `signal` in 0.0–1.0: each hit scores
+/// (1 − weight) × relevance + weight × signal, relevance being its score scaled to 1.0 for the best hit and 0.0
+/// for the worst (similarities, euclid distances and fused ranks live on different scales and run in different
+/// directions).
+pub fn blend(
+    mut points: Vec<ScoredPoint>,
+    weight: f32,
+    signal: impl Fn(&HashMap<String, String>) -> f64,
+) -> Vec<ScoredPoint> {
+    let (best, worst) = match (points.first(), points.last()) {
+        (Some(first), Some(last)) => (first.score, last.score),
+        _ => return points,
+    };
+    let weight = weight.clamp(0.0, 1.0) as f64;
+    for p in &mut points {
+        let relevance = if best != worst { ((p.score - worst) / (best - worst)) as f64 } else { 1.0 };
+        p.score = ((1.0 - weight) * relevance + weight * signal(&p.payload)) as f32;
+    }
+    // Stable: equally blended hits keep their relevance order.
+    points.sort_by(|a, b| b.score.total_cmp(&a.score));
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_halve_per_halflife_and_plan_deletes_faded_points() {
+        let decay = Decay {
+            halflife_secs: 100,
+            min_score: 20,
+        };
+        assert_eq!((decay.score(1.0, 0), decay.score(1.0, 100), decay.score(0.5, 200)), (100, 50, 13));
+
+        let mut payload = HashMap::from([("importance".to_string(), "3".to_string())]);
+        decay.stamp(&mut payload, 1000);
+        assert_eq!((payload["at"].as_str(), payload["importance"].as_str()), ("1000", "1"));
+        assert_eq!(payload["decay_score"], "100");
+
+        let point = |id: &str, fields: &[(&str, &str)]| {
+            let payload = fields.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+            (id.to_string(), payload)
+        };
+        let points = [
+            point("fresh", &[("at", "1000"), ("importance", "1"), ("decay_score", "100")]),
+            point("aging", &[("at", "900"), ("importance", "1"), ("decay_score", "100")]),
+            point("faded", &[("at", "700"), ("importance", "0.5")]),
+            point("legacy", &[]),
+        ];
+        let plan = decay.plan(&points, 1000);
+        assert_eq!(plan.delete, ["faded"]);
+        assert_eq!(plan.rescore, BTreeMap::from([(50, vec!["aging".to_string(), "legacy".to_string()])]));
+    }
+
+    #[test]
+    fn recency_blend_lets_recent_hits_outrank_stale_ones() {
+        let hit = |id: &str, score: f32, at: Option<&str>| ScoredPoint {
+            id: id.to_string(),
+            score,
+            payload: at.map(|at| ("at".to_string(), at.to_string())).into_iter().collect(),
+        };
+        let hits = vec![hit("stale", 0.9, Some("0")), hit("recent", 0.8, Some("1000")), hit("legacy", 0.7, None)];
+        let ranked = |weight: f32| -> Vec<String> {
+            blend_recency(hits.clone(), weight, 100, 1000).into_iter().map(|p| p.id).collect()
+        };
+        assert_eq!(ranked(0.0), ["stale", "recent", "legacy"], "weight 0 keeps the relevance order");
+        assert_eq!(ranked(0.5), ["recent", "stale", "legacy"]);
+        let blended = blend_recency(hits.clone(), 1.0, 100, 1000);
+        assert_eq!((blended[0].id.as_str(), blended[0].score), ("recent", 1.0));
+        assert_eq!(recency(&hits[0].payload, 100, 100), 0.5);
+        let distances: Vec<ScoredPoint> = vec![hit("near", 0.1, Some("0")), hit("far", 0.3, Some("1000"))];
+        assert_eq!(blend_recency(distances, 0.0, 100, 1000)[0].id, "near", "distances rank ascending");
+    }
+}
@@ -0,0 +1,87 @@
+// Content dedup for L4 upserts: every point with a `content` payload is stamped with `content_hash`
+// (SHA-256 hex of the content). UpsertRequest.dedup (default PAGI_UPSERT_DEDUP, "off") then decides what
+// happens when a KB already holds that hash under another id: "skip" drops the incoming point, "merge"
+// writes it over the existing point (incoming vector, payload fields layered over the stored ones).
+
+use std::collections::HashMap;
+
+use sha2::{Digest, Sha256};
+
+/// Payload field holding the content hash.
+pub const HASH_FIELD: &str = "content_hash";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DedupMode {
+    #[default]
+    Off,
+    Skip,
+    Merge,
+}
+
+impl DedupMode {
+    /// A request's mode; empty means "use the server default".
+    pub fn parse(raw: &str) -> Result<Option<Self>, String> {
+        match raw.trim().to_lowercase().as_str() {
+            "" => Ok(None),
+            "off" | "none" => Ok(Some(DedupMode::Off)),
+            "skip" => Ok(Some(DedupMode::Skip)),
+            "merge" => Ok(Some(DedupMode::Merge)),
+            other => Err(format!("unknown dedup mode {:?} (expected off, skip or merge)", other)),
+        }
+    }
+
+    /// PAGI_UPSERT_DEDUP; unknown values keep dedup off.
+    pub fn from_env() -> Self {
+        let raw = std::env::var("PAGI_UPSERT_DEDUP").unwrap_or_default();
+        match Self::parse(&raw) {
+            Ok(mode) => mode.unwrap_or_default(),
+            Err(e) => {
+                eprintln!("[Dedup] PAGI_UPSERT_DEDUP: {}; dedup off", e);
+                DedupMode::Off
+            }
+        }
+    }
+}
+
+/// SHA-256 hex of the point's `content`; None for points without one.
+pub fn content_hash(payload: &HashMap<String, String>) -> Option<String> {
+    let content = payload.get("content")?;
+    Some(format!("{:x}", Sha256::digest(content.as_bytes())))
+}
+
+/// Set (or correct) `content_hash` on a point being upserted.
+pub fn stamp(payload: &mut HashMap<String, String>) {
+    match content_hash(payload) {
+        Some(hash) => {
+            payload.insert(HASH_FIELD.to_string(), hash);
+        }
+        None => {
+            payload.remove(HASH_FIELD);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stamps_content_hash_and_parses_modes() {
+        let mut payload = HashMap::from([
+            ("content".to_string(), "hello".to_string()),
+            (HASH_FIELD.to_string(), "forged".to_string()),
+        ]);
+        stamp(&mut payload);
+        assert_eq!(
+            payload[HASH_FIELD],
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        let mut bare = HashMap::from([(HASH_FIELD.to_string(), "stale".to_string())]);
+        stamp(&mut bare);
+        assert!(bare.is_empty(), "no content, no hash");
+
+        assert_eq!(DedupMode::parse(""), Ok(None));
+        assert_eq!(DedupMode::parse(" Merge "), Ok(Some(DedupMode::Merge)));
+        assert!(DedupMode::parse("replace").is_err());
+    }
+}
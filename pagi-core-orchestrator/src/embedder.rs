@@ -0,0 +1,460 @@
+// Server-side text embedding behind one trait, so SemanticSearch (queries without a query_vector), IngestDocument
+// (chunks) and consolidation embed in the orchestrator. PAGI_EMBEDDER selects the implementation:
+// "local" (in-process BERT-family encoder from PAGI_EMBED_MODEL_DIR: config.json, tokenizer.json,
+// model.safetensors, e.g. all-MiniLM-L6-v2; needs the `local-embed` feature), "bridge" (the bridge's POST /embed
+// at PAGI_EMBED_URL), "openai" (any OpenAI-compatible POST {PAGI_OPENAI_EMBED_URL}/embeddings) or "hash"
+// (deterministic feature hashing; no model, for tests and offline runs). Unset picks local when a model dir is
+// set, else bridge when PAGI_EMBED_URL is, else none. Vectors shorter than a KB's dim are zero-padded by the
+// caller (as embed_and_upsert.py does); longer ones are rejected.
+
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// One vector per input text, in input order; errors are strings (outages are classified by the caller).
+pub type EmbedFuture<'a> = Pin<Box<dyn Future<Output = Result<Vec<Vec<f32>>, String>> + Send + 'a>>;
+
+pub trait Embedder: Send + Sync {
+    /// Implementation name for logs and errors.
+    fn name(&self) -> &'static str;
+    /// Embed `texts` for a KB of `dim`; implementations with a fixed model size may return other lengths.
+    fn embed<'a>(&'a self, texts: &'a [String], dim: usize) -> EmbedFuture<'a>;
+}
+
+/// Model directory from PAGI_EMBED_MODEL_DIR; None (unset or empty) disables local embedding.
+pub fn model_dir_from_env() -> Option<PathBuf> {
+    std::env::var("PAGI_EMBED_MODEL_DIR")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Tokens fed to the encoder; BERT position embeddings stop at 512.
+#[cfg_attr(not(feature = "local-embed"), allow(dead_code))]
+const MAX_TOKENS: usize = 512;
+
+#[cfg(feature = "local-embed")]
+pub struct LocalModel {
+    model: candle_transformers::models::bert::BertModel,
+    tokenizer: tokenizers::Tokenizer,
+    dim: usize,
+}
+
+#[cfg(feature = "local-embed")]
+impl LocalModel {
+    /// Load config, tokenizer and safetensors weights from `dir` onto the CPU (slow; call once at startup).
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        use candle_transformers::models::bert::{BertModel, Config, DTYPE};
+
+        let read = |name: &str| {
+            std::fs::read_to_string(dir.join(name)).map_err(|e| format!("{}: {}", dir.join(name).display(), e))
+        };
+        let config: Config = serde_json::from_str(&read("config.json")?).map_err(|e| format!("config.json: {}", e))?;
+        let tokenizer = tokenizers::Tokenizer::from_file(dir.join("tokenizer.json"))
+            .map_err(|e| format!("tokenizer.json: {}", e))?;
+        // SAFETY: the weights file is mapped read-only and must not be modified while the orchestrator runs.
+        let vb = unsafe {
+            candle_nn::VarBuilder::from_mmaped_safetensors(
+                &[dir.join("model.safetensors")],
+                DTYPE,
+                &candle_core::Device::Cpu,
+            )
+        }
+        .map_err(|e| format!("model.safetensors: {}", e))?;
+        let model = BertModel::load(vb, &config).map_err(|e| format!("load model: {}", e))?;
+        Ok(Self {
+            model,
+            tokenizer,
+            dim: config.hidden_size,
+        })
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Mean-pooled, L2-normalized sentence embedding (blocking; call from spawn_blocking).
+    pub fn embed(&self, text: &str) -> Result<Vec<f32>, String> {
+        use candle_core::{Tensor, D};
+
+        let encoding = self.tokenizer.encode(text, true).map_err(|e| e.to_string())?;
+        let ids: Vec<u32> = encoding.get_ids().iter().take(MAX_TOKENS).copied().collect();
+        let run = || -> candle_core::Result<Vec<f32>> {
+            let input_ids = Tensor::new(ids.as_slice(), &self.model.device)?.unsqueeze(0)?;
+            let token_type_ids = input_ids.zeros_like()?;
+            let hidden = self.model.forward(&input_ids, &token_type_ids, None)?;
+            let pooled = (hidden.sum(1)? / ids.len() as f64)?;
+            let norm = pooled.sqr()?.sum_keepdim(D::Minus1)?.sqrt()?;
+            pooled.broadcast_div(&norm)?.squeeze(0)?.to_vec1::<f32>()
+        };
+        run().map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(not(feature = "local-embed"))]
+pub struct LocalModel;
+
+#[cfg(not(feature = "local-embed"))]
+impl LocalModel {
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        Err(format!(
+            "PAGI_EMBED_MODEL_DIR={} set but the orchestrator was built without the local-embed feature",
+            dir.display()
+        ))
+    }
+
+    pub fn dim(&self) -> usize {
+        0
+    }
+
+    pub fn embed(&self, _text: &str) -> Result<Vec<f32>, String> {
+        Err("local-embed feature disabled".to_string())
+    }
+}
+
+/// The local model as an Embedder; encoding runs on the blocking pool.
+pub struct LocalEmbedder {
+    model: Arc<LocalModel>,
+}
+
+impl Embedder for LocalEmbedder {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn embed<'a>(&'a self, texts: &'a [String], _dim: usize) -> EmbedFuture<'a> {
+        Box::pin(async move {
+            let (model, texts) = (Arc::clone(&self.model), texts.to_vec());
+            tokio::task::spawn_blocking(move || {
+                texts.iter().map(|t| model.embed(t)).collect::<Result<Vec<_>, String>>()
+            })
+            .await
+            .map_err(|e| format!("local embedding panicked: {}", e))?
+        })
+    }
+}
+
+/// Load the local encoder for an L4 of `embedding_dim`; None (logged) when unloadable or when its output size
+/// does not match PAGI_EMBEDDING_DIM.
+fn load_local(dir: &Path, embedding_dim: usize) -> Option<LocalEmbedder> {
+    match LocalModel::load(dir) {
+        Ok(m) if m.dim() == embedding_dim => {
+            eprintln!("[Embedder] local embedding: {} (dim {})", dir.display(), m.dim());
+            Some(LocalEmbedder { model: Arc::new(m) })
+        }
+        Ok(m) => {
+            eprintln!(
+                "[Embedder] {} produces dim {} but PAGI_EMBEDDING_DIM={}; local embedding disabled",
+                dir.display(),
+                m.dim(),
+                embedding_dim
+            );
+            None
+        }
+        Err(e) => {
+            eprintln!("[Embedder] local embedding unavailable: {}", e);
+            None
+        }
+    }
+}
+
+/// The bridge's sentence encoder: POST {"texts": [...]} -> {"vectors": [...]}.
+pub struct BridgeEmbedder {
+    url: String,
+    http: reqwest::Client,
+}
+
+#[derive(Debug, Deserialize)]
+struct BridgeVectors {
+    vectors: Vec<Vec<f32>>,
+}
+
+impl BridgeEmbedder {
+    pub fn new(url: String, timeout: Duration) -> Self {
+        Self {
+            url,
+            http: http_client(timeout),
+        }
+    }
+}
+
+impl Embedder for BridgeEmbedder {
+    fn name(&self) -> &'static str {
+        "bridge"
+    }
+
+    fn embed<'a>(&'a self, texts: &'a [String], _dim: usize) -> EmbedFuture<'a> {
+        Box::pin(async move {
+            let BridgeVectors { vectors } = self
+                .http
+                .post(&self.url)
+                .json(&serde_json::json!({ "texts": texts }))
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| http_err(&self.url, e))?
+                .json()
+                .await
+                .map_err(|e| format!("embed {}: unreadable vectors: {}", self.url, e))?;
+            Ok(vectors)
+        })
+    }
+}
+
+/// An OpenAI-compatible embeddings endpoint (OpenAI, Azure-style proxies, vLLM, Ollama, LiteLLM, ...).
+pub struct OpenAiEmbedder {
+    /// Full /embeddings URL.
+    url: String,
+    model: String,
+    api_key: Option<String>,
+    /// Requested output size (`dimensions`), for models that can shorten their vectors.
+    dimensions: Option<usize>,
+    http: reqwest::Client,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiEmbeddings {
+    data: Vec<OpenAiEmbedding>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiEmbedding {
+    #[serde(default)]
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+impl OpenAiEmbedder {
+    /// `base` is the API root (e.g. https://api.openai.com/v1); "/embeddings" is appended.
+    pub fn new(
+        base: &str,
+        model: String,
+        api_key: Option<String>,
+        dimensions: Option<usize>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            url: format!("{}/embeddings", base.trim_end_matches('/')),
+            model,
+            api_key,
+            dimensions,
+            http: http_client(timeout),
+        }
+    }
+}
+
+impl Embedder for OpenAiEmbedder {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn embed<'a>(&'a self, texts: &'a [String], _dim: usize) -> EmbedFuture<'a> {
+        Box::pin(async move {
+            let mut body = serde_json::json!({ "model": self.model, "input": texts });
+            if let Some(d) = self.dimensions {
+                body["dimensions"] = d.into();
+            }
+            let mut req = self.http.post(&self.url).json(&body);
+            if let Some(key) = &self.api_key {
+                req = req.bearer_auth(key);
+            }
+            let OpenAiEmbeddings { mut data } = req
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| http_err(&self.url, e))?
+                .json()
+                .await
+                .map_err(|e| format!("embed {}: unreadable embeddings: {}", self.url, e))?;
+            data.sort_by_key(|d| d.index);
+            Ok(data.into_iter().map(|d| d.embedding).collect())
+        })
+    }
+}
+
+/// Deterministic feature-hashing embedding (token → signed bucket, L2-normalized); needs no model.
+pub fn hash_embed(text: &str, dim: usize) -> Vec<f32> {
+    let mut v = vec![0f32; dim.max(1)];
+    for token in text
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|t| !t.is_empty())
+    {
+        let digest = Sha256::digest(token.to_lowercase().as_bytes());
+        let bucket = u64::from_be_bytes(digest[..8].try_into().expect("8 bytes")) as usize % v.len();
+        let sign = if digest[8] & 1 == 0 { 1.0 } else { -1.0 };
+        v[bucket] += sign;
+    }
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        v.iter_mut().for_each(|x| *x /= norm);
+    }
+    v
+}
+
+/// hash_embed as an Embedder: same text, same vector, at any KB dim. Only lexical overlap counts.
+pub struct HashEmbedder;
+
+impl Embedder for HashEmbedder {
+    fn name(&self) -> &'static str {
+        "hash"
+    }
+
+    fn embed<'a>(&'a self, texts: &'a [String], dim: usize) -> EmbedFuture<'a> {
+        Box::pin(async move { Ok(texts.iter().map(|t| hash_embed(t, dim)).collect()) })
+    }
+}
+
+fn http_client(timeout: Duration) -> reqwest::Client {
+    reqwest::Client::builder().timeout(timeout).build().unwrap_or_default()
+}
+
+/// Unreachable endpoints and 5xx replies read as outages ("transport error"); 4xx replies (bad key, unknown
+/// model) do not.
+fn http_err(url: &str, e: reqwest::Error) -> String {
+    if e.status().map_or(false, |s| s.is_client_error()) {
+        format!("embed {}: {}", url, e)
+    } else {
+        format!("transport error: embed {}: {}", url, e)
+    }
+}
+
+/// `vectors` checked against `count` texts and zero-padded to `dim`; a vector longer than `dim` is an error.
+pub fn fit(vectors: Vec<Vec<f32>>, count: usize, dim: usize) -> Result<Vec<Vec<f32>>, String> {
+    if vectors.len() != count {
+        return Err(format!("{} vector(s) for {} text(s)", vectors.len(), count));
+    }
+    vectors
+        .into_iter()
+        .map(|mut v| {
+            if v.len() > dim {
+                return Err(format!("embedding dim {} exceeds the KB's {}", v.len(), dim));
+            }
+            v.resize(dim, 0.0);
+            Ok(v)
+        })
+        .collect()
+}
+
+/// The embedder PAGI_EMBEDDER selects for an L4 of `embedding_dim`; None (logged when configured) leaves
+/// searches on the zero vector and IngestDocument unavailable. PAGI_EMBED_TIMEOUT_SECS bounds each remote call
+/// (default 60).
+pub fn from_env(embedding_dim: usize) -> Option<Arc<dyn Embedder>> {
+    let var = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    };
+    let timeout = Duration::from_secs(
+        var("PAGI_EMBED_TIMEOUT_SECS")
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(60)
+            .max(1),
+    );
+    let kind = var("PAGI_EMBEDDER").map(|s| s.to_lowercase()).unwrap_or_else(|| {
+        if model_dir_from_env().is_some() {
+            "local".to_string()
+        } else if var("PAGI_EMBED_URL").is_some() {
+            "bridge".to_string()
+        } else {
+            String::new()
+        }
+    });
+    let embedder: Arc<dyn Embedder> = match kind.as_str() {
+        "" | "none" => return None,
+        "local" => {
+            let Some(dir) = model_dir_from_env() else {
+                eprintln!("[Embedder] PAGI_EMBEDDER=local needs PAGI_EMBED_MODEL_DIR; embedding disabled");
+                return None;
+            };
+            Arc::new(load_local(&dir, embedding_dim)?)
+        }
+        "bridge" => {
+            let Some(url) = var("PAGI_EMBED_URL") else {
+                eprintln!("[Embedder] PAGI_EMBEDDER=bridge needs PAGI_EMBED_URL; embedding disabled");
+                return None;
+            };
+            Arc::new(BridgeEmbedder::new(url, timeout))
+        }
+        "openai" => {
+            let base = var("PAGI_OPENAI_EMBED_URL").unwrap_or_else(|| "https://api.openai.com/v1".to_string());
+            let model = var("PAGI_OPENAI_EMBED_MODEL").unwrap_or_else(|| "text-embedding-3-small".to_string());
+            let dimensions = var("PAGI_OPENAI_EMBED_DIMENSIONS").and_then(|s| s.parse().ok());
+            Arc::new(OpenAiEmbedder::new(
+                &base,
+                model,
+                var("PAGI_OPENAI_API_KEY"),
+                dimensions,
+                timeout,
+            ))
+        }
+        "hash" => Arc::new(HashEmbedder),
+        other => {
+            eprintln!(
+                "[Embedder] unknown PAGI_EMBEDDER {:?} (expected local, bridge, openai or hash); embedding disabled",
+                other
+            );
+            return None;
+        }
+    };
+    eprintln!("[Embedder] server-side embedding: {}", embedder.name());
+    Some(embedder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_model_is_a_load_error() {
+        // Missing files (feature on) or missing feature (feature off): both are load errors, never panics.
+        let err = LocalModel::load(Path::new("/nonexistent/minilm")).err().unwrap();
+        assert!(err.contains("/nonexistent/minilm"), "{}", err);
+    }
+
+    async fn embedded() -> Vec<Vec<f32>> {
+        let texts = vec![
+            "read README".to_string(),
+            "read readme".to_string(),
+            "write code".to_string(),
+        ];
+        HashEmbedder.embed(&texts, 64).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn hash_embedder_returns_one_vector_per_text_at_the_dim() {
+        let vectors = embedded().await;
+        assert_eq!(vectors.len(), 3);
+        assert!(vectors.iter().all(|v| v.len() == 64));
+    }
+
+    #[tokio::test]
+    async fn hash_embeddings_are_case_insensitive_and_distinct() {
+        let vectors = embedded().await;
+        assert_eq!(vectors[0], vectors[1]);
+        assert_ne!(vectors[0], vectors[2]);
+    }
+
+    #[tokio::test]
+    async fn hash_embeddings_are_unit_length() {
+        let norm: f32 = embedded().await[0].iter().map(|x| x * x).sum();
+        assert!((norm - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn short_vectors_are_zero_padded_to_the_kb() {
+        assert_eq!(fit(vec![vec![1.0, 2.0]], 1, 4).unwrap(), [vec![1.0, 2.0, 0.0, 0.0]]);
+    }
+
+    #[test]
+    fn oversized_or_missing_vectors_are_rejected() {
+        assert!(fit(vec![vec![0.0; 8]], 1, 4).unwrap_err().contains("exceeds"));
+        assert!(fit(vec![], 1, 4).is_err(), "one vector per text");
+    }
+}
@@ -0,0 +1,197 @@
+// Execution-environment fingerprint for real L5 actions: python version, bridge commit, allow-list hash and
+// revision, a hash of the PAGI_* configuration, platform. Appended to the ACTION audit line and returned in
+// ActionResponse.metadata so observations can be reproduced and cross-host discrepancies diagnosed; the
+// session journal keeps it per action as the action's environment snapshot.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+use tokio::process::Command;
+
+const UNKNOWN: &str = "unknown";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvFingerprint {
+    pub python_version: String,
+    /// Short HEAD of the git repo enclosing the bridge ("+dirty" when the worktree has changes).
+    pub bridge_commit: String,
+    pub allow_list_hash: String,
+    /// Where the allow-list came from (AllowList::revision: "git:<tree>", "worktree", "sources:<digest>").
+    pub allow_list_revision: String,
+    /// sha256 of the PAGI_* environment (names and values; secrets by name only).
+    pub config_hash: String,
+    /// "<os>-<arch>" of the orchestrator host.
+    pub platform: String,
+}
+
+impl EnvFingerprint {
+    pub fn new(
+        python_version: String,
+        bridge_dir: &Path,
+        allow_list_hash: String,
+        allow_list_revision: String,
+    ) -> Self {
+        Self {
+            python_version,
+            bridge_commit: bridge_commit(bridge_dir),
+            allow_list_hash,
+            allow_list_revision,
+            config_hash: config_hash(std::env::vars()),
+            platform: format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH),
+        }
+    }
+
+    /// ActionResponse.metadata entries (keys prefixed `env.`).
+    pub fn to_metadata(&self) -> HashMap<String, String> {
+        HashMap::from([
+            ("env.python_version".to_string(), self.python_version.clone()),
+            ("env.bridge_commit".to_string(), self.bridge_commit.clone()),
+            ("env.allow_list_hash".to_string(), self.allow_list_hash.clone()),
+            ("env.allow_list_revision".to_string(), self.allow_list_revision.clone()),
+            ("env.config_hash".to_string(), self.config_hash.clone()),
+            ("env.platform".to_string(), self.platform.clone()),
+        ])
+    }
+
+    /// Compact form for the ACTION audit line.
+    pub fn audit_suffix(&self) -> String {
+        format!(
+            "[env python={} bridge={} allow_list={} config={} platform={}]",
+            self.python_version,
+            self.bridge_commit,
+            self.allow_list_hash.get(..12).unwrap_or(&self.allow_list_hash),
+            self.config_hash.get(..12).unwrap_or(&self.config_hash),
+            self.platform
+        )
+    }
+}
+
+/// `python --version` of the interpreter used for dispatch (probe bounded to 2s).
+pub async fn python_version() -> String {
+    let probe = Command::new("python")
+        .arg("--version")
+        .kill_on_drop(true)
+        .output();
+    match tokio::time::timeout(Duration::from_secs(2), probe).await {
+        Ok(Ok(out)) if out.status.success() => {
+            // Python 2 printed the version to stderr.
+            let text = if out.stdout.is_empty() { out.stderr } else { out.stdout };
+            String::from_utf8_lossy(&text)
+                .trim()
+                .trim_start_matches("Python ")
+                .to_string()
+        }
+        _ => UNKNOWN.to_string(),
+    }
+}
+
+/// Hash of the sorted PAGI_* variables. Values of secrets (names ending _KEY, _TOKEN, _SECRET, _PASSWORD) are
+/// left out so rotating a credential is not reported as configuration drift.
+fn config_hash(vars: impl Iterator<Item = (String, String)>) -> String {
+    let mut config: Vec<(String, String)> = vars.filter(|(k, _)| k.starts_with("PAGI_")).collect();
+    config.sort();
+    let mut hasher = Sha256::new();
+    for (name, value) in &config {
+        let secret = ["_KEY", "_TOKEN", "_SECRET", "_PASSWORD"].iter().any(|s| name.ends_with(s));
+        hasher.update(name.as_bytes());
+        hasher.update(b"=");
+        if !secret {
+            hasher.update(value.as_bytes());
+        }
+        hasher.update(b"\n");
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+fn bridge_commit(bridge_dir: &Path) -> String {
+    let Ok(repo) = git2::Repository::discover(bridge_dir) else {
+        return UNKNOWN.to_string();
+    };
+    let Ok(commit) = repo.head().and_then(|h| h.peel_to_commit()) else {
+        return UNKNOWN.to_string();
+    };
+    let mut short = commit.id().to_string();
+    short.truncate(12);
+    let dirty = repo
+        .statuses(Some(git2::StatusOptions::new().include_untracked(false)))
+        .map(|s| !s.is_empty())
+        .unwrap_or(false);
+    if dirty {
+        short.push_str("+dirty");
+    }
+    short
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pagi_fp_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Commits `a.txt` to a fresh repo in `dir`; returns the commit id.
+    fn committed(dir: &Path) -> String {
+        let repo = git2::Repository::init(dir).unwrap();
+        std::fs::write(dir.join("a.txt"), "a").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("a.txt")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("t", "t@t").unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[]).unwrap().to_string()
+    }
+
+    #[test]
+    fn metadata_carries_every_fingerprint_field() {
+        let dir = temp_dir();
+        let meta = EnvFingerprint::new("3.11.4".into(), &dir, "ab".repeat(32), "worktree".into()).to_metadata();
+        assert_eq!(meta["env.python_version"], "3.11.4");
+        assert_eq!(meta["env.allow_list_hash"].len(), 64);
+        assert_eq!(meta["env.allow_list_revision"], "worktree");
+        assert_eq!(meta["env.config_hash"].len(), 64);
+        assert!(meta["env.platform"].contains(std::env::consts::OS));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn audit_suffixes_shorten_the_allow_list_hash() {
+        let dir = temp_dir();
+        let fp = EnvFingerprint::new("3.11.4".into(), &dir, "ab".repeat(32), "worktree".into());
+        assert!(fp.audit_suffix().contains("allow_list=abababababab "));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn bridge_commits_are_short_ids() {
+        let dir = temp_dir();
+        let id = committed(&dir);
+        assert_eq!(bridge_commit(&dir), id[..12]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn bridge_commits_of_modified_trees_are_dirty() {
+        let dir = temp_dir();
+        committed(&dir);
+        std::fs::write(dir.join("a.txt"), "b").unwrap();
+        assert!(bridge_commit(&dir).ends_with("+dirty"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+    #[test]
+    fn config_hash_tracks_pagi_settings_but_not_secrets() {
+        let vars = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        };
+        let base = config_hash(vars(&[("PAGI_MOCK_MODE", "false"), ("PAGI_API_KEY", "k1"), ("HOME", "/a")]).into_iter());
+        let reordered = vars(&[("HOME", "/b"), ("PAGI_API_KEY", "k2"), ("PAGI_MOCK_MODE", "false")]);
+        assert_eq!(config_hash(reordered.into_iter()), base, "order, non-PAGI vars and secret values ignored");
+        let changed = vars(&[("PAGI_MOCK_MODE", "true"), ("PAGI_API_KEY", "k1")]);
+        assert_ne!(config_hash(changed.into_iter()), base);
+    }
+}
@@ -0,0 +1,88 @@
+// Search relevance feedback. RecordSearchFeedback marks hits of a search as useful or not; the counts live on the
+// points themselves (payload `feedback_up` / `feedback_down`), so they follow a point through tiering, snapshots
+// and the archive. A point's learned weight is its smoothed usefulness (up + 1) / (up + down + 2): 0.5 until rated,
+// toward 1.0 for points that keep helping and 0.0 for ones that keep misleading. Searches whose hits carry any
+// feedback blend it into the ranking (decay::blend) with SearchRequest.feedback_weight, else
+// PAGI_SEARCH_FEEDBACK_WEIGHT (default 0.2; 0 disables). Unrated hits all weigh 0.5, so only rated ones move.
+
+use std::collections::HashMap;
+
+use crate::decay;
+use crate::vector_store::ScoredPoint;
+
+/// Payload field counting "useful" marks.
+pub const UP_FIELD: &str = "feedback_up";
+/// Payload field counting "not useful" marks.
+pub const DOWN_FIELD: &str = "feedback_down";
+
+fn count(payload: &HashMap<String, String>, field: &str) -> u64 {
+    payload.get(field).and_then(|v| v.trim().parse().ok()).unwrap_or(0)
+}
+
+/// Smoothed share of useful marks; 0.5 for a point nobody rated.
+pub fn usefulness(payload: &HashMap<String, String>) -> f64 {
+    let (up, down) = (count(payload, UP_FIELD), count(payload, DOWN_FIELD));
+    (up as f64 + 1.0) / ((up + down) as f64 + 2.0)
+}
+
+/// The payload field to merge into a point for one more mark.
+pub fn mark(payload: &HashMap<String, String>, useful: bool) -> HashMap<String, String> {
+    let field = if useful { UP_FIELD } else { DOWN_FIELD };
+    HashMap::from([(field.to_string(), (count(payload, field) + 1).to_string())])
+}
+
+/// Ranking of `points` (best first) with their feedback blended in at `weight`; unchanged when no hit is rated.
+pub fn blend(points: Vec<ScoredPoint>, weight: f32) -> Vec<ScoredPoint> {
+    let rated = |p: &ScoredPoint| p.payload.contains_key(UP_FIELD) || p.payload.contains_key(DOWN_FIELD);
+    if weight <= 0.0 || !points.iter().any(rated) {
+        return points;
+    }
+    decay::blend(points, weight, usefulness)
+}
+
+/// PAGI_SEARCH_FEEDBACK_WEIGHT: share of the ranking given to feedback (default 0.2; 0 disables).
+pub fn weight_from_env() -> f32 {
+    std::env::var("PAGI_SEARCH_FEEDBACK_WEIGHT")
+        .ok()
+        .and_then(|s| s.trim().parse::<f32>().ok())
+        .filter(|w| w.is_finite())
+        .map_or(0.2, |w| w.clamp(0.0, 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_shift_usefulness_and_rated_hits_move_in_the_ranking() {
+        let hit = |id: &str, score: f32, up: u64, down: u64| {
+            let mut payload = HashMap::new();
+            for _ in 0..up {
+                payload.extend(mark(&payload, true));
+            }
+            for _ in 0..down {
+                payload.extend(mark(&payload, false));
+            }
+            ScoredPoint {
+                id: id.to_string(),
+                score,
+                payload,
+            }
+        };
+        assert_eq!(usefulness(&HashMap::new()), 0.5);
+        assert_eq!(usefulness(&hit("a", 0.0, 3, 1).payload), 4.0 / 6.0);
+        assert_eq!(hit("a", 0.0, 2, 0).payload[UP_FIELD], "2");
+
+        let unrated = vec![hit("a", 0.9, 0, 0), hit("b", 0.8, 0, 0)];
+        assert_eq!(blend(unrated.clone(), 0.5), unrated, "nothing rated: scores untouched");
+        let ids = |points: Vec<ScoredPoint>| points.into_iter().map(|p| p.id).collect::<Vec<_>>();
+        let hits = vec![
+            hit("misleading", 0.9, 0, 4),
+            hit("plain", 0.85, 0, 0),
+            hit("helpful", 0.8, 5, 0),
+            hit("tail", 0.5, 0, 0),
+        ];
+        assert_eq!(ids(blend(hits.clone(), 0.0)), ["misleading", "plain", "helpful", "tail"]);
+        assert_eq!(ids(blend(hits, 0.5)), ["helpful", "plain", "misleading", "tail"]);
+    }
+}
@@ -0,0 +1,272 @@
+// Self-heal rate governor: dedups ProposePatch by error fingerprint (one pending patch per fingerprint),
+// caps proposals per component per hour, and backs off exponentially while applies for a fingerprint
+// keep failing — so a flapping failure cannot produce a proposal storm. Both denials carry a PolicyDenial detail
+// with the time until a proposal would be admitted.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use sha2::{Digest, Sha256};
+use tonic::{Code, Status};
+
+use crate::policy_denial;
+use crate::proto::pagi_proto::PolicyDenial;
+
+const WINDOW: Duration = Duration::from_secs(3600);
+/// Trace lines hashed into the fingerprint (head of the trace carries the error identity).
+const FINGERPRINT_LINES: usize = 8;
+
+/// Stable identity for an error: component + first trace lines with volatile tokens
+/// (numbers, hex ids, uuids) masked, so repeats of the same failure collapse together.
+pub fn error_fingerprint(component: &str, error_trace: &str) -> String {
+    let mask = |word: &str| -> String {
+        let hex = word.strip_prefix("0x").unwrap_or(word);
+        if word.chars().any(|c| c.is_ascii_digit()) && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            "#".to_string()
+        } else {
+            word.to_string()
+        }
+    };
+    let mut normalized = String::new();
+    for line in error_trace
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .take(FINGERPRINT_LINES)
+    {
+        let mut word = String::new();
+        for c in line.chars() {
+            if c.is_ascii_alphanumeric() {
+                word.push(c);
+            } else {
+                normalized.push_str(&mask(&word));
+                word.clear();
+                normalized.push(c);
+            }
+        }
+        normalized.push_str(&mask(&word));
+        normalized.push('\n');
+    }
+    let digest = Sha256::digest(format!("{}\n{}", component, normalized).as_bytes());
+    digest.iter().take(8).map(|b| format!("{:02x}", b)).collect()
+}
+
+#[derive(Default)]
+struct Backoff {
+    consecutive_failures: u32,
+    until: Option<Instant>,
+}
+
+pub struct HealGovernor {
+    /// PAGI_HEAL_MAX_PROPOSALS_PER_HOUR (default 20; 0 = unlimited).
+    max_per_hour: usize,
+    /// PAGI_HEAL_BACKOFF_BASE_SECS (default 30; 0 disables backoff).
+    backoff_base: Duration,
+    /// PAGI_HEAL_BACKOFF_MAX_SECS (default 3600).
+    backoff_max: Duration,
+    /// fingerprint -> pending patch_id
+    pending: DashMap<String, String>,
+    /// component -> proposal times within the last hour
+    proposals: DashMap<String, VecDeque<Instant>>,
+    /// fingerprint -> apply-failure backoff
+    backoff: DashMap<String, Backoff>,
+}
+
+/// Admission decision for a new proposal.
+pub enum Admission {
+    /// Propose a new patch (the slot is counted against the hourly cap).
+    Propose,
+    /// A patch for this fingerprint is already pending.
+    Existing(String),
+}
+
+impl HealGovernor {
+    pub fn new(max_per_hour: usize, backoff_base: Duration, backoff_max: Duration) -> Self {
+        Self {
+            max_per_hour,
+            backoff_base,
+            backoff_max,
+            pending: DashMap::new(),
+            proposals: DashMap::new(),
+            backoff: DashMap::new(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let num = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(default)
+        };
+        Self::new(
+            num("PAGI_HEAL_MAX_PROPOSALS_PER_HOUR", 20) as usize,
+            Duration::from_secs(num("PAGI_HEAL_BACKOFF_BASE_SECS", 30)),
+            Duration::from_secs(num("PAGI_HEAL_BACKOFF_MAX_SECS", 3600)),
+        )
+    }
+
+    /// Decide whether `fingerprint` may be proposed now. `is_pending` reports whether a previously
+    /// mapped patch is still awaiting apply (stale mappings are dropped).
+    pub fn admit(
+        &self,
+        component: &str,
+        fingerprint: &str,
+        is_pending: impl Fn(&str) -> bool,
+    ) -> Result<Admission, Status> {
+        if let Some(existing) = self.pending.get(fingerprint).map(|p| p.value().clone()) {
+            if is_pending(&existing) {
+                return Ok(Admission::Existing(existing));
+            }
+            self.pending.remove(fingerprint);
+        }
+        if let Some(b) = self.backoff.get(fingerprint) {
+            if let Some(until) = b.until.filter(|u| *u > Instant::now()) {
+                let retry_after = until.saturating_duration_since(Instant::now()).as_secs_f64().ceil() as u64;
+                let message = format!(
+                    "heal backoff for fingerprint {} after {} failed apply(s); retry in {}s",
+                    fingerprint, b.consecutive_failures, retry_after
+                );
+                let detail = PolicyDenial {
+                    rule: "heal_backoff".to_string(),
+                    subject: fingerprint.to_string(),
+                    setting: "PAGI_HEAL_BACKOFF_BASE_SECS".to_string(),
+                    current: b.consecutive_failures as f64,
+                    retry_after_secs: retry_after as u32,
+                    ..Default::default()
+                };
+                return Err(policy_denial::status(Code::ResourceExhausted, message, detail));
+            }
+        }
+        let mut window = self.proposals.entry(component.to_string()).or_default();
+        let now = Instant::now();
+        while window.front().is_some_and(|t| now.duration_since(*t) >= WINDOW) {
+            window.pop_front();
+        }
+        if self.max_per_hour > 0 && window.len() >= self.max_per_hour {
+            // A slot frees when the oldest proposal leaves the window.
+            let frees_in = window.front().map_or(Duration::ZERO, |t| WINDOW.saturating_sub(now.duration_since(*t)));
+            let detail = PolicyDenial {
+                rule: "heal_rate".to_string(),
+                subject: component.to_string(),
+                setting: "PAGI_HEAL_MAX_PROPOSALS_PER_HOUR".to_string(),
+                current: window.len() as f64,
+                limit: self.max_per_hour as f64,
+                retry_after_secs: frees_in.as_secs_f64().ceil() as u32,
+                ..Default::default()
+            };
+            return Err(policy_denial::status(
+                Code::ResourceExhausted,
+                format!("heal proposal cap reached for {} ({} per hour)", component, self.max_per_hour),
+                detail,
+            ));
+        }
+        window.push_back(now);
+        Ok(Admission::Propose)
+    }
+
+    pub fn register(&self, fingerprint: &str, patch_id: &str) {
+        self.pending.insert(fingerprint.to_string(), patch_id.to_string());
+    }
+
+    /// Cancelled: the fingerprint's slot is freed if `patch_id` still holds it.
+    pub fn release(&self, fingerprint: &str, patch_id: &str) {
+        self.pending.remove_if(fingerprint, |_, pending| pending == patch_id);
+    }
+
+    /// Applied: fingerprint slot freed and its backoff cleared.
+    pub fn record_apply_success(&self, fingerprint: &str) {
+        self.pending.remove(fingerprint);
+        self.backoff.remove(fingerprint);
+    }
+
+    /// Failed apply: free the slot so a fresh patch can be proposed, after base * 2^(n-1) (capped).
+    pub fn record_apply_failure(&self, fingerprint: &str) {
+        self.pending.remove(fingerprint);
+        if self.backoff_base.is_zero() {
+            return;
+        }
+        let mut b = self.backoff.entry(fingerprint.to_string()).or_default();
+        b.consecutive_failures = b.consecutive_failures.saturating_add(1);
+        let factor = 1u32 << (b.consecutive_failures - 1).min(16);
+        let delay = self.backoff_base.saturating_mul(factor).min(self.backoff_max);
+        b.until = Some(Instant::now() + delay);
+        eprintln!(
+            "[HealGovernor] fingerprint {} failed {} time(s); backing off {}s",
+            fingerprint,
+            b.consecutive_failures,
+            delay.as_secs()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_masks_volatile_tokens() {
+        let a = error_fingerprint("rust_core", "panic at src/x.rs:12:5 id=0xdeadbeef");
+        let b = error_fingerprint("rust_core", "panic at src/x.rs:98:1 id=0x1234abcd");
+        assert_eq!(a, b);
+        assert_ne!(a, error_fingerprint("python_skill", "panic at src/x.rs:12:5 id=0xdeadbeef"));
+        assert_ne!(a, error_fingerprint("rust_core", "panic at src/y.rs:12:5"));
+    }
+
+    fn governor() -> HealGovernor {
+        HealGovernor::new(2, Duration::from_secs(60), Duration::from_secs(90))
+    }
+
+    #[test]
+    fn pending_fingerprints_reuse_their_patch() {
+        let gov = governor();
+        assert!(matches!(gov.admit("c", "fp1", |_| true), Ok(Admission::Propose)));
+        gov.register("fp1", "p1");
+        assert!(matches!(gov.admit("c", "fp1", |_| true), Ok(Admission::Existing(id)) if id == "p1"));
+    }
+
+    #[test]
+    fn stale_mappings_propose_again() {
+        let gov = governor();
+        gov.register("fp1", "p1");
+        assert!(matches!(gov.admit("c", "fp1", |_| false), Ok(Admission::Propose)));
+    }
+
+    #[test]
+    fn proposals_are_capped_per_component() {
+        let gov = governor();
+        for fp in ["fp1", "fp2"] {
+            assert!(gov.admit("c", fp, |_| false).is_ok());
+        }
+        let capped = gov.admit("c", "fp3", |_| false).err().unwrap();
+        assert_eq!(capped.code(), tonic::Code::ResourceExhausted);
+        let denial = policy_denial::of(&capped).unwrap();
+        assert_eq!((denial.rule.as_str(), denial.current, denial.limit), ("heal_rate", 2.0, 2.0));
+        assert!((3599..=3600).contains(&denial.retry_after_secs), "{}", denial.retry_after_secs);
+        assert!(matches!(gov.admit("other", "fp3", |_| false), Ok(Admission::Propose)));
+    }
+
+    #[test]
+    fn apply_failures_back_off_up_to_the_cap() {
+        let gov = governor();
+        gov.register("fp", "p");
+        gov.record_apply_failure("fp");
+        let backoff = gov.admit("c", "fp", |_| true).err().unwrap();
+        assert!(backoff.message().contains("retry in 60s"));
+        assert_eq!(policy_denial::of(&backoff).unwrap().retry_after_secs, 60);
+        gov.record_apply_failure("fp");
+        assert_eq!(gov.backoff.get("fp").unwrap().consecutive_failures, 2);
+        let capped = gov.admit("c", "fp", |_| true).err().unwrap();
+        assert!(capped.message().contains("retry in 90s"), "{}", capped.message());
+    }
+
+    #[test]
+    fn an_apply_success_clears_the_backoff() {
+        let gov = governor();
+        gov.register("fp", "p");
+        gov.record_apply_failure("fp");
+        gov.record_apply_success("fp");
+        assert!(gov.admit("c", "fp", |_| true).is_ok());
+    }
+}
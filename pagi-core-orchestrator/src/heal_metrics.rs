@@ -0,0 +1,212 @@
+// Heal-loop lifecycle metrics: per-patch unix-ms timestamps for each self-heal stage (kept in the patch
+// catalog snapshot so they survive restarts) and the GetHealReport aggregation over them: MTTR
+// (detected → applied), HITL approval latency (proposed → approved) and apply success rate.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::proto::pagi_proto::{HealReport, HealStats};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Approved,
+    Verified,
+    Applied,
+    Failed,
+}
+
+/// Stage timestamps for one patch (unix ms); a stage is stamped the first time it is reached, except
+/// `failed_ms`, which tracks the latest failed apply.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HealTimeline {
+    pub component: String,
+    #[serde(default)]
+    pub fingerprint: String,
+    /// ProposePatch received (the error report).
+    pub detected_ms: i64,
+    pub proposed_ms: i64,
+    #[serde(default)]
+    pub approved_ms: Option<i64>,
+    #[serde(default)]
+    pub verified_ms: Option<i64>,
+    #[serde(default)]
+    pub applied_ms: Option<i64>,
+    #[serde(default)]
+    pub failed_ms: Option<i64>,
+    #[serde(default)]
+    pub failures: u32,
+    /// Patch this one revises after its apply test failed (empty for first proposals).
+    #[serde(default)]
+    pub revision_of: String,
+}
+
+impl HealTimeline {
+    pub fn mark(&mut self, stage: Stage, at_ms: i64) {
+        match stage {
+            Stage::Approved => {
+                self.approved_ms.get_or_insert(at_ms);
+            }
+            Stage::Verified => {
+                self.verified_ms.get_or_insert(at_ms);
+            }
+            Stage::Applied => {
+                self.applied_ms.get_or_insert(at_ms);
+            }
+            Stage::Failed => {
+                self.failed_ms = Some(at_ms);
+                self.failures += 1;
+            }
+        }
+    }
+}
+
+fn secs(from_ms: i64, to_ms: i64) -> f64 {
+    (to_ms - from_ms).max(0) as f64 / 1000.0
+}
+
+fn mean(samples: &[f64]) -> f64 {
+    if samples.is_empty() {
+        0.0
+    } else {
+        samples.iter().sum::<f64>() / samples.len() as f64
+    }
+}
+
+fn p50(samples: &mut [f64]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    samples.sort_by(f64::total_cmp);
+    samples[samples.len().div_ceil(2) - 1]
+}
+
+fn stats<'a>(component: &str, timelines: impl Iterator<Item = &'a HealTimeline>) -> HealStats {
+    let mut out = HealStats {
+        component: component.to_string(),
+        ..Default::default()
+    };
+    let (mut mttr, mut approval, mut verify) = (Vec::new(), Vec::new(), Vec::new());
+    for t in timelines {
+        out.proposed += 1;
+        if let Some(at) = t.approved_ms {
+            out.approved += 1;
+            approval.push(secs(t.proposed_ms, at));
+        }
+        if let Some(at) = t.verified_ms {
+            verify.push(secs(t.approved_ms.unwrap_or(t.proposed_ms), at));
+        }
+        match (t.applied_ms, t.failed_ms) {
+            (Some(at), _) => {
+                out.applied += 1;
+                mttr.push(secs(t.detected_ms, at));
+            }
+            (None, Some(_)) => out.failed += 1,
+            (None, None) => out.open += 1,
+        }
+    }
+    let finished = out.applied + out.failed;
+    if finished > 0 {
+        out.success_rate = out.applied as f64 / finished as f64;
+    }
+    out.mttr_mean_secs = mean(&mttr);
+    out.mttr_p50_secs = p50(&mut mttr);
+    out.approval_latency_mean_secs = mean(&approval);
+    out.approval_latency_p50_secs = p50(&mut approval);
+    out.verify_latency_mean_secs = mean(&verify);
+    out
+}
+
+/// Overall and per-component stats for timelines matching `component` (empty: any) detected at or after
+/// `since_ms`.
+pub fn report(timelines: &[HealTimeline], component: &str, since_ms: i64) -> HealReport {
+    let selected: Vec<&HealTimeline> = timelines
+        .iter()
+        .filter(|t| t.detected_ms >= since_ms && (component.is_empty() || t.component == component))
+        .collect();
+    let mut by_component: BTreeMap<&str, Vec<&HealTimeline>> = BTreeMap::new();
+    for t in &selected {
+        by_component.entry(t.component.as_str()).or_default().push(t);
+    }
+    HealReport {
+        overall: Some(stats("", selected.iter().copied())),
+        components: by_component
+            .into_iter()
+            .map(|(c, ts)| stats(c, ts.into_iter()))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeline(component: &str, detected_ms: i64) -> HealTimeline {
+        HealTimeline {
+            component: component.to_string(),
+            detected_ms,
+            proposed_ms: detected_ms + 1_000,
+            ..Default::default()
+        }
+    }
+
+    /// Fast (applied in 20s), retried (applied after a failure, 60s), broken (failed) and still open.
+    fn timelines() -> [HealTimeline; 4] {
+        let mut fast = timeline("rust_core", 0);
+        fast.mark(Stage::Approved, 11_000);
+        fast.mark(Stage::Approved, 99_000);
+        fast.mark(Stage::Verified, 15_000);
+        fast.mark(Stage::Applied, 20_000);
+        let mut retried = timeline("rust_core", 100_000);
+        retried.mark(Stage::Failed, 130_000);
+        retried.mark(Stage::Applied, 160_000);
+        let mut broken = timeline("python_skill", 0);
+        broken.mark(Stage::Failed, 5_000);
+        [fast, retried, broken, timeline("python_skill", 200_000)]
+    }
+
+    fn overall() -> HealStats {
+        report(&timelines(), "", 0).overall.unwrap()
+    }
+
+    #[test]
+    fn outcomes_are_counted_and_open_heals_excluded_from_the_rate() {
+        let overall = overall();
+        assert_eq!((overall.proposed, overall.applied, overall.failed, overall.open), (4, 2, 1, 1));
+        assert!((overall.success_rate - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn mttr_runs_from_detection_to_apply() {
+        let overall = overall();
+        assert_eq!((overall.mttr_mean_secs, overall.mttr_p50_secs), (40.0, 20.0));
+    }
+
+    #[test]
+    fn approval_latency_uses_the_first_approval() {
+        let overall = overall();
+        assert_eq!((overall.approved, overall.approval_latency_mean_secs), (1, 10.0));
+        assert_eq!(overall.verify_latency_mean_secs, 4.0);
+    }
+
+    #[test]
+    fn failures_before_an_apply_are_counted() {
+        assert_eq!(timelines()[1].failures, 1);
+    }
+
+    #[test]
+    fn components_are_reported_in_name_order() {
+        let r = report(&timelines(), "", 0);
+        let names: Vec<&str> = r.components.iter().map(|c| c.component.as_str()).collect();
+        assert_eq!(names, ["python_skill", "rust_core"]);
+        assert_eq!(r.components[0].success_rate, 0.0);
+    }
+
+    #[test]
+    fn reports_filter_by_component_and_detection_time() {
+        let recent = report(&timelines(), "rust_core", 50_000);
+        assert_eq!(recent.overall.unwrap().mttr_mean_secs, 60.0);
+        assert_eq!(recent.components.len(), 1);
+    }
+}
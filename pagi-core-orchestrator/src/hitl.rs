@@ -0,0 +1,387 @@
+// Interactive HITL over the HitlChannel RPC. Reviewer clients hold a bidirectional stream: the orchestrator
+// pushes approval prompts (HITL-gated patches from ProposePatch, real dispatches of skills in
+// PAGI_HITL_ACTION_TIERS) as they are raised — and every still-open prompt when a reviewer connects — and
+// reviewers answer approve / reject / comment. The first approve or reject resolves a prompt and is broadcast to
+// every reviewer; comments are kept on the prompt and broadcast too. Decisions on patches are consulted by
+// ApplyPatch, alongside the request's `approved` and the approve-flag file. With authentication on, only approvers
+// and admins may connect and decisions are recorded under the token's subject, not the client's `reviewer`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use tokio::sync::{mpsc, watch};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::Status;
+
+use crate::clock::Clock;
+use crate::proto::pagi_proto::{HitlClientMessage, HitlPrompt, HitlServerMessage};
+use crate::validate::validate;
+
+/// Messages buffered per reviewer; a reviewer this far behind is disconnected.
+const REVIEWER_BUFFER: usize = 64;
+/// Decisions remembered for later lookup (ApplyPatch, GetApplyStatus).
+const MAX_DECISIONS: usize = 1024;
+
+pub type ReviewerStream = ReceiverStream<Result<HitlServerMessage, Status>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decision {
+    pub approved: bool,
+    pub reviewer: String,
+    pub comment: String,
+}
+
+impl Decision {
+    /// "approved by <reviewer>[: <comment>]".
+    pub fn describe(&self) -> String {
+        let verdict = if self.approved { "approved" } else { "rejected" };
+        if self.comment.is_empty() {
+            format!("{} by {}", verdict, self.reviewer)
+        } else {
+            format!("{} by {}: {}", verdict, self.reviewer, self.comment)
+        }
+    }
+}
+
+struct Open {
+    prompt: HitlPrompt,
+    decided: watch::Sender<Option<Decision>>,
+}
+
+pub struct HitlHub {
+    open: DashMap<String, Open>,
+    decisions: DashMap<String, Decision>,
+    reviewers: DashMap<u64, mpsc::Sender<Result<HitlServerMessage, Status>>>,
+    next_reviewer: AtomicU64,
+    clock: Clock,
+}
+
+impl HitlHub {
+    pub fn new(clock: Clock) -> Self {
+        Self {
+            open: DashMap::new(),
+            decisions: DashMap::new(),
+            reviewers: DashMap::new(),
+            next_reviewer: AtomicU64::new(1),
+            clock,
+        }
+    }
+
+    /// Connected reviewer streams.
+    pub fn reviewers(&self) -> usize {
+        self.reviewers.len()
+    }
+
+    /// Open a prompt (stamped with the raise time) and push it to connected reviewers; no-op while a prompt with
+    /// the same request_id is open.
+    pub fn raise(&self, mut prompt: HitlPrompt) {
+        match self.open.entry(prompt.request_id.clone()) {
+            Entry::Occupied(_) => return,
+            Entry::Vacant(slot) => {
+                prompt.raised_unix = self.clock.now_secs();
+                let (decided, _) = watch::channel(None);
+                slot.insert(Open {
+                    prompt: prompt.clone(),
+                    decided,
+                });
+            }
+        }
+        eprintln!(
+            "[HITL] {} {} awaiting review ({} reviewer(s) connected)",
+            prompt.kind,
+            prompt.request_id,
+            self.reviewers()
+        );
+        self.broadcast(event("prompt", Some(prompt)));
+    }
+
+    /// Wait up to `timeout` for a reviewer's decision on `request_id`; the prompt stays open on timeout.
+    pub async fn wait(&self, request_id: &str, timeout: Duration) -> Option<Decision> {
+        let mut decided = match self.open.get(request_id) {
+            Some(open) => open.decided.subscribe(),
+            None => return self.decision(request_id),
+        };
+        match tokio::time::timeout(timeout, decided.wait_for(Option::is_some)).await {
+            Ok(Ok(decision)) => decision.clone(),
+            _ => self.decision(request_id),
+        }
+    }
+
+    /// True while `request_id` awaits a decision.
+    pub fn is_open(&self, request_id: &str) -> bool {
+        self.open.contains_key(request_id)
+    }
+
+    /// The decision taken on `request_id`, if any.
+    pub fn decision(&self, request_id: &str) -> Option<Decision> {
+        self.decisions.get(request_id).map(|d| d.value().clone())
+    }
+
+    /// Close an open prompt without a decision (timed out, or settled elsewhere) and forget any decision.
+    pub fn withdraw(&self, request_id: &str, reason: &str) {
+        self.decisions.remove(request_id);
+        if self.open.remove(request_id).is_some() {
+            let mut msg = event("withdrawn", None);
+            msg.request_id = request_id.to_string();
+            msg.detail = reason.to_string();
+            self.broadcast(msg);
+        }
+    }
+
+    /// Attach a reviewer: replay the open prompts, then stream new ones; `inbound` carries its answers. `subject`
+    /// is the authenticated caller, recorded as the reviewer of every answer; None (auth off) trusts `reviewer`.
+    pub fn connect<S>(self: &Arc<Self>, subject: Option<String>, inbound: S) -> ReviewerStream
+    where
+        S: Stream<Item = Result<HitlClientMessage, Status>> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel(REVIEWER_BUFFER);
+        let id = self.next_reviewer.fetch_add(1, Ordering::Relaxed);
+        // Registered before the replay, so a prompt raised meanwhile may arrive twice but is never missed.
+        self.reviewers.insert(id, tx.clone());
+        let mut open: Vec<HitlPrompt> = self.open.iter().map(|o| o.prompt.clone()).collect();
+        open.sort_by(|a, b| (a.raised_unix, &a.request_id).cmp(&(b.raised_unix, &b.request_id)));
+        for prompt in open {
+            let _ = tx.try_send(Ok(event("prompt", Some(prompt))));
+        }
+        eprintln!("[HITL] reviewer {} connected ({} open prompt(s))", id, self.open.len());
+
+        let hub = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut inbound = Box::pin(inbound);
+            let fallback_name = format!("reviewer-{}", id);
+            let subject = subject.as_deref();
+            while let Some(msg) = inbound.next().await {
+                let msg = match msg {
+                    Ok(msg) => msg,
+                    Err(status) => {
+                        eprintln!("[HITL] reviewer {} stream error: {}", id, status.message());
+                        break;
+                    }
+                };
+                if let Err(e) = hub.handle(subject, &fallback_name, msg) {
+                    let mut reply = event("error", None);
+                    reply.detail = e;
+                    if tx.send(Ok(reply)).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            hub.reviewers.remove(&id);
+            eprintln!("[HITL] reviewer {} disconnected", id);
+        });
+        ReceiverStream::new(rx)
+    }
+
+    fn handle(&self, subject: Option<&str>, fallback_name: &str, msg: HitlClientMessage) -> Result<(), String> {
+        validate(&msg).map_err(|s| s.message().to_string())?;
+        let reviewer = match (subject, msg.reviewer.trim()) {
+            (Some(subject), _) => subject.to_string(),
+            (None, "") => fallback_name.to_string(),
+            (None, name) => name.to_string(),
+        };
+        let comment = msg.comment.trim().to_string();
+        let no_prompt = || format!("no open prompt {:?}", msg.request_id);
+        match msg.action.trim().to_lowercase().as_str() {
+            // Keep-alive.
+            "" => Ok(()),
+            "comment" => {
+                if comment.is_empty() {
+                    return Err("comment is empty".to_string());
+                }
+                let prompt = {
+                    let mut open = self.open.get_mut(&msg.request_id).ok_or_else(no_prompt)?;
+                    open.prompt.comments.push(format!("{}: {}", reviewer, comment));
+                    open.prompt.clone()
+                };
+                let mut update = event("comment", Some(prompt));
+                update.reviewer = reviewer;
+                update.detail = comment;
+                self.broadcast(update);
+                Ok(())
+            }
+            action @ ("approve" | "reject") => {
+                let (request_id, open) = self.open.remove(&msg.request_id).ok_or_else(no_prompt)?;
+                let decision = Decision {
+                    approved: action == "approve",
+                    reviewer,
+                    comment,
+                };
+                eprintln!("[HITL] {} {} {}", open.prompt.kind, request_id, decision.describe());
+                self.remember(&request_id, decision.clone());
+                let _ = open.decided.send(Some(decision.clone()));
+                let mut resolved = event("resolved", None);
+                resolved.request_id = request_id;
+                resolved.decision = if decision.approved { "approved" } else { "rejected" }.to_string();
+                resolved.reviewer = decision.reviewer;
+                resolved.detail = decision.comment;
+                self.broadcast(resolved);
+                Ok(())
+            }
+            other => Err(format!("unknown action {:?} (expected approve, reject or comment)", other)),
+        }
+    }
+
+    fn remember(&self, request_id: &str, decision: Decision) {
+        if self.decisions.len() >= MAX_DECISIONS {
+            let evict = self.decisions.iter().next().map(|d| d.key().clone());
+            if let Some(key) = evict {
+                self.decisions.remove(&key);
+            }
+        }
+        self.decisions.insert(request_id.to_string(), decision);
+    }
+
+    /// Push to every reviewer; one whose buffer is full or whose stream has closed is dropped.
+    fn broadcast(&self, msg: HitlServerMessage) {
+        let mut dropped = Vec::new();
+        for reviewer in self.reviewers.iter() {
+            if reviewer.value().try_send(Ok(msg.clone())).is_err() {
+                dropped.push(*reviewer.key());
+            }
+        }
+        for id in dropped {
+            eprintln!("[HITL] dropping reviewer {} (stream closed or {} messages behind)", id, REVIEWER_BUFFER);
+            self.reviewers.remove(&id);
+        }
+    }
+}
+
+fn event(name: &str, prompt: Option<HitlPrompt>) -> HitlServerMessage {
+    HitlServerMessage {
+        event: name.to_string(),
+        request_id: prompt.as_ref().map(|p| p.request_id.clone()).unwrap_or_default(),
+        prompt,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt(id: &str) -> HitlPrompt {
+        HitlPrompt {
+            request_id: id.to_string(),
+            kind: "patch".to_string(),
+            subject: "rust_core".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn answer(request_id: &str, action: &str, comment: &str) -> Result<HitlClientMessage, Status> {
+        Ok(HitlClientMessage {
+            request_id: request_id.to_string(),
+            action: action.to_string(),
+            comment: comment.to_string(),
+            reviewer: "alice".to_string(),
+        })
+    }
+
+    async fn next(stream: &mut ReviewerStream) -> HitlServerMessage {
+        tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("message within 5s")
+            .expect("stream open")
+            .expect("ok message")
+    }
+
+    fn hub() -> Arc<HitlHub> {
+        Arc::new(HitlHub::new(Clock::system()))
+    }
+
+    /// A reviewer stream plus the sender for its answers.
+    fn reviewer(hub: &Arc<HitlHub>) -> (mpsc::Sender<Result<HitlClientMessage, Status>>, ReviewerStream) {
+        let (to_hub, inbound) = mpsc::channel(8);
+        (to_hub, hub.connect(None, ReceiverStream::new(inbound)))
+    }
+
+    #[tokio::test]
+    async fn reviewers_get_the_open_prompts_on_connect() {
+        let hub = hub();
+        hub.raise(prompt("p1"));
+        let (_to_hub, mut alice) = reviewer(&hub);
+        let replayed = next(&mut alice).await;
+        assert_eq!((replayed.event.as_str(), replayed.request_id.as_str()), ("prompt", "p1"));
+        let mut bob = hub.connect(None, tokio_stream::pending());
+        assert_eq!(next(&mut bob).await.request_id, "p1", "late reviewers too");
+        assert_eq!(hub.reviewers(), 2);
+    }
+
+    #[tokio::test]
+    async fn new_prompts_reach_every_reviewer() {
+        let hub = hub();
+        let (_to_hub, mut alice) = reviewer(&hub);
+        let mut bob = hub.connect(None, tokio_stream::pending());
+        hub.raise(prompt("p2"));
+        assert_eq!(next(&mut alice).await.request_id, "p2");
+        assert_eq!(next(&mut bob).await.request_id, "p2");
+    }
+
+    #[tokio::test]
+    async fn comments_are_shared_with_the_other_reviewers() {
+        let hub = hub();
+        hub.raise(prompt("p1"));
+        let (to_hub, _alice) = reviewer(&hub);
+        let mut bob = hub.connect(None, tokio_stream::pending());
+        next(&mut bob).await;
+        to_hub.send(answer("p1", "comment", "looks small")).await.unwrap();
+        let comment = next(&mut bob).await;
+        assert_eq!(comment.event, "comment");
+        assert_eq!(comment.prompt.unwrap().comments, ["alice: looks small"]);
+    }
+
+    #[tokio::test]
+    async fn answers_to_unknown_prompts_are_errors_to_the_sender() {
+        let hub = hub();
+        let (to_hub, mut alice) = reviewer(&hub);
+        to_hub.send(answer("nope", "approve", "")).await.unwrap();
+        let error = next(&mut alice).await;
+        assert_eq!(error.event, "error");
+        assert!(error.detail.contains("no open prompt"), "{}", error.detail);
+    }
+
+    #[tokio::test]
+    async fn decisions_resolve_the_prompt_for_everyone() {
+        let hub = hub();
+        hub.raise(prompt("p1"));
+        let (to_hub, _alice) = reviewer(&hub);
+        let mut bob = hub.connect(None, tokio_stream::pending());
+        next(&mut bob).await;
+        to_hub.send(answer("p1", "approve", "ship it")).await.unwrap();
+        let decision = hub.wait("p1", Duration::from_secs(5)).await.unwrap();
+        assert_eq!(decision.describe(), "approved by alice: ship it");
+        let resolved = next(&mut bob).await;
+        assert_eq!((resolved.event.as_str(), resolved.decision.as_str()), ("resolved", "approved"));
+        assert!(!hub.is_open("p1"));
+    }
+
+    #[tokio::test]
+    async fn undecided_prompts_can_be_withdrawn() {
+        let hub = hub();
+        hub.raise(prompt("p2"));
+        let mut bob = hub.connect(None, tokio_stream::pending());
+        next(&mut bob).await;
+        assert!(hub.wait("p2", Duration::from_millis(20)).await.is_none(), "undecided");
+        hub.withdraw("p2", "timed out");
+        assert_eq!(next(&mut bob).await.event, "withdrawn");
+        assert!(hub.decision("p2").is_none());
+    }
+
+    #[tokio::test]
+    async fn authenticated_reviewers_decide_under_their_subject() {
+        let hub = hub();
+        hub.raise(prompt("p1"));
+        let (to_hub, inbound) = mpsc::channel(8);
+        let mut stream = hub.connect(Some("carol@idp".to_string()), ReceiverStream::new(inbound));
+        assert_eq!(next(&mut stream).await.request_id, "p1");
+
+        to_hub.send(answer("p1", "approve", "")).await.unwrap();
+        let decision = hub.wait("p1", Duration::from_secs(5)).await.unwrap();
+        assert_eq!(decision.reviewer, "carol@idp", "the client's reviewer name is ignored");
+        assert_eq!(next(&mut stream).await.reviewer, "carol@idp");
+    }
+}
@@ -0,0 +1,282 @@
+// Hot-memory tracking: read counts for L2 keys (AccessMemory reads) and L4 points (search hits), reported by
+// GetHotMemory. With PAGI_HOT_PIN_THRESHOLD > 0, L4 points read that often are pinned in an in-process cache
+// (PAGI_HOT_CACHE_SIZE entries) so tight reasoning loops can fetch them via AccessMemory layer 4 without Qdrant.
+// L2 reads are sampled (1 in PAGI_HOT_L2_SAMPLE, weighted back up) so the read path rarely takes a shard lock.
+
+use std::cell::Cell;
+use std::cmp::Reverse;
+
+use dashmap::DashMap;
+
+use crate::proto::pagi_proto::{HotEntry, HotMemoryReport};
+use crate::vector_store::ScoredPoint;
+
+/// Keys tracked per layer. Past TRIM_AT counts are halved (single reads forgotten) until back under this, so one
+/// O(n) pass pays for MAX_TRACKED / 4 new keys instead of running on every new key.
+const MAX_TRACKED: usize = 10_000;
+const TRIM_AT: usize = MAX_TRACKED + MAX_TRACKED / 4;
+
+thread_local! {
+    /// Per-thread L2 read counter driving the sample, so sampling itself shares no cache line.
+    static L2_TICK: Cell<u64> = const { Cell::new(0) };
+}
+
+pub struct HotTracker {
+    l2_reads: DashMap<String, u64>,
+    /// (kb, point id) → reads.
+    l4_reads: DashMap<(String, String), u64>,
+    pinned: DashMap<(String, String), ScoredPoint>,
+    /// Reads before an L4 point is pinned; 0 disables pinning.
+    pin_threshold: u64,
+    pin_capacity: usize,
+    /// Every `l2_sample`-th L2 read (scrambled, so periodic access patterns are not aliased) counts `l2_sample`.
+    l2_sample: u64,
+}
+
+impl HotTracker {
+    pub fn new(pin_threshold: u64, pin_capacity: usize) -> Self {
+        Self {
+            l2_reads: DashMap::new(),
+            l4_reads: DashMap::new(),
+            pinned: DashMap::new(),
+            pin_threshold,
+            pin_capacity,
+            l2_sample: 1,
+        }
+    }
+
+    /// Count 1 in `n` L2 reads (0 and 1 count every read); report counts become estimates.
+    pub fn with_l2_sample(mut self, n: u64) -> Self {
+        self.l2_sample = n.max(1);
+        self
+    }
+
+    /// PAGI_HOT_PIN_THRESHOLD (default 0: track only), PAGI_HOT_CACHE_SIZE (default 256) and
+    /// PAGI_HOT_L2_SAMPLE (default 8).
+    pub fn from_env() -> Self {
+        let var = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(default)
+        };
+        Self::new(
+            var("PAGI_HOT_PIN_THRESHOLD", 0),
+            var("PAGI_HOT_CACHE_SIZE", 256) as usize,
+        )
+        .with_l2_sample(var("PAGI_HOT_L2_SAMPLE", 8))
+    }
+
+    fn decay<K: Eq + std::hash::Hash>(reads: &DashMap<K, u64>) {
+        if reads.len() <= TRIM_AT {
+            return;
+        }
+        while reads.len() > MAX_TRACKED {
+            reads.retain(|_, n| {
+                *n /= 2;
+                *n > 0
+            });
+        }
+    }
+
+    /// Count an L2 read (when sampled); the key is only allocated (and the table trimmed) the first time.
+    pub fn record_l2(&self, key: &str) {
+        let weight = self.l2_sample;
+        if weight > 1 {
+            let tick = L2_TICK.with(|t| {
+                let tick = t.get().wrapping_add(1);
+                t.set(tick);
+                tick
+            });
+            // Fibonacci hashing: the high bits of tick * 2^64/phi spread the sampled ticks evenly.
+            if (tick.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 32) % weight != 0 {
+                return;
+            }
+        }
+        if let Some(mut n) = self.l2_reads.get_mut(key) {
+            *n += weight;
+            return;
+        }
+        *self.l2_reads.entry(key.to_string()).or_default() += weight;
+        Self::decay(&self.l2_reads);
+    }
+
+    /// Count a search's hits; pins points crossing the threshold (displacing the coldest pinned point when full).
+    pub fn record_l4(&self, kb: &str, points: &[ScoredPoint]) {
+        for p in points {
+            let key = (kb.to_string(), p.id.clone());
+            let reads = {
+                let mut n = self.l4_reads.entry(key.clone()).or_default();
+                *n += 1;
+                *n
+            };
+            if self.pin_threshold == 0 || reads < self.pin_threshold || self.pin_capacity == 0 {
+                continue;
+            }
+            if let Some(mut pinned) = self.pinned.get_mut(&key) {
+                *pinned = p.clone();
+                continue;
+            }
+            if self.pinned.len() >= self.pin_capacity {
+                let coldest = self
+                    .pinned
+                    .iter()
+                    .map(|e| (self.l4_reads.get(e.key()).map_or(0, |n| *n), e.key().clone()))
+                    .min();
+                match coldest {
+                    Some((cold_reads, cold_key)) if cold_reads < reads => {
+                        self.pinned.remove(&cold_key);
+                    }
+                    _ => continue,
+                }
+            }
+            self.pinned.insert(key, p.clone());
+        }
+        Self::decay(&self.l4_reads);
+    }
+
+    /// Drop pins (and counts) for points rewritten or deleted, so the cache never serves stale payloads.
+    pub fn forget_l4(&self, kb: &str, ids: &[String]) {
+        for id in ids {
+            let key = (kb.to_string(), id.clone());
+            self.pinned.remove(&key);
+            self.l4_reads.remove(&key);
+        }
+    }
+
+    pub fn pinned(&self, kb: &str, id: &str) -> Option<ScoredPoint> {
+        self.pinned.get(&(kb.to_string(), id.to_string())).map(|p| p.clone())
+    }
+
+    /// Top `limit` keys per layer by reads (0 → 20).
+    pub fn report(&self, limit: usize) -> HotMemoryReport {
+        let limit = if limit == 0 { 20 } else { limit };
+        let mut l2: Vec<HotEntry> = self
+            .l2_reads
+            .iter()
+            .map(|e| HotEntry {
+                layer: 2,
+                key: e.key().clone(),
+                reads: *e.value(),
+                ..Default::default()
+            })
+            .collect();
+        let mut l4: Vec<HotEntry> = self
+            .l4_reads
+            .iter()
+            .map(|e| HotEntry {
+                layer: 4,
+                kb_name: e.key().0.clone(),
+                key: e.key().1.clone(),
+                reads: *e.value(),
+                pinned: self.pinned.contains_key(e.key()),
+            })
+            .collect();
+        for entries in [&mut l2, &mut l4] {
+            entries.sort_by_key(|e| (Reverse(e.reads), e.kb_name.clone(), e.key.clone()));
+            entries.truncate(limit);
+        }
+        HotMemoryReport {
+            l2,
+            l4,
+            pinned_count: self.pinned.len() as u32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(id: &str, content: &str) -> ScoredPoint {
+        ScoredPoint {
+            id: id.to_string(),
+            score: 1.0,
+            payload: [("content".to_string(), content.to_string())].into(),
+        }
+    }
+
+    /// Two pin slots, threshold 1: "a" is read twice (pinned), "b" once.
+    fn warmed() -> HotTracker {
+        let tracker = HotTracker::new(2, 1);
+        tracker.record_l4("kb_core", &[hit("a", "alpha")]);
+        tracker.record_l4("kb_core", &[hit("a", "alpha v2"), hit("b", "beta")]);
+        tracker
+    }
+
+    /// `warmed`, then "b" read twice more so it displaces "a".
+    fn displaced() -> HotTracker {
+        let tracker = warmed();
+        tracker.record_l4("kb_core", &[hit("b", "beta")]);
+        tracker.record_l4("kb_core", &[hit("b", "beta")]);
+        tracker
+    }
+
+    #[test]
+    fn points_below_the_threshold_are_not_pinned() {
+        let tracker = HotTracker::new(2, 1);
+        tracker.record_l4("kb_core", &[hit("a", "alpha")]);
+        assert!(tracker.pinned("kb_core", "a").is_none());
+    }
+
+    #[test]
+    fn hot_points_are_pinned_with_their_latest_payload() {
+        assert_eq!(warmed().pinned("kb_core", "a").unwrap().payload["content"], "alpha v2");
+    }
+
+    #[test]
+    fn a_full_cache_is_displaced_only_by_more_read_points() {
+        let tracker = warmed();
+        tracker.record_l4("kb_core", &[hit("b", "beta")]);
+        assert!(tracker.pinned("kb_core", "b").is_none());
+        tracker.record_l4("kb_core", &[hit("b", "beta")]);
+        assert!(tracker.pinned("kb_core", "b").is_some() && tracker.pinned("kb_core", "a").is_none());
+    }
+
+    #[test]
+    fn reports_list_the_most_read_keys_per_layer() {
+        let tracker = displaced();
+        tracker.record_l2("goal");
+        tracker.record_l2("goal");
+        tracker.record_l2("plan");
+        let report = tracker.report(1);
+        assert_eq!((report.l2[0].key.as_str(), report.l2[0].reads), ("goal", 2));
+        assert_eq!((report.l4[0].key.as_str(), report.l4[0].reads, report.l4[0].pinned), ("b", 3, true));
+        assert_eq!(report.pinned_count, 1);
+    }
+
+    #[test]
+    fn forgotten_points_are_unpinned() {
+        let tracker = displaced();
+        tracker.forget_l4("kb_core", &["b".to_string()]);
+        assert!(tracker.pinned("kb_core", "b").is_none());
+    }
+
+    #[test]
+    fn tables_are_trimmed_only_past_the_high_water_mark() {
+        let tracker = HotTracker::new(0, 0);
+        for i in 0..=MAX_TRACKED {
+            tracker.record_l2(&format!("k{}", i));
+        }
+        assert_eq!(tracker.l2_reads.len(), MAX_TRACKED + 1, "no pass per new key");
+        for i in MAX_TRACKED + 1..=TRIM_AT {
+            tracker.record_l2(&format!("k{}", i));
+        }
+        assert!(tracker.l2_reads.len() <= MAX_TRACKED);
+    }
+
+    #[test]
+    fn sampled_l2_reads_are_weighted_back_up() {
+        let tracker = HotTracker::new(0, 0).with_l2_sample(4);
+        for _ in 0..400 {
+            tracker.record_l2("goal");
+        }
+        let reads = tracker.report(1).l2[0].reads;
+        assert!(reads % 4 == 0 && (300..=500).contains(&reads), "{}", reads);
+    }
+
+    #[test]
+    fn a_disabled_tracker_reports_nothing() {
+        assert!(HotTracker::new(0, 8).report(0).l4.is_empty());
+    }
+}
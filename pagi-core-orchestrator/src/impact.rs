@@ -0,0 +1,290 @@
+// Patch impact analysis computed at ProposePatch time so HITL approvers see context, not a bare blob:
+// files/symbols named in the error trace, grep-based references in the target repo, likely-affected
+// tests, and prior patches to the same area (registry patch files + L4 RCA hits).
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::proto::pagi_proto::{PatchImpact as PatchImpactProto, SearchHit};
+
+/// Caps keep the summary reviewable and the scan bounded on large trees.
+const MAX_REFERENCES: usize = 20;
+const MAX_SCANNED_FILES: usize = 2000;
+const MAX_FILE_BYTES: u64 = 512 * 1024;
+pub const SKIP_DIRS: &[&str] = &["target", ".git", ".venv", "node_modules", "__pycache__", "pagi_pb"];
+const SOURCE_EXTS: &[&str] = &["rs", "py"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PatchImpact {
+    pub files: Vec<String>,
+    pub symbols: Vec<String>,
+    /// "path:line" hits for the symbols, relative to the target repo.
+    pub references: Vec<String>,
+    pub affected_tests: Vec<String>,
+    /// Registry patch ids ("patch_<id>") and L4 document ids touching the same area.
+    pub prior_patches: Vec<String>,
+}
+
+impl PatchImpact {
+    pub fn summary(&self) -> String {
+        format!(
+            "{} file(s), {} symbol(s), {} reference(s), {} test file(s), {} prior patch(es)",
+            self.files.len(),
+            self.symbols.len(),
+            self.references.len(),
+            self.affected_tests.len(),
+            self.prior_patches.len()
+        )
+    }
+
+    pub fn to_proto(&self) -> PatchImpactProto {
+        PatchImpactProto {
+            files: self.files.clone(),
+            symbols: self.symbols.clone(),
+            references: self.references.clone(),
+            affected_tests: self.affected_tests.clone(),
+            prior_patches: self.prior_patches.clone(),
+            summary: self.summary(),
+        }
+    }
+}
+
+fn is_ident(s: &str) -> bool {
+    s.len() >= 3
+        && s.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !s.chars().next().is_some_and(|c| c.is_ascii_digit())
+}
+
+/// Source files and function symbols named in a Rust or Python error trace:
+/// `src/x.rs:12:5`, `File "a/b.py", line 3, in run`, and `crate::mod::Type::func` frames.
+pub fn parse_trace(trace: &str) -> (Vec<String>, Vec<String>) {
+    let mut files = BTreeSet::new();
+    let mut symbols = BTreeSet::new();
+    for line in trace.lines() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        for (i, raw) in tokens.iter().enumerate() {
+            let tok = raw.trim_matches(|c: char| matches!(c, '"' | '\'' | ',' | '(' | ')' | '`'));
+            let path = tok.split(':').next().unwrap_or("");
+            if SOURCE_EXTS.iter().any(|ext| path.ends_with(&format!(".{}", ext))) {
+                files.insert(path.trim_start_matches("./").to_string());
+            }
+            // Python frame: `..., line N, in func`
+            if *raw == "in" && i > 0 && tokens[i - 1].ends_with(',') {
+                if let Some(func) = tokens.get(i + 1).filter(|f| is_ident(f)) {
+                    if *func != "<module>" {
+                        symbols.insert(func.to_string());
+                    }
+                }
+            }
+            // Rust frame: `a::b::func` (skip closures / generic noise).
+            if tok.contains("::") {
+                let last = tok.rsplit("::").next().unwrap_or("");
+                let last = last.split('<').next().unwrap_or("");
+                if is_ident(last) && last.chars().next().is_some_and(|c| c.is_lowercase()) {
+                    symbols.insert(last.to_string());
+                }
+            }
+        }
+    }
+    (files.into_iter().collect(), symbols.into_iter().collect())
+}
+
+fn source_files(root: &Path) -> Vec<PathBuf> {
+    let mut out = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if path.is_dir() {
+                if !SKIP_DIRS.contains(&name.as_str()) && !name.starts_with('.') {
+                    stack.push(path);
+                }
+            } else if path
+                .extension()
+                .is_some_and(|e| SOURCE_EXTS.iter().any(|x| e == *x))
+            {
+                out.push(path);
+                if out.len() >= MAX_SCANNED_FILES {
+                    return out;
+                }
+            }
+        }
+    }
+    out.sort();
+    out
+}
+
+fn contains_word(line: &str, word: &str) -> bool {
+    line.match_indices(word).any(|(i, _)| {
+        let before = line[..i].chars().next_back();
+        let after = line[i + word.len()..].chars().next();
+        let boundary = |c: Option<char>| c.is_none_or(|c| !(c.is_alphanumeric() || c == '_'));
+        boundary(before) && boundary(after)
+    })
+}
+
+fn is_test_file(rel: &str, content: &str) -> bool {
+    let name = rel.rsplit('/').next().unwrap_or(rel);
+    rel.split('/').any(|seg| seg == "tests")
+        || name.starts_with("test_")
+        || name.ends_with("_test.py")
+        || content.contains("#[cfg(test)]")
+}
+
+/// Grep `target` for the trace's symbols/files, then match prior registry patches and L4 hits.
+/// Blocking (filesystem walk); call from spawn_blocking.
+pub fn analyze(
+    error_trace: &str,
+    target: &Path,
+    patches_dir: &Path,
+    prior_hits: &[SearchHit],
+) -> PatchImpact {
+    let (files, symbols) = parse_trace(error_trace);
+    let stems: Vec<String> = files
+        .iter()
+        .filter_map(|f| Path::new(f).file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .filter(|s| is_ident(s) && s != "main" && s != "mod" && s != "lib")
+        .collect();
+
+    let mut references = Vec::new();
+    let mut affected_tests = BTreeSet::new();
+    for path in source_files(target) {
+        if std::fs::metadata(&path).map(|m| m.len() > MAX_FILE_BYTES).unwrap_or(true) {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let rel = path
+            .strip_prefix(target)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let mut hit = false;
+        for (n, line) in content.lines().enumerate() {
+            if symbols.iter().any(|s| contains_word(line, s)) {
+                hit = true;
+                if references.len() < MAX_REFERENCES {
+                    references.push(format!("{}:{}", rel, n + 1));
+                }
+            }
+        }
+        let names_touched_file =
+            files.iter().any(|f| f.ends_with(&rel)) || stems.iter().any(|s| contains_word(&content, s));
+        if is_test_file(&rel, &content) && (hit || names_touched_file) {
+            affected_tests.insert(rel);
+        }
+    }
+
+    let mut prior_patches = Vec::new();
+    let needles: Vec<&String> = files.iter().chain(symbols.iter()).collect();
+    if !needles.is_empty() {
+        if let Ok(entries) = std::fs::read_dir(patches_dir) {
+            let mut found: Vec<String> = entries
+                .flatten()
+                .filter(|e| e.path().extension().is_some_and(|x| x == "patch"))
+                .filter(|e| {
+                    std::fs::read_to_string(e.path())
+                        .map(|c| needles.iter().any(|n| c.contains(n.as_str())))
+                        .unwrap_or(false)
+                })
+                .filter_map(|e| e.path().file_stem().map(|s| s.to_string_lossy().into_owned()))
+                .collect();
+            found.sort();
+            prior_patches.extend(found);
+        }
+    }
+    prior_patches.extend(
+        prior_hits
+            .iter()
+            .filter(|h| !h.document_id.is_empty())
+            .map(|h| format!("l4:{}", h.document_id)),
+    );
+
+    PatchImpact {
+        files,
+        symbols,
+        references,
+        affected_tests: affected_tests.into_iter().collect(),
+        prior_patches,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACE: &str = "thread 'main' panicked at src/widget.rs:12:5\n\
+                         0: pagi::widget::Widget::render_frame\n\
+                         Traceback:\n  File \"skills/peek.py\", line 3, in read_chunk\n";
+
+    /// A repo calling `render_frame` from source, a test file and build output, plus one matching prior patch.
+    fn repo() -> PathBuf {
+        let root = std::env::temp_dir().join(format!("pagi_impact_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join("src/widget.rs"), "fn render_frame() {}\nfn other() { render_frame_x(); }\n").unwrap();
+        std::fs::write(
+            root.join("src/lib_tests.rs"),
+            "#[cfg(test)]\nmod tests { fn t() { super::render_frame(); } }\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("target/gen.rs"), "render_frame();\n").unwrap();
+        let patches = root.join("patches");
+        std::fs::create_dir_all(&patches).unwrap();
+        std::fs::write(patches.join("patch_old.patch"), "X-Pagi-Component: rust_core\n+render_frame fix\n").unwrap();
+        std::fs::write(patches.join("patch_other.patch"), "unrelated\n").unwrap();
+        root
+    }
+
+    fn analyzed(root: &Path) -> PatchImpact {
+        let hits = vec![SearchHit {
+            document_id: "doc-1".into(),
+            score: 0.9,
+            content_snippet: String::new(),
+            ..Default::default()
+        }];
+        analyze(TRACE, root, &root.join("patches"), &hits)
+    }
+
+    #[test]
+    fn traces_yield_rust_and_python_files_and_symbols() {
+        let (files, symbols) = parse_trace(TRACE);
+        assert_eq!(files, vec!["skills/peek.py", "src/widget.rs"]);
+        assert_eq!(symbols, vec!["read_chunk", "render_frame"]);
+    }
+
+    #[test]
+    fn references_match_whole_words_outside_build_output() {
+        let root = repo();
+        assert_eq!(analyzed(&root).references, vec!["src/lib_tests.rs:2", "src/widget.rs:1"]);
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn test_files_referencing_a_symbol_are_affected() {
+        let root = repo();
+        assert_eq!(analyzed(&root).affected_tests, vec!["src/lib_tests.rs"]);
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn prior_patches_come_from_the_registry_then_l4() {
+        let root = repo();
+        assert_eq!(analyzed(&root).prior_patches, vec!["patch_old", "l4:doc-1"]);
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn summaries_count_files_symbols_and_references() {
+        let root = repo();
+        assert!(analyzed(&root).to_proto().summary.starts_with("2 file(s), 2 symbol(s), 2 reference(s)"));
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
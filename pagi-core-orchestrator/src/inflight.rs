@@ -0,0 +1,220 @@
+// In-flight RPC registry: tracked handlers are listed (method, reasoning_id, elapsed, child PID) by
+// AdminListRequests and can be cancelled by AbortRequest. Aborting drops the handler future; skill
+// and apply-test subprocesses are spawned kill_on_drop, so the recorded child is killed with it.
+// Completed handlers (ok, error or aborted) record their latency in the shared metrics store.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use dashmap::DashMap;
+use tokio::sync::Notify;
+use tonic::Status;
+
+use crate::metrics::MetricsStore;
+use crate::proto::pagi_proto::{AbortResponse, InFlightRequest, InFlightRequests};
+
+#[derive(Default)]
+struct Slot {
+    /// PID of the subprocess currently serving the request (0 = none).
+    child_pid: AtomicU32,
+    aborted: AtomicBool,
+    abort: Notify,
+}
+
+struct Entry {
+    method: &'static str,
+    reasoning_id: String,
+    started: Instant,
+    slot: Arc<Slot>,
+}
+
+tokio::task_local! {
+    static CURRENT: Arc<Slot>;
+}
+
+/// Record the subprocess serving the current tracked request; no-op outside a tracked handler.
+pub fn record_child_pid(pid: Option<u32>) {
+    let _ = CURRENT.try_with(|slot| slot.child_pid.store(pid.unwrap_or(0), Ordering::Relaxed));
+}
+
+#[derive(Default)]
+pub struct InFlightRegistry {
+    next_id: AtomicU64,
+    entries: DashMap<u64, Entry>,
+    metrics: Arc<MetricsStore>,
+}
+
+/// Removes the entry and records its latency when the handler completes or is dropped.
+struct Deregister<'a> {
+    registry: &'a InFlightRegistry,
+    id: u64,
+    method: &'static str,
+    started: Instant,
+}
+
+impl Drop for Deregister<'_> {
+    fn drop(&mut self) {
+        self.registry.entries.remove(&self.id);
+        self.registry
+            .metrics
+            .record_latency(self.method, self.started.elapsed());
+    }
+}
+
+impl InFlightRegistry {
+    pub fn with_metrics(metrics: Arc<MetricsStore>) -> Self {
+        Self {
+            metrics,
+            ..Self::default()
+        }
+    }
+
+    /// Run `fut` as a tracked request; resolves to Aborted when an operator aborts it.
+    pub async fn run<T, F>(&self, method: &'static str, reasoning_id: &str, fut: F) -> Result<T, Status>
+    where
+        F: Future<Output = Result<T, Status>>,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let slot = Arc::new(Slot::default());
+        let started = Instant::now();
+        self.entries.insert(
+            id,
+            Entry {
+                method,
+                reasoning_id: reasoning_id.to_string(),
+                started,
+                slot: Arc::clone(&slot),
+            },
+        );
+        let _deregister = Deregister {
+            registry: self,
+            id,
+            method,
+            started,
+        };
+        tokio::select! {
+            res = CURRENT.scope(Arc::clone(&slot), fut) => res,
+            _ = slot.abort.notified() => Err(Status::aborted(format!(
+                "request {} ({}) aborted by operator",
+                id, method
+            ))),
+        }
+    }
+
+    /// Snapshot of in-flight requests, oldest first.
+    pub fn list(&self) -> InFlightRequests {
+        let mut requests: Vec<InFlightRequest> = self
+            .entries
+            .iter()
+            .map(|e| InFlightRequest {
+                request_id: *e.key(),
+                method: e.method.to_string(),
+                reasoning_id: e.reasoning_id.clone(),
+                elapsed_ms: e.started.elapsed().as_millis() as u64,
+                child_pid: e.slot.child_pid.load(Ordering::Relaxed),
+                aborting: e.slot.aborted.load(Ordering::Relaxed),
+            })
+            .collect();
+        requests.sort_by_key(|r| r.request_id);
+        InFlightRequests { requests }
+    }
+
+    /// Cancel request_id: its handler returns Aborted and its subprocess (if any) is killed.
+    pub fn abort(&self, request_id: u64) -> AbortResponse {
+        let Some(entry) = self.entries.get(&request_id) else {
+            return AbortResponse {
+                aborted: false,
+                detail: format!("no in-flight request {}", request_id),
+            };
+        };
+        let pid = entry.slot.child_pid.load(Ordering::Relaxed);
+        if entry.slot.aborted.swap(true, Ordering::SeqCst) {
+            return AbortResponse {
+                aborted: false,
+                detail: format!("request {} is already aborting", request_id),
+            };
+        }
+        entry.slot.abort.notify_one();
+        eprintln!(
+            "[InFlight] abort {} {} (reasoning_id={}, child_pid={})",
+            request_id, entry.method, entry.reasoning_id, pid
+        );
+        AbortResponse {
+            aborted: true,
+            detail: if pid == 0 {
+                format!("{} cancelled", entry.method)
+            } else {
+                format!("{} cancelled; child pid {} killed", entry.method, pid)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Starts a 30s ExecuteAction under `registry` and waits until it is listed with its child pid.
+    async fn tracked(registry: &Arc<InFlightRegistry>) -> tokio::task::JoinHandle<Result<(), Status>> {
+        let r = Arc::clone(registry);
+        let handle = tokio::spawn(async move {
+            r.run("ExecuteAction", "trace-1", async {
+                record_child_pid(Some(4242));
+                tokio::time::sleep(Duration::from_secs(30)).await;
+                Ok::<_, Status>(())
+            })
+            .await
+        });
+        while registry.list().requests.first().is_none_or(|r| r.child_pid == 0) {
+            tokio::task::yield_now().await;
+        }
+        handle
+    }
+
+    #[tokio::test]
+    async fn tracked_requests_are_listed_with_method_and_reasoning_id() {
+        let registry = Arc::new(InFlightRegistry::default());
+        let handle = tracked(&registry).await;
+        let listed = registry.list().requests;
+        assert_eq!((listed[0].method.as_str(), listed[0].reasoning_id.as_str()), ("ExecuteAction", "trace-1"));
+        assert_eq!(listed[0].child_pid, 4242);
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn aborting_kills_the_child_and_fails_the_request() {
+        let registry = Arc::new(InFlightRegistry::default());
+        let handle = tracked(&registry).await;
+        let resp = registry.abort(registry.list().requests[0].request_id);
+        assert!(resp.aborted);
+        assert!(resp.detail.contains("4242"));
+        assert_eq!(handle.await.unwrap().unwrap_err().code(), tonic::Code::Aborted);
+    }
+
+    #[tokio::test]
+    async fn aborted_requests_are_unlisted_and_cannot_be_aborted_again() {
+        let registry = Arc::new(InFlightRegistry::default());
+        let handle = tracked(&registry).await;
+        let id = registry.list().requests[0].request_id;
+        registry.abort(id);
+        let _ = handle.await;
+        assert!(registry.list().requests.is_empty());
+        assert!(!registry.abort(id).aborted);
+    }
+
+    #[tokio::test]
+    async fn aborted_requests_still_record_latency() {
+        let registry = Arc::new(InFlightRegistry::default());
+        let handle = tracked(&registry).await;
+        registry.abort(registry.list().requests[0].request_id);
+        let _ = handle.await;
+        let (_, samples) = registry
+            .metrics
+            .percentile("ExecuteAction", 50.0, Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(samples, 1);
+    }
+}
@@ -0,0 +1,361 @@
+// IngestDocument: raw text, markdown or code is chunked here (PAGI_CHUNK_SIZE chars, PAGI_CHUNK_OVERLAP of them
+// repeated from the previous chunk), embedded server-side by the configured embedder (PAGI_EMBEDDER: embedder.rs)
+// and upserted into the KB as one point per chunk. Cuts prefer structural boundaries: headings and paragraph
+// breaks in markdown (never inside a fenced block), top-level items in code, paragraphs in text; lines longer
+// than a chunk are split at whitespace. Point ids derive from document_id and the chunk index, so re-ingesting a document overwrites its
+// chunks (extra chunks of a longer earlier version stay until deleted).
+
+use sha2::{Digest, Sha256};
+use tonic::Status;
+
+use crate::memory_manager::MemoryManager;
+use crate::proto::pagi_proto::{IngestDocumentRequest, IngestDocumentResponse, UpsertRequest, VectorPoint};
+
+const DEFAULT_CHUNK_SIZE: usize = 1000;
+const DEFAULT_CHUNK_OVERLAP: usize = 100;
+/// Extensions ingested as code when the request leaves `format` empty.
+const CODE_EXTENSIONS: &[&str] = &[
+    "c", "cc", "cpp", "cs", "go", "h", "hpp", "java", "js", "jsx", "kt", "proto", "py", "rb", "rs", "scala", "sh",
+    "sql", "swift", "ts", "tsx",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Markdown,
+    Code,
+}
+
+impl Format {
+    /// IngestDocumentRequest.format: "text", "markdown" or "code"; empty guesses from document_id's extension.
+    pub fn parse(raw: &str, document_id: &str) -> Result<Self, Status> {
+        match raw.trim().to_lowercase().as_str() {
+            "text" => Ok(Format::Text),
+            "markdown" | "md" => Ok(Format::Markdown),
+            "code" => Ok(Format::Code),
+            "" => {
+                let ext = document_id
+                    .rsplit_once('.')
+                    .map(|(_, ext)| ext.to_lowercase())
+                    .unwrap_or_default();
+                Ok(match ext.as_str() {
+                    "md" | "markdown" => Format::Markdown,
+                    ext if CODE_EXTENSIONS.contains(&ext) => Format::Code,
+                    _ => Format::Text,
+                })
+            }
+            other => Err(Status::invalid_argument(format!(
+                "unknown format {:?} (expected text, markdown or code)",
+                other
+            ))),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Format::Text => "text",
+            Format::Markdown => "markdown",
+            Format::Code => "code",
+        }
+    }
+}
+
+/// A line (or a piece of an over-long line) and how good a chunk boundary before it is (higher is better).
+struct Unit<'a> {
+    text: &'a str,
+    chars: usize,
+    line: usize,
+    score: u8,
+}
+
+fn heading_level(line: &str) -> Option<usize> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    ((1..=6).contains(&level) && line[level..].starts_with(' ')).then_some(level)
+}
+
+/// `line` in pieces of at most `size` chars, cut after whitespace when that keeps pieces at least half full.
+fn split_long(line: &str, size: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = line;
+    while let Some((limit, _)) = rest.char_indices().nth(size) {
+        let cut = rest[..limit]
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map(|(at, c)| at + c.len_utf8())
+            .filter(|&cut| cut > limit / 2)
+            .unwrap_or(limit);
+        pieces.push(&rest[..cut]);
+        rest = &rest[cut..];
+    }
+    if !rest.is_empty() {
+        pieces.push(rest);
+    }
+    pieces
+}
+
+fn units(text: &str, format: Format, size: usize) -> Vec<Unit<'_>> {
+    let mut units = Vec::new();
+    let (mut prev_blank, mut in_fence) = (true, false);
+    for (i, line) in text.split_inclusive('\n').enumerate() {
+        let trimmed = line.trim();
+        let blank = trimmed.is_empty();
+        let top_level = !line.starts_with(char::is_whitespace);
+        let fence = format == Format::Markdown && (trimmed.starts_with("```") || trimmed.starts_with("~~~"));
+        let score = if in_fence {
+            0
+        } else if blank {
+            1
+        } else {
+            match format {
+                Format::Markdown if heading_level(trimmed).is_some_and(|level| level <= 2) => 4,
+                Format::Markdown if heading_level(trimmed).is_some() => 3,
+                Format::Code if top_level && prev_blank => 3,
+                Format::Code if top_level && !trimmed.starts_with(['}', ')', ']']) => 2,
+                _ if prev_blank => 2,
+                _ => 1,
+            }
+        };
+        if fence {
+            in_fence = !in_fence;
+        }
+        prev_blank = blank;
+        for (k, piece) in split_long(line, size).into_iter().enumerate() {
+            units.push(Unit {
+                text: piece,
+                chars: piece.chars().count(),
+                line: i + 1,
+                score: if k == 0 { score } else { 0 },
+            });
+        }
+    }
+    units
+}
+
+/// One chunk of a document with its 1-based line span.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub text: String,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+/// Chunks of at most `size` chars; each (but the first) starts with up to `overlap` chars of whole lines from
+/// the end of the previous one. Whitespace-only chunks are dropped.
+pub fn chunk(text: &str, format: Format, size: usize, overlap: usize) -> Vec<Chunk> {
+    let units = units(text, format, size.max(1));
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < units.len() {
+        let (mut end, mut len) = (start, 0);
+        while end < units.len() && (end == start || len + units[end].chars <= size) {
+            len += units[end].chars;
+            end += 1;
+        }
+        if end < units.len() {
+            // Cut before the strongest boundary (the latest on ties) that keeps at least a quarter chunk.
+            let (mut best, mut best_score, mut kept) = (end, units[end].score, len);
+            for cut in (start + 1..end).rev() {
+                kept -= units[cut].chars;
+                if kept < size / 4 {
+                    break;
+                }
+                if units[cut].score > best_score {
+                    (best, best_score) = (cut, units[cut].score);
+                }
+            }
+            end = best;
+        }
+        let body: String = units[start..end].iter().map(|u| u.text).collect();
+        if !body.trim().is_empty() {
+            chunks.push(Chunk {
+                text: body.trim_end().to_string(),
+                start_line: units[start].line,
+                end_line: units[end - 1].line,
+            });
+        }
+        if end == units.len() {
+            break;
+        }
+        let (mut next, mut tail) = (end, 0);
+        while next > start + 1 && tail + units[next - 1].chars <= overlap {
+            tail += units[next - 1].chars;
+            next -= 1;
+        }
+        start = next;
+    }
+    chunks
+}
+
+/// Stable point id (UUID-shaped, as Qdrant requires) of chunk `index` of `document_id`.
+pub fn point_id(document_id: &str, index: usize) -> String {
+    let digest = Sha256::digest(format!("{}#{}", document_id, index).as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Uuid::from_bytes(bytes).to_string()
+}
+
+pub struct Ingestor {
+    chunk_size: usize,
+    chunk_overlap: usize,
+    /// Chunks per embedder call.
+    batch: usize,
+}
+
+impl Default for Ingestor {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            chunk_overlap: DEFAULT_CHUNK_OVERLAP,
+            batch: 64,
+        }
+    }
+}
+
+impl Ingestor {
+    /// PAGI_EMBED_BATCH chunks per embedder call (default 64); PAGI_CHUNK_SIZE / PAGI_CHUNK_OVERLAP for
+    /// requests that leave them unset.
+    pub fn from_env() -> Self {
+        let number = |name: &str, default: usize| {
+            std::env::var(name)
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(default)
+        };
+        let defaults = Self::default();
+        Self {
+            chunk_size: number("PAGI_CHUNK_SIZE", defaults.chunk_size).max(1),
+            chunk_overlap: number("PAGI_CHUNK_OVERLAP", defaults.chunk_overlap),
+            batch: number("PAGI_EMBED_BATCH", defaults.batch).max(1),
+        }
+    }
+
+    pub async fn ingest(
+        &self,
+        memory: &MemoryManager,
+        req: IngestDocumentRequest,
+    ) -> Result<IngestDocumentResponse, Status> {
+        let format = Format::parse(&req.format, &req.document_id)?;
+        let size = match req.chunk_size {
+            0 => self.chunk_size,
+            n => n as usize,
+        };
+        let overlap = req.chunk_overlap.map_or(self.chunk_overlap, |n| n as usize);
+        if overlap >= size {
+            return Err(Status::invalid_argument(format!(
+                "chunk_overlap {} must be below chunk_size {}",
+                overlap, size
+            )));
+        }
+        let chunks = chunk(&req.text, format, size, overlap);
+        if chunks.is_empty() {
+            return Err(Status::invalid_argument("document has no text"));
+        }
+        let dim = memory.kb_dim(&req.kb_name);
+        let mut vectors = Vec::with_capacity(chunks.len());
+        for batch in chunks.chunks(self.batch) {
+            let texts: Vec<String> = batch.iter().map(|c| c.text.clone()).collect();
+            vectors.extend(memory.embed_texts(&texts, dim).await?);
+        }
+        let count = chunks.len();
+        let points: Vec<VectorPoint> = chunks
+            .into_iter()
+            .zip(vectors)
+            .enumerate()
+            .map(|(i, (c, vector))| {
+                let mut payload = req.metadata.clone();
+                payload.extend([
+                    ("document_id".to_string(), req.document_id.clone()),
+                    ("chunk".to_string(), format!("{}/{}", i + 1, count)),
+                    ("format".to_string(), format.as_str().to_string()),
+                    ("lines".to_string(), format!("{}-{}", c.start_line, c.end_line)),
+                    ("content".to_string(), c.text),
+                ]);
+                VectorPoint {
+                    id: point_id(&req.document_id, i),
+                    vector,
+                    payload,
+                    ..Default::default()
+                }
+            })
+            .collect();
+        let point_ids = points.iter().map(|p| p.id.clone()).collect();
+        let resp = memory
+            .upsert_vectors(UpsertRequest {
+                kb_name: req.kb_name,
+                points,
+                reasoning_id: req.reasoning_id,
+                dedup: req.dedup,
+                namespace: req.namespace,
+                ttl_seconds: req.ttl_seconds,
+            })
+            .await?;
+        Ok(IngestDocumentResponse {
+            chunks: count as u32,
+            upserted_count: resp.upserted_count,
+            skipped_count: resp.skipped_count,
+            merged_count: resp.merged_count,
+            point_ids,
+            format: format.as_str().to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(chunks: Vec<Chunk>) -> Vec<String> {
+        chunks.into_iter().map(|c| c.text).collect()
+    }
+
+    #[test]
+    fn markdown_cuts_at_headings_and_keeps_fences_whole() {
+        let md = concat!(
+            "# Title\n\nIntro paragraph here.\n\n",
+            "## Setup\n\n```sh\nstep one\n\nstep two\n```\n\n",
+            "## Usage\n\nRun it.\n",
+        );
+        let chunks = chunk(md, Format::Markdown, 60, 0);
+        assert!(chunks.iter().all(|c| c.text.chars().count() <= 60));
+        assert!(chunks[1].text.starts_with("## Setup"), "{:?}", chunks);
+        assert!(chunks.iter().any(|c| c.text.contains("step one\n\nstep two")), "fence kept whole");
+        assert_eq!(chunks.last().unwrap().end_line, 15);
+    }
+
+    #[test]
+    fn code_cuts_between_items() {
+        let code = "use a;\n\nfn one() {\n    body();\n}\n\nfn two() {\n    body();\n}\n";
+        let chunks = chunk(code, Format::Code, 40, 0);
+        assert_eq!(chunks[0].text, "use a;\n\nfn one() {\n    body();\n}");
+        assert_eq!((chunks[1].text.as_str(), chunks[1].start_line), ("fn two() {\n    body();\n}", 7));
+    }
+
+    #[test]
+    fn text_overlaps_by_whole_lines() {
+        let chunks = chunk("alpha\nbeta\ngamma\ndelta\n", Format::Text, 12, 6);
+        assert_eq!(texts(chunks), ["alpha\nbeta", "beta\ngamma", "gamma\ndelta"]);
+    }
+
+    #[test]
+    fn long_lines_split_at_word_boundaries() {
+        let long = "word ".repeat(50);
+        let chunks = chunk(&long, Format::Text, 32, 8);
+        assert!(chunks.iter().all(|c| c.text.chars().count() <= 32 && !c.text.starts_with("ord")));
+        assert!(chunks.len() > 250 / 32);
+    }
+
+    #[test]
+    fn formats_default_from_the_source_extension() {
+        assert_eq!(Format::parse("", "src/lib.rs").unwrap(), Format::Code);
+        assert_eq!(Format::parse("", "README.md").unwrap(), Format::Markdown);
+        assert_eq!(Format::parse("pdf", "x").unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+
+    #[test]
+    fn point_ids_are_stable_uuids_per_source_and_index() {
+        assert_eq!(point_id("README.md", 0), point_id("README.md", 0));
+        assert_ne!(point_id("README.md", 0), point_id("README.md", 1));
+        assert!(uuid::Uuid::parse_str(&point_id("README.md", 0)).is_ok());
+    }
+}
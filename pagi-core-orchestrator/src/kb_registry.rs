@@ -0,0 +1,529 @@
+// KB registry: per-collection vector dimension, distance metric and storage for L4. The 8 built-in KBs
+// default to PAGI_EMBEDDING_DIM / cosine / in-memory; PAGI_KB_FILE (JSON object keyed by KB name) overrides
+// them or adds KBs created at init_kbs. KBs created on demand (ensure_kb) use their entry or the defaults.
+// Entries may also bound a KB's size (`max_points`, `max_age_secs`), enforced by the retention task.
+// `quantization` (or PAGI_KB_QUANTIZATION for every KB) turns on Qdrant scalar/binary quantization; it is
+// (re)applied to existing collections at init_kbs, so changing it migrates a KB without re-indexing.
+// `vectors` ({"code": 768, "text": 1536}) gives a KB named vector spaces instead of one unnamed vector; its
+// points then carry VectorPoint.vectors and searches pick a space with SearchRequest.vector_name.
+// Namespaced requests use one collection per KB and namespace ("kb_core@agent_a", see `namespaced`), shaped
+// and retained like the KB it belongs to.
+// `cold_after_secs` (or PAGI_KB_COLD_AFTER_SECS for every KB) gives each of a KB's collections a cold tier
+// ("kb_core.cold", see `KbSpec::cold_tier`) for points idle that long (tiering.rs).
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+/// Largest vector size Qdrant accepts.
+const MAX_DIM: usize = 65_536;
+/// Joins a KB and a namespace in the name of the namespace's collection.
+pub const NAMESPACE_SEP: char = '@';
+/// Appended to a collection's name for its cold tier; `.` is not allowed in namespaces, so it cannot collide.
+pub const COLD_SUFFIX: &str = ".cold";
+const MAX_NAMESPACE_LEN: usize = 64;
+
+/// Namespaces are ASCII letters, digits, `_` and `-` (1–64 chars) so they embed safely in collection names.
+pub fn check_namespace(namespace: &str) -> Result<(), String> {
+    if namespace.is_empty()
+        || namespace.len() > MAX_NAMESPACE_LEN
+        || !namespace.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(format!(
+            "invalid namespace {:?} (1-{} of A-Z a-z 0-9 _ -)",
+            namespace, MAX_NAMESPACE_LEN
+        ));
+    }
+    Ok(())
+}
+
+/// Collection holding `namespace`'s points of `kb_name`: "<kb_name>@<namespace>", or `kb_name` itself for the
+/// default (empty) namespace. A namespaced request may not name another namespace's collection.
+pub fn namespaced(kb_name: &str, namespace: &str) -> Result<String, String> {
+    if namespace.is_empty() {
+        return Ok(kb_name.to_string());
+    }
+    check_namespace(namespace)?;
+    if kb_name.contains(NAMESPACE_SEP) {
+        return Err(format!(
+            "kb_name {:?} must not contain {:?} when a namespace is set",
+            kb_name, NAMESPACE_SEP
+        ));
+    }
+    Ok(format!("{}{}{}", kb_name, NAMESPACE_SEP, namespace))
+}
+
+/// The KB a (possibly namespaced) collection belongs to.
+pub fn base_kb(collection: &str) -> &str {
+    collection.split_once(NAMESPACE_SEP).map_or(collection, |(kb, _)| kb)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Distance {
+    Cosine,
+    Dot,
+    Euclid,
+}
+
+impl Distance {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Distance::Cosine => "cosine",
+            Distance::Dot => "dot",
+            Distance::Euclid => "euclid",
+        }
+    }
+}
+
+/// Qdrant vector quantization (the memory backend ignores it).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quantization {
+    /// Off; also turns it off on a collection that had it.
+    #[serde(alias = "none")]
+    Disabled,
+    /// int8 per dimension: ~4x less vector RAM (originals stay on disk for rescoring).
+    Scalar,
+    /// 1 bit per dimension: ~32x; suited to high-dim embeddings (Qdrant >= 1.5).
+    Binary,
+}
+
+impl Quantization {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Quantization::Disabled => "disabled",
+            Quantization::Scalar => "scalar",
+            Quantization::Binary => "binary",
+        }
+    }
+
+    /// PAGI_KB_QUANTIZATION value; empty leaves collections as they are.
+    fn parse(raw: &str) -> Result<Option<Self>, String> {
+        match raw.trim().to_lowercase().as_str() {
+            "" => Ok(None),
+            "none" | "disabled" => Ok(Some(Quantization::Disabled)),
+            "scalar" => Ok(Some(Quantization::Scalar)),
+            "binary" => Ok(Some(Quantization::Binary)),
+            other => Err(format!("unknown quantization {:?} (expected scalar, binary or none)", other)),
+        }
+    }
+}
+
+/// Size bounds for a KB; unset fields are unbounded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Retention {
+    /// Keep at most this many points, dropping the oldest (by the `at` payload field) first.
+    pub max_points: Option<usize>,
+    /// Drop points whose `at` is older than this.
+    pub max_age_secs: Option<u64>,
+}
+
+impl Retention {
+    pub fn is_bounded(&self) -> bool {
+        self.max_points.is_some() || self.max_age_secs.is_some()
+    }
+
+    /// Ids to prune from `points` (id, `at` unix secs) at `now`: (expired by age, oldest over max_points).
+    /// Points without `at` never expire but count as oldest when over the cap.
+    pub fn plan(&self, mut points: Vec<(String, Option<i64>)>, now: i64) -> (Vec<String>, Vec<String>) {
+        let mut by_age = Vec::new();
+        if let Some(max_age) = self.max_age_secs {
+            let cutoff = now.saturating_sub(max_age.min(i64::MAX as u64) as i64);
+            let (expired, kept): (Vec<_>, Vec<_>) = points.into_iter().partition(|(_, at)| at.is_some_and(|at| at < cutoff));
+            by_age = expired.into_iter().map(|(id, _)| id).collect();
+            points = kept;
+        }
+        let mut by_count = Vec::new();
+        if let Some(max_points) = self.max_points.filter(|&max| points.len() > max) {
+            points.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+            let excess = points.len() - max_points;
+            by_count = points.into_iter().take(excess).map(|(id, _)| id).collect();
+        }
+        (by_age, by_count)
+    }
+}
+
+/// Vector layout of a collection: one unnamed vector of `dim`, or named spaces (name → dim, `dim` 0).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shape {
+    pub dim: usize,
+    pub vectors: BTreeMap<String, usize>,
+    pub distance: Distance,
+}
+
+impl Shape {
+    pub fn single(dim: usize, distance: Distance) -> Self {
+        Self {
+            dim,
+            vectors: BTreeMap::new(),
+            distance,
+        }
+    }
+
+    /// "<dim>/<distance>", or "<name>:<dim>+.../<distance>" for named spaces.
+    pub fn describe(&self) -> String {
+        if self.vectors.is_empty() {
+            return format!("{}/{}", self.dim, self.distance.as_str());
+        }
+        let spaces: Vec<String> = self.vectors.iter().map(|(name, dim)| format!("{}:{}", name, dim)).collect();
+        format!("{}/{}", spaces.join("+"), self.distance.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KbSpec {
+    pub name: String,
+    pub dim: usize,
+    pub distance: Distance,
+    /// Keep vectors and payloads on disk (memory-mapped) rather than in RAM (Qdrant only).
+    pub on_disk: bool,
+    pub retention: Retention,
+    /// None leaves the collection's quantization untouched.
+    pub quantization: Option<Quantization>,
+    /// Named vector spaces (name → dim); empty for a single unnamed vector of `dim`.
+    pub vectors: BTreeMap<String, usize>,
+    /// Points neither written nor read for this long move to the cold tier; None keeps a single tier.
+    pub cold_after_secs: Option<u64>,
+}
+
+impl KbSpec {
+    /// Spec of this collection's cold tier (None when untiered): same layout and retention, vectors and payloads
+    /// on disk, scalar-quantized unless the KB sets a quantization.
+    pub fn cold_tier(&self) -> Option<KbSpec> {
+        self.cold_after_secs?;
+        Some(KbSpec {
+            name: format!("{}{}", self.name, COLD_SUFFIX),
+            on_disk: true,
+            quantization: Some(self.quantization.unwrap_or(Quantization::Scalar)),
+            cold_after_secs: None,
+            ..self.clone()
+        })
+    }
+
+    /// Layout the collection must have.
+    pub fn layout(&self) -> Shape {
+        if self.vectors.is_empty() {
+            return Shape::single(self.dim, self.distance);
+        }
+        Shape {
+            dim: 0,
+            vectors: self.vectors.clone(),
+            distance: self.distance,
+        }
+    }
+
+    /// Layout as compared against existing collections ("<dim>/<distance>" for unnamed vectors).
+    pub fn shape(&self) -> String {
+        self.layout().describe()
+    }
+
+    /// Dim of the vector space a search or point addresses: `name` must be empty for unnamed KBs and one of
+    /// the spaces for named ones.
+    pub fn space_dim(&self, name: &str) -> Result<usize, String> {
+        match (self.vectors.is_empty(), name.is_empty()) {
+            (true, true) => Ok(self.dim),
+            (true, false) => Err(format!("{} has no named vectors (got vector_name {:?})", self.name, name)),
+            (false, _) => self.vectors.get(name).copied().ok_or_else(|| {
+                let names: Vec<&str> = self.vectors.keys().map(String::as_str).collect();
+                format!("{} has named vectors {:?}; vector_name {:?} is not one", self.name, names, name)
+            }),
+        }
+    }
+}
+
+/// One PAGI_KB_FILE entry; omitted fields keep the default.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KbEntry {
+    dim: Option<usize>,
+    distance: Option<Distance>,
+    on_disk: Option<bool>,
+    max_points: Option<usize>,
+    max_age_secs: Option<u64>,
+    quantization: Option<Quantization>,
+    vectors: Option<BTreeMap<String, usize>>,
+    /// 0 turns tiering off for the KB when PAGI_KB_COLD_AFTER_SECS is set.
+    cold_after_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct KbRegistry {
+    default_dim: usize,
+    /// PAGI_KB_QUANTIZATION, for KBs whose entry does not set one.
+    default_quantization: Option<Quantization>,
+    /// PAGI_KB_COLD_AFTER_SECS, for KBs whose entry does not set one.
+    default_cold_after: Option<u64>,
+    /// KBs created by init_kbs.
+    kbs: BTreeMap<String, KbSpec>,
+}
+
+impl KbRegistry {
+    pub const BUILTIN: [&'static str; 8] = [
+        "kb_core", "kb_skills", "kb_1", "kb_2", "kb_3", "kb_4", "kb_5", "kb_6",
+    ];
+
+    pub fn builtin(default_dim: usize) -> Self {
+        Self::with_defaults(default_dim, None, None)
+    }
+
+    fn with_defaults(
+        default_dim: usize,
+        default_quantization: Option<Quantization>,
+        default_cold_after: Option<u64>,
+    ) -> Self {
+        let mut registry = Self {
+            default_dim,
+            default_quantization,
+            default_cold_after,
+            kbs: BTreeMap::new(),
+        };
+        for name in Self::BUILTIN {
+            registry.kbs.insert(name.to_string(), registry.default_spec(name));
+        }
+        registry
+    }
+
+    /// Built-ins plus PAGI_KB_FILE. Unlike other config files an invalid one is an error: collections
+    /// created with the wrong shape cannot be fixed without re-indexing.
+    pub fn from_env(default_dim: usize) -> Result<Self, String> {
+        let quantization = Quantization::parse(&std::env::var("PAGI_KB_QUANTIZATION").unwrap_or_default())
+            .map_err(|e| format!("PAGI_KB_QUANTIZATION: {}", e))?;
+        let cold_after = match std::env::var("PAGI_KB_COLD_AFTER_SECS").unwrap_or_default().trim() {
+            "" => None,
+            raw => Some(
+                raw.parse::<u64>()
+                    .map_err(|e| format!("PAGI_KB_COLD_AFTER_SECS {:?}: {}", raw, e))?,
+            ),
+        };
+        let mut registry = Self::with_defaults(default_dim, quantization, cold_after.filter(|&s| s > 0));
+        let Some(path) = std::env::var("PAGI_KB_FILE")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+        else {
+            return Ok(registry);
+        };
+        let raw = std::fs::read_to_string(&path).map_err(|e| format!("PAGI_KB_FILE {}: {}", path, e))?;
+        registry
+            .merge_json(&raw)
+            .map_err(|e| format!("PAGI_KB_FILE {}: {}", path, e))?;
+        eprintln!("[KbRegistry] {} KB(s) from {}", registry.kbs.len(), path);
+        Ok(registry)
+    }
+
+    fn default_spec(&self, name: &str) -> KbSpec {
+        KbSpec {
+            name: name.to_string(),
+            dim: self.default_dim,
+            distance: Distance::Cosine,
+            on_disk: false,
+            retention: Retention::default(),
+            quantization: self.default_quantization,
+            vectors: BTreeMap::new(),
+            cold_after_secs: self.default_cold_after,
+        }
+    }
+
+    fn merge_json(&mut self, raw: &str) -> Result<(), String> {
+        let entries: BTreeMap<String, KbEntry> = serde_json::from_str(raw).map_err(|e| e.to_string())?;
+        let mut merged = self.kbs.clone();
+        for (name, entry) in entries {
+            if name.contains(NAMESPACE_SEP) {
+                return Err(format!("{}: KB names may not contain {:?}", name, NAMESPACE_SEP));
+            }
+            if name.ends_with(COLD_SUFFIX) {
+                return Err(format!("{}: KB names may not end in {:?} (cold tiers)", name, COLD_SUFFIX));
+            }
+            let base = merged.get(&name).cloned().unwrap_or_else(|| self.default_spec(&name));
+            let spec = KbSpec {
+                dim: entry.dim.unwrap_or(base.dim),
+                distance: entry.distance.unwrap_or(base.distance),
+                on_disk: entry.on_disk.unwrap_or(base.on_disk),
+                retention: Retention {
+                    max_points: entry.max_points.or(base.retention.max_points),
+                    max_age_secs: entry.max_age_secs.or(base.retention.max_age_secs),
+                },
+                quantization: entry.quantization.or(base.quantization),
+                vectors: entry.vectors.unwrap_or_else(|| base.vectors.clone()),
+                cold_after_secs: match entry.cold_after_secs {
+                    Some(0) => None,
+                    Some(secs) => Some(secs),
+                    None => base.cold_after_secs,
+                },
+                ..base
+            };
+            if !(1..=MAX_DIM).contains(&spec.dim) {
+                return Err(format!("{}: dim {} outside 1..={}", name, spec.dim, MAX_DIM));
+            }
+            for (space, &dim) in &spec.vectors {
+                if space.is_empty() || !space.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                    return Err(format!("{}: invalid vector name {:?}", name, space));
+                }
+                if !(1..=MAX_DIM).contains(&dim) {
+                    return Err(format!("{}: vector {} dim {} outside 1..={}", name, space, dim, MAX_DIM));
+                }
+            }
+            merged.insert(name, spec);
+        }
+        self.kbs = merged;
+        Ok(())
+    }
+
+    /// Spec for `name`: its configured entry, else default dim / cosine / in-memory. A namespace's collection
+    /// gets its KB's spec under the collection name.
+    pub fn get(&self, name: &str) -> KbSpec {
+        let base = base_kb(name);
+        let spec = self.kbs.get(base).cloned().unwrap_or_else(|| self.default_spec(base));
+        KbSpec {
+            name: name.to_string(),
+            ..spec
+        }
+    }
+
+    /// KBs created by init_kbs (the only ones the retention task visits).
+    pub fn specs(&self) -> impl Iterator<Item = &KbSpec> {
+        self.kbs.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The builtin registry with kb_core narrowed to 768 dims and a bounded, on-disk kb_logs added.
+    fn overridden() -> KbRegistry {
+        let mut registry = KbRegistry::builtin(1536);
+        assert_eq!(registry.get("kb_core").shape(), "1536/cosine");
+        registry
+            .merge_json(r#"{"kb_core": {"dim": 768}, "kb_logs": {"distance": "dot", "on_disk": true, "max_points": 100}}"#)
+            .unwrap();
+        registry
+    }
+
+    #[test]
+    fn file_entries_override_builtin_kbs() {
+        let registry = overridden();
+        assert_eq!(registry.get("kb_core").shape(), "768/cosine");
+        assert!(!registry.get("kb_core").retention.is_bounded());
+    }
+
+    #[test]
+    fn file_entries_add_kbs_created_at_init() {
+        let registry = overridden();
+        let logs = registry.get("kb_logs");
+        assert_eq!((logs.shape().as_str(), logs.on_disk), ("1536/dot", true));
+        assert_eq!((logs.retention.max_points, logs.retention.max_age_secs), (Some(100), None));
+        assert_eq!(registry.specs().count(), 9);
+    }
+
+    #[test]
+    fn unlisted_kbs_use_defaults() {
+        assert_eq!(overridden().get("kb_provenance").shape(), "1536/cosine");
+    }
+
+    #[test]
+    fn invalid_entries_leave_the_registry_unchanged() {
+        let mut registry = overridden();
+        assert!(registry.merge_json(r#"{"kb_1": {"dim": 0}}"#).unwrap_err().contains("outside"));
+        assert!(registry.merge_json(r#"{"kb_1": {"distance": "manhattan"}}"#).is_err());
+        assert_eq!(registry.get("kb_1").dim, 1536);
+    }
+
+    #[test]
+    fn namespaced_collections_inherit_their_kb() {
+        let mut registry = KbRegistry::builtin(1536);
+        registry.merge_json(r#"{"kb_logs": {"dim": 384, "max_points": 10}}"#).unwrap();
+        assert_eq!(namespaced("kb_logs", "").unwrap(), "kb_logs");
+        let collection = namespaced("kb_logs", "agent-a").unwrap();
+        assert_eq!(collection, "kb_logs@agent-a");
+        assert_eq!(base_kb(&collection), "kb_logs");
+        let spec = registry.get(&collection);
+        assert_eq!((spec.name.as_str(), spec.dim, spec.retention.max_points), ("kb_logs@agent-a", 384, Some(10)));
+    }
+
+    #[test]
+    fn namespaces_are_validated() {
+        assert!(namespaced("kb_logs@agent-b", "agent-a").is_err(), "no reaching into another namespace");
+        assert!(namespaced("kb_logs", "a/b").is_err());
+        assert!(namespaced("kb_logs", &"a".repeat(65)).is_err());
+        assert!(KbRegistry::builtin(1536).merge_json(r#"{"kb@x": {}}"#).is_err());
+    }
+
+    #[test]
+    fn quantization_defaults_from_env_value_and_entries_override() {
+        let mut registry = KbRegistry::with_defaults(1536, Quantization::parse("scalar").unwrap(), None);
+        registry
+            .merge_json(r#"{"kb_skills": {"quantization": "binary"}, "kb_1": {"quantization": "none"}}"#)
+            .unwrap();
+        assert_eq!(registry.get("kb_core").quantization, Some(Quantization::Scalar));
+        assert_eq!(registry.get("kb_skills").quantization, Some(Quantization::Binary));
+        assert_eq!(registry.get("kb_1").quantization, Some(Quantization::Disabled));
+    }
+
+    #[test]
+    fn quantization_is_untouched_by_default() {
+        assert_eq!(KbRegistry::builtin(1536).get("kb_core").quantization, None);
+        assert_eq!(Quantization::parse(" "), Ok(None));
+        assert!(Quantization::parse("pq").is_err());
+    }
+
+    #[test]
+    fn named_vector_spaces_need_a_vector_name() {
+        let mut registry = KbRegistry::builtin(1536);
+        registry
+            .merge_json(r#"{"kb_code": {"vectors": {"text": 1536, "code": 768}}}"#)
+            .unwrap();
+        let code = registry.get("kb_code");
+        assert_eq!(code.shape(), "code:768+text:1536/cosine");
+        assert_eq!(code.space_dim("code"), Ok(768));
+        assert!(code.space_dim("").is_err());
+        assert_eq!(registry.get("kb_core").space_dim(""), Ok(1536));
+        assert!(registry.get("kb_core").space_dim("code").is_err());
+    }
+
+    #[test]
+    fn invalid_vector_spaces_are_rejected() {
+        let mut registry = KbRegistry::builtin(1536);
+        assert!(registry.merge_json(r#"{"kb_x": {"vectors": {"a b": 8}}}"#).is_err());
+        assert!(registry.merge_json(r#"{"kb_x": {"vectors": {"big": 70000}}}"#).is_err());
+    }
+
+    #[test]
+    fn cold_tiers_go_to_disk_scalar_quantized() {
+        let registry = KbRegistry::with_defaults(1536, None, Some(86_400));
+        let cold = registry.get(&namespaced("kb_core", "agent-a").unwrap()).cold_tier().unwrap();
+        assert_eq!(cold.name, "kb_core@agent-a.cold");
+        assert_eq!((cold.on_disk, cold.quantization, cold.cold_after_secs), (true, Some(Quantization::Scalar), None));
+        assert_eq!(cold.shape(), "1536/cosine");
+    }
+
+    #[test]
+    fn entries_can_opt_out_of_or_tune_their_cold_tier() {
+        let mut registry = KbRegistry::with_defaults(1536, None, Some(86_400));
+        let entries = r#"{"kb_1": {"cold_after_secs": 0}, "kb_2": {"cold_after_secs": 60, "quantization": "binary"}}"#;
+        registry.merge_json(entries).unwrap();
+        assert!(registry.get("kb_1").cold_tier().is_none());
+        assert_eq!(registry.get("kb_2").cold_tier().unwrap().quantization, Some(Quantization::Binary));
+        assert!(registry.merge_json(r#"{"kb_x.cold": {}}"#).is_err());
+    }
+
+    #[test]
+    fn kbs_are_untiered_by_default() {
+        assert!(KbRegistry::builtin(1536).get("kb_core").cold_tier().is_none());
+    }
+
+    #[test]
+    fn retention_plans_age_then_count() {
+        let points = |spec: &[(&str, Option<i64>)]| spec.iter().map(|(id, at)| (id.to_string(), *at)).collect();
+        let pts = points(&[("a", Some(100)), ("b", Some(900)), ("c", None), ("d", Some(500)), ("e", Some(950))]);
+        let retention = Retention {
+            max_points: Some(2),
+            max_age_secs: Some(600),
+        };
+        // Cutoff 400: "a" expires; of the remaining 4, "c" (no timestamp) and "d" are oldest.
+        assert_eq!(retention.plan(pts, 1000), (vec!["a".to_string()], vec!["c".to_string(), "d".to_string()]));
+        let unbounded = Retention::default().plan(points(&[("a", Some(0))]), 1000);
+        assert_eq!(unbounded, (vec![], vec![]));
+    }
+}
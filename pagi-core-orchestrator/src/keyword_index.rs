@@ -0,0 +1,261 @@
+// In-process BM25 keyword index over L4 payload text, fed by every UpsertVectors/DeleteVectors. Hybrid
+// SemanticSearch fuses it with vector hits by reciprocal rank fusion, so exact identifiers and error strings
+// that cosine similarity misses still surface. Not persisted: covers points written since startup.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+
+use crate::proto::pagi_proto::{SearchFilter, VectorPoint};
+use crate::vector_store::{filter_matches, ScoredPoint};
+
+/// Payload fields whose text is indexed.
+const TEXT_FIELDS: [&str; 2] = ["content", "snippet"];
+const K1: f32 = 1.2;
+const B: f32 = 0.75;
+/// RRF rank constant (Cormack et al.); damps the weight of the very top ranks.
+const RRF_K: f32 = 60.0;
+
+/// Lowercased identifier-like tokens: runs of alphanumerics and `_` (keeps `snake_case` names and error codes whole).
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|t| !t.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+struct Doc {
+    terms: HashMap<String, u32>,
+    len: u32,
+    payload: HashMap<String, String>,
+}
+
+#[derive(Default)]
+struct Collection {
+    docs: HashMap<String, Doc>,
+    postings: HashMap<String, HashSet<String>>,
+    total_len: u64,
+}
+
+impl Collection {
+    fn remove(&mut self, id: &str) -> bool {
+        let Some(doc) = self.docs.remove(id) else {
+            return false;
+        };
+        self.total_len -= doc.len as u64;
+        for term in doc.terms.keys() {
+            if let Some(ids) = self.postings.get_mut(term) {
+                ids.remove(id);
+                if ids.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+        true
+    }
+
+    fn insert(&mut self, point: &VectorPoint) {
+        self.remove(&point.id);
+        let mut terms: HashMap<String, u32> = HashMap::new();
+        for field in TEXT_FIELDS {
+            for token in point.payload.get(field).map(|t| tokenize(t)).unwrap_or_default() {
+                *terms.entry(token).or_default() += 1;
+            }
+        }
+        let len = terms.values().sum::<u32>();
+        for term in terms.keys() {
+            self.postings.entry(term.clone()).or_default().insert(point.id.clone());
+        }
+        self.total_len += len as u64;
+        self.docs.insert(
+            point.id.clone(),
+            Doc {
+                terms,
+                len,
+                payload: point.payload.clone(),
+            },
+        );
+    }
+
+    fn search(&self, query: &str, limit: usize, filter: Option<&SearchFilter>) -> Vec<ScoredPoint> {
+        let n = self.docs.len() as f32;
+        if n == 0.0 {
+            return Vec::new();
+        }
+        let avg_len = self.total_len as f32 / n;
+        let mut scores: HashMap<&str, f32> = HashMap::new();
+        for term in tokenize(query).into_iter().collect::<HashSet<_>>() {
+            let Some(ids) = self.postings.get(&term) else {
+                continue;
+            };
+            let df = ids.len() as f32;
+            let idf = ((n - df + 0.5) / (df + 0.5) + 1.0).ln();
+            for id in ids {
+                let doc = &self.docs[id];
+                let tf = doc.terms[&term] as f32;
+                let norm = tf + K1 * (1.0 - B + B * doc.len as f32 / avg_len.max(1.0));
+                *scores.entry(id.as_str()).or_default() += idf * tf * (K1 + 1.0) / norm;
+            }
+        }
+        let mut hits: Vec<ScoredPoint> = scores
+            .into_iter()
+            .filter(|(id, _)| filter.is_none_or(|f| filter_matches(f, &self.docs[*id].payload)))
+            .map(|(id, score)| ScoredPoint {
+                id: id.to_string(),
+                score,
+                payload: self.docs[id].payload.clone(),
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+        hits.truncate(limit);
+        hits
+    }
+}
+
+/// Per-collection BM25 indexes.
+#[derive(Default)]
+pub struct KeywordIndex {
+    collections: RwLock<HashMap<String, Collection>>,
+}
+
+impl KeywordIndex {
+    pub fn upsert(&self, collection: &str, points: &[VectorPoint]) {
+        let mut collections = self.collections.write().unwrap_or_else(|e| e.into_inner());
+        let index = collections.entry(collection.to_string()).or_default();
+        for point in points {
+            index.insert(point);
+        }
+    }
+
+    pub fn delete(&self, collection: &str, ids: &[String]) {
+        let mut collections = self.collections.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(index) = collections.get_mut(collection) {
+            for id in ids {
+                index.remove(id);
+            }
+        }
+    }
+
+    /// Merge non-text payload `fields` (e.g. decay_score) into indexed points, keeping filters in sync.
+    pub fn set_payload(&self, collection: &str, ids: &[String], fields: &HashMap<String, String>) {
+        let mut collections = self.collections.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(index) = collections.get_mut(collection) {
+            for id in ids {
+                if let Some(doc) = index.docs.get_mut(id) {
+                    doc.payload.extend(fields.clone());
+                }
+            }
+        }
+    }
+
+    /// Top `limit` BM25 matches for `query` whose payloads satisfy `filter`.
+    pub fn search(&self, collection: &str, query: &str, limit: usize, filter: Option<&SearchFilter>) -> Vec<ScoredPoint> {
+        self.collections
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(collection)
+            .map(|index| index.search(query, limit, filter))
+            .unwrap_or_default()
+    }
+}
+
+/// Weighted reciprocal rank fusion: each (list, weight) contributes weight/(RRF_K + rank); hits keep the
+/// fused score. Equal weights give plain RRF ordering.
+pub fn rrf_fuse(lists: Vec<(Vec<ScoredPoint>, f32)>, limit: usize) -> Vec<ScoredPoint> {
+    let mut fused: HashMap<String, ScoredPoint> = HashMap::new();
+    for (list, weight) in lists {
+        for (rank, hit) in list.into_iter().enumerate() {
+            let contribution = weight / (RRF_K + rank as f32 + 1.0);
+            fused
+                .entry(hit.id.clone())
+                .and_modify(|p| p.score += contribution)
+                .or_insert(ScoredPoint {
+                    score: contribution,
+                    ..hit
+                });
+        }
+    }
+    let mut hits: Vec<ScoredPoint> = fused.into_values().collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+    hits.truncate(limit);
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(id: &str, content: &str) -> VectorPoint {
+        VectorPoint {
+            id: id.to_string(),
+            vector: Vec::new(),
+            payload: HashMap::from([("content".to_string(), content.to_string())]),
+            ..Default::default()
+        }
+    }
+
+    fn indexed() -> KeywordIndex {
+        let index = KeywordIndex::default();
+        index.upsert(
+            "kb_core",
+            &[
+                doc("a", "thread 'main' panicked at src/watchdog.rs: index out of bounds"),
+                doc("b", "ConnectionRefused while dialing qdrant; retrying connection"),
+                doc("c", "index rebuilt for kb_core after schema change"),
+            ],
+        );
+        index
+    }
+
+    fn scored(id: &str, score: f32) -> ScoredPoint {
+        ScoredPoint {
+            id: id.to_string(),
+            score,
+            payload: HashMap::new(),
+        }
+    }
+
+    /// A vector list and a keyword list that both rank "y" second, under the given weights.
+    fn lists(vector_weight: f32, keyword_weight: f32) -> Vec<(Vec<ScoredPoint>, f32)> {
+        vec![
+            (vec![scored("x", 0.9), scored("y", 0.8)], vector_weight),
+            (vec![scored("z", 12.0), scored("y", 7.0)], keyword_weight),
+        ]
+    }
+
+    #[test]
+    fn bm25_ranks_documents_sharing_more_terms_first() {
+        let hits = indexed().search("kb_core", "panicked: index out of bounds", 10, None);
+        assert_eq!(hits.iter().map(|h| h.id.as_str()).collect::<Vec<_>>(), ["a", "c"]);
+    }
+
+    #[test]
+    fn terms_match_case_insensitively() {
+        assert_eq!(indexed().search("kb_core", "connectionrefused", 10, None)[0].id, "b");
+    }
+
+    #[test]
+    fn collections_are_indexed_separately() {
+        assert!(indexed().search("kb_other", "index", 10, None).is_empty());
+    }
+
+    #[test]
+    fn deletes_and_re_upserts_drop_old_terms() {
+        let index = indexed();
+        index.delete("kb_core", &["a".to_string()]);
+        index.upsert("kb_core", &[doc("c", "unrelated text")]);
+        assert!(index.search("kb_core", "index", 10, None).is_empty());
+    }
+
+    #[test]
+    fn rrf_favours_points_ranked_in_both_lists() {
+        let fused = rrf_fuse(lists(1.0, 1.0), 2);
+        assert_eq!(fused.len(), 2);
+        assert_eq!(fused[0].id, "y");
+        assert!((fused[0].score - 2.0 / 62.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rrf_weights_let_the_keyword_top_hit_win() {
+        assert_eq!(rrf_fuse(lists(0.1, 0.9), 1)[0].id, "z");
+    }
+}
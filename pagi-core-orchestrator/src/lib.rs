@@ -0,0 +1,103 @@
+//! Phoenix AGI (pagi) — Rust backbone: gRPC orchestrator, tiered memory, watchdog and safety governor as a
+//! library, so other binaries and tests can embed the orchestrator. The `pagi-core-orchestrator` binary is a
+//! thin main over it.
+//!
+//! - [`Orchestrator`] implements the Pagi gRPC service (pagi-proto/pagi.proto). [`Orchestrator::from_env`]
+//!   wires it from the environment (.env.example) and starts its background tasks; [`Orchestrator::new`]
+//!   builds one over your own parts. [`Orchestrator::serve`] listens with auth and message limits applied, or
+//!   mount [`proto::pagi_proto::pagi_server::PagiServer`] on your own tonic server.
+//! - [`MemoryManager`]: memory layers L1–L7 with the pluggable L4 vector backend (Qdrant, in-process HNSW or
+//!   pgvector). [`MemoryManager::in_memory`] needs no external services.
+//! - [`Watchdog`]: real skill dispatch, self-heal, patch proposal and apply, the skills Git-Watcher.
+//! - [`SafetyGovernor`]: recursion depth, fan-out, parameter size and HITL gates in front of dispatch.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//! use pagi_core_orchestrator::{orchestrator, Orchestrator};
+//!
+//! Orchestrator::from_env().await?.serve(orchestrator::grpc_addr()).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+// tonic::Status is the crate-wide error type for RPC-facing helpers, sync or async.
+#![allow(clippy::result_large_err)]
+
+mod action_audit;
+mod allow_list;
+mod apply_queue;
+mod approval;
+mod archive;
+mod auth;
+mod blobs;
+pub mod bridge_contract;
+mod builtin_skills;
+mod circuit_breaker;
+mod clock;
+mod compaction;
+mod components;
+mod consolidation;
+mod decay;
+mod dedup;
+mod embedder;
+mod env_fingerprint;
+mod feedback;
+mod heal_governor;
+mod heal_metrics;
+mod hitl;
+mod hot_memory;
+mod impact;
+mod inflight;
+mod ingest;
+mod kb_registry;
+mod keyword_index;
+mod lineage;
+mod local_model;
+mod memory_audit;
+pub mod memory_manager;
+mod metrics;
+mod mock_fixtures;
+pub mod orchestrator;
+mod patch_catalog;
+mod patch_diff;
+mod patch_format;
+mod patch_history;
+mod pgvector_store;
+mod pipeline;
+mod point_log;
+mod policy_denial;
+mod profiler;
+pub mod proto;
+mod provenance;
+mod reasoning_id;
+mod registry;
+mod rerank;
+mod resource_usage;
+mod rlm_backend;
+mod runner_protocol;
+pub mod safety_governor;
+mod scheduler;
+mod search_cache;
+pub mod search_eval;
+mod session;
+mod simulation;
+mod skill_manifest;
+mod slo;
+mod smoke;
+mod smoke_test;
+mod snapshot;
+mod startup;
+mod store;
+mod test_runner;
+mod tiering;
+mod ttl;
+mod validate;
+mod vector_store;
+mod wal;
+pub mod watchdog;
+mod worker_pool;
+
+pub use memory_manager::MemoryManager;
+pub use orchestrator::Orchestrator;
+pub use safety_governor::SafetyGovernor;
+pub use watchdog::Watchdog;
@@ -0,0 +1,397 @@
+// L6 lineage: per-reasoning_id record of actions executed, RLM steps (with the L4 documents they cited),
+// patches proposed/applied (with commit hashes) and KB points written, answered by TraceQuery. Kept in process
+// (bounded like the session journal); with PAGI_L6_TRACE_FILE set, every event is also appended there as a JSON
+// line and replayed on startup; with a durable store (PAGI_STORE) installed, events go there instead.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tonic::Status;
+
+use crate::proto::pagi_proto::{ActionResponse, RlmResponse, TraceEvent, TraceQueryRequest, TraceQueryResponse};
+use crate::store::{self, Store};
+
+/// Events kept per reasoning_id; older ones are dropped first.
+const MAX_EVENTS: usize = 500;
+/// Reasoning ids tracked; the one with the oldest latest event is dropped beyond this.
+const MAX_TRACES: usize = 4096;
+/// Shortest commit-hash prefix accepted by TraceQuery.
+const MIN_COMMIT_PREFIX: usize = 7;
+
+/// One persisted lineage event (JSON line).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Record {
+    reasoning_id: String,
+    at_ms: i64,
+    kind: String,
+    name: String,
+    success: bool,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    detail: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    point_ids: Vec<String>,
+}
+
+impl Record {
+    fn to_proto(&self) -> TraceEvent {
+        TraceEvent {
+            at_unix_ms: self.at_ms,
+            kind: self.kind.clone(),
+            name: self.name.clone(),
+            success: self.success,
+            detail: self.detail.clone(),
+            point_ids: self.point_ids.clone(),
+            reasoning_id: self.reasoning_id.clone(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct LineageStore {
+    traces: DashMap<String, Vec<Record>>,
+    /// Append-only JSONL log; None keeps lineage in memory only.
+    log_path: Option<PathBuf>,
+    /// Serializes appends.
+    log_lock: Mutex<()>,
+    /// Durable store (PAGI_STORE); takes precedence over `log_path`.
+    store: Option<Arc<Store>>,
+}
+
+impl LineageStore {
+    /// Store backed by the durable store when installed, else PAGI_L6_TRACE_FILE when set (existing events
+    /// are replayed).
+    pub fn from_env() -> Self {
+        if let Some(store) = store::global() {
+            return Self::with_store(store);
+        }
+        let Some(path) = std::env::var("PAGI_L6_TRACE_FILE")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+        else {
+            return Self::default();
+        };
+        Self::with_log(PathBuf::from(path))
+    }
+
+    pub fn with_log(path: PathBuf) -> Self {
+        let store = Self::default();
+        if let Ok(raw) = std::fs::read_to_string(&path) {
+            let mut skipped = 0;
+            for line in raw.lines().filter(|l| !l.trim().is_empty()) {
+                match serde_json::from_str::<Record>(line) {
+                    Ok(r) => store.push(r),
+                    Err(_) => skipped += 1,
+                }
+            }
+            eprintln!(
+                "[Lineage] replayed {} trace(s) from {} ({} unreadable line(s))",
+                store.traces.len(),
+                path.display(),
+                skipped
+            );
+        }
+        Self {
+            log_path: Some(path),
+            ..store
+        }
+    }
+
+    pub fn with_store(store: Arc<Store>) -> Self {
+        let lineage = Self::default();
+        let mut records: Vec<Record> = store.take::<Record>(store::LINEAGE).into_iter().map(|(_, r)| r).collect();
+        records.sort_by_key(|r| r.at_ms);
+        for r in records {
+            lineage.push(r);
+        }
+        eprintln!("[Lineage] replayed {} trace(s) from the {} store", lineage.traces.len(), store.backend());
+        Self {
+            store: Some(store),
+            ..lineage
+        }
+    }
+
+    fn push(&self, record: Record) {
+        if !self.traces.contains_key(&record.reasoning_id) && self.traces.len() >= MAX_TRACES {
+            let oldest = self
+                .traces
+                .iter()
+                .min_by_key(|e| e.value().last().map_or(0, |r| r.at_ms))
+                .map(|e| e.key().clone());
+            if let Some(id) = oldest {
+                self.traces.remove(&id);
+            }
+        }
+        let mut events = self.traces.entry(record.reasoning_id.clone()).or_default();
+        if events.len() >= MAX_EVENTS {
+            events.remove(0);
+        }
+        events.push(record);
+    }
+
+    fn record(&self, reasoning_id: &str, kind: &str, name: &str, success: bool, detail: &str, point_ids: Vec<String>) {
+        if reasoning_id.is_empty() {
+            return;
+        }
+        let record = Record {
+            reasoning_id: reasoning_id.to_string(),
+            at_ms: chrono::Utc::now().timestamp_millis(),
+            kind: kind.to_string(),
+            name: name.to_string(),
+            success,
+            detail: detail.to_string(),
+            point_ids,
+        };
+        if let Some(store) = &self.store {
+            store.put(store::LINEAGE, &uuid::Uuid::new_v4().to_string(), &record);
+        } else if let Some(path) = &self.log_path {
+            let _guard = self.log_lock.lock().unwrap_or_else(|e| e.into_inner());
+            let result = serde_json::to_string(&record)
+                .map_err(|e| e.to_string())
+                .and_then(|line| {
+                    let mut f = std::fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(path)
+                        .map_err(|e| e.to_string())?;
+                    writeln!(f, "{}", line).map_err(|e| e.to_string())
+                });
+            if let Err(e) = result {
+                eprintln!("[Lineage] append to {}: {}", path.display(), e);
+            }
+        }
+        self.push(record);
+    }
+
+    pub fn record_action(&self, reasoning_id: &str, skill: &str, result: &Result<ActionResponse, Status>) {
+        let (success, detail) = match result {
+            Ok(resp) => (resp.success, resp.error.clone()),
+            Err(status) => (false, status.message().to_string()),
+        };
+        self.record(reasoning_id, "action", skill, success, &detail, Vec::new());
+    }
+
+    pub fn record_patch_proposed(&self, reasoning_id: &str, patch_id: &str, component: &str) {
+        self.record(reasoning_id, "patch_proposed", patch_id, true, component, Vec::new());
+    }
+
+    /// Applied with `commit_hash` (empty without auto-commit), or failed with the error.
+    pub fn record_patch_outcome(&self, reasoning_id: &str, patch_id: &str, outcome: Result<&str, &str>) {
+        match outcome {
+            Ok(commit_hash) => self.record(reasoning_id, "patch_applied", patch_id, true, commit_hash, Vec::new()),
+            Err(error) => self.record(reasoning_id, "patch_failed", patch_id, false, error, Vec::new()),
+        }
+    }
+
+    /// Rolled back by registry commit `commit_hash` (empty without auto-commit).
+    pub fn record_patch_rolled_back(&self, reasoning_id: &str, patch_id: &str, commit_hash: &str) {
+        self.record(reasoning_id, "patch_rolled_back", patch_id, true, commit_hash, Vec::new());
+    }
+
+    pub fn record_kb_write(&self, reasoning_id: &str, kb_name: &str, point_ids: Vec<String>) {
+        self.record(reasoning_id, "kb_write", kb_name, true, "", point_ids);
+    }
+
+    /// RecordSearchFeedback marks on hits of `kb_name`.
+    pub fn record_search_feedback(&self, reasoning_id: &str, kb_name: &str, useful: &[String], unhelpful: &[String]) {
+        let detail = format!("{} useful, {} unhelpful", useful.len(), unhelpful.len());
+        let point_ids = useful.iter().chain(unhelpful).cloned().collect();
+        self.record(reasoning_id, "search_feedback", kb_name, true, &detail, point_ids);
+    }
+
+    /// One DelegateRLM step: success is convergence; cited L4 documents are kept as "<kb_name>/<document_id>".
+    pub fn record_rlm(&self, reasoning_id: &str, depth: i32, resp: &RlmResponse) {
+        let confidence = resp.confidence.map_or_else(|| "-".to_string(), |c| format!("{:.2}", c));
+        let summary: String = resp.summary.chars().take(200).collect();
+        let detail = format!(
+            "backend={} steps={} confidence={} tokens={}+{}: {}",
+            resp.backend, resp.steps, confidence, resp.prompt_tokens, resp.completion_tokens, summary
+        );
+        let cited = resp
+            .citations
+            .iter()
+            .map(|c| format!("{}/{}", c.kb_name, c.document_id))
+            .collect();
+        self.record(reasoning_id, "rlm", &format!("depth {}", depth), resp.converged, &detail, cited);
+    }
+
+    /// TraceQuery: lineage for reasoning_id, or for the reasoning_id whose applied patch has commit_hash; with
+    /// include_descendants, merged with that of its child ids.
+    pub fn query(&self, req: &TraceQueryRequest) -> Result<TraceQueryResponse, Status> {
+        let reasoning_id = if !req.reasoning_id.is_empty() {
+            req.reasoning_id.clone()
+        } else {
+            let prefix = req.commit_hash.trim();
+            if prefix.len() < MIN_COMMIT_PREFIX {
+                return Err(Status::invalid_argument(format!(
+                    "reasoning_id or a commit_hash of at least {} chars is required",
+                    MIN_COMMIT_PREFIX
+                )));
+            }
+            self.traces
+                .iter()
+                .find(|e| {
+                    e.value()
+                        .iter()
+                        .any(|r| r.kind == "patch_applied" && !r.detail.is_empty() && r.detail.starts_with(prefix))
+                })
+                .map(|e| e.key().clone())
+                .ok_or_else(|| Status::not_found(format!("no lineage for commit {}", prefix)))?
+        };
+        let mut events = self
+            .traces
+            .get(&reasoning_id)
+            .map(|e| e.value().clone())
+            .unwrap_or_default();
+        if req.include_descendants {
+            let prefix = format!("{}/", reasoning_id);
+            for e in self.traces.iter().filter(|e| e.key().starts_with(&prefix)) {
+                events.extend(e.value().iter().cloned());
+            }
+            events.sort_by_key(|r| r.at_ms);
+        }
+        if events.is_empty() {
+            return Err(Status::not_found(format!("no lineage for reasoning_id {}", reasoning_id)));
+        }
+
+        let mut resp = TraceQueryResponse {
+            reasoning_id,
+            ..Default::default()
+        };
+        for r in &events {
+            match r.kind.as_str() {
+                "action" => resp.action_count += 1,
+                "patch_proposed" => resp.patch_ids.push(r.name.clone()),
+                "patch_applied" | "patch_rolled_back" if !r.detail.is_empty() => {
+                    resp.commit_hashes.push(r.detail.clone())
+                }
+                "kb_write" => resp.kb_points_written += r.point_ids.len() as u32,
+                _ => {}
+            }
+            resp.events.push(r.to_proto());
+        }
+        Ok(resp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::pagi_proto::RlmCitation;
+    use std::path::Path;
+
+    fn ok() -> Result<ActionResponse, Status> {
+        Ok(ActionResponse {
+            success: true,
+            ..Default::default()
+        })
+    }
+
+    /// Reasoning r1: two actions (one failed), patch p1 committed as 0123456789abcdef, two kb_core writes and
+    /// one RLM step.
+    fn recorded(path: &Path) -> LineageStore {
+        let store = LineageStore::with_log(path.to_path_buf());
+        store.record_action("r1", "peek_file", &ok());
+        store.record_action("r1", "save_skill", &Err(Status::invalid_argument("bad path")));
+        store.record_patch_proposed("r1", "p1", "rust_core");
+        store.record_patch_outcome("r1", "p1", Ok("0123456789abcdef"));
+        store.record_kb_write("r1", "kb_core", vec!["a".into(), "b".into()]);
+        let rlm = RlmResponse {
+            summary: "root cause found".into(),
+            converged: true,
+            steps: 2,
+            confidence: Some(0.8),
+            citations: vec![RlmCitation {
+                kb_name: "kb_core".into(),
+                document_id: "doc-1".into(),
+                score: 0.9,
+            }],
+            backend: "bridge".into(),
+            ..Default::default()
+        };
+        store.record_rlm("r1", 1, &rlm);
+        store
+    }
+
+    fn temp_log() -> PathBuf {
+        std::env::temp_dir().join(format!("pagi_l6_{}.jsonl", uuid::Uuid::new_v4()))
+    }
+
+    fn by_commit(commit_hash: &str) -> TraceQueryRequest {
+        TraceQueryRequest {
+            commit_hash: commit_hash.into(),
+            ..Default::default()
+        }
+    }
+
+    fn tree(reasoning_id: &str) -> TraceQueryRequest {
+        TraceQueryRequest {
+            reasoning_id: reasoning_id.into(),
+            include_descendants: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn commits_trace_back_to_the_reasoning_that_made_them() {
+        let path = temp_log();
+        let trace = recorded(&path).query(&by_commit("0123456")).unwrap();
+        assert_eq!(trace.reasoning_id, "r1");
+        assert_eq!((trace.action_count, trace.kb_points_written), (2, 2));
+        assert_eq!(trace.patch_ids, ["p1"]);
+        assert_eq!(trace.commit_hashes, ["0123456789abcdef"]);
+        assert_eq!(trace.events[1].detail, "bad path");
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn rlm_steps_cite_their_points() {
+        let path = temp_log();
+        let trace = recorded(&path).query(&by_commit("0123456")).unwrap();
+        assert_eq!(trace.events.len(), 6);
+        let step = &trace.events[5];
+        assert_eq!((step.kind.as_str(), step.success), ("rlm", true));
+        assert_eq!(step.point_ids, ["kb_core/doc-1"]);
+        assert!(step.detail.starts_with("backend=bridge steps=2 confidence=0.80 tokens=0+0"), "{}", step.detail);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn events_replay_from_the_log() {
+        let path = temp_log();
+        let store = recorded(&path);
+        store.record_action("", "ignored", &ok());
+        let restored = LineageStore::with_log(path.clone());
+        assert_eq!(restored.query(&tree("r1")).unwrap().events.len(), 6, "events without a reasoning_id are dropped");
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn short_commit_prefixes_and_unknown_ids_are_rejected() {
+        let path = temp_log();
+        let store = recorded(&path);
+        assert_eq!(store.query(&by_commit("0123")).unwrap_err().code(), tonic::Code::InvalidArgument);
+        let missing = store.query(&TraceQueryRequest {
+            reasoning_id: "nope".into(),
+            ..Default::default()
+        });
+        assert_eq!(missing.unwrap_err().code(), tonic::Code::NotFound);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn descendant_queries_follow_path_segments_only() {
+        let path = temp_log();
+        let store = recorded(&path);
+        store.record_action("r1/step-1", "peek_file", &ok());
+        store.record_action("r10", "peek_file", &ok());
+        let all = store.query(&tree("r1")).unwrap();
+        assert_eq!((all.events.len(), all.action_count), (7, 3), "r10 is not a child of r1");
+        assert_eq!(all.events[6].reasoning_id, "r1/step-1");
+        assert_eq!(store.query(&tree("r1/step-1")).unwrap().events.len(), 1);
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,131 @@
+// Optional local GGUF model for offline patch proposal (air-gapped deployments): propose_patch asks it
+// for a fix when PAGI_LOCAL_MODEL_PATH is set, falling back to the generic stub on any failure.
+// Inference needs the `local-llm` cargo feature (llama.cpp bindings); without it, loading reports why.
+
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalModelConfig {
+    /// GGUF file (PAGI_LOCAL_MODEL_PATH).
+    pub model_path: PathBuf,
+    /// Context window in tokens (PAGI_LOCAL_MODEL_CTX, default 4096).
+    pub context_size: u32,
+    /// Completion cap in tokens (PAGI_LOCAL_MODEL_MAX_TOKENS, default 512).
+    pub max_tokens: usize,
+}
+
+impl LocalModelConfig {
+    /// None when PAGI_LOCAL_MODEL_PATH is unset or empty.
+    pub fn from_env() -> Option<Self> {
+        let model_path = std::env::var("PAGI_LOCAL_MODEL_PATH")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())?;
+        let num = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(default)
+        };
+        Some(Self {
+            model_path: PathBuf::from(model_path),
+            context_size: num("PAGI_LOCAL_MODEL_CTX", 4096).max(256) as u32,
+            max_tokens: num("PAGI_LOCAL_MODEL_MAX_TOKENS", 512).max(1) as usize,
+        })
+    }
+}
+
+/// Instruction prompt for a patch proposal, bounded so it fits small context windows.
+pub fn patch_prompt(
+    component: &str,
+    language: &str,
+    error_trace: &str,
+    prior_snippets: &[String],
+    context_size: u32,
+) -> String {
+    // ~4 chars per token; leave half the window for the completion.
+    let budget = context_size as usize * 2;
+    let trace: String = error_trace.chars().take(budget / 2).collect();
+    let mut prior = String::new();
+    for snippet in prior_snippets {
+        if prior.len() + snippet.len() > budget / 2 {
+            break;
+        }
+        prior.push_str("- ");
+        prior.push_str(snippet);
+        prior.push('\n');
+    }
+    format!(
+        "You are the self-heal agent for the {component} component. Propose a minimal {language} code fix \
+         for the error below. Reply with code only.\n\nError trace:\n{trace}\n\nRelated knowledge:\n{prior}\nFix:\n"
+    )
+}
+
+#[cfg(feature = "local-llm")]
+pub struct LocalModel {
+    config: LocalModelConfig,
+    model: llama_cpp::LlamaModel,
+}
+
+#[cfg(feature = "local-llm")]
+impl LocalModel {
+    /// Load the GGUF model (slow; call from spawn_blocking).
+    pub fn load(config: LocalModelConfig) -> Result<Self, String> {
+        let model = llama_cpp::LlamaModel::load_from_file(&config.model_path, llama_cpp::LlamaParams::default())
+            .map_err(|e| format!("load {}: {}", config.model_path.display(), e))?;
+        Ok(Self { config, model })
+    }
+
+    /// Greedy-ish completion with the standard sampler (blocking).
+    pub fn generate(&self, prompt: &str) -> Result<String, String> {
+        let params = llama_cpp::SessionParams {
+            n_ctx: self.config.context_size,
+            ..Default::default()
+        };
+        let mut session = self.model.create_session(params).map_err(|e| e.to_string())?;
+        session.advance_context(prompt).map_err(|e| e.to_string())?;
+        let completion = session
+            .start_completing_with(llama_cpp::standard_sampler::StandardSampler::default(), self.config.max_tokens)
+            .map_err(|e| e.to_string())?;
+        Ok(completion.into_strings().collect::<String>().trim().to_string())
+    }
+}
+
+#[cfg(not(feature = "local-llm"))]
+pub struct LocalModel;
+
+#[cfg(not(feature = "local-llm"))]
+impl LocalModel {
+    pub fn load(config: LocalModelConfig) -> Result<Self, String> {
+        Err(format!(
+            "PAGI_LOCAL_MODEL_PATH={} set but the orchestrator was built without the local-llm feature",
+            config.model_path.display()
+        ))
+    }
+
+    pub fn generate(&self, _prompt: &str) -> Result<String, String> {
+        Err("local-llm feature disabled".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompt_is_bounded_and_model_gated() {
+        let trace = "x".repeat(100_000);
+        let prompt = patch_prompt("rust_core", "Rust", &trace, &["prior fix".to_string()], 1024);
+        assert!(prompt.contains("minimal Rust code fix"));
+        assert!(prompt.contains("- prior fix"));
+        assert!(prompt.len() < 1024 * 4);
+
+        let config = LocalModelConfig {
+            model_path: PathBuf::from("/nonexistent/model.gguf"),
+            context_size: 1024,
+            max_tokens: 16,
+        };
+        // Missing file (feature on) or missing feature (feature off): both are load errors, never panics.
+        assert!(LocalModel::load(config).is_err());
+    }
+}
@@ -0,0 +1,278 @@
+// Memory access audit log. With PAGI_MEMORY_AUDIT_LOG set, every AccessMemory and SemanticSearch call (denied ones
+// included) is appended there as one JSON line: time, RPC, caller (token subject and tenant, when auth is on),
+// reasoning_id, layer or KB, namespace, the SHA-256 of the key (salted with PAGI_MEMORY_AUDIT_SALT when set, so
+// low-entropy keys cannot be guessed back), outcome and latency. Keys are never logged in clear; values written
+// and search queries follow PAGI_MEMORY_AUDIT_REDACT: "omit" (default, length only), "hash" or "full".
+
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tonic::Status;
+
+use crate::auth::Identity;
+use crate::proto::pagi_proto::{MemoryRequest, SearchRequest};
+
+/// How values and queries appear in the log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Redaction {
+    /// Length only.
+    #[default]
+    Omit,
+    /// Salted SHA-256 hex.
+    Hash,
+    /// Verbatim.
+    Full,
+}
+
+impl Redaction {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        match raw.trim().to_lowercase().as_str() {
+            "" | "omit" => Ok(Redaction::Omit),
+            "hash" => Ok(Redaction::Hash),
+            "full" | "none" => Ok(Redaction::Full),
+            other => Err(format!("unknown redaction {:?} (expected omit, hash or full)", other)),
+        }
+    }
+}
+
+/// One audit line; fields a call does not have are left out.
+#[derive(Debug, Default, Serialize)]
+pub struct AccessRecord {
+    pub at: String,
+    pub rpc: &'static str,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub caller: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub tenant: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub reasoning_id: String,
+    /// "read", "write" or "search".
+    pub op: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layer: Option<i32>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub kb_name: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub namespace: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub key_hash: String,
+    /// Value written (AccessMemory) or query text (SemanticSearch), as redacted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_len: Option<usize>,
+    /// Whether a read found the key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub found: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hits: Option<u32>,
+    pub success: bool,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub error: String,
+    pub latency_ms: u64,
+}
+
+pub struct MemoryAudit {
+    path: PathBuf,
+    redaction: Redaction,
+    salt: String,
+    file: Mutex<File>,
+}
+
+impl MemoryAudit {
+    pub fn open(path: &Path, redaction: Redaction, salt: String) -> Result<Self, String> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("open {}: {}", path.display(), e))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            redaction,
+            salt,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// PAGI_MEMORY_AUDIT_LOG (unset or empty: no audit), PAGI_MEMORY_AUDIT_REDACT and PAGI_MEMORY_AUDIT_SALT.
+    /// An unknown redaction mode falls back to "omit"; an unopenable log disables the audit, both logged.
+    pub fn from_env() -> Option<Self> {
+        let path = std::env::var("PAGI_MEMORY_AUDIT_LOG")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())?;
+        let redaction = Redaction::parse(&std::env::var("PAGI_MEMORY_AUDIT_REDACT").unwrap_or_default())
+            .unwrap_or_else(|e| {
+                eprintln!("[MemoryAudit] PAGI_MEMORY_AUDIT_REDACT: {}; values omitted", e);
+                Redaction::Omit
+            });
+        let salt = std::env::var("PAGI_MEMORY_AUDIT_SALT").unwrap_or_default();
+        match Self::open(Path::new(&path), redaction, salt) {
+            Ok(audit) => {
+                eprintln!("[MemoryAudit] logging memory access to {} (values: {:?})", path, redaction);
+                Some(audit)
+            }
+            Err(e) => {
+                eprintln!("[MemoryAudit] disabled: {}", e);
+                None
+            }
+        }
+    }
+
+    fn hash(&self, text: &str) -> String {
+        format!("{:x}", Sha256::digest(format!("{}{}", self.salt, text).as_bytes()))
+    }
+
+    fn redact(&self, text: &str) -> Option<String> {
+        match self.redaction {
+            Redaction::Omit => None,
+            Redaction::Hash => Some(self.hash(text)),
+            Redaction::Full => Some(text.to_string()),
+        }
+    }
+
+    fn caller(record: &mut AccessRecord, caller: Option<&Identity>) {
+        if let Some(id) = caller {
+            record.caller = id.subject.clone();
+            record.tenant = id.tenant.clone().unwrap_or_default();
+        }
+    }
+
+    /// The request half of an AccessMemory line.
+    pub fn memory_access(&self, caller: Option<&Identity>, req: &MemoryRequest) -> AccessRecord {
+        let write = !req.value.is_empty();
+        let mut record = AccessRecord {
+            rpc: "AccessMemory",
+            reasoning_id: req.reasoning_id.clone(),
+            op: if write { "write" } else { "read" },
+            layer: Some(req.layer),
+            namespace: req.namespace.clone(),
+            key_hash: self.hash(&req.key),
+            value: if write { self.redact(&req.value) } else { None },
+            value_len: write.then(|| req.value.chars().count()),
+            ..Default::default()
+        };
+        Self::caller(&mut record, caller);
+        record
+    }
+
+    /// The request half of a SemanticSearch line.
+    pub fn search(&self, caller: Option<&Identity>, req: &SearchRequest) -> AccessRecord {
+        let has_query = !req.query.is_empty();
+        let mut record = AccessRecord {
+            rpc: "SemanticSearch",
+            op: "search",
+            kb_name: req.kb_name.clone(),
+            namespace: req.namespace.clone(),
+            value: if has_query { self.redact(&req.query) } else { None },
+            value_len: has_query.then(|| req.query.chars().count()),
+            ..Default::default()
+        };
+        Self::caller(&mut record, caller);
+        record
+    }
+
+    /// Complete `record` with the call's outcome and append it.
+    pub fn append(&self, mut record: AccessRecord, error: Option<&Status>, latency: Duration) {
+        record.at = chrono::Utc::now().to_rfc3339();
+        record.success = error.is_none();
+        record.error = error.map(|e| format!("{:?}: {}", e.code(), e.message())).unwrap_or_default();
+        record.latency_ms = latency.as_millis() as u64;
+        let result = serde_json::to_string(&record).map_err(|e| e.to_string()).and_then(|line| {
+            let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+            writeln!(file, "{}", line).map_err(|e| e.to_string())
+        });
+        if let Err(e) = result {
+            eprintln!("[MemoryAudit] append to {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_log() -> PathBuf {
+        std::env::temp_dir().join(format!("pagi_memory_audit_{}.jsonl", uuid::Uuid::new_v4()))
+    }
+
+    fn write() -> MemoryRequest {
+        MemoryRequest {
+            layer: 2,
+            key: "goal".into(),
+            value: "ship the release".into(),
+            namespace: "acme".into(),
+            ..Default::default()
+        }
+    }
+
+    fn search() -> SearchRequest {
+        SearchRequest {
+            kb_name: "kb_core".into(),
+            query: "secret plans".into(),
+            ..Default::default()
+        }
+    }
+
+    /// The single line written to `path`, after checking it leaks no key or value.
+    fn only_line(path: &Path) -> serde_json::Value {
+        let raw = std::fs::read_to_string(path).unwrap();
+        assert!(!raw.contains("goal") && !raw.contains("ship the") && !raw.contains("secret"), "{}", raw);
+        assert_eq!(raw.lines().count(), 1);
+        serde_json::from_str(raw.trim()).unwrap()
+    }
+
+    #[test]
+    fn writes_record_the_caller_op_and_hashed_key() {
+        let path = temp_log();
+        let caller = Identity {
+            subject: "alice".into(),
+            roles: vec![],
+            tenant: Some("acme".into()),
+        };
+        let audit = MemoryAudit::open(&path, Redaction::Omit, String::new()).unwrap();
+        audit.append(audit.memory_access(Some(&caller), &write()), None, Duration::from_millis(3));
+        let line = only_line(&path);
+        assert_eq!(line["caller"], "alice");
+        assert_eq!((line["op"].as_str(), line["value_len"].as_u64()), (Some("write"), Some(16)));
+        assert_eq!(line["key_hash"].as_str().unwrap(), format!("{:x}", Sha256::digest(b"goal")));
+        assert!(line.get("value").is_none(), "omitted");
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn hashed_values_are_peppered_and_errors_recorded() {
+        let path = temp_log();
+        let audit = MemoryAudit::open(&path, Redaction::parse("hash").unwrap(), "pepper".into()).unwrap();
+        let denied = Status::permission_denied("confined");
+        audit.append(audit.search(None, &search()), Some(&denied), Duration::ZERO);
+        let line = only_line(&path);
+        assert_eq!(line["value"].as_str().unwrap(), format!("{:x}", Sha256::digest(b"peppersecret plans")));
+        let outcome = (line["success"].as_bool(), line["error"].as_str());
+        assert_eq!(outcome, (Some(false), Some("PermissionDenied: confined")));
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn reads_carry_no_value_even_unredacted() {
+        let path = temp_log();
+        let audit = MemoryAudit::open(&path, Redaction::Full, String::new()).unwrap();
+        let mut read = audit.memory_access(None, &MemoryRequest { value: String::new(), ..write() });
+        read.found = Some(true);
+        audit.append(read, None, Duration::ZERO);
+        let line = only_line(&path);
+        assert_eq!((line["op"].as_str(), line["found"].as_bool()), (Some("read"), Some(true)));
+        assert!(line.get("value").is_none());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn unknown_redaction_modes_are_rejected() {
+        assert!(Redaction::parse("mask").is_err());
+    }
+}
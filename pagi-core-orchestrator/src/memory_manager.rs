@@ -1,33 +1,336 @@
-// 7-Layer memory hierarchy. L4: semantic (Qdrant), 1536-dim cap, 8 KBs.
-// L1/L2: DashMap stubs; L3/L5–L7: SurrealDB/other stubs deferred.
+// 7-Layer memory hierarchy. L4: semantic (VectorStore backend: Qdrant, pgvector, or in-memory HNSW, which the local
+// index persists; point_log.rs), 8 KBs shaped by the KB registry (per-KB dim / distance / storage; PAGI_KB_FILE).
+// L1/L2: DashMap stubs; L3/L5: SurrealDB/other stubs deferred (L6 lineage lives in lineage.rs, L7 in archive.rs);
+// AccessMemory fails on layers without a backend and reports each layer's capabilities (layer_capabilities).
+// L2 keeps a bounded per-key version history (PAGI_L2_HISTORY_DEPTH) for AccessMemoryAt time-travel reads,
+// optionally snapshotted to disk (PAGI_L2_SNAPSHOT_PATH) and restored on startup. Large values (PAGI_BLOB_MIN_BYTES)
+// are interned so versions and keys holding the same content share it, and snapshotted once, compressed (blobs.rs).
+// L1/L2 values are shared handles (Bytes / Arc<str>): read_l1 / read_l2 and write_l1 / write_l2 are the
+// zero-copy fast path (no per-call key or value allocation when the key exists); `access` copies for the RPC.
+// Hybrid L4 search (SearchRequest.hybrid or PAGI_SEARCH_HYBRID) fuses vector hits with a BM25 keyword index;
+// time-weighted searches (SearchRequest.recency_weight) then blend in the recency of each hit's `at`, which every
+// upsert stamps when missing; SearchRequest.rerank reorders the result by MMR or a cross-encoder (rerank.rs).
+// Read counts for L2 keys and L4 hits feed the hot-memory report; hot L4 points can be pinned in-process.
+// KBs with a retention policy (max_points / max_age_secs) are pruned periodically (PAGI_RETENTION_INTERVAL_SECS);
+// the same pass rescores and drops faded points under importance/recency decay (PAGI_MEMORY_DECAY_HALFLIFE).
+// L7: with PAGI_ARCHIVE_DIR set, points the pass drops are archived to cold JSONL segments first
+// (RecallArchive can rehydrate them).
+// Tiered KBs (cold_after_secs) keep idle points in an on-disk cold collection beside each of theirs; the same pass
+// moves points between the tiers by age and search reads, and searches fan out over both (tiering.rs).
+// With PAGI_L4_WAL_DIR set, upsert batches are logged to disk before the vector store sees them and replayed on
+// startup when the orchestrator died before the store acked (wal.rs).
+// Namespaces: AccessMemory keys and L4 collections of a namespaced request are scoped to it (`scoped_key`,
+// kb_registry::namespaced), so agents sharing the orchestrator under different namespaces never see each
+// other's entries. Requests without a namespace act at operator scope and may name scoped keys/collections.
+// Qdrant resilience: pooled clients (PAGI_QDRANT_POOL_SIZE), searches and upserts retried with backoff on outages
+// (PAGI_QDRANT_RETRY_*; each retry reconnects), a breaker over consecutive outages and a periodic health probe
+// (PAGI_QDRANT_HEALTH_INTERVAL_SECS) whose failure marks L4 degraded until it passes again.
+// SnapshotKb / RestoreKb back KBs up to PAGI_SNAPSHOT_DIR and restore them (snapshot.rs).
+// Repeated searches are served from a read-through cache (search_cache.rs) that every write to the KB clears.
+// ScrollKb streams a KB's points page by page for export (a spawned task feeds a bounded channel).
+// CompactMemory runs the maintenance pass, the store's optimizer and a WAL rewrite on demand (compaction.rs).
 
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
+use bytes::Bytes;
 use dashmap::DashMap;
-use qdrant_client::prelude::*;
-use qdrant_client::prelude::{Payload, PointStruct};
-use qdrant_client::qdrant::{
-    point_id::PointIdOptions, value::Kind, vectors_config, CreateCollection, Distance,
-    PointId, SearchPoints, VectorParams, VectorsConfig,
-};
+use serde::{Deserialize, Serialize};
+use qdrant_client::prelude::{QdrantClient, QdrantClientConfig};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
 use tonic::Status;
 
+use crate::archive::Archive;
+use crate::blobs::{self, BlobPolicy, Blobs, Interner};
+use crate::circuit_breaker::{BreakerState, CircuitBreaker, RetryPolicy};
+use crate::clock::Clock;
+use crate::decay::{self, Decay};
+use crate::dedup::{self, DedupMode};
+use crate::embedder::{self, Embedder};
+use crate::feedback;
+use crate::hot_memory::HotTracker;
+use crate::kb_registry::{self, KbRegistry, KbSpec, NAMESPACE_SEP};
+use crate::keyword_index::{self, KeywordIndex};
+use crate::pgvector_store::PgVectorStore;
+use crate::point_log::PointLog;
+use crate::reasoning_id::ReasoningId;
+use crate::rerank::{self, CrossEncoder, Rerank};
 use crate::proto::pagi_proto::{
-    SearchHit, SearchRequest, SearchResponse, UpsertRequest, UpsertResponse,
+    CollectionStats, DeleteVectorsRequest, DeleteVectorsResponse, DenseVector, FilterCondition, HealthResponse, HotMemoryReport,
+    LayerCapability, LayerStats, MemoryAtRequest, MemoryAtResponse, MemoryDeleteRequest, MemoryEntry, MemoryScanRequest,
+    MemoryScanResponse, MemoryStatsResponse, MemoryWriteRequest, MemoryWriteResponse, RecallArchiveRequest, RecallArchiveResponse,
+    RestoreKbRequest, RestoreKbResponse, RetentionStats, ScrollKbPage, ScrollKbRequest, SearchFeedbackRequest, SearchFeedbackResponse, SearchFilter, SnapshotKbRequest, SnapshotKbResponse, SearchHit, SearchRequest, SearchResponse, UpsertBatch, UpsertRequest, UpsertResponse, UpsertStreamResponse, VectorPoint,
 };
+use crate::scheduler::Scheduler;
+use crate::search_cache::{CacheKey, SearchCache};
+use crate::snapshot::{self, SnapshotDir};
+use crate::tiering::{self, ReadLog};
+use crate::ttl;
+use crate::vector_store::{self, MemoryStore, QdrantStore, ScoredPoint, VectorStore};
+use crate::wal::Wal;
+
+/// Bumped when the L2 snapshot layout changes; unknown versions are not restored. 2: large values as blobs.
+const L2_SNAPSHOT_VERSION: u32 = 2;
+
+/// One L2 write: per-key version (1-based, monotonic) and wall-clock write time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct L2Version {
+    pub version: u64,
+    pub written_at_ms: i64,
+    /// Shared with readers of the fast path; a later write never mutates it.
+    pub value: Arc<str>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct L2Snapshot {
+    version: u32,
+    updated_at: i64,
+    /// Key -> versions, with large values as blob references.
+    entries: serde_json::Value,
+    /// Blobs the entries reference (absent from version 1).
+    #[serde(default)]
+    blobs: Blobs,
+}
+
+/// Per-call L4 search knobs; the defaults reproduce SemanticSearch. The search eval harness varies them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchTuning {
+    /// Force hybrid on/off; None follows SearchRequest.hybrid / PAGI_SEARCH_HYBRID.
+    pub hybrid: Option<bool>,
+    /// Weight of the vector ranking in hybrid fusion (0.0–1.0); the keyword ranking gets the rest.
+    pub alpha: f32,
+    /// Drop vector hits scoring below this (before fusion).
+    pub min_score: Option<f32>,
+    /// Leave hot-memory and hit/miss counters alone (eval queries are not real reads).
+    pub untracked: bool,
+}
+
+impl Default for SearchTuning {
+    fn default() -> Self {
+        Self {
+            hybrid: None,
+            alpha: 0.5,
+            min_score: None,
+            untracked: false,
+        }
+    }
+}
+
+/// L4 search result with full payloads (SearchResponse keeps only the content snippet unless asked).
+#[derive(Debug)]
+pub struct PointSearch {
+    pub points: Vec<ScoredPoint>,
+    /// L4 was not consulted (disabled or circuit open); `points` is empty.
+    pub degraded: bool,
+    pub source: String,
+    /// Offset of the next page; 0 when the ranking is exhausted.
+    pub next_offset: u32,
+}
+
+/// What CompactMemory's L4 pass did (compact_l4).
+#[derive(Debug, Default)]
+pub struct L4Compaction {
+    pub retention: Vec<RetentionStats>,
+    /// Collections the store's optimizer ran on.
+    pub optimized: Vec<String>,
+    /// Deleted/replaced points the store purged; 0 when it reclaims in the background.
+    pub tombstones_purged: u64,
+    pub search_cache_purged: u64,
+    pub errors: Vec<String>,
+}
+
+/// Read hits (key or results found) and misses. Cache-line aligned so hot L1 and L2 readers on different
+/// cores do not contend on one line.
+#[derive(Default)]
+#[repr(align(64))]
+struct HitCounter {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl HitCounter {
+    fn record(&self, hit: bool) {
+        let counter = if hit { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// GetMemoryStats counters: L1/L2 reads, L4 searches (empty = miss) and hot-cache reads.
+#[derive(Default)]
+struct MemoryCounters {
+    l1: HitCounter,
+    l2: HitCounter,
+    l4: HitCounter,
+    /// Searches served empty because L4 was circuit-broken.
+    l4_degraded: AtomicU64,
+    /// L4 calls retried after an outage.
+    l4_retries: AtomicU64,
+    l4_cache: HitCounter,
+}
 
 /// Tiered memory manager; layers 1–7 per blueprint.
 pub struct MemoryManager {
     /// L1 sensory: ring-buffer stub (key -> raw bytes).
-    l1_sensory: DashMap<String, Vec<u8>>,
-    /// L2 working memory.
-    l2_working: DashMap<String, String>,
-    /// L4 semantic: local Qdrant client (1536-dim cap).
-    l4_semantic: Option<QdrantClient>,
-    /// Cached embedding dim to avoid env parsing on hot paths.
+    l1_sensory: DashMap<String, Bytes>,
+    /// L2 working memory: newest-last version history per key.
+    l2_working: DashMap<String, VecDeque<L2Version>>,
+    /// Versions retained per L2 key (PAGI_L2_HISTORY_DEPTH, default 16).
+    l2_depth: usize,
+    /// Set on every L2 write; cleared by a snapshot so idle intervals write nothing.
+    l2_dirty: AtomicBool,
+    /// Which L2 values are snapshotted as blobs, and how compressed (PAGI_BLOB_MIN_BYTES / PAGI_BLOB_ZSTD_LEVEL).
+    blob_policy: BlobPolicy,
+    /// Shares one allocation between L2 versions holding the same large value.
+    l2_blobs: Interner,
+    /// L4 semantic: vector backend (PAGI_VECTOR_BACKEND; 1536-dim cap); None when disabled.
+    l4_semantic: Option<Box<dyn VectorStore>>,
+    /// Default KB dim (PAGI_EMBEDDING_DIM), cached to avoid env parsing on hot paths.
     embedding_dim: usize,
-    /// Cached zero vector for fallback queries.
+    /// Cached zero vector (default dim) for fallback queries.
     zero_vector: Vec<f32>,
+    /// Per-KB dim, distance and storage.
+    kbs: KbRegistry,
+    /// Server-side embedder (PAGI_EMBEDDER): searches without a query_vector and IngestDocument chunks.
+    embedder: Option<Arc<dyn Embedder>>,
+    /// Hard per-call bound on L4 search/upsert/delete (PAGI_QDRANT_TIMEOUT_MS, default 5000).
+    l4_timeout: Duration,
+    /// Trips after consecutive Qdrant outages; searches then return degraded empty results.
+    l4_breaker: CircuitBreaker,
+    /// Retries of L4 searches and upserts that hit an outage (PAGI_QDRANT_RETRY_*).
+    l4_retry: RetryPolicy,
+    /// Health probe interval (PAGI_QDRANT_HEALTH_INTERVAL_SECS); zero disables the probe.
+    l4_probe_interval: Duration,
+    /// Set while the last health probe failed: L4 reports degraded and calls are not retried.
+    l4_probe_failing: AtomicBool,
+    /// Set once `l4_ready` has created the KBs and replayed the WAL.
+    l4_initialized: AtomicBool,
+    /// BM25 index over payload text of points upserted since startup (hybrid search).
+    l4_keywords: KeywordIndex,
+    /// PAGI_SEARCH_HYBRID: hybrid search for every request, not only those setting `hybrid`.
+    hybrid_default: bool,
+    /// PAGI_SEARCH_RECENCY_HALFLIFE_SECS: recency halflife of time-weighted searches that set none.
+    recency_halflife_secs: u64,
+    /// PAGI_SEARCH_MMR_LAMBDA: relevance weight of MMR reranks that set no mmr_lambda.
+    mmr_lambda: f32,
+    /// PAGI_SEARCH_FEEDBACK_WEIGHT: feedback share of the ranking for searches that set no feedback_weight.
+    feedback_weight: f32,
+    /// Serializes RecordSearchFeedback, whose count updates are read-modify-write.
+    feedback_lock: tokio::sync::Mutex<()>,
+    /// PAGI_RERANK_URL: the bridge cross-encoder for cross_encoder reranks.
+    cross_encoder: CrossEncoder,
+    /// Read counts and pinned hot L4 points (PAGI_HOT_PIN_THRESHOLD).
+    hot: HotTracker,
+    /// Recent search results per collection, dropped on writes (PAGI_SEARCH_CACHE_SIZE).
+    search_cache: SearchCache,
+    /// Importance/recency decay of L4 points (PAGI_MEMORY_DECAY_HALFLIFE); None when disabled.
+    decay: Option<Decay>,
+    /// What upserts do with content already in the KB, unless the request says (PAGI_UPSERT_DEDUP).
+    dedup_default: DedupMode,
+    counters: MemoryCounters,
+    /// KBs created on demand via ensure_kb (reported by GetMemoryStats with the registry's).
+    ensured_kbs: DashMap<String, ()>,
+    /// Last retention pass per bounded KB, reported by GetHealth.
+    retention_stats: DashMap<String, RetentionStats>,
+    /// L7 cold storage for pruned points (PAGI_ARCHIVE_DIR); None deletes without archiving.
+    archive: Option<Archive>,
+    /// Collection → its cold tier, for tiered KBs whose tiers exist (created with the collection).
+    cold_tiers: DashMap<String, String>,
+    /// Last search read of each point of a tiered collection; keeps points warm.
+    tier_reads: ReadLog,
+    /// When read tracking started (unix secs); no point is demoted before a full window has been tracked.
+    tier_reads_since: i64,
+    /// L4 write-ahead log (PAGI_L4_WAL_DIR); None sends upserts straight to the store.
+    wal: Option<Wal>,
+    /// Where SnapshotKb writes and RestoreKb reads KB backups (PAGI_SNAPSHOT_DIR).
+    snapshots: SnapshotDir,
+    /// Source of L2 version times, retention/decay cutoffs and snapshot names; shared with the Watchdog.
+    clock: Clock,
+}
+
+/// Ids per L4 delete while pruning.
+const PRUNE_CHUNK: usize = 1000;
+/// ScrollKb page size when the request leaves it at 0.
+const SCROLL_PAGE_DEFAULT: usize = 256;
+/// ScanMemory page size when the request leaves it at 0.
+const SCAN_MEMORY_PAGE_DEFAULT: usize = 100;
+
+/// ScrollKb's server stream.
+pub type KbPageStream = ReceiverStream<Result<ScrollKbPage, Status>>;
+/// Deepest hit a search can page to (offset + limit).
+const MAX_SEARCH_WINDOW: usize = 1000;
+
+/// Document text of a hit for the cross-encoder: its `content` (or `snippet`) payload field.
+fn hit_text(p: &ScoredPoint) -> String {
+    p.payload
+        .get("content")
+        .or_else(|| p.payload.get("snippet"))
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Qdrant errors that indicate an outage (vs. a bad request such as an unknown collection).
+/// qdrant-client wraps its own tonic version in anyhow, so classification is by message.
+fn is_outage(err: &str) -> bool {
+    let err = err.to_lowercase();
+    ["unavailable", "deadline", "transport", "connect", "timed out", "cancelled", "broken pipe"]
+        .iter()
+        .any(|needle| err.contains(needle))
+}
+
+/// A point for `spec`'s KB carries exactly its named vectors (at their dims), or none for unnamed KBs.
+/// AccessMemory key as stored for `namespace`: "<key>@<namespace>" on layers 1–2, and the namespace's
+/// collection in "<kb_name>/<point_id>" keys on layers 4 and 7. The default (empty) namespace keeps keys as is.
+pub fn scoped_key(layer: i32, namespace: &str, key: &str) -> Result<String, Status> {
+    if namespace.is_empty() {
+        return Ok(key.to_string());
+    }
+    kb_registry::check_namespace(namespace).map_err(Status::invalid_argument)?;
+    match layer {
+        4 | 7 => match key.split_once('/') {
+            Some((kb, id)) => {
+                let collection = kb_registry::namespaced(kb, namespace).map_err(Status::invalid_argument)?;
+                Ok(format!("{}/{}", collection, id))
+            }
+            // Malformed; `access` reports it.
+            None => Ok(key.to_string()),
+        },
+        // Lineage is keyed by reasoning_id, not namespaced.
+        6 => Ok(key.to_string()),
+        _ => Ok(format!("{}{}{}", key, NAMESPACE_SEP, namespace)),
+    }
+}
+
+fn check_vectors(spec: &KbSpec, point: &VectorPoint) -> Result<(), String> {
+    if spec.vectors.is_empty() {
+        if !point.vectors.is_empty() {
+            return Err(format!("{} has no named vectors; point {} sets `vectors`", spec.name, point.id));
+        }
+        return Ok(());
+    }
+    if let Some(name) = point.vectors.keys().find(|n| !spec.vectors.contains_key(*n)) {
+        return Err(format!("{}: point {} has unknown vector {:?}", spec.name, point.id, name));
+    }
+    for (name, &dim) in &spec.vectors {
+        match point.vectors.get(name) {
+            Some(v) if v.data.len() == dim => {}
+            Some(v) => {
+                return Err(format!(
+                    "{}: point {} vector {:?} has dim {}, expected {}",
+                    spec.name,
+                    point.id,
+                    name,
+                    v.data.len(),
+                    dim
+                ))
+            }
+            None => return Err(format!("{}: point {} is missing vector {:?}", spec.name, point.id, name)),
+        }
+    }
+    Ok(())
 }
 
 impl MemoryManager {
@@ -38,10 +341,68 @@ impl MemoryManager {
             .unwrap_or(1536)
     }
 
-    /// Create and connect to Qdrant at URI from PAGI_QDRANT_URI. Use init_kbs() after to create collections.
+    fn env_u64(name: &str, default: u64) -> u64 {
+        std::env::var(name)
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(default)
+    }
+
+    /// Breaker from PAGI_QDRANT_BREAKER_THRESHOLD (default 5) and PAGI_QDRANT_BREAKER_COOLDOWN_SECS (default 30).
+    fn breaker_from_env() -> CircuitBreaker {
+        CircuitBreaker::new(
+            Self::env_u64("PAGI_QDRANT_BREAKER_THRESHOLD", 5) as u32,
+            Duration::from_secs(Self::env_u64("PAGI_QDRANT_BREAKER_COOLDOWN_SECS", 30)),
+        )
+    }
+
+    /// PAGI_QDRANT_RETRY_ATTEMPTS (default 3), PAGI_QDRANT_RETRY_BACKOFF_MS (default 100, doubling per retry) and
+    /// PAGI_QDRANT_RETRY_MAX_BACKOFF_MS (default 2000).
+    fn retry_from_env() -> RetryPolicy {
+        RetryPolicy::new(
+            Self::env_u64("PAGI_QDRANT_RETRY_ATTEMPTS", 3) as u32,
+            Duration::from_millis(Self::env_u64("PAGI_QDRANT_RETRY_BACKOFF_MS", 100)),
+            Duration::from_millis(Self::env_u64("PAGI_QDRANT_RETRY_MAX_BACKOFF_MS", 2000)),
+        )
+    }
+
+    /// Create the L4 backend from PAGI_VECTOR_BACKEND: "qdrant" (default) connects to PAGI_QDRANT_URI,
+    /// "memory" uses an in-process HNSW store (persisted under PAGI_LOCAL_INDEX_DIR when set) and "pgvector" keeps
+    /// KBs in the Postgres at PAGI_PGVECTOR_URL. With PAGI_DISABLE_QDRANT, L4 falls back to that local index if the
+    /// dir is set, else is disabled. Use init_kbs() after to create collections.
     pub async fn new_async() -> Result<Arc<Self>, Box<dyn std::error::Error + Send + Sync>> {
-        let embedding_dim = Self::embedding_dim_from_env();
-        let zero_vector = vec![0f32; embedding_dim];
+        let l4_timeout = Duration::from_millis(Self::env_u64("PAGI_QDRANT_TIMEOUT_MS", 5000).max(1));
+        let kbs = KbRegistry::from_env(Self::embedding_dim_from_env())?;
+        let wal = Wal::from_env()?;
+        let local = PointLog::from_env()?;
+
+        match std::env::var("PAGI_VECTOR_BACKEND")
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "" | "qdrant" => {}
+            "memory" => {
+                let store = local.map_or_else(MemoryStore::new, MemoryStore::persistent);
+                let mm = Self::build(Some(Box::new(store)), l4_timeout).with_kbs(kbs).with_wal(wal);
+                return Ok(Self::with_embedder(mm));
+            }
+            "pgvector" => {
+                let url = std::env::var("PAGI_PGVECTOR_URL")
+                    .ok()
+                    .filter(|u| !u.trim().is_empty())
+                    .ok_or("PAGI_VECTOR_BACKEND=pgvector needs PAGI_PGVECTOR_URL")?;
+                let pool_size = Self::env_u64("PAGI_PGVECTOR_POOL_SIZE", 4).clamp(1, 64) as u32;
+                let store = PgVectorStore::connect_lazy(&url, pool_size, l4_timeout)?;
+                let mm = Self::build(Some(Box::new(store)), l4_timeout).with_kbs(kbs).with_wal(wal);
+                return Ok(Self::with_embedder(mm));
+            }
+            other => {
+                let expected = "expected qdrant, memory or pgvector";
+                return Err(format!("unknown PAGI_VECTOR_BACKEND {:?} ({})", other, expected).into());
+            }
+        }
 
         // Allow running orchestrator without Qdrant for Phase-3 loop/action testing.
         // This keeps polyglot wiring verifiable even when L4 infra is absent.
@@ -50,199 +411,3218 @@ impl MemoryManager {
             .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes" | "on"))
             .unwrap_or(false)
         {
-            return Ok(Arc::new(Self {
-                l1_sensory: DashMap::new(),
-                l2_working: DashMap::new(),
-                l4_semantic: None,
-                embedding_dim,
-                zero_vector,
-            }));
+            // Qdrant-less dev environments can still search (RCA priors) through the local index.
+            if let Some(log) = local {
+                eprintln!("[MemoryManager] Qdrant disabled; L4 served by the local index in {}", log.dir().display());
+                let mm = Self::build(Some(Box::new(MemoryStore::persistent(log))), l4_timeout);
+                return Ok(Self::with_embedder(mm.with_kbs(kbs).with_wal(wal)));
+            }
+            return Ok(Arc::new(Self::build(None, l4_timeout).with_kbs(kbs)));
         }
 
         let uri = std::env::var("PAGI_QDRANT_URI").unwrap_or_else(|_| "http://localhost:6334".into());
-        let mut config = QdrantClientConfig::from_url(&uri);
-        if let Ok(key) = std::env::var("PAGI_QDRANT_API_KEY") {
-            if !key.is_empty() {
-                config.set_api_key(&key);
+        let api_key = std::env::var("PAGI_QDRANT_API_KEY").ok().filter(|k| !k.is_empty());
+        // Clients connect lazily, so a pool costs nothing until calls spread over it.
+        let pool_size = Self::env_u64("PAGI_QDRANT_POOL_SIZE", 4).clamp(1, 64);
+        let mut clients = Vec::new();
+        for _ in 0..pool_size {
+            let mut config = QdrantClientConfig::from_url(&uri);
+            config.set_timeout(l4_timeout);
+            config.set_connect_timeout(l4_timeout);
+            if let Some(key) = &api_key {
+                config.set_api_key(key);
             }
+            clients.push(QdrantClient::new(Some(config)).await?);
         }
-        let l4_semantic = QdrantClient::new(Some(config)).await?;
-        Ok(Arc::new(Self {
+        let rest_uri = std::env::var("PAGI_QDRANT_REST_URI")
+            .ok()
+            .filter(|u| !u.trim().is_empty())
+            .unwrap_or_else(|| vector_store::qdrant_rest_uri(&uri));
+        let store = QdrantStore::new(clients).with_rest(rest_uri, api_key, l4_timeout);
+        Ok(Self::with_embedder(Self::build(Some(Box::new(store)), l4_timeout).with_kbs(kbs).with_wal(wal)))
+    }
+
+    fn with_kbs(mut self, kbs: KbRegistry) -> Self {
+        self.kbs = kbs;
+        self
+    }
+
+    fn with_wal(mut self, wal: Option<Wal>) -> Self {
+        self.wal = wal;
+        self
+    }
+
+    /// Replace the system clock (deterministic tests).
+    #[allow(dead_code)]
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.tier_reads_since = clock.now_secs();
+        self.clock = clock;
+        self
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// Attach the configured embedder (startup only; loading a local model blocks).
+    fn with_embedder(mut mm: Self) -> Arc<Self> {
+        mm.embedder = embedder::from_env(mm.embedding_dim);
+        Arc::new(mm)
+    }
+
+    fn build(l4_semantic: Option<Box<dyn VectorStore>>, l4_timeout: Duration) -> Self {
+        let embedding_dim = Self::embedding_dim_from_env();
+        let blob_policy = BlobPolicy::from_env();
+        Self {
             l1_sensory: DashMap::new(),
             l2_working: DashMap::new(),
-            l4_semantic: Some(l4_semantic),
+            l2_depth: Self::env_u64("PAGI_L2_HISTORY_DEPTH", 16).max(1) as usize,
+            l2_dirty: AtomicBool::new(false),
+            blob_policy,
+            l2_blobs: Interner::new(blob_policy),
+            l4_semantic,
             embedding_dim,
-            zero_vector,
-        }))
+            zero_vector: vec![0f32; embedding_dim],
+            kbs: KbRegistry::builtin(embedding_dim),
+            embedder: None,
+            l4_timeout,
+            l4_breaker: Self::breaker_from_env(),
+            l4_retry: Self::retry_from_env(),
+            l4_probe_interval: Duration::from_secs(Self::env_u64("PAGI_QDRANT_HEALTH_INTERVAL_SECS", 15)),
+            l4_probe_failing: AtomicBool::new(false),
+            l4_initialized: AtomicBool::new(false),
+            l4_keywords: KeywordIndex::default(),
+            hybrid_default: std::env::var("PAGI_SEARCH_HYBRID")
+                .map(|v| matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+                .unwrap_or(false),
+            recency_halflife_secs: Self::env_u64("PAGI_SEARCH_RECENCY_HALFLIFE_SECS", 86_400).max(1),
+            mmr_lambda: std::env::var("PAGI_SEARCH_MMR_LAMBDA")
+                .ok()
+                .and_then(|s| s.trim().parse::<f32>().ok())
+                .filter(|l| l.is_finite())
+                .map_or(0.7, |l| l.clamp(0.0, 1.0)),
+            feedback_weight: feedback::weight_from_env(),
+            feedback_lock: tokio::sync::Mutex::new(()),
+            cross_encoder: CrossEncoder::from_env(),
+            hot: HotTracker::from_env(),
+            search_cache: SearchCache::from_env(),
+            decay: Decay::from_env(),
+            dedup_default: DedupMode::from_env(),
+            counters: MemoryCounters::default(),
+            ensured_kbs: DashMap::new(),
+            retention_stats: DashMap::new(),
+            archive: Archive::from_env(),
+            cold_tiers: DashMap::new(),
+            tier_reads: ReadLog::default(),
+            tier_reads_since: Clock::system().now_secs(),
+            wal: None,
+            snapshots: SnapshotDir::from_env(),
+            clock: Clock::system(),
+        }
     }
 
-    /// Generic init for 8 KBs; dimensions from PAGI_EMBEDDING_DIM (default 1536), cosine distance.
+    /// Create the registry's KBs (8 built-ins plus PAGI_KB_FILE additions); existing collections must
+    /// match their configured dim and distance. A configured quantization is (re)applied to every KB, which
+    /// migrates existing collections in place.
     pub async fn init_kbs(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let Some(l4) = self.l4_semantic.as_ref() else {
-            // Qdrant disabled; L4 init is a no-op.
+        let Some(l4) = self.l4_semantic.as_deref() else {
+            // L4 disabled; init is a no-op.
             return Ok(());
         };
-        let dim = self.embedding_dim as u64;
-        let kb_names = [
-            "kb_core",
-            "kb_skills",
-            "kb_1",
-            "kb_2",
-            "kb_3",
-            "kb_4",
-            "kb_5",
-            "kb_6",
-        ];
-        for name in kb_names {
-            if l4.has_collection(name).await? {
-                continue;
+        for spec in self.kbs.specs() {
+            Self::create_if_missing(l4, spec).await?;
+            self.create_cold_tier(l4, spec).await?;
+        }
+        Ok(())
+    }
+
+    /// Create one extra KB (e.g. kb_provenance) from its registry spec; no-op if present or L4 disabled.
+    pub async fn ensure_kb(&self, name: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match self.l4_semantic.as_deref() {
+            Some(l4) => {
+                let spec = self.kbs.get(name);
+                Self::create_if_missing(l4, &spec).await?;
+                self.create_cold_tier(l4, &spec).await?;
+                self.ensured_kbs.insert(name.to_string(), ());
+                Ok(())
             }
-            l4
-                .create_collection(&CreateCollection {
-                    collection_name: name.into(),
-                    vectors_config: Some(VectorsConfig {
-                        config: Some(vectors_config::Config::Params(VectorParams {
-                            size: dim,
-                            distance: Distance::Cosine.into(),
-                        })),
-                    }),
-                    ..Default::default()
-                })
-                .await?;
+            None => Ok(()),
+        }
+    }
+
+    /// Collection for `kb_name` under `namespace`, created on first use; `kb_name` for the default namespace.
+    async fn namespace_kb(&self, kb_name: &str, namespace: &str) -> Result<String, Status> {
+        let collection = kb_registry::namespaced(kb_name, namespace).map_err(Status::invalid_argument)?;
+        if !namespace.is_empty() && !self.ensured_kbs.contains_key(&collection) {
+            self.ensure_kb(&collection)
+                .await
+                .map_err(|e| Status::unavailable(format!("create {}: {}", collection, e)))?;
+        }
+        Ok(collection)
+    }
+
+    /// Cold tier of a tiered KB's collection, created beside it; no-op for untiered KBs.
+    async fn create_cold_tier(
+        &self,
+        l4: &dyn VectorStore,
+        spec: &KbSpec,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(cold) = spec.cold_tier() {
+            Self::create_if_missing(l4, &cold).await?;
+            self.cold_tiers.insert(spec.name.clone(), cold.name);
+        }
+        Ok(())
+    }
+
+    /// `collection`'s cold tier, when it has one.
+    fn cold_tier(&self, collection: &str) -> Option<String> {
+        self.cold_tiers.get(collection).map(|c| c.value().clone())
+    }
+
+    /// Points of `ids` from either tier of `collection` (the cold tier is asked only for ids the hot one lacks).
+    async fn get_tiered(
+        &self,
+        l4: &dyn VectorStore,
+        collection: &str,
+        ids: Vec<String>,
+    ) -> Result<Vec<VectorPoint>, Status> {
+        let mut points = self.guarded("get", l4.get(collection, ids.clone())).await?;
+        if let Some(cold) = self.cold_tier(collection).filter(|_| points.len() < ids.len()) {
+            let found: HashSet<&str> = points.iter().map(|p| p.id.as_str()).collect();
+            let missing: Vec<String> = ids.into_iter().filter(|id| !found.contains(id.as_str())).collect();
+            points.extend(self.guarded("get", l4.get(&cold, missing)).await?);
+        }
+        Ok(points)
+    }
+
+    /// Vector search over `collection` and its cold tier, merged into one ranking of at most `limit` hits.
+    async fn search_tiered(
+        &self,
+        op: &str,
+        collection: &str,
+        vector_name: &str,
+        vector: &[f32],
+        limit: usize,
+        filter: &Option<SearchFilter>,
+    ) -> Result<Vec<ScoredPoint>, Status> {
+        let l4 = self.l4_or_disabled()?;
+        let cold = self.cold_tier(collection);
+        let cold = cold.as_deref();
+        let distance = self.kbs.get(collection).distance;
+        let search = || async move {
+            let hot = l4.search(collection, vector_name, vector.to_vec(), limit, filter.clone());
+            let cold = async {
+                match cold {
+                    Some(cold) => l4.search(cold, vector_name, vector.to_vec(), limit, filter.clone()).await,
+                    None => Ok(Vec::new()),
+                }
+            };
+            let (hot, cold) = tokio::try_join!(hot, cold)?;
+            Ok::<_, String>(tiering::merge(hot, cold, distance, limit))
+        };
+        self.guarded_retry(op, search).await
+    }
+
+    async fn create_if_missing(l4: &dyn VectorStore, spec: &KbSpec) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Self::create_or_verify(l4, spec).await?;
+        if let Some(quantization) = spec.quantization {
+            l4.set_quantization(&spec.name, quantization)
+                .await
+                .map_err(|e| format!("{}: set {} quantization: {}", spec.name, quantization.as_str(), e))?;
         }
         Ok(())
     }
 
+    async fn create_or_verify(l4: &dyn VectorStore, spec: &KbSpec) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match l4.describe_collection(&spec.name).await? {
+            Some(shape) if shape == spec.layout() => Ok(()),
+            Some(shape) => Err(format!(
+                "{} exists as {} but is configured as {}; re-index or fix PAGI_KB_FILE",
+                spec.name,
+                shape.describe(),
+                spec.shape()
+            )
+            .into()),
+            None => Ok(l4.create_collection(spec).await?),
+        }
+    }
+
+    /// False when L4 is disabled (PAGI_DISABLE_QDRANT with the qdrant backend).
+    pub fn l4_enabled(&self) -> bool {
+        self.l4_semantic.is_some()
+    }
+
+    /// Unnamed vector dim of a KB (its registry entry, else PAGI_EMBEDDING_DIM).
+    pub fn kb_dim(&self, kb_name: &str) -> usize {
+        self.kbs.get(kb_name).dim
+    }
+
+    /// True while the Qdrant breaker is open or probing, or the last health probe failed; failing searches are
+    /// served empty.
+    pub fn l4_degraded(&self) -> bool {
+        self.l4_breaker.state() != BreakerState::Closed || self.l4_probe_failing.load(Ordering::Relaxed)
+    }
+
+    /// Empty search result that tells callers L4 was not consulted.
+    fn degraded_search(source: &str) -> PointSearch {
+        PointSearch {
+            points: vec![],
+            degraded: true,
+            source: source.to_string(),
+            next_offset: 0,
+        }
+    }
+
+    /// L4 dependency status for GetHealth.
+    pub fn health(&self) -> HealthResponse {
+        let breaker = self.l4_breaker.state();
+        let l4_state = if !self.l4_enabled() {
+            "disabled"
+        } else if self.l4_degraded() {
+            "degraded"
+        } else {
+            "ok"
+        };
+        let l4_probe = if !self.l4_enabled() || self.l4_probe_interval.is_zero() {
+            "off"
+        } else if self.l4_probe_failing.load(Ordering::Relaxed) {
+            "failing"
+        } else {
+            "ok"
+        };
+        HealthResponse {
+            ok: l4_state != "degraded",
+            l4_state: l4_state.to_string(),
+            l4_breaker: breaker.as_str().to_string(),
+            l4_probe: l4_probe.to_string(),
+            retention: {
+                let mut stats: Vec<RetentionStats> = self.retention_stats.iter().map(|e| e.value().clone()).collect();
+                stats.sort_by(|a, b| a.kb_name.cmp(&b.kb_name));
+                stats
+            },
+        }
+    }
+
+    /// Run one Qdrant call under the timeout and breaker. Outages (timeouts, transport errors)
+    /// count toward tripping; request errors reset the failure streak like any answered call.
+    async fn guarded<T, E, F>(&self, op: &str, fut: F) -> Result<T, Status>
+    where
+        E: std::fmt::Display,
+        F: std::future::Future<Output = Result<T, E>>,
+    {
+        if !self.l4_breaker.allow() {
+            return Err(Status::unavailable(format!("L4 circuit open; {} skipped", op)));
+        }
+        match tokio::time::timeout(self.l4_timeout, fut).await {
+            Ok(Ok(v)) => {
+                self.l4_breaker.on_success();
+                Ok(v)
+            }
+            Ok(Err(e)) => {
+                let msg = e.to_string();
+                if is_outage(&msg) {
+                    self.l4_breaker.on_failure();
+                    Err(Status::unavailable(format!("L4 {}: {}", op, msg)))
+                } else {
+                    self.l4_breaker.on_success();
+                    Err(Status::internal(msg))
+                }
+            }
+            Err(_) => {
+                self.l4_breaker.on_failure();
+                Err(Status::deadline_exceeded(format!(
+                    "L4 {} timed out after {:?}",
+                    op, self.l4_timeout
+                )))
+            }
+        }
+    }
+
+    /// `guarded` with retries: an outage (UNAVAILABLE / DEADLINE_EXCEEDED) is retried after the policy's
+    /// backoff while the breaker stays closed and the health probe passes. `call` builds a fresh future per try.
+    async fn guarded_retry<T, E, F, Fut>(&self, op: &str, mut call: F) -> Result<T, Status>
+    where
+        E: std::fmt::Display,
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, E>>,
+    {
+        let mut retry = 0;
+        loop {
+            let result = self.guarded(op, call()).await;
+            let outage = result
+                .as_ref()
+                .is_err_and(|e| matches!(e.code(), tonic::Code::Unavailable | tonic::Code::DeadlineExceeded));
+            retry += 1;
+            if !outage || retry >= self.l4_retry.attempts || self.l4_degraded() {
+                return result;
+            }
+            let delay = self.l4_retry.backoff(retry);
+            self.counters.l4_retries.fetch_add(1, Ordering::Relaxed);
+            if let Err(e) = &result {
+                eprintln!(
+                    "[MemoryManager] L4 {} failed (attempt {}/{}): {}; retrying in {:?}",
+                    op,
+                    retry,
+                    self.l4_retry.attempts,
+                    e.message(),
+                    delay
+                );
+            }
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// One health probe under the L4 timeout; flips the degraded flag (logged on change). Returns whether it passed.
+    pub async fn probe_l4(&self) -> bool {
+        let Some(l4) = self.l4_semantic.as_deref() else {
+            return true;
+        };
+        let passed = match tokio::time::timeout(self.l4_timeout, l4.health_check()).await {
+            Ok(Ok(())) => true,
+            Ok(Err(e)) => {
+                eprintln!("[MemoryManager] L4 health probe failed: {}", e);
+                false
+            }
+            Err(_) => {
+                eprintln!("[MemoryManager] L4 health probe timed out after {:?}", self.l4_timeout);
+                false
+            }
+        };
+        if self.l4_probe_failing.swap(!passed, Ordering::Relaxed) == passed {
+            eprintln!("[MemoryManager] L4 {}", if passed { "recovered" } else { "degraded (health probe)" });
+        }
+        passed
+    }
+
+    /// Startup dependency check for L4: until it first passes, create the KBs and replay the WAL; afterwards
+    /// run the health probe. Ok when L4 is disabled.
+    pub async fn l4_ready(&self) -> Result<(), String> {
+        if !self.l4_enabled() {
+            return Ok(());
+        }
+        if !self.l4_initialized.load(Ordering::Acquire) {
+            self.init_kbs().await.map_err(|e| e.to_string())?;
+            self.l4_initialized.store(true, Ordering::Release);
+            self.replay_wal().await;
+            return Ok(());
+        }
+        if self.probe_l4().await {
+            Ok(())
+        } else {
+            Err("health probe failed".to_string())
+        }
+    }
+
+    /// Background health probe every PAGI_QDRANT_HEALTH_INTERVAL_SECS (default 15; 0 disables).
+    pub fn spawn_health_probe(self: &Arc<Self>) {
+        if self.l4_probe_interval.is_zero() || !self.l4_enabled() {
+            return;
+        }
+        let memory = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(memory.l4_probe_interval);
+            loop {
+                interval.tick().await;
+                memory.probe_l4().await;
+            }
+        });
+    }
+
+    /// Empty in-process HNSW L4 whose KBs are `dim`-sized, regardless of PAGI_VECTOR_BACKEND.
+    pub fn in_memory(dim: usize) -> Self {
+        let l4_timeout = Duration::from_millis(Self::env_u64("PAGI_QDRANT_TIMEOUT_MS", 5000).max(1));
+        Self::build(Some(Box::new(MemoryStore::new())), l4_timeout).with_kbs(KbRegistry::builtin(dim))
+    }
+
+    /// in_memory() sharing this manager's embedder (search eval datasets that bring their own documents).
+    pub fn scratch(&self, dim: usize) -> Arc<Self> {
+        let mut mm = Self::in_memory(dim);
+        mm.embedder = self.embedder.clone();
+        mm.clock = self.clock.clone();
+        Arc::new(mm)
+    }
+
     /// Sync constructor for tests without Qdrant; L4 operations will fail.
     #[allow(dead_code)]
     pub fn new_stub() -> Arc<Self> {
         unimplemented!("Use new_async() for production; stub only for unit tests without Qdrant")
     }
 
-    /// Access memory by layer (1–7), key, and optional value for writes.
-    pub fn access(&self, layer: i32, key: &str, value: Option<&str>) -> (String, bool) {
-        match layer {
-            1 => {
-                if let Some(v) = value {
-                    self.l1_sensory.insert(key.to_string(), v.as_bytes().to_vec());
+    /// Access memory by layer (1–7), key, and optional value for writes; `success` is false on a read miss.
+    /// Layer 4 reads only the hot-memory cache and layer 7 the archive (key "<kb_name>/<point_id>" for both).
+    /// Layers or operations without a backend here fail (see `layer_capabilities`) rather than answer empty;
+    /// layer 6 (lineage) is served by the orchestrator, not the memory manager.
+    pub fn access(&self, layer: i32, key: &str, value: Option<&str>) -> Result<(String, bool), Status> {
+        let (data, success) = match layer {
+            1 => match value {
+                Some(v) => {
+                    self.write_l1(key, Bytes::copy_from_slice(v.as_bytes()));
+                    (v.to_string(), true)
                 }
-                (
-                    self.l1_sensory
-                        .get(key)
-                        .map(|g| String::from_utf8_lossy(g.value()).into_owned())
-                        .unwrap_or_default(),
-                    true,
-                )
-            }
-            2 => {
-                if let Some(v) = value {
-                    self.l2_working.insert(key.to_string(), v.to_string());
+                None => self
+                    .read_l1(key)
+                    .map_or((String::new(), false), |b| (String::from_utf8_lossy(&b).into_owned(), true)),
+            },
+            2 => match value {
+                Some(v) => {
+                    self.write_l2(key, Arc::from(v));
+                    (v.to_string(), true)
                 }
-                (
-                    self.l2_working
-                        .get(key)
-                        .map(|g| g.value().clone())
-                        .unwrap_or_default(),
-                    true,
-                )
+                None => self.read_l2(key).map_or((String::new(), false), |v| (v.to_string(), true)),
+            },
+            4 if value.is_none() => {
+                let pinned = key.split_once('/').and_then(|(kb, id)| self.hot.pinned(kb, id));
+                self.counters.l4_cache.record(pinned.is_some());
+                pinned.map_or((String::new(), false), |p| (p.payload.get("content").cloned().unwrap_or_default(), true))
+            }
+            4 => return Err(Status::invalid_argument("layer 4 writes go through UpsertVectors")),
+            7 if value.is_none() => {
+                let archive = self
+                    .archive
+                    .as_ref()
+                    .ok_or_else(|| Status::failed_precondition("layer 7 (archive) not configured (set PAGI_ARCHIVE_DIR)"))?;
+                let (kb_name, id) = key
+                    .split_once('/')
+                    .ok_or_else(|| Status::invalid_argument("layer 7 keys are \"<kb_name>/<point_id>\""))?;
+                let (records, _) = archive.recall(&RecallArchiveRequest {
+                    kb_name: kb_name.to_string(),
+                    ids: vec![id.to_string()],
+                    limit: 1,
+                    ..Default::default()
+                });
+                records
+                    .into_iter()
+                    .next()
+                    .map_or((String::new(), false), |r| (r.payload.get("content").cloned().unwrap_or_default(), true))
+            }
+            7 => return Err(Status::invalid_argument("layer 7 is written by retention; use RecallArchive to rehydrate")),
+            6 => return Err(Status::unimplemented("layer 6 (lineage) is served by the orchestrator's lineage store")),
+            other => {
+                let cap = self.layer_capabilities().into_iter().find(|c| c.layer == other);
+                return Err(match cap {
+                    Some(c) if c.backend.is_empty() => {
+                        Status::failed_precondition(format!("layer {} ({}) not configured: {}", other, c.name, c.note))
+                    }
+                    Some(c) => Status::failed_precondition(format!(
+                        "layer {} ({}) is not served by AccessMemory: {}",
+                        other, c.name, c.note
+                    )),
+                    None => Status::invalid_argument(format!("unknown memory layer {} (expected 1-7)", other)),
+                });
+            }
+        };
+        Ok((data, success))
+    }
+
+    /// L1 write sharing `value`; the key is only allocated the first time it is written.
+    pub fn write_l1(&self, key: &str, value: Bytes) {
+        match self.l1_sensory.get_mut(key) {
+            Some(mut slot) => *slot = value,
+            None => {
+                self.l1_sensory.insert(key.to_string(), value);
             }
-            _ => (String::new(), true),
         }
     }
 
-    /// L4 semantic search. Uses query_vector when provided (Python embed); else zero vector (stub).
-    /// When Qdrant is disabled, returns empty hits so callers (e.g. propose_patch) can still run.
-    pub async fn semantic_search(
-        &self,
-        req: SearchRequest,
-    ) -> Result<SearchResponse, Status> {
-        let Some(l4) = self.l4_semantic.as_ref() else {
-            return Ok(SearchResponse { hits: vec![] });
+    /// Zero-copy L1 read: a refcounted handle to the stored bytes (counted as a hit or miss).
+    pub fn read_l1(&self, key: &str) -> Option<Bytes> {
+        let found = self.l1_sensory.get(key).map(|g| g.value().clone());
+        self.counters.l1.record(found.is_some());
+        found
+    }
+
+    /// L2 write sharing `value`; returns the new version. The clock is read before the key's shard is locked.
+    pub fn write_l2(&self, key: &str, value: Arc<str>) -> u64 {
+        let value = self.l2_blobs.intern(value);
+        let written_at_ms = self.clock.now_ms();
+        let push = |history: &mut VecDeque<L2Version>| {
+            let version = history.back().map_or(1, |h| h.version + 1);
+            history.push_back(L2Version {
+                version,
+                written_at_ms,
+                value,
+            });
+            while history.len() > self.l2_depth {
+                history.pop_front();
+            }
+            version
         };
-        let limit = req.limit.max(1).min(100) as u64;
-        let dim = self.embedding_dim;
-        let query_vector: Vec<f32> = if req.query_vector.len() == dim {
-            req.query_vector
+        let version = match self.l2_working.get_mut(key) {
+            Some(mut history) => push(&mut history),
+            None => push(&mut self.l2_working.entry(key.to_string()).or_default()),
+        };
+        // Skip the store while the flag is already set, so concurrent writers do not keep bouncing its line.
+        if !self.l2_dirty.load(Ordering::Relaxed) {
+            self.l2_dirty.store(true, Ordering::Relaxed);
+        }
+        version
+    }
+
+    /// Zero-copy read of an L2 key's latest value (counted as a hit or miss and in the hot-memory report).
+    pub fn read_l2(&self, key: &str) -> Option<Arc<str>> {
+        self.hot.record_l2(key);
+        let found = self.l2_working.get(key).and_then(|g| g.back().map(|h| Arc::clone(&h.value)));
+        self.counters.l2.record(found.is_some());
+        found
+    }
+
+    /// What each layer serves in this process (MemoryResponse.layers), so clients need not probe.
+    pub fn layer_capabilities(&self) -> Vec<LayerCapability> {
+        let layer = |layer: i32, name: &str, backend: &str, readable: bool, writable: bool, note: &str| LayerCapability {
+            layer,
+            name: name.to_string(),
+            backend: backend.to_string(),
+            readable,
+            writable,
+            note: note.to_string(),
+        };
+        let l4_backend = self.l4_semantic.as_deref().map_or("", |l4| l4.name());
+        let l4_note = if l4_backend.is_empty() {
+            "L4 disabled (PAGI_DISABLE_QDRANT)"
         } else {
-            self.zero_vector.clone()
+            "reads: hot-memory cache, key \"<kb_name>/<point_id>\"; search/write via SemanticSearch/UpsertVectors"
         };
+        let (l7_backend, l7_note) = match self.archive {
+            Some(_) => ("jsonl", "key \"<kb_name>/<point_id>\"; written by retention, rehydrated by RecallArchive"),
+            None => ("", "set PAGI_ARCHIVE_DIR"),
+        };
+        vec![
+            layer(1, "sensory", "in_process", true, true, "raw values by key"),
+            layer(2, "working", "in_process", true, true, "versioned values by key; history via AccessMemoryAt"),
+            layer(3, "long_term", "", false, false, "no long-term store backend is implemented yet"),
+            layer(4, "semantic", l4_backend, !l4_backend.is_empty(), false, l4_note),
+            layer(5, "procedural", "skills_registry", false, false, "skills run through ExecuteAction/RunPipeline"),
+            layer(6, "lineage", "lineage", true, false, "key: reasoning_id; events as from TraceQuery"),
+            layer(7, "archive", l7_backend, !l7_backend.is_empty(), false, l7_note),
+        ]
+    }
 
-        let search_req = SearchPoints {
-            collection_name: req.kb_name.clone(),
-            vector: query_vector,
-            filter: None,
-            limit,
-            with_payload: Some(true.into()),
-            params: None,
-            score_threshold: None,
-            offset: None,
-            vector_name: None,
-            with_vectors: None,
+    /// GetMemoryStats: per-layer entries, bytes and hit/miss counters, plus point counts of known L4 KBs.
+    /// L4 bytes are estimated from vector sizes (payloads are not counted).
+    pub async fn stats(&self) -> MemoryStatsResponse {
+        let l1_bytes = self.l1_sensory.iter().map(|e| (e.key().len() + e.value().len()) as u64).sum();
+        let l2_bytes = self
+            .l2_working
+            .iter()
+            .map(|e| (e.key().len() + e.value().iter().map(|h| h.value.len()).sum::<usize>()) as u64)
+            .sum();
+        let search_cache = self.search_cache.stats();
+        let mut l4 = LayerStats {
+            layer: 4,
+            name: "semantic".to_string(),
+            hits: self.counters.l4.hits.load(Ordering::Relaxed),
+            misses: self.counters.l4.misses.load(Ordering::Relaxed),
+            degraded: self.counters.l4_degraded.load(Ordering::Relaxed),
+            retries: self.counters.l4_retries.load(Ordering::Relaxed),
+            cache_hits: self.counters.l4_cache.hits.load(Ordering::Relaxed),
+            cache_misses: self.counters.l4_cache.misses.load(Ordering::Relaxed),
+            search_cache_hits: search_cache.hits,
+            search_cache_misses: search_cache.misses,
+            search_cache_invalidations: search_cache.invalidations,
+            search_cache_entries: search_cache.entries,
+            ..Default::default()
+        };
+        let mut collections = Vec::new();
+        if let Some(store) = self.l4_semantic.as_deref() {
+            let mut names: BTreeSet<String> = self.kbs.specs().map(|s| s.name.clone()).collect();
+            names.extend(self.ensured_kbs.iter().map(|e| e.key().clone()));
+            names.extend(self.cold_tiers.iter().map(|e| e.value().clone()));
+            for name in names {
+                // Floats per point: named spaces all hold every point.
+                let spec = self.kbs.get(name.strip_suffix(kb_registry::COLD_SUFFIX).unwrap_or(&name));
+                let dim = if spec.vectors.is_empty() { spec.dim } else { spec.vectors.values().sum() };
+                let mut c = CollectionStats {
+                    kb_name: name.clone(),
+                    dim: dim as u32,
+                    ..Default::default()
+                };
+                match self.guarded("count", store.point_count(&name)).await {
+                    Ok(Some(points)) => {
+                        c.exists = true;
+                        c.points = points;
+                        l4.entries += points;
+                        l4.bytes += points * dim as u64 * 4;
+                    }
+                    Ok(None) => {}
+                    Err(e) => c.error = e.message().to_string(),
+                }
+                collections.push(c);
+            }
+        }
+        let layer = |layer: i32, name: &str, entries: usize, bytes: u64, counter: &HitCounter| LayerStats {
+            layer,
+            name: name.to_string(),
+            entries: entries as u64,
+            bytes,
+            hits: counter.hits.load(Ordering::Relaxed),
+            misses: counter.misses.load(Ordering::Relaxed),
+            ..Default::default()
         };
+        MemoryStatsResponse {
+            layers: vec![
+                layer(1, "sensory", self.l1_sensory.len(), l1_bytes, &self.counters.l1),
+                layer(2, "working", self.l2_working.len(), l2_bytes, &self.counters.l2),
+                l4,
+            ],
+            collections,
+            l4_state: self.health().l4_state,
+        }
+    }
 
-        let response = l4
-            .search_points(&search_req)
-            .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+    /// L2 keys whose latest write is older than `cutoff_ms`, with their retained history (oldest first).
+    pub fn stale_l2(&self, cutoff_ms: i64) -> Vec<(String, Vec<L2Version>)> {
+        self.l2_working
+            .iter()
+            .filter(|e| e.value().back().is_some_and(|h| h.written_at_ms < cutoff_ms))
+            .map(|e| (e.key().clone(), e.value().iter().cloned().collect()))
+            .collect()
+    }
 
-        let hits: Vec<SearchHit> = response
-            .result
-            .into_iter()
-            .map(|p| {
-                let document_id = p
-                    .id
-                    .and_then(|id| id.point_id_options)
-                    .map(|opt| match opt {
-                        PointIdOptions::Num(n) => n.to_string(),
-                        PointIdOptions::Uuid(s) => s,
-                    })
-                    .unwrap_or_else(String::new);
-                let content_snippet = p
-                    .payload
-                    .get("content")
-                    .or_else(|| p.payload.get("snippet"))
-                    .and_then(|v| {
-                        if let Some(Kind::StringValue(s)) = v.kind.as_ref() {
-                            Some(s.clone())
-                        } else {
-                            None
-                        }
-                    })
-                    .unwrap_or_else(|| "Snippet stub".to_string());
-                SearchHit {
-                    document_id,
-                    score: p.score,
-                    content_snippet,
-                }
-            })
+    /// Remove an L2 key if its latest version is still `version` (a newer write keeps it); true when evicted.
+    pub fn evict_l2(&self, key: &str, version: u64) -> bool {
+        let evicted = self
+            .l2_working
+            .remove_if(key, |_, history| history.back().is_some_and(|h| h.version == version))
+            .is_some();
+        if evicted {
+            self.l2_dirty.store(true, Ordering::Relaxed);
+        }
+        evicted
+    }
+
+    /// GetHotMemory: most-read L2 keys and L4 points.
+    pub fn hot_report(&self, limit: usize) -> HotMemoryReport {
+        self.hot.report(limit)
+    }
+
+    /// L2 snapshot file from PAGI_L2_SNAPSHOT_PATH; None (unset or empty) disables persistence.
+    pub fn l2_snapshot_path() -> Option<PathBuf> {
+        std::env::var("PAGI_L2_SNAPSHOT_PATH")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// Write L2 (all retained versions) to `path` via temp file + rename; returns the key count. Each large value
+    /// is written once, compressed, however many versions hold it.
+    pub fn snapshot_l2(&self, path: &Path) -> Result<usize, String> {
+        self.l2_dirty.store(false, Ordering::Relaxed);
+        let entries: BTreeMap<String, VecDeque<L2Version>> = self
+            .l2_working
+            .iter()
+            .map(|e| (e.key().clone(), e.value().clone()))
             .collect();
+        let keys = entries.len();
+        let mut entries = serde_json::to_value(entries).map_err(|e| e.to_string())?;
+        let mut blobs = Blobs::new();
+        blobs::externalize(&mut entries, &self.blob_policy, &HashSet::new(), &mut blobs);
+        let snap = L2Snapshot {
+            version: L2_SNAPSHOT_VERSION,
+            updated_at: self.clock.now_secs(),
+            entries,
+            blobs,
+        };
+        let json = serde_json::to_string(&snap).map_err(|e| e.to_string())?;
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, json).map_err(|e| e.to_string())?;
+        std::fs::rename(&tmp, path).map_err(|e| e.to_string())?;
+        Ok(keys)
+    }
+
+    /// Load an L2 snapshot written by snapshot_l2 (call on startup, before serving). A missing file
+    /// restores nothing; histories are trimmed to the current PAGI_L2_HISTORY_DEPTH.
+    pub fn restore(&self, path: &Path) -> Result<usize, String> {
+        let raw = match std::fs::read_to_string(path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.to_string()),
+        };
+        let mut snap: L2Snapshot = serde_json::from_str(&raw).map_err(|e| e.to_string())?;
+        if !(1..=L2_SNAPSHOT_VERSION).contains(&snap.version) {
+            return Err(format!(
+                "snapshot version {} not in 1..={}",
+                snap.version, L2_SNAPSHOT_VERSION
+            ));
+        }
+        blobs::resolve(&mut snap.entries, &snap.blobs)?;
+        let entries: BTreeMap<String, VecDeque<L2Version>> =
+            serde_json::from_value(snap.entries).map_err(|e| e.to_string())?;
+        let restored = entries.len();
+        for (key, mut history) in entries {
+            while history.len() > self.l2_depth {
+                history.pop_front();
+            }
+            for h in history.iter_mut() {
+                h.value = self.l2_blobs.intern(Arc::clone(&h.value));
+            }
+            self.l2_working.insert(key, history);
+        }
+        Ok(restored)
+    }
 
-        Ok(SearchResponse { hits })
+    /// Restore L2 from PAGI_L2_SNAPSHOT_PATH (call on startup, before serving). No-op when persistence is
+    /// disabled.
+    pub fn restore_l2(&self) {
+        let Some(path) = Self::l2_snapshot_path() else {
+            return;
+        };
+        match self.restore(&path) {
+            Ok(n) => eprintln!("[MemoryManager] restored {} L2 key(s) from {}", n, path.display()),
+            Err(e) => eprintln!("[MemoryManager] L2 restore from {}: {}", path.display(), e),
+        }
     }
 
-    /// L4 upsert: store vector points into a KB collection. Python embeds; Rust owns I/O.
-    pub async fn upsert_vectors(&self, req: UpsertRequest) -> Result<UpsertResponse, Status> {
-        let l4 = self
-            .l4_semantic
-            .as_ref()
-            .ok_or_else(|| Status::failed_precondition("Qdrant disabled (PAGI_DISABLE_QDRANT=true)"))?;
+    /// Register the memory manager's periodic tasks: "l2_snapshot" every PAGI_L2_SNAPSHOT_SECS (default 30) while
+    /// L2 has unsaved writes, when PAGI_L2_SNAPSHOT_PATH is set; "retention", the maintenance pass (decay,
+    /// retention, tiering) every PAGI_RETENTION_INTERVAL_SECS (default 3600; 0 disables), when L4 is enabled and
+    /// has something to maintain; "ttl_sweep" every PAGI_TTL_SWEEP_SECS, when L4 is enabled.
+    pub fn schedule(self: &Arc<Self>, scheduler: Scheduler) -> Scheduler {
+        let path = Self::l2_snapshot_path();
+        let snapshot_every = match path {
+            Some(_) => Duration::from_secs(Self::env_u64("PAGI_L2_SNAPSHOT_SECS", 30).max(1)),
+            None => Duration::ZERO,
+        };
+        let retention_every = if self.l4_enabled() && self.kbs.specs().any(|s| self.is_maintained(s)) {
+            Duration::from_secs(Self::env_u64("PAGI_RETENTION_INTERVAL_SECS", 3600))
+        } else {
+            Duration::ZERO
+        };
+        let sweep_every = match self.l4_enabled() {
+            true => Duration::from_secs(ttl::sweep_secs_from_env()),
+            false => Duration::ZERO,
+        };
+        let memory = Arc::clone(self);
+        let retention = Arc::clone(self);
+        let sweeper = Arc::clone(self);
+        scheduler
+            .add("l2_snapshot", snapshot_every, move || {
+                let result = match &path {
+                    Some(path) if memory.l2_dirty.load(Ordering::Relaxed) => {
+                        memory.snapshot_l2(path).map(|n| format!("{} key(s) saved", n)).map_err(|e| {
+                            memory.l2_dirty.store(true, Ordering::Relaxed);
+                            format!("{}: {}", path.display(), e)
+                        })
+                    }
+                    _ => Ok("unchanged".to_string()),
+                };
+                std::future::ready(result)
+            })
+            .add("retention", retention_every, move || {
+                let memory = Arc::clone(&retention);
+                async move { memory.enforce_retention(memory.clock.now_secs()).await }
+            })
+            .add("ttl_sweep", sweep_every, move || {
+                let memory = Arc::clone(&sweeper);
+                async move { memory.sweep_expired(memory.clock.now_secs()).await }
+            })
+    }
 
-        let mut points: Vec<PointStruct> = Vec::with_capacity(req.points.len());
-        for p in req.points {
-            let mut payload = Payload::new();
-            for (k, v) in p.payload {
-                payload.insert(k, v);
+    /// Delete the points whose `expires_at` has passed (see ttl) from every known KB, both tiers; skipped while
+    /// L4 is degraded. Returns a summary, or the KBs whose sweep failed.
+    pub async fn sweep_expired(&self, now: i64) -> Result<String, String> {
+        let l4 = self.l4_or_disabled().map_err(|e| e.message().to_string())?;
+        if self.l4_degraded() {
+            return Err("skipped: L4 degraded".to_string());
+        }
+        let mut names: BTreeSet<String> = self.kbs.specs().map(|s| s.name.clone()).collect();
+        names.extend(self.ensured_kbs.iter().map(|e| e.key().clone()));
+        let (mut expired, mut failed) = (0, Vec::new());
+        for name in &names {
+            match self.sweep_kb(l4, name, now).await {
+                Ok(0) => {}
+                Ok(n) => {
+                    eprintln!("[MemoryManager] ttl {}: deleted {} expired point(s)", name, n);
+                    expired += n;
+                }
+                Err(e) => {
+                    eprintln!("[MemoryManager] ttl {}: {}", name, e.message());
+                    failed.push(format!("{}: {}", name, e.message()));
+                }
             }
-            points.push(PointStruct::new(PointId::from(p.id), p.vector, payload));
         }
-        let n = points.len();
-        l4
-            .upsert_points_blocking(&req.kb_name, points)
-            .await
-            .map_err(|e| Status::internal(e.to_string()))?;
-        Ok(UpsertResponse {
-            success: true,
-            upserted_count: n as u32,
-        })
+        if !failed.is_empty() {
+            return Err(failed.join("; "));
+        }
+        Ok(format!("{} KB(s), {} expired point(s) deleted", names.len(), expired))
+    }
+
+    /// Expired points of `collection` and its cold tier, deleted from both; returns how many.
+    async fn sweep_kb(&self, l4: &dyn VectorStore, collection: &str, now: i64) -> Result<u64, Status> {
+        let mut ids = Vec::new();
+        for tier in std::iter::once(collection.to_string()).chain(self.cold_tier(collection)) {
+            let mut offset = None;
+            loop {
+                let page = l4.scroll(&tier, offset, PRUNE_CHUNK, Some(ttl::expired_filter(now)), false);
+                let page = self.guarded("scroll", page).await?;
+                ids.extend(page.points.into_iter().map(|p| p.id));
+                offset = page.next_offset;
+                if offset.is_none() {
+                    break;
+                }
+            }
+        }
+        ids.sort_unstable();
+        ids.dedup();
+        for chunk in ids.chunks(PRUNE_CHUNK) {
+            self.delete_vectors(DeleteVectorsRequest {
+                kb_name: collection.to_string(),
+                ids: chunk.to_vec(),
+            })
+            .await?;
+        }
+        Ok(ids.len() as u64)
+    }
+
+    /// KBs visited by the maintenance pass: all registry KBs under decay, else the bounded and tiered ones.
+    fn is_maintained(&self, spec: &KbSpec) -> bool {
+        self.decay.is_some() || spec.retention.is_bounded() || spec.cold_after_secs.is_some()
+    }
+
+    /// One maintenance pass over the registry KBs and the namespace collections used since startup; skipped
+    /// while L4 is degraded. Returns a summary, or the KBs whose pass failed.
+    pub async fn enforce_retention(&self, now: i64) -> Result<String, String> {
+        if self.l4_degraded() {
+            return Err("skipped: L4 degraded".to_string());
+        }
+        let mut specs: Vec<KbSpec> = self.kbs.specs().cloned().collect();
+        specs.extend(
+            self.ensured_kbs
+                .iter()
+                .filter(|e| e.key().contains(NAMESPACE_SEP))
+                .map(|e| self.kbs.get(e.key())),
+        );
+        specs.retain(|s| self.is_maintained(s));
+        let (mut pruned, mut failed) = (0, Vec::new());
+        for spec in &specs {
+            let stats = self.prune_kb(spec, now).await;
+            pruned += stats.pruned_by_decay + stats.pruned_by_age + stats.pruned_by_count;
+            if !stats.error.is_empty() {
+                eprintln!("[MemoryManager] retention {}: {}", spec.name, stats.error);
+                failed.push(format!("{}: {}", spec.name, stats.error));
+            } else if stats.pruned_by_decay + stats.pruned_by_age + stats.pruned_by_count > 0 {
+                eprintln!(
+                    "[MemoryManager] retention {}: pruned {} by decay, {} by age, {} by count of {}",
+                    spec.name, stats.pruned_by_decay, stats.pruned_by_age, stats.pruned_by_count, stats.scanned
+                );
+            }
+            self.retention_stats.insert(spec.name.clone(), stats);
+        }
+        if !failed.is_empty() {
+            return Err(failed.join("; "));
+        }
+        Ok(format!("{} KB(s), {} point(s) pruned", specs.len(), pruned))
+    }
+
+    /// L4 write-ahead log, when enabled (CompactMemory rewrites it).
+    pub fn wal(&self) -> Option<&Wal> {
+        self.wal.as_ref()
+    }
+
+    /// Collections CompactMemory visits for `kb_names`: all known KBs (registry and ensured) when empty, else the
+    /// names as given, each of which must be known.
+    pub fn compaction_targets(&self, kb_names: &[String]) -> Result<Vec<String>, Status> {
+        let mut known: BTreeSet<String> = self.kbs.specs().map(|s| s.name.clone()).collect();
+        known.extend(self.ensured_kbs.iter().map(|e| e.key().clone()));
+        known.extend(self.cold_tiers.iter().map(|e| e.value().clone()));
+        if kb_names.is_empty() {
+            return Ok(known.into_iter().collect());
+        }
+        match kb_names.iter().find(|n| !known.contains(*n)) {
+            Some(unknown) => Err(Status::not_found(format!("unknown KB {:?}", unknown))),
+            None => Ok(kb_names.to_vec()),
+        }
+    }
+
+    /// CompactMemory's L4 pass: the maintenance pass (decay, retention) over `collections` that have a policy,
+    /// the store's optimizer on each when `optimize`, and expired search cache entries. Failures are collected
+    /// per collection; a degraded L4 is skipped entirely.
+    pub async fn compact_l4(&self, collections: &[String], optimize: bool, now: i64) -> L4Compaction {
+        let mut report = L4Compaction {
+            search_cache_purged: self.search_cache.purge_expired(self.clock.now_ms()) as u64,
+            ..Default::default()
+        };
+        let Some(l4) = self.l4_semantic.as_deref() else {
+            return report;
+        };
+        if self.l4_degraded() {
+            report.errors.push("L4 degraded; retention and optimizer skipped".to_string());
+            return report;
+        }
+        for name in collections {
+            let spec = self.kbs.get(name);
+            if self.is_maintained(&spec) {
+                let stats = self.prune_kb(&spec, now).await;
+                if !stats.error.is_empty() {
+                    report.errors.push(format!("{}: retention: {}", name, stats.error));
+                }
+                self.retention_stats.insert(name.clone(), stats.clone());
+                report.retention.push(stats);
+            }
+            if optimize {
+                match self.guarded("optimize", l4.optimize(name)).await {
+                    Ok(purged) => {
+                        report.optimized.push(name.clone());
+                        report.tombstones_purged += purged.unwrap_or(0);
+                    }
+                    Err(e) => report.errors.push(format!("{}: optimize: {}", name, e.message())),
+                }
+            }
+        }
+        report
+    }
+
+    /// Delete pruned points in chunks, archiving each chunk to L7 first when enabled (a failed archive write
+    /// leaves the chunk in L4).
+    pub async fn archive_and_delete(&self, kb_name: &str, ids: &[String], reason: &str, now: i64) -> Result<u64, Status> {
+        let l4 = self.l4_or_disabled()?;
+        for chunk in ids.chunks(PRUNE_CHUNK) {
+            if let Some(archive) = &self.archive {
+                let points = self.get_tiered(l4, kb_name, chunk.to_vec()).await?;
+                archive
+                    .append(kb_name, &points, reason, now)
+                    .map_err(|e| Status::internal(format!("L7 archive: {}", e)))?;
+            }
+            self.delete_vectors(DeleteVectorsRequest {
+                kb_name: kb_name.to_string(),
+                ids: chunk.to_vec(),
+            })
+            .await?;
+        }
+        Ok(ids.len() as u64)
+    }
+
+    /// RecallArchive: archived points matching the request, rehydrated into their KB (fresh `at`, marked
+    /// `rehydrated_from=l7`) when asked.
+    pub async fn recall_archive(&self, req: RecallArchiveRequest) -> Result<RecallArchiveResponse, Status> {
+        let archive = self
+            .archive
+            .as_ref()
+            .ok_or_else(|| Status::failed_precondition("L7 archive disabled (set PAGI_ARCHIVE_DIR)"))?;
+        let (records, segments_scanned) = archive.recall(&req);
+        let mut resp = RecallArchiveResponse {
+            points: records.iter().map(|r| r.to_proto()).collect(),
+            segments_scanned,
+            ..Default::default()
+        };
+        if !req.rehydrate {
+            return Ok(resp);
+        }
+        let now = self.clock.now_secs().to_string();
+        let mut by_kb: BTreeMap<String, Vec<VectorPoint>> = BTreeMap::new();
+        for r in records.into_iter().filter(|r| !r.vector.is_empty() || !r.vectors.is_empty()) {
+            let mut payload = r.payload;
+            payload.insert("at".to_string(), now.clone());
+            payload.insert("rehydrated_from".to_string(), "l7".to_string());
+            by_kb.entry(r.kb_name).or_default().push(VectorPoint {
+                id: r.id,
+                vector: r.vector,
+                payload,
+                vectors: r.vectors.into_iter().map(|(n, data)| (n, DenseVector { data })).collect(),
+            });
+        }
+        for (kb_name, points) in by_kb {
+            self.ensure_kb(&kb_name).await.map_err(|e| Status::internal(e.to_string()))?;
+            let n = self
+                .upsert_vectors(UpsertRequest {
+                    kb_name,
+                    points,
+                    ..Default::default()
+                })
+                .await?;
+            resp.rehydrated += n.upserted_count;
+        }
+        Ok(resp)
+    }
+
+    /// Move tiered points between `spec`'s tiers (tiering.rs), rescore the KB under decay (deleting faded points),
+    /// then delete what its retention policy excludes (by the `at` payload field). Decay and retention see both
+    /// tiers as one KB.
+    async fn prune_kb(&self, spec: &KbSpec, now: i64) -> RetentionStats {
+        let mut stats = RetentionStats {
+            kb_name: spec.name.clone(),
+            last_run_unix: now,
+            ..Default::default()
+        };
+        let Some(l4) = self.l4_semantic.as_deref() else {
+            return stats;
+        };
+        let mut points = match self.guarded("scroll", l4.scan(&spec.name, &decay::FIELDS)).await {
+            Ok(points) => points,
+            Err(e) => {
+                stats.error = e.message().to_string();
+                return stats;
+            }
+        };
+        // Ids now in the cold tier, for payload updates.
+        let mut cold_ids: HashSet<String> = HashSet::new();
+        if let (Some(cold), Some(cold_after)) = (self.cold_tier(&spec.name), spec.cold_after_secs) {
+            match self.tier_kb(l4, &spec.name, &cold, cold_after, &mut points, now).await {
+                Ok((ids, demoted, promoted)) => {
+                    cold_ids = ids;
+                    stats.demoted = demoted;
+                    stats.promoted = promoted;
+                }
+                Err(e) => {
+                    stats.error = e.message().to_string();
+                    return stats;
+                }
+            }
+        }
+        stats.scanned = points.len() as u64;
+        if let Some(decay) = self.decay {
+            let plan = decay.plan(&points, now);
+            match self.archive_and_delete(&spec.name, &plan.delete, "decay", now).await {
+                Ok(n) => stats.pruned_by_decay = n,
+                Err(e) => {
+                    stats.error = e.message().to_string();
+                    return stats;
+                }
+            }
+            let deleted: HashSet<&String> = plan.delete.iter().collect();
+            points.retain(|(id, _)| !deleted.contains(id));
+            let cold = self.cold_tier(&spec.name);
+            for (score, ids) in plan.rescore {
+                let fields = HashMap::from([("decay_score".to_string(), score.to_string())]);
+                let (in_cold, in_hot): (Vec<String>, Vec<String>) =
+                    ids.into_iter().partition(|id| cold_ids.contains(id));
+                let tiers = [(&spec.name, in_hot)].into_iter().chain(cold.as_ref().map(|c| (c, in_cold)));
+                for (collection, ids) in tiers {
+                    for chunk in ids.chunks(PRUNE_CHUNK) {
+                        let update = l4.set_payload(collection, chunk.to_vec(), fields.clone());
+                        match self.guarded("set_payload", update).await {
+                            Ok(_) => {
+                                self.l4_keywords.set_payload(&spec.name, chunk, &fields);
+                                self.search_cache.invalidate(&spec.name);
+                                stats.rescored += chunk.len() as u64;
+                            }
+                            Err(e) => {
+                                stats.error = e.message().to_string();
+                                return stats;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        let timestamps = points
+            .into_iter()
+            .map(|(id, payload)| (id, payload.get("at").and_then(|v| v.parse().ok())))
+            .collect();
+        let (by_age, by_count) = spec.retention.plan(timestamps, now);
+        for (ids, by_age) in [(by_age, true), (by_count, false)] {
+            let reason = if by_age { "age" } else { "count" };
+            match self.archive_and_delete(&spec.name, &ids, reason, now).await {
+                Ok(n) if by_age => stats.pruned_by_age = n,
+                Ok(n) => stats.pruned_by_count = n,
+                Err(e) => {
+                    stats.error = e.message().to_string();
+                    return stats;
+                }
+            }
+        }
+        stats
+    }
+
+    /// Tiering pass over `collection` (whose scanned points are `points`) and its `cold` tier: demote idle points,
+    /// promote read ones and drop stale cold copies. `points` then holds both tiers' points; returns the ids now
+    /// cold and the (demoted, promoted) counts.
+    async fn tier_kb(
+        &self,
+        l4: &dyn VectorStore,
+        collection: &str,
+        cold: &str,
+        cold_after_secs: u64,
+        points: &mut Vec<(String, HashMap<String, String>)>,
+        now: i64,
+    ) -> Result<(HashSet<String>, u64, u64), Status> {
+        let mut cold_points = self.guarded("scroll", l4.scan(cold, &decay::FIELDS)).await?;
+        let reads_since = self.tier_reads_since;
+        let plan = tiering::plan(collection, points, &cold_points, &self.tier_reads, cold_after_secs, reads_since, now);
+        if !plan.stale.is_empty() {
+            self.guarded("delete", l4.delete(cold, plan.stale.clone())).await?;
+            let stale: HashSet<&String> = plan.stale.iter().collect();
+            cold_points.retain(|(id, _)| !stale.contains(id));
+        }
+        let demoted = self.move_points(l4, collection, cold, &plan.demote).await?;
+        let promoted = self.move_points(l4, cold, collection, &plan.promote).await?;
+        self.tier_reads.forget_before(collection, now.saturating_sub(cold_after_secs.min(i64::MAX as u64) as i64));
+        if demoted + promoted > 0 {
+            eprintln!("[MemoryManager] tiering {}: {} demoted to {}, {} promoted", collection, demoted, cold, promoted);
+        }
+        let promote: HashSet<&String> = plan.promote.iter().collect();
+        let mut cold_ids: HashSet<String> = plan.demote.iter().cloned().collect();
+        cold_ids.extend(cold_points.iter().map(|(id, _)| id.clone()).filter(|id| !promote.contains(id)));
+        points.extend(cold_points);
+        Ok((cold_ids, demoted, promoted))
+    }
+
+    /// Copy `ids` from one tier to the other, then delete them from `from`. A crash in between leaves the point in
+    /// both tiers; the next pass keeps the hot copy.
+    async fn move_points(&self, l4: &dyn VectorStore, from: &str, to: &str, ids: &[String]) -> Result<u64, Status> {
+        let mut moved = 0;
+        for chunk in ids.chunks(PRUNE_CHUNK) {
+            let points = self.guarded("get", l4.get(from, chunk.to_vec())).await?;
+            self.guarded_retry("upsert", || l4.upsert(to, points.clone())).await?;
+            self.guarded("delete", l4.delete(from, chunk.to_vec())).await?;
+            moved += points.len() as u64;
+        }
+        Ok(moved)
+    }
+
+    /// Time-travel read of L2: exact `version` when > 0, else the value as of `as_of_unix_ms` when > 0,
+    /// else the latest. `found` is false when the key had no value then or that version was evicted. A namespace
+    /// reads its own "<key>@<namespace>" history.
+    pub fn access_at(&self, req: &MemoryAtRequest) -> Result<MemoryAtResponse, Status> {
+        if req.layer != 2 {
+            return Err(Status::invalid_argument("AccessMemoryAt supports layer 2 (working memory) only"));
+        }
+        let key = scoped_key(req.layer, &req.namespace, &req.key)?;
+        let Some(history) = self.l2_working.get(&key) else {
+            return Ok(MemoryAtResponse::default());
+        };
+        let hit = if req.version > 0 {
+            history.iter().find(|h| h.version == req.version)
+        } else if req.as_of_unix_ms > 0 {
+            history.iter().rev().find(|h| h.written_at_ms <= req.as_of_unix_ms)
+        } else {
+            history.back()
+        };
+        let oldest_version = history.front().map_or(0, |h| h.version);
+        Ok(match hit {
+            Some(h) => MemoryAtResponse {
+                data: h.value.to_string(),
+                found: true,
+                version: h.version,
+                written_at_unix_ms: h.written_at_ms,
+                oldest_version,
+            },
+            None => MemoryAtResponse {
+                oldest_version,
+                ..Default::default()
+            },
+        })
+    }
+
+    /// L1/L2 keys under `prefix`, sorted, as (key as the caller names it, stored key). A namespace sees only its
+    /// own keys, unscoped; operator scope (no namespace) sees every key as stored.
+    fn memory_keys(&self, layer: i32, namespace: &str, prefix: &str) -> Result<Vec<(String, String)>, Status> {
+        if !namespace.is_empty() {
+            kb_registry::check_namespace(namespace).map_err(Status::invalid_argument)?;
+        }
+        let suffix = format!("{}{}", NAMESPACE_SEP, namespace);
+        let visible = |stored: &str| -> Option<(String, String)> {
+            let key = if namespace.is_empty() { stored } else { stored.strip_suffix(suffix.as_str())? };
+            key.starts_with(prefix).then(|| (key.to_string(), stored.to_string()))
+        };
+        let mut keys: Vec<(String, String)> = match layer {
+            1 => self.l1_sensory.iter().filter_map(|e| visible(e.key())).collect(),
+            2 => self.l2_working.iter().filter_map(|e| visible(e.key())).collect(),
+            other => {
+                return Err(Status::invalid_argument(format!("layer {} has no keys to scan (expected 1 or 2)", other)))
+            }
+        };
+        keys.sort_unstable();
+        Ok(keys)
+    }
+
+    /// ScanMemory: one page of L1/L2 entries under a prefix, in key order.
+    pub fn scan_memory(&self, req: &MemoryScanRequest) -> Result<MemoryScanResponse, Status> {
+        let limit = match req.limit {
+            0 => SCAN_MEMORY_PAGE_DEFAULT,
+            n => n as usize,
+        };
+        let keys = self.memory_keys(req.layer, &req.namespace, &req.prefix)?;
+        let start = keys.partition_point(|(key, _)| req.start_after.as_str() >= key.as_str());
+        let mut entries = Vec::new();
+        let mut rest = keys[start..].iter();
+        for (key, stored) in rest.by_ref() {
+            // Keys removed since the listing are skipped.
+            let entry = match req.layer {
+                1 => self.l1_sensory.get(stored).map(|v| MemoryEntry {
+                    value: if req.with_values { String::from_utf8_lossy(&v).into_owned() } else { String::new() },
+                    ..Default::default()
+                }),
+                _ => self.l2_working.get(stored).and_then(|history| {
+                    history.back().map(|h| MemoryEntry {
+                        value: if req.with_values { h.value.to_string() } else { String::new() },
+                        version: h.version,
+                        ..Default::default()
+                    })
+                }),
+            };
+            if let Some(entry) = entry {
+                entries.push(MemoryEntry { key: key.clone(), ..entry });
+                if entries.len() == limit {
+                    break;
+                }
+            }
+        }
+        let next_start_after = match entries.last() {
+            Some(last) if rest.len() > 0 => last.key.clone(),
+            _ => String::new(),
+        };
+        Ok(MemoryScanResponse {
+            entries,
+            next_start_after,
+        })
+    }
+
+    /// DeleteMemory: remove every L1/L2 key under a (non-empty) prefix; returns how many were removed.
+    pub fn delete_memory(&self, req: &MemoryDeleteRequest) -> Result<u32, Status> {
+        if req.prefix.is_empty() {
+            return Err(Status::invalid_argument("DeleteMemory needs a non-empty prefix"));
+        }
+        let keys = self.memory_keys(req.layer, &req.namespace, &req.prefix)?;
+        let deleted = match req.layer {
+            1 => keys.iter().filter(|(_, stored)| self.l1_sensory.remove(stored).is_some()).count(),
+            _ => keys.iter().filter(|(_, stored)| self.l2_working.remove(stored).is_some()).count(),
+        };
+        if req.layer == 2 && deleted > 0 {
+            self.l2_dirty.store(true, Ordering::Relaxed);
+        }
+        eprintln!("[MemoryManager] deleted {} L{} keys under {:?}", deleted, req.layer, req.prefix);
+        Ok(deleted as u32)
+    }
+
+    /// L4 semantic search. Uses query_vector when provided (Python embed); else embeds `query` with the
+    /// server-side embedder when configured; else zero vector (stub). `filter` restricts hits by payload fields.
+    /// Hybrid requests fuse vector and BM25 keyword rankings by RRF (hit scores are then fused ranks).
+    /// `offset` pages through the ranking; `score_threshold` drops weak vector hits before fusion.
+    /// `recency_weight` > 0 reranks by relevance blended with recency of the hits' `at` (decay::blend_recency),
+    /// and hits rated through RecordSearchFeedback move by their learned weight (feedback.rs);
+    /// `rerank` then reorders the candidate window by MMR or the bridge cross-encoder (rerank.rs).
+    /// When L4 is disabled or circuit-broken, returns empty hits flagged `degraded` so callers
+    /// (e.g. propose_patch) can still run and tell "memory down" from "no knowledge".
+    pub async fn semantic_search(
+        &self,
+        req: SearchRequest,
+    ) -> Result<SearchResponse, Status> {
+        let with_payload = req.with_payload;
+        let found = self.search_points(req).await?;
+        let hits: Vec<SearchHit> = found
+            .points
+            .into_iter()
+            .map(|p| {
+                let content_snippet = p
+                    .payload
+                    .get("content")
+                    .or_else(|| p.payload.get("snippet"))
+                    .cloned()
+                    .unwrap_or_else(|| "Snippet stub".to_string());
+                SearchHit {
+                    document_id: p.id,
+                    score: p.score,
+                    content_snippet,
+                    payload: if with_payload { p.payload } else { HashMap::new() },
+                }
+            })
+            .collect();
+
+        Ok(SearchResponse {
+            hits,
+            degraded: found.degraded,
+            source: found.source,
+            next_offset: found.next_offset,
+        })
+    }
+
+    /// semantic_search with full hit payloads, for callers that read structured fields.
+    pub async fn search_points(&self, req: SearchRequest) -> Result<PointSearch, Status> {
+        self.search_points_tuned(req, SearchTuning::default()).await
+    }
+
+    /// search_points under explicit tuning knobs.
+    pub async fn search_points_tuned(&self, mut req: SearchRequest, tuning: SearchTuning) -> Result<PointSearch, Status> {
+        req.kb_name = self.namespace_kb(&req.kb_name, &req.namespace).await?;
+        let Some(l4) = self.l4_semantic.as_deref() else {
+            return Ok(Self::degraded_search("disabled"));
+        };
+        let limit = req.limit.max(1).min(100) as usize;
+        let offset = req.offset as usize;
+        if offset + limit > MAX_SEARCH_WINDOW {
+            return Err(Status::invalid_argument(format!(
+                "offset {} + limit {} exceeds {}; narrow the query with a filter instead",
+                offset, limit, MAX_SEARCH_WINDOW
+            )));
+        }
+        let rerank = Rerank::parse(&req.rerank, req.mmr_lambda, self.mmr_lambda)?;
+        if rerank == Some(Rerank::CrossEncoder) && !self.cross_encoder.configured() {
+            return Err(Status::failed_precondition("cross_encoder rerank needs PAGI_RERANK_URL"));
+        }
+        // Eval queries (untracked) compare tunings against L4 itself, so they bypass the cache.
+        let cache_key = (!tuning.untracked && self.search_cache.enabled()).then(|| CacheKey::new(&req, &tuning));
+        let generation = match &cache_key {
+            Some(key) => match self.search_cache.get(key, self.clock.now_ms()) {
+                Ok(mut found) => {
+                    // Cached before some of its hits expired.
+                    let now = self.clock.now_secs();
+                    found.points.retain(|p| !ttl::expired(&p.payload, now));
+                    self.record_reads(&req.kb_name, &found.points);
+                    self.counters.l4.record(!found.points.is_empty());
+                    return Ok(found);
+                }
+                Err(generation) => generation,
+            },
+            None => 0,
+        };
+        // One hit past the page tells whether another page exists.
+        let window = offset + limit + 1;
+        let dim = self
+            .kbs
+            .get(&req.kb_name)
+            .space_dim(&req.vector_name)
+            .map_err(Status::invalid_argument)?;
+        let query_vector: Vec<f32> = if req.query_vector.len() == dim {
+            req.query_vector
+        } else if let Some(v) = self.embed_query(&req.query, dim).await {
+            v
+        } else if dim == self.embedding_dim {
+            self.zero_vector.clone()
+        } else {
+            vec![0f32; dim]
+        };
+
+        let hybrid = tuning.hybrid.unwrap_or(req.hybrid || self.hybrid_default) && !req.query.trim().is_empty();
+        let time_weighted = req.recency_weight > 0.0;
+        // Hybrid / time-weighted / reranked: over-fetch so later stages can promote hits just outside the top
+        // `window`.
+        let widened = hybrid || time_weighted || rerank.is_some();
+        let candidates = if widened { (window * 4).min(MAX_SEARCH_WINDOW).max(window) } else { window };
+        // Expired points stay invisible until the TTL sweep deletes them.
+        let filter = Some(ttl::unexpired(req.filter.clone(), self.clock.now_secs()));
+        let search = self.search_tiered("search", &req.kb_name, &req.vector_name, &query_vector, candidates, &filter);
+        let mut points = match search.await {
+            Ok(r) => r,
+            // Degraded: breaker open (or just tripped) → empty hits instead of stalling callers.
+            Err(e) if self.l4_degraded() => {
+                self.counters.l4_degraded.fetch_add(1, Ordering::Relaxed);
+                eprintln!("[MemoryManager] degraded search on {}: {}", req.kb_name, e.message());
+                return Ok(Self::degraded_search("circuit_open"));
+            }
+            Err(e) => return Err(e),
+        };
+        let threshold = match (tuning.min_score, req.score_threshold) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        if let Some(min) = threshold {
+            points.retain(|p| p.score >= min);
+        }
+        let points = if hybrid {
+            let keyword_hits = self.l4_keywords.search(&req.kb_name, &req.query, candidates, filter.as_ref());
+            // Scaled so the default alpha (0.5) weighs both lists 1.0, i.e. plain RRF scores.
+            let alpha = tuning.alpha.clamp(0.0, 1.0);
+            keyword_index::rrf_fuse(vec![(points, 2.0 * alpha), (keyword_hits, 2.0 * (1.0 - alpha))], candidates)
+        } else {
+            points
+        };
+        let points = if time_weighted {
+            let halflife = match req.recency_halflife_secs {
+                0 => self.recency_halflife_secs,
+                n => n as u64,
+            };
+            decay::blend_recency(points, req.recency_weight, halflife, self.clock.now_secs())
+        } else {
+            points
+        };
+        // Within the fetched window: rated points just below it are not promoted.
+        let points = feedback::blend(points, req.feedback_weight.unwrap_or(self.feedback_weight));
+        // A failed rerank keeps the ranking so far (logged, not cached) rather than failing the search.
+        let (mut points, reranked) = match rerank {
+            Some(stage) => {
+                let ranked = self.rerank(l4, &req.kb_name, &req.vector_name, &req.query, points.clone(), stage);
+                match ranked.await {
+                    Ok(ranked) => (ranked, true),
+                    Err(e) => {
+                        eprintln!("[MemoryManager] rerank on {} skipped: {}", req.kb_name, e.message());
+                        (points, false)
+                    }
+                }
+            }
+            None => (points, true),
+        };
+        points.truncate(window);
+        let next_offset = if points.len() == window { (offset + limit) as u32 } else { 0 };
+        let points: Vec<ScoredPoint> = points.into_iter().skip(offset).take(limit).collect();
+        if !tuning.untracked {
+            self.record_reads(&req.kb_name, &points);
+            self.counters.l4.record(!points.is_empty());
+        }
+
+        let found = PointSearch {
+            points,
+            degraded: false,
+            source: l4.name().to_string(),
+            next_offset,
+        };
+        if let Some(key) = cache_key.filter(|_| reranked) {
+            self.search_cache.insert(key, &found, generation, self.clock.now_ms());
+        }
+        Ok(found)
+    }
+
+    /// RecordSearchFeedback: add each mark to its point's counts, in whichever tier holds the point.
+    pub async fn record_feedback(&self, mut req: SearchFeedbackRequest) -> Result<SearchFeedbackResponse, Status> {
+        let l4 = self.l4_or_disabled()?;
+        if let Some(id) = req.useful_ids.iter().find(|id| req.unhelpful_ids.contains(id)) {
+            return Err(Status::invalid_argument(format!("point {} is marked both useful and unhelpful", id)));
+        }
+        req.kb_name = self.namespace_kb(&req.kb_name, &req.namespace).await?;
+        let useful = req.useful_ids.into_iter().map(|id| (id, true));
+        let marks: BTreeMap<String, bool> = useful.chain(req.unhelpful_ids.into_iter().map(|id| (id, false))).collect();
+        let mut missing: Vec<String> = marks.keys().cloned().collect();
+        let mut recorded = 0;
+        let _serial = self.feedback_lock.lock().await;
+        for collection in std::iter::once(req.kb_name.clone()).chain(self.cold_tier(&req.kb_name)) {
+            if missing.is_empty() {
+                break;
+            }
+            for p in self.guarded("get", l4.get(&collection, missing.clone())).await? {
+                let Some(&mark) = marks.get(&p.id) else {
+                    continue;
+                };
+                let fields = feedback::mark(&p.payload, mark);
+                self.guarded("set_payload", l4.set_payload(&collection, vec![p.id.clone()], fields)).await?;
+                missing.retain(|id| *id != p.id);
+                recorded += 1;
+            }
+        }
+        if recorded > 0 {
+            self.search_cache.invalidate(&req.kb_name);
+        }
+        Ok(SearchFeedbackResponse {
+            recorded,
+            missing_ids: missing,
+        })
+    }
+
+    /// Search hits count as reads: for the hot-memory report and, in tiered collections, to keep points warm.
+    fn record_reads(&self, collection: &str, points: &[ScoredPoint]) {
+        self.hot.record_l4(collection, points);
+        if self.cold_tiers.contains_key(collection) {
+            self.tier_reads.record(collection, points, self.clock.now_secs());
+        }
+    }
+
+    /// Reranking stage of search_points_tuned over the candidate window (rerank.rs).
+    async fn rerank(
+        &self,
+        l4: &dyn VectorStore,
+        collection: &str,
+        vector_name: &str,
+        query: &str,
+        points: Vec<ScoredPoint>,
+        stage: Rerank,
+    ) -> Result<Vec<ScoredPoint>, Status> {
+        match stage {
+            Rerank::Mmr { lambda } => {
+                let ids = points.iter().map(|p| p.id.clone()).collect();
+                let stored = self.get_tiered(l4, collection, ids).await?;
+                let vectors: HashMap<String, Vec<f32>> = stored
+                    .into_iter()
+                    .map(|mut p| {
+                        let vector = if vector_name.is_empty() {
+                            std::mem::take(&mut p.vector)
+                        } else {
+                            p.vectors.remove(vector_name).map(|v| v.data).unwrap_or_default()
+                        };
+                        (p.id, vector)
+                    })
+                    .collect();
+                Ok(rerank::mmr(points, &vectors, lambda))
+            }
+            Rerank::CrossEncoder => self.cross_encoder.rerank(query, points, hit_text).await,
+        }
+    }
+
+    /// Embeddings of `texts` for a `dim`-sized KB, zero-padded to it: FAILED_PRECONDITION without an embedder or
+    /// when the model's vectors exceed `dim`, UNAVAILABLE when a remote embedder cannot be reached.
+    pub async fn embed_texts(&self, texts: &[String], dim: usize) -> Result<Vec<Vec<f32>>, Status> {
+        let encoder = self
+            .embedder
+            .as_ref()
+            .ok_or_else(|| Status::failed_precondition("no server-side embedder (PAGI_EMBEDDER)"))?;
+        let vectors = encoder.embed(texts, dim).await.map_err(|e| {
+            let msg = format!("{} embedder: {}", encoder.name(), e);
+            if is_outage(&e) {
+                Status::unavailable(msg)
+            } else {
+                Status::internal(msg)
+            }
+        })?;
+        embedder::fit(vectors, texts.len(), dim).map_err(|e| {
+            Status::failed_precondition(format!("{} embedder: {} (see PAGI_EMBEDDING_DIM)", encoder.name(), e))
+        })
+    }
+
+    /// Server-side embedding of a search query for a `dim`-sized KB; None (logged on failure) when no embedder
+    /// is configured, the query is blank or embedding fails.
+    pub async fn embed_query(&self, query: &str, dim: usize) -> Option<Vec<f32>> {
+        if self.embedder.is_none() || query.trim().is_empty() {
+            return None;
+        }
+        match self.embed_texts(&[query.to_string()], dim).await {
+            Ok(mut vectors) => vectors.pop(),
+            Err(e) => {
+                eprintln!("[MemoryManager] query embedding failed: {}", e.message());
+                None
+            }
+        }
+    }
+
+    fn l4_or_disabled(&self) -> Result<&dyn VectorStore, Status> {
+        self.l4_semantic
+            .as_deref()
+            .ok_or_else(|| Status::failed_precondition("Qdrant disabled (PAGI_DISABLE_QDRANT=true)"))
+    }
+
+    /// SnapshotKb: the backend's native snapshot of the KB's collection, else a portable dump of its points.
+    pub async fn snapshot_kb(&self, req: SnapshotKbRequest) -> Result<SnapshotKbResponse, Status> {
+        let l4 = self.l4_or_disabled()?;
+        let collection = kb_registry::namespaced(&req.kb_name, &req.namespace).map_err(Status::invalid_argument)?;
+        snapshot::check_label(&req.label).map_err(Status::invalid_argument)?;
+        let points = self
+            .guarded("count", l4.point_count(&collection))
+            .await?
+            .ok_or_else(|| Status::not_found(format!("no collection {}", collection)))?;
+        let native = self
+            .snapshots
+            .new_path(&collection, &req.label, snapshot::NATIVE_EXT, self.clock.now())
+            .map_err(Status::internal)?;
+        let (path, format) = match l4.snapshot_to(&collection, &snapshot::partial_path(&native)).await {
+            Ok(true) => (native, "native"),
+            Ok(false) => {
+                let path = native.with_extension(snapshot::POINTS_EXT);
+                if let Err(e) = self.dump_points(l4, &collection, &path).await {
+                    snapshot::discard(&path);
+                    return Err(e);
+                }
+                (path, "points")
+            }
+            Err(e) => {
+                snapshot::discard(&native);
+                return Err(Status::unavailable(format!("snapshot {}: {}", collection, e)));
+            }
+        };
+        let bytes = snapshot::finish(&path).map_err(Status::internal)?;
+        let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        eprintln!("[MemoryManager] snapshot {} of {} ({}, {} points, {} bytes)", name, collection, format, points, bytes);
+        Ok(SnapshotKbResponse {
+            snapshot: name,
+            format: format.to_string(),
+            bytes,
+            points,
+        })
+    }
+
+    /// Portable dump of every point of `collection` to the `.partial` file of `path`.
+    async fn dump_points(&self, l4: &dyn VectorStore, collection: &str, path: &Path) -> Result<u64, Status> {
+        let ids: Vec<String> = self
+            .guarded("scroll", l4.scan(collection, &[]))
+            .await?
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        let mut writer = snapshot::PointsWriter::create(path, collection, self.clock.now()).map_err(Status::internal)?;
+        for chunk in ids.chunks(PRUNE_CHUNK) {
+            let points = self.guarded_retry("get", || l4.get(collection, chunk.to_vec())).await?;
+            writer.write(&points).map_err(Status::internal)?;
+        }
+        writer.close().map_err(Status::internal)
+    }
+
+    /// ScrollKb: the KB's points in pages of `page_size`, from `offset` on. Pages are read as the client consumes
+    /// them (two buffered); a failed read ends the stream with its status, resumable from the last next_offset.
+    pub async fn scroll_kb(self: &Arc<Self>, req: ScrollKbRequest) -> Result<KbPageStream, Status> {
+        let l4 = self.l4_or_disabled()?;
+        let collection = kb_registry::namespaced(&req.kb_name, &req.namespace).map_err(Status::invalid_argument)?;
+        if self.guarded("count", l4.point_count(&collection)).await?.is_none() {
+            return Err(Status::not_found(format!("collection {} not found", collection)));
+        }
+        let page_size = match req.page_size {
+            0 => SCROLL_PAGE_DEFAULT,
+            n => n as usize,
+        };
+        let (tx, rx) = tokio::sync::mpsc::channel(2);
+        let memory = Arc::clone(self);
+        tokio::spawn(async move {
+            let Some(l4) = memory.l4_semantic.as_deref() else {
+                return;
+            };
+            let mut offset = Some(req.offset).filter(|o| !o.is_empty());
+            loop {
+                let page = memory
+                    .guarded_retry("scroll", || {
+                        l4.scroll(&collection, offset.clone(), page_size, req.filter.clone(), req.with_vectors)
+                    })
+                    .await;
+                let (message, next) = match page {
+                    Ok(page) => {
+                        let next_offset = page.next_offset.clone().unwrap_or_default();
+                        (Ok(ScrollKbPage { points: page.points, next_offset }), page.next_offset)
+                    }
+                    Err(e) => (Err(e), None),
+                };
+                if tx.send(message).await.is_err() || next.is_none() {
+                    return;
+                }
+                offset = next;
+            }
+        });
+        Ok(ReceiverStream::new(rx))
+    }
+
+    /// RestoreKb: replace the KB's collection with a snapshot (the newest when none is named). Native snapshots
+    /// go back through the backend; a portable dump is upserted and points missing from it are deleted, so
+    /// either way the KB ends up as snapshotted.
+    pub async fn restore_kb(&self, req: RestoreKbRequest) -> Result<RestoreKbResponse, Status> {
+        let l4 = self.l4_or_disabled()?;
+        let collection = kb_registry::namespaced(&req.kb_name, &req.namespace).map_err(Status::invalid_argument)?;
+        let path = self.snapshots.resolve(&collection, &req.snapshot).map_err(Status::not_found)?;
+        let mut resp = RestoreKbResponse {
+            snapshot: path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+            ..Default::default()
+        };
+        // The keyword index and hot cache do not see the restore; drop what they hold for the old points.
+        let before: Vec<String> = match self.guarded("count", l4.point_count(&collection)).await? {
+            Some(_) => self
+                .guarded("scroll", l4.scan(&collection, &[]))
+                .await?
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect(),
+            None => Vec::new(),
+        };
+        self.l4_keywords.delete(&collection, &before);
+        self.hot.forget_l4(&collection, &before);
+        self.search_cache.invalidate(&collection);
+
+        if path.extension().is_some_and(|ext| ext == snapshot::NATIVE_EXT) {
+            resp.format = "native".to_string();
+            l4.restore_from(&collection, &path)
+                .await
+                .map_err(|e| Status::failed_precondition(format!("restore {}: {}", collection, e)))?;
+            self.ensure_kb(&collection).await.map_err(|e| Status::internal(e.to_string()))?;
+        } else {
+            resp.format = "points".to_string();
+            let (kb_name, points) = snapshot::read_points(&path).map_err(Status::data_loss)?;
+            if kb_name != collection {
+                return Err(Status::failed_precondition(format!(
+                    "{} is a snapshot of {}, not {}",
+                    resp.snapshot, kb_name, collection
+                )));
+            }
+            self.ensure_kb(&collection).await.map_err(|e| Status::unavailable(e.to_string()))?;
+            let batch_size = Self::upsert_batch_size();
+            let mut restored: HashSet<String> = HashSet::new();
+            let mut batch = Vec::with_capacity(batch_size);
+            for point in points {
+                batch.push(point.map_err(|e| Status::data_loss(format!("{}: {}", resp.snapshot, e)))?);
+                if batch.len() >= batch_size {
+                    restored.extend(batch.iter().map(|p| p.id.clone()));
+                    self.restore_points(l4, &collection, std::mem::take(&mut batch)).await?;
+                }
+            }
+            restored.extend(batch.iter().map(|p| p.id.clone()));
+            self.restore_points(l4, &collection, batch).await?;
+            let stale: Vec<String> = before.into_iter().filter(|id| !restored.contains(id)).collect();
+            for chunk in stale.chunks(PRUNE_CHUNK) {
+                self.guarded_retry("delete", || l4.delete(&collection, chunk.to_vec())).await?;
+            }
+            resp.removed = stale.len() as u64;
+        }
+        self.search_cache.invalidate(&collection);
+        resp.points = self.guarded("count", l4.point_count(&collection)).await?.unwrap_or(0);
+        eprintln!(
+            "[MemoryManager] restored {} from {} ({}, {} points)",
+            collection, resp.snapshot, resp.format, resp.points
+        );
+        Ok(resp)
+    }
+
+    /// Write restored points as they were snapshotted (no dedup, decay stamps or WAL).
+    async fn restore_points(&self, l4: &dyn VectorStore, collection: &str, points: Vec<VectorPoint>) -> Result<(), Status> {
+        if points.is_empty() {
+            return Ok(());
+        }
+        self.guarded_retry("upsert", || l4.upsert(collection, points.clone())).await?;
+        self.l4_keywords.upsert(collection, &points);
+        self.search_cache.invalidate(collection);
+        Ok(())
+    }
+
+    /// L4 upsert: store vector points into a KB collection. Python embeds; Rust owns I/O.
+    pub async fn upsert_vectors(&self, mut req: UpsertRequest) -> Result<UpsertResponse, Status> {
+        let l4 = self.l4_or_disabled()?;
+        let (skipped, merged) = self.prepare_upsert(&mut req).await?;
+        self.write_upsert(l4, req, skipped, merged).await
+    }
+
+    /// Resolve the namespaced KB, check and dedup the points and stamp their payloads; returns (skipped, merged).
+    async fn prepare_upsert(&self, req: &mut UpsertRequest) -> Result<(u32, u32), Status> {
+        req.kb_name = self.namespace_kb(&req.kb_name, &req.namespace).await?;
+        let spec = self.kbs.get(&req.kb_name);
+        for p in &req.points {
+            check_vectors(&spec, p).map_err(Status::invalid_argument)?;
+        }
+        let mode = DedupMode::parse(&req.dedup)
+            .map_err(Status::invalid_argument)?
+            .unwrap_or(self.dedup_default);
+        for p in &mut req.points {
+            dedup::stamp(&mut p.payload);
+        }
+        // Before decay stamping, so a merged point keeps the stored `at`.
+        let (skipped, merged) = match mode {
+            DedupMode::Off => (0, 0),
+            mode => self.dedup_points(&req.kb_name, &mut req.points, mode).await?,
+        };
+        let now = self.clock.now_secs();
+        for p in &mut req.points {
+            ttl::stamp(&mut p.payload, req.ttl_seconds, now).map_err(Status::invalid_argument)?;
+            // Read by decay, retention by age and time-weighted search.
+            p.payload.entry("at".to_string()).or_insert_with(|| now.to_string());
+            if let Some(decay) = self.decay {
+                decay.stamp(&mut p.payload, now);
+            }
+        }
+        Ok((skipped, merged))
+    }
+
+    /// Write prepared points to the hot tier and refresh what indexes them.
+    async fn write_upsert(
+        &self,
+        l4: &dyn VectorStore,
+        req: UpsertRequest,
+        skipped: u32,
+        merged: u32,
+    ) -> Result<UpsertResponse, Status> {
+        // Acked when this call ends, however it ends; only a crash leaves the batch for replay.
+        let _logged = match &self.wal {
+            Some(wal) => Some(
+                wal.log(&req.kb_name, &req.points)
+                    .map_err(|e| Status::unavailable(format!("L4 WAL: {}", e)))?,
+            ),
+            None => None,
+        };
+        let n = self.guarded_retry("upsert", || l4.upsert(&req.kb_name, req.points.clone())).await?;
+        self.l4_keywords.upsert(&req.kb_name, &req.points);
+        self.search_cache.invalidate(&req.kb_name);
+        let ids: Vec<String> = req.points.iter().map(|p| p.id.clone()).collect();
+        // A rewritten point is hot again; a cold copy left by a failed delete is dropped by the next tiering pass.
+        if let Some(cold) = self.cold_tier(&req.kb_name) {
+            if let Err(e) = self.guarded("delete", l4.delete(&cold, ids.clone())).await {
+                eprintln!("[MemoryManager] drop rewritten points from {}: {}", cold, e.message());
+            }
+        }
+        self.hot.forget_l4(&req.kb_name, &ids);
+        Ok(UpsertResponse {
+            success: true,
+            upserted_count: n as u32,
+            skipped_count: skipped,
+            merged_count: merged,
+        })
+    }
+
+    /// WriteMemory: the L4 upsert, then the L2 writes, as one unit under a fresh transaction id (UUIDv7). Keys are
+    /// scoped and points checked before anything is written; a failed upsert is rolled back and leaves L2 alone.
+    pub async fn write_memory(&self, req: MemoryWriteRequest) -> Result<MemoryWriteResponse, Status> {
+        let txn_id = ReasoningId::generate_at(self.clock.now_ms()).to_string();
+        let keys = req
+            .l2
+            .iter()
+            .map(|w| scoped_key(2, &req.namespace, &w.key))
+            .collect::<Result<Vec<_>, _>>()?;
+        let l4 = match req.l4 {
+            Some(mut upsert) => {
+                upsert.namespace = req.namespace.clone();
+                upsert.reasoning_id = req.reasoning_id.clone();
+                Some(self.transact_upsert(&txn_id, upsert).await?)
+            }
+            None => None,
+        };
+        let l2_versions = keys
+            .iter()
+            .zip(&req.l2)
+            .map(|(key, w)| self.write_l2(key, Arc::from(w.value.as_str())))
+            .collect();
+        eprintln!(
+            "[MemoryManager] transaction {}: {} L2 keys, {} points",
+            txn_id,
+            keys.len(),
+            l4.as_ref().map_or(0, |r| r.upserted_count)
+        );
+        Ok(MemoryWriteResponse {
+            transaction_id: txn_id,
+            l2_versions,
+            l4,
+        })
+    }
+
+    /// A transaction's upsert. The hot-tier points it will overwrite are read first; if the write fails, they are
+    /// upserted back and the ids that were new are deleted, so a partly applied batch leaves nothing behind.
+    async fn transact_upsert(&self, txn_id: &str, mut req: UpsertRequest) -> Result<UpsertResponse, Status> {
+        let l4 = self.l4_or_disabled()?;
+        // After dedup, so merge targets are covered; cold copies are only dropped once the write succeeds.
+        let (skipped, merged) = self.prepare_upsert(&mut req).await?;
+        let kb_name = req.kb_name.clone();
+        let ids: Vec<String> = req.points.iter().map(|p| p.id.clone()).collect();
+        let prior = self.guarded("get", l4.get(&kb_name, ids.clone())).await?;
+        let err = match self.write_upsert(l4, req, skipped, merged).await {
+            Ok(resp) => return Ok(resp),
+            Err(e) => e,
+        };
+        let existed: HashSet<&str> = prior.iter().map(|p| p.id.as_str()).collect();
+        let fresh: Vec<String> = ids.into_iter().filter(|id| !existed.contains(id.as_str())).collect();
+        let rollback = async {
+            if !fresh.is_empty() {
+                self.guarded("delete", l4.delete(&kb_name, fresh)).await?;
+            }
+            if !prior.is_empty() {
+                self.guarded("upsert", l4.upsert(&kb_name, prior)).await?;
+            }
+            Ok::<_, Status>(())
+        };
+        match rollback.await {
+            Ok(()) => {
+                eprintln!(
+                    "[MemoryManager] transaction {}: upsert to {} failed, rolled back: {}",
+                    txn_id,
+                    kb_name,
+                    err.message()
+                );
+                Err(err)
+            }
+            Err(e) => {
+                eprintln!("[MemoryManager] transaction {}: rollback of {} failed: {}", txn_id, kb_name, e.message());
+                Err(Status::new(
+                    err.code(),
+                    format!(
+                        "{}; rollback failed, {} may hold part of the batch: {}",
+                        err.message(),
+                        kb_name,
+                        e.message()
+                    ),
+                ))
+            }
+        }
+    }
+
+    /// Resolve points whose content hash is already stored (or repeated within the request) under another id:
+    /// drop them (skip) or retarget them at the stored point with its payload underneath (merge). Returns
+    /// (skipped, merged).
+    async fn dedup_points(
+        &self,
+        kb_name: &str,
+        points: &mut Vec<VectorPoint>,
+        mode: DedupMode,
+    ) -> Result<(u32, u32), Status> {
+        let (mut skipped, mut merged) = (0, 0);
+        // Lookups go through the first named space (or the unnamed vector): every point has each.
+        let space = self.kbs.get(kb_name).vectors.keys().next().cloned().unwrap_or_default();
+        // Hash → index in `kept`; None when the hash is stored and the point was skipped.
+        let mut seen: HashMap<String, Option<usize>> = HashMap::new();
+        let mut kept: Vec<VectorPoint> = Vec::with_capacity(points.len());
+        for mut p in std::mem::take(points) {
+            let Some(hash) = p.payload.get(dedup::HASH_FIELD).cloned() else {
+                kept.push(p);
+                continue;
+            };
+            match (seen.get(&hash), mode) {
+                (Some(_), DedupMode::Skip) => {
+                    skipped += 1;
+                    continue;
+                }
+                (Some(Some(i)), _) => {
+                    let target = &mut kept[*i];
+                    target.vector = p.vector;
+                    target.vectors = p.vectors;
+                    target.payload.extend(p.payload);
+                    merged += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            let filter = SearchFilter {
+                must: vec![FilterCondition {
+                    key: dedup::HASH_FIELD.to_string(),
+                    r#match: hash.clone(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            };
+            let filter = ttl::unexpired(Some(filter), self.clock.now_secs());
+            let vector = match p.vectors.get(&space) {
+                Some(v) => v.data.clone(),
+                None => p.vector.clone(),
+            };
+            let stored = self
+                .search_tiered("dedup", kb_name, &space, &vector, 1, &Some(filter))
+                .await?
+                .into_iter()
+                .next()
+                .filter(|hit| hit.id != p.id);
+            if let Some(hit) = stored {
+                if mode == DedupMode::Skip {
+                    skipped += 1;
+                    seen.insert(hash, None);
+                    continue;
+                }
+                let mut payload = hit.payload;
+                payload.extend(p.payload);
+                p.payload = payload;
+                p.id = hit.id;
+                merged += 1;
+            }
+            seen.insert(hash, Some(kept.len()));
+            kept.push(p);
+        }
+        *points = kept;
+        Ok((skipped, merged))
+    }
+
+    /// Re-upsert WAL batches the store never acked (the previous process died mid-upsert); call after
+    /// init_kbs and before serving. Batches that fail again stay logged for the next startup.
+    pub async fn replay_wal(&self) -> usize {
+        let (Some(wal), Some(l4)) = (&self.wal, self.l4_semantic.as_deref()) else {
+            return 0;
+        };
+        let mut replayed = 0;
+        for batch in wal.pending() {
+            if let Err(e) = self.ensure_kb(&batch.kb_name).await {
+                eprintln!("[MemoryManager] WAL batch {} for {} not replayed: {}", batch.seq, batch.kb_name, e);
+                continue;
+            }
+            match self.guarded_retry("upsert", || l4.upsert(&batch.kb_name, batch.points.clone())).await {
+                Ok(n) => {
+                    self.l4_keywords.upsert(&batch.kb_name, &batch.points);
+                    self.search_cache.invalidate(&batch.kb_name);
+                    wal.ack(batch.seq);
+                    replayed += n;
+                }
+                Err(e) => eprintln!(
+                    "[MemoryManager] WAL batch {} for {} not replayed: {}",
+                    batch.seq,
+                    batch.kb_name,
+                    e.message()
+                ),
+            }
+        }
+        if replayed > 0 {
+            eprintln!("[MemoryManager] replayed {} point(s) from the L4 WAL", replayed);
+        }
+        replayed
+    }
+
+    /// Points per UpsertVectorsStream flush (PAGI_UPSERT_BATCH_SIZE, default 256).
+    pub fn upsert_batch_size() -> usize {
+        Self::env_u64("PAGI_UPSERT_BATCH_SIZE", 256).max(1) as usize
+    }
+
+    /// Streaming L4 upsert: points are flushed per KB in batches of `batch_size`, each awaited before the
+    /// next message is read, so memory stays bounded by one batch and HTTP/2 flow control throttles the
+    /// client. A message with an empty kb_name continues the previous KB (and namespace). A failed batch ends
+    /// the stream with an error naming how many points were already stored.
+    pub async fn upsert_stream<S>(&self, mut stream: S, batch_size: usize) -> Result<UpsertStreamResponse, Status>
+    where
+        S: Stream<Item = Result<UpsertRequest, Status>> + Unpin,
+    {
+        self.l4_or_disabled()?;
+        let mut response = UpsertStreamResponse {
+            success: true,
+            ..Default::default()
+        };
+        let mut kb_name = String::new();
+        let mut dedup = String::new();
+        let mut pending = Vec::with_capacity(batch_size);
+        loop {
+            let next = stream.next().await.transpose()?;
+            let next_kb = match &next {
+                Some(msg) if !msg.kb_name.is_empty() => Some(self.namespace_kb(&msg.kb_name, &msg.namespace).await?),
+                _ => None,
+            };
+            let switching_kb = next_kb.as_ref().is_some_and(|kb| *kb != kb_name);
+            if !pending.is_empty() && (next.is_none() || switching_kb) {
+                self.flush_batch(&kb_name, &dedup, &mut pending, &mut response).await?;
+            }
+            let Some(msg) = next else {
+                break;
+            };
+            if let Some(kb) = next_kb {
+                kb_name = kb;
+            } else if kb_name.is_empty() {
+                return Err(Status::invalid_argument("first UpsertVectorsStream message needs kb_name"));
+            }
+            if !msg.dedup.is_empty() {
+                dedup = msg.dedup;
+            }
+            for mut point in msg.points {
+                if msg.ttl_seconds > 0 {
+                    point.payload.entry(ttl::TTL_FIELD.to_string()).or_insert_with(|| msg.ttl_seconds.to_string());
+                }
+                pending.push(point);
+                if pending.len() >= batch_size {
+                    self.flush_batch(&kb_name, &dedup, &mut pending, &mut response).await?;
+                }
+            }
+        }
+        Ok(response)
+    }
+
+    async fn flush_batch(
+        &self,
+        kb_name: &str,
+        dedup: &str,
+        pending: &mut Vec<VectorPoint>,
+        response: &mut UpsertStreamResponse,
+    ) -> Result<(), Status> {
+        let req = UpsertRequest {
+            kb_name: kb_name.to_string(),
+            points: std::mem::take(pending),
+            dedup: dedup.to_string(),
+            ..Default::default()
+        };
+        let n = self.upsert_vectors(req).await.map_err(|e| {
+            Status::new(
+                e.code(),
+                format!(
+                    "batch {} for {} failed after {} point(s) stored: {}",
+                    response.batches.len() + 1,
+                    kb_name,
+                    response.upserted_count,
+                    e.message()
+                ),
+            )
+        })?;
+        response.upserted_count += n.upserted_count;
+        response.skipped_count += n.skipped_count;
+        response.merged_count += n.merged_count;
+        response.batches.push(UpsertBatch {
+            kb_name: kb_name.to_string(),
+            upserted_count: n.upserted_count,
+        });
+        Ok(())
+    }
+
+    /// L4 delete: remove points from a KB collection (and its cold tier) by id.
+    pub async fn delete_vectors(&self, req: DeleteVectorsRequest) -> Result<DeleteVectorsResponse, Status> {
+        let l4 = self.l4_or_disabled()?;
+        let mut n = self.guarded("delete", l4.delete(&req.kb_name, req.ids.clone())).await?;
+        if let Some(cold) = self.cold_tier(&req.kb_name) {
+            n += self.guarded("delete", l4.delete(&cold, req.ids.clone())).await?;
+            self.tier_reads.forget(&req.kb_name, &req.ids);
+        }
+        self.l4_keywords.delete(&req.kb_name, &req.ids);
+        self.search_cache.invalidate(&req.kb_name);
+        self.hot.forget_l4(&req.kb_name, &req.ids);
+        Ok(DeleteVectorsResponse {
+            success: true,
+            deleted_count: n as u32,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    #[test]
+    fn l2_history_is_bounded_and_time_travels() {
+        let clock = ManualClock::at(1);
+        let mut mm = MemoryManager::build(None, Duration::from_millis(1)).with_clock(clock.clone().into());
+        mm.l2_depth = 3;
+        for v in ["a", "b", "c", "d"] {
+            mm.access(2, "belief", Some(v)).unwrap();
+            clock.advance(Duration::from_secs(1));
+        }
+        assert_eq!(mm.access(2, "belief", None).unwrap().0, "d");
+        let at = |version: u64, as_of_unix_ms: i64| {
+            mm.access_at(&MemoryAtRequest {
+                layer: 2,
+                key: "belief".into(),
+                as_of_unix_ms,
+                version,
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        let v2 = at(2, 0);
+        assert_eq!((v2.data.as_str(), v2.found, v2.oldest_version), ("b", true, 2));
+        assert!(!at(1, 0).found, "version 1 evicted at depth 3");
+        assert_eq!(at(0, 0).version, 4);
+        assert_eq!(at(0, 0).written_at_unix_ms, 4_000);
+
+        // Versions 2-4 were written at 2s, 3s and 4s.
+        assert_eq!(at(0, 3_500).data, "c");
+        assert!(!at(0, 1_500).found);
+        assert!(mm
+            .access_at(&MemoryAtRequest {
+                layer: 1,
+                ..Default::default()
+            })
+            .is_err());
+    }
+
+    /// Three `session:` keys and `other` under team_a, plus an operator `session:1/goal` written twice.
+    fn scoped_l2() -> MemoryManager {
+        let mm = MemoryManager::build(None, Duration::from_millis(1));
+        for key in ["session:1/goal", "session:1/plan", "session:2/goal", "other"] {
+            mm.access(2, &scoped_key(2, "team_a", key).unwrap(), Some(key)).unwrap();
+        }
+        mm.access(2, "session:1/goal", Some("operator")).unwrap();
+        mm.access(2, "session:1/goal", Some("operator v2")).unwrap();
+        mm
+    }
+
+    fn scan(mm: &MemoryManager, namespace: &str, prefix: &str, start_after: &str) -> MemoryScanResponse {
+        let req = MemoryScanRequest {
+            layer: 2,
+            prefix: prefix.into(),
+            namespace: namespace.into(),
+            limit: 2,
+            start_after: start_after.into(),
+            with_values: true,
+        };
+        mm.scan_memory(&req).unwrap()
+    }
+
+    fn delete_prefix(namespace: &str, prefix: &str) -> MemoryDeleteRequest {
+        MemoryDeleteRequest {
+            layer: 2,
+            prefix: prefix.into(),
+            namespace: namespace.into(),
+        }
+    }
+
+    #[test]
+    fn prefix_scans_page_in_key_order() {
+        let mm = scoped_l2();
+        let first = scan(&mm, "team_a", "session:", "");
+        let keys: Vec<&str> = first.entries.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, ["session:1/goal", "session:1/plan"]);
+        assert_eq!(first.next_start_after, "session:1/plan");
+        let last = scan(&mm, "team_a", "session:", &first.next_start_after);
+        assert_eq!((last.entries.len(), last.entries[0].key.as_str()), (1, "session:2/goal"));
+        assert!(last.next_start_after.is_empty());
+    }
+
+    #[test]
+    fn unscoped_scans_see_every_namespace_with_versions() {
+        let operator = scan(&scoped_l2(), "", "session:1/goal", "");
+        let entries: Vec<(&str, &str, u64)> =
+            operator.entries.iter().map(|e| (e.key.as_str(), e.value.as_str(), e.version)).collect();
+        assert_eq!(entries, [("session:1/goal", "operator v2", 2), ("session:1/goal@team_a", "session:1/goal", 1)]);
+    }
+
+    #[test]
+    fn bulk_deletes_stay_in_their_namespace() {
+        let mm = scoped_l2();
+        assert_eq!(mm.delete_memory(&delete_prefix("team_a", "session:1/")).unwrap(), 2);
+        assert_eq!(mm.read_l2("session:1/goal").as_deref(), Some("operator v2"));
+        assert_eq!(scan(&mm, "team_a", "", "").entries.len(), 2);
+    }
+
+    #[test]
+    fn bulk_deletes_need_a_prefix() {
+        assert!(scoped_l2().delete_memory(&delete_prefix("team_a", "")).is_err());
+    }
+
+    #[test]
+    fn only_l2_can_be_scanned() {
+        assert!(scoped_l2().scan_memory(&MemoryScanRequest { layer: 4, ..Default::default() }).is_err());
+    }
+
+    #[test]
+    fn fast_path_reads_share_stored_values_and_count_like_access() {
+        let mm = MemoryManager::in_memory(4);
+        assert_eq!(mm.write_l2("goal", Arc::from("ship")), 1);
+        let (first, second) = (mm.read_l2("goal").unwrap(), mm.read_l2("goal").unwrap());
+        assert!(Arc::ptr_eq(&first, &second), "reads hand out the stored value, not copies");
+        assert_eq!(mm.write_l2("goal", Arc::from("ship v2")), 2);
+        assert_eq!(&*first, "ship", "a held handle outlives later writes");
+        assert_eq!(mm.access(2, "goal", None).unwrap().0, "ship v2");
+
+        let payload = Bytes::from_static(b"frame");
+        mm.write_l1("cam", payload.clone());
+        assert_eq!(mm.read_l1("cam").unwrap().as_ptr(), payload.as_ptr());
+        assert!(mm.read_l1("missing").is_none());
+        assert_eq!(mm.counters.l1.misses.load(Ordering::Relaxed), 1);
+        assert_eq!(mm.counters.l2.hits.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn stats_report_layer_sizes_and_read_counters() {
+        let mm = MemoryManager::in_memory(2);
+        mm.write_l1("cam", Bytes::from_static(b"frame"));
+        mm.write_l2("goal", Arc::from("ship"));
+        assert!(mm.read_l1("cam").is_some());
+        assert!(mm.read_l2("goal").is_some() && mm.read_l2("missing").is_none());
+
+        let stats = mm.stats().await;
+        let l1 = &stats.layers[0];
+        assert_eq!((l1.layer, l1.entries, l1.bytes, l1.hits, l1.misses), (1, 1, 8, 1, 0));
+        let l2 = &stats.layers[1];
+        assert_eq!((l2.name.as_str(), l2.entries, l2.bytes, l2.hits, l2.misses), ("working", 1, 8, 1, 1));
+    }
+
+    #[tokio::test]
+    async fn stats_count_kb_points_and_empty_searches_as_misses() {
+        let mm = MemoryManager::in_memory(2);
+        mm.ensure_kb("kb_core").await.unwrap();
+        mm.ensure_kb("kb_empty").await.unwrap();
+        let point = VectorPoint {
+            id: "a".into(),
+            vector: vec![1.0, 0.0],
+            ..Default::default()
+        };
+        mm.upsert_vectors(UpsertRequest {
+            kb_name: "kb_core".into(),
+            points: vec![point],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        for kb in ["kb_core", "kb_empty"] {
+            let req = SearchRequest {
+                kb_name: kb.into(),
+                query_vector: vec![1.0, 0.0],
+                limit: 1,
+                ..Default::default()
+            };
+            mm.semantic_search(req).await.unwrap();
+        }
+
+        let stats = mm.stats().await;
+        let core = stats.collections.iter().find(|c| c.kb_name == "kb_core").unwrap();
+        assert!(core.exists && core.points == 1 && core.error.is_empty());
+        let l4 = &stats.layers[2];
+        assert_eq!((l4.entries, l4.bytes), (1, core.dim as u64 * 4));
+        assert_eq!((l4.hits, l4.misses), (1, 1));
+    }
+
+    #[test]
+    fn l1_and_l2_read_misses_report_no_success() {
+        let mm = MemoryManager::in_memory(4);
+        assert_eq!(mm.access(1, "missing", None).unwrap(), (String::new(), false));
+        assert_eq!(mm.access(2, "missing", None).unwrap(), (String::new(), false));
+        mm.access(2, "goal", Some("")).unwrap();
+        assert_eq!(mm.access(2, "goal", None).unwrap(), (String::new(), true), "an empty value is still a hit");
+    }
+
+    #[test]
+    fn unbacked_layers_fail_typed_and_capabilities_say_why() {
+        let mut mm = MemoryManager::in_memory(4);
+        mm.archive = None;
+        assert_eq!(mm.access(1, "k", Some("v")).unwrap(), ("v".to_string(), true));
+        assert_eq!(mm.access(4, "kb_core/missing", None).unwrap(), (String::new(), false));
+        let code = |layer, value| mm.access(layer, "k", value).unwrap_err().code();
+        assert_eq!(code(3, None), tonic::Code::FailedPrecondition, "no long-term backend");
+        assert_eq!(code(4, Some("v")), tonic::Code::InvalidArgument, "L4 writes use UpsertVectors");
+        assert_eq!(code(7, None), tonic::Code::FailedPrecondition, "archive not configured");
+        assert_eq!(code(8, None), tonic::Code::InvalidArgument);
+
+        let caps = mm.layer_capabilities();
+        assert_eq!(caps.iter().map(|c| c.layer).collect::<Vec<_>>(), (1..=7).collect::<Vec<_>>());
+        assert_eq!((caps[3].backend.as_str(), caps[3].readable, caps[3].writable), ("memory", true, false));
+        assert!(caps[2].backend.is_empty() && caps[6].backend.is_empty());
+    }
+
+    #[test]
+    fn l2_snapshot_restores_history() {
+        let path = std::env::temp_dir()
+            .join(format!("pagi_l2_{}", uuid::Uuid::new_v4()))
+            .join("l2.json");
+        let mm = MemoryManager::build(None, Duration::from_millis(1));
+        assert_eq!(mm.restore(&path), Ok(0), "missing snapshot restores nothing");
+        for v in ["a", "b", "c"] {
+            mm.access(2, "goal", Some(v)).unwrap();
+        }
+        assert!(mm.l2_dirty.load(Ordering::Relaxed));
+        assert_eq!(mm.snapshot_l2(&path), Ok(1));
+        assert!(!mm.l2_dirty.load(Ordering::Relaxed));
+
+        let mut restarted = MemoryManager::build(None, Duration::from_millis(1));
+        restarted.l2_depth = 2;
+        assert_eq!(restarted.restore(&path), Ok(1));
+        assert_eq!(restarted.access(2, "goal", None).unwrap().0, "c");
+        let latest = |mm: &MemoryManager| {
+            mm.access_at(&MemoryAtRequest {
+                layer: 2,
+                key: "goal".into(),
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        assert_eq!(latest(&restarted).oldest_version, 2, "trimmed to the current depth");
+        restarted.access(2, "goal", Some("d")).unwrap();
+        assert_eq!(latest(&restarted).version, 4, "versions continue after restore");
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn large_l2_values_are_shared_and_snapshotted_once() {
+        let path = std::env::temp_dir()
+            .join(format!("pagi_l2_blobs_{}", uuid::Uuid::new_v4()))
+            .join("l2.json");
+        let with_blobs = || {
+            let mut mm = MemoryManager::build(None, Duration::from_millis(1));
+            mm.blob_policy = BlobPolicy {
+                min_bytes: 64,
+                level: 3,
+            };
+            mm.l2_blobs = Interner::new(mm.blob_policy);
+            mm
+        };
+        let mm = with_blobs();
+        let observation = "stderr: connection refused\n".repeat(100);
+        mm.access(2, "session:1/observation", Some(&observation)).unwrap();
+        mm.access(2, "session:2/observation", Some(&observation)).unwrap();
+        mm.access(2, "goal", Some("ship")).unwrap();
+        let shared = |mm: &MemoryManager| {
+            let (a, b) = (mm.read_l2("session:1/observation"), mm.read_l2("session:2/observation"));
+            Arc::ptr_eq(&a.unwrap(), &b.unwrap())
+        };
+        assert!(shared(&mm), "one allocation for both keys");
+        assert_eq!(mm.snapshot_l2(&path), Ok(3));
+        let raw = std::fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains("connection refused") && raw.len() < observation.len(), "{}", raw);
+
+        let restarted = with_blobs();
+        assert_eq!(restarted.restore(&path), Ok(3));
+        assert_eq!(restarted.access(2, "session:2/observation", None).unwrap().0, observation);
+        assert_eq!(restarted.access(2, "goal", None).unwrap().0, "ship");
+        assert!(shared(&restarted), "shared again after restore");
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    fn retrying() -> MemoryManager {
+        let mut mm = MemoryManager::in_memory(4);
+        mm.l4_retry = RetryPolicy::new(3, Duration::from_millis(1), Duration::from_millis(1));
+        mm
+    }
+
+    /// An L4 call that fails with a transport error twice, then returns its call count.
+    fn flaky(calls: &std::sync::atomic::AtomicU32) -> impl FnMut() -> std::future::Ready<Result<u32, String>> + '_ {
+        move || {
+            let n = calls.fetch_add(1, Ordering::Relaxed);
+            std::future::ready(if n < 2 { Err("transport error: connection refused".to_string()) } else { Ok(n) })
+        }
+    }
+
+    #[tokio::test]
+    async fn transport_errors_are_retried() {
+        let mm = retrying();
+        let calls = std::sync::atomic::AtomicU32::new(0);
+        assert_eq!(mm.guarded_retry("search", flaky(&calls)).await.unwrap(), 2);
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+        assert_eq!(mm.counters.l4_retries.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn request_errors_are_not_retried() {
+        let mm = retrying();
+        let calls = std::sync::atomic::AtomicU32::new(0);
+        let bad = || {
+            calls.fetch_add(1, Ordering::Relaxed);
+            async { Err::<(), _>("collection kb_x not found".to_string()) }
+        };
+        assert_eq!(mm.guarded_retry("search", bad).await.unwrap_err().code(), tonic::Code::Internal);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn a_failing_probe_degrades_l4_and_stops_retries() {
+        let mm = retrying();
+        mm.l4_probe_failing.store(true, Ordering::Relaxed);
+        assert_eq!((mm.health().l4_state.as_str(), mm.health().l4_probe.as_str()), ("degraded", "failing"));
+        let calls = std::sync::atomic::AtomicU32::new(0);
+        assert!(mm.guarded_retry("upsert", flaky(&calls)).await.is_err());
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn a_passing_probe_restores_l4() {
+        let mm = retrying();
+        mm.l4_probe_failing.store(true, Ordering::Relaxed);
+        assert!(mm.probe_l4().await, "the memory backend is always live");
+        assert_eq!((mm.health().l4_state.as_str(), mm.health().l4_probe.as_str()), ("ok", "ok"));
+    }
+
+    #[tokio::test]
+    async fn wal_replays_unacked_upserts() {
+        let dir = std::env::temp_dir().join(format!("pagi_mm_wal_{}", uuid::Uuid::new_v4()));
+        let wal = Wal::open(&dir, 1 << 20).unwrap();
+        let mm = MemoryManager::in_memory(4).with_wal(Some(wal));
+        mm.ensure_kb("kb_core").await.unwrap();
+        let point = VectorPoint {
+            id: "lost".into(),
+            vector: vec![1.0; 4],
+            payload: HashMap::from([("content".to_string(), "accepted before the crash".to_string())]),
+            ..Default::default()
+        };
+        // Logged but never acked: the process died before the store answered.
+        std::mem::forget(mm.wal.as_ref().unwrap().log("kb_core", &[point]).unwrap());
+        mm.upsert_vectors(UpsertRequest {
+            kb_name: "kb_core".into(),
+            points: vec![VectorPoint {
+                id: "acked".into(),
+                vector: vec![0.5; 4],
+                ..Default::default()
+            }],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        assert_eq!(mm.wal.as_ref().unwrap().pending().len(), 1, "completed upserts are acked");
+
+        assert_eq!(mm.replay_wal().await, 1);
+        let hits = mm
+            .semantic_search(SearchRequest {
+                kb_name: "kb_core".into(),
+                query_vector: vec![1.0; 4],
+                limit: 5,
+                ..Default::default()
+            })
+            .await
+            .unwrap()
+            .hits;
+        assert!(hits.iter().any(|h| h.document_id == "lost"));
+        assert!(mm.wal.as_ref().unwrap().pending().is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn upsert_dedup_skips_or_merges_stored_content() {
+        let mut mm = MemoryManager::build(Some(Box::new(MemoryStore::new())), Duration::from_secs(1));
+        mm.dedup_default = DedupMode::Off;
+        mm.ensure_kb("kb_core").await.unwrap();
+        let point = |id: &str, content: &str, extra: &str| VectorPoint {
+            id: id.into(),
+            vector: vec![1.0; mm.embedding_dim],
+            payload: HashMap::from([
+                ("content".to_string(), content.to_string()),
+                (extra.to_string(), id.to_string()),
+            ]),
+            ..Default::default()
+        };
+        let upsert = |points: Vec<VectorPoint>, dedup: &str| {
+            mm.upsert_vectors(UpsertRequest {
+                kb_name: "kb_core".into(),
+                points,
+                dedup: dedup.into(),
+                ..Default::default()
+            })
+        };
+        let first = upsert(vec![point("a", "same text", "src"), point("a2", "same text", "src")], "skip")
+            .await
+            .unwrap();
+        assert_eq!((first.upserted_count, first.skipped_count), (1, 1), "duplicate within the request");
+        let again = upsert(vec![point("b", "same text", "src"), point("c", "other", "src")], "skip")
+            .await
+            .unwrap();
+        assert_eq!((again.upserted_count, again.skipped_count, again.merged_count), (1, 1, 0));
+        let off = upsert(vec![point("a", "same text", "src")], "").await.unwrap();
+        assert_eq!((off.upserted_count, off.skipped_count), (1, 0), "same id is a plain replace");
+
+        let merged = upsert(vec![point("d", "same text", "tag")], "merge").await.unwrap();
+        assert_eq!((merged.upserted_count, merged.merged_count), (1, 1));
+        let stored = mm.l4_or_disabled().unwrap().get("kb_core", vec!["a".into(), "d".into()]).await.unwrap();
+        assert_eq!(stored.len(), 1, "merged into the stored point");
+        assert_eq!((stored[0].payload["src"].as_str(), stored[0].payload["tag"].as_str()), ("a", "d"));
+        assert_eq!(stored[0].payload[dedup::HASH_FIELD].len(), 64);
+        assert!(upsert(vec![], "replace").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn pruned_points_are_archived_and_rehydrated() {
+        let dir = std::env::temp_dir().join(format!("pagi_l7_mm_{}", uuid::Uuid::new_v4()));
+        let mut mm = MemoryManager::build(Some(Box::new(MemoryStore::new())), Duration::from_secs(1));
+        mm.archive = Some(Archive::new(dir.clone(), 1 << 20));
+        mm.ensure_kb("kb_core").await.unwrap();
+        let vector = vec![1.0; mm.embedding_dim];
+        mm.upsert_vectors(UpsertRequest {
+            kb_name: "kb_core".into(),
+            points: vec![VectorPoint {
+                id: "c".into(),
+                vector: vector.clone(),
+                payload: HashMap::from([("content".to_string(), "gamma fact".to_string())]),
+                ..Default::default()
+            }],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        assert_eq!(mm.archive_and_delete("kb_core", &["c".to_string()], "age", 40).await.unwrap(), 1);
+        let query = SearchRequest {
+            kb_name: "kb_core".into(),
+            query_vector: vector,
+            limit: 1,
+            ..Default::default()
+        };
+        assert!(mm.search_points(query.clone()).await.unwrap().points.is_empty(), "deleted from L4");
+
+        let resp = mm
+            .recall_archive(RecallArchiveRequest {
+                query: "gamma".into(),
+                rehydrate: true,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!((resp.points.len(), resp.rehydrated, resp.points[0].reason.as_str()), (1, 1, "age"));
+        let restored = mm.search_points(query).await.unwrap();
+        assert_eq!(restored.points[0].payload["content"], "gamma fact");
+        assert_eq!(restored.points[0].payload["rehydrated_from"], "l7");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn portable_snapshots_restore_the_kb_exactly() {
+        let dir = std::env::temp_dir().join(format!("pagi_mm_snapshots_{}", uuid::Uuid::new_v4()));
+        let mut mm = MemoryManager::in_memory(2);
+        mm.snapshots = SnapshotDir::new(dir.clone());
+        mm.ensure_kb("kb_core").await.unwrap();
+        let point = |id: &str, content: &str| VectorPoint {
+            id: id.to_string(),
+            vector: vec![1.0, 0.5],
+            payload: HashMap::from([("content".to_string(), content.to_string())]),
+            ..Default::default()
+        };
+        let upsert = |points: Vec<VectorPoint>| UpsertRequest {
+            kb_name: "kb_core".into(),
+            points,
+            ..Default::default()
+        };
+        mm.upsert_vectors(upsert(vec![point("a", "alpha"), point("b", "beta")])).await.unwrap();
+        let snap = mm
+            .snapshot_kb(SnapshotKbRequest {
+                kb_name: "kb_core".into(),
+                label: "pre-apply".into(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!((snap.format.as_str(), snap.points), ("points", 2));
+        assert!(snap.snapshot.ends_with("-pre-apply.jsonl") && snap.bytes > 0, "{:?}", snap.snapshot);
+
+        mm.upsert_vectors(upsert(vec![point("a", "overwritten"), point("c", "gamma")])).await.unwrap();
+        let restored = mm
+            .restore_kb(RestoreKbRequest {
+                kb_name: "kb_core".into(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(restored.snapshot, snap.snapshot, "newest snapshot by default");
+        assert_eq!((restored.points, restored.removed), (2, 1));
+        let l4 = mm.l4_semantic.as_deref().unwrap();
+        let points = l4.get("kb_core", vec!["a".into(), "c".into()]).await.unwrap();
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].payload["content"], "alpha");
+
+        let missing = mm
+            .restore_kb(RestoreKbRequest {
+                kb_name: "kb_core".into(),
+                snapshot: "nope.jsonl".into(),
+                ..Default::default()
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(missing.code(), tonic::Code::NotFound);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn search_pages_and_thresholds_hits() {
+        let mm = MemoryManager::in_memory(2);
+        mm.ensure_kb("kb_core").await.unwrap();
+        let points = [("a", [1.0, 0.0]), ("b", [1.0, 0.5]), ("c", [1.0, 1.0]), ("d", [0.5, 1.0]), ("e", [0.0, 1.0])]
+            .into_iter()
+            .map(|(id, v)| VectorPoint {
+                id: id.to_string(),
+                vector: v.to_vec(),
+                payload: HashMap::from([("content".to_string(), format!("doc {}", id))]),
+                ..Default::default()
+            })
+            .collect();
+        mm.upsert_vectors(UpsertRequest {
+            kb_name: "kb_core".into(),
+            points,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let page = |offset: u32, score_threshold: Option<f32>| SearchRequest {
+            kb_name: "kb_core".into(),
+            query_vector: vec![1.0, 0.0],
+            limit: 2,
+            offset,
+            score_threshold,
+            with_payload: true,
+            ..Default::default()
+        };
+        let first = mm.semantic_search(page(0, None)).await.unwrap();
+        let ids: Vec<&str> = first.hits.iter().map(|h| h.document_id.as_str()).collect();
+        assert_eq!((ids, first.next_offset), (vec!["a", "b"], 2));
+        assert_eq!(first.hits[1].payload["content"], "doc b");
+        let last = mm.semantic_search(page(4, None)).await.unwrap();
+        assert_eq!((last.hits.len(), last.hits[0].document_id.as_str(), last.next_offset), (1, "e", 0));
+
+        // cos([1,0],[1,1]) ≈ 0.71 passes 0.6; "d" (≈ 0.45) and "e" do not.
+        let thresholded = mm.semantic_search(page(2, Some(0.6))).await.unwrap();
+        let ids: Vec<&str> = thresholded.hits.iter().map(|h| h.document_id.as_str()).collect();
+        assert_eq!((ids, thresholded.next_offset), (vec!["c"], 0));
+        let bare = mm
+            .semantic_search(SearchRequest {
+                with_payload: false,
+                ..page(0, None)
+            })
+            .await
+            .unwrap();
+        assert!(bare.hits[0].payload.is_empty());
+        let too_deep = mm.semantic_search(page(999, None)).await.unwrap_err();
+        assert_eq!(too_deep.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn time_weighted_search_lets_recent_points_outrank_stale_ones() {
+        let clock = ManualClock::at(10_000);
+        let mm = MemoryManager::in_memory(2).with_clock(clock.into());
+        mm.ensure_kb("kb_core").await.unwrap();
+        let point = |id: &str, vector: Vec<f32>, payload: &[(&str, &str)]| VectorPoint {
+            id: id.to_string(),
+            vector,
+            payload: payload.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            ..Default::default()
+        };
+        mm.upsert_vectors(UpsertRequest {
+            kb_name: "kb_core".into(),
+            points: vec![point("stale", vec![1.0, 0.0], &[("at", "0")]), point("recent", vec![1.0, 0.5], &[])],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let search = |recency_weight: f32| SearchRequest {
+            kb_name: "kb_core".into(),
+            query_vector: vec![1.0, 0.0],
+            limit: 2,
+            with_payload: true,
+            recency_weight,
+            recency_halflife_secs: 3600,
+            ..Default::default()
+        };
+        let ids = |resp: &SearchResponse| resp.hits.iter().map(|h| h.document_id.clone()).collect::<Vec<_>>();
+        let plain = mm.semantic_search(search(0.0)).await.unwrap();
+        assert_eq!(ids(&plain), ["stale", "recent"]);
+        let weighted = mm.semantic_search(search(0.8)).await.unwrap();
+        assert_eq!(ids(&weighted), ["recent", "stale"]);
+        assert_eq!(weighted.hits[0].payload["at"], "10000", "upserts stamp `at` when missing");
+    }
+
+    #[tokio::test]
+    async fn idle_points_move_to_the_cold_tier_and_searches_bring_them_back() {
+        let clock = ManualClock::at(10_000);
+        let mm = MemoryManager::in_memory(2).with_clock(clock.clone().into());
+        let spec = KbSpec {
+            cold_after_secs: Some(100),
+            ..mm.kbs.get("kb_core")
+        };
+        let l4 = mm.l4_semantic.as_deref().unwrap();
+        MemoryManager::create_if_missing(l4, &spec).await.unwrap();
+        mm.create_cold_tier(l4, &spec).await.unwrap();
+        let points = [("idle", [1.0, 0.0]), ("busy", [0.0, 1.0])]
+            .into_iter()
+            .map(|(id, v)| VectorPoint {
+                id: id.to_string(),
+                vector: v.to_vec(),
+                ..Default::default()
+            })
+            .collect();
+        mm.upsert_vectors(UpsertRequest {
+            kb_name: "kb_core".into(),
+            points,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let search = |v: [f32; 2]| SearchRequest {
+            kb_name: "kb_core".into(),
+            query_vector: v.to_vec(),
+            limit: 1,
+            ..Default::default()
+        };
+        let ids = |resp: &SearchResponse| resp.hits.iter().map(|h| h.document_id.clone()).collect::<Vec<_>>();
+        clock.advance(Duration::from_secs(150));
+        assert_eq!(ids(&mm.semantic_search(search([0.0, 1.0])).await.unwrap()), ["busy"]);
+        clock.advance(Duration::from_secs(50));
+
+        let stats = mm.prune_kb(&spec, mm.clock().now_secs()).await;
+        assert_eq!((stats.demoted, stats.promoted, stats.scanned), (1, 0, 2), "{}", stats.error);
+        assert_eq!(l4.point_count("kb_core.cold").await.unwrap(), Some(1));
+        assert_eq!(ids(&mm.semantic_search(search([1.0, 0.0])).await.unwrap()), ["idle"], "searches cover both tiers");
+
+        let stats = mm.prune_kb(&spec, mm.clock().now_secs()).await;
+        assert_eq!((stats.demoted, stats.promoted), (0, 1), "read within the window");
+        assert_eq!(l4.point_count("kb_core.cold").await.unwrap(), Some(0));
+        assert_eq!(l4.point_count("kb_core").await.unwrap(), Some(2));
+    }
+
+    #[tokio::test]
+    async fn points_with_a_ttl_are_swept_once_expired() {
+        let clock = ManualClock::at(10_000);
+        let mm = MemoryManager::in_memory(2).with_clock(clock.clone().into());
+        mm.ensure_kb("kb_core").await.unwrap();
+        let point = |id: &str, ttl: Option<&str>| VectorPoint {
+            id: id.to_string(),
+            vector: vec![1.0, 0.0],
+            payload: ttl.map(|t| HashMap::from([("ttl_seconds".to_string(), t.to_string())])).unwrap_or_default(),
+            ..Default::default()
+        };
+        let upsert = |points: Vec<VectorPoint>, ttl_seconds: u64| UpsertRequest {
+            kb_name: "kb_core".into(),
+            points,
+            ttl_seconds,
+            ..Default::default()
+        };
+        let points = vec![point("scratch", Some("60")), point("batch", None), point("kept", Some("0"))];
+        mm.upsert_vectors(upsert(points, 300)).await.unwrap();
+        mm.upsert_vectors(upsert(vec![point("durable", None)], 0)).await.unwrap();
+        let err = mm.upsert_vectors(upsert(vec![point("bad", Some("soon"))], 0)).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+        let mut until = point("incident", None);
+        until.payload.insert("expires_at".into(), "10030".into());
+        mm.upsert_vectors(upsert(vec![until], 300)).await.unwrap();
+        let search = || async {
+            let req = SearchRequest {
+                kb_name: "kb_core".into(),
+                query_vector: vec![1.0, 0.0],
+                limit: 10,
+                ..Default::default()
+            };
+            let mut ids: Vec<String> = mm.search_points(req).await.unwrap().points.into_iter().map(|p| p.id).collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(search().await, ["batch", "durable", "incident", "kept", "scratch"]);
+
+        let l4 = mm.l4_semantic.as_deref().unwrap();
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(search().await, ["batch", "durable", "kept"], "hidden before the sweep runs");
+        assert_eq!(mm.sweep_kb(l4, "kb_core", mm.clock().now_secs()).await.unwrap(), 2);
+        // Rewriting a point restarts its TTL.
+        mm.upsert_vectors(upsert(vec![point("batch", None)], 300)).await.unwrap();
+        clock.advance(Duration::from_secs(240));
+        assert_eq!(mm.sweep_kb(l4, "kb_core", mm.clock().now_secs()).await.unwrap(), 0);
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(mm.sweep_kb(l4, "kb_core", mm.clock().now_secs()).await.unwrap(), 1);
+        let left = l4.get("kb_core", ["scratch", "batch", "kept", "durable"].map(String::from).to_vec()).await.unwrap();
+        let mut left: Vec<String> = left.into_iter().map(|p| p.id).collect();
+        left.sort();
+        assert_eq!(left, ["durable", "kept"]);
+    }
+
+    #[tokio::test]
+    async fn client_expiries_hide_points_from_cached_searches() {
+        let clock = ManualClock::at(1_700_000_000);
+        let mm = MemoryManager::in_memory(2).with_clock(clock.clone().into());
+        mm.ensure_kb("kb_core").await.unwrap();
+        let point = |id: &str, expires_at: Option<&str>| VectorPoint {
+            id: id.to_string(),
+            vector: vec![1.0, 0.0],
+            payload: expires_at
+                .map(|at| HashMap::from([("expires_at".to_string(), at.to_string())]))
+                .unwrap_or_default(),
+            ..Default::default()
+        };
+        let points = vec![point("incident", Some("2023-11-14T22:13:25Z")), point("durable", None)];
+        mm.upsert_vectors(UpsertRequest {
+            kb_name: "kb_core".into(),
+            points,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let search = || async {
+            let req = SearchRequest {
+                kb_name: "kb_core".into(),
+                query_vector: vec![1.0, 0.0],
+                limit: 10,
+                ..Default::default()
+            };
+            let mut ids: Vec<String> = mm.search_points(req).await.unwrap().points.into_iter().map(|p| p.id).collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(search().await, ["durable", "incident"]);
+
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(search().await, ["durable"], "cached before the point expired");
+        let l4 = mm.stats().await.layers.into_iter().find(|l| l.layer == 4).unwrap();
+        assert_eq!(l4.search_cache_hits, 1);
+    }
+
+    #[tokio::test]
+    async fn write_memory_applies_both_layers_or_neither() {
+        use crate::proto::pagi_proto::L2Write;
+
+        let mm = MemoryManager::in_memory(2).with_clock(ManualClock::at(1_700_000_000).into());
+        mm.ensure_kb("kb_core").await.unwrap();
+        let point = |id: &str, vector: Vec<f32>, content: &str| VectorPoint {
+            id: id.to_string(),
+            vector,
+            payload: HashMap::from([("content".to_string(), content.to_string())]),
+            ..Default::default()
+        };
+        let write = |points: Vec<VectorPoint>, value: &str| MemoryWriteRequest {
+            l2: vec![L2Write {
+                key: "goal".into(),
+                value: value.into(),
+            }],
+            l4: Some(UpsertRequest {
+                kb_name: "kb_core".into(),
+                points,
+                dedup: "off".into(),
+                ..Default::default()
+            }),
+            namespace: "team_a".into(),
+            ..Default::default()
+        };
+        let resp = mm.write_memory(write(vec![point("a", vec![1.0, 0.0], "v1")], "ship")).await.unwrap();
+        let txn: ReasoningId = resp.transaction_id.parse().unwrap();
+        assert_eq!(txn.timestamp_ms(), Some(1_700_000_000_000));
+        assert_eq!((resp.l2_versions, resp.l4.unwrap().upserted_count), (vec![1], 1));
+
+        // "c" fails the write: "a" keeps its first version, "b" is not left behind and L2 is untouched.
+        let bad = vec![point("a", vec![0.0, 1.0], "v2"), point("b", vec![0.5, 0.5], "new"), point("c", vec![1.0], "")];
+        assert!(mm.write_memory(write(bad, "abandon")).await.is_err());
+        let l4 = mm.l4_semantic.as_deref().unwrap();
+        let left = l4.get("kb_core@team_a", ["a", "b"].map(String::from).to_vec()).await.unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!((left[0].vector.as_slice(), left[0].payload["content"].as_str()), ([1.0, 0.0].as_slice(), "v1"));
+        assert_eq!(mm.read_l2("goal@team_a").as_deref(), Some("ship"));
+
+        let bad_key = MemoryWriteRequest {
+            namespace: "no spaces".into(),
+            ..write(vec![], "x")
+        };
+        assert_eq!(mm.write_memory(bad_key).await.unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn search_feedback_is_counted_on_points_and_reorders_later_searches() {
+        let mm = MemoryManager::in_memory(2);
+        mm.ensure_kb("kb_core").await.unwrap();
+        let points = [("a", [1.0, 0.0]), ("b", [0.9, 0.1]), ("c", [0.0, 1.0])]
+            .into_iter()
+            .map(|(id, v)| VectorPoint {
+                id: id.to_string(),
+                vector: v.to_vec(),
+                ..Default::default()
+            })
+            .collect();
+        mm.upsert_vectors(UpsertRequest {
+            kb_name: "kb_core".into(),
+            points,
+            dedup: "off".into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let search = || async {
+            let req = SearchRequest {
+                kb_name: "kb_core".into(),
+                query_vector: vec![1.0, 0.0],
+                limit: 3,
+                feedback_weight: Some(0.5),
+                ..Default::default()
+            };
+            let hits = mm.semantic_search(req).await.unwrap().hits;
+            hits.into_iter().map(|h| h.document_id).collect::<Vec<_>>()
+        };
+        assert_eq!(search().await, ["a", "b", "c"]);
+
+        let feedback = |useful: &[&str], unhelpful: &[&str]| SearchFeedbackRequest {
+            kb_name: "kb_core".into(),
+            useful_ids: useful.iter().map(|s| s.to_string()).collect(),
+            unhelpful_ids: unhelpful.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+        let resp = mm.record_feedback(feedback(&["b", "gone"], &["a"])).await.unwrap();
+        assert_eq!((resp.recorded, resp.missing_ids), (2, vec!["gone".to_string()]));
+        mm.record_feedback(feedback(&["b"], &[])).await.unwrap();
+        let l4 = mm.l4_semantic.as_deref().unwrap();
+        let b = l4.get("kb_core", vec!["b".into()]).await.unwrap();
+        assert_eq!(b[0].payload[feedback::UP_FIELD], "2");
+        assert_eq!(search().await, ["b", "a", "c"], "the cached ranking was dropped");
+
+        let err = mm.record_feedback(feedback(&["a"], &["a"])).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn mmr_rerank_sinks_near_duplicates_and_unusable_rerankers_are_rejected() {
+        let mm = MemoryManager::in_memory(2);
+        mm.ensure_kb("kb_core").await.unwrap();
+        let points = [("a", [1.0, 0.0]), ("a_copy", [1.0, 0.05]), ("b", [0.8, 0.6]), ("c", [0.0, 1.0])]
+            .into_iter()
+            .map(|(id, v)| VectorPoint {
+                id: id.to_string(),
+                vector: v.to_vec(),
+                ..Default::default()
+            })
+            .collect();
+        mm.upsert_vectors(UpsertRequest {
+            kb_name: "kb_core".into(),
+            points,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let search = |rerank: &str| SearchRequest {
+            kb_name: "kb_core".into(),
+            query_vector: vec![1.0, 0.0],
+            limit: 4,
+            rerank: rerank.to_string(),
+            mmr_lambda: Some(0.3),
+            ..Default::default()
+        };
+        let ids = |resp: &SearchResponse| resp.hits.iter().map(|h| h.document_id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&mm.semantic_search(search("")).await.unwrap()), ["a", "a_copy", "b", "c"]);
+        assert_eq!(ids(&mm.semantic_search(search("mmr")).await.unwrap()), ["a", "c", "b", "a_copy"]);
+        let unknown = mm.semantic_search(search("bm25")).await.unwrap_err();
+        assert_eq!(unknown.code(), tonic::Code::InvalidArgument);
+        let unconfigured = mm.semantic_search(search("cross_encoder")).await.unwrap_err();
+        assert_eq!(unconfigured.code(), tonic::Code::FailedPrecondition);
+    }
+
+    #[tokio::test]
+    async fn repeated_searches_are_cached_until_the_kb_is_written() {
+        let mm = MemoryManager::in_memory(2);
+        mm.ensure_kb("kb_core").await.unwrap();
+        let upsert = |id: &str| UpsertRequest {
+            kb_name: "kb_core".into(),
+            points: vec![VectorPoint {
+                id: id.to_string(),
+                vector: vec![1.0, 0.0],
+                payload: HashMap::from([("content".to_string(), format!("trace {}", id))]),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        mm.upsert_vectors(upsert("a")).await.unwrap();
+        let query = SearchRequest {
+            kb_name: "kb_core".into(),
+            query_vector: vec![1.0, 0.0],
+            limit: 5,
+            ..Default::default()
+        };
+        assert_eq!(mm.semantic_search(query.clone()).await.unwrap().hits.len(), 1);
+        assert_eq!(mm.semantic_search(query.clone()).await.unwrap().hits.len(), 1);
+        let l4 = |stats: &MemoryStatsResponse| stats.layers.iter().find(|l| l.layer == 4).cloned().unwrap();
+        let before = l4(&mm.stats().await);
+        assert_eq!((before.search_cache_hits, before.search_cache_misses, before.hits), (1, 1, 2));
+
+        mm.upsert_vectors(upsert("b")).await.unwrap();
+        assert_eq!(mm.semantic_search(query.clone()).await.unwrap().hits.len(), 2, "upsert invalidates");
+        let eval = SearchTuning {
+            untracked: true,
+            ..Default::default()
+        };
+        mm.search_points_tuned(query, eval).await.unwrap();
+        let after = l4(&mm.stats().await);
+        assert_eq!((after.search_cache_hits, after.search_cache_misses), (1, 2), "eval bypasses the cache");
+        assert_eq!((after.search_cache_invalidations, after.search_cache_entries), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn scroll_kb_streams_every_point_in_resumable_pages() {
+        let mm = Arc::new(MemoryManager::in_memory(2));
+        mm.ensure_kb("kb_core").await.unwrap();
+        let points = ["a", "b", "c"]
+            .iter()
+            .map(|id| VectorPoint {
+                id: id.to_string(),
+                vector: vec![1.0, 0.5],
+                payload: HashMap::from([("content".to_string(), format!("fact {}", id))]),
+                ..Default::default()
+            })
+            .collect();
+        mm.upsert_vectors(UpsertRequest {
+            kb_name: "kb_core".into(),
+            points,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let req = ScrollKbRequest {
+            kb_name: "kb_core".into(),
+            page_size: 2,
+            ..Default::default()
+        };
+        let pages: Vec<ScrollKbPage> = mm.scroll_kb(req.clone()).await.unwrap().map(Result::unwrap).collect().await;
+        let ids: Vec<Vec<&str>> = pages.iter().map(|p| p.points.iter().map(|x| x.id.as_str()).collect()).collect();
+        assert_eq!(ids, [vec!["a", "b"], vec!["c"]]);
+        assert_eq!((pages[0].next_offset.as_str(), pages[1].next_offset.as_str()), ("c", ""));
+        assert_eq!(pages[1].points[0].payload["content"], "fact c");
+        assert!(pages[1].points[0].vector.is_empty(), "vectors only on request");
+
+        let resumed = ScrollKbRequest {
+            offset: "c".into(),
+            with_vectors: true,
+            ..req.clone()
+        };
+        let pages: Vec<_> = mm.scroll_kb(resumed).await.unwrap().collect().await;
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].as_ref().unwrap().points[0].vector.len(), 2);
+        let missing = ScrollKbRequest {
+            kb_name: "kb_missing".into(),
+            ..req
+        };
+        assert_eq!(mm.scroll_kb(missing).await.unwrap_err().code(), tonic::Code::NotFound);
+    }
+
+    /// kb_core with one point per agent namespace, and each agent's L2 "goal" holding the same content.
+    async fn two_agents() -> MemoryManager {
+        let mm = MemoryManager::in_memory(2);
+        mm.ensure_kb("kb_core").await.unwrap();
+        for (namespace, content) in [("agent-a", "alpha secret"), ("agent-b", "beta secret")] {
+            mm.upsert_vectors(UpsertRequest {
+                kb_name: "kb_core".into(),
+                points: vec![VectorPoint {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    vector: vec![1.0, 0.0],
+                    payload: HashMap::from([("content".to_string(), content.to_string())]),
+                    ..Default::default()
+                }],
+                namespace: namespace.into(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+            let key = scoped_key(2, namespace, "goal").unwrap();
+            mm.access(2, &key, Some(content)).unwrap();
+        }
+        mm
+    }
+
+    fn namespace_search(namespace: &str) -> SearchRequest {
+        SearchRequest {
+            kb_name: "kb_core".into(),
+            query_vector: vec![1.0, 0.0],
+            limit: 10,
+            namespace: namespace.into(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn namespaced_searches_see_only_their_collection() {
+        let mm = two_agents().await;
+        let a = mm.semantic_search(namespace_search("agent-a")).await.unwrap();
+        assert_eq!(a.hits.iter().map(|h| h.content_snippet.as_str()).collect::<Vec<_>>(), ["alpha secret"]);
+        assert!(mm.semantic_search(namespace_search("agent-c")).await.unwrap().hits.is_empty());
+    }
+
+    #[tokio::test]
+    async fn namespaced_writes_leave_the_default_kb_untouched() {
+        let mm = two_agents().await;
+        assert!(mm.semantic_search(namespace_search("")).await.unwrap().hits.is_empty());
+    }
+
+    #[tokio::test]
+    async fn unscoped_callers_name_namespaced_collections_directly() {
+        let mm = two_agents().await;
+        let operator = SearchRequest {
+            kb_name: "kb_core@agent-b".into(),
+            ..namespace_search("")
+        };
+        assert_eq!(mm.semantic_search(operator).await.unwrap().hits.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn namespaced_callers_cannot_name_another_namespace() {
+        let mm = two_agents().await;
+        let escape = SearchRequest {
+            kb_name: "kb_core@agent-b".into(),
+            ..namespace_search("agent-a")
+        };
+        assert_eq!(mm.semantic_search(escape).await.unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn scoped_l2_keys_are_separate_entries() {
+        let mm = two_agents().await;
+        assert_eq!(mm.access(2, &scoped_key(2, "agent-b", "goal").unwrap(), None).unwrap().0, "beta secret");
+        assert_eq!(mm.access(2, "goal", None).unwrap().0, "");
+    }
+
+    #[test]
+    fn keys_are_scoped_per_layer() {
+        assert_eq!(scoped_key(4, "agent-a", "kb_core/p1").unwrap(), "kb_core@agent-a/p1");
+        assert_eq!(scoped_key(6, "agent-a", "r1").unwrap(), "r1");
+        assert!(scoped_key(2, "agent a", "goal").is_err());
+    }
+
+    #[tokio::test]
+    async fn upsert_stream_flushes_bounded_batches_per_kb() {
+        let mm = MemoryManager::build(Some(Box::new(MemoryStore::new())), Duration::from_secs(1));
+        for kb in ["kb_core", "kb_skills"] {
+            mm.ensure_kb(kb).await.unwrap();
+        }
+        let points = |ids: &[&str]| {
+            ids.iter()
+                .map(|id| VectorPoint {
+                    id: id.to_string(),
+                    vector: vec![1.0; mm.embedding_dim],
+                    ..Default::default()
+                })
+                .collect::<Vec<_>>()
+        };
+        let messages = vec![
+            Ok(UpsertRequest {
+                kb_name: "kb_core".into(),
+                points: points(&["a", "b", "c"]),
+                ..Default::default()
+            }),
+            Ok(UpsertRequest {
+                kb_name: String::new(),
+                points: points(&["d", "e"]),
+                ..Default::default()
+            }),
+            Ok(UpsertRequest {
+                kb_name: "kb_skills".into(),
+                points: points(&["f"]),
+                ..Default::default()
+            }),
+        ];
+        let resp = mm.upsert_stream(tokio_stream::iter(messages), 2).await.unwrap();
+        let batches: Vec<(&str, u32)> = resp.batches.iter().map(|b| (b.kb_name.as_str(), b.upserted_count)).collect();
+        assert_eq!(batches, [("kb_core", 2), ("kb_core", 2), ("kb_core", 1), ("kb_skills", 1)]);
+        assert_eq!(resp.upserted_count, 6);
+
+        let failing = vec![
+            Ok(UpsertRequest {
+                kb_name: "kb_core".into(),
+                points: points(&["g", "h"]),
+                ..Default::default()
+            }),
+            Ok(UpsertRequest {
+                kb_name: "kb_missing".into(),
+                points: points(&["i"]),
+                ..Default::default()
+            }),
+        ];
+        let err = mm.upsert_stream(tokio_stream::iter(failing), 2).await.unwrap_err();
+        assert!(err.message().contains("batch 2 for kb_missing failed after 2 point(s) stored"), "{}", err.message());
+        let unnamed = vec![Ok(UpsertRequest::default())];
+        assert_eq!(
+            mm.upsert_stream(tokio_stream::iter(unnamed), 2).await.unwrap_err().code(),
+            tonic::Code::InvalidArgument
+        );
     }
 }
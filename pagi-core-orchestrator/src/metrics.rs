@@ -0,0 +1,73 @@
+// In-process RPC metrics: bounded per-method latency samples recorded by the in-flight registry on
+// handler completion (including errors and aborts), plus per-skill wall/CPU series ("skill.<name>",
+// "skill.<name>.cpu") from real dispatch. Read by the SLO evaluator.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+
+/// Samples kept per method; older samples are dropped first.
+const MAX_SAMPLES: usize = 4096;
+
+static GLOBAL: OnceLock<Arc<MetricsStore>> = OnceLock::new();
+
+/// Process-wide store shared by the gRPC layer, the watchdog and the SLO evaluator.
+pub fn global() -> Arc<MetricsStore> {
+    Arc::clone(GLOBAL.get_or_init(|| Arc::new(MetricsStore::new())))
+}
+
+#[derive(Default)]
+pub struct MetricsStore {
+    latencies: DashMap<String, VecDeque<(Instant, Duration)>>,
+}
+
+impl MetricsStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_latency(&self, method: &str, elapsed: Duration) {
+        let mut samples = self.latencies.entry(method.to_string()).or_default();
+        if samples.len() >= MAX_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back((Instant::now(), elapsed));
+    }
+
+    /// Nearest-rank percentile (0–100) of `method` latencies recorded within `window`,
+    /// with the sample count; None when there are no samples in the window.
+    pub fn percentile(&self, method: &str, pct: f64, window: Duration) -> Option<(Duration, usize)> {
+        let samples = self.latencies.get(method)?;
+        let mut recent: Vec<Duration> = samples
+            .iter()
+            .filter(|(at, _)| at.elapsed() <= window)
+            .map(|(_, d)| *d)
+            .collect();
+        if recent.is_empty() {
+            return None;
+        }
+        recent.sort();
+        let rank = ((pct / 100.0) * recent.len() as f64).ceil() as usize;
+        Some((recent[rank.clamp(1, recent.len()) - 1], recent.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_rank_percentile_over_window() {
+        let m = MetricsStore::new();
+        for ms in 1..=100 {
+            m.record_latency("ExecuteAction", Duration::from_millis(ms));
+        }
+        let window = Duration::from_secs(60);
+        assert_eq!(m.percentile("ExecuteAction", 95.0, window), Some((Duration::from_millis(95), 100)));
+        assert_eq!(m.percentile("ExecuteAction", 100.0, window).unwrap().0, Duration::from_millis(100));
+        assert!(m.percentile("ExecuteAction", 95.0, Duration::ZERO).is_none());
+        assert!(m.percentile("SemanticSearch", 95.0, window).is_none());
+    }
+}
@@ -0,0 +1,183 @@
+// Mock-mode fixtures: per-skill canned observations with optional latency and failure injection, loaded
+// from PAGI_MOCK_FIXTURES (JSON object keyed by skill name; "*" is the fallback). Failures are drawn from
+// a hash of reasoning_id + skill + params, so the same request always gets the same outcome.
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::proto::pagi_proto::ActionResponse;
+
+const DEFAULT_OBSERVATION: &str = "Observation: mock executed skill={skill}";
+const DEFAULT_ERROR: &str = "mock failure injected for skill={skill}";
+
+/// One skill's simulated behavior; templates expand `{skill}` and `{<param>}` from the request.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Fixture {
+    /// Observation on success (default: the generic "mock executed" line).
+    observation: Option<String>,
+    /// Simulated execution time before responding.
+    #[serde(default)]
+    latency_ms: u64,
+    /// Fraction of requests (0.0–1.0) that fail with `error`.
+    #[serde(default)]
+    fail_rate: f64,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MockFixtures {
+    fixtures: BTreeMap<String, Fixture>,
+}
+
+impl MockFixtures {
+    /// Fixtures from PAGI_MOCK_FIXTURES; unset, unreadable or invalid files leave the generic mock (logged).
+    pub fn from_env() -> Self {
+        let Some(path) = std::env::var("PAGI_MOCK_FIXTURES")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+        else {
+            return Self::default();
+        };
+        match std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|raw| Self::from_json(&raw))
+        {
+            Ok(fixtures) => {
+                eprintln!("[MockFixtures] {} fixture(s) from {}", fixtures.fixtures.len(), path);
+                fixtures
+            }
+            Err(e) => {
+                eprintln!("[MockFixtures] ignoring {}: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    fn from_json(raw: &str) -> Result<Self, String> {
+        let fixtures: BTreeMap<String, Fixture> = serde_json::from_str(raw).map_err(|e| e.to_string())?;
+        if let Some((name, f)) = fixtures.iter().find(|(_, f)| !(0.0..=1.0).contains(&f.fail_rate)) {
+            return Err(format!("{}: fail_rate {} outside 0.0..=1.0", name, f.fail_rate));
+        }
+        Ok(Self { fixtures })
+    }
+
+    /// Simulated ActionResponse for a mock-mode request (sleeps for the fixture's latency first).
+    pub async fn respond(&self, skill: &str, params: &HashMap<String, String>, reasoning_id: &str) -> ActionResponse {
+        let fixture = self
+            .fixtures
+            .get(skill)
+            .or_else(|| self.fixtures.get("*"))
+            .cloned()
+            .unwrap_or_default();
+        if fixture.latency_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(fixture.latency_ms)).await;
+        }
+        let expand = |template: &str| {
+            params
+                .iter()
+                .fold(template.replace("{skill}", skill), |s, (k, v)| s.replace(&format!("{{{}}}", k), v))
+        };
+        if fixture.fail_rate > 0.0 && draw(skill, params, reasoning_id) < fixture.fail_rate {
+            return ActionResponse {
+                success: false,
+                error: expand(fixture.error.as_deref().unwrap_or(DEFAULT_ERROR)),
+                ..Default::default()
+            };
+        }
+        ActionResponse {
+            observation: expand(fixture.observation.as_deref().unwrap_or(DEFAULT_OBSERVATION)),
+            success: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// Uniform value in [0, 1) fixed by the request's identity.
+fn draw(skill: &str, params: &HashMap<String, String>, reasoning_id: &str) -> f64 {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{}\n{}\n", reasoning_id, skill));
+    for (k, v) in params.iter().collect::<BTreeMap<_, _>>() {
+        hasher.update(format!("{}={}\n", k, v));
+    }
+    let digest = hasher.finalize();
+    let n = u64::from_be_bytes(digest[..8].try_into().expect("sha256 has 8 bytes"));
+    (n >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> MockFixtures {
+        MockFixtures::from_json(
+            r#"{
+                "peek_file": {"observation": "Observation: first lines of {path}", "latency_ms": 20},
+                "flaky": {"fail_rate": 0.5, "error": "{skill} timed out"},
+                "*": {"observation": "Observation: default for {skill}"}
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn readme() -> HashMap<String, String> {
+        HashMap::from([("path".to_string(), "README.md".to_string())])
+    }
+
+    #[tokio::test]
+    async fn fixtures_fill_params_after_their_latency() {
+        let started = std::time::Instant::now();
+        let resp = fixtures().respond("peek_file", &readme(), "r1").await;
+        assert!(started.elapsed() >= Duration::from_millis(20));
+        assert!(resp.success);
+        assert_eq!(resp.observation, "Observation: first lines of README.md");
+    }
+
+    #[tokio::test]
+    async fn the_wildcard_fixture_covers_other_skills() {
+        let resp = fixtures().respond("list_dir", &readme(), "r1").await;
+        assert_eq!(resp.observation, "Observation: default for list_dir");
+    }
+
+    #[tokio::test]
+    async fn failures_are_deterministic_per_request_at_roughly_the_rate() {
+        let fixtures = fixtures();
+        let outcomes = request_outcomes(&fixtures).await;
+        assert_eq!(outcomes, request_outcomes(&fixtures).await);
+        let failures = outcomes.iter().filter(|ok| !**ok).count();
+        assert!((30..=70).contains(&failures), "{} of 100 failed", failures);
+    }
+
+    #[tokio::test]
+    async fn injected_failures_fill_the_error_template() {
+        let failed = (0..100)
+            .map(|i| format!("r{}", i))
+            .find(|id| draw("flaky", &HashMap::new(), id) < 0.5)
+            .unwrap();
+        assert_eq!(fixtures().respond("flaky", &HashMap::new(), &failed).await.error, "flaky timed out");
+    }
+
+    #[tokio::test]
+    async fn without_fixtures_responses_are_generic() {
+        let generic = MockFixtures::default().respond("peek_file", &readme(), "r1").await;
+        assert_eq!(generic.observation, "Observation: mock executed skill=peek_file");
+    }
+
+    #[test]
+    fn invalid_fixtures_are_rejected() {
+        assert!(MockFixtures::from_json(r#"{"x": {"fail_rate": 2.0}}"#).is_err());
+        assert!(MockFixtures::from_json(r#"{"x": {"latency": 5}}"#).is_err(), "unknown fields are rejected");
+    }
+
+    async fn request_outcomes(fixtures: &MockFixtures) -> Vec<bool> {
+        let mut outcomes = Vec::new();
+        for i in 0..100 {
+            outcomes.push(fixtures.respond("flaky", &HashMap::new(), &format!("r{}", i)).await.success);
+        }
+        outcomes
+    }
+}
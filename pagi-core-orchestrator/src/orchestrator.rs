@@ -0,0 +1,1193 @@
+// The Pagi gRPC service: Orchestrator routes every RPC to MemoryManager (L1–L7 memory), Watchdog (real dispatch,
+// self-heal, patches) and SafetyGovernor (depth, fan-out and HITL gates), with auth, validation, in-flight
+// tracking, session journals and lineage around them. `Orchestrator::from_env` wires what the binary runs;
+// `Orchestrator::new` builds one over caller-supplied parts for tests and embedders.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+use tonic::service::interceptor::InterceptedService;
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::auth::{self, Authenticator, OidcProvider};
+use crate::inflight::InFlightRegistry;
+use crate::ingest::Ingestor;
+use crate::lineage::LineageStore;
+use crate::memory_audit::MemoryAudit;
+use crate::memory_manager::{self, MemoryManager};
+use crate::mock_fixtures::MockFixtures;
+use crate::proto::pagi_proto::pagi_server::{Pagi, PagiServer};
+use crate::proto::pagi_proto::{
+    AbortInFlightRequest, AbortResponse, ActionRequest, ActionResponse, ApplyRequest, ApplyResponse, ApplyStatusRequest,
+    ApplyStatusResponse, CompactMemoryReport, CompactMemoryRequest, DeleteVectorsRequest, DeleteVectorsResponse, Empty,
+    EndSessionRequest, EndSessionResponse, GetPatchRequest, HealReport, HealReportRequest, HealRequest, HealResponse,
+    HealthResponse, HitlClientMessage, HotMemoryReport, HotMemoryRequest, InFlightRequests, IngestDocumentRequest,
+    IngestDocumentResponse, ListPendingPatchesRequest, ListPendingPatchesResponse, MemoryAtRequest, MemoryAtResponse,
+    MemoryDeleteRequest, MemoryDeleteResponse, MemoryRequest, MemoryResponse, MemoryScanRequest, MemoryScanResponse,
+    MemoryStatsResponse, MemoryWriteRequest, MemoryWriteResponse, PatchDetail, PatchRequest, PatchResponse,
+    PipelineRequest, PipelineResponse, ProfileRequest, ProfileResponse, RecallArchiveRequest, RecallArchiveResponse,
+    ReplayActionRequest, ReplayActionResponse, RestoreKbRequest, RestoreKbResponse, RlmRequest, RlmResponse,
+    RollbackPatchRequest, RollbackPatchResponse, ScrollKbRequest, SearchEvalReport, SearchEvalRequest,
+    SearchFeedbackRequest, SearchFeedbackResponse, SearchPatchesRequest, SearchPatchesResponse, SearchRequest,
+    SearchResponse, SimulationRequest, SimulationResponse, SmokeTestReport, SmokeTestRequest, SnapshotKbRequest,
+    SnapshotKbResponse, TraceQueryRequest, TraceQueryResponse, UpsertRequest, UpsertResponse, UpsertStreamResponse,
+};
+use crate::reasoning_id::ReasoningId;
+use crate::rlm_backend::RlmBackend;
+use crate::safety_governor::SafetyGovernor;
+use crate::scheduler::Scheduler;
+use crate::session::{self, SessionJournal};
+use crate::startup::Dependencies;
+use crate::validate::validate;
+use crate::watchdog::Watchdog;
+use crate::{
+    clock, compaction, consolidation, hitl, kb_registry, metrics, patch_history, pipeline, profiler, search_eval,
+    simulation, slo, smoke_test, store,
+};
+
+/// The Pagi service implementation; serve it with [`Orchestrator::serve`] or mount [`PagiServer`] yourself.
+pub struct Orchestrator {
+    memory: Arc<MemoryManager>,
+    watchdog: Arc<Watchdog>,
+    safety_governor: SafetyGovernor,
+    /// Tracked handlers for AdminListRequests / AbortRequest.
+    inflight: InFlightRegistry,
+    /// PAGI_MOCK_FIXTURES: canned observations for mock-mode dispatch.
+    mock_fixtures: MockFixtures,
+    /// Per-reasoning_id action/memory journal digested by EndSession.
+    sessions: SessionJournal,
+    /// L6 lineage (actions, RLM steps, patches, commits, KB writes per reasoning_id) for TraceQuery.
+    lineage: LineageStore,
+    /// PAGI_RLM_URL: the bridge RLM DelegateRLM forwards to (stub when unset).
+    rlm: RlmBackend,
+    /// IngestDocument chunking (embedding is MemoryManager's, PAGI_EMBEDDER).
+    ingestor: Ingestor,
+    /// Startup dependency matrix (PAGI_REQUIRED_DEPS), rechecked in the background; reported by GetHealth.
+    dependencies: Arc<Dependencies>,
+    /// AccessMemory/SemanticSearch audit log (PAGI_MEMORY_AUDIT_LOG); None when off.
+    memory_audit: Option<MemoryAudit>,
+    /// Periodic memory maintenance (L2 snapshot, retention, TTL sweep, consolidation); reported by GetHealth.
+    scheduler: Arc<Scheduler>,
+}
+
+impl Orchestrator {
+    /// An orchestrator over `memory`, `watchdog` and `safety_governor` with defaults for the rest: canned mock
+    /// observations, in-process session journal and lineage, the stub RLM, default chunking, no memory audit,
+    /// an empty dependency matrix and no scheduled maintenance. Nothing runs in the background.
+    pub fn new(memory: Arc<MemoryManager>, watchdog: Arc<Watchdog>, safety_governor: SafetyGovernor) -> Self {
+        Self {
+            memory,
+            watchdog,
+            safety_governor,
+            inflight: InFlightRegistry::default(),
+            mock_fixtures: MockFixtures::default(),
+            sessions: SessionJournal::default(),
+            lineage: LineageStore::default(),
+            rlm: RlmBackend::default(),
+            ingestor: Ingestor::default(),
+            dependencies: Arc::default(),
+            memory_audit: None,
+            scheduler: Arc::default(),
+        }
+    }
+
+    /// Everything configured from the environment (.env.example), as the binary runs it: durable store
+    /// (PAGI_STORE), L4 backend, Watchdog over [`default_paths`], the PAGI_REQUIRED_DEPS check (fails startup when
+    /// a required dependency is down), then the background tasks: dependency monitor, L4 health probe, memory
+    /// maintenance scheduler, skills Git-Watcher and SLO evaluator. Call inside a Tokio runtime, once per process
+    /// (the store and metrics are process-wide).
+    pub async fn from_env() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(store) = store::open_from_env().await? {
+            store::install(store);
+        }
+        let memory = MemoryManager::new_async().await?;
+        let (registry_path, core_dir, bridge_dir) = default_paths();
+        let watchdog = Watchdog::new(registry_path, memory.clone(), core_dir, bridge_dir);
+        let dependencies = Arc::new(
+            Dependencies::from_env(memory.clock().clone())
+                .add("qdrant", {
+                    let memory = Arc::clone(&memory);
+                    move || {
+                        let memory = Arc::clone(&memory);
+                        async move { memory.l4_ready().await }
+                    }
+                })
+                .add("bridge", {
+                    let watchdog = Arc::clone(&watchdog);
+                    move || std::future::ready(watchdog.bridge_ready())
+                })
+                .add("registry", {
+                    let watchdog = Arc::clone(&watchdog);
+                    move || {
+                        let watchdog = Arc::clone(&watchdog);
+                        async move { watchdog.registry_ready().await }
+                    }
+                }),
+        );
+        dependencies.start().await?;
+        dependencies.spawn_monitor();
+        memory.restore_l2();
+        memory.spawn_health_probe();
+        let scheduler = Scheduler::from_env(memory.clock().clone());
+        let scheduler = Arc::new(consolidation::schedule(&memory, memory.schedule(scheduler)));
+        scheduler.spawn();
+        let watchdog_clone = Arc::clone(&watchdog);
+        tokio::spawn(async move {
+            watchdog_clone.watch_and_commit().await;
+        });
+        let metrics = metrics::global();
+        slo::spawn_evaluator(Arc::clone(&metrics));
+        let sessions = SessionJournal::new(memory.clock().clone());
+        Ok(Self {
+            memory,
+            watchdog,
+            safety_governor: SafetyGovernor::new(),
+            inflight: InFlightRegistry::with_metrics(metrics),
+            mock_fixtures: MockFixtures::from_env(),
+            sessions,
+            lineage: LineageStore::from_env(),
+            rlm: RlmBackend::from_env(),
+            ingestor: Ingestor::from_env(),
+            memory_audit: MemoryAudit::from_env(),
+            dependencies,
+            scheduler,
+        })
+    }
+
+    pub fn memory(&self) -> &Arc<MemoryManager> {
+        &self.memory
+    }
+
+    pub fn watchdog(&self) -> &Arc<Watchdog> {
+        &self.watchdog
+    }
+
+    pub fn safety_governor(&self) -> &SafetyGovernor {
+        &self.safety_governor
+    }
+
+    /// Serve the Pagi API on `addr` until the server fails, with the gRPC message limits
+    /// (PAGI_GRPC_MAX_REQUEST_MB / PAGI_GRPC_MAX_RESPONSE_MB) and authentication (PAGI_AUTH_*, OIDC) applied.
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), tonic::transport::Error> {
+        let (max_request, max_response) = grpc_message_limits();
+        let oidc = OidcProvider::from_env();
+        if let Some(provider) = &oidc {
+            provider.spawn_refresh();
+        }
+        tonic::transport::Server::builder()
+            .add_service(InterceptedService::new(
+                PagiServer::new(self)
+                    .max_decoding_message_size(max_request)
+                    .max_encoding_message_size(max_response),
+                Authenticator::new(oidc),
+            ))
+            .serve(addr)
+            .await
+    }
+
+    /// ExecuteAction dispatch (depth guard, mock vs. real); shared with RunPipeline steps.
+    /// Every outcome is journaled under the request's reasoning_id (in the caller's `namespace`) for EndSession.
+    async fn dispatch_action(&self, req: ActionRequest, namespace: &str) -> Result<ActionResponse, Status> {
+        let (reasoning_id, skill) = (req.reasoning_id.clone(), req.skill_name.clone());
+        let result = self.route_action(req).await;
+        self.sessions.record_action(&reasoning_id, namespace, &skill, &result);
+        self.lineage.record_action(&reasoning_id, &skill, &result);
+        result
+    }
+
+    async fn route_action(&self, req: ActionRequest) -> Result<ActionResponse, Status> {
+        // Mirror recursion circuit-breaker semantics used by guard_rlm without introducing new schema drift.
+        self.safety_governor.check_depth(req.depth)?;
+        let _fan_out = self.safety_governor.acquire_fan_out(&req.reasoning_id, req.depth)?;
+        let req = self.safety_governor.guard_action(req)?;
+
+        // PAGI_MOCK_MODE precedence: mock path when request asks for mock or env forces mock.
+        let env_mock = std::env::var("PAGI_MOCK_MODE")
+            .map(|v| v.trim().eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        if req.mock_mode || env_mock {
+            return Ok(self.mock_response(&req).await);
+        }
+
+        // Real dispatch only when explicitly enabled (allow-list, timeout, no shell).
+        let allow_real = std::env::var("PAGI_ALLOW_REAL_DISPATCH")
+            .map(|v| v.trim().eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        if allow_real {
+            let reasoning_id = req.reasoning_id.clone();
+            return self
+                .inflight
+                .run("ExecuteAction", &reasoning_id, self.watchdog.execute_action_real(req))
+                .await;
+        }
+
+        // PAGI_STRICT_DISPATCH: a non-mock request must not be answered with a fake success.
+        let strict = std::env::var("PAGI_STRICT_DISPATCH")
+            .map(|v| v.trim().eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        if strict {
+            return Err(Status::failed_precondition(format!(
+                "real dispatch of {} requested but PAGI_ALLOW_REAL_DISPATCH is off; enable it on the orchestrator \
+                 or send mock_mode=true (PAGI_STRICT_DISPATCH=false restores the mock fallback)",
+                req.skill_name
+            )));
+        }
+
+        // PAGI_ALLOW_REAL_DISPATCH != true → return mock observation (do not expose unimplemented).
+        Ok(self.mock_response(&req).await)
+    }
+
+    /// AccessMemory layer 6: the lineage of reasoning_id `key`, one "<kind> <name> ok|failed[: detail]" line
+    /// per event, oldest first.
+    fn lineage_entry(&self, key: &str) -> (String, bool) {
+        let req = TraceQueryRequest {
+            reasoning_id: key.to_string(),
+            ..Default::default()
+        };
+        let Ok(trace) = self.lineage.query(&req) else {
+            return (String::new(), false);
+        };
+        let lines: Vec<String> = trace
+            .events
+            .iter()
+            .map(|e| {
+                let outcome = if e.success { "ok" } else { "failed" };
+                if e.detail.is_empty() {
+                    format!("{} {} {}", e.kind, e.name, outcome)
+                } else {
+                    format!("{} {} {}: {}", e.kind, e.name, outcome, e.detail)
+                }
+            })
+            .collect();
+        (lines.join("\n"), true)
+    }
+
+    /// AccessMemory on a namespace-scoped request; layer 6 reads the lineage.
+    fn access_layer(&self, req: &MemoryRequest) -> Result<MemoryResponse, Status> {
+        let value = if req.value.is_empty() {
+            None
+        } else {
+            Some(req.value.as_str())
+        };
+        let key = memory_manager::scoped_key(req.layer, &req.namespace, &req.key)?;
+        let (data, success) = match (req.layer, value) {
+            (6, None) => self.lineage_entry(&key),
+            (6, Some(_)) => return Err(Status::invalid_argument("layer 6 (lineage) is recorded by the orchestrator")),
+            (layer, value) => self.memory.access(layer, &key, value)?,
+        };
+        if value.is_some() {
+            self.sessions.record_memory_write(&req.reasoning_id, &req.namespace, req.layer, &key);
+        }
+        Ok(MemoryResponse {
+            data,
+            success,
+            layers: self.memory.layer_capabilities(),
+        })
+    }
+
+    async fn mock_response(&self, req: &ActionRequest) -> ActionResponse {
+        ActionResponse {
+            dispatch_mode: "mock".to_string(),
+            ..self.mock_fixtures.respond(&req.skill_name, &req.params, &req.reasoning_id).await
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Pagi for Orchestrator {
+    async fn access_memory(
+        &self,
+        request: Request<MemoryRequest>,
+    ) -> Result<Response<MemoryResponse>, Status> {
+        validate(request.get_ref())?;
+        let started = Instant::now();
+        let caller = auth::identity(&request).cloned();
+        // Lineage is keyed by reasoning_id alone, so layer 6 cannot be confined to a tenant.
+        let lineage_scope = match request.get_ref().layer {
+            6 => auth::require_operator_scope(&request, "AccessMemory layer 6"),
+            _ => Ok(()),
+        };
+        let mut req = request.into_inner();
+        let result = lineage_scope
+            .and_then(|_| auth::scope_namespace(caller.as_ref(), &mut req.namespace))
+            .and_then(|_| self.access_layer(&req));
+        if let Some(audit) = &self.memory_audit {
+            let mut record = audit.memory_access(caller.as_ref(), &req);
+            if req.value.is_empty() {
+                record.found = result.as_ref().ok().map(|r| r.success);
+            }
+            audit.append(record, result.as_ref().err(), started.elapsed());
+        }
+        result.map(Response::new)
+    }
+
+    async fn access_memory_at(
+        &self,
+        request: Request<MemoryAtRequest>,
+    ) -> Result<Response<MemoryAtResponse>, Status> {
+        validate(request.get_ref())?;
+        let req = auth::scoped(request, |r| &mut r.namespace)?;
+        self.memory.access_at(&req).map(Response::new)
+    }
+
+    async fn scan_memory(
+        &self,
+        request: Request<MemoryScanRequest>,
+    ) -> Result<Response<MemoryScanResponse>, Status> {
+        validate(request.get_ref())?;
+        let req = auth::scoped(request, |r| &mut r.namespace)?;
+        self.memory.scan_memory(&req).map(Response::new)
+    }
+
+    async fn delete_memory(
+        &self,
+        request: Request<MemoryDeleteRequest>,
+    ) -> Result<Response<MemoryDeleteResponse>, Status> {
+        validate(request.get_ref())?;
+        let req = auth::scoped(request, |r| &mut r.namespace)?;
+        let deleted = self.memory.delete_memory(&req)?;
+        Ok(Response::new(MemoryDeleteResponse { deleted }))
+    }
+
+    async fn delegate_rlm(
+        &self,
+        request: Request<RlmRequest>,
+    ) -> Result<Response<RlmResponse>, Status> {
+        validate(request.get_ref())?;
+        let mut request = request;
+        let reasoning_id = ReasoningId::resolve(&mut request.get_mut().reasoning_id, self.memory.clock())?;
+        self.inflight
+            .run("DelegateRLM", reasoning_id.as_str(), async {
+                let guarded_req = self.safety_governor.guard_rlm(request).await?;
+                let req = guarded_req.into_inner();
+                let _fan_out = self.safety_governor.acquire_fan_out(&req.reasoning_id, req.depth)?;
+                let resp = self.rlm.step(&req, self.safety_governor.max_depth).await?;
+                self.lineage.record_rlm(&req.reasoning_id, req.depth, &resp);
+                Ok(resp)
+            })
+            .await
+            .map(|resp| reasoning_id.tag(Response::new(resp)))
+    }
+
+    async fn execute_action(
+        &self,
+        request: Request<ActionRequest>,
+    ) -> Result<Response<ActionResponse>, Status> {
+        validate(request.get_ref())?;
+        let namespace = auth::caller_namespace(&request);
+        let mut req = request.into_inner();
+        let reasoning_id = ReasoningId::resolve(&mut req.reasoning_id, self.memory.clock())?;
+        self.dispatch_action(req, &namespace).await.map(|resp| reasoning_id.tag(Response::new(resp)))
+    }
+
+    async fn replay_action(
+        &self,
+        request: Request<ReplayActionRequest>,
+    ) -> Result<Response<ReplayActionResponse>, Status> {
+        validate(request.get_ref())?;
+        auth::require_role(&request, "ReplayAction", &[auth::ADMIN])?;
+        self.inflight
+            .run("ReplayAction", "", self.watchdog.replay_action(request.into_inner()))
+            .await
+            .map(Response::new)
+    }
+
+    async fn run_pipeline(
+        &self,
+        request: Request<PipelineRequest>,
+    ) -> Result<Response<PipelineResponse>, Status> {
+        validate(request.get_ref())?;
+        let namespace = auth::caller_namespace(&request);
+        let mut req = request.into_inner();
+        let reasoning_id = ReasoningId::resolve(&mut req.reasoning_id, self.memory.clock())?;
+        self.inflight
+            .run(
+                "RunPipeline",
+                reasoning_id.as_str(),
+                pipeline::run(req, self.memory.clock(), |step| self.dispatch_action(step, &namespace)),
+            )
+            .await
+            .map(|resp| reasoning_id.tag(Response::new(resp)))
+    }
+
+    async fn end_session(
+        &self,
+        request: Request<EndSessionRequest>,
+    ) -> Result<Response<EndSessionResponse>, Status> {
+        validate(request.get_ref())?;
+        let EndSessionRequest {
+            reasoning_id,
+            timezone,
+            namespace,
+        } = auth::scoped(request, |r| &mut r.namespace)?;
+        if reasoning_id.is_empty() {
+            return Err(Status::invalid_argument("reasoning_id is required"));
+        }
+        let tz = clock::parse_timezone(&timezone).map_err(Status::invalid_argument)?;
+        let key = memory_manager::scoped_key(2, &namespace, &format!("session_summary:{}", reasoning_id))?;
+        let env_key = memory_manager::scoped_key(2, &namespace, &format!("session_env:{}", reasoning_id))?;
+        let kb = kb_registry::namespaced(&session::summary_kb(), &namespace).map_err(Status::invalid_argument)?;
+        // Sessions of other namespaces are reported as unknown rather than denied, so their ids do not leak.
+        let log = self
+            .sessions
+            .finish(&reasoning_id, &namespace)
+            .ok_or_else(|| Status::not_found(format!("no recorded activity for session {}", reasoning_id)))?;
+        let ended_at = self.sessions.clock().now();
+        let summary = session::summarize(&reasoning_id, &log, ended_at, tz);
+        self.memory.access(2, &key, Some(&summary))?;
+        let mut stored_in = vec![format!("L2:{}", key)];
+        let env_snapshots = session::env_snapshots_json(&log);
+        if let Some(snapshots) = &env_snapshots {
+            self.memory.access(2, &env_key, Some(snapshots))?;
+            stored_in.push(format!("L2:{}", env_key));
+        }
+        if self.memory.l4_enabled() {
+            let snapshots = env_snapshots.as_deref();
+            match session::store_summary(&self.memory, &kb, &reasoning_id, &summary, snapshots).await {
+                Ok(()) => stored_in.push(format!("L4:{}", kb)),
+                // Best-effort: the digest is still returned and kept in L2.
+                Err(e) => eprintln!("[Session] storing summary of {} in {}: {}", reasoning_id, kb, e),
+            }
+        }
+        Ok(Response::new(EndSessionResponse {
+            action_count: log.action_count() as u32,
+            failed_count: log.failed_count() as u32,
+            memory_keys: log.memory_writes.into_iter().collect(),
+            summary,
+            stored_in,
+            started_at: clock::format_in(log.started_at, tz),
+            ended_at: clock::format_in(ended_at, tz),
+        }))
+    }
+
+    async fn trace_query(
+        &self,
+        request: Request<TraceQueryRequest>,
+    ) -> Result<Response<TraceQueryResponse>, Status> {
+        validate(request.get_ref())?;
+        auth::require_operator_scope(&request, "TraceQuery")?;
+        self.lineage.query(&request.into_inner()).map(Response::new)
+    }
+
+    async fn self_heal(
+        &self,
+        request: Request<HealRequest>,
+    ) -> Result<Response<HealResponse>, Status> {
+        let req = request.into_inner();
+        let (proposed_patch, auto_apply) = self.watchdog.propose_heal(&req.error_trace);
+        Ok(Response::new(HealResponse {
+            proposed_patch,
+            auto_apply,
+        }))
+    }
+
+    async fn semantic_search(
+        &self,
+        request: Request<SearchRequest>,
+    ) -> Result<Response<SearchResponse>, Status> {
+        validate(request.get_ref())?;
+        let started = Instant::now();
+        let caller = auth::identity(&request).cloned();
+        let mut req = request.into_inner();
+        let scoped = auth::scope_namespace(caller.as_ref(), &mut req.namespace);
+        let record = self.memory_audit.as_ref().map(|audit| audit.search(caller.as_ref(), &req));
+        let result = match scoped {
+            Ok(()) => self.inflight.run("SemanticSearch", "", self.memory.semantic_search(req)).await,
+            Err(e) => Err(e),
+        };
+        if let (Some(audit), Some(mut record)) = (&self.memory_audit, record) {
+            record.hits = result.as_ref().ok().map(|r| r.hits.len() as u32);
+            audit.append(record, result.as_ref().err(), started.elapsed());
+        }
+        result.map(Response::new)
+    }
+
+    async fn record_search_feedback(
+        &self,
+        request: Request<SearchFeedbackRequest>,
+    ) -> Result<Response<SearchFeedbackResponse>, Status> {
+        validate(request.get_ref())?;
+        let req = auth::scoped(request, |r| &mut r.namespace)?;
+        let (reasoning_id, kb_name) = (req.reasoning_id.clone(), req.kb_name.clone());
+        let (useful, unhelpful) = (req.useful_ids.clone(), req.unhelpful_ids.clone());
+        let resp = self
+            .inflight
+            .run("RecordSearchFeedback", &reasoning_id, self.memory.record_feedback(req))
+            .await?;
+        let rated = |ids: Vec<String>| -> Vec<String> {
+            ids.into_iter().filter(|id| !resp.missing_ids.contains(id)).collect()
+        };
+        self.lineage.record_search_feedback(&reasoning_id, &kb_name, &rated(useful), &rated(unhelpful));
+        Ok(Response::new(resp))
+    }
+
+    async fn propose_patch(
+        &self,
+        request: Request<PatchRequest>,
+    ) -> Result<Response<PatchResponse>, Status> {
+        validate(request.get_ref())?;
+        let mut req = request.into_inner();
+        let reasoning_id = ReasoningId::resolve(&mut req.reasoning_id, self.memory.clock())?;
+        let component = req.component.clone();
+        let resp = self
+            .inflight
+            .run("ProposePatch", reasoning_id.as_str(), self.watchdog.propose_patch(req))
+            .await?;
+        self.lineage.record_patch_proposed(reasoning_id.as_str(), &resp.patch_id, &component);
+        Ok(reasoning_id.tag(Response::new(resp)))
+    }
+
+    async fn apply_patch(
+        &self,
+        request: Request<ApplyRequest>,
+    ) -> Result<Response<ApplyResponse>, Status> {
+        validate(request.get_ref())?;
+        auth::require_role(&request, "ApplyPatch", &[auth::APPROVER, auth::ADMIN])?;
+        let req = request.into_inner();
+        let reasoning_id = self.watchdog.patch_reasoning_id(&req.patch_id);
+        let (patch_id, component) = (req.patch_id.clone(), req.component.clone());
+        let result = self
+            .inflight
+            .run("ApplyPatch", &reasoning_id, self.watchdog.apply_patch(req))
+            .await;
+        match &result {
+            Ok(resp) => self.lineage.record_patch_outcome(&reasoning_id, &patch_id, Ok(&resp.commit_hash)),
+            Err(e) if e.code() == tonic::Code::Internal => {
+                self.lineage.record_patch_outcome(&reasoning_id, &patch_id, Err(e.message()));
+                if let Some(revision) = self.watchdog.revised_by(&patch_id) {
+                    self.lineage.record_patch_proposed(&reasoning_id, &revision, &component);
+                }
+            }
+            Err(_) => {}
+        }
+        result.map(Response::new)
+    }
+
+    async fn rollback_patch(
+        &self,
+        request: Request<RollbackPatchRequest>,
+    ) -> Result<Response<RollbackPatchResponse>, Status> {
+        validate(request.get_ref())?;
+        auth::require_role(&request, "RollbackPatch", &[auth::APPROVER, auth::ADMIN])?;
+        let req = request.into_inner();
+        let reasoning_id = self.watchdog.patch_reasoning_id(&req.patch_id);
+        let patch_id = req.patch_id.clone();
+        let result = self
+            .inflight
+            .run("RollbackPatch", &reasoning_id, self.watchdog.rollback_patch(req))
+            .await;
+        if let Ok(resp) = &result {
+            self.lineage.record_patch_rolled_back(&reasoning_id, &patch_id, &resp.commit_hash);
+        }
+        result.map(Response::new)
+    }
+
+    async fn search_patches(
+        &self,
+        request: Request<SearchPatchesRequest>,
+    ) -> Result<Response<SearchPatchesResponse>, Status> {
+        auth::require_operator_scope(&request, "SearchPatches")?;
+        self.inflight
+            .run("SearchPatches", "", patch_history::search(&self.memory, request.into_inner()))
+            .await
+            .map(Response::new)
+    }
+
+    async fn get_apply_status(
+        &self,
+        request: Request<ApplyStatusRequest>,
+    ) -> Result<Response<ApplyStatusResponse>, Status> {
+        validate(request.get_ref())?;
+        auth::require_operator_scope(&request, "GetApplyStatus")?;
+        self.watchdog
+            .apply_status(&request.into_inner().patch_id)
+            .await
+            .map(Response::new)
+    }
+
+    async fn get_heal_report(
+        &self,
+        request: Request<HealReportRequest>,
+    ) -> Result<Response<HealReport>, Status> {
+        validate(request.get_ref())?;
+        auth::require_operator_scope(&request, "GetHealReport")?;
+        self.watchdog.heal_report(request.get_ref()).map(Response::new)
+    }
+
+    async fn list_pending_patches(
+        &self,
+        request: Request<ListPendingPatchesRequest>,
+    ) -> Result<Response<ListPendingPatchesResponse>, Status> {
+        validate(request.get_ref())?;
+        auth::require_role(&request, "ListPendingPatches", &[auth::APPROVER, auth::ADMIN])?;
+        Ok(Response::new(self.watchdog.list_pending(request.get_ref())))
+    }
+
+    async fn get_patch(&self, request: Request<GetPatchRequest>) -> Result<Response<PatchDetail>, Status> {
+        validate(request.get_ref())?;
+        auth::require_role(&request, "GetPatch", &[auth::APPROVER, auth::ADMIN])?;
+        self.watchdog.get_patch(&request.get_ref().patch_id).map(Response::new)
+    }
+
+    type HitlChannelStream = hitl::ReviewerStream;
+
+    async fn hitl_channel(
+        &self,
+        request: Request<Streaming<HitlClientMessage>>,
+    ) -> Result<Response<Self::HitlChannelStream>, Status> {
+        // Decisions gate patch applies and high-risk dispatches: approvers only, recorded under their subject.
+        auth::require_role(&request, "HitlChannel", &[auth::APPROVER, auth::ADMIN])?;
+        let subject = auth::identity(&request).map(|id| id.subject.clone());
+        Ok(Response::new(self.watchdog.hitl().connect(subject, request.into_inner())))
+    }
+
+    async fn upsert_vectors(
+        &self,
+        request: Request<UpsertRequest>,
+    ) -> Result<Response<UpsertResponse>, Status> {
+        validate(request.get_ref())?;
+        let req = auth::scoped(request, |r| &mut r.namespace)?;
+        let (reasoning_id, kb_name) = (req.reasoning_id.clone(), req.kb_name.clone());
+        let point_ids: Vec<String> = req.points.iter().map(|p| p.id.clone()).collect();
+        let resp = self
+            .inflight
+            .run("UpsertVectors", &reasoning_id, self.memory.upsert_vectors(req))
+            .await?;
+        if resp.success {
+            self.lineage.record_kb_write(&reasoning_id, &kb_name, point_ids);
+        }
+        Ok(Response::new(resp))
+    }
+
+    async fn write_memory(
+        &self,
+        request: Request<MemoryWriteRequest>,
+    ) -> Result<Response<MemoryWriteResponse>, Status> {
+        validate(request.get_ref())?;
+        let req = auth::scoped(request, |r| &mut r.namespace)?;
+        let (reasoning_id, namespace) = (req.reasoning_id.clone(), req.namespace.clone());
+        let keys = req
+            .l2
+            .iter()
+            .map(|w| memory_manager::scoped_key(2, &req.namespace, &w.key))
+            .collect::<Result<Vec<_>, _>>()?;
+        let kb_write = req
+            .l4
+            .as_ref()
+            .map(|u| (u.kb_name.clone(), u.points.iter().map(|p| p.id.clone()).collect::<Vec<_>>()));
+        let resp = self
+            .inflight
+            .run("WriteMemory", &reasoning_id, self.memory.write_memory(req))
+            .await?;
+        for key in &keys {
+            self.sessions.record_memory_write(&reasoning_id, &namespace, 2, key);
+        }
+        if let Some((kb_name, point_ids)) = kb_write {
+            self.lineage.record_kb_write(&reasoning_id, &kb_name, point_ids);
+        }
+        Ok(Response::new(resp))
+    }
+
+    async fn upsert_vectors_stream(
+        &self,
+        request: Request<Streaming<UpsertRequest>>,
+    ) -> Result<Response<UpsertStreamResponse>, Status> {
+        auth::require_operator_scope(&request, "UpsertVectorsStream")?;
+        let stream = request.into_inner();
+        self.inflight
+            .run(
+                "UpsertVectorsStream",
+                "",
+                self.memory.upsert_stream(stream, MemoryManager::upsert_batch_size()),
+            )
+            .await
+            .map(Response::new)
+    }
+
+    async fn ingest_document(
+        &self,
+        request: Request<IngestDocumentRequest>,
+    ) -> Result<Response<IngestDocumentResponse>, Status> {
+        validate(request.get_ref())?;
+        let req = auth::scoped(request, |r| &mut r.namespace)?;
+        let (reasoning_id, kb_name) = (req.reasoning_id.clone(), req.kb_name.clone());
+        let resp = self
+            .inflight
+            .run("IngestDocument", &reasoning_id, self.ingestor.ingest(&self.memory, req))
+            .await?;
+        self.lineage.record_kb_write(&reasoning_id, &kb_name, resp.point_ids.clone());
+        Ok(Response::new(resp))
+    }
+
+    async fn delete_vectors(
+        &self,
+        request: Request<DeleteVectorsRequest>,
+    ) -> Result<Response<DeleteVectorsResponse>, Status> {
+        auth::require_role(&request, "DeleteVectors", &[auth::ADMIN])?;
+        self.inflight
+            .run("DeleteVectors", "", self.memory.delete_vectors(request.into_inner()))
+            .await
+            .map(Response::new)
+    }
+
+    async fn simulate_error(
+        &self,
+        request: Request<Empty>,
+    ) -> Result<Response<Empty>, Status> {
+        // Simulations propose and may apply a patch: the same roles as ApplyPatch.
+        auth::require_role(&request, "SimulateError", &[auth::APPROVER, auth::ADMIN])?;
+        // Legacy scenario: rust_core, poll approve flag; PAGI_FORCE_TEST_FAIL passes HITL to hit the failed-test path.
+        let force_fail = std::env::var("PAGI_FORCE_TEST_FAIL")
+            .ok()
+            .is_some_and(|v| v.to_lowercase() == "true" || v == "1");
+        let scenario = SimulationRequest {
+            component: "rust_core".to_string(),
+            error_trace: "Simulated Rust error for verification".to_string(),
+            approval: if force_fail { "approve" } else { "flag" }.to_string(),
+            ..Default::default()
+        };
+        // Stage outcomes (denial / forced failure) are expected; the legacy RPC only reports completion.
+        self.inflight
+            .run("SimulateError", "", simulation::run_simulation(&self.watchdog, scenario))
+            .await?;
+        Ok(Response::new(Empty {}))
+    }
+
+    async fn run_simulation(
+        &self,
+        request: Request<SimulationRequest>,
+    ) -> Result<Response<SimulationResponse>, Status> {
+        auth::require_role(&request, "RunSimulation", &[auth::APPROVER, auth::ADMIN])?;
+        self.inflight
+            .run("RunSimulation", "", simulation::run_simulation(&self.watchdog, request.into_inner()))
+            .await
+            .map(Response::new)
+    }
+
+    async fn get_health(&self, _request: Request<Empty>) -> Result<Response<HealthResponse>, Status> {
+        let mut health = self.memory.health();
+        health.ok &= self.dependencies.all_up();
+        health.dependencies = self.dependencies.report();
+        health.scheduled_tasks = self.scheduler.report();
+        Ok(Response::new(health))
+    }
+
+    async fn get_memory_stats(&self, request: Request<Empty>) -> Result<Response<MemoryStatsResponse>, Status> {
+        auth::require_operator_scope(&request, "GetMemoryStats")?;
+        Ok(Response::new(self.memory.stats().await))
+    }
+
+    async fn get_hot_memory(
+        &self,
+        request: Request<HotMemoryRequest>,
+    ) -> Result<Response<HotMemoryReport>, Status> {
+        auth::require_operator_scope(&request, "GetHotMemory")?;
+        Ok(Response::new(self.memory.hot_report(request.into_inner().limit as usize)))
+    }
+
+    async fn recall_archive(
+        &self,
+        request: Request<RecallArchiveRequest>,
+    ) -> Result<Response<RecallArchiveResponse>, Status> {
+        auth::require_operator_scope(&request, "RecallArchive")?;
+        self.inflight
+            .run("RecallArchive", "", self.memory.recall_archive(request.into_inner()))
+            .await
+            .map(Response::new)
+    }
+
+    async fn snapshot_kb(&self, request: Request<SnapshotKbRequest>) -> Result<Response<SnapshotKbResponse>, Status> {
+        validate(request.get_ref())?;
+        self.inflight
+            .run("SnapshotKb", "", self.memory.snapshot_kb(auth::scoped(request, |r| &mut r.namespace)?))
+            .await
+            .map(Response::new)
+    }
+
+    async fn restore_kb(&self, request: Request<RestoreKbRequest>) -> Result<Response<RestoreKbResponse>, Status> {
+        validate(request.get_ref())?;
+        auth::require_role(&request, "RestoreKb", &[auth::ADMIN])?;
+        self.inflight
+            .run("RestoreKb", "", self.memory.restore_kb(request.into_inner()))
+            .await
+            .map(Response::new)
+    }
+
+    type ScrollKbStream = memory_manager::KbPageStream;
+
+    async fn scroll_kb(&self, request: Request<ScrollKbRequest>) -> Result<Response<Self::ScrollKbStream>, Status> {
+        validate(request.get_ref())?;
+        self.memory.scroll_kb(auth::scoped(request, |r| &mut r.namespace)?).await.map(Response::new)
+    }
+
+    async fn compact_memory(
+        &self,
+        request: Request<CompactMemoryRequest>,
+    ) -> Result<Response<CompactMemoryReport>, Status> {
+        validate(request.get_ref())?;
+        auth::require_role(&request, "CompactMemory", &[auth::ADMIN])?;
+        self.inflight
+            .run("CompactMemory", "", compaction::run(&self.memory, request.into_inner()))
+            .await
+            .map(Response::new)
+    }
+
+    async fn run_search_eval(
+        &self,
+        request: Request<SearchEvalRequest>,
+    ) -> Result<Response<SearchEvalReport>, Status> {
+        auth::require_operator_scope(&request, "RunSearchEval")?;
+        self.inflight
+            .run("RunSearchEval", "", search_eval::run(&self.memory, request.into_inner()))
+            .await
+            .map(Response::new)
+    }
+
+    async fn admin_list_requests(
+        &self,
+        request: Request<Empty>,
+    ) -> Result<Response<InFlightRequests>, Status> {
+        auth::require_role(&request, "AdminListRequests", &[auth::ADMIN])?;
+        Ok(Response::new(self.inflight.list()))
+    }
+
+    async fn abort_request(
+        &self,
+        request: Request<AbortInFlightRequest>,
+    ) -> Result<Response<AbortResponse>, Status> {
+        auth::require_role(&request, "AbortRequest", &[auth::ADMIN])?;
+        Ok(Response::new(self.inflight.abort(request.into_inner().request_id)))
+    }
+
+    async fn capture_profile(
+        &self,
+        request: Request<ProfileRequest>,
+    ) -> Result<Response<ProfileResponse>, Status> {
+        auth::require_role(&request, "CaptureProfile", &[auth::ADMIN])?;
+        self.inflight
+            .run("CaptureProfile", "", profiler::capture(request.into_inner()))
+            .await
+            .map(Response::new)
+    }
+
+    async fn run_smoke_test(
+        &self,
+        request: Request<SmokeTestRequest>,
+    ) -> Result<Response<SmokeTestReport>, Status> {
+        validate(request.get_ref())?;
+        auth::require_role(&request, "RunSmokeTest", &[auth::ADMIN])?;
+        let mut req = request.into_inner();
+        let reasoning_id = ReasoningId::resolve(&mut req.reasoning_id, self.memory.clock())?;
+        self.inflight
+            .run("RunSmokeTest", reasoning_id.as_str(), async {
+                Ok(smoke_test::run(&self.memory, &self.watchdog, req, |action| self.dispatch_action(action, "")).await)
+            })
+            .await
+            .map(|report| reasoning_id.tag(Response::new(report)))
+    }
+}
+
+/// Skill registry, core and bridge dirs: PAGI_REGISTRY_PATH, PAGI_CORE_DIR and PAGI_BRIDGE_DIR, else
+/// ../pagi-skills, the working directory and ../pagi-intelligence-bridge.
+pub fn default_paths() -> (PathBuf, PathBuf, PathBuf) {
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let registry = std::env::var("PAGI_REGISTRY_PATH")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| cwd.join("../pagi-skills"));
+    let core_dir = std::env::var("PAGI_CORE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| cwd.clone());
+    let bridge_dir = std::env::var("PAGI_BRIDGE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| cwd.join("../pagi-intelligence-bridge"));
+    (registry, core_dir, bridge_dir)
+}
+
+/// Largest decoded request / encoded response in bytes: PAGI_GRPC_MAX_REQUEST_MB (default 4, tonic's own
+/// default) and PAGI_GRPC_MAX_RESPONSE_MB (default 64). Oversized messages fail with RESOURCE_EXHAUSTED.
+fn grpc_message_limits() -> (usize, usize) {
+    let mb = |name: &str, default: usize| {
+        std::env::var(name)
+            .ok()
+            .and_then(|s| s.trim().parse::<usize>().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or(default)
+            .saturating_mul(1024 * 1024)
+    };
+    (mb("PAGI_GRPC_MAX_REQUEST_MB", 4), mb("PAGI_GRPC_MAX_RESPONSE_MB", 64))
+}
+
+/// Listen address: [::1]:PAGI_GRPC_PORT (default 50051).
+pub fn grpc_addr() -> SocketAddr {
+    let port = std::env::var("PAGI_GRPC_PORT")
+        .unwrap_or_else(|_| "50051".into())
+        .parse::<u16>()
+        .unwrap_or(50051);
+    format!("[::1]:{}", port)
+        .parse()
+        .unwrap_or_else(|_| "[::1]:50051".parse().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::pagi_proto::ActionRequest;
+    use crate::reasoning_id;
+    use std::collections::HashMap;
+    use tonic::Request;
+    use crate::watchdog::tests::lock_test_env;
+
+    fn in_memory_orchestrator() -> Orchestrator {
+        let (registry, core_dir, bridge_dir) = default_paths();
+        let memory = Arc::new(MemoryManager::in_memory(8));
+        let watchdog = Watchdog::new(registry, memory.clone(), core_dir, bridge_dir);
+        Orchestrator::new(memory, watchdog, SafetyGovernor::default())
+    }
+
+    /// `msg` from a tenant-scoped caller holding no roles.
+    fn as_tenant<T>(msg: T, tenant: &str) -> Request<T> {
+        let mut req = Request::new(msg);
+        req.extensions_mut().insert(auth::Identity {
+            subject: format!("{}-user", tenant),
+            roles: Vec::new(),
+            tenant: Some(tenant.to_string()),
+        });
+        req
+    }
+
+    #[tokio::test]
+    async fn test_execute_action_mock() {
+        let _g = lock_test_env().await;
+        std::env::set_var("PAGI_DISABLE_QDRANT", "1");
+        std::env::set_var("PAGI_MOCK_MODE", "true");
+        std::env::set_var("PAGI_ALLOW_REAL_DISPATCH", "false");
+
+        let (registry, core_dir, bridge_dir) = default_paths();
+        let memory = MemoryManager::new_async().await.unwrap();
+        let watchdog = Watchdog::new(registry, memory.clone(), core_dir, bridge_dir);
+        let orch = Orchestrator::new(memory, watchdog, SafetyGovernor::default());
+        let req = Request::new(ActionRequest {
+            skill_name: "peek_file".to_string(),
+            params: HashMap::new(),
+            depth: 0,
+            reasoning_id: "9b2e4c1e-8f0a-4d7b-a3c2-5e6f7a8b9c0d".to_string(),
+            mock_mode: true,
+            allow_list_hash: String::new(),
+            timeout_ms: 0,
+            caller_skills: Vec::new(),
+        });
+        let resp = orch.execute_action(req).await.unwrap();
+        let echoed = resp.metadata().get(reasoning_id::HEADER).unwrap().to_str().unwrap().to_string();
+        assert_eq!(echoed, "9b2e4c1e-8f0a-4d7b-a3c2-5e6f7a8b9c0d");
+        let inner = resp.into_inner();
+        assert!(inner.success);
+        assert!(inner.observation.contains("mock executed"));
+        assert!(inner.observation.contains("peek_file"));
+
+        std::env::remove_var("PAGI_MOCK_MODE");
+        std::env::remove_var("PAGI_ALLOW_REAL_DISPATCH");
+        std::env::remove_var("PAGI_DISABLE_QDRANT");
+    }
+
+    #[tokio::test]
+    async fn test_execute_action_fallback_mock_when_real_disabled() {
+        let _g = lock_test_env().await;
+        std::env::set_var("PAGI_DISABLE_QDRANT", "1");
+        std::env::set_var("PAGI_ALLOW_REAL_DISPATCH", "false");
+        std::env::remove_var("PAGI_MOCK_MODE");
+
+        let (registry, core_dir, bridge_dir) = default_paths();
+        let memory = MemoryManager::new_async().await.unwrap();
+        let watchdog = Watchdog::new(registry, memory.clone(), core_dir, bridge_dir);
+        let orch = Orchestrator::new(memory, watchdog, SafetyGovernor::default());
+        let req = Request::new(ActionRequest {
+            skill_name: "unknown_skill".to_string(),
+            params: HashMap::new(),
+            depth: 0,
+            reasoning_id: String::new(),
+            mock_mode: false,
+            allow_list_hash: String::new(),
+            timeout_ms: 0,
+            caller_skills: Vec::new(),
+        });
+        let resp = orch.execute_action(req).await.unwrap();
+        let header = resp.metadata().get(reasoning_id::HEADER).unwrap().to_str().unwrap();
+        let generated: ReasoningId = header.parse().unwrap();
+        assert!(generated.timestamp_ms().is_some(), "a missing reasoning_id is generated as UUIDv7");
+        let inner = resp.into_inner();
+        assert!(inner.success);
+        assert!(inner.observation.contains("mock executed"));
+        assert!(inner.observation.contains("unknown_skill"));
+        let bad = ActionRequest {
+            skill_name: "unknown_skill".to_string(),
+            reasoning_id: "r1".to_string(),
+            ..Default::default()
+        };
+        let err = orch.execute_action(Request::new(bad)).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        assert_eq!(inner.dispatch_mode, "mock");
+
+        // Strict mode: the same request fails loudly instead of faking success.
+        std::env::set_var("PAGI_STRICT_DISPATCH", "true");
+        let err = orch
+            .execute_action(Request::new(ActionRequest {
+                skill_name: "unknown_skill".to_string(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+        assert!(err.message().contains("PAGI_ALLOW_REAL_DISPATCH"));
+        let mock = orch
+            .execute_action(Request::new(ActionRequest {
+                skill_name: "unknown_skill".to_string(),
+                mock_mode: true,
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(mock.dispatch_mode, "mock", "explicit mock requests are unaffected");
+
+        std::env::remove_var("PAGI_STRICT_DISPATCH");
+        std::env::remove_var("PAGI_ALLOW_REAL_DISPATCH");
+        std::env::remove_var("PAGI_DISABLE_QDRANT");
+    }
+
+    #[tokio::test]
+    async fn test_health_and_search_report_disabled_l4() {
+        let _g = lock_test_env().await;
+        std::env::set_var("PAGI_DISABLE_QDRANT", "1");
+
+        let (registry, core_dir, bridge_dir) = default_paths();
+        let memory = MemoryManager::new_async().await.unwrap();
+        let watchdog = Watchdog::new(registry, memory.clone(), core_dir, bridge_dir);
+        let orch = Orchestrator::new(memory, watchdog, SafetyGovernor::default());
+        let health = orch.get_health(Request::new(Empty {})).await.unwrap().into_inner();
+        assert!(health.ok);
+        assert_eq!(health.l4_state, "disabled");
+        assert_eq!(health.l4_breaker, "closed");
+        assert_eq!(health.l4_probe, "off");
+
+        let search = orch
+            .semantic_search(Request::new(SearchRequest {
+                query: "anything".to_string(),
+                kb_name: "kb_core".to_string(),
+                limit: 5,
+                query_vector: vec![],
+                filter: None,
+                hybrid: false,
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(search.hits.is_empty());
+        assert!(search.degraded);
+        assert_eq!(search.source, "disabled");
+
+        orch.memory.access(2, "goal", Some("ship")).unwrap();
+        orch.memory.access(2, "goal", None).unwrap();
+        orch.memory.access(2, "missing", None).unwrap();
+        let stats = orch.get_memory_stats(Request::new(Empty {})).await.unwrap().into_inner();
+        assert_eq!(stats.l4_state, "disabled");
+        assert!(stats.collections.is_empty());
+        let l2 = stats.layers.iter().find(|l| l.layer == 2).unwrap();
+        assert_eq!((l2.entries, l2.bytes, l2.hits, l2.misses), (1, 8, 1, 1));
+
+        std::env::remove_var("PAGI_DISABLE_QDRANT");
+    }
+
+    #[tokio::test]
+    async fn memory_history_is_confined_to_the_callers_tenant() {
+        let _g = lock_test_env().await;
+        let orch = in_memory_orchestrator();
+        orch.memory.access(2, "plan@acme", Some("acme secret")).unwrap();
+        let at = |namespace: &str| MemoryAtRequest {
+            layer: 2,
+            key: "plan".to_string(),
+            namespace: namespace.to_string(),
+            ..Default::default()
+        };
+
+        let own = orch.access_memory_at(as_tenant(at(""), "acme")).await.unwrap().into_inner();
+        assert_eq!((own.found, own.data.as_str()), (true, "acme secret"));
+        let other = orch.access_memory_at(as_tenant(at(""), "globex")).await.unwrap().into_inner();
+        assert!(!other.found, "globex reads its own plan@globex");
+        let err = orch.access_memory_at(as_tenant(at("acme"), "globex")).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn lineage_reads_are_denied_to_tenant_callers() {
+        let _g = lock_test_env().await;
+        let orch = in_memory_orchestrator();
+        let rid = "9b2e4c1e-8f0a-4d7b-a3c2-5e6f7a8b9c0d";
+        orch.lineage.record_kb_write(rid, "kb_globex", vec!["p1".to_string()]);
+        let lineage = || MemoryRequest {
+            layer: 6,
+            key: rid.to_string(),
+            ..Default::default()
+        };
+
+        let err = orch.access_memory(as_tenant(lineage(), "acme")).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+        let operator = orch.access_memory(Request::new(lineage())).await.unwrap().into_inner();
+        assert!(operator.data.contains("kb_globex"));
+    }
+
+    #[tokio::test]
+    async fn sessions_end_only_in_the_namespace_that_opened_them() {
+        let _g = lock_test_env().await;
+        let orch = in_memory_orchestrator();
+        let rid = "9b2e4c1e-8f0a-4d7b-a3c2-5e6f7a8b9c0d";
+        let action = ActionRequest {
+            skill_name: "peek_file".to_string(),
+            reasoning_id: rid.to_string(),
+            mock_mode: true,
+            ..Default::default()
+        };
+        orch.execute_action(as_tenant(action, "acme")).await.unwrap();
+        let end = || EndSessionRequest {
+            reasoning_id: rid.to_string(),
+            ..Default::default()
+        };
+
+        let err = orch.end_session(as_tenant(end(), "globex")).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+        let resp = orch.end_session(as_tenant(end(), "acme")).await.unwrap().into_inner();
+        let key = format!("session_summary:{}@acme", rid);
+        assert_eq!(resp.stored_in[0], format!("L2:{}", key));
+        assert!(orch.memory.read_l2(&key).is_some());
+        assert!(orch.memory.read_l2(&format!("session_summary:{}", rid)).is_none());
+    }
+
+    #[tokio::test]
+    async fn cross_tenant_views_are_denied_to_tenant_callers() {
+        let _g = lock_test_env().await;
+        let orch = in_memory_orchestrator();
+        fn denied<T>(result: Result<T, Status>) -> bool {
+            result.is_err_and(|e| e.code() == tonic::Code::PermissionDenied)
+        }
+        assert!(denied(orch.get_memory_stats(as_tenant(Empty {}, "acme")).await));
+        assert!(denied(orch.get_hot_memory(as_tenant(HotMemoryRequest::default(), "acme")).await));
+        assert!(denied(orch.recall_archive(as_tenant(RecallArchiveRequest::default(), "acme")).await));
+        assert!(denied(orch.search_patches(as_tenant(SearchPatchesRequest::default(), "acme")).await));
+        assert!(denied(orch.run_search_eval(as_tenant(SearchEvalRequest::default(), "acme")).await));
+        let trace = TraceQueryRequest {
+            commit_hash: "abcdef0".to_string(),
+            ..Default::default()
+        };
+        assert!(denied(orch.trace_query(as_tenant(trace, "acme")).await));
+        let status = ApplyStatusRequest {
+            patch_id: "0191a7d2-8f0a-7d7b-a3c2-5e6f7a8b9c0d".to_string(),
+        };
+        assert!(denied(orch.get_apply_status(as_tenant(status, "acme")).await));
+        assert!(denied(orch.get_heal_report(as_tenant(HealReportRequest::default(), "acme")).await));
+
+        assert!(orch.get_memory_stats(Request::new(Empty {})).await.is_ok(), "operator scope");
+        assert!(orch.get_hot_memory(Request::new(HotMemoryRequest::default())).await.is_ok());
+    }
+}
@@ -0,0 +1,630 @@
+// Patch catalog: pending patches awaiting ApplyPatch plus per-patch HITL approval records and heal lifecycle
+// timelines. Timelines also link each revision (re-proposed after a failed apply test) to the patch it revises,
+// which gives the revision chain reported by GetApplyStatus.
+// Optionally snapshotted into the Evolution Registry (hitl_state/catalog.json) on every change so the
+// Git-Watcher commits HITL state alongside patches; restored from the snapshot on startup.
+// With a durable store attached (PAGI_STORE), every change is also written there per record and the stored
+// records are restored over the snapshot.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::clock::Clock;
+use crate::heal_metrics::{HealTimeline, Stage};
+use crate::impact::PatchImpact;
+use crate::store::{self, Store};
+
+/// Bumped when the snapshot layout changes; unknown versions are not restored.
+const SNAPSHOT_VERSION: u32 = 1;
+const SNAPSHOT_FILE: &str = "catalog.json";
+/// Lifecycle timelines (and approval histories) retained; the oldest are dropped beyond this.
+const MAX_TIMELINES: usize = 1000;
+
+/// Pending patch stored after ProposePatch until ApplyPatch or expiry.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PendingPatch {
+    pub proposed_code: String,
+    pub requires_hitl: bool,
+    pub component: String,
+    pub reasoning_id: String,
+    /// Impact analysis shown to approvers (absent in pre-impact snapshots).
+    #[serde(default)]
+    pub impact: PatchImpact,
+    /// Error fingerprint used by the heal governor for dedup/backoff.
+    #[serde(default)]
+    pub fingerprint: String,
+    /// Error trace the patch was proposed for (revisions keep the first proposal's trace).
+    #[serde(default)]
+    pub error_trace: String,
+    /// Patch this one revises after its apply test failed (empty for first proposals).
+    #[serde(default)]
+    pub revision_of: String,
+    /// Position in the revision chain: 0 for first proposals.
+    #[serde(default)]
+    pub revision: u32,
+    /// Unified diff against the component repo (patch_diff); empty when the trace named no file in it.
+    #[serde(default)]
+    pub diff: String,
+}
+
+/// Outcome of one HITL decision point for a patch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalOutcome {
+    /// Approved via request flag or approve-flag file.
+    Approved,
+    /// ApplyPatch attempted without approval.
+    Denied,
+    /// Approval wait elapsed; `fallback` names the policy action taken ("deny" or "reject").
+    TimedOut { waited_secs: u64, fallback: String },
+}
+
+/// A patch ApplyPatch applied, kept for RollbackPatch.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AppliedPatch {
+    pub patch: PendingPatch,
+    /// Registry commit of the apply; empty without auto-commit.
+    pub commit_hash: String,
+    /// Component repo commit of the patched files; empty when none was made.
+    #[serde(default)]
+    pub target_commit: String,
+    pub applied_ms: i64,
+    /// Set once rolled back.
+    #[serde(default)]
+    pub rolled_back_ms: Option<i64>,
+    /// Registry commit reverting the apply; empty without auto-commit.
+    #[serde(default)]
+    pub rollback_commit: String,
+    /// HITL approval history, moved here from the catalog's approvals on apply.
+    #[serde(default)]
+    pub approvals: Vec<ApprovalRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalRecord {
+    pub outcome: ApprovalOutcome,
+    pub detail: String,
+    /// Unix seconds.
+    pub at: i64,
+}
+
+/// On-disk snapshot; BTreeMaps keep the file diff-stable between commits.
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    version: u32,
+    /// Unix seconds.
+    updated_at: i64,
+    pending: BTreeMap<String, PendingPatch>,
+    approvals: BTreeMap<String, Vec<ApprovalRecord>>,
+    /// Absent in pre-lifecycle snapshots.
+    #[serde(default)]
+    lifecycle: BTreeMap<String, HealTimeline>,
+    /// Absent in pre-rollback snapshots.
+    #[serde(default)]
+    applied: BTreeMap<String, AppliedPatch>,
+}
+
+pub struct PatchCatalog {
+    /// patch_id -> PendingPatch
+    pending: DashMap<String, PendingPatch>,
+    /// patch_id -> approval history, kept after the patch leaves `pending` until it is applied (the history moves
+    /// to `applied`), discarded, or crowded out by the newest MAX_TIMELINES; rejections keep their final record.
+    approvals: DashMap<String, Vec<ApprovalRecord>>,
+    /// patch_id -> heal lifecycle timestamps (kept after the patch leaves `pending`).
+    lifecycle: DashMap<String, HealTimeline>,
+    /// patch_id -> applied patch, until the newest MAX_TIMELINES crowd it out.
+    applied: DashMap<String, AppliedPatch>,
+    /// Snapshot directory (registry hitl_state/); None disables backup.
+    backup_dir: Option<PathBuf>,
+    /// Serializes snapshot writes.
+    backup_lock: Mutex<()>,
+    /// Durable per-record copy (PAGI_STORE); None keeps the snapshot as the only persistence.
+    store: Option<Arc<Store>>,
+    /// Lifecycle, approval and snapshot times.
+    clock: Clock,
+}
+
+impl PatchCatalog {
+    pub fn new() -> Self {
+        Self {
+            pending: DashMap::new(),
+            approvals: DashMap::new(),
+            lifecycle: DashMap::new(),
+            applied: DashMap::new(),
+            backup_dir: None,
+            backup_lock: Mutex::new(()),
+            store: None,
+            clock: Clock::system(),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Restore the store's records (they win over the snapshot) and write every later change through to it.
+    pub fn with_store(mut self, store: Arc<Store>) -> Self {
+        let pending = store.take::<PendingPatch>(store::PATCH_PENDING);
+        let approvals = store.take::<Vec<ApprovalRecord>>(store::PATCH_APPROVALS);
+        let lifecycle = store.take::<HealTimeline>(store::PATCH_LIFECYCLE);
+        let applied = store.take::<AppliedPatch>(store::PATCH_APPLIED);
+        eprintln!(
+            "[PatchCatalog] restored {} pending patch(es) from the {} store",
+            pending.len(),
+            store.backend()
+        );
+        self.pending.extend(pending);
+        self.approvals.extend(approvals);
+        self.lifecycle.extend(lifecycle);
+        self.applied.extend(applied);
+        self.store = Some(store);
+        self
+    }
+
+    /// Catalog backed up to `dir`; restores any existing snapshot there.
+    pub fn with_backup(dir: PathBuf) -> Self {
+        let mut catalog = Self {
+            backup_dir: Some(dir),
+            ..Self::new()
+        };
+        catalog.restore();
+        catalog
+    }
+
+    fn snapshot_path(dir: &Path) -> PathBuf {
+        dir.join(SNAPSHOT_FILE)
+    }
+
+    fn restore(&mut self) {
+        let Some(dir) = &self.backup_dir else {
+            return;
+        };
+        let path = Self::snapshot_path(dir);
+        let Ok(raw) = std::fs::read_to_string(&path) else {
+            return;
+        };
+        match serde_json::from_str::<Snapshot>(&raw) {
+            Ok(snap) if snap.version == SNAPSHOT_VERSION => {
+                eprintln!(
+                    "[PatchCatalog] restored {} pending patch(es) from {}",
+                    snap.pending.len(),
+                    path.display()
+                );
+                self.pending.extend(snap.pending);
+                self.approvals.extend(snap.approvals);
+                self.lifecycle.extend(snap.lifecycle);
+                self.applied.extend(snap.applied);
+            }
+            Ok(snap) => eprintln!(
+                "[PatchCatalog] skipping {} (snapshot version {} != {})",
+                path.display(),
+                snap.version,
+                SNAPSHOT_VERSION
+            ),
+            Err(e) => eprintln!("[PatchCatalog] unreadable snapshot {}: {}", path.display(), e),
+        }
+    }
+
+    /// Write the current state (temp file + rename so the watcher never commits a partial file).
+    fn backup(&self) {
+        let Some(dir) = &self.backup_dir else {
+            return;
+        };
+        let _guard = self.backup_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let snap = Snapshot {
+            version: SNAPSHOT_VERSION,
+            updated_at: self.clock.now_secs(),
+            pending: self
+                .pending
+                .iter()
+                .map(|e| (e.key().clone(), e.value().clone()))
+                .collect(),
+            approvals: self
+                .approvals
+                .iter()
+                .map(|e| (e.key().clone(), e.value().clone()))
+                .collect(),
+            lifecycle: self
+                .lifecycle
+                .iter()
+                .map(|e| (e.key().clone(), e.value().clone()))
+                .collect(),
+            applied: self
+                .applied
+                .iter()
+                .map(|e| (e.key().clone(), e.value().clone()))
+                .collect(),
+        };
+        let result = serde_json::to_string_pretty(&snap)
+            .map_err(|e| e.to_string())
+            .and_then(|json| {
+                std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+                let tmp = dir.join(format!("{}.tmp", SNAPSHOT_FILE));
+                std::fs::write(&tmp, json + "\n").map_err(|e| e.to_string())?;
+                std::fs::rename(&tmp, Self::snapshot_path(dir)).map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            eprintln!("[PatchCatalog] backup to {}: {}", dir.display(), e);
+        }
+    }
+
+    /// Insert a new patch and start its lifecycle timeline (detected at `detected_ms`, proposed now).
+    pub fn insert(&self, patch_id: String, patch: PendingPatch, detected_ms: i64) {
+        if self.lifecycle.len() >= MAX_TIMELINES {
+            let oldest = self
+                .lifecycle
+                .iter()
+                .map(|e| (e.value().detected_ms, e.key().clone()))
+                .min();
+            if let Some((_, id)) = oldest {
+                self.lifecycle.remove(&id);
+                if let Some(store) = &self.store {
+                    store.delete(store::PATCH_LIFECYCLE, &id);
+                }
+            }
+        }
+        let timeline = HealTimeline {
+            component: patch.component.clone(),
+            fingerprint: patch.fingerprint.clone(),
+            detected_ms,
+            proposed_ms: self.clock.now_ms(),
+            revision_of: patch.revision_of.clone(),
+            ..Default::default()
+        };
+        if let Some(store) = &self.store {
+            store.put(store::PATCH_LIFECYCLE, &patch_id, &timeline);
+            store.put(store::PATCH_PENDING, &patch_id, &patch);
+        }
+        self.lifecycle.insert(patch_id.clone(), timeline);
+        self.pending.insert(patch_id, patch);
+        self.backup();
+    }
+
+    /// Stamp a lifecycle stage for patch_id (no-op for patches without a timeline).
+    pub fn mark(&self, patch_id: &str, stage: Stage) {
+        let marked = match self.lifecycle.get_mut(patch_id) {
+            Some(mut t) => {
+                t.mark(stage, self.clock.now_ms());
+                if let Some(store) = &self.store {
+                    store.put(store::PATCH_LIFECYCLE, patch_id, &*t);
+                }
+                true
+            }
+            None => false,
+        };
+        if marked {
+            self.backup();
+        }
+    }
+
+    pub fn timelines(&self) -> Vec<HealTimeline> {
+        self.lifecycle.iter().map(|e| e.value().clone()).collect()
+    }
+
+    /// Revision proposed after patch_id's apply test failed, if any.
+    pub fn revised_by(&self, patch_id: &str) -> Option<String> {
+        self.lifecycle
+            .iter()
+            .find(|e| e.value().revision_of == patch_id)
+            .map(|e| e.key().clone())
+    }
+
+    /// Patch ids of patch_id's revision chain, first proposal first; empty when the patch was never revised.
+    pub fn revision_chain(&self, patch_id: &str) -> Vec<String> {
+        let mut chain = vec![patch_id.to_string()];
+        while let Some(parent) = self
+            .lifecycle
+            .get(&chain[0])
+            .map(|t| t.revision_of.clone())
+            .filter(|p| !p.is_empty() && !chain.contains(p))
+        {
+            chain.insert(0, parent);
+        }
+        while let Some(child) = self.revised_by(&chain[chain.len() - 1]).filter(|c| !chain.contains(c)) {
+            chain.push(child);
+        }
+        if chain.len() > 1 {
+            chain
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Cloned out so callers never hold a shard guard across awaits or removes.
+    pub fn get(&self, patch_id: &str) -> Option<PendingPatch> {
+        self.pending.get(patch_id).map(|p| p.value().clone())
+    }
+
+    /// Every pending patch with its proposal time (unix ms; 0 once its timeline was dropped), in no order.
+    pub fn pending(&self) -> Vec<(String, PendingPatch, i64)> {
+        self.pending
+            .iter()
+            .map(|e| (e.key().clone(), e.value().clone(), self.proposed_ms(e.key())))
+            .collect()
+    }
+
+    /// When patch_id was proposed (unix ms); 0 without a timeline.
+    pub fn proposed_ms(&self, patch_id: &str) -> i64 {
+        self.lifecycle.get(patch_id).map_or(0, |t| t.proposed_ms)
+    }
+
+    pub fn remove(&self, patch_id: &str) -> Option<PendingPatch> {
+        let removed = self.pending.remove(patch_id).map(|(_, p)| p);
+        if removed.is_some() {
+            if let Some(store) = &self.store {
+                store.delete(store::PATCH_PENDING, patch_id);
+            }
+            self.backup();
+        }
+        removed
+    }
+
+    /// Drop patch_id from `pending`, keeping only its final approval record (the rejection reason) for
+    /// GetApplyStatus.
+    pub fn reject(&self, patch_id: &str) -> Option<PendingPatch> {
+        let rejected = self.remove(patch_id);
+        let trimmed = match self.approvals.get_mut(patch_id) {
+            Some(mut records) if records.len() > 1 => {
+                let last = records.len() - 1;
+                records.drain(..last);
+                if let Some(store) = &self.store {
+                    store.put(store::PATCH_APPROVALS, patch_id, &*records);
+                }
+                true
+            }
+            _ => false,
+        };
+        if trimmed {
+            self.backup();
+        }
+        rejected
+    }
+
+    /// Forget patch_id entirely: pending entry, approval history and lifecycle timeline (cancelled smoke-test
+    /// proposals).
+    pub fn discard(&self, patch_id: &str) {
+        self.remove(patch_id);
+        if !self.take_approvals(patch_id).is_empty() {
+            self.backup();
+        }
+        if self.lifecycle.remove(patch_id).is_some() {
+            if let Some(store) = &self.store {
+                store.delete(store::PATCH_LIFECYCLE, patch_id);
+            }
+            self.backup();
+        }
+    }
+
+    /// Keep an applied patch for RollbackPatch.
+    pub fn record_applied(&self, patch_id: &str, patch: PendingPatch, commit_hash: &str, target_commit: &str) {
+        if self.applied.len() >= MAX_TIMELINES {
+            let oldest = self.applied.iter().map(|e| (e.value().applied_ms, e.key().clone())).min();
+            if let Some((_, id)) = oldest {
+                self.applied.remove(&id);
+                if let Some(store) = &self.store {
+                    store.delete(store::PATCH_APPLIED, &id);
+                }
+            }
+        }
+        let applied = AppliedPatch {
+            patch,
+            commit_hash: commit_hash.to_string(),
+            target_commit: target_commit.to_string(),
+            applied_ms: self.clock.now_ms(),
+            approvals: self.take_approvals(patch_id),
+            ..Default::default()
+        };
+        if let Some(store) = &self.store {
+            store.put(store::PATCH_APPLIED, patch_id, &applied);
+        }
+        self.applied.insert(patch_id.to_string(), applied);
+        self.backup();
+    }
+
+    pub fn applied(&self, patch_id: &str) -> Option<AppliedPatch> {
+        self.applied.get(patch_id).map(|a| a.value().clone())
+    }
+
+    /// Mark an applied patch rolled back by registry commit `commit_hash`.
+    pub fn record_rollback(&self, patch_id: &str, commit_hash: &str) {
+        let Some(mut applied) = self.applied.get_mut(patch_id) else {
+            return;
+        };
+        applied.rolled_back_ms = Some(self.clock.now_ms());
+        applied.rollback_commit = commit_hash.to_string();
+        if let Some(store) = &self.store {
+            store.put(store::PATCH_APPLIED, patch_id, &*applied);
+        }
+        drop(applied);
+        self.backup();
+    }
+
+    pub fn record_approval(&self, patch_id: &str, outcome: ApprovalOutcome, detail: impl Into<String>) {
+        if self.approvals.len() >= MAX_TIMELINES && !self.approvals.contains_key(patch_id) {
+            let oldest = self
+                .approvals
+                .iter()
+                .map(|e| (e.value().last().map_or(0, |r| r.at), e.key().clone()))
+                .min();
+            if let Some((_, id)) = oldest {
+                self.take_approvals(&id);
+            }
+        }
+        let mut records = self.approvals.entry(patch_id.to_string()).or_default();
+        records.push(ApprovalRecord {
+            outcome,
+            detail: detail.into(),
+            at: self.clock.now_secs(),
+        });
+        if let Some(store) = &self.store {
+            store.put(store::PATCH_APPROVALS, patch_id, &*records);
+        }
+        drop(records);
+        self.backup();
+    }
+
+    /// Approval history of patch_id, pending or applied.
+    pub fn approvals(&self, patch_id: &str) -> Vec<ApprovalRecord> {
+        self.approvals
+            .get(patch_id)
+            .map(|r| r.value().clone())
+            .or_else(|| self.applied.get(patch_id).map(|a| a.approvals.clone()))
+            .unwrap_or_default()
+    }
+
+    fn take_approvals(&self, patch_id: &str) -> Vec<ApprovalRecord> {
+        let Some((_, records)) = self.approvals.remove(patch_id) else {
+            return Vec::new();
+        };
+        if let Some(store) = &self.store {
+            store.delete(store::PATCH_APPROVALS, patch_id);
+        }
+        records
+    }
+}
+
+impl Default for PatchCatalog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_round_trips_through_backup_dir() {
+        let dir = std::env::temp_dir().join(format!("pagi_catalog_{}", uuid::Uuid::new_v4()));
+        let catalog = PatchCatalog::with_backup(dir.clone());
+        let patch = |c: &str| PendingPatch {
+            proposed_code: "pass".into(),
+            requires_hitl: true,
+            component: c.into(),
+            reasoning_id: "r".into(),
+            ..Default::default()
+        };
+        catalog.insert("p1".into(), patch("rust_core"), 0);
+        catalog.insert("p2".into(), patch("python_skill"), 0);
+        catalog.record_approval("p2", ApprovalOutcome::Approved, "flag");
+        catalog.mark("p2", Stage::Applied);
+        catalog.remove("p2");
+        let revision = PendingPatch {
+            revision_of: "p1".into(),
+            revision: 1,
+            ..patch("rust_core")
+        };
+        catalog.insert("p3".into(), revision, 0);
+
+        let raw = std::fs::read_to_string(dir.join(SNAPSHOT_FILE)).unwrap();
+        assert!(raw.contains("\"version\": 1"));
+        let restored = PatchCatalog::with_backup(dir.clone());
+        assert_eq!(restored.get("p1").unwrap().component, "rust_core");
+        assert!(restored.get("p2").is_none());
+        assert_eq!(restored.approvals("p2")[0].outcome, ApprovalOutcome::Approved);
+        assert_eq!(restored.revision_chain("p3"), ["p1", "p3"]);
+        assert_eq!(restored.revised_by("p1").as_deref(), Some("p3"));
+        assert!(restored.revision_chain("p2").is_empty());
+        let timelines = restored.timelines();
+        assert_eq!(timelines.len(), 3);
+        assert!(timelines.iter().any(|t| t.component == "python_skill" && t.applied_ms.is_some()));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn store_records_survive_restart() {
+        let dir = std::env::temp_dir().join(format!("pagi_catalog_store_{}", uuid::Uuid::new_v4()));
+        let store = Store::open(Arc::new(store::FileRepository::new(dir.clone()))).await.unwrap();
+        let catalog = PatchCatalog::new().with_store(Arc::clone(&store));
+        let patch = PendingPatch {
+            proposed_code: "pass".into(),
+            requires_hitl: false,
+            component: "rust_core".into(),
+            reasoning_id: "r".into(),
+            ..Default::default()
+        };
+        catalog.insert("p1".into(), patch.clone(), 0);
+        catalog.insert("p2".into(), patch, 0);
+        catalog.record_approval("p2", ApprovalOutcome::Denied, "no flag");
+        catalog.mark("p2", Stage::Failed);
+        catalog.remove("p2");
+        store.flush().await;
+
+        let reopened = Store::open(Arc::new(store::FileRepository::new(dir.clone()))).await.unwrap();
+        let restored = PatchCatalog::new().with_store(reopened);
+        assert!(restored.get("p1").is_some());
+        assert!(restored.get("p2").is_none());
+        assert_eq!(restored.approvals("p2")[0].outcome, ApprovalOutcome::Denied);
+        assert!(restored.timelines().iter().any(|t| t.failed_ms.is_some()));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    fn gated() -> PendingPatch {
+        PendingPatch {
+            proposed_code: "pass".into(),
+            requires_hitl: true,
+            component: "rust_core".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn applying_moves_the_approval_history_onto_the_applied_patch() {
+        let catalog = PatchCatalog::new();
+        catalog.insert("p1".into(), gated(), 0);
+        catalog.record_approval("p1", ApprovalOutcome::Denied, "no flag");
+        catalog.record_approval("p1", ApprovalOutcome::Approved, "flag");
+        let patch = catalog.remove("p1").unwrap();
+        catalog.record_applied("p1", patch, "abc123", "");
+
+        assert!(catalog.approvals.is_empty());
+        let history: Vec<ApprovalOutcome> = catalog.approvals("p1").into_iter().map(|r| r.outcome).collect();
+        assert_eq!(history, [ApprovalOutcome::Denied, ApprovalOutcome::Approved]);
+    }
+
+    #[test]
+    fn rejecting_keeps_only_the_final_record() {
+        let catalog = PatchCatalog::new();
+        catalog.insert("p1".into(), gated(), 0);
+        catalog.record_approval("p1", ApprovalOutcome::Denied, "no flag");
+        let timed_out = ApprovalOutcome::TimedOut {
+            waited_secs: 30,
+            fallback: "reject".into(),
+        };
+        catalog.record_approval("p1", timed_out.clone(), "no approval after 30s");
+
+        assert!(catalog.reject("p1").is_some());
+        assert!(catalog.get("p1").is_none());
+        let records = catalog.approvals("p1");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].outcome, timed_out);
+    }
+
+    #[test]
+    fn discarding_forgets_the_approval_history() {
+        let catalog = PatchCatalog::new();
+        catalog.insert("p1".into(), gated(), 0);
+        catalog.record_approval("p1", ApprovalOutcome::Denied, "no flag");
+        catalog.discard("p1");
+        assert!(catalog.approvals("p1").is_empty());
+    }
+
+    #[test]
+    fn oldest_approval_histories_are_crowded_out() {
+        let manual = crate::clock::ManualClock::at(1_700_000_000);
+        let catalog = PatchCatalog::new().with_clock(manual.clone().into());
+        catalog.record_approval("old", ApprovalOutcome::Denied, "no flag");
+        manual.advance(std::time::Duration::from_secs(1));
+        for i in 0..MAX_TIMELINES {
+            catalog.record_approval(&format!("p{}", i), ApprovalOutcome::Denied, "no flag");
+        }
+        assert_eq!(catalog.approvals.len(), MAX_TIMELINES);
+        assert!(catalog.approvals("old").is_empty());
+        assert_eq!(catalog.approvals("p0").len(), 1);
+    }
+}
@@ -0,0 +1,417 @@
+// Self-heal proposals as real diffs. The failing location comes from the error trace (the Rust `src/x.rs:12:5`
+// panic location, or the innermost `File "a/b.py", line 3` frame of a Python traceback) and is resolved inside the
+// component's repo; the proposal becomes one unified-diff hunk against that file. A local-model fix replaces the
+// failing line, the generic proposal only annotates it. ApplyPatch applies the stored diff to the target tree,
+// checking every context and removed line first so a file edited since the proposal fails cleanly, and reverses
+// it when the apply test or a smoke command fails. Traces that name no file in the repo get no diff.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::impact;
+
+/// Unchanged lines shown around a change.
+const CONTEXT: usize = 3;
+const NO_NEWLINE: &str = "\\ No newline at end of file";
+
+/// A line of a file in the component repo; `file` is repo-relative with `/` separators, `line` 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub file: String,
+    pub line: usize,
+}
+
+/// (path, line) pairs named in `trace`, in trace order.
+fn trace_locations(trace: &str) -> Vec<(String, usize)> {
+    let mut out = Vec::new();
+    for line in trace.lines() {
+        let line = line.trim();
+        if let Some((path, rest)) = line.strip_prefix("File \"").and_then(|l| l.split_once('"')) {
+            let number = rest.trim_start_matches(',').trim_start().strip_prefix("line ");
+            let digits = number.map(|n| n.split(|c: char| !c.is_ascii_digit()).next().unwrap_or(""));
+            if let Some(n) = digits.and_then(|d| d.parse().ok()) {
+                out.push((path.to_string(), n));
+            }
+            continue;
+        }
+        for token in line.split_whitespace() {
+            let token = token.trim_matches(|c: char| matches!(c, '"' | '\'' | ',' | '(' | ')' | '`'));
+            let mut parts = token.split(':');
+            let (Some(path), Some(n)) = (parts.next(), parts.next().and_then(|n| n.parse().ok())) else {
+                continue;
+            };
+            if path.ends_with(".rs") || path.ends_with(".py") {
+                out.push((path.to_string(), n));
+            }
+        }
+    }
+    out
+}
+
+/// `path` as a file under `repo`: the longest suffix of it that exists there, so absolute paths and paths from
+/// another checkout resolve too. Vendored, virtualenv and build directories never match.
+fn resolve(repo: &Path, path: &str) -> Option<String> {
+    let path = path.replace('\\', "/");
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty() && *s != ".").collect();
+    if segments
+        .iter()
+        .any(|s| *s == ".." || *s == "site-packages" || impact::SKIP_DIRS.contains(s))
+    {
+        return None;
+    }
+    (0..segments.len())
+        .map(|i| segments[i..].join("/"))
+        .find(|rel| repo.join(rel).is_file())
+}
+
+/// Where `trace` failed inside `repo`: the innermost frame of a Python traceback, else the first location.
+pub fn locate(trace: &str, repo: &Path) -> Option<Location> {
+    let mut found = trace_locations(trace)
+        .into_iter()
+        .filter(|(_, line)| *line > 0)
+        .filter_map(|(path, line)| resolve(repo, &path).map(|file| Location { file, line }));
+    if trace.contains("Traceback (most recent call last)") {
+        found.last()
+    } else {
+        found.next()
+    }
+}
+
+/// The proposal for `at` as a unified diff against `repo`: `code` replaces the failing line, re-indented to it,
+/// under a comment naming the fix; without code the comment alone goes above the failing line.
+pub fn propose(repo: &Path, at: &Location, code: Option<&str>, headline: &str) -> Result<String, String> {
+    let content = std::fs::read_to_string(repo.join(&at.file)).map_err(|e| format!("read {}: {}", at.file, e))?;
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    if at.line > lines.len() {
+        return Err(format!(
+            "{} has {} lines, trace names line {}",
+            at.file,
+            lines.len(),
+            at.line
+        ));
+    }
+    let index = at.line - 1;
+    let failing = lines[index];
+    let indent = &failing[..failing.len() - failing.trim_start().len()];
+    let comment = if at.file.ends_with(".py") { "#" } else { "//" };
+    let mut inserted = vec![format!("{}{} pagi self-heal: {}", indent, comment, headline.trim())];
+    let removed = match code {
+        Some(code) => {
+            let body: Vec<&str> = code.lines().filter(|l| !l.trim_start().starts_with("```")).collect();
+            let strip = body
+                .iter()
+                .filter(|l| !l.trim().is_empty())
+                .map(|l| l.len() - l.trim_start().len())
+                .min()
+                .unwrap_or(0);
+            inserted.extend(body.iter().map(|l| {
+                if l.trim().is_empty() {
+                    String::new()
+                } else {
+                    format!("{}{}", indent, &l[strip..])
+                }
+            }));
+            1
+        }
+        None => 0,
+    };
+    Ok(hunk(&at.file, &lines, index, removed, &inserted))
+}
+
+fn push_line(out: &mut String, prefix: char, line: &str) {
+    out.push(prefix);
+    out.push_str(line);
+    if !line.ends_with('\n') {
+        out.push('\n');
+        out.push_str(NO_NEWLINE);
+        out.push('\n');
+    }
+}
+
+/// One-hunk diff of `file` replacing `removed` lines at `index` (0-based) with `inserted`.
+fn hunk(file: &str, lines: &[&str], index: usize, removed: usize, inserted: &[String]) -> String {
+    let start = index.saturating_sub(CONTEXT);
+    let end = (index + removed + CONTEXT).min(lines.len());
+    let mut out = format!(
+        "diff --git a/{f} b/{f}\n--- a/{f}\n+++ b/{f}\n@@ -{s},{old} +{s},{new} @@\n",
+        f = file,
+        s = start + 1,
+        old = end - start,
+        new = end - start - removed + inserted.len()
+    );
+    for line in &lines[start..index] {
+        push_line(&mut out, ' ', line);
+    }
+    for line in &lines[index..index + removed] {
+        push_line(&mut out, '-', line);
+    }
+    for line in inserted {
+        push_line(&mut out, '+', &format!("{}\n", line));
+    }
+    for line in &lines[index + removed..end] {
+        push_line(&mut out, ' ', line);
+    }
+    out
+}
+
+#[derive(Debug, Default)]
+struct Hunk {
+    old_start: usize,
+    new_start: usize,
+    old: Vec<String>,
+    new: Vec<String>,
+}
+
+fn last_hunk<'a>(files: &'a mut BTreeMap<String, Vec<Hunk>>, file: &Option<String>) -> Option<&'a mut Hunk> {
+    files.get_mut(file.as_ref()?)?.last_mut()
+}
+
+/// "start,count" of a hunk header side; the count defaults to 1.
+fn range(side: Option<&str>) -> Option<(usize, usize)> {
+    let side = side?;
+    let (start, count) = side.split_once(',').unwrap_or((side, "1"));
+    Some((start.parse().ok()?, count.parse().ok()?))
+}
+
+/// Hunks per file of a diff of existing files. Hunk line counts are honoured, so removed "-- x" or added "++ x"
+/// lines are not taken for file headers.
+fn parse(diff: &str) -> Result<BTreeMap<String, Vec<Hunk>>, String> {
+    let mut files: BTreeMap<String, Vec<Hunk>> = BTreeMap::new();
+    let mut file = None;
+    // Lines the current hunk still expects on the old and new side, and the sides its previous line was on.
+    let mut left = (0, 0);
+    let mut last = (false, false);
+    for line in diff.lines() {
+        if line == NO_NEWLINE {
+            if let Some(hunk) = last_hunk(&mut files, &file) {
+                for (side, on) in [(&mut hunk.old, last.0), (&mut hunk.new, last.1)] {
+                    if let Some(l) = side.last_mut().filter(|_| on) {
+                        l.pop();
+                    }
+                }
+            }
+            continue;
+        }
+        if left == (0, 0) {
+            if let Some(path) = line.strip_prefix("+++ ") {
+                let path = path
+                    .strip_prefix("b/")
+                    .ok_or_else(|| format!("unsupported target {}", path))?;
+                file = Some(path.to_string());
+            } else if let Some(header) = line.strip_prefix("@@ -") {
+                let target = file.clone().ok_or("hunk before any +++ header")?;
+                let mut sides = header.split(' ');
+                let old = range(sides.next());
+                let new = range(sides.next().and_then(|r| r.strip_prefix('+')));
+                let (Some((old_start, old_count)), Some((new_start, new_count))) = (old, new) else {
+                    return Err(format!("bad hunk header {}", line));
+                };
+                files.entry(target).or_default().push(Hunk {
+                    old_start,
+                    new_start,
+                    ..Default::default()
+                });
+                left = (old_count, new_count);
+            }
+            // diff --git, ---, index and mode lines carry nothing the hunks need.
+            continue;
+        }
+        let hunk = last_hunk(&mut files, &file).ok_or("hunk line outside a hunk")?;
+        let text = format!("{}\n", line.get(1..).unwrap_or(""));
+        last = match line.chars().next() {
+            Some('-') => (true, false),
+            Some('+') => (false, true),
+            Some(' ') | None => (true, true),
+            _ => return Err(format!("bad hunk line {:?}", line)),
+        };
+        if (last.0 && left.0 == 0) || (last.1 && left.1 == 0) {
+            return Err(format!("hunk longer than its header: {:?}", line));
+        }
+        if last.0 {
+            hunk.old.push(text.clone());
+            left.0 -= 1;
+        }
+        if last.1 {
+            hunk.new.push(text);
+            left.1 -= 1;
+        }
+    }
+    if left != (0, 0) {
+        return Err("diff ends inside a hunk".to_string());
+    }
+    Ok(files)
+}
+
+/// Files `diff` changes, repo-relative.
+pub fn files(diff: &str) -> Result<Vec<String>, String> {
+    Ok(parse(diff)?.into_keys().collect())
+}
+
+/// Apply `diff` to the files under `root` (`reverse` undoes it); every hunk must match before anything is
+/// written. Returns the changed files.
+pub fn apply(root: &Path, diff: &str, reverse: bool) -> Result<Vec<String>, String> {
+    let mut patched = Vec::new();
+    for (file, hunks) in parse(diff)? {
+        let path = root.join(&file);
+        let content = std::fs::read_to_string(&path).map_err(|e| format!("read {}: {}", file, e))?;
+        let mut lines: Vec<String> = content.split_inclusive('\n').map(str::to_string).collect();
+        // Hunks are in file order; earlier ones shift the later ones by their size change.
+        let mut shift: isize = 0;
+        for hunk in hunks {
+            let (start, old, new) = if reverse {
+                (hunk.new_start, hunk.new, hunk.old)
+            } else {
+                (hunk.old_start, hunk.old, hunk.new)
+            };
+            // A hunk without old lines inserts after line `start`.
+            let at = start as isize - isize::from(!old.is_empty()) + shift;
+            let at = usize::try_from(at).map_err(|_| format!("{}: hunk at line {} out of range", file, start))?;
+            if lines.get(at..at + old.len()) != Some(&old[..]) {
+                return Err(format!(
+                    "{}: hunk at line {} does not match (edited since the proposal?)",
+                    file, start
+                ));
+            }
+            shift += new.len() as isize - old.len() as isize;
+            lines.splice(at..at + old.len(), new);
+        }
+        patched.push((file, path, lines.concat()));
+    }
+    for (file, path, content) in &patched {
+        std::fs::write(path, content).map_err(|e| format!("write {}: {}", file, e))?;
+    }
+    Ok(patched.into_iter().map(|(file, _, _)| file).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const PEEK: &str = "def run(path):\n    data = open(path)\n    return data.read()\n";
+    const LIB: &str = "fn a() {}\nfn b() {\n    panic!(\"x\")\n}";
+
+    fn temp_repo() -> PathBuf {
+        let repo = std::env::temp_dir().join(format!("pagi_patch_diff_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(repo.join("src/skills")).unwrap();
+        std::fs::write(repo.join("src/skills/peek.py"), PEEK).unwrap();
+        std::fs::write(repo.join("src/lib.rs"), LIB).unwrap();
+        repo
+    }
+
+    fn read(repo: &Path, file: &str) -> String {
+        std::fs::read_to_string(repo.join(file)).unwrap()
+    }
+
+    fn peek_fix(repo: &Path) -> String {
+        let at = Location {
+            file: "src/skills/peek.py".into(),
+            line: 2,
+        };
+        let fix = "```python\nif not os.path.exists(path):\n    return ''\n```";
+        propose(repo, &at, Some(fix), "x").unwrap()
+    }
+
+    fn lib_annotation(repo: &Path) -> String {
+        let at = Location {
+            file: "src/lib.rs".into(),
+            line: 4,
+        };
+        propose(repo, &at, None, "panicked").unwrap()
+    }
+
+    #[test]
+    fn python_tracebacks_locate_the_innermost_repo_frame() {
+        let repo = temp_repo();
+        let traceback = "Traceback (most recent call last):\n  File \"/srv/bridge/src/run.py\", line 9, in main\n  \
+                         File \"/srv/bridge/src/skills/peek.py\", line 2, in run\nFileNotFoundError: x";
+        assert_eq!(
+            locate(traceback, &repo).unwrap(),
+            Location {
+                file: "src/skills/peek.py".into(),
+                line: 2
+            }
+        );
+        let _ = std::fs::remove_dir_all(repo);
+    }
+
+    #[test]
+    fn rust_panics_locate_their_line_and_foreign_paths_are_ignored() {
+        let repo = temp_repo();
+        assert_eq!(locate("panicked at src/lib.rs:3:5", &repo).unwrap().line, 3);
+        assert_eq!(locate("panicked at .venv/lib/x.py:1 and src/none.rs:4", &repo), None);
+        let _ = std::fs::remove_dir_all(repo);
+    }
+
+    #[test]
+    fn proposed_fixes_replace_the_failing_line() {
+        let repo = temp_repo();
+        let diff = peek_fix(&repo);
+        assert!(
+            diff.starts_with("diff --git a/src/skills/peek.py b/src/skills/peek.py\n"),
+            "{}",
+            diff
+        );
+        assert!(
+            diff.contains("@@ -1,3 +1,5 @@\n def run(path):\n-    data = open(path)\n"),
+            "{}",
+            diff
+        );
+        assert_eq!(apply(&repo, &diff, false).unwrap(), ["src/skills/peek.py"]);
+        assert_eq!(
+            read(&repo, "src/skills/peek.py"),
+            "def run(path):\n    # pagi self-heal: x\n    if not os.path.exists(path):\n        return ''\n    \
+             return data.read()\n"
+        );
+        let _ = std::fs::remove_dir_all(repo);
+    }
+
+    #[test]
+    fn applied_diffs_revert_and_do_not_apply_twice() {
+        let repo = temp_repo();
+        let diff = peek_fix(&repo);
+        apply(&repo, &diff, false).unwrap();
+        assert!(apply(&repo, &diff, false).is_err(), "already applied");
+        apply(&repo, &diff, true).unwrap();
+        assert_eq!(read(&repo, "src/skills/peek.py"), PEEK);
+        let _ = std::fs::remove_dir_all(repo);
+    }
+
+    #[test]
+    fn annotations_keep_the_failing_line_and_a_missing_trailing_newline() {
+        let repo = temp_repo();
+        let diff = lib_annotation(&repo);
+        assert!(
+            diff.contains(&format!("+// pagi self-heal: panicked\n }}\n{}\n", NO_NEWLINE)),
+            "{}",
+            diff
+        );
+        apply(&repo, &diff, false).unwrap();
+        assert_eq!(
+            read(&repo, "src/lib.rs"),
+            "fn a() {}\nfn b() {\n    panic!(\"x\")\n// pagi self-heal: panicked\n}"
+        );
+        apply(&repo, &diff, true).unwrap();
+        assert_eq!(read(&repo, "src/lib.rs"), LIB);
+        let _ = std::fs::remove_dir_all(repo);
+    }
+
+    #[test]
+    fn diffs_are_standard_enough_for_git() {
+        let repo = temp_repo();
+        let diff = lib_annotation(&repo);
+        let by_git = apply_with_git(&repo, &diff);
+        assert!(by_git.is_ok(), "{:?}", by_git);
+        let _ = std::fs::remove_dir_all(repo);
+    }
+
+    /// The diffs are standard: git applies them too.
+    fn apply_with_git(repo: &Path, diff: &str) -> Result<(), git2::Error> {
+        let git = git2::Repository::init(repo)?;
+        git.apply(
+            &git2::Diff::from_buffer(diff.as_bytes())?,
+            git2::ApplyLocation::WorkDir,
+            None,
+        )
+    }
+}
@@ -0,0 +1,253 @@
+// Evolution Registry patch files in `git format-patch` layout (mbox header + X-Pagi metadata + diff).
+// Stored files can be re-applied with `git am`/`git apply`, mailed, or reviewed with standard tooling.
+
+use crate::clock::Clock;
+
+/// Fixed placeholder commit line used by `git format-patch` when the source commit is unknown.
+const MBOX_FROM_LINE: &str = "From 0000000000000000000000000000000000000000 Mon Sep 17 00:00:00 2001";
+
+const HEADER_PATCH_ID: &str = "X-Pagi-Patch-Id";
+const HEADER_COMPONENT: &str = "X-Pagi-Component";
+const HEADER_REASONING_ID: &str = "X-Pagi-Reasoning-Id";
+const HEADER_TEST_RESULT: &str = "X-Pagi-Test-Result";
+
+/// Metadata carried in the patch headers; parsed back by `parse_metadata`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PatchMetadata {
+    pub patch_id: String,
+    pub component: String,
+    pub reasoning_id: String,
+    /// One-line summary of the apply-time test step (e.g. "passed: cargo test").
+    pub test_result: String,
+}
+
+/// Unified diff creating `rel_path` with `content` (new file, mode 100644).
+pub fn new_file_diff(rel_path: &str, content: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut diff = format!(
+        "diff --git a/{p} b/{p}\nnew file mode 100644\n--- /dev/null\n+++ b/{p}\n@@ -0,0 +1,{n} @@\n",
+        p = rel_path,
+        n = lines.len()
+    );
+    for line in &lines {
+        diff.push('+');
+        diff.push_str(line);
+        diff.push('\n');
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        diff.push_str("\\ No newline at end of file\n");
+    }
+    diff
+}
+
+/// Trace lines quoted in a patch description; longer traces are cut.
+const DESCRIPTION_TRACE_LINES: usize = 20;
+
+/// Commit-message body for a self-patch: the error trace it was proposed for (and the patch it revises).
+/// Trace lines are indented so `---`, `diff --git` or `From ` lines in it cannot end the message early.
+pub fn description(error_trace: &str, revision_of: &str) -> String {
+    let mut out = String::new();
+    let trace: Vec<&str> = error_trace.trim().lines().collect();
+    if !trace.is_empty() {
+        out.push_str("Proposed for:\n");
+        for line in trace.iter().take(DESCRIPTION_TRACE_LINES) {
+            out.push_str("    ");
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+        if trace.len() > DESCRIPTION_TRACE_LINES {
+            out.push_str("    ...\n");
+        }
+    }
+    if !revision_of.is_empty() {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("Revises: {}\n", one_line(revision_of)));
+    }
+    out
+}
+
+/// Old and new line counts of a `@@ -a[,b] +c[,d] @@` hunk header (an omitted count is 1).
+fn hunk_lengths(line: &str) -> Option<(usize, usize)> {
+    let ranges = line.strip_prefix("@@ -")?.split(" @@").next()?;
+    let (old, new) = ranges.split_once(" +")?;
+    let len = |range: &str| match range.split_once(',') {
+        Some((_, n)) => n.parse().ok(),
+        None => range.parse::<usize>().ok().map(|_| 1),
+    };
+    Some((len(old)?, len(new)?))
+}
+
+/// Count (insertions, deletions) in a unified diff. Lines are only counted inside a hunk's ranges, so file
+/// headers are skipped while removed `-- x` or added `++ y` content lines (`--- x`, `+++ y`) still count.
+fn diff_counts(diff: &str) -> (usize, usize) {
+    let (mut ins, mut del) = (0, 0);
+    // Old and new lines still expected in the current hunk.
+    let (mut old_left, mut new_left) = (0usize, 0usize);
+    for line in diff.lines() {
+        if old_left == 0 && new_left == 0 {
+            if let Some((old, new)) = hunk_lengths(line) {
+                (old_left, new_left) = (old, new);
+            }
+            continue;
+        }
+        match line.as_bytes().first() {
+            Some(b'+') => {
+                ins += 1;
+                new_left = new_left.saturating_sub(1);
+            }
+            Some(b'-') => {
+                del += 1;
+                old_left = old_left.saturating_sub(1);
+            }
+            Some(b'\\') => {}
+            _ => {
+                old_left = old_left.saturating_sub(1);
+                new_left = new_left.saturating_sub(1);
+            }
+        }
+    }
+    (ins, del)
+}
+
+/// Render a full `git format-patch` style document around `diff`.
+/// `subject` is prefixed with `[PATCH]`; `body` (e.g. reasoning) goes before the diffstat separator.
+/// The `Date:` header comes from `clock`.
+pub fn format_patch(meta: &PatchMetadata, subject: &str, body: &str, diff: &str, clock: &Clock) -> String {
+    let (ins, del) = diff_counts(diff);
+    let files = diff.lines().filter(|l| l.starts_with("diff --git ")).count();
+    let mut out = String::new();
+    out.push_str(MBOX_FROM_LINE);
+    out.push('\n');
+    out.push_str("From: Sovereign Architect <agi@core>\n");
+    out.push_str(&format!("Date: {}\n", clock.now().to_rfc2822()));
+    out.push_str(&format!("Subject: [PATCH] {}\n", one_line(subject)));
+    out.push_str(&format!("{}: {}\n", HEADER_PATCH_ID, one_line(&meta.patch_id)));
+    out.push_str(&format!("{}: {}\n", HEADER_COMPONENT, one_line(&meta.component)));
+    out.push_str(&format!("{}: {}\n", HEADER_REASONING_ID, one_line(&meta.reasoning_id)));
+    out.push_str(&format!("{}: {}\n", HEADER_TEST_RESULT, one_line(&meta.test_result)));
+    out.push('\n');
+    if !body.trim().is_empty() {
+        out.push_str(body.trim_end());
+        out.push_str("\n\n");
+    }
+    out.push_str("---\n");
+    out.push_str(&format!(
+        " {} file{} changed, {} insertion{}(+), {} deletion{}(-)\n\n",
+        files,
+        if files == 1 { "" } else { "s" },
+        ins,
+        if ins == 1 { "" } else { "s" },
+        del,
+        if del == 1 { "" } else { "s" },
+    ));
+    out.push_str(diff);
+    if !diff.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str("-- \npagi-core-orchestrator\n");
+    out
+}
+
+/// Parse X-Pagi metadata headers from a stored patch. Returns None when no patch id header is present.
+#[allow(dead_code)]
+pub fn parse_metadata(text: &str) -> Option<PatchMetadata> {
+    let mut meta = PatchMetadata::default();
+    for line in text.lines() {
+        if line.is_empty() {
+            break; // end of mail headers
+        }
+        let Some((k, v)) = line.split_once(": ") else {
+            continue;
+        };
+        let v = v.trim().to_string();
+        match k {
+            HEADER_PATCH_ID => meta.patch_id = v,
+            HEADER_COMPONENT => meta.component = v,
+            HEADER_REASONING_ID => meta.reasoning_id = v,
+            HEADER_TEST_RESULT => meta.test_result = v,
+            _ => {}
+        }
+    }
+    if meta.patch_id.is_empty() {
+        None
+    } else {
+        Some(meta)
+    }
+}
+
+/// Header values must stay on one line to keep the mbox header block valid.
+fn one_line(s: &str) -> String {
+    s.lines().next().unwrap_or("").chars().take(200).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    fn meta() -> PatchMetadata {
+        PatchMetadata {
+            patch_id: "p-1".to_string(),
+            component: "python_skill".to_string(),
+            reasoning_id: "r-1".to_string(),
+            test_result: "passed: poetry run pytest tests/ -v".to_string(),
+        }
+    }
+
+    #[test]
+    fn format_patch_round_trips_metadata() {
+        let diff = new_file_diff("patch_p-1.py", "print('hi')\n");
+        let clock = ManualClock::at(1_700_000_000).into();
+        let text = format_patch(&meta(), "Self-patch p-1 for python_skill", "reason", &diff, &clock);
+        assert!(text.starts_with("From 0000000000000000000000000000000000000000"));
+        assert!(text.contains("Subject: [PATCH] Self-patch p-1 for python_skill"));
+        assert!(text.contains("Date: Tue, 14 Nov 2023 22:13:20 +0000\n"));
+        assert!(text.contains(" 1 file changed, 1 insertion(+), 0 deletions(-)"));
+        assert_eq!(parse_metadata(&text), Some(meta()));
+    }
+
+    #[test]
+    fn diffstat_counts_content_lines_that_look_like_file_headers() {
+        let diff =
+            "diff --git a/a.sql b/a.sql\n--- a/a.sql\n+++ b/a.sql\n@@ -1,2 +1,2 @@\n--- old\n+++ new\n select 1;\n";
+        assert_eq!(diff_counts(diff), (1, 1));
+        let two_hunks = "--- a/a\n+++ b/a\n@@ -1 +1 @@\n-a\n+b\n@@ -9,0 +10,2 @@\n+c\n+d\n";
+        assert_eq!(diff_counts(two_hunks), (3, 1));
+    }
+
+    #[test]
+    fn formatted_patch_applies_with_git() {
+        let temp = std::env::temp_dir().join(format!("pagi_patch_format_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp).unwrap();
+        let repo = git2::Repository::init(&temp).unwrap();
+        let diff = new_file_diff("patch_p-1.py", "a = 1\nb = 2");
+        let text = format_patch(&meta(), "Self-patch p-1", "", &diff, &Clock::system());
+        let parsed = git2::Diff::from_buffer(text.as_bytes()).unwrap();
+        repo.apply(&parsed, git2::ApplyLocation::WorkDir, None).unwrap();
+        let written = std::fs::read_to_string(temp.join("patch_p-1.py")).unwrap();
+        assert_eq!(written, "a = 1\nb = 2");
+        let _ = std::fs::remove_dir_all(temp);
+    }
+
+    #[test]
+    fn description_quotes_the_trace_so_it_cannot_end_the_message() {
+        let body = description("panic at a.rs\n--- a/a.rs\ndiff --git a/a.rs b/a.rs\n", "p-0");
+        let quoted = "Proposed for:\n    panic at a.rs\n    --- a/a.rs\n    diff --git a/a.rs b/a.rs\n";
+        assert_eq!(body, format!("{}\nRevises: p-0\n", quoted));
+        let diff = new_file_diff("patch_p-1.py", "a = 1\n");
+        let text = format_patch(&meta(), "Self-patch p-1", &body, &diff, &Clock::system());
+        assert_eq!(text.lines().filter(|l| *l == "---").count(), 1);
+        assert_eq!(text.lines().filter(|l| l.starts_with("diff --git ")).count(), 1);
+    }
+
+    #[test]
+    fn description_cuts_long_traces_and_is_empty_without_context() {
+        let trace: Vec<String> = (0..30).map(|i| format!("frame {}", i)).collect();
+        let body = description(&trace.join("\n"), "");
+        assert_eq!(body.lines().count(), 1 + DESCRIPTION_TRACE_LINES + 1);
+        assert!(body.ends_with("    frame 19\n    ...\n"), "{}", body);
+        assert_eq!(description("  \n", ""), "");
+    }
+}
@@ -0,0 +1,201 @@
+// Patch history: every ApplyPatch outcome (applied or failed at test/commit) is indexed into a dedicated
+// L4 KB (PAGI_PATCH_KB, default kb_patches) with its error fingerprint, component and proposed code, so
+// SearchPatches and propose_patch can answer "how did we fix this last time". Writes are best-effort.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tonic::Status;
+use uuid::Uuid;
+
+use crate::memory_manager::MemoryManager;
+use crate::patch_catalog::PendingPatch;
+use crate::proto::pagi_proto::{
+    FilterCondition, PatchHistoryHit, SearchFilter, SearchPatchesRequest, SearchPatchesResponse, SearchRequest,
+    UpsertRequest, VectorPoint,
+};
+use crate::provenance;
+
+/// Chars of proposed code kept in the indexed record.
+const CODE_CHARS: usize = 2000;
+
+/// L4 collection for patch outcomes (PAGI_PATCH_KB, default kb_patches).
+pub fn kb_from_env() -> String {
+    std::env::var("PAGI_PATCH_KB")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "kb_patches".to_string())
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    match s.char_indices().nth(max) {
+        Some((i, _)) => format!("{}…", &s[..i]),
+        None => s.to_string(),
+    }
+}
+
+/// Indexed record for one apply outcome; `outcome` is "applied" or "failed", `detail` the commit or error.
+pub fn build_point(patch_id: &str, patch: &PendingPatch, outcome: &str, detail: &str, dim: usize) -> VectorPoint {
+    let code = truncate(&patch.proposed_code, CODE_CHARS);
+    let content = format!("[{} {}] {}", patch.component, outcome, code);
+    VectorPoint {
+        id: Uuid::new_v4().to_string(),
+        vector: provenance::hash_embed(&content, dim),
+        payload: HashMap::from([
+            ("patch_id".to_string(), patch_id.to_string()),
+            ("component".to_string(), patch.component.clone()),
+            ("fingerprint".to_string(), patch.fingerprint.clone()),
+            ("outcome".to_string(), outcome.to_string()),
+            ("detail".to_string(), truncate(detail, 400)),
+            ("at".to_string(), chrono::Utc::now().timestamp().to_string()),
+            ("content".to_string(), content),
+        ]),
+        ..Default::default()
+    }
+}
+
+/// Spawn a best-effort upsert of an apply outcome; no-op when L4 is disabled.
+pub fn record_outcome(memory: &Arc<MemoryManager>, patch_id: &str, patch: &PendingPatch, outcome: &str, detail: &str) {
+    if !memory.l4_enabled() {
+        return;
+    }
+    let kb_name = kb_from_env();
+    let point = build_point(patch_id, patch, outcome, detail, memory.kb_dim(&kb_name));
+    let memory = Arc::clone(memory);
+    tokio::spawn(async move {
+        if let Err(e) = memory.ensure_kb(&kb_name).await {
+            eprintln!("[PatchHistory] create {}: {}", kb_name, e);
+            return;
+        }
+        let req = UpsertRequest {
+            kb_name: kb_name.clone(),
+            points: vec![point],
+            ..Default::default()
+        };
+        if let Err(e) = memory.upsert_vectors(req).await {
+            eprintln!("[PatchHistory] upsert {}: {}", kb_name, e.message());
+        }
+    });
+}
+
+/// SearchRequest for the patch KB: hash-embedded query fused with keyword matches (error strings are
+/// often exact), restricted by the request's non-empty component/fingerprint/outcome.
+fn search_request(req: &SearchPatchesRequest, kb_name: String, dim: usize) -> SearchRequest {
+    let must = [
+        ("component", &req.component),
+        ("fingerprint", &req.fingerprint),
+        ("outcome", &req.outcome),
+    ]
+    .into_iter()
+    .filter(|(_, value)| !value.is_empty())
+    .map(|(key, value)| FilterCondition {
+        key: key.to_string(),
+        r#match: value.clone(),
+        range: None,
+    })
+    .collect::<Vec<_>>();
+    SearchRequest {
+        query: req.query.clone(),
+        kb_name,
+        limit: if req.limit == 0 { 5 } else { req.limit },
+        query_vector: provenance::hash_embed(&req.query, dim),
+        filter: (!must.is_empty()).then(|| SearchFilter {
+            must,
+            ..Default::default()
+        }),
+        hybrid: true,
+        ..Default::default()
+    }
+}
+
+/// SearchPatches: prior apply outcomes most similar to `query`.
+pub async fn search(memory: &MemoryManager, req: SearchPatchesRequest) -> Result<SearchPatchesResponse, Status> {
+    let kb_name = kb_from_env();
+    if memory.l4_enabled() {
+        memory
+            .ensure_kb(&kb_name)
+            .await
+            .map_err(|e| Status::unavailable(format!("create {}: {}", kb_name, e)))?;
+    }
+    let dim = memory.kb_dim(&kb_name);
+    let found = memory.search_points(search_request(&req, kb_name, dim)).await?;
+    let hits = found
+        .points
+        .into_iter()
+        .map(|p| {
+            let field = |k: &str| p.payload.get(k).cloned().unwrap_or_default();
+            PatchHistoryHit {
+                patch_id: field("patch_id"),
+                component: field("component"),
+                fingerprint: field("fingerprint"),
+                outcome: field("outcome"),
+                detail: field("detail"),
+                recorded_at: field("at").parse().unwrap_or(0),
+                content_snippet: field("content"),
+                score: p.score,
+            }
+        })
+        .collect();
+    Ok(SearchPatchesResponse {
+        hits,
+        degraded: found.degraded,
+        source: found.source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patch() -> PendingPatch {
+        PendingPatch {
+            proposed_code: "// Generic fix for: panicked at src/lib.rs:10\nfn fixed() {}".to_string(),
+            requires_hitl: true,
+            component: "rust_core".to_string(),
+            reasoning_id: "r1".to_string(),
+            fingerprint: "abcd1234".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn outcome_points_carry_fingerprint_and_outcome() {
+        let point = build_point("p1", &patch(), "applied", "deadbeef", 16);
+        assert_eq!(point.vector.len(), 16);
+        assert_eq!(point.payload["fingerprint"], "abcd1234");
+        assert_eq!(point.payload["outcome"], "applied");
+    }
+
+    #[test]
+    fn outcome_content_leads_with_component_and_outcome() {
+        let point = build_point("p1", &patch(), "applied", "deadbeef", 16);
+        assert!(point.payload["content"].starts_with("[rust_core applied] // Generic fix for: panicked"));
+    }
+
+    #[test]
+    fn searches_are_hybrid_and_filter_by_fingerprint_and_outcome() {
+        let req = SearchPatchesRequest {
+            query: "panicked at src/lib.rs".to_string(),
+            fingerprint: "abcd1234".to_string(),
+            outcome: "applied".to_string(),
+            ..Default::default()
+        };
+        let search = search_request(&req, "kb_patches".to_string(), 16);
+        assert!(search.hybrid);
+        assert_eq!(search.limit, 5);
+        let keys: Vec<_> = search.filter.unwrap().must.into_iter().map(|c| (c.key, c.r#match)).collect();
+        assert_eq!(
+            keys,
+            [
+                ("fingerprint".to_string(), "abcd1234".to_string()),
+                ("outcome".to_string(), "applied".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn unfiltered_searches_send_no_filter() {
+        assert!(search_request(&SearchPatchesRequest::default(), "kb_patches".into(), 16).filter.is_none());
+    }
+}
@@ -0,0 +1,673 @@
+// pgvector L4 backend (PAGI_VECTOR_BACKEND=pgvector): KBs live in an existing Postgres with the `vector`
+// extension (>= 0.5 for HNSW), so sites already running Postgres need no Qdrant. PAGI_PGVECTOR_URL is the
+// connection string and PAGI_PGVECTOR_POOL_SIZE bounds the pool; connections open lazily and PAGI_QDRANT_TIMEOUT_MS
+// bounds waiting for one. Each collection is a table (TABLE_PREFIX + name, shortened with a hash past Postgres'
+// 63-byte identifiers) with a text id, a JSONB payload and one vector column per space ("vector" for unnamed KBs,
+// "v:<name>" per named space), each under an HNSW index for the KB's distance. Layouts are recorded in COLLECTIONS.
+// KB semantics follow the Qdrant backend: canonical integer payload values are stored as JSON numbers so range
+// filters apply to them, scores are cosine/dot similarities or euclid distances, upserts replace whole points.
+// Filters are evaluated in SQL around the index scan, which is widened (hnsw.ef_search) so they still fill `limit`.
+// There are no native snapshots (callers dump points portably) and quantization is a no-op; `optimize` VACUUMs.
+// Connection failures are reported as transport errors so MemoryManager's breaker counts them as outages.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::postgres::{PgPool, PgPoolOptions, PgRow};
+use sqlx::types::Json;
+use sqlx::{Executor, Postgres, QueryBuilder, Row};
+use tokio::sync::OnceCell;
+
+use crate::kb_registry::{Distance, KbSpec, Quantization, Shape};
+use crate::proto::pagi_proto::{DenseVector, FilterCondition, SearchFilter, VectorPoint};
+use crate::vector_store::{self, ScoredPoint, ScrollPage, StoreFuture, VectorStore};
+
+/// Prefix of collection tables.
+const TABLE_PREFIX: &str = "pagi_";
+/// Table of collection layouts (name, dim, named vector dims, distance).
+const COLLECTIONS: &str = "pagi_collections";
+/// Postgres identifier length limit (bytes).
+const MAX_IDENT: usize = 63;
+/// Points per INSERT statement (Postgres allows 65535 bind parameters per statement).
+const UPSERT_BATCH: usize = 1000;
+/// Floor of hnsw.ef_search (pgvector's default is 40).
+const EF_SEARCH_MIN: usize = 100;
+
+fn quote(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+/// Table of `collection`, unquoted: TABLE_PREFIX + name, cut and suffixed with a hash of the name past MAX_IDENT.
+fn table_name(collection: &str) -> String {
+    let name = format!("{}{}", TABLE_PREFIX, collection);
+    if name.len() <= MAX_IDENT {
+        return name;
+    }
+    let hash = format!("{:x}", Sha256::digest(collection.as_bytes()));
+    let mut cut = MAX_IDENT - 17;
+    while !name.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    format!("{}_{}", &name[..cut], &hash[..16])
+}
+
+fn table(collection: &str) -> String {
+    quote(&table_name(collection))
+}
+
+/// Vector column of a space ("" for the unnamed vector), quoted.
+fn column(space: &str) -> String {
+    if space.is_empty() {
+        quote("vector")
+    } else {
+        quote(&format!("v:{}", space))
+    }
+}
+
+/// (space, dim) of every vector column of `shape`, "" for the unnamed vector.
+fn spaces(shape: &Shape) -> Vec<(String, usize)> {
+    if shape.vectors.is_empty() {
+        return vec![(String::new(), shape.dim)];
+    }
+    shape.vectors.iter().map(|(name, &dim)| (name.clone(), dim)).collect()
+}
+
+fn distance_name(name: &str) -> Result<Distance, String> {
+    match name {
+        "cosine" => Ok(Distance::Cosine),
+        "dot" => Ok(Distance::Dot),
+        "euclid" => Ok(Distance::Euclid),
+        other => Err(format!("unknown distance {:?} in {}", other, COLLECTIONS)),
+    }
+}
+
+/// pgvector distance operator and HNSW operator class for `distance`.
+fn operator(distance: Distance) -> (&'static str, &'static str) {
+    match distance {
+        Distance::Cosine => ("<=>", "vector_cosine_ops"),
+        Distance::Dot => ("<#>", "vector_ip_ops"),
+        Distance::Euclid => ("<->", "vector_l2_ops"),
+    }
+}
+
+/// Search score from a pgvector distance: similarity for cosine (1 - d) and dot (<#> is the negated product),
+/// the distance itself for euclid, as Qdrant reports them.
+fn score(distance: Distance, d: f64) -> f32 {
+    match distance {
+        Distance::Cosine => (1.0 - d) as f32,
+        Distance::Dot => -d as f32,
+        Distance::Euclid => d as f32,
+    }
+}
+
+/// pgvector text form of a vector: "[0.5,-1]".
+fn literal(vector: &[f32]) -> String {
+    let parts: Vec<String> = vector.iter().map(f32::to_string).collect();
+    format!("[{}]", parts.join(","))
+}
+
+fn parse_vector(text: &str) -> Vec<f32> {
+    text.trim_matches(|c| c == '[' || c == ']')
+        .split(',')
+        .filter_map(|x| x.trim().parse().ok())
+        .collect()
+}
+
+/// A JSON payload value as Qdrant stores it: canonical ints as numbers, anything else as a string.
+fn json_value(value: String) -> Value {
+    match vector_store::canonical_int(&value) {
+        Some(n) => Value::from(n),
+        None => Value::String(value),
+    }
+}
+
+fn json_payload(fields: HashMap<String, String>) -> Json<Value> {
+    Json(Value::Object(fields.into_iter().map(|(k, v)| (k, json_value(v))).collect()))
+}
+
+/// String and number payload values as strings (other kinds are dropped).
+fn string_payload(payload: Value) -> HashMap<String, String> {
+    let Value::Object(fields) = payload else {
+        return HashMap::new();
+    };
+    fields
+        .into_iter()
+        .filter_map(|(k, v)| match v {
+            Value::String(s) => Some((k, s)),
+            Value::Number(n) => Some((k, n.to_string())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Outages (no connection, pool exhausted, TLS) as transport errors, so the breaker classifies them.
+fn pg_err(e: sqlx::Error) -> String {
+    match e {
+        sqlx::Error::Io(_) | sqlx::Error::Tls(_) | sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed => {
+            format!("transport error: {}", e)
+        }
+        e => e.to_string(),
+    }
+}
+
+/// One condition as a non-NULL boolean, so `NOT` also holds for points missing the key (as in Qdrant).
+fn push_condition(qb: &mut QueryBuilder<'_, Postgres>, c: &FilterCondition) {
+    qb.push("COALESCE(");
+    match &c.range {
+        Some(r) => {
+            // Only numeric values range-match; CASE keeps string values from being cast.
+            let number = |qb: &mut QueryBuilder<'_, Postgres>| {
+                qb.push("CASE WHEN jsonb_typeof(payload -> ")
+                    .push_bind(c.key.clone())
+                    .push(") = 'number' THEN (payload ->> ")
+                    .push_bind(c.key.clone())
+                    .push(")::float8 END");
+            };
+            number(qb);
+            qb.push(" IS NOT NULL");
+            if let Some(gte) = r.gte {
+                qb.push(" AND ");
+                number(qb);
+                qb.push(" >= ").push_bind(gte);
+            }
+            if let Some(lte) = r.lte {
+                qb.push(" AND ");
+                number(qb);
+                qb.push(" <= ").push_bind(lte);
+            }
+        }
+        None => {
+            qb.push("payload -> ")
+                .push_bind(c.key.clone())
+                .push(" = ")
+                .push_bind(Json(json_value(c.r#match.clone())));
+        }
+    }
+    qb.push(", FALSE)");
+}
+
+/// `filter` as one parenthesized condition: all `must`, any `should` (when present), no `must_not`.
+fn push_filter(qb: &mut QueryBuilder<'_, Postgres>, filter: &SearchFilter) {
+    qb.push("(TRUE");
+    for c in &filter.must {
+        qb.push(" AND ");
+        push_condition(qb, c);
+    }
+    if !filter.should.is_empty() {
+        qb.push(" AND (FALSE");
+        for c in &filter.should {
+            qb.push(" OR ");
+            push_condition(qb, c);
+        }
+        qb.push(")");
+    }
+    for c in &filter.must_not {
+        qb.push(" AND NOT ");
+        push_condition(qb, c);
+    }
+    qb.push(")");
+}
+
+/// Whether `p` fits `shape`, so a batch can be checked before any of it is written.
+fn check(shape: &Shape, p: &VectorPoint) -> Result<(), String> {
+    if shape.vectors.is_empty() {
+        if p.vector.len() != shape.dim {
+            return Err(format!("point {} vector dim {} != collection dim {}", p.id, p.vector.len(), shape.dim));
+        }
+        return Ok(());
+    }
+    for (name, &dim) in &shape.vectors {
+        match p.vectors.get(name) {
+            None => return Err(format!("point {} has no {:?} vector", p.id, name)),
+            Some(v) if v.data.len() != dim => {
+                return Err(format!("point {} vector {:?} dim {} != {}", p.id, name, v.data.len(), dim))
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Select list for points: id, payload and, when `with_vectors`, every vector column as text.
+fn select_list(shape: &Shape, with_vectors: bool) -> String {
+    let mut list = "id, payload".to_string();
+    if with_vectors {
+        for (space, _) in spaces(shape) {
+            list.push_str(&format!(", {}::text", column(&space)));
+        }
+    }
+    list
+}
+
+/// The point of a row selected with `select_list`.
+fn row_point(row: &PgRow, shape: &Shape, with_vectors: bool) -> Result<VectorPoint, String> {
+    let Json(payload): Json<Value> = row.try_get("payload").map_err(pg_err)?;
+    let mut point = VectorPoint {
+        id: row.try_get("id").map_err(pg_err)?,
+        payload: string_payload(payload),
+        ..Default::default()
+    };
+    if with_vectors {
+        for (i, (space, _)) in spaces(shape).into_iter().enumerate() {
+            let data = parse_vector(&row.try_get::<String, _>(2 + i).map_err(pg_err)?);
+            if space.is_empty() {
+                point.vector = data;
+            } else {
+                point.vectors.insert(space, DenseVector { data });
+            }
+        }
+    }
+    Ok(point)
+}
+
+pub struct PgVectorStore {
+    pool: PgPool,
+    /// Set once the extension and COLLECTIONS exist.
+    schema: OnceCell<()>,
+    /// Layouts read from COLLECTIONS (they never change once created).
+    layouts: RwLock<HashMap<String, Shape>>,
+}
+
+impl PgVectorStore {
+    /// Pool of up to `pool_size` connections to `url`, opened on first use; `timeout` bounds waiting for one.
+    pub fn connect_lazy(url: &str, pool_size: u32, timeout: Duration) -> Result<Self, String> {
+        let pool = PgPoolOptions::new()
+            .max_connections(pool_size)
+            .acquire_timeout(timeout)
+            .connect_lazy(url)
+            .map_err(|e| format!("PAGI_PGVECTOR_URL: {}", e))?;
+        Ok(Self {
+            pool,
+            schema: OnceCell::new(),
+            layouts: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// The `vector` extension and the layout table, created on first use.
+    async fn ensure_schema(&self) -> Result<(), String> {
+        self.schema
+            .get_or_try_init(|| async {
+                let ddl = format!(
+                    "CREATE EXTENSION IF NOT EXISTS vector; CREATE TABLE IF NOT EXISTS {} (name TEXT PRIMARY KEY, \
+                     dim INTEGER NOT NULL, vectors JSONB NOT NULL, distance TEXT NOT NULL)",
+                    COLLECTIONS
+                );
+                self.pool.execute(ddl.as_str()).await.map(|_| ()).map_err(pg_err)
+            })
+            .await
+            .map(|_| ())
+    }
+
+    /// Layout of `collection`; None when it was never created.
+    async fn layout(&self, collection: &str) -> Result<Option<Shape>, String> {
+        if let Some(shape) = self.layouts.read().unwrap_or_else(|e| e.into_inner()).get(collection) {
+            return Ok(Some(shape.clone()));
+        }
+        self.ensure_schema().await?;
+        let query = format!("SELECT dim, vectors, distance FROM {} WHERE name = $1", COLLECTIONS);
+        let Some(row) = sqlx::query(&query)
+            .bind(collection)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(pg_err)?
+        else {
+            return Ok(None);
+        };
+        let Json(vectors): Json<BTreeMap<String, usize>> = row.try_get("vectors").map_err(pg_err)?;
+        let shape = Shape {
+            dim: row.try_get::<i32, _>("dim").map_err(pg_err)? as usize,
+            vectors,
+            distance: distance_name(&row.try_get::<String, _>("distance").map_err(pg_err)?)?,
+        };
+        let mut layouts = self.layouts.write().unwrap_or_else(|e| e.into_inner());
+        layouts.insert(collection.to_string(), shape.clone());
+        Ok(Some(shape))
+    }
+
+    async fn existing(&self, collection: &str) -> Result<Shape, String> {
+        self.layout(collection)
+            .await?
+            .ok_or_else(|| format!("collection {} not found", collection))
+    }
+}
+
+impl VectorStore for PgVectorStore {
+    fn name(&self) -> &'static str {
+        "pgvector"
+    }
+
+    fn describe_collection<'a>(&'a self, collection: &'a str) -> StoreFuture<'a, Option<Shape>> {
+        Box::pin(self.layout(collection))
+    }
+
+    fn point_count<'a>(&'a self, collection: &'a str) -> StoreFuture<'a, Option<u64>> {
+        Box::pin(async move {
+            if self.layout(collection).await?.is_none() {
+                return Ok(None);
+            }
+            let count: i64 = sqlx::query_scalar(&format!("SELECT count(*) FROM {}", table(collection)))
+                .fetch_one(&self.pool)
+                .await
+                .map_err(pg_err)?;
+            Ok(Some(count as u64))
+        })
+    }
+
+    /// Table, HNSW indexes and layout row in one transaction; `on_disk` is ignored (Postgres pages to disk).
+    fn create_collection<'a>(&'a self, spec: &'a KbSpec) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            self.ensure_schema().await?;
+            let shape = Shape {
+                dim: if spec.vectors.is_empty() { spec.dim } else { 0 },
+                vectors: spec.vectors.clone(),
+                distance: spec.distance,
+            };
+            let (_, opclass) = operator(spec.distance);
+            let raw_table = table_name(&spec.name);
+            let mut ddl = format!(
+                "CREATE TABLE IF NOT EXISTS {} (id TEXT PRIMARY KEY, payload JSONB NOT NULL",
+                table(&spec.name)
+            );
+            for (space, dim) in spaces(&shape) {
+                ddl.push_str(&format!(", {} vector({}) NOT NULL", column(&space), dim));
+            }
+            ddl.push_str(");");
+            for (space, _) in spaces(&shape) {
+                let index = format!("{:x}", Sha256::digest(format!("{}/{}", raw_table, space).as_bytes()));
+                ddl.push_str(&format!(
+                    " CREATE INDEX IF NOT EXISTS {} ON {} USING hnsw ({} {});",
+                    quote(&format!("pagi_hnsw_{}", &index[..16])),
+                    table(&spec.name),
+                    column(&space),
+                    opclass
+                ));
+            }
+            let mut tx = self.pool.begin().await.map_err(pg_err)?;
+            (&mut *tx).execute(ddl.as_str()).await.map_err(pg_err)?;
+            let insert = format!(
+                "INSERT INTO {} (name, dim, vectors, distance) VALUES ($1, $2, $3, $4) ON CONFLICT (name) DO NOTHING",
+                COLLECTIONS
+            );
+            sqlx::query(&insert)
+                .bind(&spec.name)
+                .bind(shape.dim as i32)
+                .bind(Json(&shape.vectors))
+                .bind(spec.distance.as_str())
+                .execute(&mut *tx)
+                .await
+                .map_err(pg_err)?;
+            tx.commit().await.map_err(pg_err)?;
+            let mut layouts = self.layouts.write().unwrap_or_else(|e| e.into_inner());
+            layouts.insert(spec.name.clone(), shape);
+            Ok(())
+        })
+    }
+
+    /// Vectors are stored as f32: quantization is a no-op.
+    fn set_quantization<'a>(&'a self, _collection: &'a str, _quantization: Quantization) -> StoreFuture<'a, ()> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// One transaction per call; a later duplicate id in the batch wins, as in Qdrant.
+    fn upsert<'a>(&'a self, collection: &'a str, points: Vec<VectorPoint>) -> StoreFuture<'a, usize> {
+        Box::pin(async move {
+            let shape = self.existing(collection).await?;
+            points.iter().try_for_each(|p| check(&shape, p))?;
+            let n = points.len();
+            let mut seen = HashSet::new();
+            let mut points: Vec<VectorPoint> = points.into_iter().rev().filter(|p| seen.insert(p.id.clone())).collect();
+            points.reverse();
+            let spaces = spaces(&shape);
+            let columns: Vec<String> = spaces.iter().map(|(space, _)| column(space)).collect();
+            let mut tx = self.pool.begin().await.map_err(pg_err)?;
+            for batch in points.chunks(UPSERT_BATCH) {
+                let mut qb = QueryBuilder::<Postgres>::new(format!(
+                    "INSERT INTO {} (id, payload, {}) ",
+                    table(collection),
+                    columns.join(", ")
+                ));
+                qb.push_values(batch, |mut row, p| {
+                    row.push_bind(p.id.clone()).push_bind(json_payload(p.payload.clone()));
+                    for (space, _) in &spaces {
+                        let data = if space.is_empty() { &p.vector } else { &p.vectors[space].data };
+                        row.push_bind(literal(data)).push_unseparated("::vector");
+                    }
+                });
+                qb.push(" ON CONFLICT (id) DO UPDATE SET payload = EXCLUDED.payload");
+                for c in &columns {
+                    qb.push(format!(", {} = EXCLUDED.{}", c, c));
+                }
+                qb.build().execute(&mut *tx).await.map_err(pg_err)?;
+            }
+            tx.commit().await.map_err(pg_err)?;
+            Ok(n)
+        })
+    }
+
+    fn search<'a>(
+        &'a self,
+        collection: &'a str,
+        vector_name: &'a str,
+        vector: Vec<f32>,
+        limit: usize,
+        filter: Option<SearchFilter>,
+    ) -> StoreFuture<'a, Vec<ScoredPoint>> {
+        Box::pin(async move {
+            let shape = self.existing(collection).await?;
+            if !spaces(&shape).iter().any(|(space, _)| space == vector_name) {
+                return Err(format!("collection {} has no vector space {:?}", collection, vector_name));
+            }
+            let (op, _) = operator(shape.distance);
+            let mut qb = QueryBuilder::<Postgres>::new(format!("SELECT id, payload, {} {} ", column(vector_name), op));
+            qb.push_bind(literal(&vector));
+            qb.push(format!("::vector AS distance FROM {}", table(collection)));
+            if let Some(filter) = filter.as_ref().filter(|f| vector_store::has_conditions(f)) {
+                qb.push(" WHERE ");
+                push_filter(&mut qb, filter);
+            }
+            qb.push(" ORDER BY distance LIMIT ").push_bind(limit as i64);
+            // ef_search applies to the transaction only; it bounds how many candidates the index hands the filter.
+            let mut tx = self.pool.begin().await.map_err(pg_err)?;
+            let ef = format!("SET LOCAL hnsw.ef_search = {}", limit.clamp(EF_SEARCH_MIN, 1000));
+            (&mut *tx).execute(ef.as_str()).await.map_err(pg_err)?;
+            let rows = qb.build().fetch_all(&mut *tx).await.map_err(pg_err)?;
+            tx.commit().await.map_err(pg_err)?;
+            rows.iter()
+                .map(|row| {
+                    let point = row_point(row, &shape, false)?;
+                    let d: f64 = row.try_get("distance").map_err(pg_err)?;
+                    Ok(ScoredPoint {
+                        id: point.id,
+                        score: score(shape.distance, d),
+                        payload: point.payload,
+                    })
+                })
+                .collect()
+        })
+    }
+
+    fn delete<'a>(&'a self, collection: &'a str, ids: Vec<String>) -> StoreFuture<'a, usize> {
+        Box::pin(async move {
+            self.existing(collection).await?;
+            let query = format!("DELETE FROM {} WHERE id = ANY($1)", table(collection));
+            let done = sqlx::query(&query).bind(ids).execute(&self.pool).await.map_err(pg_err)?;
+            Ok(done.rows_affected() as usize)
+        })
+    }
+
+    fn set_payload<'a>(
+        &'a self,
+        collection: &'a str,
+        ids: Vec<String>,
+        fields: HashMap<String, String>,
+    ) -> StoreFuture<'a, usize> {
+        Box::pin(async move {
+            self.existing(collection).await?;
+            let query = format!("UPDATE {} SET payload = payload || $1 WHERE id = ANY($2)", table(collection));
+            let done = sqlx::query(&query)
+                .bind(json_payload(fields))
+                .bind(ids)
+                .execute(&self.pool)
+                .await
+                .map_err(pg_err)?;
+            Ok(done.rows_affected() as usize)
+        })
+    }
+
+    fn scan<'a>(&'a self, collection: &'a str, fields: &'a [&'a str]) -> StoreFuture<'a, Vec<(String, HashMap<String, String>)>> {
+        Box::pin(async move {
+            let shape = self.existing(collection).await?;
+            let query = format!("SELECT id, payload FROM {}", table(collection));
+            let rows = sqlx::query(&query).fetch_all(&self.pool).await.map_err(pg_err)?;
+            rows.iter()
+                .map(|row| {
+                    let mut point = row_point(row, &shape, false)?;
+                    point.payload.retain(|k, _| fields.contains(&k.as_str()));
+                    Ok((point.id, point.payload))
+                })
+                .collect()
+        })
+    }
+
+    fn get<'a>(&'a self, collection: &'a str, ids: Vec<String>) -> StoreFuture<'a, Vec<VectorPoint>> {
+        Box::pin(async move {
+            let shape = self.existing(collection).await?;
+            let query = format!("SELECT {} FROM {} WHERE id = ANY($1)", select_list(&shape, true), table(collection));
+            let rows = sqlx::query(&query).bind(ids).fetch_all(&self.pool).await.map_err(pg_err)?;
+            rows.iter().map(|row| row_point(row, &shape, true)).collect()
+        })
+    }
+
+    /// Pages in id order; the offset is the first id of the page.
+    fn scroll<'a>(
+        &'a self,
+        collection: &'a str,
+        offset: Option<String>,
+        limit: usize,
+        filter: Option<SearchFilter>,
+        with_vectors: bool,
+    ) -> StoreFuture<'a, ScrollPage> {
+        Box::pin(async move {
+            let shape = self.existing(collection).await?;
+            let mut qb = QueryBuilder::<Postgres>::new(format!(
+                "SELECT {} FROM {} WHERE TRUE",
+                select_list(&shape, with_vectors),
+                table(collection)
+            ));
+            if let Some(offset) = offset {
+                qb.push(" AND id >= ").push_bind(offset);
+            }
+            if let Some(filter) = filter.as_ref().filter(|f| vector_store::has_conditions(f)) {
+                qb.push(" AND ");
+                push_filter(&mut qb, filter);
+            }
+            qb.push(" ORDER BY id LIMIT ").push_bind(limit as i64 + 1);
+            let rows = qb.build().fetch_all(&self.pool).await.map_err(pg_err)?;
+            let mut points = rows
+                .iter()
+                .map(|row| row_point(row, &shape, with_vectors))
+                .collect::<Result<Vec<_>, String>>()?;
+            let next_offset = (points.len() > limit).then(|| points.remove(limit).id);
+            points.truncate(limit);
+            Ok(ScrollPage { points, next_offset })
+        })
+    }
+
+    fn health_check(&self) -> StoreFuture<'_, ()> {
+        Box::pin(async move { self.pool.execute("SELECT 1").await.map(|_| ()).map_err(pg_err) })
+    }
+
+    fn snapshot_to<'a>(&'a self, _collection: &'a str, _path: &'a Path) -> StoreFuture<'a, bool> {
+        Box::pin(async { Ok(false) })
+    }
+
+    fn restore_from<'a>(&'a self, _collection: &'a str, _path: &'a Path) -> StoreFuture<'a, ()> {
+        Box::pin(async { Err("the pgvector backend has no native snapshots".to_string()) })
+    }
+
+    /// VACUUM the table so deleted and replaced rows (and their index entries) are reclaimed; Postgres reports no
+    /// count.
+    fn optimize<'a>(&'a self, collection: &'a str) -> StoreFuture<'a, Option<u64>> {
+        Box::pin(async move {
+            self.existing(collection).await?;
+            let vacuum = format!("VACUUM {}", table(collection));
+            self.pool.execute(vacuum.as_str()).await.map(|_| None).map_err(pg_err)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::pagi_proto::FilterRange;
+
+    #[test]
+    fn kb_names_map_to_quoted_tables() {
+        assert_eq!(table("kb_core"), "\"pagi_kb_core\"");
+    }
+
+    #[test]
+    fn long_table_names_are_shortened_but_stay_distinct() {
+        let long = table_name(&"kb".repeat(40));
+        assert_eq!((long.len(), long.starts_with("pagi_kbkb")), (MAX_IDENT, true));
+        assert_ne!(long, table_name(&format!("{}x", "kb".repeat(40))));
+    }
+
+    #[test]
+    fn vector_names_map_to_escaped_columns() {
+        assert_eq!((column(""), column("ti\"tle")), ("\"vector\"".to_string(), "\"v:ti\"\"tle\"".to_string()));
+    }
+
+    #[test]
+    fn vectors_round_trip_through_literals() {
+        assert_eq!(literal(&[0.5, -1.0, 2.25]), "[0.5,-1,2.25]");
+        assert_eq!(parse_vector("[0.5,-1,2.25]"), [0.5, -1.0, 2.25]);
+    }
+
+    #[test]
+    fn distances_map_to_higher_is_better_scores() {
+        assert_eq!((score(Distance::Cosine, 0.25), score(Distance::Dot, -3.0)), (0.75, 3.0));
+    }
+
+    #[test]
+    fn numeric_payloads_are_stored_as_json_numbers_unless_padded() {
+        let Json(payload) = json_payload(HashMap::from([
+            ("n".to_string(), "42".to_string()),
+            ("padded".to_string(), "007".to_string()),
+        ]));
+        assert_eq!(payload, serde_json::json!({"n": 42, "padded": "007"}));
+        assert_eq!(string_payload(payload)["n"], "42");
+    }
+
+    #[test]
+    fn filters_map_to_coalesced_jsonb_predicates() {
+        let filter = SearchFilter {
+            must: vec![FilterCondition {
+                key: "kb".into(),
+                r#match: "core".into(),
+                range: None,
+            }],
+            should: vec![],
+            must_not: vec![FilterCondition {
+                key: "age".into(),
+                range: Some(FilterRange {
+                    gte: Some(1.0),
+                    lte: None,
+                }),
+                ..Default::default()
+            }],
+        };
+        let mut qb = QueryBuilder::<Postgres>::new("SELECT id FROM t WHERE ");
+        push_filter(&mut qb, &filter);
+        assert_eq!(
+            qb.sql(),
+            "SELECT id FROM t WHERE (TRUE AND COALESCE(payload -> $1 = $2, FALSE) AND NOT COALESCE(CASE WHEN \
+             jsonb_typeof(payload -> $3) = 'number' THEN (payload ->> $4)::float8 END IS NOT NULL AND CASE WHEN \
+             jsonb_typeof(payload -> $5) = 'number' THEN (payload ->> $6)::float8 END >= $7, FALSE))"
+        );
+    }
+}
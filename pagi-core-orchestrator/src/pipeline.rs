@@ -0,0 +1,235 @@
+// Declarative skill pipelines (RunPipeline): an ordered list of skills executed inside the orchestrator
+// under one reasoning_id. Step params may reference earlier observations with `{{prev}}` or
+// `{{<step name>}}` (`{{<step name>.error}}` for the error text); a failed step aborts the rest
+// unless continue_on_failure is set.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Instant;
+
+use tonic::Status;
+
+use crate::clock::Clock;
+use crate::proto::pagi_proto::{
+    ActionRequest, ActionResponse, PipelineRequest, PipelineResponse, PipelineStepResult,
+};
+use crate::reasoning_id::ReasoningId;
+
+/// PAGI_PIPELINE_MAX_STEPS (default 16).
+fn max_steps() -> usize {
+    std::env::var("PAGI_PIPELINE_MAX_STEPS")
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or(16)
+}
+
+/// Substitute `{{ref}}` placeholders from `vars`; unknown references are errors (no silent blanks).
+pub fn render(template: &str, vars: &HashMap<String, String>) -> Result<String, String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| format!("unterminated placeholder in {:?}", template))?;
+        let name = after[..end].trim();
+        let value = vars
+            .get(name)
+            .ok_or_else(|| format!("unknown pipeline reference {{{{{}}}}}", name))?;
+        out.push_str(value);
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Run `req` step by step through `exec` (the ExecuteAction dispatch path); a missing reasoning_id is generated
+/// from `clock`.
+pub async fn run<F, Fut>(req: PipelineRequest, clock: &Clock, mut exec: F) -> Result<PipelineResponse, Status>
+where
+    F: FnMut(ActionRequest) -> Fut,
+    Fut: Future<Output = Result<ActionResponse, Status>>,
+{
+    if req.steps.is_empty() {
+        return Err(Status::invalid_argument("pipeline has no steps"));
+    }
+    let limit = max_steps();
+    if req.steps.len() > limit {
+        return Err(Status::invalid_argument(format!(
+            "pipeline has {} steps; PAGI_PIPELINE_MAX_STEPS is {}",
+            req.steps.len(),
+            limit
+        )));
+    }
+    let names: Vec<String> = req
+        .steps
+        .iter()
+        .enumerate()
+        .map(|(i, s)| if s.name.is_empty() { format!("step{}", i + 1) } else { s.name.clone() })
+        .collect();
+    for (i, name) in names.iter().enumerate() {
+        if name == "prev" || names[..i].contains(name) {
+            return Err(Status::invalid_argument(format!("duplicate or reserved step name {:?}", name)));
+        }
+    }
+    let reasoning_id = if req.reasoning_id.is_empty() {
+        ReasoningId::generate(clock).to_string()
+    } else {
+        req.reasoning_id.clone()
+    };
+
+    let mut vars: HashMap<String, String> = HashMap::new();
+    let mut results = Vec::with_capacity(req.steps.len());
+    let mut aborted = false;
+    for (step, name) in req.steps.into_iter().zip(names) {
+        let mut result = PipelineStepResult {
+            name: name.clone(),
+            skill_name: step.skill_name.clone(),
+            ..Default::default()
+        };
+        if aborted {
+            result.skipped = true;
+            results.push(result);
+            continue;
+        }
+        let started = Instant::now();
+        let params: Result<HashMap<String, String>, String> = step
+            .params
+            .iter()
+            .map(|(k, v)| render(v, &vars).map(|v| (k.clone(), v)))
+            .collect();
+        let outcome = match params {
+            Ok(params) => exec(ActionRequest {
+                skill_name: step.skill_name,
+                params,
+                depth: req.depth,
+                reasoning_id: reasoning_id.clone(),
+                mock_mode: req.mock_mode,
+                allow_list_hash: req.allow_list_hash.clone(),
+                timeout_ms: step.timeout_ms,
+                caller_skills: Vec::new(),
+            })
+            .await
+            .map_err(|e| e.message().to_string()),
+            Err(e) => Err(e),
+        };
+        match outcome {
+            Ok(resp) => {
+                result.success = resp.success;
+                result.observation = resp.observation;
+                result.error = resp.error;
+            }
+            Err(e) => result.error = e,
+        }
+        result.duration_ms = started.elapsed().as_millis() as u64;
+        vars.insert("prev".to_string(), result.observation.clone());
+        vars.insert(name.clone(), result.observation.clone());
+        vars.insert(format!("{}.error", name), result.error.clone());
+        if !result.success && !req.continue_on_failure {
+            aborted = true;
+        }
+        results.push(result);
+    }
+
+    let success = results.iter().all(|r| r.success);
+    eprintln!(
+        "[Pipeline] {} {} step(s) -> {}",
+        reasoning_id,
+        results.len(),
+        if success { "ok" } else { "failed" }
+    );
+    Ok(PipelineResponse {
+        steps: results,
+        success,
+        reasoning_id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::proto::pagi_proto::PipelineStep;
+
+    fn step(name: &str, skill: &str, params: &[(&str, &str)]) -> PipelineStep {
+        PipelineStep {
+            name: name.into(),
+            skill_name: skill.into(),
+            params: params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            timeout_ms: 0,
+        }
+    }
+
+    async fn echo(req: ActionRequest) -> Result<ActionResponse, Status> {
+        if req.skill_name == "fail" {
+            return Err(Status::permission_denied("Skill not in registry"));
+        }
+        Ok(ActionResponse {
+            observation: format!("{}:{}", req.skill_name, req.params.get("path").cloned().unwrap_or_default()),
+            success: true,
+            ..Default::default()
+        })
+    }
+
+    const ID: &str = "9b2e4c1e-8f0a-4d7b-a3c2-5e6f7a8b9c0d";
+    const T0: i64 = 1_700_000_000;
+
+    async fn failing_run() -> PipelineResponse {
+        let req = PipelineRequest {
+            steps: vec![
+                step("list", "list_dir", &[("path", "src")]),
+                step("", "peek_file", &[("path", "{{list}}/{{prev}}")]),
+                step("", "fail", &[]),
+                step("", "peek_file", &[]),
+            ],
+            reasoning_id: ID.into(),
+            ..Default::default()
+        };
+        run(req, &ManualClock::at(T0).into(), echo).await.unwrap()
+    }
+
+    async fn bad_ref_run() -> PipelineResponse {
+        let req = PipelineRequest {
+            steps: vec![step("", "peek_file", &[("path", "{{missing}}")]), step("", "list_dir", &[])],
+            continue_on_failure: true,
+            ..Default::default()
+        };
+        run(req, &ManualClock::at(T0).into(), echo).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn templates_chain_named_and_previous_observations() {
+        assert_eq!(failing_run().await.steps[1].observation, "peek_file:list_dir:src/list_dir:src");
+    }
+
+    #[tokio::test]
+    async fn a_failed_step_skips_the_rest() {
+        let resp = failing_run().await;
+        assert!(!resp.success);
+        assert_eq!(resp.steps[2].error, "Skill not in registry");
+        assert!(resp.steps[3].skipped);
+    }
+
+    #[tokio::test]
+    async fn the_callers_reasoning_id_is_kept() {
+        assert_eq!(failing_run().await.reasoning_id, ID);
+    }
+
+    #[tokio::test]
+    async fn unknown_references_fail_their_step() {
+        let resp = bad_ref_run().await;
+        assert!(resp.steps[0].error.contains("unknown pipeline reference {{missing}}"));
+    }
+
+    #[tokio::test]
+    async fn continue_on_failure_runs_later_steps() {
+        assert!(bad_ref_run().await.steps[1].success);
+    }
+
+    #[tokio::test]
+    async fn missing_reasoning_ids_are_generated_as_uuid_v7() {
+        let generated: ReasoningId = bad_ref_run().await.reasoning_id.parse().unwrap();
+        assert_eq!(generated.timestamp_ms(), Some(T0 * 1000));
+    }
+}
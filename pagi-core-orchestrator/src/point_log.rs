@@ -0,0 +1,261 @@
+// Persistent local L4 index. With PAGI_LOCAL_INDEX_DIR set, the in-process HNSW store (MemoryStore) logs every
+// upsert, delete and payload update to `<collection>.log` there (one JSON line each, fsynced) and replays the
+// log when the collection is created at startup. It backs PAGI_VECTOR_BACKEND=memory and, when the dir is set,
+// PAGI_DISABLE_QDRANT, so Qdrant-less dev environments keep their KBs (and RCA its priors) across restarts.
+// A log past PAGI_LOCAL_INDEX_COMPACT_MB is rewritten as a single upsert of the live points, as is every log
+// the store optimizes (CompactMemory).
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::proto::pagi_proto::VectorPoint;
+use crate::snapshot;
+use crate::wal::{self, WalPoint};
+
+const LOG_EXT: &str = "log";
+
+/// One JSON line of a collection's log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum LogOp {
+    Upsert { points: Vec<WalPoint> },
+    Delete { ids: Vec<String> },
+    SetPayload { ids: Vec<String>, fields: HashMap<String, String> },
+}
+
+impl LogOp {
+    pub fn upsert(points: &[VectorPoint]) -> Self {
+        Self::Upsert {
+            points: points.iter().map(WalPoint::from_proto).collect(),
+        }
+    }
+}
+
+struct LogFile {
+    file: File,
+    /// Bytes in the file.
+    len: u64,
+}
+
+pub struct PointLog {
+    dir: PathBuf,
+    compact_bytes: u64,
+    /// Logs of the collections replayed so far; appends go only to these.
+    files: Mutex<HashMap<String, LogFile>>,
+}
+
+impl PointLog {
+    pub fn open(dir: &Path, compact_bytes: u64) -> Result<Self, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("create {}: {}", dir.display(), e))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            compact_bytes: compact_bytes.max(1),
+            files: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// PAGI_LOCAL_INDEX_DIR (unset or empty: no local index) and PAGI_LOCAL_INDEX_COMPACT_MB (default 64).
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let Some(dir) = std::env::var("PAGI_LOCAL_INDEX_DIR")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+        else {
+            return Ok(None);
+        };
+        let mb = std::env::var("PAGI_LOCAL_INDEX_COMPACT_MB")
+            .ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .unwrap_or(64);
+        Self::open(Path::new(&dir), mb.saturating_mul(1024 * 1024)).map(Some)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, collection: &str) -> Result<PathBuf, String> {
+        snapshot::check_collection(collection)?;
+        Ok(self.dir.join(format!("{}.{}", collection, LOG_EXT)))
+    }
+
+    /// Feed `collection`'s logged ops to `apply`, oldest first, then open the log for appends; returns the ops
+    /// replayed. A torn final line (crash mid-append) is cut off: its call never returned.
+    pub fn replay(
+        &self,
+        collection: &str,
+        mut apply: impl FnMut(LogOp) -> Result<(), String>,
+    ) -> Result<usize, String> {
+        let path = self.path(collection)?;
+        let mut raw = match std::fs::read_to_string(&path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("read {}: {}", path.display(), e)),
+        };
+        if !raw.ends_with('\n') {
+            raw.truncate(raw.rfind('\n').map_or(0, |i| i + 1));
+        }
+        let mut replayed = 0;
+        for (n, line) in raw.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            let op = serde_json::from_str(line).map_err(|e| format!("{} line {}: {}", path.display(), n + 1, e))?;
+            apply(op).map_err(|e| format!("{} line {}: {}", path.display(), n + 1, e))?;
+            replayed += 1;
+        }
+        let file = wal::open_append(&path)?;
+        file.set_len(raw.len() as u64)
+            .map_err(|e| format!("truncate {}: {}", path.display(), e))?;
+        let log = LogFile {
+            file,
+            len: raw.len() as u64,
+        };
+        self.files.lock().unwrap_or_else(|e| e.into_inner()).insert(collection.to_string(), log);
+        Ok(replayed)
+    }
+
+    /// Durably append `op` to `collection`'s log; true once the log has outgrown PAGI_LOCAL_INDEX_COMPACT_MB
+    /// (the caller then rewrites it). Collections never replayed are not logged.
+    pub fn append(&self, collection: &str, op: &LogOp) -> Result<bool, String> {
+        let mut files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(log) = files.get_mut(collection) else {
+            return Ok(false);
+        };
+        let mut line = serde_json::to_string(op).map_err(|e| e.to_string())?;
+        line.push('\n');
+        log.file
+            .write_all(line.as_bytes())
+            .and_then(|_| log.file.sync_data())
+            .map_err(|e| format!("append to {} log: {}", collection, e))?;
+        log.len += line.len() as u64;
+        Ok(log.len > self.compact_bytes)
+    }
+
+    /// Replace `collection`'s log with one upsert of `points`, its live points.
+    pub fn rewrite(&self, collection: &str, points: &[VectorPoint]) -> Result<(), String> {
+        let path = self.path(collection)?;
+        let mut body = if points.is_empty() {
+            String::new()
+        } else {
+            serde_json::to_string(&LogOp::upsert(points)).map_err(|e| e.to_string())?
+        };
+        if !body.is_empty() {
+            body.push('\n');
+        }
+        let tmp = path.with_extension(format!("{}.tmp", LOG_EXT));
+        let mut f = File::create(&tmp).map_err(|e| format!("create {}: {}", tmp.display(), e))?;
+        f.write_all(body.as_bytes())
+            .and_then(|_| f.sync_all())
+            .map_err(|e| format!("write {}: {}", tmp.display(), e))?;
+        std::fs::rename(&tmp, &path).map_err(|e| format!("rename {}: {}", tmp.display(), e))?;
+        let log = LogFile {
+            file: wal::open_append(&path)?,
+            len: body.len() as u64,
+        };
+        self.files.lock().unwrap_or_else(|e| e.into_inner()).insert(collection.to_string(), log);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("pagi_point_log_{}", uuid::Uuid::new_v4()))
+    }
+
+    fn point(id: &str) -> VectorPoint {
+        VectorPoint {
+            id: id.to_string(),
+            vector: vec![1.0, 0.0],
+            ..Default::default()
+        }
+    }
+
+    fn delete(id: &str) -> LogOp {
+        LogOp::Delete { ids: vec![id.into()] }
+    }
+
+    fn replay(log: &PointLog) -> Result<Vec<LogOp>, String> {
+        let mut ops = Vec::new();
+        log.replay("kb_core", |op| {
+            ops.push(op);
+            Ok(())
+        })
+        .map(|_| ops)
+    }
+
+    /// A `kb_core` log holding an upsert of a and b, then a delete of a.
+    fn logged(dir: &Path) -> PointLog {
+        let log = PointLog::open(dir, 1 << 20).unwrap();
+        assert!(replay(&log).unwrap().is_empty());
+        assert!(!log.append("kb_core", &LogOp::upsert(&[point("a"), point("b")])).unwrap());
+        log.append("kb_core", &delete("a")).unwrap();
+        PointLog::open(dir, 1 << 20).unwrap()
+    }
+
+    #[test]
+    fn appended_ops_replay_in_order() {
+        let dir = temp_dir();
+        let ops = replay(&logged(&dir)).unwrap();
+        assert_eq!(ops, [LogOp::upsert(&[point("a"), point("b")]), delete("a")]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn collections_never_replayed_are_not_logged() {
+        let dir = temp_dir();
+        let log = PointLog::open(&dir, 1 << 20).unwrap();
+        assert!(!log.append("kb_other", &delete("a")).unwrap());
+        assert!(!dir.join("kb_other.log").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn torn_tails_are_cut_and_appends_land_after_them() {
+        let dir = temp_dir();
+        drop(logged(&dir));
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(dir.join("kb_core.log"))
+            .unwrap()
+            .write_all(b"{\"op\":\"delete\",\"ids\":[\"b")
+            .unwrap();
+        let log = PointLog::open(&dir, 1 << 20).unwrap();
+        assert_eq!(replay(&log).unwrap().len(), 2);
+        log.append("kb_core", &delete("c")).unwrap();
+        assert_eq!(replay(&log).unwrap().last(), Some(&delete("c")));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rewrites_shrink_the_log_to_one_upsert() {
+        let dir = temp_dir();
+        let log = logged(&dir);
+        log.rewrite("kb_core", &[point("b")]).unwrap();
+        assert_eq!(replay(&log).unwrap(), [LogOp::upsert(&[point("b")])]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn appends_past_the_threshold_ask_for_a_rewrite() {
+        let dir = temp_dir();
+        drop(logged(&dir));
+        let small = PointLog::open(&dir, 1).unwrap();
+        replay(&small).unwrap();
+        assert!(small.append("kb_core", &delete("c")).unwrap());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn collection_names_cannot_escape_the_dir() {
+        let dir = temp_dir();
+        let log = PointLog::open(&dir, 1 << 20).unwrap();
+        assert!(log.replay("../kb_core", |_| Ok(())).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
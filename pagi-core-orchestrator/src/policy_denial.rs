@@ -0,0 +1,49 @@
+// Typed governor denials. Recursion depth, fan-out, param size, HITL gates, the heal budget, the allow-list and
+// skill manifest paths fail with a PolicyDenial in Status.details (the mechanism AllowListMismatch and BadRequest
+// already use): rule id, what was denied, the setting behind the limit, current usage against it, when a retry
+// can pass and where an approval would come from. Clients decode it to build their UX; the message stays readable.
+
+use prost::Message;
+use tonic::{Code, Status};
+
+use crate::proto::pagi_proto::PolicyDenial;
+
+/// Reviewer stream that answers HITL prompts.
+pub const HITL_CHANNEL: &str = "HitlChannel";
+
+/// `code` with `message` and `detail` encoded as the status details.
+pub fn status(code: Code, message: impl Into<String>, detail: PolicyDenial) -> Status {
+    Status::with_details(code, message, detail.encode_to_vec().into())
+}
+
+/// The denial a status carries; None for statuses without one.
+#[allow(dead_code)]
+pub fn of(status: &Status) -> Option<PolicyDenial> {
+    PolicyDenial::decode(status.details()).ok().filter(|d| !d.rule.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn denials_round_trip_through_status_details() {
+        let denied = status(
+            Code::ResourceExhausted,
+            "heal proposal cap reached",
+            PolicyDenial {
+                rule: "heal_rate".into(),
+                subject: "rust_core".into(),
+                setting: "PAGI_HEAL_MAX_PROPOSALS_PER_HOUR".into(),
+                current: 20.0,
+                limit: 20.0,
+                retry_after_secs: 90,
+                ..Default::default()
+            },
+        );
+        assert_eq!(denied.message(), "heal proposal cap reached");
+        let detail = of(&denied).unwrap();
+        assert_eq!((detail.rule.as_str(), detail.retry_after_secs, detail.limit), ("heal_rate", 90, 20.0));
+        assert_eq!(of(&Status::permission_denied("plain")), None);
+    }
+}
@@ -0,0 +1,201 @@
+// Self-profiling for CaptureProfile: samples the orchestrator's own threads (pprof-rs, SIGPROF) for a
+// bounded window and returns the CPU profile as pprof protobuf (`go tool pprof`, Pyroscope ingest) plus an
+// optional flamegraph SVG. Allocation data is totals only: with the feature on, a counting global allocator
+// reports bytes/allocations made during the window. Needs the `profiling` feature, else UNIMPLEMENTED.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tonic::Status;
+
+use crate::proto::pagi_proto::{ProfileRequest, ProfileResponse};
+
+const DEFAULT_SECS: u32 = 10;
+const DEFAULT_HZ: u32 = 99;
+const MAX_HZ: u32 = 1000;
+
+/// The SIGPROF profiler is process-wide: one capture at a time.
+static BUSY: AtomicBool = AtomicBool::new(false);
+
+/// PAGI_PROFILE_MAX_SECS: longest window a caller may request (default 60).
+fn max_secs_from_env() -> u32 {
+    std::env::var("PAGI_PROFILE_MAX_SECS")
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .filter(|&s: &u32| s > 0)
+        .unwrap_or(60)
+}
+
+/// Sampling window and frequency for a request; zeros take the defaults.
+pub fn window(req: &ProfileRequest, max_secs: u32) -> Result<(Duration, i32), Status> {
+    let secs = if req.seconds == 0 { DEFAULT_SECS.min(max_secs) } else { req.seconds };
+    if secs > max_secs {
+        return Err(Status::invalid_argument(format!(
+            "seconds {} exceeds PAGI_PROFILE_MAX_SECS ({})",
+            secs, max_secs
+        )));
+    }
+    let hz = if req.frequency_hz == 0 { DEFAULT_HZ } else { req.frequency_hz };
+    if hz > MAX_HZ {
+        return Err(Status::invalid_argument(format!("frequency_hz {} exceeds {}", hz, MAX_HZ)));
+    }
+    Ok((Duration::from_secs(secs as u64), hz as i32))
+}
+
+/// Releases BUSY when the capture ends (or its thread panics).
+struct Busy;
+
+impl Drop for Busy {
+    fn drop(&mut self) {
+        BUSY.store(false, Ordering::Release);
+    }
+}
+
+/// Stops the sampling loop early when the RPC is dropped (AbortRequest, client gone).
+struct StopOnDrop(Arc<AtomicBool>);
+
+impl Drop for StopOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Sample the process for the requested window on a blocking thread.
+pub async fn capture(req: ProfileRequest) -> Result<ProfileResponse, Status> {
+    let (window, hz) = window(&req, max_secs_from_env())?;
+    if BUSY.swap(true, Ordering::AcqRel) {
+        return Err(Status::failed_precondition("a profile capture is already running"));
+    }
+    let busy = Busy;
+    let stop = Arc::new(AtomicBool::new(false));
+    let _stop_on_drop = StopOnDrop(Arc::clone(&stop));
+    eprintln!("[Profiler] capturing {}s at {} Hz", window.as_secs(), hz);
+    tokio::task::spawn_blocking(move || {
+        let _busy = busy;
+        sample(window, hz, req.flamegraph, &stop)
+    })
+    .await
+    .map_err(|e| Status::internal(format!("profile capture: {}", e)))?
+}
+
+#[cfg(feature = "profiling")]
+fn sample(window: Duration, hz: i32, flamegraph: bool, stop: &AtomicBool) -> Result<ProfileResponse, Status> {
+    use pprof::protos::Message;
+    use std::time::Instant;
+
+    const POLL: Duration = Duration::from_millis(100);
+
+    let started = Instant::now();
+    let guard = pprof::ProfilerGuardBuilder::default()
+        .frequency(hz)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()
+        .map_err(|e| Status::internal(format!("start profiler: {}", e)))?;
+    let allocs_before = alloc_counter::snapshot();
+    while started.elapsed() < window && !stop.load(Ordering::Relaxed) {
+        std::thread::sleep(POLL.min(window.saturating_sub(started.elapsed())));
+    }
+    let (alloc_bytes, allocations) = alloc_counter::since(allocs_before);
+    let report = guard
+        .report()
+        .build()
+        .map_err(|e| Status::internal(format!("build profile: {}", e)))?;
+    let samples = report.data.values().map(|&n| n.max(0) as u64).sum();
+    let pprof = report
+        .pprof()
+        .map_err(|e| Status::internal(format!("encode profile: {}", e)))?
+        .encode_to_vec();
+    let mut flamegraph_svg = Vec::new();
+    if flamegraph {
+        report
+            .flamegraph(&mut flamegraph_svg)
+            .map_err(|e| Status::internal(format!("render flamegraph: {}", e)))?;
+    }
+    Ok(ProfileResponse {
+        pprof,
+        flamegraph_svg,
+        samples,
+        alloc_bytes,
+        allocations,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(not(feature = "profiling"))]
+fn sample(_window: Duration, _hz: i32, _flamegraph: bool, _stop: &AtomicBool) -> Result<ProfileResponse, Status> {
+    Err(Status::unimplemented(
+        "CaptureProfile needs an orchestrator built with the profiling feature",
+    ))
+}
+
+#[cfg(feature = "profiling")]
+#[global_allocator]
+static ALLOCATOR: alloc_counter::Counting = alloc_counter::Counting;
+
+#[cfg(feature = "profiling")]
+mod alloc_counter {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static BYTES: AtomicU64 = AtomicU64::new(0);
+    static COUNT: AtomicU64 = AtomicU64::new(0);
+
+    /// System allocator that counts allocated bytes and calls (reallocs count their new size).
+    pub struct Counting;
+
+    fn record(size: usize) {
+        BYTES.fetch_add(size as u64, Ordering::Relaxed);
+        COUNT.fetch_add(1, Ordering::Relaxed);
+    }
+
+    unsafe impl GlobalAlloc for Counting {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            record(layout.size());
+            System.alloc(layout)
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            record(layout.size());
+            System.alloc_zeroed(layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            record(new_size);
+            System.realloc(ptr, layout, new_size)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    pub fn snapshot() -> (u64, u64) {
+        (BYTES.load(Ordering::Relaxed), COUNT.load(Ordering::Relaxed))
+    }
+
+    /// (bytes, allocations) since an earlier snapshot.
+    pub fn since(before: (u64, u64)) -> (u64, u64) {
+        let (bytes, count) = snapshot();
+        (bytes.saturating_sub(before.0), count.saturating_sub(before.1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_defaults_and_bounds() {
+        let req = |seconds, frequency_hz| ProfileRequest {
+            seconds,
+            frequency_hz,
+            ..Default::default()
+        };
+        assert_eq!(window(&req(0, 0), 60).unwrap(), (Duration::from_secs(10), 99));
+        assert_eq!(window(&req(0, 0), 5).unwrap().0, Duration::from_secs(5), "default capped by the max");
+        assert_eq!(window(&req(30, 250), 60).unwrap(), (Duration::from_secs(30), 250));
+        assert!(window(&req(61, 0), 60).is_err());
+        assert!(window(&req(1, 5000), 60).is_err());
+    }
+}
@@ -0,0 +1,232 @@
+// Action provenance: after a successful real action, embed a short record (skill, params, observation,
+// reasoning_id) and upsert it into a dedicated L4 KB so RCA/planning can recall prior agent actions.
+// Opt-in per skill tier via PAGI_PROVENANCE_TIERS; upserts run in the background and never fail the action.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::memory_manager::MemoryManager;
+use crate::proto::pagi_proto::{UpsertRequest, VectorPoint};
+
+/// Max chars kept per summarized field.
+const SUMMARY_CHARS: usize = 400;
+
+/// Coarse side-effect tier of an allow-listed skill.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SkillTier {
+    /// Read-only inspection (peek_file, list_dir, search_codebase, ...).
+    Read,
+    /// Writes files or skills.
+    Write,
+    /// Runs code or other skills; also the default for unknown skills.
+    Exec,
+}
+
+impl SkillTier {
+    pub fn of(skill: &str) -> Self {
+        match skill {
+            "peek_file" | "list_dir" | "read_entire_file_safe" | "list_files_recursive"
+            | "analyze_code" | "search_codebase" => SkillTier::Read,
+            "save_skill" | "write_file_safe" => SkillTier::Write,
+            _ => SkillTier::Exec,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SkillTier::Read => "read",
+            SkillTier::Write => "write",
+            SkillTier::Exec => "exec",
+        }
+    }
+}
+
+/// Skill tiers from `var`: comma list of read/write/exec, or "all"; unset or empty is none.
+pub fn tiers_from_env(var: &str) -> Vec<SkillTier> {
+    let raw = std::env::var(var).unwrap_or_default();
+    let mut tiers = Vec::new();
+    for part in raw.split(',').map(|s| s.trim().to_lowercase()) {
+        let add: &[SkillTier] = match part.as_str() {
+            "all" => &[SkillTier::Read, SkillTier::Write, SkillTier::Exec],
+            "read" => &[SkillTier::Read],
+            "write" => &[SkillTier::Write],
+            "exec" => &[SkillTier::Exec],
+            "" => &[],
+            other => {
+                eprintln!("[Provenance] ignoring unknown tier {:?} in {}", other, var);
+                &[]
+            }
+        };
+        for t in add {
+            if !tiers.contains(t) {
+                tiers.push(*t);
+            }
+        }
+    }
+    tiers
+}
+
+#[derive(Debug, Clone)]
+pub struct ProvenanceConfig {
+    /// Tiers recorded (PAGI_PROVENANCE_TIERS: comma list of read/write/exec, or "all"; empty disables).
+    pub tiers: Vec<SkillTier>,
+    /// Target collection (PAGI_PROVENANCE_KB, default kb_provenance).
+    pub kb_name: String,
+}
+
+impl ProvenanceConfig {
+    pub fn from_env() -> Self {
+        let tiers = tiers_from_env("PAGI_PROVENANCE_TIERS");
+        let kb_name = std::env::var("PAGI_PROVENANCE_KB")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "kb_provenance".to_string());
+        Self { tiers, kb_name }
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.tiers.is_empty()
+    }
+
+    pub fn records(&self, skill: &str) -> bool {
+        self.tiers.contains(&SkillTier::of(skill))
+    }
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    match s.char_indices().nth(max) {
+        Some((i, _)) => format!("{}…", &s[..i]),
+        None => s.to_string(),
+    }
+}
+
+/// Params as sorted `k=v` pairs, each value truncated.
+pub fn summarize_params(params: &HashMap<String, String>) -> String {
+    let mut keys: Vec<&String> = params.keys().collect();
+    keys.sort();
+    let pairs: Vec<String> = keys
+        .into_iter()
+        .map(|k| format!("{}={}", k, truncate(&params[k], 80)))
+        .collect();
+    truncate(&pairs.join(", "), SUMMARY_CHARS)
+}
+
+/// Provenance is written from Rust without a model, so the provenance KB is queried with this same embedder.
+pub use crate::embedder::hash_embed;
+
+/// Build the provenance point for one action.
+pub fn build_point(
+    skill: &str,
+    params: &HashMap<String, String>,
+    observation: &str,
+    reasoning_id: &str,
+    dim: usize,
+) -> VectorPoint {
+    let params_summary = summarize_params(params);
+    let observation_summary = truncate(observation.trim(), SUMMARY_CHARS);
+    let content = format!("{}({}) -> {}", skill, params_summary, observation_summary);
+    let payload = HashMap::from([
+        ("skill".to_string(), skill.to_string()),
+        ("tier".to_string(), SkillTier::of(skill).as_str().to_string()),
+        ("params_summary".to_string(), params_summary),
+        ("observation_summary".to_string(), observation_summary),
+        ("reasoning_id".to_string(), reasoning_id.to_string()),
+        ("at".to_string(), chrono::Utc::now().timestamp().to_string()),
+        ("content".to_string(), content.clone()),
+    ]);
+    VectorPoint {
+        id: Uuid::new_v4().to_string(),
+        vector: hash_embed(&content, dim),
+        payload,
+        ..Default::default()
+    }
+}
+
+/// Spawn a best-effort upsert of the action's provenance record when its tier is enabled.
+pub fn record_action(
+    memory: &Arc<MemoryManager>,
+    config: &ProvenanceConfig,
+    skill: &str,
+    params: &HashMap<String, String>,
+    observation: &str,
+    reasoning_id: &str,
+) {
+    if !config.records(skill) || !memory.l4_enabled() {
+        return;
+    }
+    let point = build_point(skill, params, observation, reasoning_id, memory.kb_dim(&config.kb_name));
+    let memory = Arc::clone(memory);
+    let kb_name = config.kb_name.clone();
+    tokio::spawn(async move {
+        if let Err(e) = memory.ensure_kb(&kb_name).await {
+            eprintln!("[Provenance] create {}: {}", kb_name, e);
+            return;
+        }
+        let req = UpsertRequest {
+            kb_name: kb_name.clone(),
+            points: vec![point],
+            ..Default::default()
+        };
+        if let Err(e) = memory.upsert_vectors(req).await {
+            eprintln!("[Provenance] upsert {}: {}", kb_name, e.message());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_point() -> VectorPoint {
+        let params = HashMap::from([
+            ("path".to_string(), "README.md".to_string()),
+            ("content".to_string(), "x".repeat(500)),
+        ]);
+        build_point("write_file_safe", &params, "  wrote 500 bytes \n", "r-1", 64)
+    }
+
+    #[tokio::test]
+    async fn configured_tiers_select_recorded_skills() {
+        let _g = crate::watchdog::tests::lock_test_env().await;
+        std::env::set_var("PAGI_PROVENANCE_TIERS", "write, exec");
+        let cfg = ProvenanceConfig::from_env();
+        std::env::remove_var("PAGI_PROVENANCE_TIERS");
+        assert!(cfg.enabled());
+        assert!(!cfg.records("peek_file"));
+        assert!(cfg.records("write_file_safe"));
+        assert!(cfg.records("some_new_skill"), "unknown skills count as exec");
+        assert_eq!(cfg.kb_name, "kb_provenance");
+    }
+
+    #[tokio::test]
+    async fn provenance_is_disabled_without_tiers() {
+        let _g = crate::watchdog::tests::lock_test_env().await;
+        std::env::remove_var("PAGI_PROVENANCE_TIERS");
+        assert!(!ProvenanceConfig::from_env().enabled());
+    }
+
+    #[test]
+    fn points_carry_tier_reasoning_id_and_trimmed_observation() {
+        let p = write_point();
+        assert_eq!(p.vector.len(), 64);
+        assert_eq!(p.payload["tier"], "write");
+        assert_eq!(p.payload["reasoning_id"], "r-1");
+        assert_eq!(p.payload["observation_summary"], "wrote 500 bytes");
+    }
+
+    #[test]
+    fn params_summaries_are_truncated() {
+        let p = write_point();
+        let summary = &p.payload["params_summary"];
+        assert!(summary.starts_with("content=xxx"));
+        assert!(summary.len() < 200);
+    }
+
+    #[test]
+    fn hash_embedding_ignores_case() {
+        assert_eq!(hash_embed("read README", 64), hash_embed("read readme", 64));
+    }
+}
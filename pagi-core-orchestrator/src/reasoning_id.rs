@@ -0,0 +1,215 @@
+// Typed reasoning ids. A reasoning_id is a UUID root in canonical form (lowercase, hyphenated), optionally
+// followed by child segments: "<uuid>/<label>/<label>". Ids the orchestrator generates are UUIDv7, whose first
+// 48 bits are the unix-ms creation time, so they sort by creation and carry their own timestamp; clients may send
+// any UUID (the bridge uses v4). Request fields annotated `@validate(reasoning_id)` reject anything else, and the
+// reasoning entry points (ExecuteAction, DelegateRLM, RunPipeline, ProposePatch) generate one when it is empty and
+// echo it in the `x-reasoning-id` response header. Child ids (`child`) scope sub-steps under their parent, and
+// TraceQuery with include_descendants gathers a whole tree.
+
+use std::fmt;
+
+use tonic::{Response, Status};
+use uuid::Uuid;
+
+use crate::clock::Clock;
+
+/// Response header carrying the reasoning_id a request ran under.
+pub const HEADER: &str = "x-reasoning-id";
+/// Longest accepted id, segments included.
+const MAX_LEN: usize = 256;
+/// Longest child label.
+const MAX_LABEL: usize = 64;
+/// Separator between an id and its child labels.
+const SEP: char = '/';
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ReasoningId(String);
+
+fn check_label(label: &str) -> Result<(), String> {
+    if label.is_empty() || label.len() > MAX_LABEL {
+        return Err(format!("child label must be 1-{} characters (got {:?})", MAX_LABEL, label));
+    }
+    if !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.') {
+        return Err(format!("child label may only hold [A-Za-z0-9._-] (got {:?})", label));
+    }
+    Ok(())
+}
+
+impl ReasoningId {
+    /// A fresh UUIDv7 id created at `unix_ms`.
+    pub fn generate_at(unix_ms: i64) -> Self {
+        let mut bytes = *Uuid::new_v4().as_bytes();
+        bytes[..6].copy_from_slice(&(unix_ms.max(0) as u64).to_be_bytes()[2..]);
+        bytes[6] = (bytes[6] & 0x0F) | 0x70;
+        bytes[8] = (bytes[8] & 0x3F) | 0x80;
+        Self(Uuid::from_bytes(bytes).to_string())
+    }
+
+    /// A fresh UUIDv7 id created now by `clock`.
+    pub fn generate(clock: &Clock) -> Self {
+        Self::generate_at(clock.now_ms())
+    }
+
+    /// A request's id: parsed when given, else generated from `clock` and written back to `field`.
+    pub fn resolve(field: &mut String, clock: &Clock) -> Result<Self, Status> {
+        if field.is_empty() {
+            let id = Self::generate(clock);
+            field.push_str(id.as_str());
+            return Ok(id);
+        }
+        field.parse().map_err(|e| Status::invalid_argument(format!("reasoning_id: {}", e)))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The UUID the id (or its ancestors) started from.
+    pub fn root(&self) -> &str {
+        self.0.split(SEP).next().unwrap_or(&self.0)
+    }
+
+    /// Creation time (unix ms) of a UUIDv7 root; None for other UUID versions.
+    pub fn timestamp_ms(&self) -> Option<i64> {
+        let uuid = Uuid::parse_str(self.root()).ok()?;
+        let bytes = uuid.as_bytes();
+        if bytes[6] >> 4 != 7 {
+            return None;
+        }
+        let mut ms = [0u8; 8];
+        ms[2..].copy_from_slice(&bytes[..6]);
+        Some(u64::from_be_bytes(ms) as i64)
+    }
+
+    /// The id of a sub-step `label` ([A-Za-z0-9._-], at most 64 chars) of this one.
+    pub fn child(&self, label: &str) -> Result<Self, String> {
+        check_label(label)?;
+        let id = format!("{}{}{}", self.0, SEP, label);
+        if id.len() > MAX_LEN {
+            return Err(format!("child id would exceed {} characters", MAX_LEN));
+        }
+        Ok(Self(id))
+    }
+
+    /// The id this one is a child of; None for a root.
+    pub fn parent(&self) -> Option<Self> {
+        self.0.rsplit_once(SEP).map(|(parent, _)| Self(parent.to_string()))
+    }
+
+    /// Whether `other` is a strict descendant of this id.
+    pub fn is_ancestor_of(&self, other: &str) -> bool {
+        other.len() > self.0.len() + 1 && other.starts_with(&self.0) && other[self.0.len()..].starts_with(SEP)
+    }
+
+    /// `resp` with this id in its `x-reasoning-id` header.
+    pub fn tag<T>(&self, mut resp: Response<T>) -> Response<T> {
+        if let Ok(value) = self.0.parse() {
+            resp.metadata_mut().insert(HEADER, value);
+        }
+        resp
+    }
+}
+
+impl std::str::FromStr for ReasoningId {
+    type Err = String;
+
+    fn from_str(raw: &str) -> Result<Self, String> {
+        if raw.len() > MAX_LEN {
+            return Err(format!("must be at most {} characters (got {})", MAX_LEN, raw.len()));
+        }
+        let mut parts = raw.split(SEP);
+        let root = parts.next().unwrap_or_default();
+        match Uuid::parse_str(root) {
+            Ok(uuid) if uuid.to_string() == root => {}
+            _ => return Err(format!("must start with a lowercase hyphenated UUID (got {:?})", raw)),
+        }
+        parts.try_for_each(check_label)?;
+        Ok(Self(raw.to_string()))
+    }
+}
+
+impl fmt::Display for ReasoningId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    const T0: i64 = 1_700_000_000_000;
+
+    #[test]
+    fn generated_ids_sort_by_creation_time() {
+        let earlier = ReasoningId::generate_at(T0);
+        assert!(earlier < ReasoningId::generate_at(T0 + 1));
+        assert_eq!(earlier.timestamp_ms(), Some(T0));
+    }
+
+    #[test]
+    fn generated_ids_are_rfc_v7_uuids_that_round_trip() {
+        let id = ReasoningId::generate_at(T0);
+        let uuid = Uuid::parse_str(id.as_str()).unwrap();
+        assert_eq!((uuid.get_version_num(), uuid.as_bytes()[8] >> 6), (7, 0b10));
+        assert_eq!(id.as_str().parse::<ReasoningId>().unwrap(), id);
+    }
+
+    #[test]
+    fn v4_roots_parse_without_a_timestamp() {
+        let v4: ReasoningId = "9b2e4c1e-8f0a-4d7b-a3c2-5e6f7a8b9c0d".parse().unwrap();
+        assert_eq!(v4.timestamp_ms(), None, "only v7 roots carry a timestamp");
+    }
+
+    #[test]
+    fn malformed_roots_are_rejected() {
+        for bad in ["r1", "9B2E4C1E-8F0A-4D7B-A3C2-5E6F7A8B9C0D", "9b2e4c1e8f0a4d7ba3c25e6f7a8b9c0d", ""] {
+            assert!(bad.parse::<ReasoningId>().is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn children_nest_under_their_root_and_round_trip() {
+        let root = ReasoningId::generate_at(T0);
+        let step = root.child("step-1").unwrap().child("rlm.2").unwrap();
+        assert_eq!(step.as_str(), format!("{}/step-1/rlm.2", root));
+        assert_eq!((step.root(), step.timestamp_ms()), (root.as_str(), Some(T0)));
+        assert_eq!(step.as_str().parse::<ReasoningId>().unwrap(), step);
+        assert_eq!(step.parent().unwrap().parent(), Some(root));
+    }
+
+    #[test]
+    fn ancestry_matches_whole_segments_only() {
+        let root = ReasoningId::generate_at(T0);
+        let step = root.child("step-1").unwrap();
+        assert!(root.is_ancestor_of(step.as_str()));
+        assert!(!root.is_ancestor_of(root.as_str()));
+        assert!(!step.is_ancestor_of(&format!("{}x", step)));
+    }
+
+    #[test]
+    fn empty_or_slashed_segments_are_rejected() {
+        let root = ReasoningId::generate_at(T0);
+        assert!(root.child("a/b").is_err() && root.child("").is_err());
+        assert!(format!("{}//x", root).parse::<ReasoningId>().is_err());
+    }
+
+    #[test]
+    fn tagged_responses_carry_the_header() {
+        let id = ReasoningId::generate_at(T0);
+        let resp = id.tag(Response::new(()));
+        assert_eq!(resp.metadata().get(HEADER).unwrap().to_str().unwrap(), id.as_str());
+    }
+
+    #[test]
+    fn resolve_writes_generated_ids_back_and_rejects_bad_ones() {
+        let clock: Clock = ManualClock::at(T0 / 1000).into();
+        let mut field = String::new();
+        let generated = ReasoningId::resolve(&mut field, &clock).unwrap();
+        assert_eq!(generated.timestamp_ms(), Some(T0), "generated ids are stamped by the given clock");
+        assert_eq!(field, generated.as_str(), "generated ids are written back to the request");
+        let bad = ReasoningId::resolve(&mut "r1".to_string(), &clock).unwrap_err();
+        assert_eq!(bad.code(), tonic::Code::InvalidArgument);
+    }
+}
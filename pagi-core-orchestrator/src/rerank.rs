@@ -0,0 +1,197 @@
+// Reranking of the L4 candidate window after vector search (SearchRequest.rerank). "mmr" (maximal marginal
+// relevance) runs here: hits are picked greedily by λ × relevance − (1 − λ) × their highest cosine similarity to the
+// hits already picked, so near-duplicates of a better hit sink (scores are left as ranked). "cross_encoder" sends
+// the query and each hit's content to the bridge's POST /rerank (PAGI_RERANK_URL), whose cross-encoder scores
+// become the hit scores.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::Deserialize;
+use tonic::Status;
+
+use crate::vector_store::ScoredPoint;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rerank {
+    /// Diversity-aware reordering; λ weighs relevance against similarity to the hits already picked.
+    Mmr { lambda: f32 },
+    CrossEncoder,
+}
+
+impl Rerank {
+    /// SearchRequest.rerank: "" (none), "mmr" or "cross_encoder"; `lambda` from mmr_lambda, else `default_lambda`.
+    pub fn parse(raw: &str, lambda: Option<f32>, default_lambda: f32) -> Result<Option<Self>, Status> {
+        match raw.trim().to_lowercase().as_str() {
+            "" | "none" => Ok(None),
+            "mmr" => Ok(Some(Rerank::Mmr {
+                lambda: lambda.unwrap_or(default_lambda).clamp(0.0, 1.0),
+            })),
+            "cross_encoder" => Ok(Some(Rerank::CrossEncoder)),
+            other => Err(Status::invalid_argument(format!(
+                "unknown rerank {:?} (expected mmr or cross_encoder)",
+                other
+            ))),
+        }
+    }
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denom = norm(a) * norm(b);
+    if denom > 0.0 {
+        dot / denom
+    } else {
+        0.0
+    }
+}
+
+/// MMR order of `points` (best first). Relevance is the score scaled to 1.0 for the best hit and 0.0 for the
+/// worst, so similarities and distances both work; hits missing from `vectors` count as unlike every other.
+pub fn mmr(points: Vec<ScoredPoint>, vectors: &HashMap<String, Vec<f32>>, lambda: f32) -> Vec<ScoredPoint> {
+    let (best, worst) = match (points.first(), points.last()) {
+        (Some(first), Some(last)) => (first.score, last.score),
+        _ => return points,
+    };
+    let relevance: Vec<f32> = points
+        .iter()
+        .map(|p| if best != worst { (p.score - worst) / (best - worst) } else { 1.0 })
+        .collect();
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut picked: Vec<usize> = Vec::with_capacity(points.len());
+    while !remaining.is_empty() {
+        let marginal = |i: usize| {
+            let redundancy = picked
+                .iter()
+                .filter_map(|&j| Some(cosine(vectors.get(&points[i].id)?, vectors.get(&points[j].id)?)))
+                .fold(0.0f32, f32::max);
+            lambda * relevance[i] - (1.0 - lambda) * redundancy
+        };
+        // Ties go to the better-ranked hit.
+        let (at, _) = remaining
+            .iter()
+            .enumerate()
+            .map(|(at, &i)| (at, marginal(i)))
+            .fold((0, f32::NEG_INFINITY), |acc, (at, m)| if m > acc.1 { (at, m) } else { acc });
+        picked.push(remaining.remove(at));
+    }
+    let mut slots: Vec<Option<ScoredPoint>> = points.into_iter().map(Some).collect();
+    picked.into_iter().filter_map(|i| slots[i].take()).collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct RerankScores {
+    scores: Vec<f32>,
+}
+
+/// Client for the bridge cross-encoder.
+#[derive(Default)]
+pub struct CrossEncoder {
+    /// None: cross_encoder reranks fail with FAILED_PRECONDITION.
+    url: Option<String>,
+    http: reqwest::Client,
+}
+
+impl CrossEncoder {
+    /// PAGI_RERANK_URL (e.g. http://127.0.0.1:8000/rerank) with PAGI_RERANK_TIMEOUT_SECS per call (default 30).
+    pub fn from_env() -> Self {
+        let url = std::env::var("PAGI_RERANK_URL")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        let timeout = std::env::var("PAGI_RERANK_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or(30);
+        Self {
+            url,
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(timeout))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn configured(&self) -> bool {
+        self.url.is_some()
+    }
+
+    /// Hits rescored by the cross-encoder against `query` and sorted by that score; `text` picks each hit's
+    /// document text.
+    pub async fn rerank(
+        &self,
+        query: &str,
+        mut points: Vec<ScoredPoint>,
+        text: impl Fn(&ScoredPoint) -> String,
+    ) -> Result<Vec<ScoredPoint>, Status> {
+        let url = self
+            .url
+            .as_deref()
+            .ok_or_else(|| Status::failed_precondition("cross_encoder rerank needs PAGI_RERANK_URL"))?;
+        if points.is_empty() {
+            return Ok(points);
+        }
+        let documents: Vec<String> = points.iter().map(text).collect();
+        let resp = self
+            .http
+            .post(url)
+            .json(&serde_json::json!({ "query": query, "documents": documents }))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| Status::unavailable(format!("rerank {}: {}", url, e)))?;
+        let RerankScores { scores } = resp
+            .json()
+            .await
+            .map_err(|e| Status::internal(format!("rerank {}: unreadable scores: {}", url, e)))?;
+        if scores.len() != points.len() {
+            return Err(Status::internal(format!(
+                "rerank {}: {} score(s) for {} document(s)",
+                url,
+                scores.len(),
+                points.len()
+            )));
+        }
+        for (p, score) in points.iter_mut().zip(scores) {
+            p.score = score;
+        }
+        points.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Ok(points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mmr_sinks_near_duplicates_of_better_hits() {
+        let hit = |id: &str, score: f32| ScoredPoint {
+            id: id.to_string(),
+            score,
+            payload: HashMap::new(),
+        };
+        let points = vec![hit("a", 0.95), hit("a_copy", 0.94), hit("b", 0.90), hit("c", 0.50)];
+        let vectors = HashMap::from([
+            ("a".to_string(), vec![1.0, 0.0, 0.0]),
+            ("a_copy".to_string(), vec![1.0, 0.01, 0.0]),
+            ("b".to_string(), vec![0.0, 1.0, 0.0]),
+            ("c".to_string(), vec![0.0, 0.0, 1.0]),
+        ]);
+        let order = |lambda: f32| -> Vec<String> {
+            mmr(points.clone(), &vectors, lambda).into_iter().map(|p| p.id).collect()
+        };
+        assert_eq!(order(1.0), ["a", "a_copy", "b", "c"], "λ = 1 keeps the relevance order");
+        assert_eq!(order(0.5), ["a", "b", "c", "a_copy"]);
+        assert_eq!(mmr(points.clone(), &vectors, 0.5)[3].score, 0.94, "scores are left as ranked");
+
+        assert_eq!(Rerank::parse("", None, 0.7).unwrap(), None);
+        assert_eq!(Rerank::parse("MMR", None, 0.7).unwrap(), Some(Rerank::Mmr { lambda: 0.7 }));
+        assert_eq!(Rerank::parse("bm25", None, 0.7).unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+}
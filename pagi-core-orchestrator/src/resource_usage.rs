@@ -0,0 +1,145 @@
+// Per-invocation resource usage for skill subprocesses: wall time always; CPU time and peak RSS by
+// sampling /proc/<pid> while the child runs (Linux only — elsewhere they are reported as unknown).
+// Samples miss at most the final poll interval of CPU time; VmHWM is the kernel's own high-water mark.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Kernel USER_HZ; 100 on every mainstream Linux configuration.
+const CLOCK_TICKS_PER_SEC: u64 = 100;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProcSample {
+    /// utime + stime, in milliseconds.
+    pub cpu_ms: u64,
+    /// Peak resident set size (VmHWM), in KiB.
+    pub max_rss_kb: u64,
+}
+
+/// Read CPU time and peak RSS of a live process; None when /proc is unavailable or the pid is gone.
+pub fn read_proc(pid: u32) -> Option<ProcSample> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // Fields after the parenthesised comm (which may contain spaces); utime/stime are fields 14/15.
+    let after_comm = &stat[stat.rfind(')')? + 1..];
+    let fields: Vec<&str> = after_comm.split_whitespace().collect();
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    let max_rss_kb = status
+        .lines()
+        .find_map(|l| l.strip_prefix("VmHWM:"))
+        .and_then(|v| v.trim().trim_end_matches("kB").trim().parse().ok())
+        .unwrap_or(0);
+    Some(ProcSample {
+        cpu_ms: (utime + stime) * 1000 / CLOCK_TICKS_PER_SEC,
+        max_rss_kb,
+    })
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceUsage {
+    pub wall_ms: u64,
+    pub cpu_ms: Option<u64>,
+    pub max_rss_kb: Option<u64>,
+}
+
+impl ResourceUsage {
+    /// Usage from the first and last /proc samples of a process (for pooled workers: before/after a call).
+    pub fn from_samples(wall: Duration, before: Option<ProcSample>, last: Option<ProcSample>) -> Self {
+        Self {
+            wall_ms: wall.as_millis() as u64,
+            cpu_ms: last.map(|l| l.cpu_ms.saturating_sub(before.map_or(0, |b| b.cpu_ms))),
+            max_rss_kb: last.map(|l| l.max_rss_kb).filter(|kb| *kb > 0),
+        }
+    }
+
+    /// ActionResponse.metadata entries (keys prefixed `usage.`); unknown values are omitted.
+    pub fn to_metadata(self) -> HashMap<String, String> {
+        let mut meta = HashMap::from([("usage.wall_ms".to_string(), self.wall_ms.to_string())]);
+        if let Some(cpu) = self.cpu_ms {
+            meta.insert("usage.cpu_ms".to_string(), cpu.to_string());
+        }
+        if let Some(rss) = self.max_rss_kb {
+            meta.insert("usage.max_rss_kb".to_string(), rss.to_string());
+        }
+        meta
+    }
+
+    /// Compact form for the ACTION audit line.
+    pub fn audit_suffix(self) -> String {
+        let opt = |v: Option<u64>| v.map_or_else(|| "?".to_string(), |v| v.to_string());
+        format!(
+            "[usage wall_ms={} cpu_ms={} max_rss_kb={}]",
+            self.wall_ms,
+            opt(self.cpu_ms),
+            opt(self.max_rss_kb)
+        )
+    }
+}
+
+/// Polls /proc/<pid> every PAGI_RESOURCE_SAMPLE_MS (default 50) until stopped, keeping the last sample.
+pub struct Sampler {
+    last: Arc<Mutex<Option<ProcSample>>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl Sampler {
+    pub fn start(pid: Option<u32>) -> Self {
+        let last = Arc::new(Mutex::new(pid.and_then(read_proc)));
+        let interval = Duration::from_millis(
+            std::env::var("PAGI_RESOURCE_SAMPLE_MS")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(50u64)
+                .max(1),
+        );
+        let slot = Arc::clone(&last);
+        let task = tokio::spawn(async move {
+            let Some(pid) = pid else {
+                return;
+            };
+            loop {
+                tokio::time::sleep(interval).await;
+                match read_proc(pid) {
+                    Some(sample) => *slot.lock().unwrap_or_else(|e| e.into_inner()) = Some(sample),
+                    None => return,
+                }
+            }
+        });
+        Self { last, task }
+    }
+
+    /// Stop sampling; call after the child has exited or been killed.
+    pub fn finish(self, wall: Duration) -> ResourceUsage {
+        self.task.abort();
+        let last = *self.last.lock().unwrap_or_else(|e| e.into_inner());
+        ResourceUsage::from_samples(wall, None, last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn usage_metadata_and_proc_sampling() {
+        let usage = ResourceUsage::from_samples(
+            Duration::from_millis(120),
+            Some(ProcSample { cpu_ms: 40, max_rss_kb: 900 }),
+            Some(ProcSample { cpu_ms: 70, max_rss_kb: 1024 }),
+        );
+        assert_eq!(usage.cpu_ms, Some(30));
+        assert_eq!(usage.to_metadata()["usage.max_rss_kb"], "1024");
+        assert_eq!(usage.audit_suffix(), "[usage wall_ms=120 cpu_ms=30 max_rss_kb=1024]");
+
+        let unknown = ResourceUsage::from_samples(Duration::from_millis(5), None, None);
+        assert!(!unknown.to_metadata().contains_key("usage.cpu_ms"));
+        assert!(unknown.audit_suffix().ends_with("cpu_ms=? max_rss_kb=?]"));
+
+        if cfg!(target_os = "linux") {
+            let own = read_proc(std::process::id()).expect("/proc sample of self");
+            assert!(own.max_rss_kb > 0);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+// DelegateRLM backend. With PAGI_RLM_URL set (the bridge's POST /rlm, e.g. http://127.0.0.1:8000/rlm) each
+// delegation runs one step of the Python RLM and its structured result (steps, confidence, L4 citations, token
+// usage) is returned; without it, a stub answers from the depth alone. The orchestrator records either in the
+// reasoning_id's L6 trace.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+use tonic::Status;
+
+use crate::proto::pagi_proto::{RlmCitation, RlmRequest, RlmResponse};
+
+/// Bridge RLMSummary (recursive_loop.py); fields added after summary/converged are optional.
+#[derive(Debug, Deserialize)]
+struct BridgeSummary {
+    summary: String,
+    converged: bool,
+    #[serde(default = "one")]
+    steps: u32,
+    #[serde(default)]
+    confidence: Option<f32>,
+    #[serde(default)]
+    citations: Vec<BridgeCitation>,
+    #[serde(default)]
+    usage: BridgeUsage,
+}
+
+#[derive(Debug, Deserialize)]
+struct BridgeCitation {
+    kb_name: String,
+    document_id: String,
+    #[serde(default)]
+    score: f32,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct BridgeUsage {
+    #[serde(default)]
+    prompt_tokens: u32,
+    #[serde(default)]
+    completion_tokens: u32,
+}
+
+fn one() -> u32 {
+    1
+}
+
+impl From<BridgeSummary> for RlmResponse {
+    fn from(s: BridgeSummary) -> Self {
+        RlmResponse {
+            summary: s.summary,
+            converged: s.converged,
+            steps: s.steps,
+            confidence: s.confidence.map(|c| c.clamp(0.0, 1.0)),
+            citations: s
+                .citations
+                .into_iter()
+                .map(|c| RlmCitation {
+                    kb_name: c.kb_name,
+                    document_id: c.document_id,
+                    score: c.score,
+                })
+                .collect(),
+            prompt_tokens: s.usage.prompt_tokens,
+            completion_tokens: s.usage.completion_tokens,
+            backend: "bridge".to_string(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RlmBackend {
+    /// None answers with the stub.
+    url: Option<String>,
+    http: reqwest::Client,
+}
+
+impl RlmBackend {
+    /// PAGI_RLM_URL (unset or empty: stub) with PAGI_RLM_TIMEOUT_SECS per step (default 120; model calls are slow).
+    pub fn from_env() -> Self {
+        let url = std::env::var("PAGI_RLM_URL")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        let timeout = std::env::var("PAGI_RLM_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or(120);
+        if let Some(url) = &url {
+            eprintln!("[RLM] delegating to {} ({}s timeout)", url, timeout);
+        }
+        Self {
+            url,
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(timeout))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// One RLM step for an already guarded request. `max_depth` only shapes the stub's convergence.
+    pub async fn step(&self, req: &RlmRequest, max_depth: u32) -> Result<RlmResponse, Status> {
+        let Some(url) = &self.url else {
+            return Ok(RlmResponse {
+                summary: "Generic delegation processed".to_string(),
+                converged: (req.depth as u32) <= max_depth,
+                steps: 1,
+                backend: "stub".to_string(),
+                ..Default::default()
+            });
+        };
+        let body = serde_json::json!({
+            "query": req.sub_query,
+            "context": req.sub_context,
+            "depth": req.depth.max(0),
+        });
+        let resp = self
+            .http
+            .post(url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| Status::unavailable(format!("RLM backend {}: {}", url, e)))?;
+        let status = resp.status();
+        if !status.is_success() {
+            let text: String = resp.text().await.unwrap_or_default().chars().take(300).collect();
+            let msg = format!("RLM backend {} returned {}: {}", url, status, text);
+            return Err(if status.is_client_error() {
+                Status::invalid_argument(msg)
+            } else {
+                Status::unavailable(msg)
+            });
+        }
+        let summary: BridgeSummary = resp
+            .json()
+            .await
+            .map_err(|e| Status::internal(format!("RLM backend {}: unreadable result: {}", url, e)))?;
+        Ok(summary.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bridge_summaries_map_to_structured_responses() {
+        let full: BridgeSummary = serde_json::from_str(
+            r#"{"summary": "done", "converged": true, "steps": 2, "confidence": 0.8,
+                "citations": [{"kb_name": "kb_core", "document_id": "doc-1", "score": 0.92}],
+                "usage": {"prompt_tokens": 120, "completion_tokens": 45}}"#,
+        )
+        .unwrap();
+        let resp = RlmResponse::from(full);
+        assert_eq!((resp.steps, resp.confidence), (2, Some(0.8)));
+        assert_eq!(resp.citations[0].document_id, "doc-1");
+        assert_eq!((resp.prompt_tokens, resp.completion_tokens), (120, 45));
+        assert_eq!(resp.backend, "bridge");
+    }
+
+    #[test]
+    fn confidence_is_clamped_to_the_unit_range() {
+        let json = r#"{"summary": "done", "converged": true, "confidence": 1.4}"#;
+        let summary: BridgeSummary = serde_json::from_str(json).unwrap();
+        assert_eq!(RlmResponse::from(summary).confidence, Some(1.0));
+    }
+
+    #[test]
+    fn legacy_summaries_default_to_one_step_without_citations() {
+        let legacy: BridgeSummary = serde_json::from_str(r#"{"summary": "old", "converged": false}"#).unwrap();
+        let resp = RlmResponse::from(legacy);
+        assert_eq!((resp.steps, resp.confidence, resp.citations.len()), (1, None, 0));
+    }
+
+    #[tokio::test]
+    async fn without_a_bridge_url_the_stub_answers() {
+        let req = RlmRequest {
+            depth: 3,
+            ..Default::default()
+        };
+        let stub = RlmBackend::default().step(&req, 5).await.unwrap();
+        assert!(stub.converged);
+        assert_eq!(stub.backend, "stub");
+    }
+}
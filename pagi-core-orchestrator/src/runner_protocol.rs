@@ -0,0 +1,190 @@
+// Skill runner protocol v2: the Watchdog writes an invocation envelope (skill, params, invocation id, temp dir,
+// deadline, the manifest's filesystem allow-list when declared) to the runner's stdin and sets
+// PAGI_RUNNER_PROTOCOL=2 in its environment; a v2 runner answers with one JSON result envelope (status,
+// observation, artifacts, metrics, inputs) as its last stdout line. Runners that ignore the envelope (v1) still
+// get `<skill> <json> [<skill_path>]` argv and their raw stdout is the observation.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::skill_manifest::Filesystem;
+
+pub const VERSION: u32 = 2;
+
+/// Runner protocol from PAGI_RUNNER_PROTOCOL: 2 (default) sends the envelope; 1 uses argv only.
+pub fn version_from_env() -> u32 {
+    match std::env::var("PAGI_RUNNER_PROTOCOL").ok().as_deref().map(str::trim) {
+        Some("1") => 1,
+        _ => VERSION,
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Invocation<'a> {
+    pub protocol: u32,
+    pub skill: &'a str,
+    pub params: &'a HashMap<String, String>,
+    pub invocation_id: &'a str,
+    /// Scratch directory for the skill; removed after the run.
+    pub temp_dir: &'a Path,
+    pub deadline_unix_ms: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skill_path: Option<&'a Path>,
+    /// Directories the skill may read / write (its manifest's "filesystem"); the runner refuses other opens.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filesystem: Option<&'a Filesystem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResultEnvelope {
+    protocol: u32,
+    /// "ok" or "error".
+    status: String,
+    #[serde(default)]
+    observation: String,
+    #[serde(default)]
+    error: String,
+    #[serde(default)]
+    artifacts: Vec<String>,
+    #[serde(default)]
+    metrics: BTreeMap<String, f64>,
+    /// sha256 of each file the skill read, keyed by path.
+    #[serde(default)]
+    inputs: BTreeMap<String, String>,
+}
+
+/// Outcome of one skill run, whichever protocol the runner spoke.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunnerOutput {
+    pub observation: String,
+    pub success: bool,
+    pub error: String,
+    /// Files the skill created (bridge-relative paths).
+    pub artifacts: Vec<String>,
+    pub metrics: BTreeMap<String, f64>,
+    /// Files the skill read and their sha256 (path → hex digest), for environment snapshots.
+    pub inputs: BTreeMap<String, String>,
+}
+
+impl RunnerOutput {
+    pub fn failed(error: impl Into<String>) -> Self {
+        Self {
+            error: error.into(),
+            ..Default::default()
+        }
+    }
+
+    /// ActionResponse metadata: `runner.artifacts` (JSON array), `runner.inputs` (JSON object of path → sha256)
+    /// and `runner.metric.<name>`.
+    pub fn to_metadata(&self) -> HashMap<String, String> {
+        let mut meta: HashMap<String, String> = self
+            .metrics
+            .iter()
+            .map(|(k, v)| (format!("runner.metric.{}", k), v.to_string()))
+            .collect();
+        if !self.artifacts.is_empty() {
+            meta.insert(
+                "runner.artifacts".to_string(),
+                serde_json::to_string(&self.artifacts).unwrap_or_default(),
+            );
+        }
+        if !self.inputs.is_empty() {
+            meta.insert(
+                "runner.inputs".to_string(),
+                serde_json::to_string(&self.inputs).unwrap_or_default(),
+            );
+        }
+        meta
+    }
+}
+
+/// Decode a finished runner: a v2 result envelope on the last stdout line wins; otherwise (v1 runner) stdout
+/// is the observation and the exit status decides success, with stderr as the error.
+pub fn parse_output(stdout: &str, stderr: &str, exit_ok: bool, exit_code: Option<i32>) -> RunnerOutput {
+    let envelope = stdout
+        .lines()
+        .rev()
+        .find(|l| !l.trim().is_empty())
+        .and_then(|l| serde_json::from_str::<ResultEnvelope>(l.trim()).ok())
+        .filter(|e| e.protocol == VERSION);
+    if let Some(e) = envelope {
+        let success = exit_ok && e.status == "ok";
+        let error = if success {
+            String::new()
+        } else if !e.error.trim().is_empty() {
+            e.error.trim().to_string()
+        } else {
+            format!("runner status {:?}", e.status)
+        };
+        return RunnerOutput {
+            observation: e.observation.trim().to_string(),
+            success,
+            error,
+            artifacts: e.artifacts,
+            metrics: e.metrics,
+            inputs: e.inputs,
+        };
+    }
+    let stderr = stderr.trim();
+    RunnerOutput {
+        observation: stdout.trim().to_string(),
+        success: exit_ok,
+        error: if exit_ok {
+            String::new()
+        } else if stderr.is_empty() {
+            format!("exit code {:?}", exit_code)
+        } else {
+            stderr.to_string()
+        },
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V2: &str = "skill chatter\n{\"protocol\": 2, \"status\": \"ok\", \"observation\": \"done\", \
+                      \"artifacts\": [\"src/skills/evolved_1.py\"], \"metrics\": {\"duration_ms\": 12.5}, \
+                      \"inputs\": {\"README.md\": \"ab12\"}}\n";
+
+    #[test]
+    fn v2_envelopes_are_read_from_the_last_line() {
+        let out = parse_output(V2, "", true, Some(0));
+        assert_eq!((out.observation.as_str(), out.success), ("done", true));
+        assert_eq!(out.artifacts, ["src/skills/evolved_1.py"]);
+    }
+
+    #[test]
+    fn v2_artifacts_metrics_and_inputs_become_metadata() {
+        let meta = parse_output(V2, "", true, Some(0)).to_metadata();
+        assert_eq!(meta["runner.artifacts"], r#"["src/skills/evolved_1.py"]"#);
+        assert_eq!(meta["runner.metric.duration_ms"], "12.5");
+        assert_eq!(meta["runner.inputs"], r#"{"README.md":"ab12"}"#);
+    }
+
+    #[test]
+    fn v2_errors_carry_their_message() {
+        let failed = parse_output(r#"{"protocol": 2, "status": "error", "error": "bad params"}"#, "trace", false, Some(1));
+        assert_eq!((failed.success, failed.error.as_str()), (false, "bad params"));
+    }
+
+    #[test]
+    fn plain_output_is_v1() {
+        let v1 = parse_output("EVOLVED_PATH:src/skills/x.py\n", "", true, Some(0));
+        assert_eq!((v1.observation.as_str(), v1.success), ("EVOLVED_PATH:src/skills/x.py", true));
+        assert!(v1.artifacts.is_empty());
+    }
+
+    #[test]
+    fn json_without_protocol_2_is_raw_v1_output() {
+        assert_eq!(parse_output(r#"{"status": "ok"}"#, "", true, Some(0)).observation, r#"{"status": "ok"}"#);
+    }
+
+    #[test]
+    fn v1_failures_report_the_exit_code() {
+        assert_eq!(parse_output("", "", false, Some(3)).error, "exit code Some(3)");
+    }
+}
@@ -1,15 +1,63 @@
-// Generic CORE SafetyGovernor: recursion limits, HITL gates, basic sanitization.
+// Generic CORE SafetyGovernor: recursion limits, fan-out caps, HITL gates, basic sanitization.
+// Callers authenticated with the approver or admin role (auth.rs) pass the HITL gate themselves.
+// ExecuteAction params are size-capped and stripped of control characters before dispatch; params a skill
+// manifest types as paths are checked for traversal before real dispatch.
+// Denials carry a PolicyDenial detail (policy_denial.rs) naming the rule, the limit and the usage that hit it.
 // No Red/Blue or adversarial elements; extensibility hooks for future verticals.
 
-use tonic::{Request, Status};
+use std::path::{Component, Path};
 
-use crate::proto::pagi_proto::{HealRequest, RlmRequest};
+use dashmap::DashMap;
+use tonic::{Code, Request, Status};
+
+use crate::auth;
+use crate::policy_denial;
+use crate::proto::pagi_proto::{ActionRequest, HealRequest, PolicyDenial, RlmRequest};
 
 pub struct SafetyGovernor {
     /// Configurable via env or config.toml in future verticals.
     pub max_depth: u32,
     /// Toggle for human approval on critical ops.
     pub hitl_gate: bool,
+    /// Max concurrent actions/delegations per (reasoning_id, depth); index = depth, the last entry covers
+    /// deeper levels. Empty disables the cap.
+    pub max_fan_out: Vec<u32>,
+    /// (reasoning_id, depth) → calls in flight.
+    in_flight: DashMap<(String, u32), u32>,
+    /// Max bytes of one ExecuteAction param value (PAGI_MAX_PARAM_BYTES).
+    pub max_param_bytes: usize,
+    /// Max bytes of all param keys and values together (PAGI_MAX_PARAMS_BYTES).
+    pub max_params_bytes: usize,
+}
+
+/// Held while a fanned-out call runs; releases its slot on drop.
+pub struct FanOutPermit<'a> {
+    in_flight: &'a DashMap<(String, u32), u32>,
+    key: Option<(String, u32)>,
+}
+
+impl Drop for FanOutPermit<'_> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.in_flight.remove_if_mut(&key, |_, n| {
+                *n -= 1;
+                *n == 0
+            });
+        }
+    }
+}
+
+/// PAGI_MAX_FAN_OUT: one cap ("8") or per-depth caps ("8,4,2"); "0" or unset with no value disables.
+fn parse_fan_out(raw: &str) -> Vec<u32> {
+    let caps: Vec<u32> = raw
+        .split(',')
+        .filter_map(|s| s.trim().parse().ok())
+        .collect();
+    if caps.iter().all(|&c| c == 0) {
+        Vec::new()
+    } else {
+        caps
+    }
 }
 
 impl SafetyGovernor {
@@ -26,7 +74,142 @@ impl SafetyGovernor {
                 _ => s.parse().ok(),
             })
             .unwrap_or(true);
-        Self { max_depth, hitl_gate }
+        let max_fan_out = parse_fan_out(&std::env::var("PAGI_MAX_FAN_OUT").unwrap_or_else(|_| "8".into()));
+        let bytes = |name: &str, default: usize| {
+            std::env::var(name)
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(default)
+        };
+        Self {
+            max_depth,
+            hitl_gate,
+            max_fan_out,
+            in_flight: DashMap::new(),
+            max_param_bytes: bytes("PAGI_MAX_PARAM_BYTES", 1024 * 1024),
+            max_params_bytes: bytes("PAGI_MAX_PARAMS_BYTES", 2 * 1024 * 1024),
+        }
+    }
+
+    /// Sanitize ExecuteAction params: strip control characters (newline, carriage return and tab are kept
+    /// for code and file content), then reject oversized values or totals rather than truncating them.
+    pub fn guard_action(&self, mut req: ActionRequest) -> Result<ActionRequest, Status> {
+        let mut total = 0;
+        for (key, value) in req.params.iter_mut() {
+            if value.chars().any(is_stripped_control) {
+                value.retain(|c| !is_stripped_control(c));
+            }
+            if value.len() > self.max_param_bytes {
+                let message = format!(
+                    "param {:?} is {} bytes (limit {}, PAGI_MAX_PARAM_BYTES)",
+                    key,
+                    value.len(),
+                    self.max_param_bytes
+                );
+                let detail = PolicyDenial {
+                    rule: "max_param_bytes".to_string(),
+                    subject: key.clone(),
+                    setting: "PAGI_MAX_PARAM_BYTES".to_string(),
+                    current: value.len() as f64,
+                    limit: self.max_param_bytes as f64,
+                    ..Default::default()
+                };
+                return Err(policy_denial::status(Code::InvalidArgument, message, detail));
+            }
+            total += key.len() + value.len();
+        }
+        if total > self.max_params_bytes {
+            let message =
+                format!("params total {} bytes (limit {}, PAGI_MAX_PARAMS_BYTES)", total, self.max_params_bytes);
+            let detail = PolicyDenial {
+                rule: "max_params_bytes".to_string(),
+                subject: req.skill_name.clone(),
+                setting: "PAGI_MAX_PARAMS_BYTES".to_string(),
+                current: total as f64,
+                limit: self.max_params_bytes as f64,
+                ..Default::default()
+            };
+            return Err(policy_denial::status(Code::InvalidArgument, message, detail));
+        }
+        Ok(req)
+    }
+
+    /// Path-typed params must stay relative and below the skill's working directory.
+    pub fn check_path_param(name: &str, value: &str) -> Result<(), Status> {
+        let path = Path::new(value);
+        let escapes = path
+            .components()
+            .any(|c| matches!(c, Component::ParentDir | Component::RootDir | Component::Prefix(_)));
+        if value.contains('\0') || escapes {
+            return Err(Status::invalid_argument(format!(
+                "param {:?} must be a relative path without '..' (got {:?})",
+                name, value
+            )));
+        }
+        Ok(())
+    }
+
+    fn fan_out_cap(&self, depth: u32) -> Option<u32> {
+        let last = self.max_fan_out.last()?;
+        Some(*self.max_fan_out.get(depth as usize).unwrap_or(last)).filter(|&c| c > 0)
+    }
+
+    /// Reserve a fan-out slot for a call at `depth` under `reasoning_id`; the depth check alone cannot stop
+    /// a shallow tree from exploding in width. Calls without a reasoning_id are not tracked.
+    pub fn acquire_fan_out(&self, reasoning_id: &str, depth: i32) -> Result<FanOutPermit<'_>, Status> {
+        let depth = depth.max(0) as u32;
+        let cap = match self.fan_out_cap(depth) {
+            Some(cap) if !reasoning_id.is_empty() => cap,
+            _ => {
+                return Ok(FanOutPermit {
+                    in_flight: &self.in_flight,
+                    key: None,
+                })
+            }
+        };
+        let key = (reasoning_id.to_string(), depth);
+        let mut n = self.in_flight.entry(key.clone()).or_insert(0);
+        if *n >= cap {
+            let message = format!(
+                "Fan-out limit: reasoning_id {} already has {} call(s) in flight at depth {} (PAGI_MAX_FAN_OUT)",
+                reasoning_id, *n, depth
+            );
+            // A slot frees as soon as a sibling call finishes.
+            let detail = PolicyDenial {
+                rule: "max_fan_out".to_string(),
+                subject: reasoning_id.to_string(),
+                setting: "PAGI_MAX_FAN_OUT".to_string(),
+                current: *n as f64,
+                limit: cap as f64,
+                retry_after_secs: 1,
+                ..Default::default()
+            };
+            return Err(policy_denial::status(Code::ResourceExhausted, message, detail));
+        }
+        *n += 1;
+        Ok(FanOutPermit {
+            in_flight: &self.in_flight,
+            key: Some(key),
+        })
+    }
+
+    /// Recursion limit shared by DelegateRLM and ExecuteAction.
+    pub fn check_depth(&self, depth: i32) -> Result<(), Status> {
+        if (depth as u32) <= self.max_depth {
+            return Ok(());
+        }
+        let detail = PolicyDenial {
+            rule: "max_depth".to_string(),
+            setting: "PAGI_MAX_RECURSION_DEPTH".to_string(),
+            current: depth as f64,
+            limit: self.max_depth as f64,
+            ..Default::default()
+        };
+        Err(policy_denial::status(
+            Code::InvalidArgument,
+            "Recursion depth exceeded; circuit breaker activated",
+            detail,
+        ))
     }
 
     /// Middleware: Enforce recursion limit and basic sanitization.
@@ -34,19 +217,25 @@ impl SafetyGovernor {
         &self,
         req: Request<RlmRequest>,
     ) -> Result<Request<RlmRequest>, Status> {
+        let approver = auth::identity(&req).is_some_and(|id| id.has_role(auth::APPROVER) || id.has_role(auth::ADMIN));
         let msg = req.into_inner();
-        if (msg.depth as u32) > self.max_depth {
-            return Err(Status::invalid_argument(
-                "Recursion depth exceeded; circuit breaker activated",
-            ));
-        }
+        self.check_depth(msg.depth)?;
 
         let sanitized_query = self.sanitize(&msg.sub_query);
         let sanitized_context = self.sanitize(&msg.sub_context);
 
-        if self.hitl_gate && msg.sub_query.contains("patch_core") {
-            return Err(Status::permission_denied(
+        if self.hitl_gate && !approver && msg.sub_query.contains("patch_core") {
+            let detail = PolicyDenial {
+                rule: "hitl_gate".to_string(),
+                subject: "patch_core".to_string(),
+                setting: "PAGI_HITL_GATE".to_string(),
+                approval_channel: format!("call as {} or {}", auth::APPROVER, auth::ADMIN),
+                ..Default::default()
+            };
+            return Err(policy_denial::status(
+                Code::PermissionDenied,
                 "HITL approval required for core operations",
+                detail,
             ));
         }
 
@@ -54,6 +243,7 @@ impl SafetyGovernor {
             sub_query: sanitized_query,
             sub_context: sanitized_context,
             depth: msg.depth,
+            reasoning_id: msg.reasoning_id,
         }))
     }
 
@@ -69,6 +259,10 @@ impl SafetyGovernor {
     }
 }
 
+fn is_stripped_control(c: char) -> bool {
+    c.is_control() && !matches!(c, '\n' | '\r' | '\t')
+}
+
 impl Default for SafetyGovernor {
     fn default() -> Self {
         Self::new()
@@ -87,11 +281,14 @@ mod tests {
             sub_query: "test".to_string(),
             sub_context: "ctx".to_string(),
             depth: 6,
+            ..Default::default()
         });
         let result = gov.guard_rlm(req).await;
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        let denial = policy_denial::of(&err).unwrap();
+        assert_eq!((denial.rule.as_str(), denial.current, denial.limit), ("max_depth", 6.0, 5.0));
     }
 
     #[tokio::test]
@@ -101,6 +298,7 @@ mod tests {
             sub_query: "ok".to_string(),
             sub_context: "ctx".to_string(),
             depth: 5,
+            ..Default::default()
         });
         let result = gov.guard_rlm(req).await;
         assert!(result.is_ok());
@@ -117,6 +315,7 @@ mod tests {
             sub_query: format!("  {}  ", long),
             sub_context: "ctx".to_string(),
             depth: 0,
+            ..Default::default()
         });
         let result = gov.guard_rlm(req).await;
         assert!(result.is_ok());
@@ -132,9 +331,102 @@ mod tests {
             sub_query: "patch_core apply".to_string(),
             sub_context: "".to_string(),
             depth: 0,
+            ..Default::default()
         });
         let result = gov.guard_rlm(req).await;
         assert!(result.is_err());
         assert_eq!(result.unwrap_err().code(), tonic::Code::PermissionDenied);
     }
+
+    #[tokio::test]
+    async fn guard_rlm_lets_approvers_through_the_hitl_gate() {
+        let gov = SafetyGovernor::new();
+        let mut approved = Request::new(RlmRequest {
+            sub_query: "patch_core apply".to_string(),
+            ..Default::default()
+        });
+        approved.extensions_mut().insert(auth::Identity {
+            subject: "reviewer".to_string(),
+            roles: vec![auth::APPROVER.to_string()],
+            tenant: None,
+        });
+        assert!(gov.guard_rlm(approved).await.is_ok());
+    }
+
+    fn capped() -> SafetyGovernor {
+        SafetyGovernor {
+            max_param_bytes: 8,
+            max_params_bytes: 16,
+            ..SafetyGovernor::new()
+        }
+    }
+
+    fn action(params: &[(&str, &str)]) -> ActionRequest {
+        ActionRequest {
+            params: params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn fanned_out() -> SafetyGovernor {
+        SafetyGovernor {
+            max_fan_out: parse_fan_out("2,1"),
+            ..SafetyGovernor::new()
+        }
+    }
+
+    #[test]
+    fn guard_action_strips_control_chars_but_keeps_whitespace() {
+        let guarded = capped().guard_action(action(&[("code", "a\u{1b}[0m\n\tb\0")])).unwrap();
+        assert_eq!(guarded.params["code"], "a[0m\n\tb");
+    }
+
+    #[test]
+    fn guard_action_caps_each_param_and_their_total() {
+        let too_long = capped().guard_action(action(&[("path", "123456789")])).unwrap_err();
+        assert_eq!(too_long.code(), tonic::Code::InvalidArgument);
+        assert!(capped().guard_action(action(&[("a", "12345678"), ("b", "12345678")])).is_err());
+    }
+
+    #[test]
+    fn path_params_must_stay_relative_and_inside() {
+        assert!(SafetyGovernor::check_path_param("path", "src/skills/peek_file.py").is_ok());
+        for bad in ["../secrets", "/etc/passwd", "src/../../x"] {
+            assert!(SafetyGovernor::check_path_param("path", bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn fan_out_is_capped_per_reasoning_id() {
+        let gov = fanned_out();
+        let _permits = [gov.acquire_fan_out("r1", 0).unwrap(), gov.acquire_fan_out("r1", 0).unwrap()];
+        let err = gov.acquire_fan_out("r1", 0).err().unwrap();
+        assert_eq!(err.code(), tonic::Code::ResourceExhausted);
+        let denial = policy_denial::of(&err).unwrap();
+        assert_eq!((denial.rule.as_str(), denial.current, denial.limit), ("max_fan_out", 2.0, 2.0));
+        assert!(gov.acquire_fan_out("r2", 0).is_ok());
+    }
+
+    #[test]
+    fn the_last_fan_out_cap_covers_deeper_levels() {
+        let gov = fanned_out();
+        let _deep = gov.acquire_fan_out("r1", 3).unwrap();
+        assert!(gov.acquire_fan_out("r1", 3).is_err());
+    }
+
+    #[test]
+    fn dropped_permits_free_their_slot() {
+        let gov = fanned_out();
+        let first = gov.acquire_fan_out("r1", 0).unwrap();
+        let _second = gov.acquire_fan_out("r1", 0).unwrap();
+        drop(first);
+        assert!(gov.acquire_fan_out("r1", 0).is_ok());
+    }
+
+    #[test]
+    fn untracked_calls_and_zero_caps_are_unbounded() {
+        let gov = fanned_out();
+        assert!(gov.acquire_fan_out("", 0).is_ok() && gov.acquire_fan_out("", 0).is_ok());
+        assert!(parse_fan_out("0").is_empty());
+    }
 }
@@ -0,0 +1,248 @@
+// Embedded scheduler for periodic memory hygiene. Maintenance jobs (the L2 snapshot, the L4 maintenance pass, the
+// TTL sweep, L2→L4 consolidation) register here instead of running their own timers. A task runs at wall-clock
+// multiples of its interval, like cron's */N (an hourly task runs on the hour), delayed by a random jitter of up to
+// PAGI_SCHEDULER_JITTER_PCT of the interval so replicas sharing a vector store spread their passes. A task never
+// overlaps itself: slots missed while a run overran are skipped. PAGI_SCHEDULER_DISABLE (comma-separated task names)
+// turns tasks off; GetHealth reports every task's interval, latest run and outcome, and next run.
+
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::clock::Clock;
+use crate::proto::pagi_proto::ScheduledTask;
+
+/// A run's outcome: a short summary for the report, or the error.
+pub type JobFuture = Pin<Box<dyn Future<Output = Result<String, String>> + Send>>;
+type Job = Box<dyn Fn() -> JobFuture + Send + Sync>;
+
+#[derive(Clone, Default)]
+struct State {
+    running: bool,
+    runs: u64,
+    failures: u64,
+    last_run_unix: i64,
+    last_duration_ms: u64,
+    last_outcome: String,
+    last_error: String,
+    next_run_unix: i64,
+}
+
+struct Task {
+    name: &'static str,
+    interval: Duration,
+    disabled: bool,
+    job: Job,
+    state: Mutex<State>,
+}
+
+pub struct Scheduler {
+    tasks: Vec<Task>,
+    disabled: BTreeSet<String>,
+    jitter_pct: u64,
+    clock: Clock,
+}
+
+/// Start of the first interval slot after `now_ms`, plus `jitter_ms` (unix ms).
+fn next_slot(interval: Duration, now_ms: i64, jitter_ms: u64) -> i64 {
+    let every = (interval.as_millis() as i64).max(1);
+    (now_ms.div_euclid(every) + 1).saturating_mul(every).saturating_add(jitter_ms as i64)
+}
+
+impl Scheduler {
+    pub fn new(disabled: BTreeSet<String>, jitter_pct: u64, clock: Clock) -> Self {
+        Self {
+            tasks: Vec::new(),
+            disabled,
+            jitter_pct: jitter_pct.min(100),
+            clock,
+        }
+    }
+
+    /// PAGI_SCHEDULER_DISABLE (comma-separated task names) and PAGI_SCHEDULER_JITTER_PCT (default 10, max 100).
+    pub fn from_env(clock: Clock) -> Self {
+        let disabled = std::env::var("PAGI_SCHEDULER_DISABLE")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+            .collect();
+        let jitter_pct = std::env::var("PAGI_SCHEDULER_JITTER_PCT")
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(10);
+        Self::new(disabled, jitter_pct, clock)
+    }
+
+    /// Register a task; a zero `interval` (its feature is off) keeps it in the report without running it.
+    pub fn add<F, Fut>(mut self, name: &'static str, interval: Duration, job: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, String>> + Send + 'static,
+    {
+        self.tasks.push(Task {
+            name,
+            interval,
+            disabled: self.disabled.contains(name),
+            job: Box::new(move || -> JobFuture { Box::pin(job()) }),
+            state: Mutex::new(State::default()),
+        });
+        self
+    }
+
+    /// Start one loop per runnable task; call once. Unknown names in PAGI_SCHEDULER_DISABLE are logged.
+    pub fn spawn(self: &Arc<Self>) {
+        for name in self.disabled.iter().filter(|n| !self.tasks.iter().any(|t| t.name == n.as_str())) {
+            let known: Vec<&str> = self.tasks.iter().map(|t| t.name).collect();
+            eprintln!(
+                "[Scheduler] ignoring unknown task {:?} in PAGI_SCHEDULER_DISABLE (known: {})",
+                name,
+                known.join(", ")
+            );
+        }
+        for (i, task) in self.tasks.iter().enumerate() {
+            if task.interval.is_zero() || task.disabled {
+                continue;
+            }
+            eprintln!("[Scheduler] {} every {:?}", task.name, task.interval);
+            let scheduler = Arc::clone(self);
+            tokio::spawn(async move {
+                let task = &scheduler.tasks[i];
+                loop {
+                    let now_ms = scheduler.clock.now_ms();
+                    let at = next_slot(task.interval, now_ms, scheduler.jitter(task.interval));
+                    task.state.lock().unwrap_or_else(|e| e.into_inner()).next_run_unix = at.div_euclid(1000);
+                    tokio::time::sleep(Duration::from_millis(at.saturating_sub(now_ms) as u64)).await;
+                    scheduler.run(task).await;
+                }
+            });
+        }
+    }
+
+    /// Random delay of up to jitter_pct of `interval`, in ms.
+    fn jitter(&self, interval: Duration) -> u64 {
+        let max = interval.as_millis() as u64 / 100 * self.jitter_pct;
+        if max == 0 {
+            return 0;
+        }
+        (uuid::Uuid::new_v4().as_u128() % (max as u128 + 1)) as u64
+    }
+
+    /// Run `task` once and record the outcome.
+    async fn run(&self, task: &Task) {
+        let started = Instant::now();
+        {
+            let mut state = task.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.running = true;
+            state.last_run_unix = self.clock.now_secs();
+            state.next_run_unix = 0;
+        }
+        let result = (task.job)().await;
+        let mut state = task.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.running = false;
+        state.runs += 1;
+        state.last_duration_ms = started.elapsed().as_millis() as u64;
+        match result {
+            Ok(outcome) => {
+                state.last_outcome = outcome;
+                state.last_error.clear();
+            }
+            Err(e) => {
+                eprintln!("[Scheduler] {} failed: {}", task.name, e);
+                state.failures += 1;
+                state.last_error = e;
+            }
+        }
+    }
+
+    /// Every registered task, in registration order.
+    pub fn report(&self) -> Vec<ScheduledTask> {
+        self.tasks
+            .iter()
+            .map(|t| {
+                let state = t.state.lock().unwrap_or_else(|e| e.into_inner()).clone();
+                let status = if t.interval.is_zero() {
+                    "off"
+                } else if t.disabled {
+                    "disabled"
+                } else if state.running {
+                    "running"
+                } else {
+                    "scheduled"
+                };
+                ScheduledTask {
+                    name: t.name.to_string(),
+                    state: status.to_string(),
+                    interval_secs: t.interval.as_secs(),
+                    last_run_unix: state.last_run_unix,
+                    next_run_unix: state.next_run_unix,
+                    last_duration_ms: state.last_duration_ms,
+                    last_outcome: state.last_outcome,
+                    last_error: state.last_error,
+                    runs: state.runs,
+                    failures: state.failures,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new(BTreeSet::new(), 0, Clock::system())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    /// Hourly retention (first run succeeds, later ones fail), a disabled consolidation and an off snapshot.
+    fn scheduler() -> Scheduler {
+        let calls = Arc::new(AtomicU32::new(0));
+        Scheduler::new(["consolidation".to_string()].into(), 10, ManualClock::at(1_000).into())
+            .add("retention", HOUR, move || {
+                let n = calls.fetch_add(1, Ordering::Relaxed);
+                let result = if n == 0 { Ok("pruned 3".to_string()) } else { Err("L4 degraded".to_string()) };
+                std::future::ready(result)
+            })
+            .add("consolidation", HOUR, || std::future::ready(Ok(String::new())))
+            .add("l2_snapshot", Duration::ZERO, || std::future::ready(Ok(String::new())))
+    }
+
+    #[test]
+    fn slots_align_to_the_interval_plus_jitter() {
+        assert_eq!(next_slot(HOUR, 7_200_000, 0), 10_800_000, "a run exactly on a slot waits for the next");
+        assert_eq!(next_slot(HOUR, 7_200_001, 500), 10_800_500);
+        assert_eq!(next_slot(Duration::ZERO, 41, 0), 42);
+    }
+
+    #[test]
+    fn jitter_is_at_most_the_configured_share_of_the_interval() {
+        let scheduler = scheduler();
+        assert!((0..100).all(|_| scheduler.jitter(HOUR) <= 360_000));
+    }
+
+    #[test]
+    fn reports_show_scheduled_disabled_and_off_tasks() {
+        let report = scheduler().report();
+        let states: Vec<&str> = report.iter().map(|t| t.state.as_str()).collect();
+        assert_eq!(states, ["scheduled", "disabled", "off"]);
+    }
+
+    #[tokio::test]
+    async fn runs_record_their_counts_last_outcome_and_last_error() {
+        let scheduler = scheduler();
+        scheduler.run(&scheduler.tasks[0]).await;
+        scheduler.run(&scheduler.tasks[0]).await;
+        let r = &scheduler.report()[0];
+        assert_eq!((r.interval_secs, r.last_run_unix, r.runs, r.failures), (3600, 1_000, 2, 1));
+        assert_eq!((r.last_outcome.as_str(), r.last_error.as_str()), ("pruned 3", "L4 degraded"));
+    }
+}
@@ -0,0 +1,285 @@
+// Read-through cache for L4 searches. RCA during heal storms repeats identical searches (same error trace,
+// same KB), so results are kept per (collection, hash of query text/vector, filter and paging) for
+// PAGI_SEARCH_CACHE_TTL_SECS, least recently used first out beyond PAGI_SEARCH_CACHE_SIZE entries. Every write to
+// a collection (upsert, delete, decay rescore, WAL replay, restore) drops its entries and bumps its generation, so
+// a search that started before the write cannot store its now stale result.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::memory_manager::{PointSearch, SearchTuning};
+use crate::proto::pagi_proto::SearchRequest;
+use crate::vector_store::ScoredPoint;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    collection: String,
+    digest: u64,
+}
+
+impl CacheKey {
+    /// Key for `req` (kb_name already resolved to its collection) under `tuning`. Everything that shapes the
+    /// result is hashed: query text and vector, vector space, filter, paging, threshold, hybrid, recency
+    /// and rerank settings. Time-weighted rankings are reused for up to the TTL like any other result.
+    pub fn new(req: &SearchRequest, tuning: &SearchTuning) -> Self {
+        let mut h = DefaultHasher::new();
+        req.query.hash(&mut h);
+        for x in &req.query_vector {
+            x.to_bits().hash(&mut h);
+        }
+        req.vector_name.hash(&mut h);
+        // prost messages do not implement Hash; their Debug output covers every field.
+        format!("{:?}", req.filter).hash(&mut h);
+        (req.limit, req.offset, req.hybrid).hash(&mut h);
+        req.score_threshold.map(f32::to_bits).hash(&mut h);
+        (req.recency_weight.to_bits(), req.recency_halflife_secs).hash(&mut h);
+        (&req.rerank, req.mmr_lambda.map(f32::to_bits)).hash(&mut h);
+        req.feedback_weight.map(f32::to_bits).hash(&mut h);
+        tuning.hybrid.hash(&mut h);
+        tuning.alpha.to_bits().hash(&mut h);
+        tuning.min_score.map(f32::to_bits).hash(&mut h);
+        Self {
+            collection: req.kb_name.clone(),
+            digest: h.finish(),
+        }
+    }
+}
+
+struct Entry {
+    points: Vec<ScoredPoint>,
+    source: String,
+    next_offset: u32,
+    stored_ms: i64,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct State {
+    entries: HashMap<CacheKey, Entry>,
+    /// Per-collection write count; a result computed under an older generation is not stored.
+    generations: HashMap<String, u64>,
+    tick: u64,
+}
+
+#[derive(Default)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub invalidations: u64,
+    pub entries: u64,
+}
+
+pub struct SearchCache {
+    state: Mutex<State>,
+    capacity: usize,
+    ttl_ms: i64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+impl SearchCache {
+    pub fn new(capacity: usize, ttl_secs: u64) -> Self {
+        Self {
+            state: Mutex::new(State::default()),
+            capacity,
+            ttl_ms: (ttl_secs as i64).saturating_mul(1000),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
+        }
+    }
+
+    /// PAGI_SEARCH_CACHE_SIZE (default 256; 0 disables) and PAGI_SEARCH_CACHE_TTL_SECS (default 30; 0 disables).
+    pub fn from_env() -> Self {
+        let var = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(default)
+        };
+        Self::new(var("PAGI_SEARCH_CACHE_SIZE", 256) as usize, var("PAGI_SEARCH_CACHE_TTL_SECS", 30))
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.capacity > 0 && self.ttl_ms > 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Cached result for `key` when younger than the TTL; on a miss, the collection's current generation to
+    /// hand back to `insert`.
+    pub fn get(&self, key: &CacheKey, now_ms: i64) -> Result<PointSearch, u64> {
+        let mut state = self.lock();
+        state.tick += 1;
+        let tick = state.tick;
+        if state.entries.get(key).is_some_and(|e| now_ms - e.stored_ms >= self.ttl_ms) {
+            state.entries.remove(key);
+        }
+        let fresh = state.entries.get_mut(key).map(|entry| {
+            entry.last_used = tick;
+            PointSearch {
+                points: entry.points.clone(),
+                degraded: false,
+                source: entry.source.clone(),
+                next_offset: entry.next_offset,
+            }
+        });
+        match fresh {
+            Some(found) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Ok(found)
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                Err(state.generations.get(&key.collection).copied().unwrap_or(0))
+            }
+        }
+    }
+
+    /// Store a result computed under `generation` (from `get`); dropped when the collection was written since.
+    pub fn insert(&self, key: CacheKey, found: &PointSearch, generation: u64, now_ms: i64) {
+        let mut state = self.lock();
+        if state.generations.get(&key.collection).copied().unwrap_or(0) != generation {
+            return;
+        }
+        if !state.entries.contains_key(&key) && state.entries.len() >= self.capacity {
+            let lru = state.entries.iter().min_by_key(|(_, e)| e.last_used).map(|(k, _)| k.clone());
+            if let Some(lru) = lru {
+                state.entries.remove(&lru);
+            }
+        }
+        state.tick += 1;
+        let last_used = state.tick;
+        state.entries.insert(
+            key,
+            Entry {
+                points: found.points.clone(),
+                source: found.source.clone(),
+                next_offset: found.next_offset,
+                stored_ms: now_ms,
+                last_used,
+            },
+        );
+    }
+
+    /// Drop every cached result for `collection` after a write to it.
+    pub fn invalidate(&self, collection: &str) {
+        let mut state = self.lock();
+        *state.generations.entry(collection.to_string()).or_default() += 1;
+        let before = state.entries.len();
+        state.entries.retain(|k, _| k.collection != collection);
+        if state.entries.len() < before {
+            self.invalidations.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Drop entries older than the TTL (normally only dropped when next looked up); returns how many.
+    pub fn purge_expired(&self, now_ms: i64) -> usize {
+        let mut state = self.lock();
+        let before = state.entries.len();
+        state.entries.retain(|_, e| now_ms - e.stored_ms < self.ttl_ms);
+        before - state.entries.len()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+            entries: self.lock().entries.len() as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(id: &str) -> PointSearch {
+        PointSearch {
+            points: vec![ScoredPoint {
+                id: id.to_string(),
+                score: 1.0,
+                payload: HashMap::new(),
+            }],
+            degraded: false,
+            source: "memory".to_string(),
+            next_offset: 0,
+        }
+    }
+
+    fn key(kb: &str, query: &str) -> CacheKey {
+        let req = SearchRequest {
+            kb_name: kb.to_string(),
+            query: query.to_string(),
+            limit: 5,
+            ..Default::default()
+        };
+        CacheKey::new(&req, &SearchTuning::default())
+    }
+
+    #[test]
+    fn entries_are_served_until_the_ttl() {
+        let cache = SearchCache::new(2, 10);
+        let generation = cache.get(&key("kb_core", "panic"), 0).unwrap_err();
+        cache.insert(key("kb_core", "panic"), &found("a"), generation, 0);
+        assert_eq!(cache.get(&key("kb_core", "panic"), 5_000).unwrap().points[0].id, "a");
+        assert!(cache.get(&key("kb_core", "panic"), 10_000).is_err());
+    }
+
+    #[test]
+    fn the_least_recently_used_entry_is_evicted() {
+        let cache = SearchCache::new(2, 10);
+        cache.insert(key("kb_core", "a"), &found("a"), 0, 0);
+        cache.insert(key("kb_skills", "b"), &found("b"), 0, 0);
+        assert!(cache.get(&key("kb_core", "a"), 1).is_ok());
+        cache.insert(key("kb_skills", "c"), &found("c"), 0, 0);
+        assert!(cache.get(&key("kb_skills", "b"), 1).is_err());
+        assert!(cache.get(&key("kb_core", "a"), 1).is_ok());
+    }
+
+    #[test]
+    fn writes_invalidate_only_their_collection() {
+        let cache = SearchCache::new(2, 10);
+        cache.insert(key("kb_core", "a"), &found("a"), 0, 0);
+        cache.insert(key("kb_skills", "c"), &found("c"), 0, 0);
+        cache.invalidate("kb_core");
+        assert!(cache.get(&key("kb_core", "a"), 1).is_err());
+        assert!(cache.get(&key("kb_skills", "c"), 1).is_ok());
+    }
+
+    #[test]
+    fn results_from_before_a_write_are_not_stored() {
+        let cache = SearchCache::new(2, 10);
+        let stale = cache.get(&key("kb_core", "d"), 1).unwrap_err();
+        cache.invalidate("kb_core");
+        cache.insert(key("kb_core", "d"), &found("d"), stale, 1);
+        assert!(cache.get(&key("kb_core", "d"), 1).is_err());
+    }
+
+    #[test]
+    fn stats_count_hits_invalidations_and_entries() {
+        let cache = SearchCache::new(2, 10);
+        cache.insert(key("kb_core", "a"), &found("a"), 0, 0);
+        cache.insert(key("kb_skills", "b"), &found("b"), 0, 0);
+        assert!(cache.get(&key("kb_core", "a"), 1).is_ok());
+        assert!(cache.get(&key("kb_skills", "b"), 1).is_ok());
+        cache.invalidate("kb_core");
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.invalidations, stats.entries), (2, 1, 1));
+    }
+
+    #[test]
+    fn purging_drops_only_expired_entries() {
+        let cache = SearchCache::new(2, 10);
+        cache.insert(key("kb_core", "a"), &found("a"), 0, 0);
+        assert_eq!(cache.purge_expired(9_999), 0);
+        assert_eq!(cache.purge_expired(10_000), 1);
+    }
+}
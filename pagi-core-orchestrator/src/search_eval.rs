@@ -0,0 +1,376 @@
+// Offline search-quality evaluation: a labeled dataset (queries with the point ids relevant to each) is run
+// through L4 search under several tuning configurations and scored by recall@k and MRR, so threshold and
+// hybrid-fusion choices rest on numbers. Datasets that bring their own documents are seeded into a scratch
+// in-memory L4; otherwise the queries hit the live KB. Served by RunSearchEval and the search_eval binary.
+// Dataset JSON: {"kb_name", "documents": [{"id", "content", "vector"}], "queries": [{"query", "query_vector",
+// "relevant": [ids]}], "configs": [{"name", "hybrid", "alpha", "min_score"}]}; documents and configs are optional.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Deserialize;
+use tonic::Status;
+
+use crate::memory_manager::{MemoryManager, SearchTuning};
+use crate::proto::pagi_proto::{
+    SearchEvalConfig, SearchEvalReport, SearchEvalRequest, SearchEvalResult, SearchRequest, UpsertRequest, VectorPoint,
+};
+
+const DEFAULT_K: u32 = 10;
+/// Points per upsert while seeding a scratch L4.
+const SEED_CHUNK: usize = 256;
+
+/// A document seeded into the scratch L4; `vector` may be omitted when a server-side embedder is configured.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EvalDocument {
+    pub id: String,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub vector: Vec<f32>,
+    /// Extra payload fields (e.g. for filters); `content` is added from the field above.
+    #[serde(default)]
+    pub payload: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LabeledQuery {
+    #[serde(default)]
+    pub query: String,
+    #[serde(default)]
+    pub query_vector: Vec<f32>,
+    /// Ids that count as correct answers.
+    pub relevant: Vec<String>,
+}
+
+/// One configuration as written in a dataset file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigEntry {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    hybrid: bool,
+    alpha: Option<f32>,
+    min_score: Option<f32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Dataset {
+    pub kb_name: String,
+    #[serde(default)]
+    pub documents: Vec<EvalDocument>,
+    pub queries: Vec<LabeledQuery>,
+    #[serde(default)]
+    configs: Vec<ConfigEntry>,
+}
+
+impl Dataset {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let raw = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::from_json(&raw).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn from_json(raw: &str) -> Result<Self, String> {
+        let dataset: Dataset = serde_json::from_str(raw).map_err(|e| e.to_string())?;
+        if dataset.kb_name.is_empty() {
+            return Err("kb_name is required".to_string());
+        }
+        if dataset.queries.is_empty() {
+            return Err("no queries".to_string());
+        }
+        if let Some(i) = dataset.queries.iter().position(|q| q.relevant.is_empty()) {
+            return Err(format!("query {} has no relevant ids", i));
+        }
+        if let Some(i) = dataset
+            .queries
+            .iter()
+            .position(|q| q.query.trim().is_empty() && q.query_vector.is_empty())
+        {
+            return Err(format!("query {} has neither query nor query_vector", i));
+        }
+        let dims: HashSet<usize> = dataset.documents.iter().map(|d| d.vector.len()).filter(|&n| n > 0).collect();
+        if dims.len() > 1 {
+            return Err(format!("documents mix vector sizes {:?}", dims));
+        }
+        Ok(dataset)
+    }
+
+    /// Vector size of the seeded documents, when they carry vectors.
+    fn document_dim(&self) -> Option<usize> {
+        self.documents.iter().map(|d| d.vector.len()).find(|&n| n > 0)
+    }
+}
+
+/// A named set of search knobs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalConfig {
+    pub name: String,
+    pub tuning: SearchTuning,
+}
+
+impl EvalConfig {
+    fn new(index: usize, name: &str, hybrid: bool, alpha: Option<f32>, min_score: Option<f32>) -> Result<Self, String> {
+        let alpha = alpha.unwrap_or(SearchTuning::default().alpha);
+        if !(0.0..=1.0).contains(&alpha) {
+            return Err(format!("config {}: alpha {} outside 0.0..=1.0", index, alpha));
+        }
+        Ok(Self {
+            name: if name.is_empty() { format!("config_{}", index) } else { name.to_string() },
+            tuning: SearchTuning {
+                hybrid: Some(hybrid),
+                alpha,
+                min_score,
+                untracked: true,
+            },
+        })
+    }
+
+    /// Vector-only plus hybrid at three fusion weights.
+    pub fn default_grid() -> Vec<Self> {
+        let mut grid = vec![Self::new(0, "vector", false, None, None).expect("valid default")];
+        for alpha in [0.3, 0.5, 0.7] {
+            grid.push(Self::new(grid.len(), &format!("hybrid_a{}", alpha), true, Some(alpha), None).expect("valid default"));
+        }
+        grid
+    }
+
+    /// Request configs, else the dataset's, else the default grid.
+    fn resolve(requested: &[SearchEvalConfig], dataset: &Dataset) -> Result<Vec<Self>, String> {
+        if !requested.is_empty() {
+            return requested
+                .iter()
+                .enumerate()
+                .map(|(i, c)| Self::new(i, &c.name, c.hybrid, c.alpha, c.min_score))
+                .collect();
+        }
+        if !dataset.configs.is_empty() {
+            return dataset
+                .configs
+                .iter()
+                .enumerate()
+                .map(|(i, c)| Self::new(i, &c.name, c.hybrid, c.alpha, c.min_score))
+                .collect();
+        }
+        Ok(Self::default_grid())
+    }
+}
+
+/// Recall of `relevant` within `ranked` and the reciprocal rank of the first relevant id (0 when none).
+fn score(ranked: &[String], relevant: &[String]) -> (f64, f64) {
+    let relevant: HashSet<&String> = relevant.iter().collect();
+    let found = ranked.iter().filter(|id| relevant.contains(id)).collect::<HashSet<_>>().len();
+    let reciprocal_rank = ranked
+        .iter()
+        .position(|id| relevant.contains(id))
+        .map_or(0.0, |rank| 1.0 / (rank as f64 + 1.0));
+    (found as f64 / relevant.len() as f64, reciprocal_rank)
+}
+
+/// Dataset directory for RunSearchEval (PAGI_SEARCH_EVAL_DIR, default "eval").
+pub fn dataset_dir() -> PathBuf {
+    std::env::var("PAGI_SEARCH_EVAL_DIR")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("eval"))
+}
+
+/// RunSearchEval: load `dataset_path` from the dataset directory and score its configurations.
+pub async fn run(memory: &MemoryManager, req: SearchEvalRequest) -> Result<SearchEvalReport, Status> {
+    let rel = Path::new(&req.dataset_path);
+    let escapes = rel
+        .components()
+        .any(|c| matches!(c, Component::ParentDir | Component::RootDir | Component::Prefix(_)));
+    if req.dataset_path.is_empty() || escapes {
+        return Err(Status::invalid_argument(format!(
+            "dataset_path must be a relative path under PAGI_SEARCH_EVAL_DIR (got {:?})",
+            req.dataset_path
+        )));
+    }
+    let dataset = Dataset::load(&dataset_dir().join(rel)).map_err(Status::invalid_argument)?;
+    evaluate_dataset(memory, &dataset, &req.configs, req.k).await
+}
+
+/// Score `dataset` under the requested configurations (see EvalConfig::resolve); `k` 0 means 10.
+pub async fn evaluate_dataset(
+    memory: &MemoryManager,
+    dataset: &Dataset,
+    configs: &[SearchEvalConfig],
+    k: u32,
+) -> Result<SearchEvalReport, Status> {
+    let configs = EvalConfig::resolve(configs, dataset).map_err(Status::invalid_argument)?;
+    let k = if k == 0 { DEFAULT_K } else { k.min(100) };
+    if dataset.documents.is_empty() {
+        if !memory.l4_enabled() {
+            return Err(Status::failed_precondition(
+                "dataset has no documents and L4 is disabled; nothing to search",
+            ));
+        }
+        let source = memory.health().l4_state;
+        return evaluate(memory, dataset, &configs, k, &source).await;
+    }
+    let scratch = seed(memory, dataset).await?;
+    evaluate(&scratch, dataset, &configs, k, "dataset").await
+}
+
+/// Scratch L4 holding the dataset's documents in its KB.
+async fn seed(memory: &MemoryManager, dataset: &Dataset) -> Result<Arc<MemoryManager>, Status> {
+    let dim = dataset.document_dim().unwrap_or_else(|| memory.kb_dim(&dataset.kb_name));
+    let scratch = memory.scratch(dim);
+    scratch
+        .ensure_kb(&dataset.kb_name)
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?;
+    let mut points = Vec::with_capacity(dataset.documents.len());
+    for doc in &dataset.documents {
+        let vector = if !doc.vector.is_empty() {
+            doc.vector.clone()
+        } else {
+            scratch.embed_query(&doc.content, dim).await.ok_or_else(|| {
+                Status::invalid_argument(format!(
+                    "document {} has no vector and no server-side embedder is configured (PAGI_EMBEDDER)",
+                    doc.id
+                ))
+            })?
+        };
+        let mut payload = doc.payload.clone();
+        if !doc.content.is_empty() {
+            payload.insert("content".to_string(), doc.content.clone());
+        }
+        points.push(VectorPoint {
+            id: doc.id.clone(),
+            vector,
+            payload,
+            ..Default::default()
+        });
+    }
+    for chunk in points.chunks(SEED_CHUNK) {
+        scratch
+            .upsert_vectors(UpsertRequest {
+                kb_name: dataset.kb_name.clone(),
+                points: chunk.to_vec(),
+                ..Default::default()
+            })
+            .await?;
+    }
+    Ok(scratch)
+}
+
+async fn evaluate(
+    memory: &MemoryManager,
+    dataset: &Dataset,
+    configs: &[EvalConfig],
+    k: u32,
+    source: &str,
+) -> Result<SearchEvalReport, Status> {
+    let mut report = SearchEvalReport {
+        k,
+        source: source.to_string(),
+        ..Default::default()
+    };
+    for config in configs {
+        let mut result = SearchEvalResult {
+            name: config.name.clone(),
+            queries: dataset.queries.len() as u32,
+            ..Default::default()
+        };
+        let (mut recall_sum, mut rr_sum) = (0.0, 0.0);
+        for q in &dataset.queries {
+            let found = memory
+                .search_points_tuned(
+                    SearchRequest {
+                        query: q.query.clone(),
+                        kb_name: dataset.kb_name.clone(),
+                        limit: k,
+                        query_vector: q.query_vector.clone(),
+                        ..Default::default()
+                    },
+                    config.tuning,
+                )
+                .await?;
+            if found.degraded {
+                result.degraded += 1;
+            }
+            let ranked: Vec<String> = found.points.into_iter().map(|p| p.id).collect();
+            let (recall, rr) = score(&ranked, &q.relevant);
+            if rr == 0.0 {
+                result.missed += 1;
+            }
+            recall_sum += recall;
+            rr_sum += rr;
+        }
+        result.recall_at_k = recall_sum / dataset.queries.len() as f64;
+        result.mrr = rr_sum / dataset.queries.len() as f64;
+        report.results.push(result);
+    }
+    // Ties go to the config listed first.
+    let mut best: Option<&SearchEvalResult> = None;
+    for r in &report.results {
+        let better = match best {
+            None => true,
+            Some(b) => (r.mrr, r.recall_at_k) > (b.mrr, b.recall_at_k),
+        };
+        if better {
+            best = Some(r);
+        }
+    }
+    report.best = best.map(|r| r.name.clone()).unwrap_or_default();
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_recall_and_reciprocal_rank() {
+        let ids = |s: &[&str]| s.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        assert_eq!(score(&ids(&["a", "b", "c"]), &ids(&["b", "z"])), (0.5, 0.5));
+        assert_eq!(score(&ids(&["a"]), &ids(&["z"])), (0.0, 0.0));
+        assert!(Dataset::from_json(r#"{"kb_name": "kb_core", "queries": [{"query": "x", "relevant": []}]}"#)
+            .unwrap_err()
+            .contains("no relevant ids"));
+        assert!(EvalConfig::new(0, "", true, Some(1.5), None).is_err());
+        assert_eq!(EvalConfig::new(3, "", false, None, None).unwrap().name, "config_3");
+    }
+
+    #[tokio::test]
+    async fn keyword_fusion_rescues_identifier_queries() {
+        let memory = MemoryManager::in_memory(2);
+        // Vectors point "q1" at the wrong document; only its keywords name the right one.
+        let dataset = Dataset::from_json(
+            r#"{
+                "kb_name": "kb_eval",
+                "documents": [
+                    {"id": "oom", "content": "E0599 method not found in Vec", "vector": [1.0, 0.0]},
+                    {"id": "net", "content": "connection refused on port 6334", "vector": [0.0, 1.0]}
+                ],
+                "queries": [
+                    {"query": "E0599", "query_vector": [0.0, 1.0], "relevant": ["oom"]},
+                    {"query": "port", "query_vector": [0.1, 1.0], "relevant": ["net"]}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let report = evaluate_dataset(&memory, &dataset, &[], 1).await.unwrap();
+        assert_eq!((report.k, report.source.as_str()), (1, "dataset"));
+        let result = |name: &str| report.results.iter().find(|r| r.name == name).unwrap();
+        assert_eq!((result("vector").mrr, result("vector").missed), (0.5, 1));
+        assert_eq!(result("hybrid_a0.3").recall_at_k, 1.0);
+        assert_eq!(report.best, "hybrid_a0.3");
+
+        let strict = SearchEvalConfig {
+            name: "strict".into(),
+            min_score: Some(2.0),
+            ..Default::default()
+        };
+        let report = evaluate_dataset(&memory, &dataset, &[strict], 5).await.unwrap();
+        assert_eq!((report.results[0].missed, report.results[0].queries), (2, 2), "threshold drops every hit");
+    }
+}
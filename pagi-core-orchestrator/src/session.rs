@@ -0,0 +1,519 @@
+// Reasoning-session journal: ExecuteAction/RunPipeline steps and AccessMemory writes carrying a reasoning_id
+// are recorded per session; EndSession turns the journal into a digest (actions, outcomes, memory written)
+// stored in L2 (`session_summary:<id>`) and, when L4 is up, the session KB — so humans need not read raw logs.
+// A session belongs to the namespace that opened it: the digest keys and KB are scoped to it like AccessMemory's.
+// Each action also keeps an environment snapshot (fingerprint, allow-list revision, config hash, sha256 of the
+// files the skill read); the snapshots are stored as JSON next to the digest (`session_env:<id>`, the L4
+// `env_snapshots` payload) so a replay can tell code changes from environment drift with `drift`.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use chrono::{DateTime, FixedOffset, Utc};
+use dashmap::DashMap;
+use tonic::Status;
+use uuid::Uuid;
+
+use crate::clock::{self, Clock};
+use crate::memory_manager::MemoryManager;
+use crate::proto::pagi_proto::{ActionResponse, UpsertRequest, VectorPoint};
+use crate::provenance;
+
+/// Actions kept per session; older entries are counted but not itemized.
+const MAX_ACTIONS: usize = 200;
+/// Open sessions tracked; the least recently active is dropped beyond this.
+const MAX_SESSIONS: usize = 1024;
+/// Chars of each observation/error quoted in the digest.
+const OUTCOME_CHARS: usize = 120;
+
+/// Snapshot keys that identify code (bridge checkout, skill allow-list); any other key is environment.
+const CODE_KEYS: &[&str] = &["env.bridge_commit", "env.allow_list_hash", "env.allow_list_revision"];
+/// Prefix of snapshot keys holding the sha256 of a file the skill read.
+const INPUT_PREFIX: &str = "input:";
+
+/// What an action depended on: the `env.*` fingerprint entries of its metadata plus `input:<path>` → sha256
+/// for each file the runner reported reading. Empty when the dispatch reported neither (mock, RPC error).
+pub type EnvSnapshot = BTreeMap<String, String>;
+
+pub fn env_snapshot(metadata: &HashMap<String, String>) -> EnvSnapshot {
+    let mut snapshot: EnvSnapshot = metadata
+        .iter()
+        .filter(|(k, _)| k.starts_with("env."))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    if let Some(inputs) = metadata
+        .get("runner.inputs")
+        .and_then(|raw| serde_json::from_str::<BTreeMap<String, String>>(raw).ok())
+    {
+        snapshot.extend(inputs.into_iter().map(|(path, sha)| (format!("{}{}", INPUT_PREFIX, path), sha)));
+    }
+    snapshot
+}
+
+/// Snapshot keys whose values differ between two runs, split by cause.
+#[derive(Debug, Default, PartialEq)]
+pub struct Drift {
+    /// Bridge commit or allow-list changed: the code that ran is different.
+    pub code: Vec<String>,
+    /// Interpreter, platform, configuration or input files changed under the same code.
+    pub environment: Vec<String>,
+}
+
+impl Drift {
+    pub fn is_empty(&self) -> bool {
+        self.code.is_empty() && self.environment.is_empty()
+    }
+}
+
+/// Compare keys present in both snapshots; keys only one side recorded (another skill's inputs, an older
+/// orchestrator's fingerprint) are not drift.
+pub fn drift(recorded: &EnvSnapshot, current: &EnvSnapshot) -> Drift {
+    let mut out = Drift::default();
+    for (key, value) in recorded {
+        if current.get(key).is_some_and(|v| v != value) {
+            if CODE_KEYS.contains(&key.as_str()) {
+                out.code.push(key.clone());
+            } else {
+                out.environment.push(key.clone());
+            }
+        }
+    }
+    out
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionEntry {
+    pub skill: String,
+    pub success: bool,
+    pub dispatch_mode: String,
+    /// Observation on success, error otherwise (truncated).
+    pub outcome: String,
+    pub env: EnvSnapshot,
+}
+
+#[derive(Debug, Clone)]
+pub struct SessionLog {
+    /// First and latest recorded activity (journal clock).
+    pub started_at: DateTime<Utc>,
+    last_active: DateTime<Utc>,
+    pub actions: Vec<ActionEntry>,
+    /// Actions beyond MAX_ACTIONS (counted, not itemized).
+    pub dropped_actions: usize,
+    /// `L<layer>:<key>` for every memory write attributed to the session.
+    pub memory_writes: BTreeSet<String>,
+    /// Namespace of the caller that opened the session (empty: operator scope). Other tenants can neither add
+    /// to nor end it.
+    pub namespace: String,
+}
+
+impl SessionLog {
+    fn new(now: DateTime<Utc>, namespace: &str) -> Self {
+        Self {
+            started_at: now,
+            last_active: now,
+            actions: Vec::new(),
+            dropped_actions: 0,
+            memory_writes: BTreeSet::new(),
+            namespace: namespace.to_string(),
+        }
+    }
+
+    /// Operator scope (empty `namespace`) reaches every session; a tenant only its own.
+    fn visible_to(&self, namespace: &str) -> bool {
+        namespace.is_empty() || self.namespace == namespace
+    }
+
+    pub fn action_count(&self) -> usize {
+        self.actions.len() + self.dropped_actions
+    }
+
+    pub fn failed_count(&self) -> usize {
+        self.actions.iter().filter(|a| !a.success).count()
+    }
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    let s = s.trim().replace('\n', " ");
+    match s.char_indices().nth(max) {
+        Some((i, _)) => format!("{}…", &s[..i]),
+        None => s,
+    }
+}
+
+#[derive(Default)]
+pub struct SessionJournal {
+    sessions: DashMap<String, SessionLog>,
+    clock: Clock,
+}
+
+impl SessionJournal {
+    pub fn new(clock: Clock) -> Self {
+        Self {
+            sessions: DashMap::new(),
+            clock,
+        }
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    fn touch(&self, reasoning_id: &str, namespace: &str, f: impl FnOnce(&mut SessionLog)) {
+        if reasoning_id.is_empty() {
+            return;
+        }
+        if !self.sessions.contains_key(reasoning_id) && self.sessions.len() >= MAX_SESSIONS {
+            let oldest = self
+                .sessions
+                .iter()
+                .min_by_key(|e| e.value().last_active)
+                .map(|e| e.key().clone());
+            if let Some(id) = oldest {
+                eprintln!("[Session] dropping idle session {} (over {} open)", id, MAX_SESSIONS);
+                self.sessions.remove(&id);
+            }
+        }
+        let now = self.clock.now();
+        let mut log = self
+            .sessions
+            .entry(reasoning_id.to_string())
+            .or_insert_with(|| SessionLog::new(now, namespace));
+        if !log.visible_to(namespace) {
+            eprintln!("[Session] {:?} may not add to session {} of {:?}", namespace, reasoning_id, log.namespace);
+            return;
+        }
+        log.last_active = now;
+        f(&mut log);
+    }
+
+    /// Record one dispatched action by a caller in `namespace` (RPC errors count as failures).
+    pub fn record_action(
+        &self,
+        reasoning_id: &str,
+        namespace: &str,
+        skill: &str,
+        result: &Result<ActionResponse, Status>,
+    ) {
+        let entry = match result {
+            Ok(resp) => ActionEntry {
+                skill: skill.to_string(),
+                success: resp.success,
+                dispatch_mode: resp.dispatch_mode.clone(),
+                outcome: truncate(if resp.success { &resp.observation } else { &resp.error }, OUTCOME_CHARS),
+                env: env_snapshot(&resp.metadata),
+            },
+            Err(status) => ActionEntry {
+                skill: skill.to_string(),
+                success: false,
+                dispatch_mode: String::new(),
+                outcome: truncate(&format!("{:?}: {}", status.code(), status.message()), OUTCOME_CHARS),
+                env: EnvSnapshot::new(),
+            },
+        };
+        self.touch(reasoning_id, namespace, |log| {
+            if log.actions.len() < MAX_ACTIONS {
+                log.actions.push(entry);
+            } else {
+                log.dropped_actions += 1;
+            }
+        });
+    }
+
+    pub fn record_memory_write(&self, reasoning_id: &str, namespace: &str, layer: i32, key: &str) {
+        self.touch(reasoning_id, namespace, |log| {
+            log.memory_writes.insert(format!("L{}:{}", layer, key));
+        });
+    }
+
+    /// Close a session, returning its journal; None when nothing was recorded under `reasoning_id` or the
+    /// session belongs to another namespace.
+    pub fn finish(&self, reasoning_id: &str, namespace: &str) -> Option<SessionLog> {
+        self.sessions.remove_if(reasoning_id, |_, log| log.visible_to(namespace)).map(|(_, log)| log)
+    }
+}
+
+/// Human-readable digest of a session finished at `ended_at`, with times rendered in `tz`.
+pub fn summarize(reasoning_id: &str, log: &SessionLog, ended_at: DateTime<Utc>, tz: FixedOffset) -> String {
+    let mut out = format!(
+        "Session {} ({}s, {} to {}): {} action(s), {} failed, {} memory write(s).",
+        reasoning_id,
+        (ended_at - log.started_at).num_seconds().max(0),
+        clock::format_in(log.started_at, tz),
+        clock::format_in(ended_at, tz),
+        log.action_count(),
+        log.failed_count(),
+        log.memory_writes.len()
+    );
+    if !log.actions.is_empty() {
+        out.push_str("\nActions:");
+        for (i, a) in log.actions.iter().enumerate() {
+            let mode = if a.dispatch_mode.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", a.dispatch_mode)
+            };
+            let status = if a.success { "ok" } else { "FAILED" };
+            out.push_str(&format!("\n{}. {}{} {}: {}", i + 1, a.skill, mode, status, a.outcome));
+        }
+        if log.dropped_actions > 0 {
+            out.push_str(&format!("\n… {} more action(s) not itemized", log.dropped_actions));
+        }
+        out.push_str(&environment_lines(log));
+    }
+    if !log.memory_writes.is_empty() {
+        out.push_str("\nMemory written: ");
+        out.push_str(&log.memory_writes.iter().cloned().collect::<Vec<_>>().join(", "));
+    }
+    out
+}
+
+/// "Environment: …" from the first snapshotted action, then a line per action whose snapshot drifted from the
+/// previous snapshotted one.
+fn environment_lines(log: &SessionLog) -> String {
+    let mut snapshotted = log.actions.iter().enumerate().filter(|(_, a)| !a.env.is_empty());
+    let Some((_, first)) = snapshotted.next() else {
+        return String::new();
+    };
+    let get = |key: &str| first.env.get(key).map_or("?", String::as_str);
+    let short = |key: &str| get(key).get(..12).unwrap_or(get(key));
+    let mut out = format!(
+        "\nEnvironment: python={} platform={} bridge={} allow_list={} config={}",
+        get("env.python_version"),
+        get("env.platform"),
+        get("env.bridge_commit"),
+        get("env.allow_list_revision"),
+        short("env.config_hash")
+    );
+    let mut previous = &first.env;
+    for (i, a) in snapshotted {
+        let d = drift(previous, &a.env);
+        if !d.is_empty() {
+            let mut causes = Vec::new();
+            if !d.code.is_empty() {
+                causes.push(format!("code {}", d.code.join(", ")));
+            }
+            if !d.environment.is_empty() {
+                causes.push(format!("environment {}", d.environment.join(", ")));
+            }
+            out.push_str(&format!("\nDrift at action {} ({}): {}", i + 1, a.skill, causes.join("; ")));
+        }
+        previous = &a.env;
+    }
+    out
+}
+
+/// Per-action environment snapshots as JSON (`[{"action": n, "skill": …, "env": {…}}]`); None when no action
+/// recorded one.
+pub fn env_snapshots_json(log: &SessionLog) -> Option<String> {
+    let snapshots: Vec<serde_json::Value> = log
+        .actions
+        .iter()
+        .enumerate()
+        .filter(|(_, a)| !a.env.is_empty())
+        .map(|(i, a)| serde_json::json!({ "action": i + 1, "skill": a.skill, "env": a.env }))
+        .collect();
+    if snapshots.is_empty() {
+        return None;
+    }
+    serde_json::to_string(&snapshots).ok()
+}
+
+/// L4 collection for session digests (PAGI_SESSION_KB, default kb_sessions).
+pub fn summary_kb() -> String {
+    std::env::var("PAGI_SESSION_KB")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "kb_sessions".to_string())
+}
+
+/// Embed the digest (provenance hash embedder) and upsert it into `kb`, with the environment snapshots (if any)
+/// as the `env_snapshots` payload; caller checks L4 is enabled.
+pub async fn store_summary(
+    memory: &MemoryManager,
+    kb: &str,
+    reasoning_id: &str,
+    summary: &str,
+    env_snapshots: Option<&str>,
+) -> Result<(), String> {
+    memory.ensure_kb(kb).await.map_err(|e| e.to_string())?;
+    let mut payload = HashMap::from([
+        ("reasoning_id".to_string(), reasoning_id.to_string()),
+        ("at".to_string(), memory.clock().now_secs().to_string()),
+        ("content".to_string(), summary.to_string()),
+    ]);
+    if let Some(snapshots) = env_snapshots {
+        payload.insert("env_snapshots".to_string(), snapshots.to_string());
+    }
+    let point = VectorPoint {
+        id: Uuid::new_v4().to_string(),
+        vector: provenance::hash_embed(summary, memory.kb_dim(kb)),
+        payload,
+        ..Default::default()
+    };
+    memory
+        .upsert_vectors(UpsertRequest {
+            kb_name: kb.to_string(),
+            points: vec![point],
+            ..Default::default()
+        })
+        .await
+        .map(|_| ())
+        .map_err(|e| e.message().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    fn ok(observation: &str) -> Result<ActionResponse, Status> {
+        Ok(ActionResponse {
+            observation: observation.to_string(),
+            success: true,
+            dispatch_mode: "real".to_string(),
+            ..Default::default()
+        })
+    }
+
+    /// A journal whose r1 session spans 90s: a peek, a timed-out test run and a repeated L2 write.
+    fn journaled() -> SessionJournal {
+        let manual = ManualClock::at(1_700_000_000);
+        let journal = SessionJournal::new(manual.clone().into());
+        journal.record_action("r1", "", "peek_file", &ok("Observation: fn main() {}"));
+        manual.advance(std::time::Duration::from_secs(90));
+        journal.record_action("r1", "", "run_tests", &Err(Status::deadline_exceeded("skill timed out")));
+        journal.record_memory_write("r1", "", 2, "goal");
+        journal.record_memory_write("r1", "", 2, "goal");
+        journal.record_action("", "", "ignored", &ok("no session"));
+        journal.record_action("r2", "", "list_dir", &ok("a.rs"));
+        journal
+    }
+
+    fn digest(journal: &SessionJournal) -> String {
+        let log = journal.finish("r1", "").unwrap();
+        summarize("r1", &log, journal.clock().now(), clock::parse_timezone("+02:00").unwrap())
+    }
+
+    #[test]
+    fn sessions_count_actions_and_failures() {
+        let log = journaled().finish("r1", "").unwrap();
+        assert_eq!((log.action_count(), log.failed_count()), (2, 1));
+    }
+
+    #[test]
+    fn digests_span_the_session_in_the_configured_timezone() {
+        let digest = digest(&journaled());
+        let span = "Session r1 (90s, 2023-11-15T00:13:20+02:00 to 2023-11-15T00:14:50+02:00): ";
+        assert!(digest.starts_with(span), "{}", digest);
+    }
+
+    #[test]
+    fn digests_list_actions_and_distinct_memory_writes() {
+        let digest = digest(&journaled());
+        assert!(digest.contains("2 action(s), 1 failed, 1 memory write(s)"), "{}", digest);
+        assert!(digest.contains("1. peek_file [real] ok: Observation: fn main() {}"), "{}", digest);
+        assert!(digest.contains("2. run_tests FAILED: DeadlineExceeded: skill timed out"), "{}", digest);
+        assert!(digest.ends_with("Memory written: L2:goal"), "{}", digest);
+    }
+
+    #[test]
+    fn finish_closes_only_its_own_session() {
+        let journal = journaled();
+        assert!(journal.finish("r1", "").is_some());
+        assert!(journal.finish("r1", "").is_none());
+        assert!(journal.finish("r2", "").is_some());
+    }
+
+    #[test]
+    fn sessions_are_confined_to_the_opening_namespace() {
+        let journal = SessionJournal::default();
+        journal.record_action("r1", "acme", "peek_file", &ok("a.rs"));
+        journal.record_action("r1", "globex", "list_dir", &ok("b.rs"));
+        assert!(journal.finish("r1", "globex").is_none());
+        let log = journal.finish("r1", "acme").unwrap();
+        assert_eq!(log.action_count(), 1, "globex's action is not added");
+    }
+
+    #[test]
+    fn operator_scope_ends_tenant_sessions() {
+        let journal = SessionJournal::default();
+        journal.record_memory_write("r1", "acme", 2, "goal@acme");
+        assert_eq!(journal.finish("r1", "").unwrap().namespace, "acme");
+    }
+
+    fn run(bridge: &str, config: &str, readme_sha: &str) -> Result<ActionResponse, Status> {
+        Ok(ActionResponse {
+            observation: "ok".to_string(),
+            success: true,
+            error: String::new(),
+            dispatch_mode: "real".to_string(),
+            metadata: HashMap::from([
+                ("env.python_version".to_string(), "3.11.4".to_string()),
+                ("env.bridge_commit".to_string(), bridge.to_string()),
+                ("env.config_hash".to_string(), config.to_string()),
+                ("runner.inputs".to_string(), format!(r#"{{"README.md":"{}"}}"#, readme_sha)),
+                ("runner.metric.duration_ms".to_string(), "3".to_string()),
+            ]),
+        })
+    }
+
+    /// Three peeks (README.md changes, then the bridge commit does) and a failed fourth.
+    fn snapshotted() -> SessionLog {
+        let journal = SessionJournal::default();
+        journal.record_action("r1", "", "peek_file", &run("abc", "c1", "s1"));
+        journal.record_action("r1", "", "peek_file", &run("abc", "c1", "s2"));
+        journal.record_action("r1", "", "peek_file", &run("def", "c1", "s2"));
+        journal.record_action("r1", "", "peek_file", &Err(Status::unavailable("bridge down")));
+        journal.finish("r1", "").unwrap()
+    }
+
+    #[test]
+    fn snapshots_record_inputs_but_not_metrics() {
+        let env = &snapshotted().actions[0].env;
+        assert_eq!(env["input:README.md"], "s1");
+        assert!(!env.contains_key("runner.metric.duration_ms"));
+    }
+
+    #[test]
+    fn failed_actions_have_no_snapshot() {
+        assert!(snapshotted().actions[3].env.is_empty());
+    }
+
+    #[test]
+    fn drift_separates_code_from_environment() {
+        let log = snapshotted();
+        assert_eq!(
+            drift(&log.actions[0].env, &log.actions[2].env),
+            Drift {
+                code: vec!["env.bridge_commit".to_string()],
+                environment: vec!["input:README.md".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn drift_compares_only_shared_keys() {
+        let recorded = snapshotted().actions.swap_remove(0).env;
+        let mut other_skill = recorded.clone();
+        other_skill.remove("input:README.md");
+        other_skill.insert("input:src/main.rs".to_string(), "s9".to_string());
+        assert!(drift(&recorded, &other_skill).is_empty());
+    }
+
+    #[test]
+    fn digests_report_the_environment_and_each_drift() {
+        let log = snapshotted();
+        let digest = summarize("r1", &log, log.started_at, clock::parse_timezone("").unwrap());
+        let environment = "\nEnvironment: python=3.11.4 platform=? bridge=abc allow_list=? config=c1";
+        assert!(digest.contains(environment), "{}", digest);
+        assert!(digest.contains("\nDrift at action 2 (peek_file): environment input:README.md"), "{}", digest);
+        assert!(digest.ends_with("\nDrift at action 3 (peek_file): code env.bridge_commit"), "{}", digest);
+    }
+
+    #[test]
+    fn env_snapshots_serialize_successful_actions() {
+        let json: serde_json::Value = serde_json::from_str(&env_snapshots_json(&snapshotted()).unwrap()).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 3);
+        assert_eq!(json[2]["env"]["env.bridge_commit"], "def");
+    }
+}
@@ -0,0 +1,230 @@
+// RunSimulation: scenario-driven heal verification (propose → HITL → apply → log) with per-stage results.
+// Replaces the fixed rust_core SimulateError flow; SimulateError now runs the legacy default scenario.
+
+use std::io::Write;
+use std::time::Instant;
+
+use tonic::{Code, Status};
+
+use crate::approval::ApprovalPolicy;
+use crate::proto::pagi_proto::{
+    ApplyRequest, PatchRequest, SimulationRequest, SimulationResponse, SimulationStage,
+};
+use crate::reasoning_id::ReasoningId;
+use crate::watchdog::Watchdog;
+
+/// How the simulated reviewer answers the HITL gate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Approval {
+    /// Pass approved=true on ApplyPatch.
+    Approve,
+    /// Never approve; a HITL-gated patch must be denied (policy-denied path).
+    Deny,
+    /// Poll PAGI_APPROVE_FLAG like a real heal cycle (legacy SimulateError behavior).
+    Flag,
+}
+
+impl Approval {
+    fn parse(raw: &str) -> Result<Self, Status> {
+        match raw.trim().to_lowercase().as_str() {
+            "" | "flag" => Ok(Approval::Flag),
+            "approve" => Ok(Approval::Approve),
+            "deny" => Ok(Approval::Deny),
+            other => Err(Status::invalid_argument(format!(
+                "unknown approval mode {:?} (expected approve, deny or flag)",
+                other
+            ))),
+        }
+    }
+}
+
+fn stage(name: &str, success: bool, detail: String, started: Instant) -> SimulationStage {
+    SimulationStage {
+        name: name.to_string(),
+        success,
+        detail,
+        duration_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+/// Run one scenario end to end. Stage failures are reported in the response, not as RPC errors;
+/// `expectation_met` says whether the observed apply outcome matched the scenario.
+pub async fn run_simulation(
+    watchdog: &Watchdog,
+    req: SimulationRequest,
+) -> Result<SimulationResponse, Status> {
+    let approval = Approval::parse(&req.approval)?;
+    let component = if req.component.is_empty() {
+        "rust_core".to_string()
+    } else {
+        req.component
+    };
+    let error_trace = if req.error_trace.is_empty() {
+        format!("Simulated {} error for verification", component)
+    } else {
+        req.error_trace
+    };
+    let mut stages = Vec::new();
+
+    let t = Instant::now();
+    let propose = watchdog
+        .propose_patch(PatchRequest {
+            error_trace,
+            component: component.clone(),
+            reasoning_id: ReasoningId::generate(watchdog.clock()).to_string(),
+        })
+        .await;
+    let propose = match propose {
+        Ok(p) => p,
+        Err(e) => {
+            stages.push(stage("propose", false, e.message().to_string(), t));
+            return Ok(SimulationResponse {
+                patch_id: String::new(),
+                stages,
+                expectation_met: false,
+            });
+        }
+    };
+    let hitl_as_expected = !req.check_requires_hitl || propose.requires_hitl == req.expect_requires_hitl;
+    stages.push(stage(
+        "propose",
+        hitl_as_expected,
+        format!("requires_hitl={}", propose.requires_hitl),
+        t,
+    ));
+
+    let t = Instant::now();
+    let approved = match approval {
+        Approval::Approve => true,
+        Approval::Deny => false,
+        Approval::Flag => {
+            !propose.requires_hitl
+                || watchdog
+                    .await_approval(&propose.patch_id, &ApprovalPolicy::from_env())
+                    .await
+        }
+    };
+    stages.push(stage(
+        "hitl",
+        true,
+        format!("mode={:?} approved={}", approval, approved),
+        t,
+    ));
+
+    let t = Instant::now();
+    let result = watchdog
+        .apply_patch_with_options(
+            ApplyRequest {
+                patch_id: propose.patch_id.clone(),
+                approved,
+                component: component.clone(),
+                requires_hitl: propose.requires_hitl,
+            },
+            req.inject_test_failure,
+        )
+        .await;
+    let expected_code = if propose.requires_hitl && !approved {
+        Some(Code::PermissionDenied)
+    } else if req.inject_test_failure {
+        Some(Code::Internal)
+    } else {
+        None
+    };
+    let (apply_ok, apply_detail, observed_code) = match &result {
+        Ok(r) => (true, format!("commit_hash={}", r.commit_hash), None),
+        Err(e) => (false, format!("{:?}: {}", e.code(), e.message()), Some(e.code())),
+    };
+    stages.push(stage("apply", apply_ok, apply_detail, t));
+
+    let t = Instant::now();
+    let log_path = std::env::var("PAGI_SELF_HEAL_LOG").unwrap_or_else(|_| "agent_actions.log".into());
+    let logged = std::fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(&log_path)
+        .and_then(|mut f| writeln!(f, "Heal cycle simulated"))
+        .is_ok();
+    stages.push(stage("log", logged, log_path, t));
+
+    Ok(SimulationResponse {
+        patch_id: propose.patch_id,
+        stages,
+        expectation_met: hitl_as_expected && observed_code == expected_code,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_manager::MemoryManager;
+    use crate::watchdog::tests::lock_test_env;
+    use std::path::PathBuf;
+
+    async fn temp_watchdog() -> (std::sync::Arc<Watchdog>, PathBuf) {
+        let registry = std::env::temp_dir().join(format!("pagi_sim_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&registry).unwrap();
+        let memory = MemoryManager::new_async().await.unwrap();
+        let cwd = std::env::current_dir().unwrap();
+        let watchdog = Watchdog::new(registry.clone(), memory, cwd.clone(), cwd);
+        (watchdog, registry)
+    }
+
+    #[tokio::test]
+    async fn simulation_covers_policy_denied_and_injected_failure() {
+        let _g = lock_test_env().await;
+        std::env::set_var("PAGI_DISABLE_QDRANT", "true");
+        std::env::set_var("PAGI_SKIP_APPLY_TEST", "true");
+        std::env::set_var("PAGI_AUTO_COMMIT_SELF_PATCH", "false");
+        let (watchdog, registry) = temp_watchdog().await;
+
+        let denied = run_simulation(
+            &watchdog,
+            SimulationRequest {
+                component: "rust_core".to_string(),
+                approval: "deny".to_string(),
+                check_requires_hitl: true,
+                expect_requires_hitl: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert!(denied.expectation_met);
+        let apply = denied.stages.iter().find(|s| s.name == "apply").unwrap();
+        assert!(!apply.success);
+        assert!(apply.detail.contains("PermissionDenied"));
+
+        let failed = run_simulation(
+            &watchdog,
+            SimulationRequest {
+                component: "python_skill".to_string(),
+                // Own trace: the failed apply puts this fingerprint into heal backoff.
+                error_trace: "Simulated python_skill test failure".to_string(),
+                approval: "approve".to_string(),
+                inject_test_failure: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert!(failed.expectation_met);
+
+        let applied = run_simulation(
+            &watchdog,
+            SimulationRequest {
+                component: "python_skill".to_string(),
+                approval: "approve".to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert!(applied.expectation_met);
+        assert_eq!(applied.stages.len(), 4);
+
+        let _ = std::fs::remove_dir_all(registry);
+        std::env::remove_var("PAGI_AUTO_COMMIT_SELF_PATCH");
+        std::env::remove_var("PAGI_SKIP_APPLY_TEST");
+        std::env::remove_var("PAGI_DISABLE_QDRANT");
+    }
+}
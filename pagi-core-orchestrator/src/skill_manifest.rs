@@ -0,0 +1,277 @@
+// Skill manifests: `<skill>.json` beside the skill file types params and may declare the filesystem the skill
+// is confined to:
+//
+//   {"params": {"path": {"type": "path", "access": "write", "root": "data"}},
+//    "filesystem": {"read": ["src/skills", "data:reports"], "write": ["data:out"]}}
+//
+// Filesystem entries are directories relative to a root: the bridge dir (default, or "bridge:") or the data root
+// (PAGI_SKILL_DATA_ROOT, "data:"); write entries are readable too. Path-typed params resolve against their root
+// ("root": "data" params are handed to the skill as absolute paths) and must fall inside the declared set for
+// their access ("read" by default). The set is also passed to v2 runners, which refuse other file access.
+// Skills without a "filesystem" section keep the traversal check only.
+
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+impl Access {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Access::Read => "read",
+            Access::Write => "write",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Root {
+    Bridge,
+    Data,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathParam {
+    pub name: String,
+    pub access: Access,
+    pub root: Root,
+}
+
+/// Absolute directories a skill may read and write.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Filesystem {
+    pub read: Vec<PathBuf>,
+    pub write: Vec<PathBuf>,
+}
+
+impl Filesystem {
+    /// True when `path` (absolute) lies under a directory declared for `access`.
+    pub fn permits(&self, path: &Path, access: Access) -> bool {
+        let path = resolve(path);
+        let under = |dirs: &[PathBuf]| dirs.iter().any(|d| path.starts_with(d));
+        match access {
+            Access::Read => under(&self.read) || under(&self.write),
+            Access::Write => under(&self.write),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SkillManifest {
+    pub path_params: Vec<PathParam>,
+    /// None when the manifest declares no filesystem (unconfined).
+    pub filesystem: Option<Filesystem>,
+}
+
+/// Where a skill's params resolve: the bridge dir and the optional data root.
+#[derive(Debug, Clone)]
+pub struct Roots {
+    pub bridge: PathBuf,
+    pub data: Option<PathBuf>,
+}
+
+impl Roots {
+    /// Data root from PAGI_SKILL_DATA_ROOT (relative to the bridge dir when not absolute).
+    pub fn from_env(bridge_dir: &Path) -> Self {
+        let data = std::env::var("PAGI_SKILL_DATA_ROOT")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .map(|s| bridge_dir.join(s));
+        Self {
+            bridge: bridge_dir.to_path_buf(),
+            data,
+        }
+    }
+
+    pub fn dir(&self, root: Root) -> Option<&Path> {
+        match root {
+            Root::Bridge => Some(&self.bridge),
+            Root::Data => self.data.as_deref(),
+        }
+    }
+
+    /// "data:out" → <data root>/out; "src/skills" or "bridge:src/skills" → <bridge>/src/skills.
+    fn entry(&self, spec: &str) -> Result<PathBuf, String> {
+        let (root, rel) = match spec.split_once(':') {
+            Some(("data", rel)) => (Root::Data, rel),
+            Some(("bridge", rel)) => (Root::Bridge, rel),
+            Some((other, _)) => return Err(format!("unknown root {:?} (expected bridge or data)", other)),
+            None => (Root::Bridge, spec),
+        };
+        let rel = Path::new(rel.trim());
+        if rel.components().any(|c| !matches!(c, Component::Normal(_) | Component::CurDir)) {
+            return Err(format!("{:?} must be relative without '..'", spec));
+        }
+        let base = self
+            .dir(root)
+            .ok_or_else(|| format!("{:?} needs PAGI_SKILL_DATA_ROOT", spec))?;
+        Ok(resolve(&base.join(rel)))
+    }
+}
+
+/// Manifest of a skill: `<skill_path>.json` when the skill file is known, else `src/skills/<skill>.json`.
+/// Missing or unreadable manifests declare nothing; bad filesystem entries are skipped with a warning (so a
+/// typo narrows what the skill may touch rather than widening it).
+pub fn load(roots: &Roots, skill_name: &str, skill_path: Option<&Path>) -> SkillManifest {
+    let path = match skill_path {
+        Some(p) => roots.bridge.join(p).with_extension("json"),
+        None => roots.bridge.join("src/skills").join(format!("{}.json", skill_name)),
+    };
+    let Ok(raw) = std::fs::read_to_string(&path) else {
+        return SkillManifest::default();
+    };
+    match serde_json::from_str::<serde_json::Value>(&raw) {
+        Ok(v) => parse(&v, roots, &path),
+        Err(e) => {
+            eprintln!("[Watchdog] ignoring skill manifest {}: {}", path.display(), e);
+            SkillManifest::default()
+        }
+    }
+}
+
+fn parse(v: &serde_json::Value, roots: &Roots, source: &Path) -> SkillManifest {
+    let path_params = v["params"]
+        .as_object()
+        .map(|params| {
+            params
+                .iter()
+                .filter(|(_, spec)| spec["type"] == "path")
+                .map(|(name, spec)| PathParam {
+                    name: name.clone(),
+                    access: if spec["access"] == "write" { Access::Write } else { Access::Read },
+                    root: if spec["root"] == "data" { Root::Data } else { Root::Bridge },
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let filesystem = v["filesystem"].as_object().map(|fs| {
+        let entries = |key: &str| -> Vec<PathBuf> {
+            fs.get(key)
+                .and_then(|e| e.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|e| e.as_str())
+                .filter_map(|spec| match roots.entry(spec) {
+                    Ok(dir) => Some(dir),
+                    Err(e) => {
+                        eprintln!("[Watchdog] skill manifest {}: skipping {} entry: {}", source.display(), key, e);
+                        None
+                    }
+                })
+                .collect()
+        };
+        Filesystem {
+            read: entries("read"),
+            write: entries("write"),
+        }
+    });
+    SkillManifest {
+        path_params,
+        filesystem,
+    }
+}
+
+/// Absolute form of `path` with symlinks in its longest existing prefix resolved, so a link inside an allowed
+/// directory cannot point a skill outside it.
+pub fn resolve(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(real) = existing.canonicalize() {
+            return rest.iter().rev().fold(real, |acc: PathBuf, part| acc.join(part));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A bridge and data dir with an `export` manifest declaring one readable and several writable roots.
+    fn exported() -> (PathBuf, Roots) {
+        let temp = std::env::temp_dir().join(format!("pagi_manifest_{}", uuid::Uuid::new_v4()));
+        let bridge = temp.join("bridge");
+        std::fs::create_dir_all(bridge.join("src/skills")).unwrap();
+        std::fs::create_dir_all(temp.join("data/out")).unwrap();
+        std::fs::write(
+            bridge.join("src/skills/export.json"),
+            r#"{"params": {"src": {"type": "path"}, "dest": {"type": "path", "access": "write", "root": "data"}},
+                "filesystem": {"read": ["src/skills"], "write": ["data:out", "/etc", "bridge:../x", "s3:bucket"]}}"#,
+        )
+        .unwrap();
+        let roots = Roots {
+            bridge,
+            data: Some(temp.join("data")),
+        };
+        (temp, roots)
+    }
+
+    fn filesystem(roots: &Roots) -> Filesystem {
+        load(roots, "export", None).filesystem.unwrap()
+    }
+
+    #[test]
+    fn path_params_declare_their_access_and_root() {
+        let (temp, roots) = exported();
+        let manifest = load(&roots, "export", None);
+        let dest = manifest.path_params.iter().find(|p| p.name == "dest").unwrap();
+        assert_eq!((dest.access, dest.root), (Access::Write, Root::Data));
+        let _ = std::fs::remove_dir_all(temp);
+    }
+
+    #[test]
+    fn absolute_escaping_and_unknown_root_entries_are_skipped() {
+        let (temp, roots) = exported();
+        assert_eq!(filesystem(&roots).write.len(), 1);
+        let _ = std::fs::remove_dir_all(temp);
+    }
+
+    #[test]
+    fn writable_paths_are_also_readable() {
+        let (temp, roots) = exported();
+        let (fs, data) = (filesystem(&roots), resolve(&temp.join("data")));
+        assert!(fs.permits(&data.join("out/report.csv"), Access::Write));
+        assert!(fs.permits(&data.join("out/new/deeper.csv"), Access::Read));
+        assert!(!fs.permits(&data.join("secrets.txt"), Access::Read));
+        let _ = std::fs::remove_dir_all(temp);
+    }
+
+    #[test]
+    fn readable_paths_are_not_writable() {
+        let (temp, roots) = exported();
+        let skill = roots.bridge.join("src/skills/peek_file.py");
+        assert!(filesystem(&roots).permits(&skill, Access::Read));
+        assert!(!filesystem(&roots).permits(&skill, Access::Write));
+        let _ = std::fs::remove_dir_all(temp);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_out_of_a_declared_root_are_followed() {
+        let (temp, roots) = exported();
+        let data = resolve(&temp.join("data"));
+        std::os::unix::fs::symlink(&temp, data.join("out/escape")).unwrap();
+        assert!(!filesystem(&roots).permits(&data.join("out/escape/bridge/x"), Access::Write));
+        let _ = std::fs::remove_dir_all(temp);
+    }
+
+    #[test]
+    fn skills_without_a_manifest_declare_nothing() {
+        let (temp, roots) = exported();
+        assert!(load(&roots, "missing", None).filesystem.is_none());
+        let _ = std::fs::remove_dir_all(temp);
+    }
+}
@@ -0,0 +1,220 @@
+// Per-method latency SLOs (PAGI_SLOS, e.g. "ExecuteAction:p95<2s,SemanticSearch:p99<500ms") evaluated
+// in the background over the metrics store. Breach and recovery transitions are logged and POSTed as
+// slo_breach / slo_recovered events to PAGI_SLO_WEBHOOK_URL.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::approval;
+use crate::metrics::MetricsStore;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Slo {
+    pub method: String,
+    /// Percentile in (0, 100].
+    pub percentile: f64,
+    pub threshold: Duration,
+}
+
+fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    if let Some(ms) = s.strip_suffix("ms") {
+        ms.trim().parse().ok().map(Duration::from_millis)
+    } else if let Some(secs) = s.strip_suffix('s') {
+        secs.trim().parse::<f64>().ok().filter(|v| *v >= 0.0).map(Duration::from_secs_f64)
+    } else {
+        s.parse().ok().map(Duration::from_millis)
+    }
+}
+
+impl Slo {
+    /// `Method:pNN<duration>`; duration in ms (bare or "ms" suffix) or seconds ("s" suffix).
+    pub fn parse(entry: &str) -> Option<Self> {
+        let (method, rule) = entry.trim().split_once(':')?;
+        let (pct, threshold) = rule.trim().strip_prefix('p')?.split_once('<')?;
+        let percentile: f64 = pct.trim().parse().ok().filter(|p| *p > 0.0 && *p <= 100.0)?;
+        Some(Self {
+            method: method.trim().to_string(),
+            percentile,
+            threshold: parse_duration(threshold)?,
+        })
+    }
+
+    fn label(&self) -> String {
+        format!("{} p{}<{}ms", self.method, self.percentile, self.threshold.as_millis())
+    }
+}
+
+pub struct SloEvaluator {
+    slos: Vec<Slo>,
+    /// Latency window evaluated (PAGI_SLO_WINDOW_SECS, default 300).
+    window: Duration,
+    /// Fewer samples than this in the window → no verdict (PAGI_SLO_MIN_SAMPLES, default 20).
+    min_samples: usize,
+    /// SLO label -> currently breached.
+    breached: HashMap<String, bool>,
+}
+
+impl SloEvaluator {
+    pub fn new(slos: Vec<Slo>, window: Duration, min_samples: usize) -> Self {
+        Self {
+            slos,
+            window,
+            min_samples: min_samples.max(1),
+            breached: HashMap::new(),
+        }
+    }
+
+    /// None when PAGI_SLOS is unset or has no valid entries.
+    pub fn from_env() -> Option<Self> {
+        let raw = std::env::var("PAGI_SLOS").unwrap_or_default();
+        let mut slos = Vec::new();
+        for entry in raw.split(',').filter(|e| !e.trim().is_empty()) {
+            match Slo::parse(entry) {
+                Some(slo) => slos.push(slo),
+                None => eprintln!("[SLO] ignoring invalid entry {:?} (expected Method:p95<2s)", entry),
+            }
+        }
+        if slos.is_empty() {
+            return None;
+        }
+        let num = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(default)
+        };
+        Some(Self::new(
+            slos,
+            Duration::from_secs(num("PAGI_SLO_WINDOW_SECS", 300)),
+            num("PAGI_SLO_MIN_SAMPLES", 20) as usize,
+        ))
+    }
+
+    /// Alert events for SLOs whose breached state changed since the last evaluation.
+    pub fn evaluate(&mut self, metrics: &MetricsStore) -> Vec<serde_json::Value> {
+        let mut events = Vec::new();
+        for slo in &self.slos {
+            let Some((observed, samples)) = metrics.percentile(&slo.method, slo.percentile, self.window) else {
+                continue;
+            };
+            if samples < self.min_samples {
+                continue;
+            }
+            let breached = observed > slo.threshold;
+            let label = slo.label();
+            let was = self.breached.insert(label.clone(), breached).unwrap_or(false);
+            if breached == was {
+                continue;
+            }
+            events.push(serde_json::json!({
+                "event": if breached { "slo_breach" } else { "slo_recovered" },
+                "slo": label,
+                "method": slo.method,
+                "percentile": slo.percentile,
+                "threshold_ms": slo.threshold.as_millis() as u64,
+                "observed_ms": observed.as_millis() as u64,
+                "samples": samples,
+                "window_secs": self.window.as_secs(),
+                "at": chrono::Utc::now().to_rfc3339(),
+            }));
+        }
+        events
+    }
+}
+
+/// Background evaluator every PAGI_SLO_EVAL_SECS (default 60); no-op when PAGI_SLOS is unset.
+pub fn spawn_evaluator(metrics: Arc<MetricsStore>) {
+    let Some(mut evaluator) = SloEvaluator::from_env() else {
+        return;
+    };
+    let secs = std::env::var("PAGI_SLO_EVAL_SECS")
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or(60u64)
+        .max(1);
+    let webhook_url = std::env::var("PAGI_SLO_WEBHOOK_URL")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    eprintln!(
+        "[SLO] evaluating {} every {}s",
+        evaluator.slos.iter().map(Slo::label).collect::<Vec<_>>().join(", "),
+        secs
+    );
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(secs));
+        loop {
+            interval.tick().await;
+            for event in evaluator.evaluate(&metrics) {
+                eprintln!("[SLO] {}", event);
+                if let Some(url) = webhook_url.as_deref() {
+                    approval::send_reminder(url, &event).await;
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluator() -> (MetricsStore, SloEvaluator) {
+        let slo = Slo::parse("ExecuteAction:p50<100ms").unwrap();
+        (MetricsStore::new(), SloEvaluator::new(vec![slo], Duration::from_secs(60), 3))
+    }
+
+    fn record(metrics: &MetricsStore, count: usize, ms: u64) {
+        for _ in 0..count {
+            metrics.record_latency("ExecuteAction", Duration::from_millis(ms));
+        }
+    }
+
+    #[test]
+    fn slos_parse_method_percentile_and_threshold() {
+        assert_eq!(
+            Slo::parse("ExecuteAction:p95<2s"),
+            Some(Slo {
+                method: "ExecuteAction".into(),
+                percentile: 95.0,
+                threshold: Duration::from_secs(2),
+            })
+        );
+        assert_eq!(Slo::parse(" SemanticSearch : p99.9<500ms").unwrap().threshold, Duration::from_millis(500));
+    }
+
+    #[test]
+    fn malformed_slos_are_rejected() {
+        assert!(Slo::parse("ExecuteAction:p0<2s").is_none());
+        assert!(Slo::parse("ExecuteAction<2s").is_none());
+    }
+
+    #[test]
+    fn nothing_fires_below_the_minimum_samples() {
+        let (metrics, mut eval) = evaluator();
+        record(&metrics, 2, 500);
+        assert!(eval.evaluate(&metrics).is_empty());
+    }
+
+    #[test]
+    fn breaches_alert_once_with_the_observed_latency() {
+        let (metrics, mut eval) = evaluator();
+        record(&metrics, 3, 500);
+        let events = eval.evaluate(&metrics);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["event"], "slo_breach");
+        assert_eq!(events[0]["observed_ms"], 500);
+        assert!(eval.evaluate(&metrics).is_empty(), "no repeat while still breached");
+    }
+
+    #[test]
+    fn recovery_is_alerted() {
+        let (metrics, mut eval) = evaluator();
+        record(&metrics, 3, 500);
+        eval.evaluate(&metrics);
+        record(&metrics, 4, 10);
+        assert_eq!(eval.evaluate(&metrics)[0]["event"], "slo_recovered");
+    }
+}
@@ -0,0 +1,160 @@
+// Apply-time smoke commands: config-declared per-component commands run after the test step, each in
+// a fresh sandbox dir holding the proposed code (e.g. launch a binary and probe its health endpoint).
+// Commands are argv (no shell); `{sandbox}` and `{patch}` in args expand to the sandbox dir and patch file.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::inflight;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmokeCommand {
+    pub component: String,
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl SmokeCommand {
+    pub fn label(&self) -> String {
+        std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SmokeConfig {
+    /// PAGI_SMOKE_COMMANDS: `component=program arg...` entries separated by `;`.
+    pub commands: Vec<SmokeCommand>,
+    /// Per-command limit (PAGI_SMOKE_TIMEOUT_SECS, default 60).
+    pub timeout: Duration,
+}
+
+impl SmokeConfig {
+    pub fn from_env() -> Self {
+        let timeout = std::env::var("PAGI_SMOKE_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(60u64)
+            .max(1);
+        Self {
+            commands: parse_commands(&std::env::var("PAGI_SMOKE_COMMANDS").unwrap_or_default()),
+            timeout: Duration::from_secs(timeout),
+        }
+    }
+
+    pub fn for_component(&self, component: &str) -> Vec<&SmokeCommand> {
+        self.commands.iter().filter(|c| c.component == component).collect()
+    }
+}
+
+/// Parse `component=program arg...;...`; malformed entries are logged and skipped.
+pub fn parse_commands(raw: &str) -> Vec<SmokeCommand> {
+    raw.split(';')
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .filter_map(|entry| {
+            let parsed = entry.split_once('=').and_then(|(component, cmd)| {
+                let mut words = cmd.split_whitespace().map(str::to_string);
+                let program = words.next()?;
+                let component = component.trim();
+                (!component.is_empty()).then(|| SmokeCommand {
+                    component: component.to_string(),
+                    program,
+                    args: words.collect(),
+                })
+            });
+            if parsed.is_none() {
+                eprintln!("[Smoke] ignoring malformed PAGI_SMOKE_COMMANDS entry {:?}", entry);
+            }
+            parsed
+        })
+        .collect()
+}
+
+/// Fresh sandbox under the temp dir with the proposed code written to `patch.<ext>`.
+pub fn prepare_sandbox(patch_id: &str, ext: &str, code: &str) -> std::io::Result<(PathBuf, PathBuf)> {
+    let sandbox = std::env::temp_dir().join(format!("pagi_smoke_{}_{}", patch_id, uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&sandbox)?;
+    let patch_file = sandbox.join(format!("patch.{}", ext));
+    std::fs::write(&patch_file, code)?;
+    Ok((sandbox, patch_file))
+}
+
+/// Run `commands` in order from `dir`; the first failure (non-zero exit, spawn error or timeout) stops
+/// the run. Children are recorded for AbortRequest and killed on drop. Ok carries the command labels.
+pub async fn run(
+    commands: &[&SmokeCommand],
+    dir: &Path,
+    sandbox: &Path,
+    patch_file: &Path,
+    timeout: Duration,
+) -> Result<Vec<String>, String> {
+    let expand = |arg: &str| {
+        arg.replace("{sandbox}", &sandbox.display().to_string())
+            .replace("{patch}", &patch_file.display().to_string())
+    };
+    let mut passed = Vec::new();
+    for cmd in commands {
+        let label = cmd.label();
+        let child = tokio::process::Command::new(&cmd.program)
+            .args(cmd.args.iter().map(|a| expand(a)))
+            .current_dir(dir)
+            .env("PAGI_SMOKE_SANDBOX", sandbox)
+            .env("PAGI_SMOKE_PATCH_FILE", patch_file)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("{}: spawn: {}", label, e))?;
+        inflight::record_child_pid(child.id());
+        let result = tokio::time::timeout(timeout, child.wait_with_output()).await;
+        inflight::record_child_pid(None);
+        match result {
+            Err(_) => return Err(format!("{}: timed out after {:?}", label, timeout)),
+            Ok(Err(e)) => return Err(format!("{}: {}", label, e)),
+            Ok(Ok(out)) if !out.status.success() => {
+                let stderr = String::from_utf8_lossy(&out.stderr);
+                let stderr = stderr.trim();
+                let start = stderr.char_indices().rev().nth(299).map_or(0, |(i, _)| i);
+                return Err(format!("{}: {} {}", label, out.status, &stderr[start..])
+                    .trim_end()
+                    .to_string());
+            }
+            Ok(Ok(_)) => passed.push(label),
+        }
+    }
+    Ok(passed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn parses_and_runs_component_smoke_commands() {
+        let config = SmokeConfig {
+            commands: parse_commands(
+                "rust_core=test -f {patch} ; python_skill=false; broken; rust_core=sleep 5",
+            ),
+            timeout: Duration::from_millis(200),
+        };
+        assert_eq!(config.commands.len(), 3);
+        let core = config.for_component("rust_core");
+        assert_eq!(core[0].label(), "test -f {patch}");
+
+        let (sandbox, patch_file) = prepare_sandbox("p1", "rs", "fn main() {}\n").unwrap();
+        let dir = std::env::temp_dir();
+        let ok = run(&core[..1], &dir, &sandbox, &patch_file, config.timeout).await.unwrap();
+        assert_eq!(ok, vec!["test -f {patch}".to_string()]);
+
+        let err = run(&core, &dir, &sandbox, &patch_file, config.timeout).await.unwrap_err();
+        assert!(err.starts_with("sleep 5: timed out"), "{}", err);
+        let err = run(&config.for_component("python_skill"), &dir, &sandbox, &patch_file, config.timeout)
+            .await
+            .unwrap_err();
+        assert!(err.starts_with("false: exit status: 1"), "{}", err);
+        let _ = std::fs::remove_dir_all(&sandbox);
+    }
+}
@@ -0,0 +1,279 @@
+// RunSmokeTest (admin): one-call post-deploy verification over a safe end-to-end path, instead of ad-hoc grpcurl
+// scripts. Steps run in order and each reports passed / failed / skipped with its latency; a failure does not stop
+// the later steps. l2_write_read writes a scratch key and reads it back; mock_action dispatches a skill in mock
+// mode through the governor; l4_search seeds a KB with one probe point and expects it as the top hit of a search
+// for its vector (skipped when L4 is disabled); patch_propose_cancel proposes a patch for a synthetic error and
+// cancels it. Scratch keys, probe points and the patch are removed again. (Apply-time smoke commands are smoke.rs.)
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Instant;
+
+use tonic::Status;
+use uuid::Uuid;
+
+use crate::embedder;
+use crate::memory_manager::MemoryManager;
+use crate::proto::pagi_proto::{
+    ActionRequest, ActionResponse, DeleteVectorsRequest, MemoryDeleteRequest, SearchRequest, SmokeTestReport,
+    SmokeTestRequest, SmokeTestStep, UpsertRequest, VectorPoint,
+};
+use crate::watchdog::Watchdog;
+
+const DEFAULT_KB: &str = "kb_smoke";
+const DEFAULT_SKILL: &str = "peek_file";
+const DEFAULT_COMPONENT: &str = "python_skill";
+/// L2 scratch keys: "<prefix><reasoning_id>".
+const KEY_PREFIX: &str = "pagi_smoke/";
+
+const PASSED: &str = "passed";
+const FAILED: &str = "failed";
+const SKIPPED: &str = "skipped";
+
+fn or_default(value: &str, default: &str) -> String {
+    if value.trim().is_empty() {
+        default.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Run `check` as step `name`: Ok((status, detail)) or a failure detail.
+async fn step<F>(name: &str, check: F) -> SmokeTestStep
+where
+    F: Future<Output = Result<(&'static str, String), String>>,
+{
+    let started = Instant::now();
+    let (status, detail) = check.await.unwrap_or_else(|e| (FAILED, e));
+    eprintln!("[SmokeTest] {}: {} ({})", name, status, detail);
+    SmokeTestStep {
+        name: name.to_string(),
+        status: status.to_string(),
+        detail,
+        latency_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+async fn l2_write_read(memory: &MemoryManager, reasoning_id: &str) -> Result<(&'static str, String), String> {
+    let key = format!("{}{}", KEY_PREFIX, reasoning_id);
+    let value = format!("smoke {}", Uuid::new_v4());
+    let written = memory.access(2, &key, Some(&value));
+    let read = written.and_then(|_| memory.access(2, &key, None));
+    let cleanup = memory.delete_memory(&MemoryDeleteRequest {
+        layer: 2,
+        prefix: key.clone(),
+        namespace: String::new(),
+    });
+    let (data, found) = read.map_err(|e| format!("L2 {}: {}", key, e.message()))?;
+    if !found || data != value {
+        return Err(format!(
+            "L2 {} read back {:?} (found: {}), wrote {:?}",
+            key, data, found, value
+        ));
+    }
+    cleanup.map_err(|e| format!("L2 {} read back but not deleted: {}", key, e.message()))?;
+    Ok((PASSED, format!("wrote, read back and deleted {}", key)))
+}
+
+async fn mock_action<D, Fut>(dispatch: D, skill: String, reasoning_id: &str) -> Result<(&'static str, String), String>
+where
+    D: FnOnce(ActionRequest) -> Fut,
+    Fut: Future<Output = Result<ActionResponse, Status>>,
+{
+    let resp = dispatch(ActionRequest {
+        skill_name: skill.clone(),
+        reasoning_id: reasoning_id.to_string(),
+        mock_mode: true,
+        ..Default::default()
+    })
+    .await
+    .map_err(|e| format!("{}: {:?}: {}", skill, e.code(), e.message()))?;
+    if !resp.success || resp.dispatch_mode != "mock" {
+        return Err(format!(
+            "{}: success={} dispatch_mode={:?}: {}",
+            skill, resp.success, resp.dispatch_mode, resp.observation
+        ));
+    }
+    Ok((PASSED, format!("{} answered in mock mode", skill)))
+}
+
+async fn l4_search(memory: &MemoryManager, kb: String, reasoning_id: &str) -> Result<(&'static str, String), String> {
+    if !memory.l4_enabled() {
+        return Ok((SKIPPED, "L4 disabled".to_string()));
+    }
+    memory.ensure_kb(&kb).await.map_err(|e| format!("{}: {}", kb, e))?;
+    let id = Uuid::new_v4().to_string();
+    let vector = embedder::hash_embed(&format!("pagi smoke probe {}", id), memory.kb_dim(&kb));
+    memory
+        .upsert_vectors(UpsertRequest {
+            kb_name: kb.clone(),
+            points: vec![VectorPoint {
+                id: id.clone(),
+                vector: vector.clone(),
+                payload: HashMap::from([("content".to_string(), "pagi smoke probe".to_string())]),
+                ..Default::default()
+            }],
+            reasoning_id: reasoning_id.to_string(),
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| format!("seed {}: {}", kb, e.message()))?;
+    let found = memory
+        .semantic_search(SearchRequest {
+            kb_name: kb.clone(),
+            query_vector: vector,
+            limit: 1,
+            ..Default::default()
+        })
+        .await;
+    let cleanup = memory
+        .delete_vectors(DeleteVectorsRequest {
+            kb_name: kb.clone(),
+            ids: vec![id.clone()],
+        })
+        .await;
+    let found = found.map_err(|e| format!("search {}: {}", kb, e.message()))?;
+    if found.degraded {
+        return Err(format!("search {}: L4 degraded ({})", kb, found.source));
+    }
+    let hit = match found.hits.first() {
+        Some(hit) if hit.document_id == id => hit,
+        Some(hit) => {
+            return Err(format!(
+                "search {}: top hit {} instead of probe {}",
+                kb, hit.document_id, id
+            ))
+        }
+        None => return Err(format!("search {}: probe {} not found", kb, id)),
+    };
+    cleanup.map_err(|e| format!("probe {} found but not deleted from {}: {}", id, kb, e.message()))?;
+    Ok((
+        PASSED,
+        format!(
+            "probe found in {} ({}, score {:.3}) and deleted",
+            kb, found.source, hit.score
+        ),
+    ))
+}
+
+async fn patch_propose_cancel(
+    watchdog: &Watchdog,
+    component: String,
+    reasoning_id: &str,
+) -> Result<(&'static str, String), String> {
+    let patch_id = watchdog
+        .smoke_patch(&component, reasoning_id)
+        .await
+        .map_err(|e| format!("{}: {:?}: {}", component, e.code(), e.message()))?;
+    Ok((PASSED, format!("proposed and cancelled {} for {}", patch_id, component)))
+}
+
+/// Run every step for `req` (reasoning_id already resolved); `dispatch` is the orchestrator's ExecuteAction path.
+pub async fn run<D, Fut>(
+    memory: &MemoryManager,
+    watchdog: &Watchdog,
+    req: SmokeTestRequest,
+    dispatch: D,
+) -> SmokeTestReport
+where
+    D: FnOnce(ActionRequest) -> Fut,
+    Fut: Future<Output = Result<ActionResponse, Status>>,
+{
+    let started = Instant::now();
+    let rid = req.reasoning_id.as_str();
+    let skill = or_default(&req.skill_name, DEFAULT_SKILL);
+    let kb = or_default(&req.kb_name, DEFAULT_KB);
+    let component = or_default(&req.component, DEFAULT_COMPONENT);
+    let steps = vec![
+        step("l2_write_read", l2_write_read(memory, rid)).await,
+        step("mock_action", mock_action(dispatch, skill, rid)).await,
+        step("l4_search", l4_search(memory, kb, rid)).await,
+        step("patch_propose_cancel", patch_propose_cancel(watchdog, component, rid)).await,
+    ];
+    SmokeTestReport {
+        ok: steps.iter().all(|s| s.status != FAILED),
+        steps,
+        reasoning_id: req.reasoning_id,
+        duration_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::watchdog::tests::lock_test_env;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    fn setup() -> (Arc<MemoryManager>, Arc<Watchdog>, PathBuf) {
+        let memory = Arc::new(MemoryManager::in_memory(64));
+        let registry = std::env::temp_dir().join(format!("pagi_smoke_test_{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&registry).unwrap();
+        let cwd = std::env::current_dir().unwrap();
+        let watchdog = Watchdog::new(registry.clone(), memory.clone(), cwd.clone(), cwd);
+        (memory, watchdog, registry)
+    }
+
+    async fn mock_dispatch(req: ActionRequest) -> Result<ActionResponse, Status> {
+        Ok(ActionResponse {
+            success: req.mock_mode,
+            dispatch_mode: "mock".to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn smoke_request() -> SmokeTestRequest {
+        SmokeTestRequest {
+            reasoning_id: "smoke-1".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn steps_pass_on_an_in_memory_l4() {
+        let _g = lock_test_env().await;
+        let (memory, watchdog, registry) = setup();
+        let report = run(&memory, &watchdog, smoke_request(), mock_dispatch).await;
+        let statuses: Vec<(&str, &str)> = report
+            .steps
+            .iter()
+            .map(|s| (s.name.as_str(), s.status.as_str()))
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                ("l2_write_read", PASSED),
+                ("mock_action", PASSED),
+                ("l4_search", PASSED),
+                ("patch_propose_cancel", PASSED)
+            ],
+            "{:?}",
+            report.steps
+        );
+        assert!(report.ok);
+        let _ = std::fs::remove_dir_all(registry);
+    }
+
+    #[tokio::test]
+    async fn steps_leave_no_scratch_key_or_patch_behind() {
+        let _g = lock_test_env().await;
+        let (memory, watchdog, registry) = setup();
+        let report = run(&memory, &watchdog, smoke_request(), mock_dispatch).await;
+        assert!(!memory.access(2, "pagi_smoke/smoke-1", None).unwrap().1, "scratch key deleted");
+        let patch_id = report.steps[3].detail.split_whitespace().nth(3).unwrap();
+        assert!(watchdog.apply_status(patch_id).await.is_err(), "cancelled patch is gone");
+        let _ = std::fs::remove_dir_all(registry);
+    }
+
+    #[tokio::test]
+    async fn a_failing_step_fails_the_report_but_later_steps_still_run() {
+        let _g = lock_test_env().await;
+        let (memory, watchdog, registry) = setup();
+        let failing = |_: ActionRequest| async { Err::<ActionResponse, _>(Status::permission_denied("gated")) };
+        let report = run(&memory, &watchdog, SmokeTestRequest::default(), failing).await;
+        assert!(!report.ok);
+        assert_eq!(report.steps.len(), 4);
+        assert!(report.steps[1].detail.contains("gated"), "{:?}", report.steps[1]);
+        let _ = std::fs::remove_dir_all(registry);
+    }
+}
@@ -0,0 +1,291 @@
+// KB backups for SnapshotKb / RestoreKb. Files live under PAGI_SNAPSHOT_DIR (default pagi_snapshots) as
+// <collection>/<collection>-<utc time>[-<label>].<ext>: ".snapshot" holds the backend's native snapshot (Qdrant),
+// ".jsonl" the portable dump used for backends without one (a header line, then one point per line). Files are
+// written under a ".partial" name and renamed when complete, so a listed snapshot is always whole.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::proto::pagi_proto::{DenseVector, VectorPoint};
+
+/// Backend-native snapshot file.
+pub const NATIVE_EXT: &str = "snapshot";
+/// Portable point dump.
+pub const POINTS_EXT: &str = "jsonl";
+const POINTS_FORMAT: &str = "pagi-kb-points/1";
+const PARTIAL_EXT: &str = "partial";
+
+/// Optional label: 1–64 characters from [A-Za-z0-9_-].
+pub fn check_label(label: &str) -> Result<(), String> {
+    if label.is_empty()
+        || (label.len() <= 64 && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+    {
+        Ok(())
+    } else {
+        Err(format!("label {:?}: use up to 64 characters from [A-Za-z0-9_-]", label))
+    }
+}
+
+/// Collection names become directory names; reject anything that could leave the snapshot root.
+pub fn check_collection(collection: &str) -> Result<(), String> {
+    if collection.is_empty() || collection.starts_with('.') || collection.contains(['/', '\\']) {
+        return Err(format!("collection {:?} cannot be snapshotted", collection));
+    }
+    Ok(())
+}
+
+pub struct SnapshotDir {
+    root: PathBuf,
+}
+
+impl SnapshotDir {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// PAGI_SNAPSHOT_DIR (default pagi_snapshots).
+    pub fn from_env() -> Self {
+        let root = std::env::var("PAGI_SNAPSHOT_DIR")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "pagi_snapshots".to_string());
+        Self::new(PathBuf::from(root))
+    }
+
+    fn collection_dir(&self, collection: &str) -> PathBuf {
+        self.root.join(collection)
+    }
+
+    /// Final path for a new snapshot of `collection` taken `at` (its directory is created).
+    pub fn new_path(&self, collection: &str, label: &str, ext: &str, at: DateTime<Utc>) -> Result<PathBuf, String> {
+        check_collection(collection)?;
+        check_label(label)?;
+        let dir = self.collection_dir(collection);
+        std::fs::create_dir_all(&dir).map_err(|e| format!("create {}: {}", dir.display(), e))?;
+        let at = at.format("%Y%m%dT%H%M%S%3fZ");
+        let name = if label.is_empty() {
+            format!("{}-{}.{}", collection, at, ext)
+        } else {
+            format!("{}-{}-{}.{}", collection, at, label, ext)
+        };
+        Ok(dir.join(name))
+    }
+
+    /// Complete snapshots of `collection`, oldest first (names start with a sortable UTC time).
+    pub fn list(&self, collection: &str) -> Vec<String> {
+        if check_collection(collection).is_err() {
+            return Vec::new();
+        }
+        let Ok(entries) = std::fs::read_dir(self.collection_dir(collection)) else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = entries
+            .flatten()
+            .filter_map(|e| e.file_name().into_string().ok())
+            .filter(|n| n.ends_with(&format!(".{}", NATIVE_EXT)) || n.ends_with(&format!(".{}", POINTS_EXT)))
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Path of snapshot `name` of `collection`, or of the newest one when `name` is empty.
+    pub fn resolve(&self, collection: &str, name: &str) -> Result<PathBuf, String> {
+        if name.is_empty() {
+            return self
+                .list(collection)
+                .pop()
+                .map(|newest| self.collection_dir(collection).join(newest))
+                .ok_or_else(|| format!("no snapshots of {} under {}", collection, self.root.display()));
+        }
+        if name.contains(['/', '\\']) || name.starts_with('.') || !self.list(collection).iter().any(|n| n == name) {
+            return Err(format!("no snapshot {:?} of {}", name, collection));
+        }
+        Ok(self.collection_dir(collection).join(name))
+    }
+}
+
+/// Where a snapshot is written before it is complete.
+pub fn partial_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", PARTIAL_EXT));
+    path.with_file_name(name)
+}
+
+/// Publish a completed `.partial` file under its final name; returns its size in bytes.
+pub fn finish(path: &Path) -> Result<u64, String> {
+    std::fs::rename(partial_path(path), path).map_err(|e| format!("rename {}: {}", path.display(), e))?;
+    std::fs::metadata(path).map(|m| m.len()).map_err(|e| e.to_string())
+}
+
+/// Drop an unfinished snapshot.
+pub fn discard(path: &Path) {
+    let _ = std::fs::remove_file(partial_path(path));
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+    format: String,
+    kb_name: String,
+    created_at: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PointRecord {
+    id: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    vector: Vec<f32>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    vectors: BTreeMap<String, Vec<f32>>,
+    payload: HashMap<String, String>,
+}
+
+/// Portable dump writer (to the `.partial` file of `path`).
+pub struct PointsWriter {
+    out: BufWriter<File>,
+    points: u64,
+}
+
+impl PointsWriter {
+    pub fn create(path: &Path, kb_name: &str, created_at: DateTime<Utc>) -> Result<Self, String> {
+        let partial = partial_path(path);
+        let file = File::create(&partial).map_err(|e| format!("create {}: {}", partial.display(), e))?;
+        let mut writer = Self {
+            out: BufWriter::new(file),
+            points: 0,
+        };
+        let header = Header {
+            format: POINTS_FORMAT.to_string(),
+            kb_name: kb_name.to_string(),
+            created_at: created_at.to_rfc3339(),
+        };
+        writer.line(&header)?;
+        Ok(writer)
+    }
+
+    fn line<T: Serialize>(&mut self, value: &T) -> Result<(), String> {
+        serde_json::to_writer(&mut self.out, value).map_err(|e| e.to_string())?;
+        self.out.write_all(b"\n").map_err(|e| e.to_string())
+    }
+
+    pub fn write(&mut self, points: &[VectorPoint]) -> Result<(), String> {
+        for p in points {
+            self.line(&PointRecord {
+                id: p.id.clone(),
+                vector: p.vector.clone(),
+                vectors: p.vectors.iter().map(|(n, v)| (n.clone(), v.data.clone())).collect(),
+                payload: p.payload.clone(),
+            })?;
+            self.points += 1;
+        }
+        Ok(())
+    }
+
+    /// Flush and sync; `finish` publishes the file.
+    pub fn close(self) -> Result<u64, String> {
+        let file = self.out.into_inner().map_err(|e| e.to_string())?;
+        file.sync_all().map_err(|e| e.to_string())?;
+        Ok(self.points)
+    }
+}
+
+/// Open a portable dump: the KB it was taken from and its points, read lazily.
+pub fn read_points(path: &Path) -> Result<(String, impl Iterator<Item = Result<VectorPoint, String>>), String> {
+    let file = File::open(path).map_err(|e| format!("open {}: {}", path.display(), e))?;
+    let mut lines = BufReader::new(file).lines();
+    let first = lines
+        .next()
+        .ok_or_else(|| format!("{}: empty snapshot", path.display()))?
+        .map_err(|e| e.to_string())?;
+    let header: Header = serde_json::from_str(&first).map_err(|e| format!("{}: bad header: {}", path.display(), e))?;
+    if header.format != POINTS_FORMAT {
+        return Err(format!("{}: unsupported format {:?}", path.display(), header.format));
+    }
+    let points = lines.filter(|l| !l.as_ref().is_ok_and(|l| l.trim().is_empty())).map(|line| {
+        let record: PointRecord = serde_json::from_str(&line.map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+        Ok(VectorPoint {
+            id: record.id,
+            vector: record.vector,
+            vectors: record.vectors.into_iter().map(|(n, data)| (n, DenseVector { data })).collect(),
+            payload: record.payload,
+        })
+    });
+    Ok((header.kb_name, points))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> SnapshotDir {
+        SnapshotDir::new(std::env::temp_dir().join(format!("pagi_snapshots_{}", uuid::Uuid::new_v4())))
+    }
+
+    fn point() -> VectorPoint {
+        VectorPoint {
+            id: "p1".into(),
+            vector: vec![0.5, 1.0],
+            payload: HashMap::from([("content".to_string(), "hello".to_string())]),
+            ..Default::default()
+        }
+    }
+
+    /// Writes one `pre-apply` snapshot of `kb_core` holding `point()`, optionally finishing it.
+    fn written(dir: &SnapshotDir, finished: bool) -> PathBuf {
+        let at = Utc::now();
+        let path = dir.new_path("kb_core", "pre-apply", POINTS_EXT, at).unwrap();
+        let mut writer = PointsWriter::create(&path, "kb_core", at).unwrap();
+        writer.write(&[point()]).unwrap();
+        assert_eq!(writer.close().unwrap(), 1);
+        if finished {
+            assert!(finish(&path).unwrap() > 0);
+        }
+        path
+    }
+
+    #[test]
+    fn resolve_fails_without_snapshots() {
+        assert!(temp_dir().resolve("kb_core", "").is_err());
+    }
+
+    #[test]
+    fn snapshot_paths_reject_traversal() {
+        let dir = temp_dir();
+        assert!(dir.new_path("kb_core", "../x", POINTS_EXT, Utc::now()).is_err());
+        assert!(dir.new_path("../kb_core", "", POINTS_EXT, Utc::now()).is_err());
+        assert!(dir.resolve("kb_core", "../kb_other/x.jsonl").is_err());
+    }
+
+    #[test]
+    fn unfinished_snapshots_are_not_listed() {
+        let dir = temp_dir();
+        written(&dir, false);
+        assert!(dir.list("kb_core").is_empty());
+        let _ = std::fs::remove_dir_all(&dir.root);
+    }
+
+    #[test]
+    fn finished_snapshots_resolve_by_name_or_as_newest() {
+        let dir = temp_dir();
+        let path = written(&dir, true);
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("kb_core-") && name.ends_with("-pre-apply.jsonl"), "{}", name);
+        assert_eq!(dir.resolve("kb_core", "").unwrap(), path);
+        assert_eq!(dir.resolve("kb_core", name).unwrap(), path);
+        let _ = std::fs::remove_dir_all(&dir.root);
+    }
+
+    #[test]
+    fn points_round_trip() {
+        let dir = temp_dir();
+        let (kb, points) = read_points(&written(&dir, true)).unwrap();
+        assert_eq!(kb, "kb_core");
+        assert_eq!(points.collect::<Result<Vec<_>, _>>().unwrap(), vec![point()]);
+        let _ = std::fs::remove_dir_all(&dir.root);
+    }
+}
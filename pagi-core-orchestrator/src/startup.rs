@@ -0,0 +1,291 @@
+// Dependency-aware startup. Each external dependency — qdrant (L4 collections and WAL replay), bridge (the
+// Python runner checkout) and registry (the Evolution Registry backend) — is required or optional per
+// PAGI_REQUIRED_DEPS (default "qdrant"). Startup retries required dependencies with backoff for up to
+// PAGI_STARTUP_TIMEOUT_SECS and exits if one is still down; optional dependencies that are down are logged and
+// the server starts degraded. A monitor rechecks every dependency each PAGI_DEP_RETRY_SECS and logs
+// transitions; GetHealth reports the matrix.
+
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::circuit_breaker::RetryPolicy;
+use crate::clock::Clock;
+use crate::proto::pagi_proto::DependencyStatus;
+
+/// Dependency names main registers; anything else in PAGI_REQUIRED_DEPS is ignored with a warning.
+const KNOWN: &[&str] = &["qdrant", "bridge", "registry"];
+
+pub type CheckFuture = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+type Check = Box<dyn Fn() -> CheckFuture + Send + Sync>;
+
+#[derive(Clone, Default)]
+struct State {
+    checked: bool,
+    up: bool,
+    detail: String,
+    since_unix: i64,
+    failed_checks: u32,
+}
+
+struct Dependency {
+    name: &'static str,
+    required: bool,
+    check: Check,
+    state: Mutex<State>,
+}
+
+pub struct Dependencies {
+    deps: Vec<Dependency>,
+    required: BTreeSet<String>,
+    startup_timeout: Duration,
+    retry_interval: Duration,
+    clock: Clock,
+}
+
+impl Dependencies {
+    pub fn new(required: BTreeSet<String>, startup_timeout: Duration, retry_interval: Duration, clock: Clock) -> Self {
+        Self {
+            deps: Vec::new(),
+            required,
+            startup_timeout,
+            retry_interval,
+            clock,
+        }
+    }
+
+    /// PAGI_REQUIRED_DEPS (comma-separated, default "qdrant"; "none" for none), PAGI_STARTUP_TIMEOUT_SECS
+    /// (default 30) and PAGI_DEP_RETRY_SECS (default 15; 0 disables the monitor).
+    pub fn from_env(clock: Clock) -> Self {
+        let required: BTreeSet<String> = std::env::var("PAGI_REQUIRED_DEPS")
+            .unwrap_or_else(|_| "qdrant".into())
+            .split(',')
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty() && s != "none")
+            .collect();
+        for name in required.iter().filter(|n| !KNOWN.contains(&n.as_str())) {
+            eprintln!(
+                "[Startup] ignoring unknown dependency {:?} in PAGI_REQUIRED_DEPS (known: {})",
+                name,
+                KNOWN.join(", ")
+            );
+        }
+        let secs = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|s| s.trim().parse::<u64>().ok())
+                .unwrap_or(default)
+        };
+        Self::new(
+            required,
+            Duration::from_secs(secs("PAGI_STARTUP_TIMEOUT_SECS", 30)),
+            Duration::from_secs(secs("PAGI_DEP_RETRY_SECS", 15)),
+            clock,
+        )
+    }
+
+    /// Register a dependency; `check` must be cheap enough to run every retry interval.
+    pub fn add<F, Fut>(mut self, name: &'static str, check: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        self.deps.push(Dependency {
+            name,
+            required: self.required.contains(name),
+            check: Box::new(move || -> CheckFuture { Box::pin(check()) }),
+            state: Mutex::new(State::default()),
+        });
+        self
+    }
+
+    /// Run one check and record the outcome, logging state changes; true when up.
+    async fn check(&self, dep: &Dependency) -> bool {
+        let result = (dep.check)().await;
+        let now = self.clock.now_secs();
+        let mut state = dep.state.lock().unwrap_or_else(|e| e.into_inner());
+        let kind = if dep.required { "required" } else { "optional" };
+        match result {
+            Ok(()) => {
+                if !state.checked {
+                    eprintln!("[Startup] {} ({}) ok", dep.name, kind);
+                } else if !state.up {
+                    eprintln!("[Startup] {} recovered after {} failed check(s)", dep.name, state.failed_checks);
+                }
+                if !state.up {
+                    state.since_unix = now;
+                }
+                state.up = true;
+                state.detail.clear();
+                state.failed_checks = 0;
+            }
+            Err(e) => {
+                if !state.checked || state.up {
+                    eprintln!("[Startup] {} ({}) down: {}", dep.name, kind, e);
+                    state.since_unix = now;
+                }
+                state.up = false;
+                state.detail = e;
+                state.failed_checks += 1;
+            }
+        }
+        state.checked = true;
+        state.up
+    }
+
+    /// Check every dependency once, then retry the required ones that are down with backoff until the startup
+    /// timeout. Err lists the required dependencies still down; optional ones never fail startup.
+    pub async fn start(&self) -> Result<(), String> {
+        let mut waiting = Vec::new();
+        for dep in &self.deps {
+            if !self.check(dep).await && dep.required {
+                waiting.push(dep);
+            }
+        }
+        let policy = RetryPolicy::new(u32::MAX, Duration::from_millis(250), Duration::from_secs(5));
+        let deadline = tokio::time::Instant::now() + self.startup_timeout;
+        let mut retry = 0;
+        while !waiting.is_empty() {
+            retry += 1;
+            let delay = policy.backoff(retry);
+            if tokio::time::Instant::now() + delay > deadline {
+                let down: Vec<String> = waiting
+                    .iter()
+                    .map(|d| format!("{} ({})", d.name, d.state.lock().unwrap_or_else(|e| e.into_inner()).detail))
+                    .collect();
+                return Err(format!(
+                    "required dependencies still down after {:?}: {}",
+                    self.startup_timeout,
+                    down.join(", ")
+                ));
+            }
+            tokio::time::sleep(delay).await;
+            let mut still = Vec::new();
+            for dep in waiting {
+                if !self.check(dep).await {
+                    still.push(dep);
+                }
+            }
+            waiting = still;
+        }
+        let degraded: Vec<&str> = self.deps.iter().filter(|d| !self.is_up(d)).map(|d| d.name).collect();
+        if !degraded.is_empty() {
+            eprintln!("[Startup] serving degraded; down: {}", degraded.join(", "));
+        }
+        Ok(())
+    }
+
+    /// Recheck every dependency each PAGI_DEP_RETRY_SECS, so outages and recoveries after startup are logged
+    /// and reported.
+    pub fn spawn_monitor(self: &Arc<Self>) {
+        if self.retry_interval.is_zero() || self.deps.is_empty() {
+            return;
+        }
+        let deps = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(deps.retry_interval);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                for dep in &deps.deps {
+                    deps.check(dep).await;
+                }
+            }
+        });
+    }
+
+    fn is_up(&self, dep: &Dependency) -> bool {
+        dep.state.lock().unwrap_or_else(|e| e.into_inner()).up
+    }
+
+    /// True when every registered dependency passed its latest check.
+    pub fn all_up(&self) -> bool {
+        self.deps.iter().all(|d| self.is_up(d))
+    }
+
+    /// The matrix in registration order.
+    pub fn report(&self) -> Vec<DependencyStatus> {
+        self.deps
+            .iter()
+            .map(|d| {
+                let state = d.state.lock().unwrap_or_else(|e| e.into_inner()).clone();
+                DependencyStatus {
+                    name: d.name.to_string(),
+                    required: d.required,
+                    state: match (state.checked, state.up) {
+                        (false, _) => "unchecked",
+                        (true, true) => "ok",
+                        (true, false) => "down",
+                    }
+                    .to_string(),
+                    detail: state.detail,
+                    since_unix: state.since_unix,
+                    failed_checks: state.failed_checks,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for Dependencies {
+    fn default() -> Self {
+        Self::new(BTreeSet::new(), Duration::ZERO, Duration::ZERO, Clock::system())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Check that fails its first `failures` runs, then passes.
+    fn flaky(failures: u32) -> impl Fn() -> std::future::Ready<Result<(), String>> + Send + Sync {
+        let calls = AtomicU32::new(0);
+        move || {
+            let n = calls.fetch_add(1, Ordering::Relaxed);
+            std::future::ready(if n < failures { Err(format!("attempt {} refused", n + 1)) } else { Ok(()) })
+        }
+    }
+
+    fn required(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn required_dependencies_are_retried_and_optional_ones_degrade() {
+        let deps = Dependencies::new(required(&["qdrant"]), Duration::from_secs(5), Duration::ZERO, Clock::system())
+            .add("qdrant", flaky(2))
+            .add("bridge", flaky(u32::MAX));
+        deps.start().await.unwrap();
+        let report = deps.report();
+        assert_eq!((report[0].name.as_str(), report[0].state.as_str()), ("qdrant", "ok"));
+        assert!(report[0].required);
+        assert_eq!((report[1].state.as_str(), report[1].failed_checks), ("down", 1), "optional: checked once");
+        assert!(report[1].detail.contains("refused"));
+        assert!(!deps.all_up());
+    }
+
+    #[tokio::test]
+    async fn startup_fails_when_a_required_dependency_stays_down() {
+        let timeout = Duration::from_millis(600);
+        let deps = Dependencies::new(required(&["registry"]), timeout, Duration::ZERO, Clock::system())
+            .add("registry", flaky(u32::MAX));
+        let err = deps.start().await.unwrap_err();
+        assert!(err.contains("registry (attempt"), "{}", err);
+        assert!(deps.report()[0].failed_checks > 1, "retried before giving up");
+    }
+
+    #[tokio::test]
+    async fn monitor_checks_report_recovery() {
+        let deps = Dependencies::default().add("bridge", flaky(1));
+        deps.start().await.unwrap();
+        assert_eq!(deps.report()[0].state, "down");
+        assert!(!deps.report()[0].required);
+        deps.check(&deps.deps[0]).await;
+        let status = &deps.report()[0];
+        assert_eq!((status.state.as_str(), status.failed_checks, status.detail.as_str()), ("ok", 0, ""));
+        assert!(deps.all_up());
+    }
+}
@@ -0,0 +1,556 @@
+// Durable orchestrator state: JSON records keyed by (table, id) behind one Repository trait, shared by the
+// patch catalog, the apply queue, L6 lineage and the action audit trail so their state survives restarts
+// through a single backend. PAGI_STORE selects it: "surreal" (SurrealDB at PAGI_SURREAL_URL), "file" (one
+// JSONL log per table under PAGI_STORE_DIR, compacted on open) or unset (each subsystem keeps its own
+// in-RAM / flat-file behavior). Records are read once at open and handed to subsystems as they restore;
+// writes go through one background writer, in order, so sync call sites never wait on I/O. The writer moves
+// large string fields (observations, summaries) into the "blobs" table, compressed and stored once per content
+// (blobs.rs); records keep a reference that open resolves again.
+
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::io::Write;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use surrealdb::engine::any::Any;
+use surrealdb::Surreal;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::blobs::{self, BlobPolicy, Blobs};
+
+pub type RepoFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, String>> + Send + 'a>>;
+
+/// Pending patches (patch_id -> PendingPatch).
+pub const PATCH_PENDING: &str = "patch_pending";
+/// HITL approval history (patch_id -> Vec<ApprovalRecord>).
+pub const PATCH_APPROVALS: &str = "patch_approvals";
+/// Heal lifecycle timelines (patch_id -> HealTimeline).
+pub const PATCH_LIFECYCLE: &str = "patch_lifecycle";
+/// Applied patches kept for RollbackPatch (patch_id -> AppliedPatch).
+pub const PATCH_APPLIED: &str = "patch_applied";
+/// Apply queue states (patch_id -> state and target repo).
+pub const APPLY_STATUS: &str = "apply_status";
+/// L6 lineage events.
+pub const LINEAGE: &str = "lineage";
+/// ACTION audit records of real dispatches (audit_id -> ActionRecord).
+pub const AUDIT: &str = "audit";
+/// Large record fields by sha256 (id -> packed content), referenced from the other tables.
+pub const BLOBS: &str = "blobs";
+/// Tables read at open.
+const TABLES: [&str; 7] = [
+    PATCH_PENDING,
+    PATCH_APPROVALS,
+    PATCH_LIFECYCLE,
+    PATCH_APPLIED,
+    APPLY_STATUS,
+    LINEAGE,
+    AUDIT,
+];
+
+pub trait Repository: Send + Sync {
+    /// Backend name for logs.
+    fn name(&self) -> &'static str;
+    /// Create or replace one record.
+    fn put<'a>(&'a self, table: &'a str, id: &'a str, record: Value) -> RepoFuture<'a, ()>;
+    fn delete<'a>(&'a self, table: &'a str, id: &'a str) -> RepoFuture<'a, ()>;
+    /// Every record of `table`, in no particular order.
+    fn list<'a>(&'a self, table: &'a str) -> RepoFuture<'a, Vec<(String, Value)>>;
+}
+
+/// One line of a table log; `record` None marks a delete.
+#[derive(Serialize, Deserialize)]
+struct LogLine {
+    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    record: Option<Value>,
+}
+
+/// Append-only JSONL log per table (<dir>/<table>.jsonl); the last line for an id wins. list() rewrites
+/// the log with only live records (temp file + rename).
+pub struct FileRepository {
+    dir: PathBuf,
+    /// Serializes appends and compaction.
+    lock: Mutex<()>,
+}
+
+impl FileRepository {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn table_path(&self, table: &str) -> PathBuf {
+        self.dir.join(format!("{}.jsonl", table))
+    }
+
+    fn append(&self, table: &str, line: &LogLine) -> Result<(), String> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        std::fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        let json = serde_json::to_string(line).map_err(|e| e.to_string())?;
+        let mut f = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.table_path(table))
+            .map_err(|e| e.to_string())?;
+        writeln!(f, "{}", json).map_err(|e| e.to_string())
+    }
+
+    fn compact(&self, table: &str) -> Result<Vec<(String, Value)>, String> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let path = self.table_path(table);
+        let raw = match std::fs::read_to_string(&path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.to_string()),
+        };
+        let mut order: Vec<String> = Vec::new();
+        let mut live: HashMap<String, Value> = HashMap::new();
+        let mut skipped = 0;
+        for line in raw.lines().filter(|l| !l.trim().is_empty()) {
+            let Ok(line) = serde_json::from_str::<LogLine>(line) else {
+                skipped += 1;
+                continue;
+            };
+            match line.record {
+                Some(record) => {
+                    if live.insert(line.id.clone(), record).is_none() {
+                        order.push(line.id);
+                    }
+                }
+                None => {
+                    live.remove(&line.id);
+                }
+            }
+        }
+        if skipped > 0 {
+            eprintln!("[Store] {}: skipped {} unreadable line(s)", path.display(), skipped);
+        }
+        let records: Vec<(String, Value)> = order
+            .into_iter()
+            .filter_map(|id| live.remove(&id).map(|record| (id, record)))
+            .collect();
+        let mut out = String::new();
+        for (id, record) in &records {
+            let line = LogLine {
+                id: id.clone(),
+                record: Some(record.clone()),
+            };
+            out.push_str(&serde_json::to_string(&line).map_err(|e| e.to_string())?);
+            out.push('\n');
+        }
+        let tmp = path.with_extension("jsonl.tmp");
+        std::fs::write(&tmp, out).map_err(|e| e.to_string())?;
+        std::fs::rename(&tmp, &path).map_err(|e| e.to_string())?;
+        Ok(records)
+    }
+}
+
+impl Repository for FileRepository {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn put<'a>(&'a self, table: &'a str, id: &'a str, record: Value) -> RepoFuture<'a, ()> {
+        let line = LogLine {
+            id: id.to_string(),
+            record: Some(record),
+        };
+        Box::pin(async move { self.append(table, &line) })
+    }
+
+    fn delete<'a>(&'a self, table: &'a str, id: &'a str) -> RepoFuture<'a, ()> {
+        let line = LogLine {
+            id: id.to_string(),
+            record: None,
+        };
+        Box::pin(async move { self.append(table, &line) })
+    }
+
+    fn list<'a>(&'a self, table: &'a str) -> RepoFuture<'a, Vec<(String, Value)>> {
+        Box::pin(async move { self.compact(table) })
+    }
+}
+
+/// Row layout in SurrealDB: the caller's id is kept as `key` beside the record id so lists need no
+/// record-id parsing.
+#[derive(Serialize, Deserialize)]
+struct Row {
+    key: String,
+    data: Value,
+}
+
+/// SurrealDB (any engine: ws://, http://) in namespace/database "pagi" unless PAGI_SURREAL_NS / PAGI_SURREAL_DB
+/// say otherwise.
+pub struct SurrealRepository {
+    db: Surreal<Any>,
+}
+
+impl SurrealRepository {
+    pub async fn connect(url: &str) -> Result<Self, String> {
+        let db = surrealdb::engine::any::connect(url).await.map_err(|e| e.to_string())?;
+        let user = std::env::var("PAGI_SURREAL_USER").unwrap_or_default();
+        if !user.is_empty() {
+            let password = std::env::var("PAGI_SURREAL_PASS").unwrap_or_default();
+            db.signin(surrealdb::opt::auth::Root {
+                username: &user,
+                password: &password,
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+        let ns = std::env::var("PAGI_SURREAL_NS").unwrap_or_else(|_| "pagi".into());
+        let database = std::env::var("PAGI_SURREAL_DB").unwrap_or_else(|_| "pagi".into());
+        db.use_ns(ns).use_db(database).await.map_err(|e| e.to_string())?;
+        Ok(Self { db })
+    }
+}
+
+impl Repository for SurrealRepository {
+    fn name(&self) -> &'static str {
+        "surreal"
+    }
+
+    fn put<'a>(&'a self, table: &'a str, id: &'a str, record: Value) -> RepoFuture<'a, ()> {
+        Box::pin(async move {
+            let row = Row {
+                key: id.to_string(),
+                data: record,
+            };
+            self.db
+                .query("UPDATE type::thing($tb, $id) CONTENT $row")
+                .bind(("tb", table))
+                .bind(("id", id))
+                .bind(("row", row))
+                .await
+                .and_then(|r| r.check())
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn delete<'a>(&'a self, table: &'a str, id: &'a str) -> RepoFuture<'a, ()> {
+        Box::pin(async move {
+            self.db
+                .query("DELETE type::thing($tb, $id)")
+                .bind(("tb", table))
+                .bind(("id", id))
+                .await
+                .and_then(|r| r.check())
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn list<'a>(&'a self, table: &'a str) -> RepoFuture<'a, Vec<(String, Value)>> {
+        Box::pin(async move {
+            let mut response = self
+                .db
+                .query("SELECT key, data FROM type::table($tb)")
+                .bind(("tb", table))
+                .await
+                .map_err(|e| e.to_string())?;
+            let rows: Vec<Row> = response.take(0).map_err(|e| e.to_string())?;
+            Ok(rows.into_iter().map(|r| (r.key, r.data)).collect())
+        })
+    }
+}
+
+enum Op {
+    Put { table: &'static str, id: String, record: Value },
+    Delete { table: &'static str, id: String },
+    /// Answered once every earlier op has been applied.
+    Flush(oneshot::Sender<()>),
+}
+
+/// Handle shared by the subsystems: records loaded at open plus the write queue.
+pub struct Store {
+    backend: &'static str,
+    /// Records read at open, by table; each subsystem takes its tables once while restoring.
+    loaded: Mutex<HashMap<&'static str, Vec<(String, Value)>>>,
+    writes: mpsc::UnboundedSender<Op>,
+}
+
+impl Store {
+    /// Load every table and start the writer (needs a Tokio runtime), with the blob policy from the environment.
+    pub async fn open(repo: Arc<dyn Repository>) -> Result<Arc<Self>, String> {
+        Self::open_with(repo, BlobPolicy::from_env()).await
+    }
+
+    /// `open` moving string fields `policy` applies to into BLOBS. References are resolved at open and blobs no
+    /// record references any more are deleted.
+    pub async fn open_with(repo: Arc<dyn Repository>, policy: BlobPolicy) -> Result<Arc<Self>, String> {
+        let stored: Blobs = repo
+            .list(BLOBS)
+            .await
+            .map_err(|e| format!("{} {}: {}", repo.name(), BLOBS, e))?
+            .into_iter()
+            .filter_map(|(id, packed)| packed.as_str().map(|p| (id, p.to_string())))
+            .collect();
+        let mut known = HashSet::new();
+        let mut loaded = HashMap::new();
+        for table in TABLES {
+            let mut records = repo
+                .list(table)
+                .await
+                .map_err(|e| format!("{} {}: {}", repo.name(), table, e))?;
+            records.retain_mut(|(id, record)| {
+                blobs::references(record, &mut known);
+                match blobs::resolve(record, &stored) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        eprintln!("[Store] skipping {}/{}: {}", table, id, e);
+                        false
+                    }
+                }
+            });
+            loaded.insert(table, records);
+        }
+        for id in stored.keys().filter(|id| !known.contains(*id)) {
+            if let Err(e) = repo.delete(BLOBS, id).await {
+                eprintln!("[Store] {} delete unreferenced {}/{}: {}", repo.name(), BLOBS, id, e);
+            }
+        }
+        known.retain(|id| stored.contains_key(id));
+        let (writes, mut rx) = mpsc::unbounded_channel::<Op>();
+        let backend = repo.name();
+        tokio::spawn(async move {
+            while let Some(op) = rx.recv().await {
+                let (table, id, result) = match op {
+                    Op::Put { table, id, mut record } => {
+                        // Blobs land before the record that references them.
+                        let (mut fresh, mut failed) = (Blobs::new(), Blobs::new());
+                        blobs::externalize(&mut record, &policy, &known, &mut fresh);
+                        for (blob, packed) in fresh {
+                            match repo.put(BLOBS, &blob, Value::String(packed.clone())).await {
+                                Ok(()) => {
+                                    known.insert(blob);
+                                }
+                                Err(e) => {
+                                    eprintln!("[Store] {} write {}/{}: {}", repo.name(), BLOBS, blob, e);
+                                    failed.insert(blob, packed);
+                                }
+                            }
+                        }
+                        if let Err(e) = blobs::inline(&mut record, &failed) {
+                            eprintln!("[Store] {}/{} references an unwritten blob: {}", table, id, e);
+                        }
+                        let result = repo.put(table, &id, record).await;
+                        (table, id, result)
+                    }
+                    Op::Delete { table, id } => {
+                        let result = repo.delete(table, &id).await;
+                        (table, id, result)
+                    }
+                    Op::Flush(done) => {
+                        let _ = done.send(());
+                        continue;
+                    }
+                };
+                if let Err(e) = result {
+                    eprintln!("[Store] {} write {}/{}: {}", repo.name(), table, id, e);
+                }
+            }
+        });
+        Ok(Arc::new(Self {
+            backend,
+            loaded: Mutex::new(loaded),
+            writes,
+        }))
+    }
+
+    pub fn backend(&self) -> &'static str {
+        self.backend
+    }
+
+    /// Queue a create-or-replace of `table`/`id`.
+    pub fn put<T: Serialize>(&self, table: &'static str, id: &str, record: &T) {
+        match serde_json::to_value(record) {
+            Ok(record) => {
+                let _ = self.writes.send(Op::Put {
+                    table,
+                    id: id.to_string(),
+                    record,
+                });
+            }
+            Err(e) => eprintln!("[Store] encoding {}/{}: {}", table, id, e),
+        }
+    }
+
+    pub fn delete(&self, table: &'static str, id: &str) {
+        let _ = self.writes.send(Op::Delete {
+            table,
+            id: id.to_string(),
+        });
+    }
+
+    /// Records of `table` loaded at open (empty on later calls); undecodable records are logged and skipped.
+    pub fn take<T: DeserializeOwned>(&self, table: &'static str) -> Vec<(String, T)> {
+        let records = self
+            .loaded
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(table)
+            .unwrap_or_default();
+        records
+            .into_iter()
+            .filter_map(|(id, record)| match serde_json::from_value(record) {
+                Ok(v) => Some((id, v)),
+                Err(e) => {
+                    eprintln!("[Store] skipping {}/{}: {}", table, id, e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Wait until every write queued so far has been applied.
+    pub async fn flush(&self) {
+        let (done, wait) = oneshot::channel();
+        if self.writes.send(Op::Flush(done)).is_ok() {
+            let _ = wait.await;
+        }
+    }
+}
+
+static GLOBAL: OnceLock<Arc<Store>> = OnceLock::new();
+
+/// Process-wide store, once installed at startup; None keeps subsystems on their own persistence.
+pub fn global() -> Option<Arc<Store>> {
+    GLOBAL.get().cloned()
+}
+
+pub fn install(store: Arc<Store>) {
+    let _ = GLOBAL.set(store);
+}
+
+/// Store from PAGI_STORE ("surreal", "file" or unset/"none").
+pub async fn open_from_env() -> Result<Option<Arc<Store>>, String> {
+    let repo: Arc<dyn Repository> = match std::env::var("PAGI_STORE")
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+        .as_str()
+    {
+        "" | "none" => return Ok(None),
+        "file" => {
+            let dir = std::env::var("PAGI_STORE_DIR").unwrap_or_else(|_| "pagi_state".into());
+            Arc::new(FileRepository::new(PathBuf::from(dir)))
+        }
+        "surreal" => {
+            let url = std::env::var("PAGI_SURREAL_URL").unwrap_or_else(|_| "ws://localhost:8000".into());
+            Arc::new(
+                SurrealRepository::connect(&url)
+                    .await
+                    .map_err(|e| format!("SurrealDB {}: {}", url, e))?,
+            )
+        }
+        other => return Err(format!("unknown PAGI_STORE {:?} (expected surreal, file or none)", other)),
+    };
+    let store = Store::open(repo).await?;
+    eprintln!("[Store] durable state via {}", store.backend());
+    Ok(Some(store))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("pagi_store_{}", uuid::Uuid::new_v4()))
+    }
+
+    async fn open(dir: &Path) -> Arc<Store> {
+        Store::open(Arc::new(FileRepository::new(dir.to_path_buf()))).await.unwrap()
+    }
+
+    /// p1 written twice, p2 written then deleted.
+    async fn written(dir: &Path) {
+        let store = open(dir).await;
+        store.put(PATCH_PENDING, "p1", &serde_json::json!({"component": "rust_core"}));
+        store.put(PATCH_PENDING, "p2", &serde_json::json!({"component": "python_skill"}));
+        store.put(PATCH_PENDING, "p1", &serde_json::json!({"component": "rust_core", "v": 2}));
+        store.delete(PATCH_PENDING, "p2");
+        store.flush().await;
+    }
+
+    #[tokio::test]
+    async fn file_store_replays_last_write_per_id() {
+        let dir = temp_dir();
+        written(&dir).await;
+        let pending: Vec<(String, Value)> = open(&dir).await.take(PATCH_PENDING);
+        assert_eq!(pending, vec![("p1".to_string(), serde_json::json!({"component": "rust_core", "v": 2}))]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn records_are_taken_once() {
+        let dir = temp_dir();
+        written(&dir).await;
+        let store = open(&dir).await;
+        assert_eq!(store.take::<Value>(PATCH_PENDING).len(), 1);
+        assert!(store.take::<Value>(PATCH_PENDING).is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn logs_are_compacted_on_open() {
+        let dir = temp_dir();
+        written(&dir).await;
+        open(&dir).await;
+        let raw = std::fs::read_to_string(dir.join("patch_pending.jsonl")).unwrap();
+        assert_eq!(raw.lines().count(), 1);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn undecodable_records_are_skipped() {
+        let dir = temp_dir();
+        written(&dir).await;
+        let typed: Vec<(String, u32)> = open(&dir).await.take(PATCH_PENDING);
+        assert!(typed.is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn large_fields_are_stored_once_as_blobs() {
+        let dir = std::env::temp_dir().join(format!("pagi_store_blobs_{}", uuid::Uuid::new_v4()));
+        let policy = BlobPolicy {
+            min_bytes: 64,
+            level: 3,
+        };
+        let open = || Store::open_with(Arc::new(FileRepository::new(dir.clone())), policy);
+        let observation = "Traceback (most recent call last):\n".repeat(200);
+        let store = open().await.unwrap();
+        for (id, skill) in [("a1", "peek"), ("a2", "grep")] {
+            store.put(AUDIT, id, &serde_json::json!({"skill": skill, "detail": observation}));
+        }
+        store.put(AUDIT, "a3", &serde_json::json!({"detail": "other ".repeat(20)}));
+        store.flush().await;
+        let raw = std::fs::read_to_string(dir.join("audit.jsonl")).unwrap();
+        assert!(!raw.contains("Traceback") && raw.contains(blobs::REF_KEY), "{}", raw);
+        let stored = std::fs::read_to_string(dir.join("blobs.jsonl")).unwrap();
+        assert_eq!(stored.lines().count(), 2, "one blob per distinct content");
+        assert!(stored.len() < observation.len() / 4, "compressed");
+
+        store.delete(AUDIT, "a3");
+        store.flush().await;
+        let mut audit: Vec<(String, Value)> = open().await.unwrap().take(AUDIT);
+        audit.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(audit.len(), 2);
+        assert!(
+            audit.iter().all(|(_, r)| r["detail"] == observation.as_str()),
+            "resolved at open"
+        );
+        let left = FileRepository::new(dir.clone()).list(BLOBS).await.unwrap();
+        assert_eq!(left.len(), 1, "the deleted record's blob is dropped");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
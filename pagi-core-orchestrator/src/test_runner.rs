@@ -0,0 +1,429 @@
+// Apply-time test step, per component: program and args (no shell), working dir, extra environment and what
+// counts as passing — exit codes (default 0) and, optionally, a JUnit XML report that must list test cases and
+// no failures or errors. Components using npm test, go test, make check or anything that writes JUnit XML
+// (pytest --junitxml, jest-junit, go-junit-report) are configured through PAGI_COMPONENTS_FILE (components.rs).
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::inflight;
+
+/// Test output kept for a revision's RCA (the tail, where failures are reported).
+const OUTPUT_TAIL_CHARS: usize = 4000;
+/// Failing test case names listed in a JUnit failure.
+const MAX_FAILED_NAMES: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestRunner {
+    /// Program; empty skips the test step.
+    pub command: String,
+    pub args: Vec<String>,
+    /// Working dir, relative to the component repo (absolute paths as is); None runs in the repo.
+    pub cwd: Option<PathBuf>,
+    /// Set on top of the orchestrator's environment.
+    pub env: BTreeMap<String, String>,
+    /// Exit codes that pass; default [0].
+    pub exit_codes: Vec<i32>,
+    /// JUnit XML report, relative to the working dir; removed before the run so a stale one never passes.
+    pub junit_xml: Option<PathBuf>,
+}
+
+impl Default for TestRunner {
+    fn default() -> Self {
+        Self {
+            command: String::new(),
+            args: Vec::new(),
+            cwd: None,
+            env: BTreeMap::new(),
+            exit_codes: vec![0],
+            junit_xml: None,
+        }
+    }
+}
+
+/// PAGI_COMPONENTS_FILE `test` object; omitted fields keep the component's current runner.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TestSpec {
+    command: Option<String>,
+    args: Option<Vec<String>>,
+    cwd: Option<PathBuf>,
+    env: Option<BTreeMap<String, String>>,
+    success: Option<SuccessSpec>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SuccessSpec {
+    exit_codes: Option<Vec<i32>>,
+    junit_xml: Option<PathBuf>,
+}
+
+impl TestRunner {
+    /// Runner for an argv (`test_command`); empty skips the test step.
+    pub fn argv(argv: Vec<String>) -> Self {
+        let mut words = argv.into_iter();
+        Self {
+            command: words.next().unwrap_or_default(),
+            args: words.collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.command.is_empty()
+    }
+
+    /// Command line as reported in test results ("passed: <label>").
+    pub fn label(&self) -> String {
+        std::iter::once(self.command.as_str())
+            .chain(self.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Apply `spec` over this runner.
+    pub fn overlay(self, spec: TestSpec) -> Result<Self, String> {
+        let success = spec.success.unwrap_or_default();
+        let runner = Self {
+            command: spec.command.map(|c| c.trim().to_string()).unwrap_or(self.command),
+            args: spec.args.unwrap_or(self.args),
+            cwd: spec.cwd.or(self.cwd),
+            env: spec.env.unwrap_or(self.env),
+            exit_codes: success.exit_codes.unwrap_or(self.exit_codes),
+            junit_xml: success.junit_xml.or(self.junit_xml),
+        };
+        if runner.exit_codes.is_empty() {
+            return Err("test.success.exit_codes is empty".to_string());
+        }
+        if runner.is_empty() && (!runner.args.is_empty() || runner.junit_xml.is_some()) {
+            return Err("test has args or a junit_xml report but no command".to_string());
+        }
+        Ok(runner)
+    }
+}
+
+/// Counts from a JUnit XML report.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct JunitSummary {
+    pub tests: usize,
+    pub failures: usize,
+    pub errors: usize,
+    pub skipped: usize,
+    /// "classname.name" of failing or erroring test cases, in report order.
+    pub failed: Vec<String>,
+}
+
+/// Count test cases and their failures, errors and skips. Counting elements rather than trusting the suite
+/// attributes handles nested suites and reporters that omit or misreport the totals.
+pub fn parse_junit(xml: &str) -> Result<JunitSummary, String> {
+    let mut summary = JunitSummary::default();
+    let mut rooted = false;
+    let mut case: Option<String> = None;
+    let mut rest = xml;
+    while let Some(open) = rest.find('<') {
+        rest = &rest[open..];
+        let skip_to = |end: &str| rest.find(end).map(|i| i + end.len());
+        let consumed = if rest.starts_with("<!--") {
+            skip_to("-->")
+        } else if rest.starts_with("<![CDATA[") {
+            skip_to("]]>")
+        } else if rest.starts_with("<?") {
+            skip_to("?>")
+        } else {
+            skip_to(">")
+        }
+        .ok_or("unterminated markup")?;
+        let tag = &rest[1..consumed - 1];
+        rest = &rest[consumed..];
+        if tag.starts_with('!') || tag.starts_with('?') {
+            continue;
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            if name.trim() == "testcase" {
+                case = None;
+            }
+            continue;
+        }
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let name = tag.split_whitespace().next().unwrap_or_default();
+        match name {
+            "testsuites" | "testsuite" => rooted = true,
+            "testcase" => {
+                summary.tests += 1;
+                let label = match (attr(tag, "classname"), attr(tag, "name")) {
+                    (Some(class), Some(name)) if !class.is_empty() => format!("{}.{}", class, name),
+                    (_, Some(name)) => name,
+                    (Some(class), None) => class,
+                    (None, None) => format!("#{}", summary.tests),
+                };
+                case = (!self_closing).then_some(label);
+            }
+            "failure" | "error" => {
+                if name == "failure" {
+                    summary.failures += 1;
+                } else {
+                    summary.errors += 1;
+                }
+                if let Some(label) = &case {
+                    if summary.failed.last() != Some(label) {
+                        summary.failed.push(label.clone());
+                    }
+                }
+            }
+            "skipped" => summary.skipped += 1,
+            _ => {}
+        }
+    }
+    if !rooted {
+        return Err("no <testsuite> or <testsuites> element".to_string());
+    }
+    Ok(summary)
+}
+
+/// Unescaped value of attribute `key` in a start tag.
+fn attr(tag: &str, key: &str) -> Option<String> {
+    let mut rest = tag;
+    loop {
+        let at = rest.find(key)?;
+        let preceded = rest[..at].ends_with(|c: char| c.is_whitespace());
+        rest = &rest[at + key.len()..];
+        let after = rest.trim_start();
+        let Some(value) = after.strip_prefix('=').map(str::trim_start) else {
+            continue;
+        };
+        let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            continue;
+        };
+        let end = value[1..].find(quote)? + 1;
+        if preceded {
+            return Some(
+                value[1..end]
+                    .replace("&lt;", "<")
+                    .replace("&gt;", ">")
+                    .replace("&quot;", "\"")
+                    .replace("&apos;", "'")
+                    .replace("&amp;", "&"),
+            );
+        }
+        // Another attribute ending in `key` (classname for name): skip its value.
+        rest = &value[end + 1..];
+    }
+}
+
+/// Last OUTPUT_TAIL_CHARS of the combined output.
+fn tail(stdout: &[u8], stderr: &[u8]) -> String {
+    let text = format!("{}{}", String::from_utf8_lossy(stdout), String::from_utf8_lossy(stderr));
+    let text = text.trim();
+    let start = text
+        .char_indices()
+        .rev()
+        .nth(OUTPUT_TAIL_CHARS - 1)
+        .map_or(0, |(i, _)| i);
+    text[start..].to_string()
+}
+
+/// Run the test step for a component in `repo`. The child is recorded for AbortRequest and killed if the handler is
+/// dropped; Err carries the reason and the tail of its stdout and stderr.
+pub async fn run(runner: &TestRunner, repo: &Path) -> Result<(), String> {
+    if runner.is_empty() {
+        return Ok(());
+    }
+    let label = runner.label();
+    let dir = runner
+        .cwd
+        .as_ref()
+        .map_or_else(|| repo.to_path_buf(), |cwd| repo.join(cwd));
+    let report = runner.junit_xml.as_ref().map(|path| dir.join(path));
+    if let Some(report) = &report {
+        match std::fs::remove_file(report) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("{}: remove stale {}: {}", label, report.display(), e)),
+        }
+    }
+    let child = tokio::process::Command::new(&runner.command)
+        .args(&runner.args)
+        .envs(&runner.env)
+        .current_dir(&dir)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("spawn {} in {}: {}", runner.command, dir.display(), e))?;
+    inflight::record_child_pid(child.id());
+    let output = child.wait_with_output().await;
+    inflight::record_child_pid(None);
+    let output = output.map_err(|e| format!("wait for {}: {}", runner.command, e))?;
+    if !output
+        .status
+        .code()
+        .is_some_and(|code| runner.exit_codes.contains(&code))
+    {
+        return Err(format!(
+            "{}: {}\n{}",
+            label,
+            output.status,
+            tail(&output.stdout, &output.stderr)
+        ));
+    }
+    let Some(report) = report else {
+        return Ok(());
+    };
+    let summary = std::fs::read_to_string(&report)
+        .map_err(|e| e.to_string())
+        .and_then(|xml| parse_junit(&xml));
+    let verdict = match summary {
+        Err(e) => format!("JUnit report {}: {}", report.display(), e),
+        Ok(s) if s.tests == 0 => format!("JUnit report {} lists no test cases", report.display()),
+        Ok(s) if s.failures + s.errors > 0 => {
+            let mut failed = s
+                .failed
+                .iter()
+                .take(MAX_FAILED_NAMES)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ");
+            if s.failed.len() > MAX_FAILED_NAMES {
+                failed.push_str(", …");
+            }
+            format!(
+                "JUnit report {}: {} failure(s), {} error(s) of {} test(s): {}",
+                report.display(),
+                s.failures,
+                s.errors,
+                s.tests,
+                failed
+            )
+        }
+        Ok(_) => return Ok(()),
+    };
+    Err(format!(
+        "{}: {}\n{}",
+        label,
+        verdict,
+        tail(&output.stdout, &output.stderr)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+        <testsuites><testsuite name="pytest" tests="3" failures="0">
+          <testcase classname="tests.test_skills" name="test_peek"/>
+          <testcase classname="tests.test_skills" name="test_write"><skipped message="slow"/></testcase>
+          <testcase classname="tests.test_skills" name="test_&quot;quoted&quot;">
+            <failure message="assert 1 == 2"><![CDATA[<error> in output is not an element]]></failure>
+          </testcase>
+          <!-- <testcase name="commented out"/> -->
+        </testsuite></testsuites>"#;
+
+    fn temp_repo() -> PathBuf {
+        let repo = std::env::temp_dir().join(format!("pagi_test_runner_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(repo.join("web")).unwrap();
+        repo
+    }
+
+    fn sh(script: &str) -> TestRunner {
+        TestRunner {
+            command: "sh".into(),
+            args: vec!["-c".into(), script.into()],
+            ..Default::default()
+        }
+    }
+
+    /// `sh -c script` run from web/ with PAGI_REPORT naming the junit report it must write.
+    fn junit(script: &str) -> TestRunner {
+        TestRunner {
+            cwd: Some("web".into()),
+            env: BTreeMap::from([("PAGI_REPORT".to_string(), "junit.xml".to_string())]),
+            junit_xml: Some("junit.xml".into()),
+            ..sh(script)
+        }
+    }
+
+    #[test]
+    fn junit_reports_count_outcomes_and_name_failures() {
+        let summary = parse_junit(REPORT).unwrap();
+        assert_eq!(
+            (summary.tests, summary.failures, summary.errors, summary.skipped),
+            (3, 1, 0, 1)
+        );
+        assert_eq!(summary.failed, vec![r#"tests.test_skills.test_"quoted""#.to_string()]);
+        assert!(parse_junit("<html></html>").is_err());
+    }
+
+    #[tokio::test]
+    async fn no_command_skips_the_step() {
+        let repo = temp_repo();
+        assert_eq!(run(&TestRunner::default(), &repo).await, Ok(()));
+        let _ = std::fs::remove_dir_all(repo);
+    }
+
+    #[tokio::test]
+    async fn failing_exit_codes_report_the_command_and_its_output() {
+        let repo = temp_repo();
+        assert_eq!(run(&sh("exit 0"), &repo).await, Ok(()));
+        let err = run(&sh("echo boom; exit 3"), &repo).await.unwrap_err();
+        assert!(
+            err.starts_with("sh -c echo boom; exit 3: ") && err.ends_with("boom"),
+            "{}",
+            err
+        );
+        let _ = std::fs::remove_dir_all(repo);
+    }
+
+    #[tokio::test]
+    async fn listed_exit_codes_pass() {
+        let repo = temp_repo();
+        let lenient = TestRunner {
+            exit_codes: vec![0, 5],
+            ..sh("exit 5")
+        };
+        assert_eq!(
+            run(&lenient, &repo).await,
+            Ok(()),
+            "pytest exits 5 when it collects nothing"
+        );
+        let _ = std::fs::remove_dir_all(repo);
+    }
+
+    #[tokio::test]
+    async fn junit_failures_fail_the_step() {
+        let repo = temp_repo();
+        std::fs::write(repo.join("web/report.xml"), REPORT).unwrap();
+        let err = run(&junit("cp report.xml \"$PAGI_REPORT\""), &repo).await.unwrap_err();
+        assert!(
+            err.contains("1 failure(s), 0 error(s) of 3 test(s): tests.test_skills.test_"),
+            "{}",
+            err
+        );
+        let _ = std::fs::remove_dir_all(repo);
+    }
+
+    #[tokio::test]
+    async fn junit_runs_apply_cwd_and_env() {
+        let repo = temp_repo();
+        std::fs::write(
+            repo.join("web/passing.xml"),
+            r#"<testsuite><testcase name="ok"/></testsuite>"#,
+        )
+        .unwrap();
+        assert_eq!(run(&junit("cp passing.xml \"$PAGI_REPORT\""), &repo).await, Ok(()));
+        let _ = std::fs::remove_dir_all(repo);
+    }
+
+    #[tokio::test]
+    async fn stale_junit_reports_are_removed_before_the_run() {
+        let repo = temp_repo();
+        std::fs::write(repo.join("web/junit.xml"), r#"<testsuite><testcase name="ok"/></testsuite>"#).unwrap();
+        let err = run(&junit("true"), &repo).await.unwrap_err();
+        assert!(err.contains("junit.xml"), "the previous report was removed: {}", err);
+        let _ = std::fs::remove_dir_all(repo);
+    }
+}
@@ -0,0 +1,179 @@
+// Cold/warm tiering of L4 points. A KB with `cold_after_secs` (PAGI_KB_FILE, or PAGI_KB_COLD_AFTER_SECS for
+// every KB) keeps a cold tier next to each of its collections ("<collection>.cold": on disk and quantized, see
+// KbSpec::cold_tier). The maintenance pass moves points neither written (`at`) nor returned by a search within
+// that window to the cold tier, and cold points a search returned since back; searches, dedup lookups, deletes
+// and retention cover both tiers, so callers never see which tier a point lives in.
+// Read times are kept in process only: nothing is demoted until the orchestrator has tracked reads for a full
+// window, so a restart never makes every point look idle.
+
+use std::collections::{HashMap, HashSet};
+
+use dashmap::DashMap;
+
+use crate::kb_registry::Distance;
+use crate::vector_store::ScoredPoint;
+
+/// Last search read per point: (hot collection, point id) → unix secs.
+#[derive(Default)]
+pub struct ReadLog {
+    reads: DashMap<(String, String), i64>,
+}
+
+impl ReadLog {
+    pub fn record(&self, collection: &str, points: &[ScoredPoint], now: i64) {
+        for p in points {
+            self.reads.insert((collection.to_string(), p.id.clone()), now);
+        }
+    }
+
+    fn last_read(&self, collection: &str, id: &str) -> Option<i64> {
+        self.reads.get(&(collection.to_string(), id.to_string())).map(|t| *t)
+    }
+
+    /// Drop `collection`'s reads older than `cutoff`; they no longer keep a point warm.
+    pub fn forget_before(&self, collection: &str, cutoff: i64) {
+        self.reads.retain(|(c, _), at| c != collection || *at >= cutoff);
+    }
+
+    pub fn forget(&self, collection: &str, ids: &[String]) {
+        for id in ids {
+            self.reads.remove(&(collection.to_string(), id.clone()));
+        }
+    }
+}
+
+/// What one tiering pass does to a collection and its cold tier.
+#[derive(Debug, Default, PartialEq)]
+pub struct TierPlan {
+    /// Idle hot points to move to the cold tier.
+    pub demote: Vec<String>,
+    /// Cold points read within the window, to move back.
+    pub promote: Vec<String>,
+    /// Cold copies of points that are also hot (a move interrupted between its upsert and delete); hot wins.
+    pub stale: Vec<String>,
+}
+
+/// Plan for `collection` at `now` from its hot and cold points (id, payload with `at`) and the reads tracked
+/// since `tracking_since`. Points without `at` count as written long ago.
+pub fn plan(
+    collection: &str,
+    hot: &[(String, HashMap<String, String>)],
+    cold: &[(String, HashMap<String, String>)],
+    reads: &ReadLog,
+    cold_after_secs: u64,
+    tracking_since: i64,
+    now: i64,
+) -> TierPlan {
+    let cutoff = now.saturating_sub(cold_after_secs.min(i64::MAX as u64) as i64);
+    let warm = |id: &str| reads.last_read(collection, id).is_some_and(|at| at >= cutoff);
+    let hot_ids: HashSet<&str> = hot.iter().map(|(id, _)| id.as_str()).collect();
+    let mut plan = TierPlan::default();
+    if tracking_since <= cutoff {
+        plan.demote = hot
+            .iter()
+            .filter(|(id, payload)| {
+                let written = payload.get("at").and_then(|v| v.parse::<i64>().ok()).unwrap_or(i64::MIN);
+                written < cutoff && !warm(id)
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+    }
+    for (id, _) in cold {
+        if hot_ids.contains(id.as_str()) {
+            plan.stale.push(id.clone());
+        } else if warm(id) {
+            plan.promote.push(id.clone());
+        }
+    }
+    plan
+}
+
+/// Hits from both tiers in one ranking (ascending distance for euclid, else descending similarity), capped
+/// at `limit`.
+pub fn merge(mut hot: Vec<ScoredPoint>, cold: Vec<ScoredPoint>, distance: Distance, limit: usize) -> Vec<ScoredPoint> {
+    if cold.is_empty() {
+        return hot;
+    }
+    hot.extend(cold);
+    match distance {
+        Distance::Euclid => hot.sort_by(|a, b| a.score.total_cmp(&b.score)),
+        Distance::Cosine | Distance::Dot => hot.sort_by(|a, b| b.score.total_cmp(&a.score)),
+    }
+    hot.truncate(limit);
+    hot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Stored = (String, HashMap<String, String>);
+
+    fn point(id: &str, at: Option<i64>) -> Stored {
+        let payload = at.map(|at| HashMap::from([("at".to_string(), at.to_string())])).unwrap_or_default();
+        (id.to_string(), payload)
+    }
+
+    fn hit(id: &str, score: f32) -> ScoredPoint {
+        ScoredPoint {
+            id: id.to_string(),
+            score,
+            payload: HashMap::new(),
+        }
+    }
+
+    fn hot() -> [Stored; 4] {
+        [point("fresh", Some(950)), point("idle", Some(100)), point("read", Some(100)), point("old", None)]
+    }
+
+    fn cold() -> [Stored; 3] {
+        [point("sleeping", Some(50)), point("woken", Some(50)), point("read", Some(100))]
+    }
+
+    /// "read" and "woken" read in kb at 920; "idle" read only in another KB.
+    fn reads() -> ReadLog {
+        let reads = ReadLog::default();
+        reads.record("kb", &[hit("read", 0.9), hit("woken", 0.8)], 920);
+        reads.record("other", &[hit("idle", 0.9)], 990);
+        reads
+    }
+
+    fn ids(points: &[ScoredPoint]) -> Vec<&str> {
+        points.iter().map(|p| p.id.as_str()).collect()
+    }
+
+    #[test]
+    fn idle_and_undated_hot_points_demote() {
+        assert_eq!(plan("kb", &hot(), &cold(), &reads(), 100, 0, 1000).demote, ["idle", "old"]);
+    }
+
+    #[test]
+    fn read_cold_points_promote() {
+        assert_eq!(plan("kb", &hot(), &cold(), &reads(), 100, 0, 1000).promote, ["woken"]);
+    }
+
+    #[test]
+    fn a_point_in_both_tiers_keeps_its_hot_copy() {
+        assert_eq!(plan("kb", &hot(), &cold(), &reads(), 100, 0, 1000).stale, ["read"]);
+    }
+
+    #[test]
+    fn nothing_demotes_until_reads_span_one_window() {
+        assert!(plan("kb", &hot(), &cold(), &reads(), 100, 901, 1000).demote.is_empty());
+    }
+
+    #[test]
+    fn forgotten_reads_no_longer_promote() {
+        let reads = reads();
+        reads.forget_before("kb", 950);
+        assert!(plan("kb", &hot(), &cold(), &reads, 100, 0, 1000).promote.is_empty());
+    }
+
+    #[test]
+    fn merged_tiers_rank_by_the_kb_distance() {
+        let merged = merge(vec![hit("a", 0.9), hit("b", 0.5)], vec![hit("c", 0.7)], Distance::Cosine, 2);
+        assert_eq!(ids(&merged), ["a", "c"]);
+        let merged = merge(vec![hit("a", 0.9), hit("b", 0.5)], vec![hit("c", 0.7)], Distance::Euclid, 2);
+        assert_eq!(ids(&merged), ["b", "c"]);
+    }
+}
@@ -0,0 +1,153 @@
+// Per-point expiry for L4. A point upserted with a `ttl_seconds` payload field (or under UpsertRequest.ttl_seconds,
+// the batch default) is stamped with `expires_at` (unix secs: write time + ttl), so each write restarts its
+// clock. A point may instead carry its own `expires_at` (unix secs or RFC 3339, stored as unix secs) for facts
+// valid until a known time. Searches (and upsert dedup) skip points whose `expires_at` has passed at once; the
+// scheduler task "ttl_sweep" (every PAGI_TTL_SWEEP_SECS, default 60; 0 disables) deletes them from every KB and
+// its cold tier. Expired points are dropped outright, not archived to L7: a TTL marks scratch data (exploratory
+// reasoning, intermediate embeddings, short-lived incident context) nobody wants back.
+
+use std::collections::HashMap;
+
+use crate::proto::pagi_proto::{FilterCondition, FilterRange, SearchFilter};
+
+/// Payload field a client sets: seconds the point lives after each write; 0 means no expiry.
+pub const TTL_FIELD: &str = "ttl_seconds";
+/// Payload field with the expiry (unix secs): stamped from TTL_FIELD, else as the client set it.
+pub const EXPIRES_FIELD: &str = "expires_at";
+
+/// Stamp `expires_at` on a point being upserted at `now`, applying `default_ttl` (non-zero) when the point has
+/// neither a TTL nor an expiry of its own. A TTL wins over a client `expires_at`. Errors on a TTL that is not a
+/// whole number of seconds or an expiry that is neither unix seconds nor RFC 3339.
+pub fn stamp(payload: &mut HashMap<String, String>, default_ttl: u64, now: i64) -> Result<(), String> {
+    if default_ttl > 0 && !payload.contains_key(EXPIRES_FIELD) {
+        payload.entry(TTL_FIELD.to_string()).or_insert_with(|| default_ttl.to_string());
+    }
+    let Some(raw) = payload.get(TTL_FIELD) else {
+        if let Some(raw) = payload.get(EXPIRES_FIELD) {
+            let expires_at = parse_expiry(raw)?;
+            payload.insert(EXPIRES_FIELD.to_string(), expires_at.to_string());
+        }
+        return Ok(());
+    };
+    let ttl = raw
+        .trim()
+        .parse::<u64>()
+        .map_err(|_| format!("{} must be a whole number of seconds, got {:?}", TTL_FIELD, raw))?;
+    if ttl == 0 {
+        payload.remove(TTL_FIELD);
+        payload.remove(EXPIRES_FIELD);
+    } else {
+        let expires_at = now.saturating_add(ttl.min(i64::MAX as u64) as i64);
+        payload.insert(EXPIRES_FIELD.to_string(), expires_at.to_string());
+    }
+    Ok(())
+}
+
+fn parse_expiry(raw: &str) -> Result<i64, String> {
+    let raw = raw.trim();
+    raw.parse::<i64>()
+        .or_else(|_| chrono::DateTime::parse_from_rfc3339(raw).map(|at| at.timestamp()))
+        .map_err(|_| format!("{} must be unix seconds or RFC 3339, got {:?}", EXPIRES_FIELD, raw))
+}
+
+/// Whether a point with `payload` has expired at `now`.
+pub fn expired(payload: &HashMap<String, String>, now: i64) -> bool {
+    payload
+        .get(EXPIRES_FIELD)
+        .and_then(|at| at.parse::<i64>().ok())
+        .is_some_and(|at| at <= now)
+}
+
+/// `filter` (if any) narrowed to the points still live at `now`.
+pub fn unexpired(filter: Option<SearchFilter>, now: i64) -> SearchFilter {
+    let mut filter = filter.unwrap_or_default();
+    filter.must_not.extend(expired_filter(now).must);
+    filter
+}
+
+/// Filter matching the points expired at `now`.
+pub fn expired_filter(now: i64) -> SearchFilter {
+    SearchFilter {
+        must: vec![FilterCondition {
+            key: EXPIRES_FIELD.to_string(),
+            range: Some(FilterRange {
+                gte: None,
+                lte: Some(now as f64),
+            }),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+/// PAGI_TTL_SWEEP_SECS: seconds between sweeps (default 60; 0 disables).
+pub fn sweep_secs_from_env() -> u64 {
+    std::env::var("PAGI_TTL_SWEEP_SECS")
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or(60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector_store::filter_matches;
+
+    fn payload(fields: &[(&str, &str)]) -> HashMap<String, String> {
+        fields.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn a_points_own_ttl_wins_over_the_batch_default() {
+        let mut scratch = payload(&[("ttl_seconds", "60")]);
+        stamp(&mut scratch, 3600, 1_000).unwrap();
+        assert_eq!(scratch["expires_at"], "1060");
+        let mut batch = payload(&[]);
+        stamp(&mut batch, 3600, 1_000).unwrap();
+        assert_eq!((batch["ttl_seconds"].as_str(), batch["expires_at"].as_str()), ("3600", "4600"));
+    }
+
+    #[test]
+    fn a_zero_ttl_clears_the_expiry() {
+        let mut kept = payload(&[("ttl_seconds", "0"), ("expires_at", "5")]);
+        stamp(&mut kept, 0, 1_000).unwrap();
+        assert!(kept.is_empty());
+        assert!(stamp(&mut payload(&[("ttl_seconds", "1.5")]), 0, 1_000).is_err());
+    }
+
+    #[test]
+    fn client_expiries_are_unix_seconds_or_rfc3339_and_beat_the_batch_default() {
+        let mut until = payload(&[("expires_at", "1970-01-01T00:30:00+00:00")]);
+        stamp(&mut until, 3600, 1_000).unwrap();
+        assert_eq!(until, payload(&[("expires_at", "1800")]));
+        let mut secs = payload(&[("expires_at", " 1800 ")]);
+        stamp(&mut secs, 0, 1_000).unwrap();
+        assert_eq!(secs, payload(&[("expires_at", "1800")]));
+        assert!(stamp(&mut payload(&[("expires_at", "tomorrow")]), 0, 1_000).is_err());
+    }
+
+    #[test]
+    fn points_expire_at_their_expiry_second() {
+        let until = payload(&[("expires_at", "1800")]);
+        assert_eq!((expired(&until, 1_799), expired(&until, 1_800)), (false, true));
+        assert!(!expired(&payload(&[]), i64::MAX), "no expiry, never expired");
+    }
+
+    #[test]
+    fn the_sweep_filter_matches_only_expired_points() {
+        let scratch = payload(&[("expires_at", "1060")]);
+        assert!(!filter_matches(&expired_filter(1_059), &scratch));
+        assert!(filter_matches(&expired_filter(1_060), &scratch));
+        assert!(!filter_matches(&expired_filter(i64::MAX), &payload(&[])));
+    }
+
+    #[test]
+    fn search_filters_hide_expired_points_and_keep_the_callers_conditions() {
+        let scratch = payload(&[("expires_at", "1060")]);
+        assert!(filter_matches(&unexpired(None, 1_059), &scratch));
+        assert!(!filter_matches(&unexpired(None, 1_060), &scratch));
+        assert!(filter_matches(&unexpired(None, i64::MAX), &payload(&[])));
+        let live = unexpired(Some(expired_filter(0)), 1_800);
+        assert_eq!((live.must.len(), live.must_not.len()), (1, 1));
+    }
+}
@@ -0,0 +1,170 @@
+// Request shape validation. Field constraints are declared next to the fields in pagi.proto as
+// `@validate(...)` comment annotations (protovalidate-style); build.rs turns them into `impl Validate` for each
+// annotated message and every message holding one. Rules: strings min_len / max_len (characters), uuid and
+// reasoning_id (empty or a ReasoningId); repeated fields min_items / max_items; maps min_pairs / max_pairs;
+// numbers gte / lte.
+// Handlers call `validate` first, so bad input fails with INVALID_ARGUMENT naming every violated field
+// (message text plus a BadRequest detail) instead of surfacing as a downstream error.
+
+use prost::Message;
+use tonic::{Code, Status};
+
+use crate::proto::pagi_proto::{self, BadRequest, FieldViolation};
+use crate::reasoning_id::ReasoningId;
+
+pub trait Validate {
+    /// Append a violation per broken constraint; `path` prefixes field names ("" at the top level).
+    fn check(&self, path: &str, out: &mut Vec<FieldViolation>);
+}
+
+include!(concat!(env!("OUT_DIR"), "/pagi_validate.rs"));
+
+/// INVALID_ARGUMENT listing every violated constraint of `msg`, with the list as a BadRequest detail.
+pub fn validate<T: Validate>(msg: &T) -> Result<(), Status> {
+    let mut violations = Vec::new();
+    msg.check("", &mut violations);
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let message = violations
+        .iter()
+        .map(|v| format!("{}: {}", v.field, v.description))
+        .collect::<Vec<_>>()
+        .join("; ");
+    let detail = BadRequest {
+        field_violations: violations,
+    };
+    Err(Status::with_details(Code::InvalidArgument, message, detail.encode_to_vec().into()))
+}
+
+fn field_path(path: &str, field: &str) -> String {
+    if path.is_empty() {
+        field.to_string()
+    } else {
+        format!("{}.{}", path, field)
+    }
+}
+
+fn violation(out: &mut Vec<FieldViolation>, field: &str, description: String) {
+    out.push(FieldViolation {
+        field: field.to_string(),
+        description,
+    });
+}
+
+fn at_least(out: &mut Vec<FieldViolation>, field: &str, n: usize, min: usize, unit: &str) {
+    if n >= min {
+        return;
+    }
+    let description = if min == 1 {
+        "must not be empty".to_string()
+    } else {
+        format!("must have at least {} {} (got {})", min, unit, n)
+    };
+    violation(out, field, description);
+}
+
+fn at_most(out: &mut Vec<FieldViolation>, field: &str, n: usize, max: usize, unit: &str) {
+    if n > max {
+        violation(out, field, format!("must have at most {} {} (got {})", max, unit, n));
+    }
+}
+
+fn gte(out: &mut Vec<FieldViolation>, field: &str, value: f64, min: f64) {
+    if value < min {
+        violation(out, field, format!("must be >= {} (got {})", min, value));
+    }
+}
+
+fn lte(out: &mut Vec<FieldViolation>, field: &str, value: f64, max: f64) {
+    if value > max {
+        violation(out, field, format!("must be <= {} (got {})", max, value));
+    }
+}
+
+fn uuid(out: &mut Vec<FieldViolation>, field: &str, value: &str) {
+    if ::uuid::Uuid::parse_str(value).is_err() {
+        violation(out, field, format!("must be a UUID (got {:?})", value));
+    }
+}
+
+/// Empty, or a reasoning id (see `ReasoningId`).
+fn reasoning_id(out: &mut Vec<FieldViolation>, field: &str, value: &str) {
+    if value.is_empty() {
+        return;
+    }
+    if let Err(e) = value.parse::<ReasoningId>() {
+        violation(out, field, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::pagi_proto::{ApplyRequest, PipelineRequest, PipelineStep, SearchRequest, TraceQueryRequest};
+
+    const UUID: &str = "9b2e4c1e-8f0a-4d7b-a3c2-5e6f7a8b9c0d";
+
+    #[test]
+    fn valid_requests_pass() {
+        let search = SearchRequest {
+            kb_name: "kb_core".into(),
+            limit: 10,
+            ..Default::default()
+        };
+        assert!(validate(&search).is_ok());
+    }
+
+    #[test]
+    fn every_violation_is_reported_in_the_message_and_details() {
+        let status = validate(&SearchRequest {
+            limit: 5000,
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(status.message(), "kb_name: must not be empty; limit: must be <= 1000 (got 5000)");
+        let detail = BadRequest::decode(status.details()).unwrap();
+        assert_eq!(detail.field_violations.len(), 2);
+        assert_eq!(detail.field_violations[1].field, "limit");
+    }
+
+    #[test]
+    fn nested_violations_carry_their_field_path() {
+        let pipeline = PipelineRequest {
+            steps: vec![
+                PipelineStep {
+                    skill_name: "peek_file".into(),
+                    ..Default::default()
+                },
+                PipelineStep::default(),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            validate(&pipeline).unwrap_err().message(),
+            "steps[1].skill_name: must not be empty"
+        );
+    }
+
+    #[test]
+    fn patch_ids_must_be_uuids() {
+        let apply = |patch_id: &str| ApplyRequest {
+            patch_id: patch_id.into(),
+            ..Default::default()
+        };
+        assert!(validate(&apply(UUID)).is_ok());
+        assert!(validate(&apply("p-1")).unwrap_err().message().contains("must be a UUID"));
+    }
+
+    #[test]
+    fn reasoning_ids_are_optional_uuid_paths() {
+        let trace = |reasoning_id: &str| TraceQueryRequest {
+            reasoning_id: reasoning_id.into(),
+            ..Default::default()
+        };
+        assert!(validate(&trace("")).is_ok());
+        assert!(validate(&trace(&format!("{}/step-1", UUID))).is_ok());
+        assert!(validate(&trace("trace-1")).unwrap_err().message().starts_with("reasoning_id: must start with"));
+    }
+}
@@ -0,0 +1,1651 @@
+// L4 vector backends behind one trait (collection management, upsert, search, delete), selected by
+// PAGI_VECTOR_BACKEND: "qdrant" (default; external server), "memory" (in-process HNSW, persisted only with
+// PAGI_LOCAL_INDEX_DIR: point_log.rs) or "pgvector" (tables in an existing Postgres: pgvector_store.rs).
+// Errors are strings so MemoryManager's breaker can classify outages by message for any backend.
+// Search takes SearchRequest's payload filter: Qdrant evaluates it server-side; the memory backend scans
+// matching points exactly. Canonical integer payload values are stored as numbers so range filters apply.
+// Quantization goes through Qdrant's REST API (PATCH /collections/{name}) since the gRPC client predates it.
+// KBs with named vector spaces store every point in each space; `vector_name` picks the space searched.
+// QdrantStore spreads calls round-robin over PAGI_QDRANT_POOL_SIZE clients (one gRPC connection each);
+// qdrant-client drops a client's channel after a transport error, so its next call reconnects.
+// Native snapshots (SnapshotKb / RestoreKb) use Qdrant's REST snapshot API; the memory backend has none, and callers
+// dump points portably instead (snapshot.rs).
+// `scroll` pages through a collection in a stable order for ScrollKb exports.
+// `optimize` (CompactMemory) triggers Qdrant's optimizer over REST; the memory backend rebuilds its graphs without
+// the tombstones deletes and replacements leave behind.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+use std::future::Future;
+use std::io::Write;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::RwLock;
+
+use qdrant_client::prelude::{Payload, PointStruct, QdrantClient};
+use qdrant_client::qdrant::{
+    point_id::PointIdOptions, r#match::MatchValue, value::Kind, vectors::VectorsOptions, vectors_config,
+    with_payload_selector, with_vectors_selector, Condition,
+    CreateCollection, Distance as QdrantDistance, FieldCondition, Filter, Match, NamedVectors, OptimizersConfigDiff,
+    PayloadIncludeSelector, PointId, Range, RetrievedPoint, ScrollPoints, SearchPoints, Value, Vector, VectorParams,
+    VectorParamsMap, Vectors, VectorsConfig, WithPayloadSelector, WithVectorsSelector,
+};
+
+use crate::kb_registry::{Distance, KbSpec, Quantization, Shape};
+use crate::point_log::{LogOp, PointLog};
+use crate::proto::pagi_proto::{DenseVector, FilterCondition, SearchFilter, VectorPoint};
+
+pub type StoreFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, String>> + Send + 'a>>;
+
+/// Search hit: point id, score (cosine/dot similarity or euclid distance) and string payload fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoredPoint {
+    pub id: String,
+    pub score: f32,
+    pub payload: HashMap<String, String>,
+}
+
+/// One page of a scroll (in the backend's stable point order) and where the next page starts (None after the last).
+#[derive(Debug, Default)]
+pub struct ScrollPage {
+    pub points: Vec<VectorPoint>,
+    pub next_offset: Option<String>,
+}
+
+pub trait VectorStore: Send + Sync {
+    /// Backend name reported as SearchResponse.source.
+    fn name(&self) -> &'static str;
+    /// Vector layout of an existing collection (None when absent).
+    fn describe_collection<'a>(&'a self, collection: &'a str) -> StoreFuture<'a, Option<Shape>>;
+    /// Number of points in a collection (None when absent).
+    fn point_count<'a>(&'a self, collection: &'a str) -> StoreFuture<'a, Option<u64>>;
+    /// Create a collection with the spec's vector layout, distance and storage.
+    fn create_collection<'a>(&'a self, spec: &'a KbSpec) -> StoreFuture<'a, ()>;
+    /// Apply a quantization setting to an existing collection (idempotent; the backend re-indexes in the
+    /// background).
+    fn set_quantization<'a>(&'a self, collection: &'a str, quantization: Quantization) -> StoreFuture<'a, ()>;
+    /// Insert or replace points by id; returns the number written. Points of named-vector collections carry
+    /// one `vectors` entry per space.
+    fn upsert<'a>(&'a self, collection: &'a str, points: Vec<VectorPoint>) -> StoreFuture<'a, usize>;
+    /// Nearest `limit` points to `vector` in the `vector_name` space ("" for unnamed vectors), restricted to
+    /// payloads matching `filter` when given.
+    fn search<'a>(
+        &'a self,
+        collection: &'a str,
+        vector_name: &'a str,
+        vector: Vec<f32>,
+        limit: usize,
+        filter: Option<SearchFilter>,
+    ) -> StoreFuture<'a, Vec<ScoredPoint>>;
+    /// Remove points by id; returns the number of ids submitted (Qdrant) or found (memory).
+    fn delete<'a>(&'a self, collection: &'a str, ids: Vec<String>) -> StoreFuture<'a, usize>;
+    /// Merge `fields` into the payloads of existing points; returns the number of ids submitted (Qdrant) or found (memory).
+    fn set_payload<'a>(
+        &'a self,
+        collection: &'a str,
+        ids: Vec<String>,
+        fields: HashMap<String, String>,
+    ) -> StoreFuture<'a, usize>;
+    /// Every point id with the listed payload `fields` it has, for retention and decay passes.
+    fn scan<'a>(&'a self, collection: &'a str, fields: &'a [&'a str]) -> StoreFuture<'a, Vec<(String, HashMap<String, String>)>>;
+    /// Full points (vector and payload) for the ids that exist, for archival before deletes.
+    fn get<'a>(&'a self, collection: &'a str, ids: Vec<String>) -> StoreFuture<'a, Vec<VectorPoint>>;
+    /// Up to `limit` points with full payloads (vectors only when asked) matching `filter`, starting at `offset`
+    /// (a previous page's next_offset; None from the start).
+    fn scroll<'a>(
+        &'a self,
+        collection: &'a str,
+        offset: Option<String>,
+        limit: usize,
+        filter: Option<SearchFilter>,
+        with_vectors: bool,
+    ) -> StoreFuture<'a, ScrollPage>;
+    /// Cheap liveness check for the health probe.
+    fn health_check(&self) -> StoreFuture<'_, ()>;
+    /// Write a backend-native snapshot of `collection` to `path`; false (nothing written) when the backend has no
+    /// native format.
+    fn snapshot_to<'a>(&'a self, collection: &'a str, path: &'a Path) -> StoreFuture<'a, bool>;
+    /// Replace `collection` with a native snapshot written by `snapshot_to`.
+    fn restore_from<'a>(&'a self, collection: &'a str, path: &'a Path) -> StoreFuture<'a, ()>;
+    /// Reclaim the space of deleted and replaced points; the number purged, or None when the backend reclaims in
+    /// the background.
+    fn optimize<'a>(&'a self, collection: &'a str) -> StoreFuture<'a, Option<u64>>;
+}
+
+/// Payload string that round-trips through i64 ("42", "-7"; not "007" or "4.0").
+pub fn canonical_int(value: &str) -> Option<i64> {
+    value.parse::<i64>().ok().filter(|n| n.to_string() == value)
+}
+
+/// Qdrant payload for string fields, canonical ints stored as integers.
+fn qdrant_payload(fields: HashMap<String, String>) -> Payload {
+    let mut payload = Payload::new();
+    for (k, v) in fields {
+        match canonical_int(&v) {
+            Some(n) => payload.insert(k, n),
+            None => payload.insert(k, v),
+        }
+    }
+    payload
+}
+
+pub fn has_conditions(filter: &SearchFilter) -> bool {
+    !(filter.must.is_empty() && filter.should.is_empty() && filter.must_not.is_empty())
+}
+
+/// Whether `payload` satisfies `filter`: all `must`, any `should` (when present), no `must_not`.
+pub fn filter_matches(filter: &SearchFilter, payload: &HashMap<String, String>) -> bool {
+    let holds = |c: &FilterCondition| {
+        let Some(value) = payload.get(&c.key) else {
+            return false;
+        };
+        match &c.range {
+            Some(r) => value
+                .parse::<f64>()
+                .is_ok_and(|x| r.gte.is_none_or(|g| x >= g) && r.lte.is_none_or(|l| x <= l)),
+            None => *value == c.r#match,
+        }
+    };
+    filter.must.iter().all(holds)
+        && (filter.should.is_empty() || filter.should.iter().any(holds))
+        && !filter.must_not.iter().any(holds)
+}
+
+fn qdrant_condition(c: &FilterCondition) -> Condition {
+    let field = match &c.range {
+        Some(r) => FieldCondition {
+            key: c.key.clone(),
+            range: Some(Range {
+                gte: r.gte,
+                lte: r.lte,
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+        None => FieldCondition {
+            key: c.key.clone(),
+            r#match: Some(Match {
+                match_value: Some(match canonical_int(&c.r#match) {
+                    Some(n) => MatchValue::Integer(n),
+                    None => MatchValue::Keyword(c.r#match.clone()),
+                }),
+            }),
+            ..Default::default()
+        },
+    };
+    field.into()
+}
+
+/// Qdrant form of a SearchRequest filter; None when it has no conditions.
+fn qdrant_filter(filter: &SearchFilter) -> Option<Filter> {
+    let conditions = |cs: &[FilterCondition]| cs.iter().map(qdrant_condition).collect::<Vec<_>>();
+    has_conditions(filter).then(|| Filter {
+        must: conditions(&filter.must),
+        should: conditions(&filter.should),
+        must_not: conditions(&filter.must_not),
+    })
+}
+
+/// String, integer and float payload values as strings (other kinds are dropped).
+fn string_payload(payload: HashMap<String, Value>) -> HashMap<String, String> {
+    payload
+        .into_iter()
+        .filter_map(|(k, v)| match v.kind {
+            Some(Kind::StringValue(s)) => Some((k, s)),
+            Some(Kind::IntegerValue(n)) => Some((k, n.to_string())),
+            Some(Kind::DoubleValue(x)) => Some((k, x.to_string())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Point id as sent to Qdrant: unsigned integers stay numeric, anything else is a UUID string.
+fn point_id(id: String) -> PointId {
+    match canonical_int(&id).and_then(|n| u64::try_from(n).ok()) {
+        Some(n) => PointId::from(n),
+        None => PointId::from(id),
+    }
+}
+
+fn point_id_string(id: Option<PointId>) -> String {
+    id.and_then(|id| id.point_id_options)
+        .map(|opt| match opt {
+            PointIdOptions::Num(n) => n.to_string(),
+            PointIdOptions::Uuid(s) => s,
+        })
+        .unwrap_or_default()
+}
+
+/// VectorPoint of a point read back from Qdrant (vectors empty when not requested).
+fn retrieved_point(p: RetrievedPoint) -> VectorPoint {
+    let (vector, vectors) = match p.vectors.and_then(|v| v.vectors_options) {
+        Some(VectorsOptions::Vector(v)) => (v.data, HashMap::new()),
+        Some(VectorsOptions::Vectors(named)) => (
+            Vec::new(),
+            named
+                .vectors
+                .into_iter()
+                .map(|(n, v)| (n, DenseVector { data: v.data }))
+                .collect(),
+        ),
+        None => (Vec::new(), HashMap::new()),
+    };
+    VectorPoint {
+        id: point_id_string(p.id),
+        vector,
+        payload: string_payload(p.payload),
+        vectors,
+    }
+}
+
+/// Page size for Qdrant scrolls.
+const SCROLL_PAGE: u32 = 1000;
+
+/// Body of a collection-update PATCH setting `quantization`.
+fn quantization_config(quantization: Quantization) -> serde_json::Value {
+    let config = match quantization {
+        Quantization::Disabled => serde_json::json!("Disabled"),
+        // Quantized vectors in RAM; originals stay where the collection keeps them, used for rescoring.
+        Quantization::Scalar => serde_json::json!({"scalar": {"type": "int8", "quantile": 0.99, "always_ram": true}}),
+        Quantization::Binary => serde_json::json!({"binary": {"always_ram": true}}),
+    };
+    serde_json::json!({ "quantization_config": config })
+}
+
+/// Bound on one native snapshot create, download or upload (well above the per-call L4 timeout).
+const SNAPSHOT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(600);
+
+/// REST base for a Qdrant gRPC uri: the default gRPC port 6334 maps to the REST port 6333.
+pub fn qdrant_rest_uri(grpc_uri: &str) -> String {
+    grpc_uri.trim_end_matches('/').replace(":6334", ":6333")
+}
+
+pub struct QdrantStore {
+    /// Connection pool; never empty.
+    clients: Vec<QdrantClient>,
+    next: AtomicUsize,
+    http: reqwest::Client,
+    /// REST base uri (quantization updates).
+    rest_uri: String,
+    api_key: Option<String>,
+}
+
+impl QdrantStore {
+    pub fn new(clients: Vec<QdrantClient>) -> Self {
+        assert!(!clients.is_empty(), "QdrantStore needs at least one client");
+        Self {
+            clients,
+            next: AtomicUsize::new(0),
+            http: reqwest::Client::new(),
+            rest_uri: "http://localhost:6333".into(),
+            api_key: None,
+        }
+    }
+
+    pub fn with_rest(mut self, rest_uri: String, api_key: Option<String>, timeout: std::time::Duration) -> Self {
+        self.rest_uri = rest_uri.trim_end_matches('/').to_string();
+        self.api_key = api_key;
+        self.http = reqwest::Client::builder().timeout(timeout).build().unwrap_or_default();
+        self
+    }
+
+    /// Next pooled client (round-robin).
+    fn client(&self) -> &QdrantClient {
+        &self.clients[self.next.fetch_add(1, AtomicOrdering::Relaxed) % self.clients.len()]
+    }
+
+    /// REST request to `path` (under the REST base) with the API key.
+    fn rest(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let req = self.http.request(method, format!("{}{}", self.rest_uri, path));
+        match &self.api_key {
+            Some(key) => req.header("api-key", key),
+            None => req,
+        }
+    }
+
+    /// `resp` if it succeeded, else an error naming `what` with the status and body.
+    async fn rest_ok(resp: reqwest::Response, what: &str) -> Result<reqwest::Response, String> {
+        if resp.status().is_success() {
+            return Ok(resp);
+        }
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        Err(format!("{} returned {}: {}", what, status, body))
+    }
+
+    /// Stream a REST download to `dest`.
+    async fn download(&self, path: &str, dest: &Path) -> Result<(), String> {
+        let resp = self
+            .rest(reqwest::Method::GET, path)
+            .timeout(SNAPSHOT_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let mut resp = Self::rest_ok(resp, "snapshot download").await?;
+        let mut file = std::fs::File::create(dest).map_err(|e| format!("create {}: {}", dest.display(), e))?;
+        while let Some(chunk) = resp.chunk().await.map_err(|e| e.to_string())? {
+            file.write_all(&chunk).map_err(|e| format!("write {}: {}", dest.display(), e))?;
+        }
+        file.sync_all().map_err(|e| format!("sync {}: {}", dest.display(), e))
+    }
+}
+
+impl VectorStore for QdrantStore {
+    fn name(&self) -> &'static str {
+        "qdrant"
+    }
+
+    fn describe_collection<'a>(&'a self, collection: &'a str) -> StoreFuture<'a, Option<Shape>> {
+        Box::pin(async move {
+            if !self.client().has_collection(collection).await.map_err(|e| e.to_string())? {
+                return Ok(None);
+            }
+            let info = self.client().collection_info(collection).await.map_err(|e| e.to_string())?;
+            let params = info
+                .result
+                .and_then(|r| r.config)
+                .and_then(|c| c.params)
+                .and_then(|p| p.vectors_config)
+                .and_then(|v| v.config);
+            let distance = |p: &VectorParams| match QdrantDistance::from_i32(p.distance) {
+                Some(QdrantDistance::Dot) => Distance::Dot,
+                Some(QdrantDistance::Euclid) => Distance::Euclid,
+                _ => Distance::Cosine,
+            };
+            match params {
+                Some(vectors_config::Config::Params(p)) => Ok(Some(Shape::single(p.size as usize, distance(&p)))),
+                // Spaces share the KB's distance; the first one stands for all.
+                Some(vectors_config::Config::ParamsMap(m)) if !m.map.is_empty() => {
+                    let vectors: BTreeMap<String, usize> = m.map.iter().map(|(n, p)| (n.clone(), p.size as usize)).collect();
+                    let first = &m.map[vectors.keys().next().expect("non-empty")];
+                    Ok(Some(Shape {
+                        dim: 0,
+                        vectors,
+                        distance: distance(first),
+                    }))
+                }
+                _ => Err(format!("{}: no vector config", collection)),
+            }
+        })
+    }
+
+    fn point_count<'a>(&'a self, collection: &'a str) -> StoreFuture<'a, Option<u64>> {
+        Box::pin(async move {
+            if !self.client().has_collection(collection).await.map_err(|e| e.to_string())? {
+                return Ok(None);
+            }
+            let info = self.client().collection_info(collection).await.map_err(|e| e.to_string())?;
+            Ok(Some(info.result.map_or(0, |r| r.points_count)))
+        })
+    }
+
+    fn create_collection<'a>(&'a self, spec: &'a KbSpec) -> StoreFuture<'a, ()> {
+        let distance = match spec.distance {
+            Distance::Cosine => QdrantDistance::Cosine,
+            Distance::Dot => QdrantDistance::Dot,
+            Distance::Euclid => QdrantDistance::Euclid,
+        };
+        let config = if spec.vectors.is_empty() {
+            vectors_config::Config::Params(VectorParams {
+                size: spec.dim as u64,
+                distance: distance.into(),
+            })
+        } else {
+            vectors_config::Config::ParamsMap(VectorParamsMap {
+                map: spec
+                    .vectors
+                    .iter()
+                    .map(|(name, &dim)| {
+                        let params = VectorParams {
+                            size: dim as u64,
+                            distance: distance.into(),
+                        };
+                        (name.clone(), params)
+                    })
+                    .collect(),
+            })
+        };
+        Box::pin(async move {
+            self.client()
+                .create_collection(&CreateCollection {
+                    collection_name: spec.name.clone(),
+                    vectors_config: Some(VectorsConfig { config: Some(config) }),
+                    // On-disk: payloads on disk and segments memory-mapped past the threshold (KB).
+                    on_disk_payload: spec.on_disk.then_some(true),
+                    optimizers_config: spec.on_disk.then(|| OptimizersConfigDiff {
+                        memmap_threshold: Some(20_000),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn set_quantization<'a>(&'a self, collection: &'a str, quantization: Quantization) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            let resp = self
+                .rest(reqwest::Method::PATCH, &format!("/collections/{}", collection))
+                .json(&quantization_config(quantization))
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            Self::rest_ok(resp, &format!("{}: quantization update", collection)).await.map(|_| ())
+        })
+    }
+
+    fn upsert<'a>(&'a self, collection: &'a str, points: Vec<VectorPoint>) -> StoreFuture<'a, usize> {
+        Box::pin(async move {
+            let n = points.len();
+            let points: Vec<PointStruct> = points
+                .into_iter()
+                .map(|p| {
+                    let vectors: Vectors = if p.vectors.is_empty() {
+                        p.vector.into()
+                    } else {
+                        Vectors {
+                            vectors_options: Some(VectorsOptions::Vectors(NamedVectors {
+                                vectors: p.vectors.into_iter().map(|(n, v)| (n, Vector { data: v.data })).collect(),
+                            })),
+                        }
+                    };
+                    PointStruct::new(PointId::from(p.id), vectors, qdrant_payload(p.payload))
+                })
+                .collect();
+            self.client()
+                .upsert_points_blocking(collection, points)
+                .await
+                .map(|_| n)
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn search<'a>(
+        &'a self,
+        collection: &'a str,
+        vector_name: &'a str,
+        vector: Vec<f32>,
+        limit: usize,
+        filter: Option<SearchFilter>,
+    ) -> StoreFuture<'a, Vec<ScoredPoint>> {
+        Box::pin(async move {
+            let request = SearchPoints {
+                collection_name: collection.to_string(),
+                vector,
+                filter: filter.as_ref().and_then(qdrant_filter),
+                limit: limit as u64,
+                with_payload: Some(true.into()),
+                params: None,
+                score_threshold: None,
+                offset: None,
+                vector_name: (!vector_name.is_empty()).then(|| vector_name.to_string()),
+                with_vectors: None,
+            };
+            let response = self.client().search_points(&request).await.map_err(|e| e.to_string())?;
+            Ok(response
+                .result
+                .into_iter()
+                .map(|p| ScoredPoint {
+                    id: point_id_string(p.id),
+                    score: p.score,
+                    payload: p
+                        .payload
+                        .into_iter()
+                        .filter_map(|(k, v)| match v.kind {
+                            Some(Kind::StringValue(s)) => Some((k, s)),
+                            Some(Kind::IntegerValue(n)) => Some((k, n.to_string())),
+                            _ => None,
+                        })
+                        .collect(),
+                })
+                .collect())
+        })
+    }
+
+    fn delete<'a>(&'a self, collection: &'a str, ids: Vec<String>) -> StoreFuture<'a, usize> {
+        Box::pin(async move {
+            let n = ids.len();
+            let ids: Vec<PointId> = ids.into_iter().map(PointId::from).collect();
+            self.client()
+                .delete_points_blocking(collection, &ids.into())
+                .await
+                .map(|_| n)
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn set_payload<'a>(
+        &'a self,
+        collection: &'a str,
+        ids: Vec<String>,
+        fields: HashMap<String, String>,
+    ) -> StoreFuture<'a, usize> {
+        Box::pin(async move {
+            let n = ids.len();
+            let ids: Vec<PointId> = ids.into_iter().map(PointId::from).collect();
+            self.client()
+                .set_payload_blocking(collection, ids, qdrant_payload(fields))
+                .await
+                .map(|_| n)
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn scan<'a>(&'a self, collection: &'a str, fields: &'a [&'a str]) -> StoreFuture<'a, Vec<(String, HashMap<String, String>)>> {
+        Box::pin(async move {
+            let mut out = Vec::new();
+            let mut offset = None;
+            loop {
+                let request = ScrollPoints {
+                    collection_name: collection.to_string(),
+                    filter: None,
+                    offset,
+                    limit: Some(SCROLL_PAGE),
+                    with_payload: Some(WithPayloadSelector {
+                        selector_options: Some(with_payload_selector::SelectorOptions::Include(
+                            PayloadIncludeSelector {
+                                fields: fields.iter().map(|f| f.to_string()).collect(),
+                            },
+                        )),
+                    }),
+                    with_vectors: None,
+                };
+                let page = self.client().scroll(&request).await.map_err(|e| e.to_string())?;
+                out.extend(page.result.into_iter().map(|p| (point_id_string(p.id), string_payload(p.payload))));
+                match page.next_page_offset {
+                    Some(next) => offset = Some(next),
+                    None => return Ok(out),
+                }
+            }
+        })
+    }
+
+    fn get<'a>(&'a self, collection: &'a str, ids: Vec<String>) -> StoreFuture<'a, Vec<VectorPoint>> {
+        Box::pin(async move {
+            let ids: Vec<PointId> = ids.into_iter().map(PointId::from).collect();
+            let response = self
+                .client()
+                .get_points(collection, &ids, Some(true), Some(true))
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(response.result.into_iter().map(retrieved_point).collect())
+        })
+    }
+
+    fn scroll<'a>(
+        &'a self,
+        collection: &'a str,
+        offset: Option<String>,
+        limit: usize,
+        filter: Option<SearchFilter>,
+        with_vectors: bool,
+    ) -> StoreFuture<'a, ScrollPage> {
+        Box::pin(async move {
+            let request = ScrollPoints {
+                collection_name: collection.to_string(),
+                filter: filter.as_ref().and_then(qdrant_filter),
+                offset: offset.map(point_id),
+                limit: Some(limit as u32),
+                with_payload: Some(WithPayloadSelector {
+                    selector_options: Some(with_payload_selector::SelectorOptions::Enable(true)),
+                }),
+                with_vectors: Some(WithVectorsSelector {
+                    selector_options: Some(with_vectors_selector::SelectorOptions::Enable(with_vectors)),
+                }),
+            };
+            let page = self.client().scroll(&request).await.map_err(|e| e.to_string())?;
+            Ok(ScrollPage {
+                points: page.result.into_iter().map(retrieved_point).collect(),
+                next_offset: page.next_page_offset.map(|id| point_id_string(Some(id))),
+            })
+        })
+    }
+
+    fn health_check(&self) -> StoreFuture<'_, ()> {
+        Box::pin(async move { self.client().health_check().await.map(|_| ()).map_err(|e| e.to_string()) })
+    }
+
+    /// Create a server-side snapshot, download it and delete the server copy.
+    fn snapshot_to<'a>(&'a self, collection: &'a str, path: &'a Path) -> StoreFuture<'a, bool> {
+        Box::pin(async move {
+            let base = format!("/collections/{}/snapshots", collection);
+            let resp = self
+                .rest(reqwest::Method::POST, &base)
+                .timeout(SNAPSHOT_TIMEOUT)
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            let created: serde_json::Value = Self::rest_ok(resp, "snapshot create")
+                .await?
+                .json()
+                .await
+                .map_err(|e| e.to_string())?;
+            let name = created["result"]["name"]
+                .as_str()
+                .ok_or("snapshot create: no snapshot name in the response")?;
+            let server_copy = format!("{}/{}", base, name);
+            let downloaded = self.download(&server_copy, path).await;
+            if let Err(e) = self.rest(reqwest::Method::DELETE, &server_copy).send().await {
+                eprintln!("[QdrantStore] snapshot {} left on the server: {}", name, e);
+            }
+            downloaded.map(|_| true)
+        })
+    }
+
+    /// Upload the snapshot as multipart form data; the snapshot wins over any existing collection data.
+    fn restore_from<'a>(&'a self, collection: &'a str, path: &'a Path) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            let data = tokio::fs::read(path)
+                .await
+                .map_err(|e| format!("read {}: {}", path.display(), e))?;
+            let boundary = format!("pagi-{}", uuid::Uuid::new_v4());
+            let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            let mut body = format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"snapshot\"; filename=\"{}\"\r\n\
+                 Content-Type: application/octet-stream\r\n\r\n",
+                boundary, file_name
+            )
+            .into_bytes();
+            body.extend_from_slice(&data);
+            body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+            let resp = self
+                .rest(
+                    reqwest::Method::POST,
+                    &format!("/collections/{}/snapshots/upload?priority=snapshot", collection),
+                )
+                .timeout(SNAPSHOT_TIMEOUT)
+                .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+                .body(body)
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            Self::rest_ok(resp, "snapshot upload").await.map(|_| ())
+        })
+    }
+
+    /// An empty optimizers_config update makes Qdrant re-run its optimizers (vacuum, merge) on the collection.
+    fn optimize<'a>(&'a self, collection: &'a str) -> StoreFuture<'a, Option<u64>> {
+        Box::pin(async move {
+            let resp = self
+                .rest(reqwest::Method::PATCH, &format!("/collections/{}", collection))
+                .json(&serde_json::json!({ "optimizers_config": {} }))
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            Self::rest_ok(resp, &format!("{}: optimize", collection)).await.map(|_| None)
+        })
+    }
+}
+
+/// In-process backend: one HNSW graph per collection and vector space.
+#[derive(Default)]
+pub struct MemoryStore {
+    collections: RwLock<HashMap<String, Collection>>,
+    /// The local index's log (PAGI_LOCAL_INDEX_DIR): collections are replayed from it when created and every
+    /// change is appended to it under the collections lock, so the log's order is the store's.
+    log: Option<PointLog>,
+}
+
+/// Graphs keyed by vector space ("" for the unnamed vector); every space holds every point with the same
+/// payload, so the first space answers counts, scans and payload reads.
+struct Collection {
+    spaces: BTreeMap<String, Hnsw>,
+}
+
+impl Collection {
+    fn new(spec: &KbSpec) -> Self {
+        let spaces = if spec.vectors.is_empty() {
+            BTreeMap::from([(String::new(), Hnsw::new(spec.dim, spec.distance))])
+        } else {
+            spec.vectors
+                .iter()
+                .map(|(name, &dim)| (name.clone(), Hnsw::new(dim, spec.distance)))
+                .collect()
+        };
+        Self { spaces }
+    }
+
+    fn primary(&self) -> &Hnsw {
+        self.spaces.values().next().expect("collection has a vector space")
+    }
+
+    /// The point behind primary node `n`, with its vector in every space when `with_vectors`.
+    fn point(&self, n: usize, with_vectors: bool) -> VectorPoint {
+        let node = &self.primary().nodes[n];
+        let mut point = VectorPoint {
+            id: node.id.clone(),
+            payload: node.payload.clone(),
+            ..Default::default()
+        };
+        if !with_vectors {
+            return point;
+        }
+        if self.spaces.contains_key("") {
+            point.vector = node.vector.clone();
+            return point;
+        }
+        for (name, hnsw) in &self.spaces {
+            if let Some(&m) = hnsw.live.get(&node.id) {
+                let data = hnsw.nodes[m].vector.clone();
+                point.vectors.insert(name.clone(), DenseVector { data });
+            }
+        }
+        point
+    }
+
+    fn shape(&self) -> Shape {
+        let primary = self.primary();
+        if self.spaces.contains_key("") {
+            return Shape::single(primary.dim, primary.metric);
+        }
+        Shape {
+            dim: 0,
+            vectors: self.spaces.iter().map(|(name, hnsw)| (name.clone(), hnsw.dim)).collect(),
+            distance: primary.metric,
+        }
+    }
+
+    /// Whether `insert` would take `p`, so a batch can be checked before any of it is applied.
+    fn check(&self, p: &VectorPoint) -> Result<(), String> {
+        if let Some(hnsw) = self.spaces.get("") {
+            if p.vector.len() != hnsw.dim {
+                return Err(format!("point {} vector dim {} != collection dim {}", p.id, p.vector.len(), hnsw.dim));
+            }
+            return Ok(());
+        }
+        for (name, hnsw) in &self.spaces {
+            match p.vectors.get(name) {
+                None => return Err(format!("point {} has no {:?} vector", p.id, name)),
+                Some(v) if v.data.len() != hnsw.dim => {
+                    return Err(format!("point {} vector {:?} dim {} != {}", p.id, name, v.data.len(), hnsw.dim))
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, p: VectorPoint) -> Result<(), String> {
+        if let Some(hnsw) = self.spaces.get_mut("") {
+            return hnsw.insert(p.id, p.vector, p.payload);
+        }
+        // Check every space first so a bad point is not left in some of them.
+        self.check(&p)?;
+        let mut vectors = p.vectors;
+        for (name, hnsw) in self.spaces.iter_mut() {
+            let vector = vectors.remove(name).map(|v| v.data).unwrap_or_default();
+            hnsw.insert(p.id.clone(), vector, p.payload.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Removes `ids` from every space; returns how many were stored.
+    fn delete(&mut self, ids: &[String]) -> usize {
+        let mut removed = 0;
+        for (i, hnsw) in self.spaces.values_mut().enumerate() {
+            let n = ids.iter().filter(|id| hnsw.remove(id)).count();
+            if i == 0 {
+                removed = n;
+            }
+        }
+        removed
+    }
+
+    /// Merges `fields` into the payloads of `ids`; returns how many were stored.
+    fn set_payload(&mut self, ids: &[String], fields: &HashMap<String, String>) -> usize {
+        let mut found = 0;
+        for (i, hnsw) in self.spaces.values_mut().enumerate() {
+            for id in ids {
+                if let Some(&n) = hnsw.live.get(id) {
+                    hnsw.nodes[n].payload.extend(fields.clone());
+                    if i == 0 {
+                        found += 1;
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    /// Replay one logged change.
+    fn apply(&mut self, op: LogOp) -> Result<(), String> {
+        match op {
+            LogOp::Upsert { points } => points.into_iter().try_for_each(|p| self.insert(p.into_proto())),
+            LogOp::Delete { ids } => {
+                self.delete(&ids);
+                Ok(())
+            }
+            LogOp::SetPayload { ids, fields } => {
+                self.set_payload(&ids, &fields);
+                Ok(())
+            }
+        }
+    }
+
+    fn live_points(&self) -> Vec<VectorPoint> {
+        self.primary().live.values().map(|&n| self.point(n, true)).collect()
+    }
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store whose collections are replayed from and logged to `log` (the local index).
+    pub fn persistent(log: PointLog) -> Self {
+        Self {
+            log: Some(log),
+            ..Self::default()
+        }
+    }
+
+    fn with_collection<T>(&self, collection: &str, f: impl FnOnce(&mut Collection) -> Result<T, String>) -> Result<T, String> {
+        let mut collections = self.collections.write().unwrap_or_else(|e| e.into_inner());
+        let c = collections
+            .get_mut(collection)
+            .ok_or_else(|| format!("collection {} not found", collection))?;
+        f(c)
+    }
+
+    /// The log line for a change, built only for a persistent store.
+    fn logged(&self, op: impl FnOnce() -> LogOp) -> Option<LogOp> {
+        self.log.as_ref().map(|_| op())
+    }
+
+    /// with_collection for changes: once `f` succeeds, `op` is logged, and the log rewritten when it has outgrown
+    /// its compaction threshold.
+    fn change<T>(
+        &self,
+        collection: &str,
+        op: Option<LogOp>,
+        f: impl FnOnce(&mut Collection) -> Result<T, String>,
+    ) -> Result<T, String> {
+        self.with_collection(collection, |c| {
+            let out = f(c)?;
+            if let (Some(log), Some(op)) = (&self.log, op) {
+                if log.append(collection, &op)? {
+                    log.rewrite(collection, &c.live_points())?;
+                }
+            }
+            Ok(out)
+        })
+    }
+}
+
+impl VectorStore for MemoryStore {
+    fn name(&self) -> &'static str {
+        if self.log.is_some() {
+            "local"
+        } else {
+            "memory"
+        }
+    }
+
+    fn describe_collection<'a>(&'a self, collection: &'a str) -> StoreFuture<'a, Option<Shape>> {
+        let shape = self
+            .collections
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(collection)
+            .map(Collection::shape);
+        Box::pin(async move { Ok(shape) })
+    }
+
+    fn point_count<'a>(&'a self, collection: &'a str) -> StoreFuture<'a, Option<u64>> {
+        let count = self
+            .collections
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(collection)
+            .map(|c| c.primary().live.len() as u64);
+        Box::pin(async move { Ok(count) })
+    }
+
+    /// In-process (`on_disk` is ignored); a persistent store replays the collection's log into it.
+    fn create_collection<'a>(&'a self, spec: &'a KbSpec) -> StoreFuture<'a, ()> {
+        let mut collections = self.collections.write().unwrap_or_else(|e| e.into_inner());
+        let result = match (collections.contains_key(&spec.name), &self.log) {
+            (true, _) => Ok(()),
+            (false, None) => {
+                collections.insert(spec.name.clone(), Collection::new(spec));
+                Ok(())
+            }
+            (false, Some(log)) => {
+                let mut c = Collection::new(spec);
+                log.replay(&spec.name, |op| c.apply(op)).map(|replayed| {
+                    if replayed > 0 {
+                        let points = c.primary().live.len();
+                        let dir = log.dir().display();
+                        eprintln!("[MemoryStore] {}: {} point(s) restored from {}", spec.name, points, dir);
+                    }
+                    collections.insert(spec.name.clone(), c);
+                })
+            }
+        };
+        Box::pin(async move { result })
+    }
+
+    /// Vectors are kept as f32: quantization is a no-op.
+    fn set_quantization<'a>(&'a self, _collection: &'a str, _quantization: Quantization) -> StoreFuture<'a, ()> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn upsert<'a>(&'a self, collection: &'a str, points: Vec<VectorPoint>) -> StoreFuture<'a, usize> {
+        let op = self.logged(|| LogOp::upsert(&points));
+        let result = self.change(collection, op, |c| {
+            points.iter().try_for_each(|p| c.check(p))?;
+            let n = points.len();
+            for p in points {
+                c.insert(p)?;
+            }
+            Ok(n)
+        });
+        Box::pin(async move { result })
+    }
+
+    fn search<'a>(
+        &'a self,
+        collection: &'a str,
+        vector_name: &'a str,
+        vector: Vec<f32>,
+        limit: usize,
+        filter: Option<SearchFilter>,
+    ) -> StoreFuture<'a, Vec<ScoredPoint>> {
+        let result = self
+            .collections
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(collection)
+            .ok_or_else(|| format!("collection {} not found", collection))
+            .and_then(|c| {
+                c.spaces
+                    .get(vector_name)
+                    .ok_or_else(|| format!("collection {} has no vector space {:?}", collection, vector_name))
+            })
+            .and_then(|hnsw| hnsw.search(&vector, limit, filter.as_ref().filter(|f| has_conditions(f))));
+        Box::pin(async move { result })
+    }
+
+    fn delete<'a>(&'a self, collection: &'a str, ids: Vec<String>) -> StoreFuture<'a, usize> {
+        let op = self.logged(|| LogOp::Delete { ids: ids.clone() });
+        let result = self.change(collection, op, |c| Ok(c.delete(&ids)));
+        Box::pin(async move { result })
+    }
+
+    fn set_payload<'a>(
+        &'a self,
+        collection: &'a str,
+        ids: Vec<String>,
+        fields: HashMap<String, String>,
+    ) -> StoreFuture<'a, usize> {
+        let op = self.logged(|| LogOp::SetPayload {
+            ids: ids.clone(),
+            fields: fields.clone(),
+        });
+        let result = self.change(collection, op, |c| Ok(c.set_payload(&ids, &fields)));
+        Box::pin(async move { result })
+    }
+
+    fn scan<'a>(&'a self, collection: &'a str, fields: &'a [&'a str]) -> StoreFuture<'a, Vec<(String, HashMap<String, String>)>> {
+        let result = self.with_collection(collection, |c| {
+            let hnsw = c.primary();
+            Ok(hnsw
+                .live
+                .iter()
+                .map(|(id, &n)| {
+                    let payload = &hnsw.nodes[n].payload;
+                    let picked = fields
+                        .iter()
+                        .filter_map(|f| payload.get(*f).map(|v| (f.to_string(), v.clone())))
+                        .collect();
+                    (id.clone(), picked)
+                })
+                .collect())
+        });
+        Box::pin(async move { result })
+    }
+
+    fn get<'a>(&'a self, collection: &'a str, ids: Vec<String>) -> StoreFuture<'a, Vec<VectorPoint>> {
+        let result = self.with_collection(collection, |c| {
+            Ok(ids
+                .iter()
+                .filter_map(|id| c.primary().live.get(id))
+                .map(|&n| c.point(n, true))
+                .collect())
+        });
+        Box::pin(async move { result })
+    }
+
+    /// Pages in id order; the offset is the first id of the page.
+    fn scroll<'a>(
+        &'a self,
+        collection: &'a str,
+        offset: Option<String>,
+        limit: usize,
+        filter: Option<SearchFilter>,
+        with_vectors: bool,
+    ) -> StoreFuture<'a, ScrollPage> {
+        let result = self.with_collection(collection, |c| {
+            let primary = c.primary();
+            let filter = filter.as_ref().filter(|f| has_conditions(f));
+            let mut ids: Vec<(&String, usize)> = primary
+                .live
+                .iter()
+                .map(|(id, &n)| (id, n))
+                .filter(|&(id, n)| {
+                    offset.as_ref().is_none_or(|o| id >= o)
+                        && filter.is_none_or(|f| filter_matches(f, &primary.nodes[n].payload))
+                })
+                .collect();
+            ids.sort_unstable();
+            Ok(ScrollPage {
+                points: ids.iter().take(limit).map(|&(_, n)| c.point(n, with_vectors)).collect(),
+                next_offset: ids.get(limit).map(|(id, _)| id.to_string()),
+            })
+        });
+        Box::pin(async move { result })
+    }
+
+    fn health_check(&self) -> StoreFuture<'_, ()> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn snapshot_to<'a>(&'a self, _collection: &'a str, _path: &'a Path) -> StoreFuture<'a, bool> {
+        Box::pin(async { Ok(false) })
+    }
+
+    fn restore_from<'a>(&'a self, _collection: &'a str, _path: &'a Path) -> StoreFuture<'a, ()> {
+        Box::pin(async { Err("the memory backend has no native snapshots".to_string()) })
+    }
+
+    /// Rebuild every vector space from its live points (and a persistent store's log from them too).
+    fn optimize<'a>(&'a self, collection: &'a str) -> StoreFuture<'a, Option<u64>> {
+        let result = self.with_collection(collection, |c| {
+            let mut purged = 0;
+            for (i, hnsw) in c.spaces.values_mut().enumerate() {
+                let n = hnsw.compact()?;
+                if i == 0 {
+                    purged = n;
+                }
+            }
+            if let Some(log) = &self.log {
+                log.rewrite(collection, &c.live_points())?;
+            }
+            Ok(Some(purged as u64))
+        });
+        Box::pin(async move { result })
+    }
+}
+
+/// Distance under the collection metric (smaller is nearer) with a total order, for the HNSW heaps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Dist(f32);
+
+impl Eq for Dist {}
+
+impl PartialOrd for Dist {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Dist {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+struct Node {
+    id: String,
+    /// Unit-normalized under cosine, so similarity is a dot product.
+    vector: Vec<f32>,
+    payload: HashMap<String, String>,
+    /// Neighbor lists, one per layer 0..=level.
+    links: Vec<Vec<usize>>,
+    /// Replaced or deleted points stay in the graph for navigation but are never returned.
+    deleted: bool,
+}
+
+/// Hierarchical navigable small-world graph (Malkov & Yashunin).
+struct Hnsw {
+    dim: usize,
+    metric: Distance,
+    /// Max neighbors per node on upper layers (2*m on layer 0).
+    m: usize,
+    ef_construction: usize,
+    ef_search: usize,
+    nodes: Vec<Node>,
+    live: HashMap<String, usize>,
+    entry: Option<usize>,
+    max_level: usize,
+    rng: u64,
+}
+
+impl Hnsw {
+    fn new(dim: usize, metric: Distance) -> Self {
+        Self {
+            dim,
+            metric,
+            m: 16,
+            ef_construction: 100,
+            ef_search: 64,
+            nodes: Vec::new(),
+            live: HashMap::new(),
+            entry: None,
+            max_level: 0,
+            rng: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    fn normalize(&self, vector: &[f32]) -> Result<Vec<f32>, String> {
+        if vector.len() != self.dim {
+            return Err(format!("vector dim {} != collection dim {}", vector.len(), self.dim));
+        }
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        Ok(if self.metric == Distance::Cosine && norm > 0.0 {
+            vector.iter().map(|x| x / norm).collect()
+        } else {
+            vector.to_vec()
+        })
+    }
+
+    fn distance(&self, query: &[f32], node: usize) -> Dist {
+        let stored = &self.nodes[node].vector;
+        match self.metric {
+            Distance::Cosine => Dist(1.0 - query.iter().zip(stored).map(|(a, b)| a * b).sum::<f32>()),
+            Distance::Dot => Dist(-query.iter().zip(stored).map(|(a, b)| a * b).sum::<f32>()),
+            Distance::Euclid => Dist(query.iter().zip(stored).map(|(a, b)| (a - b) * (a - b)).sum::<f32>().sqrt()),
+        }
+    }
+
+    /// Reported score, as Qdrant does: similarity for cosine/dot, distance for euclid.
+    fn score(&self, d: Dist) -> f32 {
+        match self.metric {
+            Distance::Cosine => 1.0 - d.0,
+            Distance::Dot => -d.0,
+            Distance::Euclid => d.0,
+        }
+    }
+
+    /// Level drawn from the geometric distribution with mL = 1/ln(m) (xorshift; deterministic per store).
+    fn random_level(&mut self) -> usize {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        let uniform = ((self.rng >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+        (-uniform.ln() / (self.m as f64).ln()).floor() as usize
+    }
+
+    fn max_links(&self, layer: usize) -> usize {
+        if layer == 0 {
+            self.m * 2
+        } else {
+            self.m
+        }
+    }
+
+    /// Beam search on one layer; returns up to `ef` nodes closest to `query`, nearest first.
+    fn search_layer(&self, query: &[f32], entry: usize, ef: usize, layer: usize) -> Vec<(Dist, usize)> {
+        let mut visited = HashSet::from([entry]);
+        let d = self.distance(query, entry);
+        let mut candidates = BinaryHeap::from([Reverse((d, entry))]);
+        let mut best = BinaryHeap::from([(d, entry)]);
+        while let Some(Reverse((d, node))) = candidates.pop() {
+            if best.len() >= ef && best.peek().is_some_and(|(worst, _)| d > *worst) {
+                break;
+            }
+            for &next in self.nodes[node].links.get(layer).into_iter().flatten() {
+                if !visited.insert(next) {
+                    continue;
+                }
+                let dn = self.distance(query, next);
+                if best.len() < ef || best.peek().is_some_and(|(worst, _)| dn < *worst) {
+                    candidates.push(Reverse((dn, next)));
+                    best.push((dn, next));
+                    if best.len() > ef {
+                        best.pop();
+                    }
+                }
+            }
+        }
+        best.into_sorted_vec()
+    }
+
+    /// Greedy descent from layer `from` down to `layer` (inclusive); returns the nearest node found.
+    fn descend(&self, query: &[f32], mut entry: usize, from: usize, layer: usize) -> usize {
+        for l in (layer..=from).rev() {
+            if let Some(&(_, nearest)) = self.search_layer(query, entry, 1, l).first() {
+                entry = nearest;
+            }
+        }
+        entry
+    }
+
+    fn insert(&mut self, id: String, vector: Vec<f32>, payload: HashMap<String, String>) -> Result<(), String> {
+        let vector = self.normalize(&vector)?;
+        self.remove(&id);
+        let level = self.random_level();
+        let idx = self.nodes.len();
+        self.nodes.push(Node {
+            id: id.clone(),
+            vector,
+            payload,
+            links: vec![Vec::new(); level + 1],
+            deleted: false,
+        });
+        self.live.insert(id, idx);
+        let Some(mut entry) = self.entry else {
+            self.entry = Some(idx);
+            self.max_level = level;
+            return Ok(());
+        };
+        let query = self.nodes[idx].vector.clone();
+        if self.max_level > level {
+            entry = self.descend(&query, entry, self.max_level, level + 1);
+        }
+        for layer in (0..=level.min(self.max_level)).rev() {
+            let found = self.search_layer(&query, entry, self.ef_construction, layer);
+            let neighbors: Vec<usize> = found.iter().take(self.max_links(layer)).map(|&(_, n)| n).collect();
+            for &n in &neighbors {
+                self.nodes[n].links[layer].push(idx);
+                if self.nodes[n].links[layer].len() > self.max_links(layer) {
+                    self.prune(n, layer);
+                }
+            }
+            self.nodes[idx].links[layer] = neighbors;
+            entry = found[0].1;
+        }
+        if level > self.max_level {
+            self.entry = Some(idx);
+            self.max_level = level;
+        }
+        Ok(())
+    }
+
+    /// Keep only the closest max_links(layer) neighbors of `node`.
+    fn prune(&mut self, node: usize, layer: usize) {
+        let base = self.nodes[node].vector.clone();
+        let mut links = std::mem::take(&mut self.nodes[node].links[layer]);
+        links.sort_by_cached_key(|&n| self.distance(&base, n));
+        links.truncate(self.max_links(layer));
+        self.nodes[node].links[layer] = links;
+    }
+
+    /// Re-insert the live nodes (in insertion order) into an empty graph; returns the tombstones dropped.
+    fn compact(&mut self) -> Result<usize, String> {
+        let dropped = self.nodes.len() - self.live.len();
+        if dropped == 0 {
+            return Ok(0);
+        }
+        let mut order: Vec<usize> = self.live.drain().map(|(_, idx)| idx).collect();
+        order.sort_unstable();
+        let mut old: Vec<Option<Node>> = std::mem::take(&mut self.nodes).into_iter().map(Some).collect();
+        self.entry = None;
+        self.max_level = 0;
+        for idx in order {
+            if let Some(node) = old[idx].take() {
+                self.insert(node.id, node.vector, node.payload)?;
+            }
+        }
+        Ok(dropped)
+    }
+
+    fn remove(&mut self, id: &str) -> bool {
+        match self.live.remove(id) {
+            Some(idx) => {
+                self.nodes[idx].deleted = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Approximate k-NN; with a filter, an exact scan over matching live points (graph walks would
+    /// stall on selective filters).
+    fn search(&self, vector: &[f32], limit: usize, filter: Option<&SearchFilter>) -> Result<Vec<ScoredPoint>, String> {
+        let query = self.normalize(vector)?;
+        if let Some(filter) = filter {
+            let mut matching: Vec<(Dist, usize)> = self
+                .live
+                .values()
+                .filter(|&&n| filter_matches(filter, &self.nodes[n].payload))
+                .map(|&n| (self.distance(&query, n), n))
+                .collect();
+            matching.sort();
+            return Ok(matching.into_iter().take(limit).map(|(d, n)| self.scored(d, n)).collect());
+        }
+        let Some(entry) = self.entry else {
+            return Ok(Vec::new());
+        };
+        let entry = self.descend(&query, entry, self.max_level, 1);
+        // Widen the beam by the tombstone count so deleted nodes don't crowd out live results.
+        let tombstones = self.nodes.len() - self.live.len();
+        let ef = self.ef_search.max(limit) + tombstones.min(self.ef_search * 4);
+        Ok(self
+            .search_layer(&query, entry, ef, 0)
+            .into_iter()
+            .filter(|&(_, n)| !self.nodes[n].deleted)
+            .take(limit)
+            .map(|(d, n)| self.scored(d, n))
+            .collect())
+    }
+
+    fn scored(&self, d: Dist, node: usize) -> ScoredPoint {
+        ScoredPoint {
+            id: self.nodes[node].id.clone(),
+            score: self.score(d),
+            payload: self.nodes[node].payload.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(name: &str, dim: usize, distance: Distance) -> KbSpec {
+        KbSpec {
+            name: name.to_string(),
+            dim,
+            distance,
+            on_disk: false,
+            retention: Default::default(),
+            quantization: None,
+            vectors: BTreeMap::new(),
+            cold_after_secs: None,
+        }
+    }
+
+    fn point(id: &str, vector: Vec<f32>) -> VectorPoint {
+        VectorPoint {
+            id: id.to_string(),
+            vector,
+            payload: HashMap::from([("content".to_string(), format!("doc {}", id))]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn quantization_updates_target_the_rest_api() {
+        assert_eq!(qdrant_rest_uri("http://qdrant:6334/"), "http://qdrant:6333");
+        assert_eq!(qdrant_rest_uri("https://cloud.example:443"), "https://cloud.example:443");
+        assert_eq!(
+            quantization_config(Quantization::Scalar)["quantization_config"]["scalar"]["type"],
+            "int8"
+        );
+        assert_eq!(quantization_config(Quantization::Disabled)["quantization_config"], "Disabled");
+    }
+
+    /// kb_core (dim 8, cosine) holding 500 pseudo-random points p0..p499.
+    async fn random_kb() -> MemoryStore {
+        let store = MemoryStore::new();
+        store.create_collection(&spec("kb_core", 8, Distance::Cosine)).await.unwrap();
+        let mut seed = 7u64;
+        let mut rand_vec = || {
+            (0..8)
+                .map(|_| {
+                    seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                    ((seed >> 33) as f32 / u32::MAX as f32) - 0.25
+                })
+                .collect::<Vec<f32>>()
+        };
+        let points: Vec<VectorPoint> = (0..500).map(|i| point(&format!("p{}", i), rand_vec())).collect();
+        assert_eq!(store.upsert("kb_core", points).await.unwrap(), 500);
+        store
+    }
+
+    async fn vector_of(store: &MemoryStore, id: &str) -> Vec<f32> {
+        store.get("kb_core", vec![id.to_string()]).await.unwrap().remove(0).vector
+    }
+
+    #[tokio::test]
+    async fn memory_store_requires_a_created_collection() {
+        let store = MemoryStore::new();
+        assert!(store.search("kb_core", "", vec![1.0; 8], 3, None).await.is_err());
+        assert_eq!(store.describe_collection("kb_core").await.unwrap(), None);
+        store.create_collection(&spec("kb_core", 8, Distance::Cosine)).await.unwrap();
+        assert_eq!(store.describe_collection("kb_core").await.unwrap(), Some(Shape::single(8, Distance::Cosine)));
+        assert!(store.upsert("kb_core", vec![point("bad", vec![1.0; 3])]).await.is_err(), "wrong dimension");
+    }
+
+    #[tokio::test]
+    async fn memory_store_search_matches_brute_force_nearest() {
+        let store = random_kb().await;
+        let target = vector_of(&store, "p123").await;
+        let hits = store.search("kb_core", "", target, 5, None).await.unwrap();
+        assert_eq!(hits.len(), 5);
+        assert_eq!(hits[0].id, "p123");
+        assert!((hits[0].score - 1.0).abs() < 1e-4);
+        assert_eq!(hits[0].payload["content"], "doc p123");
+        assert!(hits.windows(2).all(|w| w[0].score >= w[1].score));
+    }
+
+    #[tokio::test]
+    async fn memory_store_deletes_hide_points_from_search() {
+        let store = random_kb().await;
+        let target = vector_of(&store, "p123").await;
+        assert_eq!(store.delete("kb_core", vec!["p123".into(), "missing".into()]).await.unwrap(), 1);
+        let hits = store.search("kb_core", "", target, 5, None).await.unwrap();
+        assert!(hits.iter().all(|h| h.id != "p123"));
+    }
+
+    #[tokio::test]
+    async fn memory_store_upserts_replace_by_id() {
+        let store = random_kb().await;
+        let target = vector_of(&store, "p123").await;
+        store.upsert("kb_core", vec![point("p1", target.clone())]).await.unwrap();
+        let hits = store.search("kb_core", "", target, 2, None).await.unwrap();
+        let mut ids: Vec<&str> = hits.iter().map(|h| h.id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, ["p1", "p123"]);
+    }
+
+    #[tokio::test]
+    async fn memory_store_optimize_drops_stale_graph_nodes() {
+        let store = random_kb().await;
+        let target = vector_of(&store, "p123").await;
+        store.delete("kb_core", vec!["p123".into()]).await.unwrap();
+        store.upsert("kb_core", vec![point("p1", target.clone())]).await.unwrap();
+        assert_eq!(store.optimize("kb_core").await.unwrap(), Some(2), "the deleted and the replaced node");
+        assert_eq!(store.optimize("kb_core").await.unwrap(), Some(0));
+        assert_eq!(store.point_count("kb_core").await.unwrap(), Some(499));
+        assert_eq!(store.search("kb_core", "", target, 1, None).await.unwrap()[0].id, "p1");
+    }
+
+    #[tokio::test]
+    async fn memory_store_euclid_scores_are_raw_distances() {
+        let store = MemoryStore::new();
+        store.create_collection(&spec("kb_euclid", 2, Distance::Euclid)).await.unwrap();
+        assert_eq!(store.describe_collection("kb_euclid").await.unwrap(), Some(Shape::single(2, Distance::Euclid)));
+        store
+            .upsert("kb_euclid", vec![point("near", vec![1.0, 1.0]), point("far", vec![10.0, 10.0])])
+            .await
+            .unwrap();
+        let hits = store.search("kb_euclid", "", vec![2.0, 1.0], 2, None).await.unwrap();
+        assert_eq!((hits[0].id.as_str(), hits[0].score), ("near", 1.0));
+    }
+
+    #[tokio::test]
+    async fn persistent_memory_store_replays_its_log_on_create() {
+        let dir = std::env::temp_dir().join(format!("pagi_local_index_{}", uuid::Uuid::new_v4()));
+        let open = || MemoryStore::persistent(PointLog::open(&dir, 1 << 20).unwrap());
+        let kb = spec("kb_core", 2, Distance::Cosine);
+        {
+            let store = open();
+            assert_eq!(store.name(), "local");
+            store.create_collection(&kb).await.unwrap();
+            let points = vec![point("a", vec![1.0, 0.0]), point("b", vec![0.0, 1.0]), point("c", vec![1.0, 1.0])];
+            store.upsert("kb_core", points).await.unwrap();
+            assert!(store.upsert("kb_core", vec![point("ok", vec![1.0, 0.0]), point("bad", vec![1.0])]).await.is_err());
+            store.delete("kb_core", vec!["c".into()]).await.unwrap();
+            let fields = HashMap::from([("decay_score".to_string(), "0.5".to_string())]);
+            store.set_payload("kb_core", vec!["a".into()], fields).await.unwrap();
+        }
+
+        let store = open();
+        assert_eq!(store.describe_collection("kb_core").await.unwrap(), None, "restored on create");
+        store.create_collection(&kb).await.unwrap();
+        assert_eq!(store.point_count("kb_core").await.unwrap(), Some(2), "a rejected batch is not logged");
+        let a = store.get("kb_core", vec!["a".into()]).await.unwrap().remove(0);
+        assert_eq!((a.vector, a.payload["decay_score"].as_str()), (vec![1.0, 0.0], "0.5"));
+        assert_eq!(store.search("kb_core", "", vec![0.0, 1.0], 1, None).await.unwrap()[0].id, "b");
+
+        store.optimize("kb_core").await.unwrap();
+        let log_len = std::fs::metadata(dir.join("kb_core.log")).unwrap().len();
+        assert_eq!(std::fs::read_to_string(dir.join("kb_core.log")).unwrap().lines().count(), 1, "rewritten");
+        drop(store);
+        let resized = open();
+        assert!(resized.create_collection(&spec("kb_core", 3, Distance::Cosine)).await.is_err(), "shape changed");
+        assert_eq!(std::fs::metadata(dir.join("kb_core.log")).unwrap().len(), log_len);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn memory_store_keeps_named_vector_spaces() {
+        let store = MemoryStore::new();
+        let mut dual = spec("kb_dual", 2, Distance::Cosine);
+        dual.vectors = BTreeMap::from([("code".to_string(), 2), ("text".to_string(), 3)]);
+        store.create_collection(&dual).await.unwrap();
+        assert_eq!(store.describe_collection("kb_dual").await.unwrap().unwrap().describe(), "code:2+text:3/cosine");
+
+        let named = |id: &str, code: Vec<f32>, text: Vec<f32>| VectorPoint {
+            id: id.to_string(),
+            vectors: HashMap::from([
+                ("code".to_string(), DenseVector { data: code }),
+                ("text".to_string(), DenseVector { data: text }),
+            ]),
+            ..Default::default()
+        };
+        store
+            .upsert(
+                "kb_dual",
+                vec![
+                    named("a", vec![1.0, 0.0], vec![0.0, 0.0, 1.0]),
+                    named("b", vec![0.0, 1.0], vec![1.0, 0.0, 0.0]),
+                ],
+            )
+            .await
+            .unwrap();
+        let top = |space: &'static str, v: Vec<f32>| store.search("kb_dual", space, v, 1, None);
+        assert_eq!(top("code", vec![1.0, 0.1]).await.unwrap()[0].id, "a");
+        assert_eq!(top("text", vec![1.0, 0.1, 0.0]).await.unwrap()[0].id, "b", "each space ranks on its own vectors");
+        assert!(top("", vec![1.0, 0.0]).await.is_err(), "named collections need a space");
+
+        let missing = VectorPoint {
+            id: "c".into(),
+            vectors: HashMap::from([("code".to_string(), DenseVector { data: vec![1.0, 1.0] })]),
+            ..Default::default()
+        };
+        assert!(store.upsert("kb_dual", vec![missing]).await.is_err());
+        assert_eq!(store.point_count("kb_dual").await.unwrap(), Some(2), "rejected point left no trace");
+        let got = store.get("kb_dual", vec!["b".into()]).await.unwrap();
+        assert_eq!(got[0].vectors["text"].data, [1.0, 0.0, 0.0]);
+        assert_eq!(store.delete("kb_dual", vec!["a".into()]).await.unwrap(), 1);
+        assert_eq!(top("text", vec![0.0, 0.0, 1.0]).await.unwrap()[0].id, "b");
+    }
+
+    /// kb_core (dim 2) with a (rust_core, 100), bb (rust_core, 200) and ccc (python_skill, 300).
+    async fn tagged_kb() -> MemoryStore {
+        let store = MemoryStore::new();
+        store.create_collection(&spec("kb_core", 2, Distance::Cosine)).await.unwrap();
+        let tagged = |id: &str, component: &str, ts: &str| VectorPoint {
+            id: id.to_string(),
+            vector: vec![1.0, id.len() as f32],
+            payload: HashMap::from([
+                ("component".to_string(), component.to_string()),
+                ("timestamp".to_string(), ts.to_string()),
+            ]),
+            ..Default::default()
+        };
+        let points = vec![
+            tagged("a", "rust_core", "100"),
+            tagged("bb", "rust_core", "200"),
+            tagged("ccc", "python_skill", "300"),
+        ];
+        store.upsert("kb_core", points).await.unwrap();
+        store
+    }
+
+    fn eq(key: &str, value: &str) -> FilterCondition {
+        FilterCondition {
+            key: key.to_string(),
+            r#match: value.to_string(),
+            range: None,
+        }
+    }
+
+    fn since(ts: f64) -> FilterCondition {
+        FilterCondition {
+            key: "timestamp".to_string(),
+            range: Some(crate::proto::pagi_proto::FilterRange { gte: Some(ts), lte: None }),
+            ..Default::default()
+        }
+    }
+
+    async fn filtered_ids(store: &MemoryStore, filter: SearchFilter) -> Vec<String> {
+        let hits = store.search("kb_core", "", vec![1.0, 0.0], 10, Some(filter)).await.unwrap();
+        let mut ids: Vec<String> = hits.into_iter().map(|p| p.id).collect();
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn memory_store_must_conditions_all_apply() {
+        let store = tagged_kb().await;
+        let component = SearchFilter { must: vec![eq("component", "rust_core")], ..Default::default() };
+        assert_eq!(filtered_ids(&store, component).await, ["a", "bb"]);
+        let recent_core = SearchFilter { must: vec![eq("component", "rust_core"), since(150.0)], ..Default::default() };
+        assert_eq!(filtered_ids(&store, recent_core).await, ["bb"]);
+    }
+
+    #[tokio::test]
+    async fn memory_store_should_conditions_need_any_and_must_not_none() {
+        let store = tagged_kb().await;
+        let either = SearchFilter {
+            should: vec![eq("timestamp", "100"), eq("component", "python_skill")],
+            must_not: vec![eq("missing_key", "x")],
+            ..Default::default()
+        };
+        assert_eq!(filtered_ids(&store, either).await, ["a", "ccc"]);
+        assert_eq!(filtered_ids(&store, SearchFilter::default()).await.len(), 3, "empty filter searches everything");
+    }
+
+    #[tokio::test]
+    async fn memory_store_scrolls_page_in_id_order() {
+        let store = tagged_kb().await;
+        let page = store.scroll("kb_core", None, 2, None, false).await.unwrap();
+        let page_ids: Vec<&str> = page.points.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!((page_ids, page.next_offset.as_deref()), (vec!["a", "bb"], Some("ccc")));
+        assert!(page.points[0].vector.is_empty() && page.points[0].payload["timestamp"] == "100");
+        let last = store.scroll("kb_core", page.next_offset, 2, None, true).await.unwrap();
+        assert_eq!((last.points.len(), last.next_offset), (1, None));
+        assert_eq!(last.points[0].vector.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn memory_store_scrolls_take_filters() {
+        let store = tagged_kb().await;
+        let core = SearchFilter { must: vec![eq("component", "rust_core")], ..Default::default() };
+        assert_eq!(store.scroll("kb_core", None, 10, Some(core), false).await.unwrap().points.len(), 2);
+    }
+
+    #[test]
+    fn qdrant_filters_match_integers_and_keep_zero_padded_keywords() {
+        let q = qdrant_filter(&SearchFilter { must: vec![eq("timestamp", "100"), eq("file", "007")], ..Default::default() })
+            .unwrap();
+        let values: Vec<_> = q
+            .must
+            .iter()
+            .filter_map(|c| match &c.condition_one_of {
+                Some(qdrant_client::qdrant::condition::ConditionOneOf::Field(f)) => f.r#match.clone()?.match_value,
+                _ => None,
+            })
+            .collect();
+        assert_eq!(values, [MatchValue::Integer(100), MatchValue::Keyword("007".to_string())]);
+        assert!(qdrant_filter(&SearchFilter::default()).is_none());
+    }
+}
@@ -0,0 +1,330 @@
+// L4 write-ahead log. With PAGI_L4_WAL_DIR set, every UpsertVectors batch (after dedup and decay stamping) is
+// appended to `l4.wal` and fsynced before it is sent to the vector store; an ack line follows once the call
+// completes (stored, rejected or cancelled). Batches without an ack — the orchestrator died mid-upsert — are
+// re-upserted by MemoryManager::replay_wal at startup. The log is truncated whenever nothing is in flight and
+// rewritten down to the in-flight batches once it grows past PAGI_L4_WAL_COMPACT_MB.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::proto::pagi_proto::{DenseVector, VectorPoint};
+
+const LOG_NAME: &str = "l4.wal";
+
+/// A logged point (also the local index's, point_log.rs).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WalPoint {
+    id: String,
+    vector: Vec<f32>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    vectors: BTreeMap<String, Vec<f32>>,
+    payload: HashMap<String, String>,
+}
+
+impl WalPoint {
+    pub fn from_proto(p: &VectorPoint) -> Self {
+        Self {
+            id: p.id.clone(),
+            vector: p.vector.clone(),
+            vectors: p.vectors.iter().map(|(n, v)| (n.clone(), v.data.clone())).collect(),
+            payload: p.payload.clone(),
+        }
+    }
+
+    pub fn into_proto(self) -> VectorPoint {
+        VectorPoint {
+            id: self.id,
+            vector: self.vector,
+            payload: self.payload,
+            vectors: self.vectors.into_iter().map(|(n, data)| (n, DenseVector { data })).collect(),
+        }
+    }
+}
+
+/// One JSON line of the log.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Line {
+    Upsert { seq: u64, kb_name: String, points: Vec<WalPoint> },
+    Ack { seq: u64 },
+}
+
+/// An upsert batch that was logged but never acked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Batch {
+    pub seq: u64,
+    pub kb_name: String,
+    pub points: Vec<VectorPoint>,
+}
+
+struct State {
+    file: File,
+    /// Bytes in the log file.
+    len: u64,
+    next_seq: u64,
+    /// Unacked upsert lines (newline-terminated), kept verbatim for compaction and replay.
+    pending: BTreeMap<u64, String>,
+}
+
+pub struct Wal {
+    path: PathBuf,
+    compact_bytes: u64,
+    state: Mutex<State>,
+}
+
+/// Acks its batch when dropped, so a failed or cancelled upsert never lingers in the log.
+pub struct Logged<'a> {
+    wal: &'a Wal,
+    seq: u64,
+}
+
+impl Drop for Logged<'_> {
+    fn drop(&mut self) {
+        self.wal.ack(self.seq);
+    }
+}
+
+pub fn open_append(path: &Path) -> Result<File, String> {
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("open {}: {}", path.display(), e))
+}
+
+impl Wal {
+    /// Open (or create) the log in `dir`, keeping only batches that were never acked.
+    pub fn open(dir: &Path, compact_bytes: u64) -> Result<Self, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("create {}: {}", dir.display(), e))?;
+        let path = dir.join(LOG_NAME);
+        let raw = match std::fs::read_to_string(&path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("read {}: {}", path.display(), e)),
+        };
+        let mut pending = BTreeMap::new();
+        let mut next_seq = 1;
+        // A torn final line (crash mid-append) fails to parse and is dropped: its upsert never started.
+        for line in raw.lines() {
+            match serde_json::from_str::<Line>(line) {
+                Ok(Line::Upsert { seq, .. }) => {
+                    next_seq = next_seq.max(seq + 1);
+                    pending.insert(seq, format!("{}\n", line));
+                }
+                Ok(Line::Ack { seq }) => {
+                    pending.remove(&seq);
+                }
+                Err(_) => {}
+            }
+        }
+        let file = open_append(&path)?;
+        let wal = Self {
+            path,
+            compact_bytes: compact_bytes.max(1),
+            state: Mutex::new(State {
+                file,
+                len: raw.len() as u64,
+                next_seq,
+                pending,
+            }),
+        };
+        wal.compact(&mut wal.state.lock().unwrap_or_else(|e| e.into_inner()))?;
+        Ok(wal)
+    }
+
+    /// PAGI_L4_WAL_DIR (unset or empty disables the WAL) and PAGI_L4_WAL_COMPACT_MB (default 64).
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let Some(dir) = std::env::var("PAGI_L4_WAL_DIR")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+        else {
+            return Ok(None);
+        };
+        let mb = std::env::var("PAGI_L4_WAL_COMPACT_MB")
+            .ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .unwrap_or(64);
+        Self::open(Path::new(&dir), mb.saturating_mul(1024 * 1024)).map(Some)
+    }
+
+    /// Durably log an upsert batch; the returned guard acks it when dropped.
+    pub fn log(&self, kb_name: &str, points: &[VectorPoint]) -> Result<Logged<'_>, String> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let seq = state.next_seq;
+        let line = Line::Upsert {
+            seq,
+            kb_name: kb_name.to_string(),
+            points: points.iter().map(WalPoint::from_proto).collect(),
+        };
+        let mut line = serde_json::to_string(&line).map_err(|e| e.to_string())?;
+        line.push('\n');
+        state
+            .file
+            .write_all(line.as_bytes())
+            .and_then(|_| state.file.sync_data())
+            .map_err(|e| format!("write {}: {}", self.path.display(), e))?;
+        state.next_seq += 1;
+        state.len += line.len() as u64;
+        state.pending.insert(seq, line);
+        Ok(Logged { wal: self, seq })
+    }
+
+    /// Mark a batch done. Not fsynced: a lost ack only replays an upsert that already landed.
+    pub fn ack(&self, seq: u64) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.pending.remove(&seq).is_none() {
+            return;
+        }
+        if state.pending.is_empty() || state.len > self.compact_bytes {
+            if let Err(e) = self.compact(&mut state) {
+                eprintln!("[Wal] compact failed: {}", e);
+            }
+            return;
+        }
+        let line = format!("{}\n", serde_json::json!({ "op": "ack", "seq": seq }));
+        match state.file.write_all(line.as_bytes()) {
+            Ok(()) => state.len += line.len() as u64,
+            Err(e) => eprintln!("[Wal] ack {} not written: {}", seq, e),
+        }
+    }
+
+    /// Batches logged but never acked, oldest first.
+    pub fn pending(&self) -> Vec<Batch> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state
+            .pending
+            .values()
+            .filter_map(|line| match serde_json::from_str::<Line>(line) {
+                Ok(Line::Upsert { seq, kb_name, points }) => Some(Batch {
+                    seq,
+                    kb_name,
+                    points: points.into_iter().map(WalPoint::into_proto).collect(),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Compact now instead of at the next ack (CompactMemory); returns the log size before and after.
+    pub fn compact_now(&self) -> Result<(u64, u64), String> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let before = state.len;
+        self.compact(&mut state)?;
+        Ok((before, state.len))
+    }
+
+    /// Rewrite the log as just the pending batches (truncate when there are none).
+    fn compact(&self, state: &mut State) -> Result<(), String> {
+        if state.pending.is_empty() {
+            if state.len > 0 {
+                state
+                    .file
+                    .set_len(0)
+                    .and_then(|_| state.file.sync_data())
+                    .map_err(|e| format!("truncate {}: {}", self.path.display(), e))?;
+                state.len = 0;
+            }
+            return Ok(());
+        }
+        let body: String = state.pending.values().map(String::as_str).collect();
+        if body.len() as u64 == state.len {
+            return Ok(());
+        }
+        let tmp = self.path.with_extension("wal.tmp");
+        let mut f = File::create(&tmp).map_err(|e| format!("create {}: {}", tmp.display(), e))?;
+        f.write_all(body.as_bytes())
+            .and_then(|_| f.sync_all())
+            .map_err(|e| format!("write {}: {}", tmp.display(), e))?;
+        std::fs::rename(&tmp, &self.path).map_err(|e| format!("rename {}: {}", tmp.display(), e))?;
+        state.file = open_append(&self.path)?;
+        state.len = body.len() as u64;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(id: &str) -> VectorPoint {
+        VectorPoint {
+            id: id.to_string(),
+            vector: vec![0.5; 4],
+            payload: HashMap::from([("content".to_string(), format!("doc {}", id))]),
+            ..Default::default()
+        }
+    }
+
+    fn log_len(dir: &Path) -> u64 {
+        std::fs::metadata(dir.join(LOG_NAME)).map_or(0, |m| m.len())
+    }
+
+    /// A WAL dir left by a crash: batch 1 acked, batch 2 (kb_logs: b, c) never acked, then a torn append.
+    fn crashed() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pagi_wal_{}", uuid::Uuid::new_v4()));
+        {
+            let wal = Wal::open(&dir, 1 << 20).unwrap();
+            drop(wal.log("kb_core", &[point("a")]).unwrap());
+            std::mem::forget(wal.log("kb_logs", &[point("b"), point("c")]).unwrap());
+        }
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(dir.join(LOG_NAME))
+            .unwrap()
+            .write_all(b"{\"op\":\"upsert\",\"seq\":9,")
+            .unwrap();
+        dir
+    }
+
+    #[test]
+    fn only_unacked_batches_are_pending_after_reopen() {
+        let dir = crashed();
+        let pending = Wal::open(&dir, 1 << 20).unwrap().pending();
+        assert_eq!(pending.len(), 1);
+        assert_eq!((pending[0].seq, pending[0].kb_name.as_str()), (2, "kb_logs"));
+        assert_eq!(pending[0].points, vec![point("b"), point("c")]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn sequences_continue_past_replayed_batches() {
+        let dir = crashed();
+        let wal = Wal::open(&dir, 1 << 20).unwrap();
+        assert_eq!(wal.log("kb_core", &[point("d")]).unwrap().seq, 3);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn compaction_drops_acked_batches_but_keeps_in_flight_ones() {
+        let dir = crashed();
+        let wal = Wal::open(&dir, 1 << 20).unwrap();
+        let in_flight = wal.log("kb_core", &[point("d")]).unwrap();
+        wal.ack(2);
+        let (before, after) = wal.compact_now().unwrap();
+        assert!(after > 0 && after < before && after == log_len(&dir));
+        drop(in_flight);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn the_log_is_truncated_once_nothing_is_in_flight() {
+        let dir = crashed();
+        let wal = Wal::open(&dir, 1 << 20).unwrap();
+        let in_flight = wal.log("kb_core", &[point("d")]).unwrap();
+        wal.ack(2);
+        drop(in_flight);
+        assert_eq!(log_len(&dir), 0);
+        assert!(Wal::open(&dir, 1 << 20).unwrap().pending().is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
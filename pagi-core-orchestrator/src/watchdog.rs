@@ -4,39 +4,135 @@
 use std::collections::HashMap;
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::process::Command as StdCommand;
 use std::sync::Arc;
 
-use dashmap::DashMap;
-use git2::{IndexAddOption, Repository, Signature};
-use sha2::{Digest, Sha256};
-use tonic::Status;
+use git2::{Repository, Signature};
+use tonic::{Code, Status};
 use uuid::Uuid;
 
+use crate::action_audit::{self, ActionRecord, AuditTrail};
+use crate::allow_list::{self, AllowList, SkillSource};
+use crate::apply_queue::{ApplyQueue, ApplyState};
+use crate::components::{Component, ComponentRegistry};
+use crate::approval::{self, ApprovalPolicy, TimeoutFallback};
+use crate::builtin_skills::{self, BuiltinConfig};
+use crate::clock::{self, Clock};
+use crate::env_fingerprint::{self, EnvFingerprint};
+use crate::heal_governor::{self, Admission, HealGovernor};
+use crate::heal_metrics::{self, Stage};
+use crate::hitl::HitlHub;
+use crate::impact;
+use crate::local_model::{self, LocalModel, LocalModelConfig};
+use crate::inflight;
 use crate::memory_manager::MemoryManager;
+use crate::metrics;
+use crate::patch_catalog::{AppliedPatch, ApprovalOutcome, ApprovalRecord, PatchCatalog, PendingPatch};
+use crate::patch_diff;
+use crate::patch_format::{self, PatchMetadata};
+use crate::patch_history;
+use crate::policy_denial;
+use crate::provenance::{self, ProvenanceConfig};
+use crate::registry::{self, GitRegistry, Registry};
+use crate::resource_usage::{self, ResourceUsage};
+use crate::runner_protocol::{self, RunnerOutput};
+use crate::safety_governor::SafetyGovernor;
+use crate::skill_manifest::{self, Filesystem, Root, Roots, SkillManifest};
+use crate::smoke::{self, SmokeConfig};
+use crate::snapshot;
+use crate::store;
+use crate::test_runner;
+use crate::worker_pool::{PoolOutcome, WorkerPool};
 use crate::proto::pagi_proto::{
-    ActionRequest, ActionResponse, ApplyRequest, ApplyResponse, PatchRequest, PatchResponse,
-    SearchRequest,
+    ActionRequest, ActionResponse, ApplyRequest, ApplyResponse, ApplyStatusResponse, HealReport,
+    HealReportRequest, HitlPrompt, ListPendingPatchesRequest, ListPendingPatchesResponse, PatchDetail, PatchRequest,
+    PatchResponse, PendingPatchSummary, PolicyDenial, ReplayActionRequest, ReplayActionResponse,
+    RollbackPatchRequest, RollbackPatchResponse, SearchHit,
+    SearchPatchesRequest, SearchRequest, SnapshotKbRequest,
 };
 
-/// Pending patch stored after ProposePatch until ApplyPatch or expiry.
-struct PendingPatch {
-    proposed_code: String,
-    requires_hitl: bool,
-    component: String,
-}
-
 /// Watchdog: self-healing (RCA via L4), Git-Watcher for pagi-skills, patch propose/apply.
 pub struct Watchdog {
-    /// Evolution Registry path (pagi-skills/).
-    registry_path: PathBuf,
+    /// Evolution Registry (pagi-skills/): git commits or versioned object-store writes (PAGI_REGISTRY_BACKEND).
+    registry: Box<dyn Registry>,
     /// L4 for RCA search.
     memory: Arc<MemoryManager>,
-    /// patch_id -> PendingPatch
-    pending_patches: DashMap<String, PendingPatch>,
-    /// Cargo/Pytest roots for test step (optional; default from cwd).
+    /// The memory manager's clock: commit, audit, lifecycle and report times.
+    clock: Clock,
+    /// Pending patches, HITL approval records and heal lifecycle timelines.
+    catalog: PatchCatalog,
+    /// Core repo (approve-flag location) and bridge repo (skills, runner).
     core_dir: PathBuf,
     bridge_dir: PathBuf,
+    /// Patch components: target repo, test command, extension, HITL tier (PAGI_COMPONENTS_FILE).
+    components: ComponentRegistry,
+    /// Serializes ApplyPatch per target repo; backs GetApplyStatus.
+    apply_queue: ApplyQueue,
+    /// Optional warm skill workers (PAGI_SKILL_WORKER_POOL); None → spawn per action.
+    worker_pool: Option<WorkerPool>,
+    /// Which skill tiers get provenance records in L4 (PAGI_PROVENANCE_TIERS).
+    provenance: ProvenanceConfig,
+    /// Dispatch interpreter version, probed once on first real action.
+    python_version: tokio::sync::OnceCell<String>,
+    /// Proposal dedup, hourly cap and apply-failure backoff for the heal loop.
+    heal_governor: HealGovernor,
+    /// Skill discovery roots (PAGI_SKILL_SOURCES; default src/skills).
+    skill_sources: Vec<SkillSource>,
+    /// Bases for manifest filesystem entries and path params: bridge dir and PAGI_SKILL_DATA_ROOT.
+    skill_roots: Roots,
+    /// Native `builtin:` skills and their sandbox (PAGI_BUILTIN_*).
+    builtins: BuiltinConfig,
+    /// Offline patch proposals (PAGI_LOCAL_MODEL_PATH); the model is loaded on first use.
+    local_model_config: Option<LocalModelConfig>,
+    local_model: tokio::sync::OnceCell<Option<Arc<LocalModel>>>,
+    /// Per-component smoke commands run after the apply test step (PAGI_SMOKE_COMMANDS).
+    smoke: SmokeConfig,
+    /// Reviewer streams (HitlChannel): prompts for HITL-gated patches and gated actions, and their decisions.
+    hitl: Arc<HitlHub>,
+    /// Wait and gated tiers for action prompts (PAGI_HITL_POLL_SECS, PAGI_HITL_ACTION_TIERS).
+    action_approval: ApprovalPolicy,
+    /// Wait, reminders and timeout fallback for unapproved HITL-gated applies (PAGI_HITL_APPLY_WAIT_SECS).
+    apply_approval: ApprovalPolicy,
+    /// Revisions re-proposed per chain after failed apply tests (PAGI_PATCH_MAX_REVISIONS; 0 disables).
+    max_revisions: u32,
+    /// Audit records of real dispatches, for ReplayAction.
+    audit: AuditTrail,
+}
+
+/// Proposed-code characters in a ListPendingPatches preview.
+const PREVIEW_CHARS: usize = 200;
+/// ListPendingPatches page size when the request leaves limit at 0.
+const LIST_PENDING_DEFAULT: usize = 50;
+
+/// Link from a re-proposal to the patch whose apply test failed.
+struct Revision {
+    of: String,
+    number: u32,
+}
+
+/// One HITL record as GetApplyStatus and GetPatch report it.
+fn describe_approval(r: &ApprovalRecord) -> String {
+    match &r.outcome {
+        ApprovalOutcome::Approved => format!("approved: {}", r.detail),
+        ApprovalOutcome::Denied => format!("denied: {}", r.detail),
+        ApprovalOutcome::TimedOut { fallback, .. } => format!("timed_out: {}; fallback={}", r.detail, fallback),
+    }
+}
+
+/// Start of `code` on one line, cut at PREVIEW_CHARS.
+fn preview(code: &str) -> String {
+    let code = code.split_whitespace().collect::<Vec<_>>().join(" ");
+    match code.char_indices().nth(PREVIEW_CHARS) {
+        Some((i, _)) => format!("{}…", &code[..i]),
+        None => code,
+    }
+}
+
+/// Seconds from `since_ms` to `now_ms`; 0 when unknown (0) or in the future.
+fn age_secs(since_ms: i64, now_ms: i64) -> u64 {
+    if since_ms <= 0 {
+        return 0;
+    }
+    (now_ms.saturating_sub(since_ms).max(0) / 1000) as u64
 }
 
 impl Watchdog {
@@ -47,25 +143,88 @@ impl Watchdog {
         core_dir: PathBuf,
         bridge_dir: PathBuf,
     ) -> Arc<Self> {
+        let worker_pool = WorkerPool::from_env(&bridge_dir);
+        let provenance = ProvenanceConfig::from_env();
+        let clock = memory.clock().clone();
+        let mut catalog = if Self::env_truthy("PAGI_HITL_STATE_BACKUP", true) {
+            PatchCatalog::with_backup(registry_path.join("hitl_state"))
+        } else {
+            PatchCatalog::new()
+        }
+        .with_clock(clock.clone());
+        if let Some(store) = store::global() {
+            catalog = catalog.with_store(store);
+        }
+        if provenance.enabled() {
+            eprintln!(
+                "[Watchdog] action provenance -> {} (tiers: {:?})",
+                provenance.kb_name, provenance.tiers
+            );
+        }
+        let components = ComponentRegistry::from_env(&core_dir, &bridge_dir);
+        let builtins = BuiltinConfig::from_env(&bridge_dir);
         Arc::new(Self {
-            registry_path,
+            registry: registry::from_env(&registry_path, clock.clone()),
             memory,
-            pending_patches: DashMap::new(),
+            clock: clock.clone(),
+            catalog,
+            components,
             core_dir,
             bridge_dir,
+            apply_queue: match store::global() {
+                Some(store) => ApplyQueue::new().with_store(store),
+                None => ApplyQueue::new(),
+            },
+            worker_pool,
+            provenance,
+            python_version: tokio::sync::OnceCell::new(),
+            heal_governor: HealGovernor::from_env(),
+            skill_sources: SkillSource::from_env(),
+            skill_roots: Roots::from_env(&bridge_dir),
+            builtins,
+            local_model_config: LocalModelConfig::from_env(),
+            local_model: tokio::sync::OnceCell::new(),
+            smoke: SmokeConfig::from_env(),
+            hitl: Arc::new(HitlHub::new(clock.clone())),
+            action_approval: ApprovalPolicy::from_env(),
+            apply_approval: ApprovalPolicy::for_apply(),
+            max_revisions: std::env::var("PAGI_PATCH_MAX_REVISIONS")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(2),
+            audit: AuditTrail::new(store::global()),
         })
     }
 
-    fn open_repo(&self) -> Result<Repository, git2::Error> {
-        if self.registry_path.exists() {
-            Repository::open(&self.registry_path)
+    /// Clock stamping patches, approvals and generated reasoning ids.
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// HITL reviewer hub backing the HitlChannel RPC.
+    pub fn hitl(&self) -> &Arc<HitlHub> {
+        &self.hitl
+    }
+
+    /// Startup dependency check for the bridge: the checkout real dispatch runs skills from.
+    pub fn bridge_ready(&self) -> Result<(), String> {
+        let runner = self.bridge_dir.join("scripts").join("run_skill.py");
+        if runner.is_file() {
+            Ok(())
         } else {
-            std::fs::create_dir_all(&self.registry_path)
-                .map_err(|e| git2::Error::from_str(&e.to_string()))?;
-            Repository::init(&self.registry_path)
+            Err(format!("no skill runner at {}", runner.display()))
         }
     }
 
+    /// Startup dependency check for the Evolution Registry: its backend answers a status query.
+    pub async fn registry_ready(&self) -> Result<(), String> {
+        self.registry
+            .pending_changes()
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("{} registry at {}: {}", self.registry.name(), self.registry.dir().display(), e))
+    }
+
     /// Open bridge dir as Git repo (for auto-evolved skill commit).
     fn open_bridge_repo(&self) -> Result<Repository, git2::Error> {
         if self.bridge_dir.join(".git").exists() {
@@ -75,118 +234,114 @@ impl Watchdog {
         }
     }
 
-    /// Git-Watcher: poll registry, commit changes. Run in tokio::spawn. Interval from PAGI_WATCH_INTERVAL_SECS.
+    /// Git-Watcher: poll registry, version changes through the registry backend. Run in tokio::spawn. Interval
+    /// from PAGI_WATCH_INTERVAL_SECS. PAGI_WATCH_DRY_RUN: only report what would be committed (log + optional
+    /// PAGI_WATCH_WEBHOOK_URL event).
     pub async fn watch_and_commit(self: Arc<Self>) {
         let secs = std::env::var("PAGI_WATCH_INTERVAL_SECS")
             .ok()
             .and_then(|s| s.parse().ok())
             .unwrap_or(60);
+        let dry_run = Self::env_truthy("PAGI_WATCH_DRY_RUN", false);
+        let webhook_url = std::env::var("PAGI_WATCH_WEBHOOK_URL")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        if dry_run {
+            eprintln!(
+                "[Watchdog] Git-Watcher dry-run ({}): registry changes are reported, not committed",
+                self.registry.name()
+            );
+        }
+        let mut last_reported: Vec<String> = Vec::new();
         let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(secs));
         loop {
             interval.tick().await;
-            if let Ok(repo) = self.open_repo() {
-                if let Err(e) = self.commit_changes(&repo) {
-                    eprintln!("[Watchdog] commit_changes: {}", e);
+            if !dry_run {
+                if let Err(e) = self.registry.commit(&[], "Auto-commit self-patch (L6 traceability)").await {
+                    eprintln!("[Watchdog] {} registry commit: {}", self.registry.name(), e);
                 }
+                continue;
             }
+            let changes = match self.registry.pending_changes().await {
+                Ok(changes) => changes,
+                Err(e) => {
+                    eprintln!("[Watchdog] dry-run status: {}", e);
+                    continue;
+                }
+            };
+            // Report each distinct change set once, not on every tick.
+            if changes.is_empty() || changes == last_reported {
+                last_reported = changes;
+                continue;
+            }
+            let event = self.dry_run_event(&changes);
+            eprintln!(
+                "[Watchdog] dry-run: would commit {} change(s): {}",
+                changes.len(),
+                changes.join(", ")
+            );
+            if let Some(url) = webhook_url.as_deref() {
+                approval::send_reminder(url, &event).await;
+            }
+            last_reported = changes;
         }
     }
 
-    fn commit_changes(&self, repo: &Repository) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let mut index = repo.index()?;
-        index.add_all(["*"].iter(), IndexAddOption::DEFAULT, None)?;
-        index.write()?;
-        let tree_id = index.write_tree()?;
-        let tree = repo.find_tree(tree_id)?;
-        if let Ok(head) = repo.head() {
-            let head_commit = head.peel_to_commit()?;
-            if head_commit.tree_id() == tree_id {
-                return Ok(());
-            }
-        }
-        let head = repo.head();
-        let parent = match head {
-            Ok(r) => {
-                let head_commit = r.peel_to_commit()?;
-                vec![head_commit]
-            }
-            Err(_) => vec![],
-        };
-        let sig = Signature::now("Sovereign Architect", "agi@core")?;
-        let msg = "Auto-commit self-patch (L6 traceability)";
-        let _ = repo.commit(
-            Some("HEAD"),
-            &sig,
-            &sig,
-            msg,
-            &tree,
-            parent.iter().collect::<Vec<_>>().as_slice(),
-        )?;
-        Ok(())
+    fn dry_run_event(&self, changes: &[String]) -> serde_json::Value {
+        serde_json::json!({
+            "event": "registry_dry_run",
+            "would_commit": changes,
+            "count": changes.len(),
+            "at": self.clock.now().to_rfc3339(),
+        })
     }
 
-    /// Load allow-list of skill names from bridge src/skills: .py files only, exclude __init__.py.
-    /// Prefer Git tree (tracked files only); fallback to read_dir.
-    fn load_skills_allow_list(&self) -> Result<Vec<String>, String> {
-        let skills_dir = self.bridge_dir.join("src").join("skills");
-        let mut names: Vec<String> = Vec::new();
-
-        if let Ok(repo) = Repository::discover(&self.bridge_dir) {
-            if let Ok(workdir) = repo.workdir().ok_or_else(|| "no workdir".to_string()) {
-                let workdir = workdir.to_path_buf();
-                if let Ok(rel) = skills_dir.strip_prefix(&workdir) {
-                    let rel_str = rel.to_string_lossy().replace('\\', "/");
-                    if let Ok(head) = repo.head() {
-                        if let Ok(commit) = head.peel_to_commit() {
-                            if let Ok(root_tree) = commit.tree() {
-                                if let Ok(entry) = root_tree.get_path(Path::new(&rel_str)) {
-                                    if let Ok(obj) = entry.to_object(&repo) {
-                                        if let Ok(tree) = obj.peel_to_tree() {
-                                            for e in tree.iter() {
-                                                if let Some(n) = e.name() {
-                                                    if n.ends_with(".py") && n != "__init__.py" {
-                                                        if let Some(stem) = n.strip_suffix(".py") {
-                                                            names.push(stem.to_string());
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
+    /// Check path-typed params against the skill's manifest: no traversal, and inside the declared filesystem
+    /// for their access when the manifest declares one. Data-root params are rewritten to absolute paths.
+    fn confine_path_params(&self, req: &mut ActionRequest, manifest: &SkillManifest) -> Result<(), Status> {
+        for param in &manifest.path_params {
+            let Some(value) = req.params.get_mut(&param.name) else {
+                continue;
+            };
+            SafetyGovernor::check_path_param(&param.name, value)?;
+            let base = self.skill_roots.dir(param.root).ok_or_else(|| {
+                Status::failed_precondition(format!(
+                    "param {:?} of {} is under the data root; set PAGI_SKILL_DATA_ROOT",
+                    param.name, req.skill_name
+                ))
+            })?;
+            let path = base.join(value.as_str());
+            if let Some(fs) = &manifest.filesystem {
+                if !fs.permits(&path, param.access) {
+                    let message = format!(
+                        "param {:?} ({:?}) is outside the paths {} may {}",
+                        param.name,
+                        value,
+                        req.skill_name,
+                        param.access.as_str()
+                    );
+                    let detail = PolicyDenial {
+                        rule: "skill_paths".to_string(),
+                        subject: param.name.clone(),
+                        ..Default::default()
+                    };
+                    return Err(policy_denial::status(Code::PermissionDenied, message, detail));
                 }
             }
-        }
-
-        if names.is_empty() {
-            if let Ok(rd) = std::fs::read_dir(&skills_dir) {
-                for e in rd.flatten() {
-                    if let Some(n) = e.file_name().to_str() {
-                        if n.ends_with(".py") && n != "__init__.py" {
-                            if let Some(stem) = n.strip_suffix(".py") {
-                                names.push(stem.to_string());
-                            }
-                        }
-                    }
-                }
+            if param.root == Root::Data {
+                *value = path.display().to_string();
             }
         }
-
-        names.sort();
-        Ok(names)
+        Ok(())
     }
 
-    /// SHA256 hex of sorted allow-list (one name per line) for consistency check.
-    fn allow_list_hash(skills: &[String]) -> String {
-        let mut hasher = Sha256::new();
-        for s in skills {
-            hasher.update(s.as_bytes());
-            hasher.update(b"\n");
-        }
-        format!("{:x}", hasher.finalize())
+    /// Load allow-list of skill names from the configured skill sources (PAGI_SKILL_SOURCES),
+    /// plus the `builtin:` skills. Per root: prefer Git tree (tracked files only); fallback to read_dir.
+    fn load_skills_allow_list(&self) -> Result<AllowList, String> {
+        let mut list = allow_list::load(&self.bridge_dir, &self.skill_sources);
+        self.builtins.register(&mut list);
+        Ok(list)
     }
 
     fn env_truthy(name: &str, default: bool) -> bool {
@@ -221,25 +376,25 @@ impl Watchdog {
     /// Constraints:
     /// - Gated by PAGI_AUTO_EVOLVE_SKILLS
     /// - Uses existing ExecuteAction/allow-list machinery (no new proto)
-    /// - Single call to evolve_skill_from_patch; parse EVOLVED_PATH from observation; git add/commit in bridge repo
-    async fn propose_new_skill_from_patch(&self, patch_path: &Path) -> Result<(), Status> {
-        let patch_content = std::fs::read_to_string(patch_path)
-            .map_err(|e| Status::internal(format!("read patch: {}", e)))?;
-
+    /// - Single call to evolve_skill_from_patch; take the evolved file from the runner's artifacts (v2) or the
+    ///   EVOLVED_PATH observation (v1); git add/commit in bridge repo
+    /// - `patch_content` is the applied code, not the stored format-patch file
+    async fn propose_new_skill_from_patch(&self, patch_content: &str) -> Result<(), Status> {
         let allow_list = self
             .load_skills_allow_list()
             .map_err(|e| Status::internal(format!("load allow-list: {}", e)))?;
 
         let mut params = HashMap::new();
-        params.insert("patch_content".to_string(), patch_content);
+        params.insert("patch_content".to_string(), patch_content.to_string());
         let evolve_req = ActionRequest {
             skill_name: "evolve_skill_from_patch".to_string(),
             params,
             depth: 0,
             reasoning_id: format!("auto-evolve-{}", Uuid::new_v4()),
             mock_mode: false,
-            allow_list_hash: Self::allow_list_hash(&allow_list),
+            allow_list_hash: allow_list.hash(),
             timeout_ms: 15_000,
+            caller_skills: Vec::new(),
         };
 
         let evolve_resp = self.execute_action_real(evolve_req).await?;
@@ -250,15 +405,20 @@ impl Watchdog {
             )));
         }
 
+        // v2 runners report the file as an artifact; v1 runners print EVOLVED_PATH:<path>.
+        let artifact = evolve_resp
+            .metadata
+            .get("runner.artifacts")
+            .and_then(|a| serde_json::from_str::<Vec<String>>(a).ok())
+            .and_then(|a| a.into_iter().next());
         let obs = evolve_resp.observation.trim();
         const PREFIX: &str = "EVOLVED_PATH:";
-        let rel_path = obs
-            .strip_prefix(PREFIX)
-            .map(|s| s.trim())
+        let rel_path = artifact
+            .or_else(|| obs.strip_prefix(PREFIX).map(|s| s.trim().to_string()))
             .filter(|s| !s.is_empty())
             .ok_or_else(|| {
                 Status::internal(format!(
-                    "evolve_skill_from_patch observation missing EVOLVED_PATH: {:?}",
+                    "evolve_skill_from_patch returned no artifact or EVOLVED_PATH: {:?}",
                     obs.chars().take(80).collect::<String>()
                 ))
             })?;
@@ -289,7 +449,7 @@ impl Watchdog {
                 .map_err(|e| Status::internal(e.to_string()))?],
             Err(_) => vec![],
         };
-        let sig = Signature::now("Sovereign Architect", "agi@core")
+        let sig = Signature::new("Sovereign Architect", "agi@core", &git2::Time::new(self.clock.now_secs(), 0))
             .map_err(|e| Status::internal(e.to_string()))?;
         let msg = "Auto-evolved skill from self-patch";
         let _ = repo
@@ -306,30 +466,130 @@ impl Watchdog {
         Ok(())
     }
 
-    /// Real L5 dispatch: allow-list check, hash check, spawn python skill with timeout, log, return.
-    /// No shell; timeout hard-enforced. Logs to PAGI_AGENT_ACTIONS_LOG (or PAGI_SELF_HEAL_LOG).
-    pub async fn execute_action_real(
-        &self,
-        req: ActionRequest,
-    ) -> Result<ActionResponse, Status> {
-        let allow_list = self
-            .load_skills_allow_list()
-            .map_err(|e| Status::internal(format!("load allow-list: {}", e)))?;
-
-        if !allow_list.contains(&req.skill_name) {
-            return Err(Status::permission_denied("Skill not in registry"));
+    /// Snapshot the KBs listed in PAGI_SNAPSHOT_BEFORE_APPLY (comma-separated; empty disables) so agent memory
+    /// can be rolled back with RestoreKb if the patch misbehaves. A failed snapshot aborts the apply.
+    async fn snapshot_before_apply(&self, patch_id: &str) -> Result<(), Status> {
+        let kbs = std::env::var("PAGI_SNAPSHOT_BEFORE_APPLY").unwrap_or_default();
+        let label = format!("pre-apply-{}", patch_id);
+        let label = if snapshot::check_label(&label).is_ok() { label } else { "pre-apply".to_string() };
+        for kb in kbs.split(',').map(str::trim).filter(|kb| !kb.is_empty()) {
+            let snap = self
+                .memory
+                .snapshot_kb(SnapshotKbRequest {
+                    kb_name: kb.to_string(),
+                    label: label.clone(),
+                    ..Default::default()
+                })
+                .await
+                .map_err(|e| {
+                    Status::failed_precondition(format!("pre-apply snapshot of {} failed: {}", kb, e.message()))
+                })?;
+            eprintln!("[Watchdog] {}: snapshot {} of {} ({} points)", patch_id, snap.snapshot, kb, snap.points);
         }
+        Ok(())
+    }
 
-        let computed_hash = Self::allow_list_hash(&allow_list);
-        if !req.allow_list_hash.is_empty() && req.allow_list_hash != computed_hash {
-            return Err(Status::invalid_argument("Allow-list mismatch"));
-        }
+    /// One-shot runner: `python scripts/run_skill.py <skill> <json> [<skill_path>]` with a hard
+    /// timeout (no shell). `skill_path` is set for namespaced skills outside src/skills. Under protocol v2
+    /// the invocation envelope is also written to stdin with a per-invocation temp dir and the skill's declared
+    /// filesystem, which the runner enforces (see runner_protocol).
+    async fn spawn_runner(
+        runner_script: &Path,
+        bridge_dir: &Path,
+        req: &ActionRequest,
+        params_json: &str,
+        skill_path: Option<&Path>,
+        filesystem: Option<&Filesystem>,
+        timeout_dur: std::time::Duration,
+    ) -> Result<(RunnerOutput, ResourceUsage), Status> {
+        let protocol = runner_protocol::version_from_env();
+        let invocation_id = Uuid::new_v4().to_string();
+        let temp_dir = std::env::temp_dir().join(format!("pagi_skill_{}", invocation_id));
+        let envelope = if protocol == runner_protocol::VERSION {
+            std::fs::create_dir_all(&temp_dir).map_err(|e| Status::internal(format!("skill temp dir: {}", e)))?;
+            let invocation = runner_protocol::Invocation {
+                protocol,
+                skill: &req.skill_name,
+                params: &req.params,
+                invocation_id: &invocation_id,
+                temp_dir: &temp_dir,
+                deadline_unix_ms: chrono::Utc::now().timestamp_millis() + timeout_dur.as_millis() as i64,
+                skill_path,
+                filesystem,
+            };
+            Some(serde_json::to_vec(&invocation).map_err(|e| Status::internal(format!("encode envelope: {}", e)))?)
+        } else {
+            None
+        };
 
-        let timeout_ms = if req.timeout_ms > 0 {
-            req.timeout_ms
+        let mut command = tokio::process::Command::new("python");
+        command.arg(runner_script).arg(&req.skill_name).arg(params_json);
+        if let Some(path) = skill_path {
+            command.arg(path);
+        }
+        let stdin = if envelope.is_some() {
+            command.env("PAGI_RUNNER_PROTOCOL", protocol.to_string());
+            std::process::Stdio::piped()
         } else {
-            5000
+            std::process::Stdio::null()
         };
+        let mut child = command
+            .current_dir(bridge_dir)
+            .stdin(stdin)
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| Status::internal(format!("spawn python: {}", e)))?;
+        // Written off-task: a v1 runner never reads stdin, and a large envelope must not block the wait.
+        if let (Some(mut pipe), Some(envelope)) = (child.stdin.take(), envelope) {
+            tokio::spawn(async move {
+                use tokio::io::AsyncWriteExt;
+                let _ = pipe.write_all(&envelope).await;
+            });
+        }
+        inflight::record_child_pid(child.id());
+        let started = std::time::Instant::now();
+        let sampler = resource_usage::Sampler::start(child.id());
+
+        let child = Arc::new(tokio::sync::Mutex::new(Some(child)));
+        let child_timeout = Arc::clone(&child);
+        let outcome = tokio::select! {
+            res = async move {
+                let c = child.lock().await.take().unwrap();
+                c.wait_with_output().await
+            } => match res {
+                Ok(output) => runner_protocol::parse_output(
+                    &String::from_utf8_lossy(&output.stdout),
+                    &String::from_utf8_lossy(&output.stderr),
+                    output.status.success(),
+                    output.status.code(),
+                ),
+                Err(e) => {
+                    let _ = std::fs::remove_dir_all(&temp_dir);
+                    return Err(Status::internal(format!("wait_with_output: {}", e)));
+                }
+            },
+            _ = tokio::time::sleep(timeout_dur) => {
+                if let Some(mut c) = child_timeout.lock().await.take() {
+                    let _ = c.start_kill();
+                    let _ = c.wait().await;
+                }
+                RunnerOutput::failed("Execution timed out")
+            }
+        };
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        Ok((outcome, sampler.finish(started.elapsed())))
+    }
+
+    /// Python dispatch for allow-listed skills: warm pool first (when enabled), else a fresh runner.
+    async fn dispatch_python_skill(
+        &self,
+        req: &ActionRequest,
+        skill_path: Option<&Path>,
+        filesystem: Option<&Filesystem>,
+        timeout_dur: std::time::Duration,
+    ) -> Result<(RunnerOutput, ResourceUsage), Status> {
         let runner_script = self.bridge_dir.join("scripts").join("run_skill.py");
         if !runner_script.exists() {
             return Err(Status::not_found(format!(
@@ -346,56 +606,163 @@ impl Watchdog {
                 .collect();
             serde_json::to_string(&map).unwrap_or_else(|_| "{}".to_string())
         };
+        let skill_name = req.skill_name.as_str();
+
+        // Any pool failure other than a timeout falls back to a fresh spawn. Confined skills always get a fresh
+        // runner: pool workers do not enforce a filesystem allow-list.
+        let started = std::time::Instant::now();
+        let pooled = match &self.worker_pool {
+            Some(pool) if filesystem.is_none() => match pool
+                .execute(skill_name, &params_json, skill_path, timeout_dur)
+                .await
+            {
+                PoolOutcome::Done(observation, success, error, usage) => Some((
+                    RunnerOutput {
+                        observation,
+                        success,
+                        error,
+                        ..Default::default()
+                    },
+                    usage,
+                )),
+                PoolOutcome::TimedOut => Some((
+                    RunnerOutput::failed("Execution timed out"),
+                    ResourceUsage::from_samples(started.elapsed(), None, None),
+                )),
+                PoolOutcome::Unavailable(e) => {
+                    eprintln!("[Watchdog] worker pool unavailable ({}); spawning runner", e);
+                    None
+                }
+            },
+            _ => None,
+        };
+        match pooled {
+            Some(outcome) => Ok(outcome),
+            None => {
+                let bridge_dir = &self.bridge_dir;
+                Self::spawn_runner(&runner_script, bridge_dir, req, &params_json, skill_path, filesystem, timeout_dur)
+                    .await
+            }
+        }
+    }
 
+    /// HITL gate for real dispatches of skills in PAGI_HITL_ACTION_TIERS: prompt reviewers on the HITL channel
+    /// and wait up to PAGI_HITL_POLL_SECS; a rejection or no decision denies the action.
+    async fn approve_action(&self, req: &ActionRequest) -> Result<(), Status> {
+        if !self.action_approval.gates_action(&req.skill_name) {
+            return Ok(());
+        }
+        let request_id = Uuid::new_v4().to_string();
+        let wait = self.action_approval.wait;
+        self.hitl.raise(HitlPrompt {
+            request_id: request_id.clone(),
+            kind: "action".to_string(),
+            subject: req.skill_name.clone(),
+            summary: provenance::summarize_params(&req.params),
+            reasoning_id: req.reasoning_id.clone(),
+            deadline_unix: self.clock.now_secs().saturating_add(wait.as_secs() as i64),
+            ..Default::default()
+        });
+        let denial = |rule: &str, setting: &str| PolicyDenial {
+            rule: rule.to_string(),
+            subject: req.skill_name.clone(),
+            setting: setting.to_string(),
+            approval_channel: policy_denial::HITL_CHANNEL.to_string(),
+            ..Default::default()
+        };
+        match self.hitl.wait(&request_id, wait).await {
+            Some(d) if d.approved => Ok(()),
+            Some(d) => Err(policy_denial::status(
+                Code::PermissionDenied,
+                format!("HITL reviewer {} ({})", d.describe(), req.skill_name),
+                denial("hitl_rejected", "PAGI_HITL_ACTION_TIERS"),
+            )),
+            None => {
+                self.hitl.withdraw(&request_id, "no decision before the deadline");
+                let detail = PolicyDenial {
+                    current: wait.as_secs() as f64,
+                    limit: wait.as_secs() as f64,
+                    ..denial("hitl_timeout", "PAGI_HITL_POLL_SECS")
+                };
+                Err(policy_denial::status(
+                    Code::PermissionDenied,
+                    format!("HITL approval for {} not given within {}s", req.skill_name, wait.as_secs()),
+                    detail,
+                ))
+            }
+        }
+    }
+
+    /// Real L5 dispatch: allow-list check, hash check, spawn python skill with timeout, log, return.
+    /// No shell; timeout hard-enforced. Logs to PAGI_AGENT_ACTIONS_LOG (or PAGI_SELF_HEAL_LOG).
+    pub async fn execute_action_real(
+        &self,
+        mut req: ActionRequest,
+    ) -> Result<ActionResponse, Status> {
+        let allow_list = self
+            .load_skills_allow_list()
+            .map_err(|e| Status::internal(format!("load allow-list: {}", e)))?;
+
+        if !allow_list.contains(&req.skill_name) {
+            let detail = PolicyDenial {
+                rule: "allow_list".to_string(),
+                subject: req.skill_name.clone(),
+                ..Default::default()
+            };
+            return Err(policy_denial::status(Code::PermissionDenied, "Skill not in registry", detail));
+        }
+        let skill_path = allow_list.paths.get(&req.skill_name).map(PathBuf::as_path);
+        let manifest = skill_manifest::load(&self.skill_roots, &req.skill_name, skill_path);
+        self.confine_path_params(&mut req, &manifest)?;
+
+        let computed_hash = allow_list.hash();
+        if !req.allow_list_hash.is_empty() && req.allow_list_hash != computed_hash {
+            return Err(allow_list.mismatch_status(&req.allow_list_hash, &req.caller_skills));
+        }
+        self.approve_action(&req).await?;
+        let python_version = self
+            .python_version
+            .get_or_init(env_fingerprint::python_version)
+            .await
+            .clone();
+        let fingerprint =
+            EnvFingerprint::new(python_version, &self.bridge_dir, computed_hash, allow_list.revision.clone());
+
+        let timeout_ms = if req.timeout_ms > 0 {
+            req.timeout_ms
+        } else {
+            5000
+        };
         let skill_name = req.skill_name.clone();
         let reasoning_id = req.reasoning_id.clone();
         let timeout_dur = std::time::Duration::from_millis(timeout_ms as u64);
+        let started = std::time::Instant::now();
 
-        let child = tokio::process::Command::new("python")
-            .arg(&runner_script)
-            .arg(&req.skill_name)
-            .arg(&params_json)
-            .current_dir(&self.bridge_dir)
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
-            .kill_on_drop(true)
-            .spawn()
-            .map_err(|e| Status::internal(format!("spawn python: {}", e)))?;
+        let skill_file = allow_list.skill_file(&skill_name);
+        let skill_blob = if skill_name.starts_with(builtin_skills::PREFIX) {
+            String::new()
+        } else {
+            let source = std::fs::read(self.bridge_dir.join(&skill_file));
+            source.map_or_else(|_| String::new(), |c| action_audit::blob_id(&c))
+        };
 
-        let child = Arc::new(tokio::sync::Mutex::new(Some(child)));
-        let child_timeout = Arc::clone(&child);
-        let (observation, success, error_msg) = tokio::select! {
-            res = async move {
-                let c = child.lock().await.take().unwrap();
-                c.wait_with_output().await
-            } => match res {
-                Ok(output) => {
-                    let observation = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-                    let success = output.status.success();
-                    let error_msg = if success {
-                        String::new()
-                    } else if stderr.is_empty() {
-                        format!("exit code {:?}", output.status.code())
-                    } else {
-                        stderr
-                    };
-                    (observation, success, error_msg)
-                }
-                Err(e) => return Err(Status::internal(format!("wait_with_output: {}", e))),
-            },
-            _ = tokio::time::sleep(timeout_dur) => {
-                if let Some(mut c) = child_timeout.lock().await.take() {
-                    let _ = c.start_kill();
-                    let _ = c.wait().await;
-                }
-                (
-                    String::new(),
-                    false,
-                    "Execution timed out".to_string(),
-                )
+        let (output, usage) = match skill_name.strip_prefix(builtin_skills::PREFIX) {
+            // Native skills run in-process: no runner script, bridge or interpreter needed.
+            Some(builtin) => {
+                let output = self.run_builtin(builtin, &req.params, timeout_dur).await;
+                (output, ResourceUsage::from_samples(started.elapsed(), None, None))
+            }
+            None => {
+                self.dispatch_python_skill(&req, skill_path, manifest.filesystem.as_ref(), timeout_dur).await?
             }
         };
+        let runner_metadata = output.to_metadata();
+        let RunnerOutput {
+            observation,
+            success,
+            error: error_msg,
+            ..
+        } = output;
 
         let log_path = std::env::var("PAGI_AGENT_ACTIONS_LOG")
             .or_else(|_| std::env::var("PAGI_SELF_HEAL_LOG"))
@@ -405,213 +772,1148 @@ impl Watchdog {
             .create(true)
             .open(&log_path)
         {
-            let log_line = if success {
-                format!("ACTION {} {} -> {}", reasoning_id, skill_name, observation)
-            } else {
-                format!("ACTION {} {} -> {}", reasoning_id, skill_name, error_msg)
-            };
+            let log_line = format!(
+                "ACTION {} {} -> {} {} {}",
+                reasoning_id,
+                skill_name,
+                if success { &observation } else { &error_msg },
+                fingerprint.audit_suffix(),
+                usage.audit_suffix()
+            );
             let _ = writeln!(f, "{}", log_line);
         }
+        let audit_id = self.audit.record(ActionRecord {
+            at_ms: self.clock.now_ms(),
+            reasoning_id: reasoning_id.clone(),
+            skill: skill_name.clone(),
+            success,
+            detail: if success { observation.clone() } else { error_msg.clone() },
+            fingerprint: fingerprint.audit_suffix(),
+            usage: usage.audit_suffix(),
+            params: req.params.clone(),
+            timeout_ms,
+            skill_file: if skill_blob.is_empty() { String::new() } else { skill_file.to_string_lossy().into_owned() },
+            skill_blob,
+            filesystem: manifest.filesystem.clone(),
+        });
+        let metrics = metrics::global();
+        metrics.record_latency(&format!("skill.{}", skill_name), std::time::Duration::from_millis(usage.wall_ms));
+        if let Some(cpu_ms) = usage.cpu_ms {
+            metrics.record_latency(&format!("skill.{}.cpu", skill_name), std::time::Duration::from_millis(cpu_ms));
+        }
+
+        if success {
+            provenance::record_action(
+                &self.memory,
+                &self.provenance,
+                &skill_name,
+                &req.params,
+                &observation,
+                &reasoning_id,
+            );
+        }
 
         Ok(ActionResponse {
             observation,
             success,
             error: error_msg,
+            metadata: fingerprint
+                .to_metadata()
+                .into_iter()
+                .chain(usage.to_metadata())
+                .chain(runner_metadata)
+                .chain([("audit_id".to_string(), audit_id)])
+                .collect(),
+            dispatch_mode: "real".to_string(),
+        })
+    }
+
+    /// A native `builtin:` skill, bounded by `timeout`.
+    async fn run_builtin(
+        &self,
+        name: &str,
+        params: &HashMap<String, String>,
+        timeout: std::time::Duration,
+    ) -> RunnerOutput {
+        match tokio::time::timeout(timeout, builtin_skills::execute(name, params, &self.builtins)).await {
+            Ok(Ok(observation)) => RunnerOutput {
+                observation,
+                success: true,
+                ..Default::default()
+            },
+            Ok(Err(e)) => RunnerOutput::failed(e),
+            Err(_) => RunnerOutput::failed("Execution timed out"),
+        }
+    }
+
+    /// ReplayAction: re-run an audited dispatch as recorded (skill source recovered by its blob id, same params
+    /// and timeout) with every write denied, and diff the new observation against the recorded one. Forensic
+    /// only: the allow-list and HITL gates are not consulted, and nothing is logged, audited or recorded in L4.
+    pub async fn replay_action(&self, req: ReplayActionRequest) -> Result<ReplayActionResponse, Status> {
+        let record = self
+            .audit
+            .get(&req.audit_id)
+            .ok_or_else(|| Status::not_found(format!("no audit record {} (only recent ones are kept)", req.audit_id)))?;
+        let timeout_ms = match (req.timeout_ms, record.timeout_ms) {
+            (0, 0) => 5000,
+            (0, recorded) => recorded,
+            (asked, _) => asked,
+        };
+        let timeout_dur = std::time::Duration::from_millis(timeout_ms as u64);
+        let sandbox = action_audit::read_only(record.filesystem.as_ref(), &self.skill_roots);
+        let output = match record.skill.strip_prefix(builtin_skills::PREFIX) {
+            // Builtins only read: files under PAGI_BUILTIN_ROOTS and allow-listed URLs.
+            Some(builtin) => self.run_builtin(builtin, &record.params, timeout_dur).await,
+            None => {
+                let source =
+                    action_audit::recover_source(&self.bridge_dir, &record).map_err(Status::failed_precondition)?;
+                let runner_script = self.bridge_dir.join("scripts").join("run_skill.py");
+                if !runner_script.exists() {
+                    return Err(Status::not_found(format!("Runner script not found: {}", runner_script.display())));
+                }
+                let dir = std::env::temp_dir().join(format!("pagi_replay_{}", req.audit_id));
+                let skill_path = dir.join(format!("{}.py", record.skill.rsplit('.').next().unwrap_or(&record.skill)));
+                std::fs::create_dir_all(&dir)
+                    .and_then(|_| std::fs::write(&skill_path, source))
+                    .map_err(|e| Status::internal(format!("stage skill source: {}", e)))?;
+                let action = ActionRequest {
+                    skill_name: record.skill.clone(),
+                    params: record.params.clone(),
+                    reasoning_id: record.reasoning_id.clone(),
+                    timeout_ms,
+                    ..Default::default()
+                };
+                let params_json = serde_json::to_string(&record.params).unwrap_or_else(|_| "{}".to_string());
+                let bridge_dir = &self.bridge_dir;
+                let run = Self::spawn_runner(
+                    &runner_script,
+                    bridge_dir,
+                    &action,
+                    &params_json,
+                    Some(&skill_path),
+                    Some(&sandbox),
+                    timeout_dur,
+                )
+                .await;
+                let _ = std::fs::remove_dir_all(&dir);
+                run?.0
+            }
+        };
+        let replayed = if output.success { output.observation } else { output.error };
+        let matches = output.success == record.success && replayed == record.detail;
+        eprintln!(
+            "[Watchdog] replayed {} (audit {}): {}",
+            record.skill,
+            req.audit_id,
+            if matches { "matches the record" } else { "differs from the record" }
+        );
+        Ok(ReplayActionResponse {
+            diff: action_audit::diff(&record.detail, &replayed),
+            audit_id: req.audit_id,
+            skill_name: record.skill,
+            params: record.params,
+            skill_blob: record.skill_blob,
+            recorded_fingerprint: record.fingerprint,
+            recorded_success: record.success,
+            recorded_observation: record.detail,
+            replayed_success: output.success,
+            replayed_observation: replayed,
+            matches,
+            sandbox_read: sandbox.read.iter().map(|p| p.display().to_string()).collect(),
+        })
+    }
+
+    /// Offline proposal from the local GGUF model; None when unconfigured, unloadable or empty.
+    async fn local_model_patch(&self, req: &PatchRequest, component: &Component, hits: &[SearchHit]) -> Option<String> {
+        let config = self.local_model_config.clone()?;
+        let model = self
+            .local_model
+            .get_or_init(|| async {
+                let path = config.model_path.display().to_string();
+                match tokio::task::spawn_blocking(move || LocalModel::load(config)).await {
+                    Ok(Ok(model)) => {
+                        eprintln!("[Watchdog] local model loaded: {}", path);
+                        Some(Arc::new(model))
+                    }
+                    Ok(Err(e)) => {
+                        eprintln!("[Watchdog] local model unavailable: {}", e);
+                        None
+                    }
+                    Err(e) => {
+                        eprintln!("[Watchdog] local model load panicked: {}", e);
+                        None
+                    }
+                }
+            })
+            .await
+            .clone()?;
+        let snippets: Vec<String> = hits.iter().map(|h| h.content_snippet.clone()).collect();
+        let prompt = local_model::patch_prompt(
+            &req.component,
+            &component.language,
+            &req.error_trace,
+            &snippets,
+            self.local_model_config.as_ref()?.context_size,
+        );
+        match tokio::task::spawn_blocking(move || model.generate(&prompt)).await {
+            Ok(Ok(code)) if !code.trim().is_empty() => Some(code),
+            Ok(Ok(_)) => None,
+            Ok(Err(e)) => {
+                eprintln!("[Watchdog] local model generation failed: {}", e);
+                None
+            }
+            Err(e) => {
+                eprintln!("[Watchdog] local model generation panicked: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Self-healing: RCA via L4 search, return the proposed patch and its diff against the component repo.
+    pub async fn propose_patch(
+        &self,
+        req: PatchRequest,
+    ) -> Result<PatchResponse, Status> {
+        let detected_ms = self.clock.now_ms();
+        self.components.get(&req.component)?;
+        let fingerprint = heal_governor::error_fingerprint(&req.component, &req.error_trace);
+        let admission = self
+            .heal_governor
+            .admit(&req.component, &fingerprint, |id| self.catalog.get(id).is_some())?;
+        if let Admission::Existing(patch_id) = admission {
+            if let Some(p) = self.catalog.get(&patch_id) {
+                eprintln!("[Watchdog] dedup: fingerprint {} already pending as {}", fingerprint, patch_id);
+                return Ok(PatchResponse {
+                    patch_id,
+                    proposed_code: p.proposed_code,
+                    requires_hitl: p.requires_hitl,
+                    impact: Some(p.impact.to_proto()),
+                    revision_of: p.revision_of,
+                    revision: p.revision,
+                    diff: p.diff,
+                });
+            }
+        }
+        let trace = req.error_trace.clone();
+        self.propose(req, detected_ms, fingerprint, trace, None).await
+    }
+
+    /// RunSmokeTest: a patch for a synthetic error on `component`, proposed through the usual RCA and proposal path
+    /// and cancelled at once; returns its id. Heal admission is bypassed so the probe spends no heal budget, and
+    /// nothing is left pending or in heal reports (a HITL prompt raised for it is withdrawn).
+    pub async fn smoke_patch(&self, component: &str, reasoning_id: &str) -> Result<String, Status> {
+        let req = PatchRequest {
+            error_trace: format!("pagi smoke test: synthetic error {}", Uuid::new_v4()),
+            component: component.to_string(),
+            reasoning_id: reasoning_id.to_string(),
+        };
+        let fingerprint = heal_governor::error_fingerprint(&req.component, &req.error_trace);
+        let trace = req.error_trace.clone();
+        let resp = self.propose(req, self.clock.now_ms(), fingerprint.clone(), trace, None).await?;
+        self.catalog.discard(&resp.patch_id);
+        self.hitl.withdraw(&resp.patch_id, "smoke test patch cancelled");
+        self.heal_governor.release(&fingerprint, &resp.patch_id);
+        Ok(resp.patch_id)
+    }
+
+    /// RCA and proposal body shared by ProposePatch and revisions. `error_trace` is stored with the patch; for a
+    /// revision the request's trace also carries the failed test output.
+    async fn propose(
+        &self,
+        req: PatchRequest,
+        detected_ms: i64,
+        fingerprint: String,
+        error_trace: String,
+        revision: Option<Revision>,
+    ) -> Result<PatchResponse, Status> {
+        let component = self.components.get(&req.component)?;
+
+        // Time-weighted (PAGI_RCA_RECENCY_WEIGHT, default 0.3) so recent errors and observations outrank stale ones,
+        // then reranked (PAGI_RCA_RERANK, default mmr) so near-duplicate traces do not crowd out other evidence.
+        let recency_weight = std::env::var("PAGI_RCA_RECENCY_WEIGHT")
+            .ok()
+            .and_then(|s| s.trim().parse::<f32>().ok())
+            .filter(|w| w.is_finite())
+            .map_or(0.3, |w| w.clamp(0.0, 1.0));
+        let rerank = std::env::var("PAGI_RCA_RERANK").unwrap_or_else(|_| "mmr".into());
+        let search_req = SearchRequest {
+            query: req.error_trace.clone(),
+            kb_name: "kb_core".to_string(),
+            limit: 5,
+            query_vector: vec![],
+            filter: None,
+            hybrid: false,
+            recency_weight,
+            rerank,
+            ..Default::default()
+        };
+        let prior = self
+            .memory
+            .semantic_search(search_req)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let mut rca_note = if prior.degraded {
+            format!("\n// RCA degraded: L4 {} (no prior knowledge consulted)", prior.source)
+        } else {
+            String::new()
+        };
+        // Prior successful fixes for the same fingerprint, so reviewers see how it was fixed last time.
+        let history = SearchPatchesRequest {
+            query: req.error_trace.clone(),
+            fingerprint: fingerprint.clone(),
+            outcome: "applied".to_string(),
+            limit: 3,
+            ..Default::default()
+        };
+        match patch_history::search(&self.memory, history).await {
+            Ok(found) => {
+                for hit in found.hits {
+                    rca_note.push_str(&format!("\n// Prior fix: patch {} (commit {})", hit.patch_id, hit.detail));
+                }
+            }
+            Err(e) => eprintln!("[Watchdog] patch history search failed: {}", e.message()),
+        }
+        let headline = req
+            .error_trace
+            .lines()
+            .next()
+            .unwrap_or("")
+            .chars()
+            .take(200)
+            .collect::<String>();
+        let model_code = self.local_model_patch(&req, component, &prior.hits).await;
+        let proposed_code = match &model_code {
+            Some(code) => format!("// Local model fix for: {}{}\n{}", headline, rca_note, code),
+            None => format!(
+                "// Generic fix for: {}\n// Based on prior hits: {:?}{}",
+                headline,
+                prior
+                    .hits
+                    .iter()
+                    .map(|h| &h.content_snippet)
+                    .take(2)
+                    .collect::<Vec<_>>(),
+                rca_note
+            ),
+        };
+
+        let (revision_of, revision) = revision.map_or((String::new(), 0), |r| (r.of, r.number));
+        let proposed_code = if revision_of.is_empty() {
+            proposed_code
+        } else {
+            format!("// Revision {} of patch {} (its apply test failed)\n{}", revision, revision_of, proposed_code)
+        };
+        let diff = match patch_diff::locate(&req.error_trace, &component.repo) {
+            Some(at) => match patch_diff::propose(&component.repo, &at, model_code.as_deref(), &headline) {
+                Ok(diff) => diff,
+                Err(e) => {
+                    eprintln!("[Watchdog] no diff against {}: {}", at.file, e);
+                    String::new()
+                }
+            },
+            None => String::new(),
+        };
+        let requires_hitl = component.requires_hitl();
+        let patch_id = Uuid::new_v4().to_string();
+
+        let target = component.repo.clone();
+        let patches_dir = self.registry.dir().join("patches");
+        let trace = req.error_trace.clone();
+        let hits = prior.hits.clone();
+        let impact = tokio::task::spawn_blocking(move || impact::analyze(&trace, &target, &patches_dir, &hits))
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("[Watchdog] impact analysis failed: {}", e);
+                Default::default()
+            });
+
+        self.catalog.insert(
+            patch_id.clone(),
+            PendingPatch {
+                proposed_code: proposed_code.clone(),
+                requires_hitl,
+                component: req.component.clone(),
+                reasoning_id: req.reasoning_id.clone(),
+                impact: impact.clone(),
+                fingerprint: fingerprint.clone(),
+                error_trace,
+                revision_of: revision_of.clone(),
+                revision,
+                diff: diff.clone(),
+            },
+            detected_ms,
+        );
+        self.heal_governor.register(&fingerprint, &patch_id);
+        if requires_hitl {
+            let summary = if revision_of.is_empty() {
+                format!("{}\n{}", headline, impact.summary())
+            } else {
+                format!("revision {} of {}: {}\n{}", revision, revision_of, headline, impact.summary())
+            };
+            self.hitl.raise(HitlPrompt {
+                request_id: patch_id.clone(),
+                kind: "patch".to_string(),
+                subject: req.component.clone(),
+                summary,
+                reasoning_id: req.reasoning_id.clone(),
+                ..Default::default()
+            });
+        }
+
+        Ok(PatchResponse {
+            patch_id: patch_id.clone(),
+            proposed_code,
+            requires_hitl,
+            impact: Some(impact.to_proto()),
+            revision_of,
+            revision,
+            diff,
+        })
+    }
+
+    /// Re-propose after `patch_id` failed its apply test: RCA again on the chain's error trace with the test
+    /// output appended, as the next revision of the chain. The failed patch leaves the catalog once revised.
+    /// None when the chain already has PAGI_PATCH_MAX_REVISIONS revisions or the proposal fails.
+    async fn revise(&self, patch_id: &str, failed: &PendingPatch, test_output: &str) -> Option<PatchResponse> {
+        if failed.revision >= self.max_revisions {
+            eprintln!(
+                "[Watchdog] {}: apply test failed; revision limit {} reached, not re-proposing",
+                patch_id, self.max_revisions
+            );
+            return None;
+        }
+        let req = PatchRequest {
+            error_trace: format!(
+                "{}\n\n// Apply test failed for patch {}:\n{}",
+                failed.error_trace, patch_id, test_output
+            )
+            .trim_start()
+            .to_string(),
+            component: failed.component.clone(),
+            reasoning_id: failed.reasoning_id.clone(),
+        };
+        let revision = Revision {
+            of: patch_id.to_string(),
+            number: failed.revision + 1,
+        };
+        // Revisions keep the chain's trace and fingerprint and bypass heal admission: the failure backoff is meant
+        // for fresh proposals, and the revision limit bounds the chain.
+        let (trace, fingerprint) = (failed.error_trace.clone(), failed.fingerprint.clone());
+        match self.propose(req, self.clock.now_ms(), fingerprint, trace, Some(revision)).await {
+            Ok(resp) => {
+                eprintln!(
+                    "[Watchdog] {}: apply test failed; revised as {} (revision {})",
+                    patch_id, resp.patch_id, resp.revision
+                );
+                self.catalog.remove(patch_id);
+                self.hitl.withdraw(patch_id, &format!("superseded by revision {}", resp.patch_id));
+                Some(resp)
+            }
+            Err(e) => {
+                eprintln!("[Watchdog] {}: re-proposal after failed apply test: {}", patch_id, e.message());
+                None
+            }
+        }
+    }
+
+    /// Path to HITL approve flag file (e.g. approve.patch in core dir). Presence enables apply for core patches.
+    fn approve_flag_path(&self) -> PathBuf {
+        let name = std::env::var("PAGI_APPROVE_FLAG").unwrap_or_else(|_| "approve.patch".into());
+        self.core_dir.join(name)
+    }
+
+    /// Check if HITL approve flag file exists (poll for human-in-the-loop).
+    fn hitl_approved_via_flag(&self) -> bool {
+        self.approve_flag_path().exists()
+    }
+
+    /// Wait for HITL approval of patch_id under `policy`: a reviewer decision on the HITL channel or the approve
+    /// flag, notifying the webhook at start and every reminder interval; on timeout record the outcome and apply
+    /// the policy fallback.
+    pub async fn await_approval(&self, patch_id: &str, policy: &ApprovalPolicy) -> bool {
+        let (component, impact) = self
+            .catalog
+            .get(patch_id)
+            .map(|p| (p.component, p.impact))
+            .unwrap_or_default();
+        let notify = |reminder: u32, waited: u64| {
+            serde_json::json!({
+                "event": "hitl_approval_pending",
+                "patch_id": patch_id,
+                "component": component,
+                "impact": impact,
+                "impact_summary": impact.summary(),
+                "reminder": reminder,
+                "waited_secs": waited,
+                "timeout_secs": policy.wait.as_secs(),
+            })
+        };
+
+        let started = std::time::Instant::now();
+        let deadline = started + policy.wait;
+        let mut reminders = 0u32;
+        if let Some(url) = policy.webhook_url.as_deref() {
+            approval::send_reminder(url, &notify(reminders, 0)).await;
+        }
+        let mut next_reminder = started + policy.reminder_interval;
+        loop {
+            match self.hitl.decision(patch_id) {
+                Some(decision) if decision.approved => return true,
+                Some(decision) => {
+                    self.catalog.record_approval(
+                        patch_id,
+                        ApprovalOutcome::Denied,
+                        format!("via HITL channel: {}", decision.describe()),
+                    );
+                    return false;
+                }
+                None if self.hitl_approved_via_flag() => return true,
+                None => {}
+            }
+            let now = std::time::Instant::now();
+            if now >= deadline {
+                break;
+            }
+            if let Some(url) = policy.webhook_url.as_deref() {
+                if !policy.reminder_interval.is_zero() && now >= next_reminder {
+                    reminders += 1;
+                    approval::send_reminder(url, &notify(reminders, started.elapsed().as_secs())).await;
+                    next_reminder = now + policy.reminder_interval;
+                }
+            }
+            // A reviewer's decision ends the step early; the flag is still polled each step.
+            let step = policy.poll_interval.min(deadline - now);
+            if self.hitl.is_open(patch_id) {
+                self.hitl.wait(patch_id, step).await;
+            } else {
+                tokio::time::sleep(step).await;
+            }
+        }
+
+        let waited_secs = started.elapsed().as_secs();
+        self.catalog.record_approval(
+            patch_id,
+            ApprovalOutcome::TimedOut {
+                waited_secs,
+                fallback: policy.fallback.as_str().to_string(),
+            },
+            format!("no approval after {}s ({} reminders)", waited_secs, reminders),
+        );
+        if policy.fallback == TimeoutFallback::Reject {
+            self.catalog.reject(patch_id);
+            self.hitl.withdraw(patch_id, "approval timed out; patch rejected");
+        }
+        false
+    }
+
+    /// Apply: queue behind other applies to the same target repo, then run the guarded apply.
+    /// Outcome is recorded for GetApplyStatus. A failed test or smoke step re-proposes the patch as a revision
+    /// (see `revise`), named in the returned error.
+    pub async fn apply_patch(
+        &self,
+        req: ApplyRequest,
+    ) -> Result<ApplyResponse, Status> {
+        self.apply_patch_with_options(req, false).await
+    }
+
+    /// ApplyPatch with simulation hooks: `inject_test_failure` fails the test step like PAGI_FORCE_TEST_FAIL.
+    pub async fn apply_patch_with_options(
+        &self,
+        req: ApplyRequest,
+        inject_test_failure: bool,
+    ) -> Result<ApplyResponse, Status> {
+        let pending = self
+            .catalog
+            .get(&req.patch_id)
+            .ok_or_else(|| Status::not_found("patch_id not found"))?;
+        let fingerprint = pending.fingerprint.clone();
+        let target = self.components.get(&pending.component)?.repo.clone();
+        let ticket = self
+            .apply_queue
+            .acquire(&target, &req.patch_id)
+            .await
+            .map_err(Status::aborted)?;
+
+        let patch_id = req.patch_id.clone();
+        let mut test_failure = None;
+        let result = self.apply_patch_locked(req, inject_test_failure, &mut test_failure).await;
+        let state = match &result {
+            Ok(resp) => ApplyState::Applied {
+                commit_hash: resp.commit_hash.clone(),
+            },
+            Err(e) => ApplyState::Failed {
+                error: e.message().to_string(),
+            },
+        };
+        self.apply_queue.finish(&patch_id, state);
+        drop(ticket);
+        match &result {
+            Ok(resp) => {
+                self.catalog.mark(&patch_id, Stage::Applied);
+                self.catalog.record_applied(&patch_id, pending.clone(), &resp.commit_hash, &resp.target_commit);
+                self.heal_governor.record_apply_success(&fingerprint);
+                patch_history::record_outcome(&self.memory, &patch_id, &pending, "applied", &resp.commit_hash);
+            }
+            // Test/commit failures count toward backoff; HITL denials do not.
+            Err(e) if e.code() == tonic::Code::Internal => {
+                self.catalog.mark(&patch_id, Stage::Failed);
+                self.heal_governor.record_apply_failure(&fingerprint);
+                patch_history::record_outcome(&self.memory, &patch_id, &pending, "failed", e.message());
+            }
+            Err(_) => {}
+        }
+        match (result, test_failure) {
+            (Err(e), Some(output)) => match self.revise(&patch_id, &pending, &output).await {
+                Some(revised) => Err(Status::internal(format!(
+                    "{}; revised as patch {} (revision {})",
+                    e.message(),
+                    revised.patch_id,
+                    revised.revision
+                ))),
+                None => Err(e),
+            },
+            (result, _) => result,
+        }
+    }
+
+    /// RollbackPatch: undo an applied patch, queued behind applies to the same target. The component change is
+    /// undone by a `git revert` of its apply commit (or, without one, by reversing the diff) and the component's
+    /// tests rerun (a failure re-applies it), then the registry apply commit is reverted too, removing the patch
+    /// file. GetApplyStatus then reports "rolled_back".
+    pub async fn rollback_patch(&self, req: RollbackPatchRequest) -> Result<RollbackPatchResponse, Status> {
+        let applied = self
+            .catalog
+            .applied(&req.patch_id)
+            .ok_or_else(|| Status::not_found("patch_id was never applied"))?;
+        if applied.rolled_back_ms.is_some() {
+            let message = format!("patch {} is already rolled back", req.patch_id);
+            return Err(Status::failed_precondition(message));
+        }
+        let component = self.components.get(&applied.patch.component)?;
+        let ticket = self
+            .apply_queue
+            .acquire(&component.repo, &req.patch_id)
+            .await
+            .map_err(Status::aborted)?;
+        let result = self.rollback_locked(&req, &applied, component).await;
+        let state = match &result {
+            Ok(resp) => ApplyState::RolledBack {
+                commit_hash: resp.commit_hash.clone(),
+            },
+            Err(_) => ApplyState::Applied {
+                commit_hash: applied.commit_hash.clone(),
+            },
+        };
+        self.apply_queue.finish(&req.patch_id, state);
+        drop(ticket);
+        if let Ok(resp) = &result {
+            self.catalog.record_rollback(&req.patch_id, &resp.commit_hash);
+            let commit_hash = resp.commit_hash.as_str();
+            patch_history::record_outcome(&self.memory, &req.patch_id, &applied.patch, "rolled_back", commit_hash);
+        }
+        result
+    }
+
+    async fn rollback_locked(
+        &self,
+        req: &RollbackPatchRequest,
+        applied: &AppliedPatch,
+        component: &Component,
+    ) -> Result<RollbackPatchResponse, Status> {
+        let (patch_id, diff) = (req.patch_id.as_str(), applied.patch.diff.as_str());
+        let revert_message = |commit: &str| {
+            let mut msg = format!("Revert self-patch apply {} for {}", patch_id, applied.patch.component);
+            if !commit.is_empty() {
+                msg.push_str(&format!("\n\nThis reverts commit {}.", commit));
+            }
+            if !req.reason.trim().is_empty() {
+                msg.push_str(&format!("\n\nReason: {}", req.reason.trim()));
+            }
+            msg
+        };
+        let cannot_revert =
+            |e: String| Status::failed_precondition(format!("patch {} cannot be reverted: {}", patch_id, e));
+        // The component change is undone by `git revert` of its apply commit when there is one, else by
+        // reverse-applying the diff.
+        let target = self.target_repo(component).filter(|_| !applied.target_commit.is_empty());
+        let (files, target_commit) = match &target {
+            Some(repo) => {
+                let files = patch_diff::files(diff).map_err(cannot_revert)?;
+                let message = revert_message(&applied.target_commit);
+                let hash = repo
+                    .revert_commit(&applied.target_commit, &message)
+                    .map_err(|e| cannot_revert(e.to_string()))?;
+                (files, hash)
+            }
+            None if diff.is_empty() => (Vec::new(), String::new()),
+            None => (patch_diff::apply(&component.repo, diff, true).map_err(cannot_revert)?, String::new()),
+        };
+        let reapply = || {
+            let reapplied = match &target {
+                Some(repo) => repo
+                    .revert_commit(&target_commit, &format!("Reapply self-patch {} after a failed rollback", patch_id))
+                    .map(|_| ())
+                    .map_err(|e| e.to_string()),
+                None if diff.is_empty() => Ok(()),
+                None => patch_diff::apply(&component.repo, diff, false).map(|_| ()),
+            };
+            if let Err(e) = reapplied {
+                eprintln!("[Watchdog] {}: re-applying after a failed rollback: {}", patch_id, e);
+            }
+        };
+
+        let skip_test = component.test.is_empty() || Self::env_truthy("PAGI_SKIP_APPLY_TEST", false);
+        let test_result = if skip_test {
+            format!("skipped: {}", component.test_label())
+        } else if let Err(output) = test_runner::run(&component.test, &component.repo).await {
+            reapply();
+            return Err(Status::internal(format!(
+                "tests fail with patch {} rolled back, so it stays applied: {}",
+                patch_id, output
+            )));
+        } else {
+            format!("passed: {}", component.test_label())
+        };
+
+        // The apply commit only added the patch file, so the registry revert removes it.
+        let rel = format!("patches/patch_{}.patch", patch_id);
+        let patch_file = self.registry.dir().join(&rel);
+        let recorded = std::fs::read(&patch_file).ok();
+        let restore = || {
+            if let Some(content) = &recorded {
+                let _ = std::fs::write(&patch_file, content);
+            }
+            reapply();
+        };
+        let commit_hash = if Self::env_truthy("PAGI_AUTO_COMMIT_SELF_PATCH", true) {
+            let message = revert_message(&applied.commit_hash);
+            match self.registry.revert(&applied.commit_hash, &[rel.clone()], &message).await {
+                Ok(hash) => hash,
+                Err(e) => {
+                    restore();
+                    let message = format!("{} registry revert: {}", self.registry.name(), e);
+                    return Err(Status::internal(message));
+                }
+            }
+        } else {
+            if recorded.is_some() {
+                if let Err(e) = std::fs::remove_file(&patch_file) {
+                    restore();
+                    return Err(Status::internal(format!("remove {}: {}", rel, e)));
+                }
+            }
+            String::new()
+        };
+        eprintln!("[Watchdog] {}: rolled back; tests {}", patch_id, test_result);
+        Ok(RollbackPatchResponse {
+            success: true,
+            commit_hash,
+            reverted_commit: applied.commit_hash.clone(),
+            files,
+            test_result,
+            target_commit,
         })
     }
 
-    /// Self-healing: RCA via L4 search, return proposed patch (stub code).
-    pub async fn propose_patch(
-        &self,
-        req: PatchRequest,
-    ) -> Result<PatchResponse, Status> {
-        let search_req = SearchRequest {
-            query: req.error_trace.clone(),
-            kb_name: "kb_core".to_string(),
-            limit: 5,
-            query_vector: vec![],
-        };
-        let prior = self
-            .memory
-            .semantic_search(search_req)
-            .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+    /// Revision proposed after patch_id's apply test failed, if any.
+    pub fn revised_by(&self, patch_id: &str) -> Option<String> {
+        self.catalog.revised_by(patch_id)
+    }
 
-        let proposed_code = format!(
-            "// Generic fix for: {}\n// Based on prior hits: {:?}",
-            req.error_trace
-                .lines()
-                .next()
-                .unwrap_or("")
-                .chars()
-                .take(200)
-                .collect::<String>(),
-            prior
-                .hits
-                .iter()
-                .map(|h| &h.content_snippet)
-                .take(2)
-                .collect::<Vec<_>>()
-        );
+    /// GetHealReport: lifecycle stats over retained patch timelines, stamped in the request's time zone.
+    pub fn heal_report(&self, req: &HealReportRequest) -> Result<HealReport, Status> {
+        let tz = clock::parse_timezone(&req.timezone).map_err(Status::invalid_argument)?;
+        let mut report =
+            heal_metrics::report(&self.catalog.timelines(), &req.component, req.since_unix.saturating_mul(1000));
+        report.generated_at = clock::format_in(self.clock.now(), tz);
+        Ok(report)
+    }
 
-        let requires_hitl = req.component == "rust_core";
-        let patch_id = Uuid::new_v4().to_string();
-        self.pending_patches.insert(
-            patch_id.clone(),
-            PendingPatch {
-                proposed_code: proposed_code.clone(),
-                requires_hitl,
-                component: req.component.clone(),
-            },
-        );
+    /// reasoning_id recorded with a pending patch (empty when unknown).
+    pub fn patch_reasoning_id(&self, patch_id: &str) -> String {
+        self.catalog
+            .get(patch_id)
+            .or_else(|| self.catalog.applied(patch_id).map(|a| a.patch))
+            .map(|p| p.reasoning_id)
+            .unwrap_or_default()
+    }
 
-        Ok(PatchResponse {
-            patch_id: patch_id.clone(),
-            proposed_code,
-            requires_hitl,
+    /// Latest HITL approval record for patch_id as "<outcome>: <detail>" (empty when none).
+    fn approval_summary(&self, patch_id: &str) -> String {
+        self.catalog
+            .approvals(patch_id)
+            .last()
+            .map(describe_approval)
+            .unwrap_or_default()
+    }
+
+    /// GetApplyStatus: queue state for patch_id ("pending" when proposed but not yet submitted,
+    /// "rejected" when dropped by an approval-timeout fallback) and its revision chain.
+    pub async fn apply_status(&self, patch_id: &str) -> Result<ApplyStatusResponse, Status> {
+        let approval = self.approval_summary(patch_id);
+        let revision_chain = self.catalog.revision_chain(patch_id);
+        if let Some(st) = self.apply_queue.status(patch_id) {
+            let (commit_hash, error) = match &st.state {
+                ApplyState::Applied { commit_hash } => (commit_hash.clone(), String::new()),
+                ApplyState::Failed { error } => (String::new(), error.clone()),
+                ApplyState::RolledBack { commit_hash } => (commit_hash.clone(), String::new()),
+                _ => (String::new(), String::new()),
+            };
+            return Ok(ApplyStatusResponse {
+                patch_id: patch_id.to_string(),
+                state: st.state.as_str().to_string(),
+                queue_position: st.queue_position,
+                target: st.target.display().to_string(),
+                commit_hash,
+                error,
+                approval,
+                revision_chain,
+            });
+        }
+        if let Some(p) = self.catalog.get(patch_id) {
+            return Ok(ApplyStatusResponse {
+                patch_id: patch_id.to_string(),
+                state: "pending".to_string(),
+                queue_position: 0,
+                target: self
+                    .components
+                    .get(&p.component)
+                    .map(|c| c.repo.display().to_string())
+                    .unwrap_or_default(),
+                commit_hash: String::new(),
+                error: String::new(),
+                approval,
+                revision_chain,
+            });
+        }
+        if approval.is_empty() {
+            return Err(Status::not_found("patch_id not found"));
+        }
+        Ok(ApplyStatusResponse {
+            patch_id: patch_id.to_string(),
+            state: "rejected".to_string(),
+            queue_position: 0,
+            target: String::new(),
+            commit_hash: String::new(),
+            error: String::new(),
+            approval,
+            revision_chain,
         })
     }
 
-    /// Path to HITL approve flag file (e.g. approve.patch in core dir). Presence enables apply for core patches.
-    fn approve_flag_path(&self) -> PathBuf {
-        let name = std::env::var("PAGI_APPROVE_FLAG").unwrap_or_else(|_| "approve.patch".into());
-        self.core_dir.join(name)
+    /// ListPendingPatches: patches awaiting ApplyPatch, oldest first, filtered by component and HITL gate.
+    pub fn list_pending(&self, req: &ListPendingPatchesRequest) -> ListPendingPatchesResponse {
+        let now_ms = self.clock.now_ms();
+        let mut pending: Vec<(String, PendingPatch, i64)> = self
+            .catalog
+            .pending()
+            .into_iter()
+            .filter(|(_, p, _)| req.component.is_empty() || p.component == req.component)
+            .filter(|(_, p, _)| !req.hitl_only || p.requires_hitl)
+            .collect();
+        pending.sort_by(|a, b| (a.2, &a.0).cmp(&(b.2, &b.0)));
+        let total = pending.len() as u32;
+        let limit = match req.limit {
+            0 => LIST_PENDING_DEFAULT,
+            n => n as usize,
+        };
+        let patches = pending
+            .into_iter()
+            .take(limit)
+            .map(|(patch_id, p, proposed_ms)| PendingPatchSummary {
+                patch_id,
+                preview: preview(&p.proposed_code),
+                proposed_at: proposed_ms / 1000,
+                age_secs: age_secs(proposed_ms, now_ms),
+                summary: p.impact.summary(),
+                component: p.component,
+                requires_hitl: p.requires_hitl,
+                reasoning_id: p.reasoning_id,
+                revision: p.revision,
+            })
+            .collect();
+        ListPendingPatchesResponse { patches, total }
     }
 
-    /// Check if HITL approve flag file exists (poll for human-in-the-loop).
-    fn hitl_approved_via_flag(&self) -> bool {
-        self.approve_flag_path().exists()
+    /// GetPatch: a pending patch, else an applied one (state "applied" or "rolled_back"), in full.
+    pub fn get_patch(&self, patch_id: &str) -> Result<PatchDetail, Status> {
+        let (state, patch, commit_hash) = match self.catalog.get(patch_id) {
+            Some(p) => ("pending", p, String::new()),
+            None => match self.catalog.applied(patch_id) {
+                Some(a) if a.rolled_back_ms.is_some() => ("rolled_back", a.patch, a.commit_hash),
+                Some(a) => ("applied", a.patch, a.commit_hash),
+                None => return Err(Status::not_found("patch_id is neither pending nor applied")),
+            },
+        };
+        let proposed_ms = self.catalog.proposed_ms(patch_id);
+        Ok(PatchDetail {
+            patch_id: patch_id.to_string(),
+            state: state.to_string(),
+            impact: Some(patch.impact.to_proto()),
+            component: patch.component,
+            requires_hitl: patch.requires_hitl,
+            proposed_code: patch.proposed_code,
+            diff: patch.diff,
+            error_trace: patch.error_trace,
+            reasoning_id: patch.reasoning_id,
+            revision_of: patch.revision_of,
+            revision: patch.revision,
+            proposed_at: proposed_ms / 1000,
+            age_secs: age_secs(proposed_ms, self.clock.now_ms()),
+            approvals: self.catalog.approvals(patch_id).iter().map(describe_approval).collect(),
+            commit_hash,
+        })
     }
 
-    /// Apply: HITL check (request approved or approve-flag file present), run tests, write patch to registry and commit.
-    pub async fn apply_patch(
+    /// Apply body (caller holds the repo lane): HITL check (request approved, a reviewer's approval on the HITL
+    /// channel, or approve-flag file present, waited for under the apply approval policy; a reviewer's rejection
+    /// overrides the flag), run tests, write patch to registry and commit. A failed test or smoke step leaves its
+    /// output in `test_failure`.
+    async fn apply_patch_locked(
         &self,
         req: ApplyRequest,
+        inject_test_failure: bool,
+        test_failure: &mut Option<String>,
     ) -> Result<ApplyResponse, Status> {
         let pending = self
-            .pending_patches
+            .catalog
             .get(&req.patch_id)
             .ok_or_else(|| Status::not_found("patch_id not found"))?;
+        let component = self.components.get(&pending.component)?;
+        let denial = |rule: &str| PolicyDenial {
+            rule: rule.to_string(),
+            subject: req.patch_id.clone(),
+            approval_channel: format!(
+                "{}, ApplyRequest.approved or the PAGI_APPROVE_FLAG file",
+                policy_denial::HITL_CHANNEL
+            ),
+            ..Default::default()
+        };
+
+        // With PAGI_HITL_APPLY_WAIT_SECS set, an apply with no approval yet waits for one under the approval
+        // policy (webhook reminders, timeout fallback), which records a rejection or timeout itself.
+        let undecided = self.hitl.decision(&req.patch_id).is_none() && !self.hitl_approved_via_flag();
+        if pending.requires_hitl && !req.approved && undecided && !self.apply_approval.wait.is_zero() {
+            if !self.await_approval(&req.patch_id, &self.apply_approval).await {
+                if let Some(d) = self.hitl.decision(&req.patch_id).filter(|d| !d.approved) {
+                    let message = format!("HITL reviewer {}", d.describe());
+                    return Err(policy_denial::status(Code::PermissionDenied, message, denial("hitl_rejected")));
+                }
+                let wait = self.apply_approval.wait.as_secs();
+                let detail = PolicyDenial {
+                    setting: "PAGI_HITL_APPLY_WAIT_SECS".to_string(),
+                    current: wait as f64,
+                    limit: wait as f64,
+                    ..denial("hitl_timeout")
+                };
+                return Err(policy_denial::status(
+                    Code::PermissionDenied,
+                    format!(
+                        "HITL approval for patch {} not given within {}s (fallback: {})",
+                        req.patch_id,
+                        wait,
+                        self.apply_approval.fallback.as_str()
+                    ),
+                    detail,
+                ));
+            }
+        }
 
-        let approved = req.approved || (pending.requires_hitl && self.hitl_approved_via_flag());
+        let reviewed = if pending.requires_hitl && !req.approved {
+            self.hitl.decision(&req.patch_id)
+        } else {
+            None
+        };
+        let via_channel = reviewed.as_ref().is_some_and(|d| d.approved);
+        let via_flag = !req.approved && pending.requires_hitl && reviewed.is_none() && self.hitl_approved_via_flag();
+        let approved = req.approved || via_channel || via_flag;
+        if pending.requires_hitl {
+            if approved {
+                let via = match &reviewed {
+                    Some(d) if d.approved => format!("HITL channel ({})", d.reviewer),
+                    _ if via_flag => "approve flag".to_string(),
+                    _ => "request".to_string(),
+                };
+                self.catalog
+                    .record_approval(&req.patch_id, ApprovalOutcome::Approved, format!("via {}", via));
+                self.catalog.mark(&req.patch_id, Stage::Approved);
+            } else {
+                let detail = match &reviewed {
+                    Some(d) => format!("via HITL channel: {}", d.describe()),
+                    None => "apply without approval".to_string(),
+                };
+                self.catalog.record_approval(&req.patch_id, ApprovalOutcome::Denied, detail);
+            }
+        }
+        if let Some(d) = reviewed.as_ref().filter(|d| !d.approved) {
+            let message = format!("HITL reviewer {}", d.describe());
+            return Err(policy_denial::status(Code::PermissionDenied, message, denial("hitl_rejected")));
+        }
         if pending.requires_hitl && !approved {
-            return Err(Status::permission_denied(
-                "HITL approval required for this patch (set approved or create PAGI_APPROVE_FLAG file)",
+            return Err(policy_denial::status(
+                Code::PermissionDenied,
+                "HITL approval required for this patch (set approved, approve it on HitlChannel or create \
+                 PAGI_APPROVE_FLAG file)",
+                denial("hitl_gate"),
             ));
         }
 
-        let force_fail = std::env::var("PAGI_FORCE_TEST_FAIL")
-            .ok()
-            .map_or(false, |v| v.to_lowercase() == "true" || v == "1");
+        let force_fail = inject_test_failure
+            || std::env::var("PAGI_FORCE_TEST_FAIL")
+                .ok()
+                .map_or(false, |v| v.to_lowercase() == "true" || v == "1");
         if force_fail {
+            *test_failure = Some("Forced test failure for verification".to_string());
             return Err(Status::internal(
                 "Forced test failure for verification",
             ));
         }
+        self.snapshot_before_apply(&req.patch_id).await?;
+
+        // The diff goes into the component repo first so the test and smoke steps run against the patched tree;
+        // any later failure reverts it.
+        if !pending.diff.is_empty() {
+            let files = patch_diff::apply(&component.repo, &pending.diff, false)
+                .map_err(|e| Status::failed_precondition(format!("patch {} does not apply: {}", req.patch_id, e)))?;
+            eprintln!("[Watchdog] {}: patched {}", req.patch_id, files.join(", "));
+        }
+        let result = self.verify_and_record(&req, &pending, component, test_failure).await;
+        if result.is_err() && !pending.diff.is_empty() {
+            match patch_diff::apply(&component.repo, &pending.diff, true) {
+                Ok(files) => eprintln!("[Watchdog] {}: reverted {}", req.patch_id, files.join(", ")),
+                Err(e) => eprintln!("[Watchdog] {}: revert failed, tree left patched: {}", req.patch_id, e),
+            }
+        }
+        result
+    }
+
+    /// The component's repo for apply and revert commits, when its dir is a git repo root. A crate inside a larger
+    /// checkout (the default core dir) gets none, so self-patches never commit to the surrounding project.
+    fn target_repo(&self, component: &Component) -> Option<GitRegistry> {
+        Repository::open(&component.repo).ok()?;
+        Some(GitRegistry::new(component.repo.clone(), self.clock.clone()))
+    }
 
+    /// Test and smoke steps of an apply, then the registry record and commit.
+    async fn verify_and_record(
+        &self,
+        req: &ApplyRequest,
+        pending: &PendingPatch,
+        component: &Component,
+        test_failure: &mut Option<String>,
+    ) -> Result<ApplyResponse, Status> {
         // Skip test step when set (e.g. test_apply_patch_auto_commit); not for production.
-        let skip_apply_test = std::env::var("PAGI_SKIP_APPLY_TEST")
-            .ok()
-            .map_or(false, |v| v.to_lowercase() == "true" || v == "1");
-
-        // Run tests (generic: cargo test or pytest)
-        let test_ok = if skip_apply_test {
-            true
-        } else if pending.component == "rust_core" {
-            StdCommand::new("cargo")
-                .args(["test"])
-                .current_dir(&self.core_dir)
-                .output()
-                .map(|o| o.status.success())
-                .unwrap_or(false)
+        // Components without a test command skip it too.
+        let skip_apply_test = component.test.is_empty()
+            || std::env::var("PAGI_SKIP_APPLY_TEST")
+                .ok()
+                .map_or(false, |v| v.to_lowercase() == "true" || v == "1");
+
+        // Run the component's test step from its repo (the runner's cwd is relative to it)
+        let test_dir = component.repo.as_path();
+        let test_label = component.test_label();
+        if !skip_apply_test {
+            if let Err(output) = test_runner::run(&component.test, test_dir).await {
+                *test_failure = Some(output);
+                return Err(Status::internal("Patch test failed; apply aborted"));
+            }
+        }
+        let mut test_result = if skip_apply_test {
+            format!("skipped: {}", test_label)
         } else {
-            StdCommand::new("poetry")
-                .args(["run", "pytest", "tests/", "-v"])
-                .current_dir(&self.bridge_dir)
-                .output()
-                .map(|o| o.status.success())
-                .unwrap_or(false)
+            format!("passed: {}", test_label)
         };
 
-        if !test_ok {
-            return Err(Status::internal("Patch test failed; apply aborted"));
+        let ext = component.extension.as_str();
+
+        // Smoke commands catch runtime failures the test suite misses; they run even when tests are skipped.
+        let smoke_commands = self.smoke.for_component(&pending.component);
+        if !smoke_commands.is_empty() {
+            let (sandbox, patch_file) = smoke::prepare_sandbox(&req.patch_id, ext, &pending.proposed_code)
+                .map_err(|e| Status::internal(format!("smoke sandbox: {}", e)))?;
+            let outcome = smoke::run(&smoke_commands, test_dir, &sandbox, &patch_file, self.smoke.timeout).await;
+            let _ = std::fs::remove_dir_all(&sandbox);
+            match outcome {
+                Ok(passed) => test_result.push_str(&format!("; smoke passed: {}", passed.join(", "))),
+                Err(e) => {
+                    let message = format!("Patch smoke test failed; apply aborted: {}", e);
+                    *test_failure = Some(e);
+                    return Err(Status::internal(message));
+                }
+            }
         }
+        self.catalog.mark(&req.patch_id, Stage::Verified);
 
-        // Write proposed code to registry and commit
-        let ext = if pending.component == "rust_core" {
-            "rs"
-        } else {
-            "py"
-        };
-        let patches_dir = self.registry_path.join("patches");
+        // Record the patch in the registry as a git format-patch file and version it: its diff against the
+        // component, or the proposed code as a new file when there is none.
+        let patches_dir = self.registry.dir().join("patches");
         std::fs::create_dir_all(&patches_dir).map_err(|e| {
             Status::internal(format!("create patches dir: {}", e))
         })?;
-        let patch_file = patches_dir.join(format!("patch_{}.{}", req.patch_id, ext));
-        std::fs::write(&patch_file, &pending.proposed_code).map_err(|e| {
+        let meta = PatchMetadata {
+            patch_id: req.patch_id.clone(),
+            component: pending.component.clone(),
+            reasoning_id: pending.reasoning_id.clone(),
+            test_result,
+        };
+        let diff = if pending.diff.is_empty() {
+            patch_format::new_file_diff(&format!("patch_{}.{}", req.patch_id, ext), &pending.proposed_code)
+        } else {
+            pending.diff.clone()
+        };
+        let patch_text = patch_format::format_patch(
+            &meta,
+            &format!("Self-patch apply {} for {}", req.patch_id, pending.component),
+            &patch_format::description(&pending.error_trace, &pending.revision_of),
+            &diff,
+            &self.clock,
+        );
+        let patch_file = patches_dir.join(format!("patch_{}.patch", req.patch_id));
+        std::fs::write(&patch_file, &patch_text).map_err(|e| {
             Status::internal(format!("write patch file: {}", e))
         })?;
 
         let auto_commit = Self::env_truthy("PAGI_AUTO_COMMIT_SELF_PATCH", true);
 
+        let msg = format!("Self-patch apply {} for {}", req.patch_id, pending.component);
         let commit_hash = if auto_commit {
-            let repo = self.open_repo().map_err(|e| {
-                Status::internal(format!("open repo: {}", e))
-            })?;
-            let mut index = repo.index().map_err(|e| {
-                Status::internal(format!("index: {}", e))
-            })?;
-            let rel = format!("patches/patch_{}.{}", req.patch_id, ext);
-            index
-                .add_path(std::path::Path::new(&rel))
-                .map_err(|e| Status::internal(format!("add_path: {}", e)))?;
-            index.write().map_err(|e| Status::internal(format!("index write: {}", e)))?;
-            let tree_id = index.write_tree().map_err(|e| Status::internal(format!("write_tree: {}", e)))?;
-            let tree = repo.find_tree(tree_id).map_err(|e| Status::internal(format!("find_tree: {}", e)))?;
-            let head = repo.head();
-            let parent = match head {
-                Ok(r) => {
-                    let head_commit = r.peel_to_commit().map_err(|e| Status::internal(e.to_string()))?;
-                    vec![head_commit]
-                }
-                Err(_) => vec![],
-            };
-            let sig = Signature::now("Sovereign Architect", "agi@core")
-                .map_err(|e| Status::internal(e.to_string()))?;
-            let msg = format!("Self-patch apply {} for {}", req.patch_id, pending.component);
-            let commit = repo
-                .commit(
-                    Some("HEAD"),
-                    &sig,
-                    &sig,
-                    &msg,
-                    &tree,
-                    parent.iter().collect::<Vec<_>>().as_slice(),
-                )
-                .map_err(|e| Status::internal(e.to_string()))?;
-            let hash = repo
-                .find_commit(commit)
-                .map_err(|e| Status::internal(e.to_string()))?
-                .id()
-                .to_string();
-            hash
+            let rel = format!("patches/patch_{}.patch", req.patch_id);
+            self.registry
+                .commit(&[rel], &msg)
+                .await
+                .map_err(|e| Status::internal(format!("{} registry commit: {}", self.registry.name(), e)))?
         } else {
             String::new()
         };
+        let target_commit = match self.target_repo(component) {
+            Some(target) if auto_commit && !pending.diff.is_empty() => {
+                let committed = patch_diff::files(&pending.diff)
+                    .and_then(|files| target.commit_paths(&files, &msg).map_err(|e| e.to_string()));
+                // Best-effort: without it a rollback reverse-applies the diff instead of reverting.
+                committed.unwrap_or_else(|e| {
+                    eprintln!("[Watchdog] {}: commit in {}: {}", req.patch_id, component.repo.display(), e);
+                    String::new()
+                })
+            }
+            _ => String::new(),
+        };
 
-        // Auto-evolve: after python_skill apply *and* auto-commit, propose and persist a new skill from the patch.
-        // Gate: PAGI_AUTO_EVOLVE_SKILLS=true.
+        // Auto-evolve: after a skill-evolving component's apply (python_skill) *and* auto-commit, propose and
+        // persist a new skill from the patch. Gate: PAGI_AUTO_EVOLVE_SKILLS=true.
         let auto_evolve = Self::env_truthy("PAGI_AUTO_EVOLVE_SKILLS", false);
-        if auto_commit && auto_evolve && pending.component == "python_skill" {
+        if auto_commit && auto_evolve && component.evolves_skills {
             // Best-effort: if evolution fails, do not fail the patch apply.
-            let _ = self.propose_new_skill_from_patch(&patch_file).await;
+            let _ = self.propose_new_skill_from_patch(&pending.proposed_code).await;
         }
 
-        self.pending_patches.remove(&req.patch_id);
+        self.catalog.remove(&req.patch_id);
+        self.hitl.withdraw(&req.patch_id, "applied");
 
         Ok(ApplyResponse {
             success: true,
             commit_hash,
+            target_commit,
         })
     }
 
@@ -619,75 +1921,28 @@ impl Watchdog {
     pub fn propose_heal(&self, _error_trace: &str) -> (String, bool) {
         (String::new(), false)
     }
-
-    /// Simulation: run propose → optionally poll for HITL approve flag → apply. With PAGI_FORCE_TEST_FAIL use approved=true to hit force_fail path.
-    pub async fn simulate_error(&self) -> Result<crate::proto::pagi_proto::Empty, Status> {
-        let error_trace = "Simulated Rust error for verification".to_string();
-        let component = "rust_core".to_string();
-        let req = PatchRequest {
-            error_trace: error_trace.clone(),
-            component: component.clone(),
-        };
-        let propose_resp = self.propose_patch(req).await?;
-
-        let force_fail = std::env::var("PAGI_FORCE_TEST_FAIL")
-            .ok()
-            .map_or(false, |v| v.to_lowercase() == "true" || v == "1");
-        let mut approved = force_fail; // When forcing fail, pass HITL so apply_patch hits the force_fail return
-
-        // When HITL required and not force_fail, poll for approve flag file (e.g. approve.patch) before apply.
-        if propose_resp.requires_hitl && !approved {
-            let poll_secs: u64 = std::env::var("PAGI_HITL_POLL_SECS")
-                .ok()
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(30);
-            let step = std::time::Duration::from_secs(1);
-            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(poll_secs);
-            while std::time::Instant::now() < deadline {
-                if self.hitl_approved_via_flag() {
-                    approved = true;
-                    break;
-                }
-                tokio::time::sleep(step).await;
-            }
-        }
-
-        let apply_req = ApplyRequest {
-            patch_id: propose_resp.patch_id,
-            approved,
-            component: component.clone(),
-            requires_hitl: propose_resp.requires_hitl,
-        };
-        let _apply_result = self.apply_patch(apply_req).await;
-        // Expected: Err(permission_denied) when !approved, or Err(internal) when force_fail. We do not surface it; simulation succeeded.
-
-        let log_path = std::env::var("PAGI_SELF_HEAL_LOG").unwrap_or_else(|_| "agent_actions.log".into());
-        if let Ok(mut f) = std::fs::OpenOptions::new().append(true).create(true).open(&log_path) {
-            let _ = writeln!(f, "Heal cycle simulated");
-        }
-
-        Ok(crate::proto::pagi_proto::Empty {})
-    }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use crate::clock::ManualClock;
     use crate::proto::pagi_proto::{ActionRequest, ApplyRequest, PatchRequest};
     use std::collections::HashMap;
     use std::fs;
     use std::path::PathBuf;
-    use std::sync::{Mutex, OnceLock};
+    use std::sync::OnceLock;
 
-    static TEST_ENV_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    static TEST_ENV_LOCK: OnceLock<tokio::sync::Mutex<()>> = OnceLock::new();
 
-    fn lock_test_env() -> std::sync::MutexGuard<'static, ()> {
-        // Tests in this module mutate global process env vars.
+    pub(crate) async fn lock_test_env() -> tokio::sync::MutexGuard<'static, ()> {
+        // Tests across the crate mutate global process env vars.
         // `cargo test` runs tests in parallel by default, so serialize to avoid cross-test interference/hangs.
+        // Async-aware mutex: the guard is held across awaits inside #[tokio::test] bodies.
         TEST_ENV_LOCK
-            .get_or_init(|| Mutex::new(()))
+            .get_or_init(|| tokio::sync::Mutex::new(()))
             .lock()
-            .expect("lock TEST_ENV_LOCK")
+            .await
     }
 
     fn temp_bridge_dir(skills: &[&str], run_script_sleep: bool) -> PathBuf {
@@ -711,7 +1966,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_execute_action_unknown_skill() {
-        let _g = lock_test_env();
+        let _g = lock_test_env().await;
         std::env::set_var("PAGI_DISABLE_QDRANT", "1");
         let temp = temp_bridge_dir(&["peek_file"], false);
         let registry = temp.join("registry");
@@ -727,6 +1982,7 @@ mod tests {
             mock_mode: false,
             allow_list_hash: String::new(),
             timeout_ms: 5000,
+            caller_skills: Vec::new(),
         };
         let result = watchdog.execute_action_real(req).await;
         assert!(result.is_err());
@@ -739,7 +1995,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_execute_action_timeout() {
-        let _g = lock_test_env();
+        let _g = lock_test_env().await;
         std::env::set_var("PAGI_DISABLE_QDRANT", "1");
         let temp = temp_bridge_dir(&["peek_file", "sleep"], true);
         let registry = temp.join("registry");
@@ -755,19 +2011,22 @@ mod tests {
             mock_mode: false,
             allow_list_hash: String::new(),
             timeout_ms: 50,
+            caller_skills: Vec::new(),
         };
         let result = watchdog.execute_action_real(req).await;
         assert!(result.is_ok());
         let resp = result.unwrap();
         assert!(!resp.success);
         assert!(resp.error.contains("Execution timed out"));
+        assert_eq!(resp.metadata["env.allow_list_hash"].len(), 64);
+        assert!(resp.metadata.contains_key("env.python_version"));
         let _ = fs::remove_dir_all(temp);
         std::env::remove_var("PAGI_DISABLE_QDRANT");
     }
 
     #[tokio::test]
     async fn test_apply_patch_auto_commit() {
-        let _g = lock_test_env();
+        let _g = lock_test_env().await;
         // When PAGI_AUTO_COMMIT_SELF_PATCH=false, apply_patch succeeds but returns empty commit_hash (no git commit).
         std::env::set_var("PAGI_AUTO_COMMIT_SELF_PATCH", "false");
         let temp_registry = std::env::temp_dir().join(format!("pagi_apply_test_{}", uuid::Uuid::new_v4()));
@@ -790,6 +2049,7 @@ mod tests {
             .propose_patch(PatchRequest {
                 error_trace: "test apply_patch auto_commit".to_string(),
                 component: "rust_core".to_string(),
+                reasoning_id: String::new(),
             })
             .await
             .unwrap();
@@ -813,9 +2073,88 @@ mod tests {
         std::env::remove_var("PAGI_DISABLE_QDRANT");
     }
 
+    /// The self-heal tests' failing function, in a scratch core tree's `src/watchdog.rs`.
+    const SCRATCH_SOURCE: &str = "fn apply_patch_locked() {\n    todo!()\n}\n";
+
+    /// Watchdog whose core dir is a scratch tree holding SCRATCH_SOURCE, so proposed diffs never touch this
+    /// crate; returns it with the temp root (registry repo in `registry/`, core tree in `core/`).
+    async fn scratch_core_watchdog() -> (Watchdog, PathBuf) {
+        std::env::set_var("PAGI_DISABLE_QDRANT", "true");
+        std::env::set_var("PAGI_SKIP_APPLY_TEST", "true");
+        let temp = std::env::temp_dir().join(format!("pagi_scratch_core_{}", uuid::Uuid::new_v4()));
+        let (registry, core_dir) = (temp.join("registry"), temp.join("core"));
+        fs::create_dir_all(core_dir.join("src")).unwrap();
+        fs::write(core_dir.join("src/watchdog.rs"), SCRATCH_SOURCE).unwrap();
+        fs::create_dir_all(&registry).unwrap();
+        let _ = Repository::init(&registry);
+        let memory = MemoryManager::new_async().await.unwrap();
+        (Watchdog::new(registry, memory, core_dir, temp.clone()), temp)
+    }
+
+    fn scratch_failure() -> PatchRequest {
+        PatchRequest {
+            error_trace: "panicked at src/watchdog.rs:2:5 in pagi::watchdog::Watchdog::apply_patch_locked".to_string(),
+            component: "rust_core".to_string(),
+            reasoning_id: "r1".to_string(),
+        }
+    }
+
+    fn approve(proposal: &PatchResponse) -> ApplyRequest {
+        ApplyRequest {
+            patch_id: proposal.patch_id.clone(),
+            approved: true,
+            component: "rust_core".to_string(),
+            requires_hitl: proposal.requires_hitl,
+        }
+    }
+
+    fn clear_scratch_env(temp: PathBuf) {
+        let _ = fs::remove_dir_all(temp);
+        std::env::remove_var("PAGI_AUTO_COMMIT_SELF_PATCH");
+        std::env::remove_var("PAGI_SKIP_APPLY_TEST");
+        std::env::remove_var("PAGI_DISABLE_QDRANT");
+    }
+
+    #[tokio::test]
+    async fn test_propose_patch_reports_the_impact_of_the_failing_file() {
+        let _g = lock_test_env().await;
+        let (watchdog, temp) = scratch_core_watchdog().await;
+        let impact = watchdog.propose_patch(scratch_failure()).await.unwrap().impact.unwrap();
+        assert_eq!(impact.files, vec!["src/watchdog.rs"]);
+        assert!(impact.references.iter().any(|r| r.starts_with("src/watchdog.rs:")));
+        clear_scratch_env(temp);
+    }
+
+    #[tokio::test]
+    async fn test_apply_status_moves_from_pending_to_applied() {
+        let _g = lock_test_env().await;
+        std::env::set_var("PAGI_AUTO_COMMIT_SELF_PATCH", "false");
+        let (watchdog, temp) = scratch_core_watchdog().await;
+        let proposal = watchdog.propose_patch(scratch_failure()).await.unwrap();
+        assert_eq!(watchdog.apply_status(&proposal.patch_id).await.unwrap().state, "pending");
+        watchdog.apply_patch(approve(&proposal)).await.unwrap();
+        let status = watchdog.apply_status(&proposal.patch_id).await.unwrap();
+        assert_eq!((status.state.as_str(), status.queue_position), ("applied", 0));
+        clear_scratch_env(temp);
+    }
+
+    #[tokio::test]
+    async fn test_apply_patch_applies_the_proposed_diff_to_the_core_tree() {
+        let _g = lock_test_env().await;
+        std::env::set_var("PAGI_AUTO_COMMIT_SELF_PATCH", "false");
+        let (watchdog, temp) = scratch_core_watchdog().await;
+        let proposal = watchdog.propose_patch(scratch_failure()).await.unwrap();
+        assert!(proposal.diff.starts_with("diff --git a/src/watchdog.rs b/src/watchdog.rs"));
+        watchdog.apply_patch(approve(&proposal)).await.unwrap();
+        let patched = fs::read_to_string(temp.join("core/src/watchdog.rs")).unwrap();
+        assert!(patched.contains("\n    // pagi self-heal: panicked at src/watchdog.rs:2:5"), "{}", patched);
+        assert!(patched.ends_with("\n    todo!()\n}\n") && patched != SCRATCH_SOURCE);
+        clear_scratch_env(temp);
+    }
+
     #[tokio::test]
     async fn test_apply_patch_auto_commit_when_enabled() {
-        let _g = lock_test_env();
+        let _g = lock_test_env().await;
         // When PAGI_AUTO_COMMIT_SELF_PATCH=true (default), apply_patch commits and returns non-empty commit_hash.
         std::env::set_var("PAGI_AUTO_COMMIT_SELF_PATCH", "true");
         let temp_registry = std::env::temp_dir().join(format!("pagi_apply_commit_{}", uuid::Uuid::new_v4()));
@@ -838,6 +2177,7 @@ mod tests {
             .propose_patch(PatchRequest {
                 error_trace: "test apply_patch auto_commit when enabled".to_string(),
                 component: "rust_core".to_string(),
+                reasoning_id: String::new(),
             })
             .await
             .unwrap();
@@ -861,6 +2201,340 @@ mod tests {
         std::env::remove_var("PAGI_DISABLE_QDRANT");
     }
 
+    #[tokio::test]
+    async fn test_applied_patches_are_stored_with_metadata_headers() {
+        let _g = lock_test_env().await;
+        std::env::set_var("PAGI_AUTO_COMMIT_SELF_PATCH", "true");
+        let (watchdog, temp) = scratch_core_watchdog().await;
+        let proposal = watchdog.propose_patch(scratch_failure()).await.unwrap();
+        watchdog.apply_patch(approve(&proposal)).await.unwrap();
+        let stored = temp.join("registry/patches").join(format!("patch_{}.patch", proposal.patch_id));
+        let meta = patch_format::parse_metadata(&fs::read_to_string(stored).unwrap()).unwrap();
+        assert_eq!(meta.patch_id, proposal.patch_id);
+        assert_eq!((meta.component.as_str(), meta.reasoning_id.as_str()), ("rust_core", "r1"));
+        assert!(meta.test_result.starts_with("skipped"));
+        clear_scratch_env(temp);
+    }
+
+    #[tokio::test]
+    async fn test_rollback_patch_restores_the_tree_and_reverts_the_registry() {
+        let _g = lock_test_env().await;
+        std::env::set_var("PAGI_DISABLE_QDRANT", "true");
+        std::env::set_var("PAGI_SKIP_APPLY_TEST", "true");
+        let temp = std::env::temp_dir().join(format!("pagi_rollback_{}", uuid::Uuid::new_v4()));
+        let (registry, core_dir) = (temp.join("registry"), temp.join("core"));
+        fs::create_dir_all(core_dir.join("src")).unwrap();
+        fs::create_dir_all(&registry).unwrap();
+        let _ = Repository::init(&registry);
+        let source = "fn main() {\n    run();\n}\n";
+        fs::write(core_dir.join("src/main.rs"), source).unwrap();
+        let memory = MemoryManager::new_async().await.unwrap();
+        let watchdog = Watchdog::new(registry.clone(), memory, core_dir.clone(), temp.clone());
+        let patch_id = watchdog
+            .propose_patch(PatchRequest {
+                error_trace: "panicked at src/main.rs:2:5".to_string(),
+                component: "rust_core".to_string(),
+                reasoning_id: "r1".to_string(),
+            })
+            .await
+            .unwrap()
+            .patch_id;
+        let rollback = |reason: &str| {
+            watchdog.rollback_patch(RollbackPatchRequest {
+                patch_id: patch_id.clone(),
+                reason: reason.to_string(),
+            })
+        };
+        assert_eq!(rollback("").await.unwrap_err().code(), tonic::Code::NotFound, "not applied yet");
+        let applied = watchdog
+            .apply_patch(ApplyRequest {
+                patch_id: patch_id.clone(),
+                approved: true,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_ne!(fs::read_to_string(core_dir.join("src/main.rs")).unwrap(), source);
+
+        let resp = rollback("broke startup").await.unwrap();
+        assert_eq!(resp.files, ["src/main.rs"]);
+        assert_eq!(resp.reverted_commit, applied.commit_hash);
+        assert_eq!(fs::read_to_string(core_dir.join("src/main.rs")).unwrap(), source);
+        assert!(!registry.join(format!("patches/patch_{}.patch", patch_id)).exists());
+        let head = Repository::open(&registry).unwrap().head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.id().to_string(), resp.commit_hash);
+        assert!(head.message().unwrap().contains(&format!("This reverts commit {}.", applied.commit_hash)));
+        let status = watchdog.apply_status(&patch_id).await.unwrap();
+        assert_eq!((status.state.as_str(), status.commit_hash), ("rolled_back", resp.commit_hash));
+        assert_eq!(rollback("").await.unwrap_err().code(), tonic::Code::FailedPrecondition);
+        let _ = fs::remove_dir_all(temp);
+        std::env::remove_var("PAGI_SKIP_APPLY_TEST");
+        std::env::remove_var("PAGI_DISABLE_QDRANT");
+    }
+
+    #[tokio::test]
+    async fn test_rollback_patch_reverts_the_apply_commits_in_both_repos() {
+        let _g = lock_test_env().await;
+        std::env::set_var("PAGI_AUTO_COMMIT_SELF_PATCH", "true");
+        let (watchdog, temp) = scratch_core_watchdog().await;
+        let core = GitRegistry::new(temp.join("core"), watchdog.clock.clone());
+        core.commit_paths(&["src/watchdog.rs".to_string()], "initial").unwrap();
+        let proposal = watchdog.propose_patch(scratch_failure()).await.unwrap();
+        let applied = watchdog.apply_patch(approve(&proposal)).await.unwrap();
+        assert!(!applied.target_commit.is_empty(), "the core dir is a repo root, so the apply is committed there");
+
+        let resp = watchdog
+            .rollback_patch(RollbackPatchRequest {
+                patch_id: proposal.patch_id.clone(),
+                reason: String::new(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(fs::read_to_string(temp.join("core/src/watchdog.rs")).unwrap(), SCRATCH_SOURCE);
+        for (dir, revert, reverted) in [
+            ("core", &resp.target_commit, &applied.target_commit),
+            ("registry", &resp.commit_hash, &applied.commit_hash),
+        ] {
+            let head = Repository::open(temp.join(dir)).unwrap().head().unwrap().peel_to_commit().unwrap();
+            assert_eq!(&head.id().to_string(), revert, "{}", dir);
+            assert_eq!(&head.parent_id(0).unwrap().to_string(), reverted, "{}", dir);
+            assert!(head.message().unwrap().contains(&format!("This reverts commit {}.", reverted)));
+        }
+        clear_scratch_env(temp);
+    }
+
+    #[tokio::test]
+    async fn test_apply_patch_waits_for_approval_under_the_apply_policy() {
+        let _g = lock_test_env().await;
+        std::env::set_var("PAGI_HITL_APPLY_WAIT_SECS", "5");
+        std::env::set_var("PAGI_HITL_POLL_INTERVAL_MS", "10");
+        let (watchdog, temp) = scratch_core_watchdog().await;
+        std::env::remove_var("PAGI_HITL_APPLY_WAIT_SECS");
+        std::env::remove_var("PAGI_HITL_POLL_INTERVAL_MS");
+        assert_eq!(watchdog.apply_approval.poll_interval, std::time::Duration::from_millis(10));
+        let proposal = watchdog.propose_patch(scratch_failure()).await.unwrap();
+        assert!(proposal.requires_hitl);
+        let flag = watchdog.approve_flag_path();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            fs::write(flag, "").unwrap();
+        });
+        let request = ApplyRequest {
+            approved: false,
+            ..approve(&proposal)
+        };
+        assert!(watchdog.apply_patch(request).await.unwrap().success);
+        let records = watchdog.catalog.approvals(&proposal.patch_id);
+        assert!(matches!(records.last().map(|r| &r.outcome), Some(ApprovalOutcome::Approved)));
+        clear_scratch_env(temp);
+    }
+
+    #[tokio::test]
+    async fn test_apply_patch_denies_after_the_apply_wait_elapses() {
+        let _g = lock_test_env().await;
+        std::env::set_var("PAGI_HITL_APPLY_WAIT_SECS", "1");
+        std::env::set_var("PAGI_HITL_POLL_INTERVAL_MS", "10");
+        let (watchdog, temp) = scratch_core_watchdog().await;
+        std::env::remove_var("PAGI_HITL_APPLY_WAIT_SECS");
+        std::env::remove_var("PAGI_HITL_POLL_INTERVAL_MS");
+        let proposal = watchdog.propose_patch(scratch_failure()).await.unwrap();
+        let request = ApplyRequest {
+            approved: false,
+            ..approve(&proposal)
+        };
+        let err = watchdog.apply_patch(request).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+        let denial = policy_denial::of(&err).unwrap();
+        assert_eq!((denial.rule.as_str(), denial.setting.as_str()), ("hitl_timeout", "PAGI_HITL_APPLY_WAIT_SECS"));
+        let records = watchdog.catalog.approvals(&proposal.patch_id);
+        assert_eq!(records.len(), 1, "the timeout is recorded once");
+        assert!(matches!(&records[0].outcome, ApprovalOutcome::TimedOut { fallback, .. } if fallback == "deny"));
+        assert!(watchdog.catalog.get(&proposal.patch_id).is_some(), "deny keeps the patch pending");
+        clear_scratch_env(temp);
+    }
+
+    #[tokio::test]
+    async fn test_await_approval_timeout_records_fallback() {
+        let _g = lock_test_env().await;
+        std::env::set_var("PAGI_DISABLE_QDRANT", "true");
+        let temp = std::env::temp_dir().join(format!("pagi_approval_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&temp).unwrap();
+        let memory = MemoryManager::new_async().await.unwrap();
+        // core_dir = temp: no approve flag present there.
+        let watchdog = Watchdog::new(temp.join("registry"), memory, temp.clone(), temp.clone());
+        let propose_resp = watchdog
+            .propose_patch(PatchRequest {
+                error_trace: "approval timeout".to_string(),
+                component: "rust_core".to_string(),
+                reasoning_id: "r1".to_string(),
+            })
+            .await
+            .unwrap();
+        let policy = ApprovalPolicy {
+            wait: std::time::Duration::from_millis(30),
+            poll_interval: std::time::Duration::from_millis(10),
+            webhook_url: None,
+            reminder_interval: std::time::Duration::ZERO,
+            fallback: TimeoutFallback::Reject,
+            action_tiers: Vec::new(),
+        };
+        assert!(!watchdog.await_approval(&propose_resp.patch_id, &policy).await);
+        let records = watchdog.catalog.approvals(&propose_resp.patch_id);
+        assert_eq!(records.len(), 1);
+        assert!(matches!(
+            &records[0].outcome,
+            ApprovalOutcome::TimedOut { fallback, .. } if fallback == "reject"
+        ));
+        // Reject fallback drops the pending patch; status reports it as rejected.
+        let status = watchdog.apply_status(&propose_resp.patch_id).await.unwrap();
+        assert_eq!(status.state, "rejected");
+        assert!(status.approval.starts_with("timed_out"));
+        let _ = fs::remove_dir_all(temp);
+        std::env::remove_var("PAGI_DISABLE_QDRANT");
+    }
+
+    #[tokio::test]
+    async fn test_failed_apply_test_re_proposes_a_bounded_revision_chain() {
+        let _g = lock_test_env().await;
+        std::env::set_var("PAGI_DISABLE_QDRANT", "true");
+        std::env::set_var("PAGI_PATCH_MAX_REVISIONS", "1");
+        let temp = std::env::temp_dir().join(format!("pagi_revision_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&temp).unwrap();
+        let memory = MemoryManager::new_async().await.unwrap();
+        let watchdog = Watchdog::new(temp.join("registry"), memory, temp.clone(), temp.clone());
+        std::env::remove_var("PAGI_PATCH_MAX_REVISIONS");
+        let original = watchdog
+            .propose_patch(PatchRequest {
+                error_trace: "panicked at src/lib.rs:7".to_string(),
+                component: "rust_core".to_string(),
+                reasoning_id: "r1".to_string(),
+            })
+            .await
+            .unwrap()
+            .patch_id;
+        let apply = |patch_id: &str| {
+            watchdog.apply_patch_with_options(
+                ApplyRequest {
+                    patch_id: patch_id.to_string(),
+                    approved: true,
+                    ..Default::default()
+                },
+                true,
+            )
+        };
+
+        let err = apply(&original).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Internal);
+        let revised = watchdog.revised_by(&original).expect("revision proposed");
+        assert!(err.message().ends_with(&format!("revised as patch {} (revision 1)", revised)), "{}", err.message());
+        assert!(watchdog.catalog.get(&original).is_none(), "superseded patch leaves the catalog");
+        let pending = watchdog.catalog.get(&revised).unwrap();
+        assert_eq!((pending.revision_of.as_str(), pending.revision), (original.as_str(), 1));
+        assert_eq!(pending.error_trace, "panicked at src/lib.rs:7");
+        assert!(pending.proposed_code.starts_with(&format!("// Revision 1 of patch {}", original)));
+        let status = watchdog.apply_status(&original).await.unwrap();
+        assert_eq!(status.state, "failed");
+        assert_eq!(status.revision_chain, [original.clone(), revised.clone()]);
+
+        // The chain is at PAGI_PATCH_MAX_REVISIONS: the revision's failure is reported as is.
+        let err = apply(&revised).await.unwrap_err();
+        assert_eq!(err.message(), "Forced test failure for verification");
+        assert!(watchdog.revised_by(&revised).is_none());
+        assert_eq!(watchdog.apply_status(&revised).await.unwrap().revision_chain, [original, revised]);
+        let _ = fs::remove_dir_all(temp);
+        std::env::remove_var("PAGI_DISABLE_QDRANT");
+    }
+
+    #[tokio::test]
+    async fn test_hitl_channel_decides_gated_patches() {
+        use crate::proto::pagi_proto::HitlClientMessage;
+        use tokio_stream::StreamExt;
+
+        let _g = lock_test_env().await;
+        std::env::set_var("PAGI_DISABLE_QDRANT", "true");
+        let temp = std::env::temp_dir().join(format!("pagi_hitl_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&temp).unwrap();
+        let memory = MemoryManager::new_async().await.unwrap();
+        let watchdog = Watchdog::new(temp.join("registry"), memory, temp.clone(), temp.clone());
+        let (to_hub, inbound) = tokio::sync::mpsc::channel(4);
+        let mut reviewer = watchdog.hitl().connect(None, tokio_stream::wrappers::ReceiverStream::new(inbound));
+        let patch_id = watchdog
+            .propose_patch(PatchRequest {
+                error_trace: "hitl channel".to_string(),
+                component: "rust_core".to_string(),
+                reasoning_id: "r1".to_string(),
+            })
+            .await
+            .unwrap()
+            .patch_id;
+        let prompt = reviewer.next().await.unwrap().unwrap().prompt.unwrap();
+        assert_eq!((prompt.request_id.as_str(), prompt.kind.as_str()), (patch_id.as_str(), "patch"));
+        assert!(prompt.summary.starts_with("hitl channel"), "{}", prompt.summary);
+
+        to_hub
+            .send(Ok(HitlClientMessage {
+                request_id: patch_id.clone(),
+                action: "reject".to_string(),
+                comment: "not this way".to_string(),
+                reviewer: "bob".to_string(),
+            }))
+            .await
+            .unwrap();
+        let policy = ApprovalPolicy {
+            wait: std::time::Duration::from_secs(5),
+            poll_interval: std::time::Duration::from_millis(10),
+            webhook_url: None,
+            reminder_interval: std::time::Duration::ZERO,
+            fallback: TimeoutFallback::Deny,
+            action_tiers: Vec::new(),
+        };
+        assert!(!watchdog.await_approval(&patch_id, &policy).await);
+        let err = watchdog
+            .apply_patch(ApplyRequest {
+                patch_id: patch_id.clone(),
+                approved: false,
+                ..Default::default()
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+        assert_eq!(err.message(), "HITL reviewer rejected by bob: not this way");
+        let denial = policy_denial::of(&err).unwrap();
+        assert_eq!((denial.rule.as_str(), denial.subject.as_str()), ("hitl_rejected", patch_id.as_str()));
+        assert!(denial.approval_channel.starts_with(policy_denial::HITL_CHANNEL));
+        let status = watchdog.apply_status(&patch_id).await.unwrap();
+        assert_eq!(status.approval, "denied: via HITL channel: rejected by bob: not this way");
+        let _ = fs::remove_dir_all(temp);
+        std::env::remove_var("PAGI_DISABLE_QDRANT");
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_reports_without_committing() {
+        let _g = lock_test_env().await;
+        std::env::set_var("PAGI_DISABLE_QDRANT", "true");
+        let temp = std::env::temp_dir().join(format!("pagi_dry_run_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&temp).unwrap();
+        let memory = MemoryManager::new_async().await.unwrap();
+        let watchdog = Watchdog::new(temp.join("registry"), memory, temp.clone(), temp.clone());
+        let repo = Repository::init(temp.join("registry")).unwrap();
+        fs::write(temp.join("registry").join("a.patch"), "a").unwrap();
+        watchdog.registry.commit(&[], "auto").await.unwrap();
+        let head = repo.head().unwrap().target().unwrap();
+
+        fs::write(temp.join("registry").join("a.patch"), "b").unwrap();
+        fs::create_dir_all(temp.join("registry").join("skills")).unwrap();
+        fs::write(temp.join("registry").join("skills").join("new.py"), "x").unwrap();
+        let changes = watchdog.registry.pending_changes().await.unwrap();
+        assert!(changes.contains(&"modified a.patch".to_string()), "{:?}", changes);
+        assert!(changes.contains(&"added skills/new.py".to_string()), "{:?}", changes);
+        let event = watchdog.dry_run_event(&changes);
+        assert_eq!(event["event"], "registry_dry_run");
+        assert_eq!(event["count"], changes.len());
+        assert_eq!(repo.head().unwrap().target().unwrap(), head, "dry run must not commit");
+        let _ = fs::remove_dir_all(temp);
+        std::env::remove_var("PAGI_DISABLE_QDRANT");
+    }
+
     fn temp_bridge_repo_for_auto_evolve() -> PathBuf {
         // Create a minimal bridge-like directory with:
         // - src/skills/evolve_skill_from_patch.py (for allow-list)
@@ -928,7 +2602,7 @@ if __name__ == "__main__":
     async fn test_apply_patch_auto_evolve() {
         // Mock successful apply/commit; PAGI_AUTO_EVOLVE_SKILLS=true.
         // Assert: evolve_skill_from_patch request is executed and bridge commit "Auto-evolved skill from self-patch" is called.
-        let _g = lock_test_env();
+        let _g = lock_test_env().await;
         std::env::set_var("PAGI_AUTO_COMMIT_SELF_PATCH", "true");
         std::env::set_var("PAGI_AUTO_EVOLVE_SKILLS", "true");
         std::env::set_var("PAGI_DISABLE_QDRANT", "true");
@@ -951,6 +2625,7 @@ if __name__ == "__main__":
             .propose_patch(PatchRequest {
                 error_trace: "test auto evolve".to_string(),
                 component: "python_skill".to_string(),
+                reasoning_id: "r1".to_string(),
             })
             .await
             .unwrap();
@@ -999,4 +2674,94 @@ if __name__ == "__main__":
         std::env::remove_var("PAGI_SKIP_APPLY_TEST");
         std::env::remove_var("PAGI_DISABLE_QDRANT");
     }
+
+    #[tokio::test]
+    async fn heal_lifecycle_and_report_follow_the_injected_clock() {
+        let _g = lock_test_env().await;
+        let temp = std::env::temp_dir().join(format!("pagi_clock_{}", uuid::Uuid::new_v4()));
+        let manual = ManualClock::at(1_700_000_000);
+        let memory = Arc::new(MemoryManager::in_memory(4).with_clock(manual.clone().into()));
+        let watchdog = Watchdog::new(temp.join("registry"), memory, temp.clone(), temp.clone());
+        let pending = PendingPatch {
+            proposed_code: "pass".into(),
+            requires_hitl: true,
+            component: "rust_core".into(),
+            reasoning_id: "r1".into(),
+            ..Default::default()
+        };
+        watchdog.catalog.insert("p1".into(), pending, watchdog.clock.now_ms());
+        manual.advance(std::time::Duration::from_secs(30));
+        watchdog.catalog.record_approval("p1", ApprovalOutcome::Approved, "flag");
+        watchdog.catalog.mark("p1", Stage::Approved);
+
+        let timeline = watchdog.catalog.timelines().pop().unwrap();
+        assert_eq!((timeline.detected_ms, timeline.proposed_ms), (1_700_000_000_000, 1_700_000_000_000));
+        assert_eq!(timeline.approved_ms, Some(1_700_000_030_000));
+        assert_eq!(watchdog.catalog.approvals("p1")[0].at, 1_700_000_030);
+        let report = |timezone: &str| {
+            watchdog.heal_report(&HealReportRequest {
+                timezone: timezone.into(),
+                ..Default::default()
+            })
+        };
+        let utc = report("").unwrap();
+        assert_eq!(utc.generated_at, "2023-11-14T22:13:50Z");
+        assert_eq!(utc.overall.unwrap().approval_latency_mean_secs, 30.0);
+        assert_eq!(report("-05:00").unwrap().generated_at, "2023-11-14T17:13:50-05:00");
+        assert_eq!(report("Europe/Berlin").unwrap_err().code(), tonic::Code::InvalidArgument);
+        let _ = fs::remove_dir_all(temp);
+    }
+
+    #[tokio::test]
+    async fn pending_patches_are_listed_oldest_first_and_fetched_by_id() {
+        let _g = lock_test_env().await;
+        let temp = std::env::temp_dir().join(format!("pagi_pending_{}", uuid::Uuid::new_v4()));
+        let manual = ManualClock::at(1_700_000_000);
+        let memory = Arc::new(MemoryManager::in_memory(4).with_clock(manual.clone().into()));
+        let watchdog = Watchdog::new(temp.join("registry"), memory, temp.clone(), temp.clone());
+        let patch = |component: &str, requires_hitl: bool, code: String| PendingPatch {
+            proposed_code: code,
+            requires_hitl,
+            component: component.into(),
+            reasoning_id: "r1".into(),
+            ..Default::default()
+        };
+        let code = "fn fix() {\n    todo!()\n}";
+        watchdog.catalog.insert("p2".into(), patch("rust_core", true, code.into()), watchdog.clock.now_ms());
+        manual.advance(std::time::Duration::from_secs(60));
+        let long = "x = 1\n".repeat(100);
+        watchdog.catalog.insert("p1".into(), patch("python_skill", false, long), watchdog.clock.now_ms());
+        watchdog.catalog.insert("p3".into(), patch("rust_core", false, "pass".into()), watchdog.clock.now_ms());
+        watchdog.catalog.record_approval("p2", ApprovalOutcome::Denied, "needs a test");
+        manual.advance(std::time::Duration::from_secs(60));
+
+        let list = |req: ListPendingPatchesRequest| watchdog.list_pending(&req);
+        let all = list(ListPendingPatchesRequest::default());
+        let ids: Vec<&str> = all.patches.iter().map(|p| p.patch_id.as_str()).collect();
+        assert_eq!((ids, all.total), (vec!["p2", "p1", "p3"], 3), "oldest first, ties by id");
+        assert_eq!(all.patches[0].preview, "fn fix() { todo!() }");
+        assert_eq!((all.patches[0].proposed_at, all.patches[0].age_secs), (1_700_000_000, 120));
+        assert!(all.patches[1].preview.ends_with('…') && all.patches[1].preview.chars().count() == PREVIEW_CHARS + 1);
+        let gated = list(ListPendingPatchesRequest {
+            component: "rust_core".into(),
+            hitl_only: true,
+            ..Default::default()
+        });
+        assert_eq!((gated.patches.len(), gated.total), (1, 1));
+        let first = list(ListPendingPatchesRequest { limit: 1, ..Default::default() });
+        assert_eq!((first.patches.len(), first.total), (1, 3), "total counts past the limit");
+
+        let detail = watchdog.get_patch("p2").unwrap();
+        assert_eq!((detail.state.as_str(), detail.age_secs), ("pending", 120));
+        assert_eq!(detail.proposed_code, code);
+        assert_eq!(detail.approvals, vec!["denied: needs a test".to_string()]);
+        assert!(detail.impact.is_some());
+        let applied = watchdog.catalog.remove("p3").unwrap();
+        watchdog.catalog.record_applied("p3", applied, "abc123", "");
+        let detail = watchdog.get_patch("p3").unwrap();
+        assert_eq!((detail.state.as_str(), detail.commit_hash.as_str()), ("applied", "abc123"));
+        assert_eq!(list(ListPendingPatchesRequest::default()).total, 2, "applied patches are no longer pending");
+        assert_eq!(watchdog.get_patch("p9").unwrap_err().code(), tonic::Code::NotFound);
+        let _ = fs::remove_dir_all(temp);
+    }
 }
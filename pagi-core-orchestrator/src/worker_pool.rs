@@ -0,0 +1,282 @@
+// Warm Python worker pool for L5 dispatch: long-lived scripts/skill_worker.py processes speaking
+// length-prefixed JSON over stdin/stdout. Avoids interpreter startup + imports per action; callers
+// fall back to per-invocation spawning whenever the pool reports Unavailable.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+
+use crate::resource_usage::{self, ResourceUsage};
+
+/// Frames above this size are treated as protocol errors.
+const MAX_FRAME: u32 = 16 * 1024 * 1024;
+/// Workers idle longer than this are pinged before reuse.
+const HEALTH_CHECK_IDLE: Duration = Duration::from_secs(30);
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Result of a pooled invocation.
+pub enum PoolOutcome {
+    /// Worker answered: (observation, success, error, resource usage of this call).
+    Done(String, bool, String, ResourceUsage),
+    /// Deadline hit; the worker was killed.
+    TimedOut,
+    /// Pool could not deliver the request (spawn/write failure); caller should spawn per invocation.
+    Unavailable(String),
+}
+
+struct Worker {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: ChildStdout,
+    served: u32,
+    last_used: Instant,
+}
+
+impl Worker {
+    async fn call(&mut self, req: &serde_json::Value) -> Result<serde_json::Value, String> {
+        self.send(req).await?;
+        self.recv().await
+    }
+
+    async fn send(&mut self, req: &serde_json::Value) -> Result<(), String> {
+        let body = serde_json::to_vec(req).map_err(|e| e.to_string())?;
+        self.stdin
+            .write_all(&(body.len() as u32).to_be_bytes())
+            .await
+            .map_err(|e| format!("write: {}", e))?;
+        self.stdin
+            .write_all(&body)
+            .await
+            .map_err(|e| format!("write: {}", e))?;
+        self.stdin.flush().await.map_err(|e| format!("flush: {}", e))
+    }
+
+    async fn recv(&mut self) -> Result<serde_json::Value, String> {
+        let mut header = [0u8; 4];
+        self.stdout
+            .read_exact(&mut header)
+            .await
+            .map_err(|e| format!("read header: {}", e))?;
+        let len = u32::from_be_bytes(header);
+        if len > MAX_FRAME {
+            return Err(format!("frame too large: {}", len));
+        }
+        let mut buf = vec![0u8; len as usize];
+        self.stdout
+            .read_exact(&mut buf)
+            .await
+            .map_err(|e| format!("read body: {}", e))?;
+        serde_json::from_slice(&buf).map_err(|e| format!("decode: {}", e))
+    }
+}
+
+pub struct WorkerPool {
+    script: PathBuf,
+    cwd: PathBuf,
+    /// Max idle workers kept warm.
+    size: usize,
+    /// Recycle a worker after this many requests.
+    max_requests: u32,
+    idle: Mutex<Vec<Worker>>,
+    next_id: AtomicU64,
+}
+
+impl WorkerPool {
+    pub fn new(script: PathBuf, cwd: PathBuf, size: usize, max_requests: u32) -> Self {
+        Self {
+            script,
+            cwd,
+            size,
+            max_requests: max_requests.max(1),
+            idle: Mutex::new(Vec::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Pool from PAGI_SKILL_WORKER_POOL (idle workers; 0/unset disables) and PAGI_SKILL_WORKER_MAX_REQUESTS
+    /// (default 100). None when disabled or the bridge has no scripts/skill_worker.py.
+    pub fn from_env(bridge_dir: &Path) -> Option<Self> {
+        let size: usize = std::env::var("PAGI_SKILL_WORKER_POOL")
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(0);
+        if size == 0 {
+            return None;
+        }
+        let script = bridge_dir.join("scripts").join("skill_worker.py");
+        if !script.exists() {
+            eprintln!("[WorkerPool] {} missing; using per-invocation spawn", script.display());
+            return None;
+        }
+        let max_requests = std::env::var("PAGI_SKILL_WORKER_MAX_REQUESTS")
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(100);
+        Some(Self::new(script, bridge_dir.to_path_buf(), size, max_requests))
+    }
+
+    fn spawn(&self) -> Result<Worker, String> {
+        let mut child = Command::new("python")
+            .arg(&self.script)
+            .current_dir(&self.cwd)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("spawn worker: {}", e))?;
+        let stdin = child.stdin.take().ok_or("worker stdin unavailable")?;
+        let stdout = child.stdout.take().ok_or("worker stdout unavailable")?;
+        Ok(Worker {
+            child,
+            stdin,
+            stdout,
+            served: 0,
+            last_used: Instant::now(),
+        })
+    }
+
+    fn request_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Take a healthy idle worker (exited ones dropped, stale ones pinged) or spawn a fresh one.
+    async fn checkout(&self) -> Result<Worker, String> {
+        loop {
+            let candidate = self.idle.lock().await.pop();
+            let Some(mut w) = candidate else {
+                return self.spawn();
+            };
+            if !matches!(w.child.try_wait(), Ok(None)) {
+                continue;
+            }
+            if w.last_used.elapsed() >= HEALTH_CHECK_IDLE {
+                let ping = serde_json::json!({"id": self.request_id(), "op": "ping"});
+                match tokio::time::timeout(PING_TIMEOUT, w.call(&ping)).await {
+                    Ok(Ok(resp)) if resp["ok"].as_bool() == Some(true) => {}
+                    _ => continue,
+                }
+            }
+            return Ok(w);
+        }
+    }
+
+    async fn checkin(&self, mut w: Worker) {
+        w.served += 1;
+        w.last_used = Instant::now();
+        if w.served >= self.max_requests {
+            return; // recycle: dropped worker is killed (kill_on_drop)
+        }
+        let mut idle = self.idle.lock().await;
+        if idle.len() < self.size {
+            idle.push(w);
+        }
+    }
+
+    /// Run one skill on a pooled worker with a hard deadline. `skill_path` locates namespaced skills.
+    pub async fn execute(
+        &self,
+        skill: &str,
+        params_json: &str,
+        skill_path: Option<&Path>,
+        timeout: Duration,
+    ) -> PoolOutcome {
+        let params: serde_json::Value =
+            serde_json::from_str(params_json).unwrap_or_else(|_| serde_json::json!({}));
+        let mut w = match self.checkout().await {
+            Ok(w) => w,
+            Err(e) => return PoolOutcome::Unavailable(e),
+        };
+        // Aborting the request drops `w` mid-call, which kills the worker (kill_on_drop).
+        crate::inflight::record_child_pid(w.child.id());
+        // Long-lived worker: CPU is the delta over this call; peak RSS is the worker's lifetime peak.
+        let started = Instant::now();
+        let before = w.child.id().and_then(resource_usage::read_proc);
+        let mut req = serde_json::json!({
+            "id": self.request_id(),
+            "op": "invoke",
+            "skill": skill,
+            "params": params,
+        });
+        if let Some(path) = skill_path {
+            req["path"] = serde_json::json!(path.to_string_lossy());
+        }
+        // A failed send means the skill never started, so a fresh spawn is safe; once the request is
+        // delivered, failures are reported as-is to avoid running a side-effecting skill twice.
+        let deadline = tokio::time::Instant::now() + timeout;
+        match tokio::time::timeout_at(deadline, w.send(&req)).await {
+            Err(_) => return PoolOutcome::TimedOut,
+            Ok(Err(e)) => return PoolOutcome::Unavailable(e),
+            Ok(Ok(())) => {}
+        }
+        match tokio::time::timeout_at(deadline, w.recv()).await {
+            Err(_) => PoolOutcome::TimedOut, // worker dropped → killed
+            Ok(Err(e)) => PoolOutcome::Done(
+                String::new(),
+                false,
+                format!("worker protocol error: {}", e),
+                ResourceUsage::from_samples(started.elapsed(), None, None),
+            ),
+            Ok(Ok(resp)) => {
+                let observation = resp["observation"].as_str().unwrap_or("").trim().to_string();
+                let success = resp["ok"].as_bool().unwrap_or(false);
+                let error = resp["error"].as_str().unwrap_or("").trim().to_string();
+                let after = w.child.id().and_then(resource_usage::read_proc);
+                let usage = ResourceUsage::from_samples(started.elapsed(), before, after);
+                self.checkin(w).await;
+                PoolOutcome::Done(observation, success, error, usage)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fixture worker: answers every request with its own PID, or sleeps for skill "sleep".
+    const FIXTURE_WORKER: &str = r#"import json, os, struct, sys, time
+inp, out = sys.stdin.buffer, sys.stdout.buffer
+while True:
+    hdr = inp.read(4)
+    if len(hdr) < 4:
+        break
+    req = json.loads(inp.read(struct.unpack('>I', hdr)[0]))
+    if req.get('skill') == 'sleep':
+        time.sleep(100)
+    data = json.dumps({"id": req.get("id"), "ok": True, "observation": str(os.getpid()), "error": ""}).encode()
+    out.write(struct.pack('>I', len(data)) + data)
+    out.flush()
+"#;
+
+    #[tokio::test]
+    async fn pool_reuses_recycles_and_times_out() {
+        let temp = std::env::temp_dir().join(format!("pagi_worker_pool_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp).unwrap();
+        let script = temp.join("skill_worker.py");
+        std::fs::write(&script, FIXTURE_WORKER).unwrap();
+        let pool = WorkerPool::new(script, temp.clone(), 1, 2);
+        let t = Duration::from_secs(10);
+
+        let pid = |o: PoolOutcome| match o {
+            PoolOutcome::Done(obs, true, _, _) => obs,
+            _ => panic!("expected successful pooled call"),
+        };
+        let first = pid(pool.execute("peek_file", "{}", None, t).await);
+        let second = pid(pool.execute("peek_file", "{}", None, t).await);
+        assert_eq!(first, second, "warm worker reused");
+        let third = pid(pool.execute("peek_file", "{}", None, t).await);
+        assert_ne!(second, third, "worker recycled after max_requests");
+
+        assert!(matches!(
+            pool.execute("sleep", "{}", None, Duration::from_millis(100)).await,
+            PoolOutcome::TimedOut
+        ));
+        let _ = std::fs::remove_dir_all(temp);
+    }
+}
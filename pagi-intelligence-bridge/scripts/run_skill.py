@@ -1,12 +1,27 @@
-"""CLI entrypoint for Rust-mediated L5 dispatch: python run_skill.py <skill_name> <json_params>.
+"""CLI entrypoint for Rust-mediated L5 dispatch: python run_skill.py <skill_name> <json_params> [<skill_path>].
 
 Run from bridge root (current_dir). Adds src to path and invokes skills.<skill>.run(Params).
+Namespaced skills (``plugin.skill``, from PAGI_SKILL_SOURCES roots) are loaded from <skill_path>.
+
+Protocol v2 (PAGI_RUNNER_PROTOCOL=2 in the environment): the invocation envelope (skill, params,
+invocation_id, temp_dir, deadline_unix_ms, skill_path, filesystem) is read from stdin instead of argv, skill output
+printed to stdout is moved to stderr, and one JSON result envelope (status, observation, error,
+artifacts, metrics, inputs) is written as the last stdout line. ``inputs`` maps each file the skill opened
+for reading (seen through an audit hook; interpreter files excluded) to its sha256, so a recorded action can
+later be checked for input drift. When the envelope carries ``filesystem`` (the read/write directories
+declared in the skill's manifest), opening, creating, renaming or removing files elsewhere raises
+PermissionError; the interpreter, the bridge's src/ and scripts/ (read only) and temp_dir stay reachable.
 """
 
 from __future__ import annotations
 
+import contextlib
+import hashlib
+import importlib.util
 import json
+import os
 import sys
+import time
 from pathlib import Path
 
 # Bridge root = parent of scripts/
@@ -24,42 +39,180 @@ def _params_class_name(skill_name: str) -> str:
     return "".join(w.capitalize() for w in skill_name.split("_")) + "Params"
 
 
+def _load_module(skill_name: str, skill_path: str | None):
+    if not skill_path:
+        return __import__(f"skills.{skill_name}", fromlist=["run"])
+    path = Path(skill_path)
+    if not path.is_absolute():
+        path = BRIDGE_ROOT / path
+    spec = importlib.util.spec_from_file_location(f"pagi_skill_{skill_name.replace('.', '_')}", path)
+    if spec is None or spec.loader is None:
+        raise RuntimeError(f"Cannot load skill from {path}")
+    mod = importlib.util.module_from_spec(spec)
+    spec.loader.exec_module(mod)
+    return mod
+
+
+def invoke_skill(skill_name: str, params: dict, skill_path: str | None = None) -> str:
+    """Import skills.<skill_name> (or the file at skill_path), validate params against its Params model,
+    run it; raises on failure.
+
+    Shared by this CLI (one process per action) and scripts/skill_worker.py (warm worker pool).
+    """
+    mod = _load_module(skill_name, skill_path)
+    run_fn = getattr(mod, "run", None)
+    if run_fn is None:
+        raise RuntimeError("Skill missing run()")
+    params_cls = getattr(mod, _params_class_name(skill_name.rsplit(".", 1)[-1]), None)
+    if params_cls is None:
+        for cand in (
+            "PeekFileParams",
+            "SaveSkillParams",
+            "ExecuteSkillParams",
+            "ListDirParams",
+            "ReadEntireFileSafeParams",
+            "WriteFileSafeParams",
+            "ListFilesRecursiveParams",
+            "AnalyzeCodeParams",
+            "EvolveSkillFromPatchParams",
+            "GenerateNewSkillParams",
+        ):
+            if hasattr(mod, cand):
+                params_cls = getattr(mod, cand)
+                break
+    if params_cls is None:
+        raise RuntimeError("Params model not found")
+    return str(run_fn(params_cls.model_validate(params)))
+
+
+def _artifacts(observation: str) -> list[str]:
+    """Files reported by skills that predate v2 (evolve_skill_from_patch prints EVOLVED_PATH:<path>)."""
+    prefix = "EVOLVED_PATH:"
+    if observation.startswith(prefix):
+        path = observation[len(prefix):].strip()
+        return [path] if path else []
+    return []
+
+
+# Files opened for reading while a v2 skill runs; None outside the run.
+_READS: set[str] | None = None
+MAX_INPUTS = 256
+_WRITE_FLAGS = os.O_WRONLY | os.O_RDWR | os.O_APPEND | os.O_CREAT
+# (readable dirs, writable dirs) declared by the skill's manifest; None when unconfined or outside the run.
+_CONFINE: tuple[tuple[Path, ...], tuple[Path, ...]] | None = None
+# Audit events that modify the filesystem, with the indexes of their path arguments.
+_WRITE_EVENTS = {"os.remove": (0,), "os.rmdir": (0,), "os.mkdir": (0,), "os.rename": (0, 1)}
+
+
+def _confine(filesystem: dict, temp_dir: str | None) -> tuple[tuple[Path, ...], tuple[Path, ...]]:
+    write = [Path(p) for p in filesystem.get("write") or []]
+    if temp_dir:
+        write.append(Path(temp_dir).resolve())
+    interpreter = {Path(p).resolve() for p in (sys.prefix, sys.base_prefix, sys.exec_prefix)}
+    read = [Path(p) for p in filesystem.get("read") or []]
+    read += [*interpreter, SRC, BRIDGE_ROOT / "scripts", *write]
+    return tuple(read), tuple(write)
+
+
+def _check_access(path, writing: bool) -> None:
+    if _CONFINE is None or isinstance(path, int) or path is None:
+        return
+    resolved = Path(os.fsdecode(path)).resolve()
+    allowed = _CONFINE[1] if writing else _CONFINE[0]
+    if not any(resolved.is_relative_to(d) for d in allowed):
+        access = "write" if writing else "read"
+        raise PermissionError(f"skill may not {access} {resolved} (outside its declared filesystem)")
+
+
+def _audit_open(event: str, args: tuple) -> None:
+    if event in _WRITE_EVENTS:
+        for i in _WRITE_EVENTS[event]:
+            _check_access(args[i], writing=True)
+        return
+    if event != "open":
+        return
+    path, mode, flags = args
+    if isinstance(path, int) or path is None:
+        return
+    if isinstance(mode, str):
+        reading = not any(c in mode for c in "wax+")
+    else:  # os.open: mode is None, flags carry the access mode
+        reading = not (flags or 0) & _WRITE_FLAGS
+    _check_access(path, writing=not reading)
+    if reading and _READS is not None and len(_READS) < MAX_INPUTS:
+        _READS.add(os.fsdecode(path))
+
+
+def _hash_inputs(paths: set[str]) -> dict[str, str]:
+    """sha256 per file read (bridge-relative when under the bridge); interpreter and bytecode files skipped."""
+    skip = tuple({str(Path(p).resolve()) for p in (sys.prefix, sys.base_prefix, sys.exec_prefix)})
+    inputs: dict[str, str] = {}
+    for raw in sorted(paths):
+        path = Path(raw).resolve()
+        if str(path).startswith(skip) or path.suffix == ".pyc" or not path.is_file():
+            continue
+        try:
+            digest = hashlib.sha256(path.read_bytes()).hexdigest()
+        except OSError:
+            continue
+        try:
+            key = path.relative_to(BRIDGE_ROOT).as_posix()
+        except ValueError:
+            key = str(path)
+        inputs[key] = digest
+    return inputs
+
+
+def main_v2() -> None:
+    global _READS, _CONFINE
+    started = time.monotonic()
+    result: dict = {
+        "protocol": 2,
+        "status": "error",
+        "observation": "",
+        "error": "",
+        "artifacts": [],
+        "metrics": {},
+        "inputs": {},
+    }
+    sys.addaudithook(_audit_open)
+    try:
+        envelope = json.loads(sys.stdin.read() or "{}")
+        if envelope.get("temp_dir"):
+            os.environ["PAGI_SKILL_TMPDIR"] = envelope["temp_dir"]
+        _READS = set()
+        if envelope.get("filesystem") is not None:
+            _CONFINE = _confine(envelope["filesystem"], envelope.get("temp_dir"))
+        with contextlib.redirect_stdout(sys.stderr):
+            observation = invoke_skill(envelope["skill"], envelope.get("params") or {}, envelope.get("skill_path"))
+        result.update(status="ok", observation=observation, artifacts=_artifacts(observation))
+    except Exception as e:
+        result["error"] = f"[run_skill] Error: {e!s}"
+    _CONFINE = None
+    reads, _READS = _READS or set(), None
+    result["inputs"] = _hash_inputs(reads)
+    result["metrics"]["duration_ms"] = round((time.monotonic() - started) * 1000, 3)
+    print(json.dumps(result))
+    if result["status"] != "ok":
+        sys.exit(1)
+
+
 def main() -> None:
+    if os.environ.get("PAGI_RUNNER_PROTOCOL") == "2":
+        main_v2()
+        return
     if len(sys.argv) < 3:
-        print("[run_skill] usage: python run_skill.py <skill_name> <json_params>", file=sys.stderr)
+        print(
+            "[run_skill] usage: python run_skill.py <skill_name> <json_params> [<skill_path>]",
+            file=sys.stderr,
+        )
         sys.exit(1)
     skill_name = sys.argv[1]
     params_json = sys.argv[2]
+    skill_path = sys.argv[3] if len(sys.argv) > 3 else None
 
     try:
-        mod = __import__(f"skills.{skill_name}", fromlist=["run"])
-        run_fn = getattr(mod, "run", None)
-        if run_fn is None:
-            print("[run_skill] Skill missing run()", file=sys.stderr)
-            sys.exit(1)
-        params_cls = getattr(mod, _params_class_name(skill_name), None)
-        if params_cls is None:
-            for cand in (
-                "PeekFileParams",
-                "SaveSkillParams",
-                "ExecuteSkillParams",
-                "ListDirParams",
-                "ReadEntireFileSafeParams",
-                "WriteFileSafeParams",
-                "ListFilesRecursiveParams",
-                "AnalyzeCodeParams",
-                "EvolveSkillFromPatchParams",
-                "GenerateNewSkillParams",
-            ):
-                if hasattr(mod, cand):
-                    params_cls = getattr(mod, cand)
-                    break
-        if params_cls is None:
-            print("[run_skill] Params model not found", file=sys.stderr)
-            sys.exit(1)
-        params = params_cls.model_validate(json.loads(params_json))
-        result = run_fn(params)
-        print(result)
+        print(invoke_skill(skill_name, json.loads(params_json), skill_path))
     except Exception as e:
         print(f"[run_skill] Error: {e!s}", file=sys.stderr)
         sys.exit(1)
@@ -0,0 +1,66 @@
+"""Warm skill worker for the orchestrator's worker pool: python skill_worker.py (no args).
+
+Protocol (stdin/stdout, binary): each frame is a 4-byte big-endian length followed by UTF-8 JSON.
+Requests:  {"id": n, "op": "invoke", "skill": "<name>", "params": {...}, "path": "<file>"?}  or  {"id": n, "op": "ping"}
+Responses: {"id": n, "ok": bool, "observation": "<text>", "error": "<text>"}
+
+Skill output printed to stdout is redirected to stderr so it cannot corrupt the frame stream.
+The process exits on EOF; the orchestrator recycles workers after PAGI_SKILL_WORKER_MAX_REQUESTS.
+"""
+
+from __future__ import annotations
+
+import json
+import struct
+import sys
+from pathlib import Path
+
+SCRIPTS = Path(__file__).resolve().parent
+if str(SCRIPTS) not in sys.path:
+    sys.path.insert(0, str(SCRIPTS))
+
+from run_skill import invoke_skill  # noqa: E402  (also puts bridge src on sys.path)
+
+MAX_FRAME = 16 * 1024 * 1024
+
+
+def _read_frame(inp) -> dict | None:
+    header = inp.read(4)
+    if len(header) < 4:
+        return None
+    (length,) = struct.unpack(">I", header)
+    if length > MAX_FRAME:
+        raise ValueError(f"frame too large: {length}")
+    return json.loads(inp.read(length).decode("utf-8"))
+
+
+def _write_frame(out, payload: dict) -> None:
+    data = json.dumps(payload).encode("utf-8")
+    out.write(struct.pack(">I", len(data)) + data)
+    out.flush()
+
+
+def main() -> None:
+    inp = sys.stdin.buffer
+    out = sys.stdout.buffer
+    sys.stdout = sys.stderr  # skills may print(); keep the frame channel clean
+    while True:
+        req = _read_frame(inp)
+        if req is None:
+            return
+        resp = {"id": req.get("id"), "ok": True, "observation": "", "error": ""}
+        if req.get("op") == "ping":
+            resp["observation"] = "pong"
+        else:
+            try:
+                resp["observation"] = invoke_skill(
+                    req.get("skill", ""), req.get("params") or {}, req.get("path")
+                )
+            except Exception as e:  # report, keep worker alive
+                resp["ok"] = False
+                resp["error"] = f"[skill_worker] Error: {e!s}"
+        _write_frame(out, resp)
+
+
+if __name__ == "__main__":
+    main()
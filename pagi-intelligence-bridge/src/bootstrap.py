@@ -0,0 +1,140 @@
+"""Cold-start L4 bootstrap: index the system's own code and docs so self-heal RCA has data on day one.
+
+Sources:
+  kb_core   - orchestrator Rust sources, pagi.proto, ARCHITECTURE.md/README.md, and doc folders
+              from PAGI_BOOTSTRAP_DOC_DIRS (os.pathsep-separated; default: repo docs/).
+  kb_skills - each L5 skill's module docstring plus any metadata JSON manifests in src/skills/.
+
+Chunks go through the embed_and_upsert pipeline (same chunking and embedding as `make index-kb`).
+Point ids are uuid5(kb, source, chunk) so re-running bootstrap overwrites instead of duplicating.
+Usage:
+  poetry run pagi bootstrap [--dry-run] [--chunk-size 1000]
+"""
+
+from __future__ import annotations
+
+import ast
+import os
+import uuid
+from dataclasses import dataclass
+from pathlib import Path
+
+from src.embed_and_upsert import _grpc_addr, chunk_text, embed_text
+
+_BRIDGE_ROOT = Path(__file__).resolve().parent.parent
+_REPO_ROOT = _BRIDGE_ROOT.parent
+_DOC_SUFFIXES = {".md", ".txt", ".rst"}
+_UPSERT_BATCH = 64
+
+
+@dataclass
+class Source:
+    kb_name: str
+    label: str  # repo-relative path (plus "#docstring" for skill docstrings)
+    text: str
+
+
+def _rel(path: Path) -> str:
+    try:
+        return path.resolve().relative_to(_REPO_ROOT).as_posix()
+    except ValueError:
+        return path.as_posix()
+
+
+def _read(path: Path) -> str:
+    return path.read_text(encoding="utf-8", errors="replace")
+
+
+def _doc_dirs() -> list[Path]:
+    raw = os.environ.get("PAGI_BOOTSTRAP_DOC_DIRS", "").strip()
+    if not raw:
+        return [_REPO_ROOT / "docs"]
+    return [Path(p).expanduser() for p in raw.split(os.pathsep) if p.strip()]
+
+
+def collect_core_sources(repo_root: Path = _REPO_ROOT, doc_dirs: list[Path] | None = None) -> list[Source]:
+    sources: list[Source] = []
+    for path in sorted((repo_root / "pagi-core-orchestrator" / "src").rglob("*.rs")):
+        sources.append(Source("kb_core", _rel(path), _read(path)))
+    for path in (
+        repo_root / "pagi-proto" / "pagi.proto",
+        repo_root / "ARCHITECTURE.md",
+        repo_root / "README.md",
+    ):
+        if path.is_file():
+            sources.append(Source("kb_core", _rel(path), _read(path)))
+    for folder in doc_dirs if doc_dirs is not None else _doc_dirs():
+        if not folder.is_dir():
+            print(f"[bootstrap] doc folder not found, skipping: {folder}")
+            continue
+        for path in sorted(folder.rglob("*")):
+            if path.is_file() and path.suffix.lower() in _DOC_SUFFIXES:
+                sources.append(Source("kb_core", _rel(path), _read(path)))
+    return sources
+
+
+def collect_skill_sources(skills_dir: Path = _BRIDGE_ROOT / "src" / "skills") -> list[Source]:
+    sources: list[Source] = []
+    for path in sorted(skills_dir.glob("*.py")):
+        if path.name.startswith("_"):
+            continue
+        try:
+            doc = ast.get_docstring(ast.parse(_read(path)))
+        except SyntaxError as e:
+            print(f"[bootstrap] cannot parse {path.name}: {e}")
+            continue
+        if doc:
+            sources.append(Source("kb_skills", f"{_rel(path)}#docstring", f"Skill {path.stem}\n\n{doc}"))
+    for path in sorted(skills_dir.glob("*.json")):
+        sources.append(Source("kb_skills", _rel(path), _read(path)))
+    readme = skills_dir / "README.md"
+    if readme.is_file():
+        sources.append(Source("kb_skills", _rel(readme), _read(readme)))
+    return sources
+
+
+def point_id(kb_name: str, label: str, idx: int) -> str:
+    return str(uuid.uuid5(uuid.NAMESPACE_URL, f"pagi://{kb_name}/{label}#{idx}"))
+
+
+def bootstrap(chunk_size: int = 1000, dry_run: bool = False, grpc_addr: str | None = None) -> dict[str, int]:
+    """Index all sources; returns points upserted (or chunks planned, with dry_run) per KB."""
+    sources = collect_core_sources() + collect_skill_sources()
+    planned: dict[str, list[tuple[str, str]]] = {}
+    for src in sources:
+        for idx, chunk in enumerate(chunk_text(src.text, chunk_size)):
+            if chunk.strip():
+                planned.setdefault(src.kb_name, []).append((point_id(src.kb_name, src.label, idx), chunk))
+        print(f"[bootstrap] {src.kb_name} <- {src.label}")
+    if dry_run:
+        return {kb: len(items) for kb, items in planned.items()}
+
+    import grpc
+    from sentence_transformers import SentenceTransformer
+
+    import pagi_pb2
+    import pagi_pb2_grpc
+
+    model = SentenceTransformer(os.environ.get("PAGI_EMBED_MODEL", "all-MiniLM-L6-v2"))
+    stub = pagi_pb2_grpc.PagiStub(grpc.insecure_channel(grpc_addr or _grpc_addr()))
+    upserted: dict[str, int] = {}
+    for kb_name, items in planned.items():
+        for start in range(0, len(items), _UPSERT_BATCH):
+            batch = items[start : start + _UPSERT_BATCH]
+            points = [
+                pagi_pb2.VectorPoint(
+                    id=pid,
+                    vector=embed_text(chunk, model),
+                    payload={"content": (chunk[:500] + "…") if len(chunk) > 500 else chunk},
+                )
+                for pid, chunk in batch
+            ]
+            resp = stub.UpsertVectors(pagi_pb2.UpsertRequest(kb_name=kb_name, points=points))
+            upserted[kb_name] = upserted.get(kb_name, 0) + resp.upserted_count
+
+    log_path = os.environ.get("PAGI_SELF_HEAL_LOG")
+    if log_path:
+        with open(log_path, "a", encoding="utf-8") as f:
+            summary = ", ".join(f"{kb}={n}" for kb, n in sorted(upserted.items()))
+            f.write(f"L6 KB bootstrap: cold start indexed {len(sources)} sources ({summary})\n")
+    return upserted
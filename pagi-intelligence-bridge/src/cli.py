@@ -0,0 +1,37 @@
+"""`pagi` command-line entry point (poetry script).
+
+Subcommands:
+  bootstrap  Cold-start kb_core/kb_skills from the repo's own sources, skill docstrings and docs.
+"""
+
+from __future__ import annotations
+
+import argparse
+import sys
+
+
+def main(argv: list[str] | None = None) -> None:
+    parser = argparse.ArgumentParser(prog="pagi", description="Phoenix AGI bridge tools")
+    sub = parser.add_subparsers(dest="command", required=True)
+
+    boot = sub.add_parser("bootstrap", help="Index orchestrator source, skill docstrings and docs into L4")
+    boot.add_argument("--dry-run", action="store_true", help="List sources and chunk counts without embedding")
+    boot.add_argument("--chunk-size", type=int, default=1000, help="Chars per chunk")
+    boot.add_argument("--grpc", default=None, help="gRPC address (default [::1]:PAGI_GRPC_PORT)")
+
+    args = parser.parse_args(argv)
+    if args.command == "bootstrap":
+        from src.bootstrap import bootstrap
+
+        try:
+            counts = bootstrap(chunk_size=args.chunk_size, dry_run=args.dry_run, grpc_addr=args.grpc)
+        except Exception as e:
+            print(f"Error: {e}", file=sys.stderr)
+            sys.exit(1)
+        verb = "Planned" if args.dry_run else "Upserted"
+        for kb_name, n in sorted(counts.items()):
+            print(f"{verb} {n} chunks -> {kb_name}")
+
+
+if __name__ == "__main__":
+    main()
@@ -70,15 +70,19 @@ def search_kb(
     return response.hits
 
 
+def chunk_text(text: str, chunk_size: int = 1000) -> list[str]:
+    return [
+        text[i : i + chunk_size]
+        for i in range(0, len(text), chunk_size)
+    ]
+
+
 def chunk_doc(file_path: str | Path, chunk_size: int = 1000) -> list[str]:
     path = Path(file_path)
     if not path.exists():
         raise FileNotFoundError(f"Doc not found: {path}")
     text = path.read_text(encoding="utf-8", errors="replace")
-    return [
-        text[i : i + chunk_size]
-        for i in range(0, len(text), chunk_size)
-    ]
+    return chunk_text(text, chunk_size)
 
 
 def upsert_to_kb(
@@ -2,9 +2,11 @@
 
 import os
 import traceback
+from functools import lru_cache
 
 from dotenv import load_dotenv
 from fastapi import FastAPI
+from pydantic import BaseModel, Field
 
 load_dotenv()  # Load .env from cwd if present (reproducible L5 verification)
 
@@ -23,6 +25,48 @@ class RLMMultiTurnRequest(RLMQuery):
     max_turns: int = 5
 
 
+class RerankRequest(BaseModel):
+    """Query and candidate documents from the orchestrator's SemanticSearch (rerank=cross_encoder)."""
+
+    query: str
+    documents: list[str] = Field(default_factory=list, max_length=1000)
+
+
+class RerankResponse(BaseModel):
+    """One cross-encoder score per document, in request order (higher is more relevant)."""
+
+    scores: list[float]
+    model: str
+
+
+class EmbedRequest(BaseModel):
+    """Chunks from the orchestrator's IngestDocument."""
+
+    texts: list[str] = Field(default_factory=list, max_length=1000)
+
+
+class EmbedResponse(BaseModel):
+    """One embedding per text, in request order (the orchestrator zero-pads them to the KB's dimension)."""
+
+    vectors: list[list[float]]
+    model: str
+    dim: int
+
+
+@lru_cache(maxsize=1)
+def _sentence_encoder(model_name: str):
+    from sentence_transformers import SentenceTransformer
+
+    return SentenceTransformer(model_name)
+
+
+@lru_cache(maxsize=1)
+def _cross_encoder(model_name: str):
+    from sentence_transformers import CrossEncoder
+
+    return CrossEncoder(model_name)
+
+
 app = FastAPI(title="pagi-intelligence-bridge", version="0.1.0")
 
 
@@ -84,3 +128,23 @@ def handle_rlm_multi_turn(body: RLMMultiTurnRequest) -> list[dict]:
             depth=query.depth,
         )
     return summaries
+
+
+@app.post("/rerank", response_model=RerankResponse)
+def handle_rerank(body: RerankRequest) -> RerankResponse:
+    """Score (query, document) pairs with the PAGI_RERANK_MODEL cross-encoder (loaded on first use)."""
+    model_name = os.environ.get("PAGI_RERANK_MODEL", "cross-encoder/ms-marco-MiniLM-L-6-v2")
+    if not body.documents:
+        return RerankResponse(scores=[], model=model_name)
+    scores = _cross_encoder(model_name).predict([(body.query, doc) for doc in body.documents])
+    return RerankResponse(scores=[float(x) for x in scores], model=model_name)
+
+
+@app.post("/embed", response_model=EmbedResponse)
+def handle_embed(body: EmbedRequest) -> EmbedResponse:
+    """Embed texts with the PAGI_EMBED_MODEL sentence encoder (loaded on first use), as embed_and_upsert does."""
+    model_name = os.environ.get("PAGI_EMBED_MODEL", "all-MiniLM-L6-v2")
+    if not body.texts:
+        return EmbedResponse(vectors=[], model=model_name, dim=0)
+    vectors = [[float(x) for x in v] for v in _sentence_encoder(model_name).encode(body.texts)]
+    return EmbedResponse(vectors=vectors, model=model_name, dim=len(vectors[0]))
@@ -113,9 +113,24 @@ class RLMRequest(BaseModel):
     depth: int = Field(default=0, ge=0, le=10)
 
 
+class RLMCitation(BaseModel):
+    kb_name: str
+    document_id: str
+    score: float = 0.0
+
+
+class RLMTokenUsage(BaseModel):
+    prompt_tokens: int = 0
+    completion_tokens: int = 0
+
+
 class RLMResponse(BaseModel):
     summary: str
     converged: bool
+    steps: int = 1
+    confidence: float | None = None
+    citations: list[RLMCitation] = Field(default_factory=list)
+    usage: RLMTokenUsage = Field(default_factory=RLMTokenUsage)
 
 
 # ---------------------------------------------------------------------------
@@ -228,9 +243,12 @@ def api_action(req: ExecuteActionRequest) -> ExecuteActionResponse:
 
 @app.post("/api/rlm", response_model=RLMResponse)
 def api_rlm(req: RLMRequest) -> RLMResponse:
+    converged = req.depth >= 1
     return RLMResponse(
         summary=f"Mock RLM summary for: {req.query[:80]}...",
-        converged=req.depth >= 1,
+        converged=converged,
+        confidence=0.9 if converged else 0.4,
+        citations=[RLMCitation(kb_name="kb_core", document_id=d["id"], score=1.0) for d in _kbs["kb_core"][:3]],
     )
 
 
@@ -222,11 +222,34 @@ class RLMQuery(BaseModel):
     depth: int = Field(default=0, ge=0, le=MAX_RECURSION_DEPTH)
 
 
+class Citation(BaseModel):
+    """L4 document the step relied on (kb_name + point id) with its retrieval score."""
+
+    kb_name: str
+    document_id: str
+    score: float = 0.0
+
+
+class TokenUsage(BaseModel):
+    """Model tokens spent by one RLM step (zero when no model was called)."""
+
+    prompt_tokens: int = 0
+    completion_tokens: int = 0
+
+
 class RLMSummary(BaseModel):
-    """Output of one RLM step; converged signals synthesis done."""
+    """Output of one RLM step; converged signals synthesis done.
+
+    steps counts thoughts plus actions executed; confidence is the model's self-reported 0..1 estimate (None
+    when it gave none). The Rust orchestrator persists these into the reasoning trace (L6).
+    """
 
     summary: str
     converged: bool
+    steps: int = 1
+    confidence: Optional[float] = Field(default=None, ge=0.0, le=1.0)
+    citations: list[Citation] = Field(default_factory=list)
+    usage: TokenUsage = Field(default_factory=TokenUsage)
 
 
 class ActionSpec(BaseModel):
@@ -243,6 +266,8 @@ class RLMStructuredResponse(BaseModel):
     action: Optional[ActionSpec] = None
     observation: Optional[str] = None
     is_final: bool = False
+    confidence: Optional[float] = Field(default=None, ge=0.0, le=1.0)
+    citations: list[Citation] = Field(default_factory=list)
 
 
 class SynthesisAction(BaseModel):
@@ -326,6 +351,15 @@ def _stub_llm_raw_response() -> Optional[str]:
     return os.environ.get("PAGI_RLM_STUB_JSON")
 
 
+def _token_usage(resp: Any) -> TokenUsage:
+    """Token counts from a litellm completion (OpenAI-style usage block), zero when absent."""
+    usage = getattr(resp, "usage", None)
+    return TokenUsage(
+        prompt_tokens=int(getattr(usage, "prompt_tokens", 0) or 0),
+        completion_tokens=int(getattr(usage, "completion_tokens", 0) or 0),
+    )
+
+
 def _execute_action(
     action: ActionSpec,
     *,
@@ -446,7 +480,7 @@ def _recursive_loop_impl(query: RLMQuery) -> RLMSummary:
         )
         obs, ok, err = _execute_action(action, depth=query.depth, reasoning_id=rid, mock_mode=True)
         summary = f"MockMode thought: planned={action.skill_name}; ok={ok}; err={err}; {obs}"
-        return RLMSummary(summary=summary, converged=True)
+        return RLMSummary(summary=summary, converged=True, steps=2)
 
     # Structured JSON enforcement (no outbound by default):
     # - If PAGI_RLM_STUB_JSON is set, parse and act on it.
@@ -454,6 +488,7 @@ def _recursive_loop_impl(query: RLMQuery) -> RLMSummary:
     stub = _stub_llm_raw_response()
     if enforce_structured and (stub is not None or (allow_outbound and litellm is not None)):
         try:
+            usage = TokenUsage()
             if stub is not None:
                 raw = stub
             else:
@@ -475,9 +510,16 @@ def _recursive_loop_impl(query: RLMQuery) -> RLMSummary:
                     ],
                 )
                 raw = resp.choices[0].message.content or "{}"
+                usage = _token_usage(resp)
 
             parsed = _parse_structured_response(raw)
             _log_action(f"THOUGHT: {parsed.thought}")
+            step = dict(
+                steps=1 if parsed.action is None else 2,
+                confidence=parsed.confidence,
+                citations=parsed.citations,
+                usage=usage,
+            )
 
             if parsed.action is not None:
                 rid = str(parsed.action.params.get("reasoning_id") or "") if parsed.action.params else ""
@@ -575,8 +617,8 @@ def _recursive_loop_impl(query: RLMQuery) -> RLMSummary:
                         summary = f"{summary}\nSYNTHESIS_ACTION:{synth.model_dump_json()}"
                     except Exception:
                         pass
-                return RLMSummary(summary=summary, converged=True)
-            return RLMSummary(summary=parsed.thought, converged=False)
+                return RLMSummary(summary=summary, converged=True, **step)
+            return RLMSummary(summary=parsed.thought, converged=False, **step)
         except Exception as e:
             error_trace = f"Schema enforcement failed: {e!s}"
             _report_self_heal(error_trace, "python_skill")
@@ -592,6 +634,7 @@ def _recursive_loop_impl(query: RLMQuery) -> RLMSummary:
                 context += f"\nPeeked: {peeked[:PEEK_MAX_CHARS]}"
 
     # Delegation: outbound delegation is disabled unless PAGI_ALLOW_OUTBOUND=true.
+    usage = TokenUsage()
     if allow_outbound and "complex" in query.query.lower():
         if litellm is not None:
             try:
@@ -600,6 +643,7 @@ def _recursive_loop_impl(query: RLMQuery) -> RLMSummary:
                     messages=[{"role": "user", "content": query.model_dump_json()}],
                 )
                 sub_summary = resp.choices[0].message.content or ""
+                usage = _token_usage(resp)
                 context += f"\nSub-summary: {sub_summary[:PEEK_MAX_CHARS]}"
             except Exception as e:
                 context += f"\nSub-error: {e!s}"
@@ -636,7 +680,7 @@ def _recursive_loop_impl(query: RLMQuery) -> RLMSummary:
             obs, ok, err = _execute_action(patch_action, depth=query.depth, reasoning_id=rid, mock_mode=False)
             summary_final = f"Self-patch synthesis: ok={ok}; obs={obs[:200]}"
 
-    return RLMSummary(summary=summary_final, converged=converged)
+    return RLMSummary(summary=summary_final, converged=converged, usage=usage)
 
 
 def save_skill(filename: str, code: str) -> None:
@@ -0,0 +1,31 @@
+"""Cold-start bootstrap source collection (no Qdrant/gRPC)."""
+
+from pathlib import Path
+
+from src.bootstrap import collect_core_sources, collect_skill_sources, point_id
+
+
+def test_collect_sources(tmp_path: Path):
+    skills = tmp_path / "skills"
+    skills.mkdir()
+    (skills / "demo.py").write_text('"""Demo skill: does nothing."""\n\ndef run(p):\n    return ""\n')
+    (skills / "nodoc.py").write_text("x = 1\n")
+    (skills / "demo.json").write_text('{"name": "demo"}')
+    found = collect_skill_sources(skills)
+    labels = [s.label for s in found]
+    assert any(l.endswith("demo.py#docstring") for l in labels)
+    assert not any("nodoc" in l for l in labels)
+    assert any(l.endswith("demo.json") for l in labels)
+    assert all(s.kb_name == "kb_skills" for s in found)
+
+    docs = tmp_path / "docs"
+    docs.mkdir()
+    (docs / "guide.md").write_text("# Guide")
+    (docs / "image.png").write_bytes(b"\x89PNG")
+    core = collect_core_sources(tmp_path, doc_dirs=[docs])
+    assert [s.label.endswith("guide.md") for s in core] == [True]
+
+
+def test_point_id_stable():
+    assert point_id("kb_core", "README.md", 0) == point_id("kb_core", "README.md", 0)
+    assert point_id("kb_core", "README.md", 0) != point_id("kb_core", "README.md", 1)
@@ -81,6 +81,25 @@ def test_rlm_structured_stub_json_is_final(monkeypatch):
     assert data["summary"] == "done"
 
 
+def test_rlm_structured_stub_reports_convergence_metadata(monkeypatch):
+    """Confidence and L4 citations from the model pass through; steps count the executed action."""
+    monkeypatch.delenv("PAGI_MOCK_MODE", raising=False)
+    monkeypatch.delenv("PAGI_ACTIONS_VIA_GRPC", raising=False)
+    monkeypatch.delenv("PAGI_ALLOW_LOCAL_DISPATCH", raising=False)
+    monkeypatch.setenv(
+        "PAGI_RLM_STUB_JSON",
+        '{"thought":"done","action":{"skill_name":"unknown_skill","params":{}},"is_final":true,'
+        '"confidence":0.8,"citations":[{"kb_name":"kb_core","document_id":"doc-1","score":0.92}]}',
+    )
+    r = client.post("/rlm", json={"query": "anything", "context": "", "depth": 0})
+    assert r.status_code == 200
+    data = r.json()
+    assert data["steps"] == 2
+    assert data["confidence"] == 0.8
+    assert data["citations"] == [{"kb_name": "kb_core", "document_id": "doc-1", "score": 0.92}]
+    assert data["usage"] == {"prompt_tokens": 0, "completion_tokens": 0}
+
+
 def test_rlm_structured_invalid_json_reports_schema_failure(monkeypatch):
     """Invalid JSON should return converged=False and include schema failure message."""
     monkeypatch.delenv("PAGI_MOCK_MODE", raising=False)
@@ -730,3 +749,34 @@ def test_auto_evolve_from_patch():
     full_path = (bridge_root / path_str.replace("\\", "/")).resolve()
     assert full_path.exists()
     full_path.unlink()
+
+
+def test_rerank_scores_documents_in_request_order(monkeypatch):
+    """POST /rerank returns one cross-encoder score per document (model stubbed, no download)."""
+
+    class FakeCrossEncoder:
+        def predict(self, pairs):
+            return [float(len(set(q.split()) & set(d.split()))) for q, d in pairs]
+
+    monkeypatch.setattr("src.main._cross_encoder", lambda name: FakeCrossEncoder())
+    monkeypatch.setenv("PAGI_RERANK_MODEL", "fake")
+    r = client.post(
+        "/rerank",
+        json={"query": "panic in apply queue", "documents": ["unrelated text", "apply queue panic"]},
+    )
+    assert r.status_code == 200
+    assert r.json() == {"scores": [0.0, 3.0], "model": "fake"}
+
+
+def test_embed_returns_one_vector_per_text(monkeypatch):
+    """POST /embed returns one embedding per text in order (encoder stubbed, no download)."""
+
+    class FakeEncoder:
+        def encode(self, texts):
+            return [[float(len(t)), 1.0] for t in texts]
+
+    monkeypatch.setattr("src.main._sentence_encoder", lambda name: FakeEncoder())
+    monkeypatch.setenv("PAGI_EMBED_MODEL", "fake")
+    r = client.post("/embed", json={"texts": ["abc", "de"]})
+    assert r.status_code == 200
+    assert r.json() == {"vectors": [[3.0, 1.0], [2.0, 1.0]], "model": "fake", "dim": 2}